@@ -1,7 +1,7 @@
 use crate::{
-    js_config::{ConfigSchema, JsConfig},
+    js_config::{ConfigSchema, JsConfig, CONFIG_VERSION, CONFIG_VERSION_PROPERTY},
     option::OptionMeta,
-    option_group, CliPlugin, WitOptions,
+    option_group, CliPlugin, PluginKind, Target, WitOptions,
 };
 use anyhow::{anyhow, bail, Result};
 use clap::{
@@ -9,10 +9,7 @@ use clap::{
     error::ErrorKind,
     CommandFactory, Parser, Subcommand,
 };
-use std::{
-    collections::{HashMap, HashSet},
-    path::PathBuf,
-};
+use std::{collections::HashMap, path::PathBuf, str::FromStr};
 
 use crate::option::{
     fmt_help, GroupDescriptor, GroupOption, GroupOptionBuilder, GroupOptionParser, OptionValue,
@@ -53,6 +50,17 @@ pub enum Command {
     /// Initializes a plugin binary.
     #[command(arg_required_else_help = true)]
     InitPlugin(InitPluginCommandOpts),
+    /// Runs a WebAssembly module generated by Javy, building it first if
+    /// given a JavaScript source.
+    #[command(arg_required_else_help = true)]
+    Run(RunCommandOpts),
+    /// Prints a listing of the QuickJS bytecode compiled from a JavaScript
+    /// source, or evaluated by a WebAssembly module generated by Javy.
+    #[command(arg_required_else_help = true)]
+    Disassemble(DisassembleCommandOpts),
+    /// Prints the runtime config properties a plugin supports, which can be
+    /// set with `-J`, as JSON.
+    PluginInfo(PluginInfoCommandOpts),
 }
 
 #[derive(Debug, Parser)]
@@ -72,7 +80,8 @@ pub struct CompileCommandOpts {
 
     #[structopt(long)]
     /// Optional path to WIT file describing exported functions.
-    /// Only supports function exports with no arguments and no return values.
+    /// Only supports function exports with no arguments and no return values
+    /// when `-d` is specified.
     pub wit: Option<PathBuf>,
 
     #[arg(short = 'n')]
@@ -91,11 +100,14 @@ const RUNTIME_CONFIG_ARG_LONG: &str = "javascript";
 #[derive(Debug, Parser)]
 pub struct BuildCommandOpts {
     #[arg(value_name = "INPUT")]
-    /// Path of the JavaScript input file.
+    /// Path of the JavaScript input file, or `-` to read the JavaScript
+    /// source from stdin. Omitting it also reads from stdin when stdin is
+    /// not a terminal.
     pub input: Option<PathBuf>,
 
     #[arg(short, default_value = "index.wasm")]
-    /// Desired path of the WebAssembly output file.
+    /// Desired path of the WebAssembly output file, or `-` to write the
+    /// WebAssembly module to stdout.
     pub output: PathBuf,
 
     #[arg(short = 'C', long = "codegen")]
@@ -107,6 +119,107 @@ pub struct BuildCommandOpts {
     /// JavaScript runtime options.
     /// Use `-J help` for more details.
     pub js: Vec<JsGroupValue>,
+
+    #[arg(long = "module", value_name = "NAME=PATH")]
+    /// Additional JavaScript module the input can import with the given
+    /// name, resolved against `function.mjs` for relative imports of the
+    /// input. Can be specified multiple times.
+    pub modules: Vec<ModuleInput>,
+
+    #[arg(long)]
+    /// Generates identical WebAssembly output for the same inputs, by fixing
+    /// the clocks and randomness seen while pre-initializing the plugin.
+    pub deterministic: bool,
+
+    #[arg(long)]
+    /// Prints the size of the generated module and a breakdown of the
+    /// embedded source, bytecode and plugin to stderr.
+    pub stats: bool,
+
+    #[arg(long)]
+    /// Watches the input, the modules given with `--module`, the input source
+    /// map and the WIT file, and rebuilds the output each time one of them
+    /// changes until Ctrl-C is pressed.
+    pub watch: bool,
+}
+
+/// An additional JavaScript module given as `NAME=PATH`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModuleInput {
+    /// The name the module is imported with.
+    pub name: String,
+    /// Path of the JavaScript module file.
+    pub path: PathBuf,
+}
+
+impl FromStr for ModuleInput {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.split_once('=') {
+            Some((name, path)) if !name.is_empty() && !path.is_empty() => Ok(Self {
+                name: name.to_string(),
+                path: PathBuf::from(path),
+            }),
+            _ => bail!("Invalid module `{value}`, expected NAME=PATH"),
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct RunCommandOpts {
+    #[arg(value_name = "INPUT", required = true)]
+    /// Path of the WebAssembly module to run, or of a JavaScript input file
+    /// to build and run.
+    pub input: PathBuf,
+
+    #[arg(long, value_name = "PATH")]
+    /// Path of a file to pass as the module's stdin (default is the stdin of
+    /// this process).
+    pub stdin: Option<PathBuf>,
+
+    #[arg(long, value_name = "FUNCTION", default_value = "_start")]
+    /// Name of the exported function to invoke.
+    pub invoke: String,
+
+    #[arg(short = 'C', long = "codegen")]
+    /// Code generation options when building a JavaScript input. The
+    /// `plugin` option also gives the plugin a dynamically linked module
+    /// is linked to.
+    /// Use `-C help` for more details.
+    pub codegen: Vec<GroupOption<CodegenOption>>,
+
+    #[arg(short = RUNTIME_CONFIG_ARG_SHORT, long = RUNTIME_CONFIG_ARG_LONG)]
+    /// JavaScript runtime options when building a JavaScript input.
+    /// Use `-J help` for more details.
+    pub js: Vec<JsGroupValue>,
+
+    #[arg(long = "module", value_name = "NAME=PATH")]
+    /// Additional JavaScript module a JavaScript input can import with the
+    /// given name. Can be specified multiple times.
+    pub modules: Vec<ModuleInput>,
+
+    #[arg(long)]
+    /// Prints the fuel consumed by the module to stderr.
+    pub fuel: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct DisassembleCommandOpts {
+    #[arg(value_name = "INPUT", required = true)]
+    /// Path of the JavaScript input file to compile, or of the WebAssembly
+    /// module to read the bytecode of.
+    pub input: PathBuf,
+
+    #[arg(short = 'C', long = "codegen")]
+    /// Code generation options when compiling a JavaScript input.
+    /// Use `-C help` for more details.
+    pub codegen: Vec<GroupOption<CodegenOption>>,
+
+    #[arg(long = "module", value_name = "NAME=PATH")]
+    /// Additional JavaScript module a JavaScript input can import with the
+    /// given name. Can be specified multiple times.
+    pub modules: Vec<ModuleInput>,
 }
 
 #[derive(Debug, Parser)]
@@ -116,6 +229,13 @@ pub struct EmitPluginCommandOpts {
     pub out: Option<PathBuf>,
 }
 
+#[derive(Debug, Parser)]
+pub struct PluginInfoCommandOpts {
+    #[arg(value_name = "PLUGIN")]
+    /// Path to the plugin (default is the plugin included in the CLI).
+    pub plugin: Option<PathBuf>,
+}
+
 #[derive(Debug, Parser)]
 pub struct InitPluginCommandOpts {
     #[arg(value_name = "PLUGIN", required = true)]
@@ -182,6 +302,12 @@ pub struct CodegenOptionGroup {
     pub wit: WitOptions,
     pub source_compression: bool,
     pub plugin: Option<PathBuf>,
+    pub pretty_errors: bool,
+    pub minify: bool,
+    pub source_map: Option<PathBuf>,
+    pub input_source_map: Option<PathBuf>,
+    pub module_name: Option<String>,
+    pub target: Target,
 }
 
 impl Default for CodegenOptionGroup {
@@ -191,6 +317,12 @@ impl Default for CodegenOptionGroup {
             wit: WitOptions::default(),
             source_compression: true,
             plugin: None,
+            pretty_errors: false,
+            minify: false,
+            source_map: None,
+            input_source_map: None,
+            module_name: None,
+            target: Target::Module,
         }
     }
 }
@@ -201,8 +333,9 @@ option_group! {
         /// Creates a smaller module that requires a dynamically linked QuickJS
         /// plugin Wasm module to execute (see `emit-plugin` command).
         Dynamic(bool),
-        /// Optional path to WIT file describing exported functions. Only
-        /// supports function exports with no arguments and no return values.
+        /// Optional path to WIT file describing exported functions. Exported
+        /// functions can take and return booleans, numbers, chars, strings and
+        /// records of them.
         Wit(PathBuf),
         /// Optional WIT world name for WIT file. Must be specified if WIT is
         /// file path is specified.
@@ -214,6 +347,45 @@ option_group! {
         /// linked modules. JavaScript config options are also not supported when
         /// using this parameter.
         Plugin(PathBuf),
+        /// Include the offending source line and a caret under the error
+        /// column in JavaScript compilation errors.
+        PrettyErrors(bool),
+        /// Minify the JavaScript source code before compiling it, removing
+        /// comments and unneeded whitespace from the embedded source code.
+        Minify(bool),
+        /// Optional path to write a source map to, mapping the positions in
+        /// `function.mjs` shown in stack traces back to the input file.
+        SourceMap(PathBuf),
+        /// Optional path to the source map of the input, like the one of a
+        /// bundle generated from TypeScript. It's embedded in the module to
+        /// remap the locations in the stack traces of errors to the original
+        /// files. Without it, a source map inlined in the input as a
+        /// `//# sourceMappingURL=data:` URL is embedded.
+        InputSourceMap(PathBuf),
+        /// Optional name of the module evaluating the input, shown in stack
+        /// traces and errors. Defaults to the name of the input file when
+        /// building with the default plugin, and to `function.mjs`
+        /// otherwise.
+        ModuleName(String),
+        /// The kind of WebAssembly binary to generate, either a core `module`
+        /// (the default) or a `component` wrapping the module. Components
+        /// target the world given with `wit-world`, if any, and require
+        /// static linking.
+        Target(Target),
+    }
+}
+
+impl OptionValue for Target {
+    fn help() -> &'static str {
+        "=module|component"
+    }
+
+    fn parse(val: Option<&str>) -> Result<Self> {
+        match val {
+            Some("module") => Ok(Target::Module),
+            Some("component") => Ok(Target::Component),
+            _ => bail!("Unknown target. Valid options: module, component"),
+        }
     }
 }
 
@@ -230,6 +402,12 @@ impl TryFrom<Vec<GroupOption<CodegenOption>>> for CodegenOptionGroup {
         let mut wit_world_specified = false;
         let mut source_compression_specified = false;
         let mut plugin_specified = false;
+        let mut pretty_errors_specified = false;
+        let mut minify_specified = false;
+        let mut source_map_specified = false;
+        let mut input_source_map_specified = false;
+        let mut module_name_specified = false;
+        let mut target_specified = false;
 
         for option in value.iter().flat_map(|i| i.0.iter()) {
             match option {
@@ -268,6 +446,48 @@ impl TryFrom<Vec<GroupOption<CodegenOption>>> for CodegenOptionGroup {
                     options.plugin = Some(path.clone());
                     plugin_specified = true;
                 }
+                CodegenOption::PrettyErrors(enabled) => {
+                    if pretty_errors_specified {
+                        bail!("pretty-errors can only be specified once");
+                    }
+                    options.pretty_errors = *enabled;
+                    pretty_errors_specified = true;
+                }
+                CodegenOption::Minify(enabled) => {
+                    if minify_specified {
+                        bail!("minify can only be specified once");
+                    }
+                    options.minify = *enabled;
+                    minify_specified = true;
+                }
+                CodegenOption::SourceMap(path) => {
+                    if source_map_specified {
+                        bail!("source-map can only be specified once");
+                    }
+                    options.source_map = Some(path.clone());
+                    source_map_specified = true;
+                }
+                CodegenOption::InputSourceMap(path) => {
+                    if input_source_map_specified {
+                        bail!("input-source-map can only be specified once");
+                    }
+                    options.input_source_map = Some(path.clone());
+                    input_source_map_specified = true;
+                }
+                CodegenOption::ModuleName(name) => {
+                    if module_name_specified {
+                        bail!("module-name can only be specified once");
+                    }
+                    options.module_name = Some(name.clone());
+                    module_name_specified = true;
+                }
+                CodegenOption::Target(target) => {
+                    if target_specified {
+                        bail!("target can only be specified once");
+                    }
+                    options.target = target.clone();
+                    target_specified = true;
+                }
             }
         }
 
@@ -285,6 +505,10 @@ impl TryFrom<Vec<GroupOption<CodegenOption>>> for CodegenOptionGroup {
             bail!("Must specify plugin when using dynamic linking");
         }
 
+        if options.dynamic && options.target == Target::Component {
+            bail!("Cannot generate a component when using dynamic linking");
+        }
+
         Ok(options)
     }
 }
@@ -301,6 +525,7 @@ pub enum JsGroupValue {
 pub enum JsOptionValue {
     Boolean(bool),
     Number(u64),
+    String(String),
 }
 
 /// A runtime config group option.
@@ -345,24 +570,13 @@ impl TypedValueParser for JsGroupOptionParser {
         let key = splits.next().unwrap();
         let value_str = splits.next();
         
-        let option_value = match (key, value_str) {
-            // Special handling for wait-timeout-ms which expects a number
-            ("wait-timeout-ms", Some(num_str)) => {
-                match num_str.parse::<u64>() {
-                    Ok(num) => JsOptionValue::Number(num),
-                    Err(_) => return Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
-                }
-            }
-            ("wait-timeout-ms", None) => {
-                return Err(clap::Error::new(clap::error::ErrorKind::InvalidValue));
-            }
-            // All other options are boolean
-            (_, Some("y")) => JsOptionValue::Boolean(true),
-            (_, Some("n")) => JsOptionValue::Boolean(false),
-            (_, None) => JsOptionValue::Boolean(true),
-            (_, Some(_)) => return Err(clap::Error::new(clap::error::ErrorKind::InvalidValue)),
+        // The value is converted to the type of the property declared in
+        // the plugin's config schema once the plugin is known.
+        let option_value = match value_str {
+            Some(value) => JsOptionValue::String(value.to_string()),
+            None => JsOptionValue::Boolean(true),
         };
-        
+
         Ok(JsGroupValue::Option(JsGroupOption {
             name: key.to_string(),
             value: option_value,
@@ -380,9 +594,9 @@ impl JsConfig {
         let supported_properties = ConfigSchema::from_cli_plugin(cli_plugin)?
             .map_or(Vec::new(), |schema| schema.supported_properties);
 
-        let mut supported_names = HashSet::new();
+        let mut properties_by_name = HashMap::new();
         for property in &supported_properties {
-            supported_names.insert(property.name.as_str());
+            properties_by_name.insert(property.name.as_str(), property);
         }
 
         let mut config = HashMap::new();
@@ -393,25 +607,22 @@ impl JsConfig {
                         RUNTIME_CONFIG_ARG_LONG,
                         &RUNTIME_CONFIG_ARG_SHORT.to_string(),
                         &supported_properties
-                            .into_iter()
+                            .iter()
                             .map(|prop| OptionMeta {
                                 name: prop.name.clone(),
-                                help: if prop.name == "wait-timeout-ms" {
-                                    "=<milliseconds>".to_string()
-                                } else {
-                                    "[=y|n]".to_string()
-                                },
-                                doc: prop.doc,
+                                help: prop.value_help().to_string(),
+                                doc: prop.doc_help(),
                             })
                             .collect::<Vec<_>>(),
                     );
                     std::process::exit(0);
                 }
                 JsGroupValue::Option(JsGroupOption { name, value }) => {
-                    if supported_names.contains(name.as_str()) {
+                    if let Some(property) = properties_by_name.get(name.as_str()) {
                         if config.contains_key(&name) {
                             bail!("{name} can only be specified once");
                         }
+                        let value = property.parse_value(value)?;
                         config.insert(name, value);
                     } else {
                         Cli::command()
@@ -435,7 +646,14 @@ impl JsConfig {
                 }
             }
         }
-        
+
+        if matches!(cli_plugin.kind, PluginKind::Default) {
+            config.insert(
+                CONFIG_VERSION_PROPERTY.to_string(),
+                JsOptionValue::Number(CONFIG_VERSION),
+            );
+        }
+
         Ok(JsConfig::from_hash(config))
     }
 }
@@ -446,12 +664,12 @@ mod tests {
 
     use crate::{
         commands::{JsGroupOption, JsGroupValue, JsOptionValue},
-        js_config::JsConfig,
+        js_config::{ConfigSchema, JsConfig, JsConfigPropertyType, CONFIG_VERSION},
         plugin::PLUGIN_MODULE,
         CliPlugin, Plugin, PluginKind,
     };
 
-    use super::{CodegenOption, CodegenOptionGroup, GroupOption};
+    use super::{CodegenOption, CodegenOptionGroup, GroupOption, ModuleInput, Target};
     use anyhow::{Error, Result};
 
     #[test]
@@ -568,6 +786,66 @@ mod tests {
 
         assert_eq!(group, expected);
 
+        let raw = vec![GroupOption(vec![CodegenOption::PrettyErrors(true)])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            pretty_errors: true,
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
+        let raw = vec![GroupOption(vec![CodegenOption::Minify(true)])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            minify: true,
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
+        let raw = vec![GroupOption(vec![CodegenOption::SourceMap(
+            "index.js.map".into(),
+        )])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            source_map: Some(PathBuf::from("index.js.map")),
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
+        let raw = vec![GroupOption(vec![CodegenOption::InputSourceMap(
+            PathBuf::from("bundle.js.map"),
+        )])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            input_source_map: Some(PathBuf::from("bundle.js.map")),
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
+        let raw = vec![GroupOption(vec![CodegenOption::ModuleName(
+            "handler.js".to_string(),
+        )])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            module_name: Some("handler.js".to_string()),
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
+        let raw = vec![GroupOption(vec![CodegenOption::Target(Target::Component)])];
+        let group: CodegenOptionGroup = raw.try_into()?;
+        let expected = CodegenOptionGroup {
+            target: Target::Component,
+            ..Default::default()
+        };
+
+        assert_eq!(group, expected);
+
         let raw = vec![GroupOption(vec![CodegenOption::Dynamic(true)])];
         let result: Result<CodegenOptionGroup, Error> = raw.try_into();
         assert_eq!(
@@ -575,6 +853,17 @@ mod tests {
             "Must specify plugin when using dynamic linking"
         );
 
+        let raw = vec![GroupOption(vec![
+            CodegenOption::Dynamic(true),
+            CodegenOption::Plugin(PathBuf::from("file.wasm")),
+            CodegenOption::Target(Target::Component),
+        ])];
+        let result: Result<CodegenOptionGroup, Error> = raw.try_into();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "Cannot generate a component when using dynamic linking"
+        );
+
         Ok(())
     }
 
@@ -629,6 +918,36 @@ mod tests {
             result.err().unwrap().to_string(),
             "plugin can only be specified once"
         );
+
+        let raw = vec![GroupOption(vec![
+            CodegenOption::PrettyErrors(true),
+            CodegenOption::PrettyErrors(false),
+        ])];
+        let result: Result<CodegenOptionGroup, Error> = raw.try_into();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "pretty-errors can only be specified once"
+        );
+
+        let raw = vec![GroupOption(vec![
+            CodegenOption::Minify(true),
+            CodegenOption::Minify(false),
+        ])];
+        let result: Result<CodegenOptionGroup, Error> = raw.try_into();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "minify can only be specified once"
+        );
+
+        let raw = vec![GroupOption(vec![
+            CodegenOption::SourceMap("a.map".into()),
+            CodegenOption::SourceMap("b.map".into()),
+        ])];
+        let result: Result<CodegenOptionGroup, Error> = raw.try_into();
+        assert_eq!(
+            result.err().unwrap().to_string(),
+            "source-map can only be specified once"
+        );
         Ok(())
     }
 
@@ -751,4 +1070,251 @@ mod tests {
         let config = result.unwrap();
         assert_eq!(config.get_number("wait-timeout-ms"), Some(1000));
     }
+
+    #[test]
+    fn interrupt_after_parameter_parsing() {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+
+        let result = JsConfig::from_group_values(
+            &plugin,
+            vec![JsGroupValue::Option(JsGroupOption {
+                name: "interrupt-after".to_string(),
+                value: JsOptionValue::Number(100),
+            })],
+        );
+        assert!(result.is_ok());
+        let config = result.unwrap();
+        assert_eq!(config.get_number("interrupt-after"), Some(100));
+    }
+
+    #[test]
+    fn max_blob_storage_bytes_parameter_parsing() {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+
+        let result = JsConfig::from_group_values(
+            &plugin,
+            vec![JsGroupValue::Option(JsGroupOption {
+                name: "max-blob-storage-bytes".to_string(),
+                value: JsOptionValue::Number(1024),
+            })],
+        );
+        assert!(result.is_ok());
+        let config = result.unwrap();
+        assert_eq!(config.get_number("max-blob-storage-bytes"), Some(1024));
+    }
+
+    #[test]
+    fn memory_limit_bytes_parameter_parsing() {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+
+        let result = JsConfig::from_group_values(
+            &plugin,
+            vec![
+                JsGroupValue::Option(JsGroupOption {
+                    name: "memory-limit-bytes".to_string(),
+                    value: JsOptionValue::Number(10_000_000),
+                }),
+                JsGroupValue::Option(JsGroupOption {
+                    name: "max-stack-size-bytes".to_string(),
+                    value: JsOptionValue::Number(65536),
+                }),
+            ],
+        );
+        assert!(result.is_ok());
+        let config = result.unwrap();
+        assert_eq!(config.get_number("memory-limit-bytes"), Some(10_000_000));
+        assert_eq!(config.get_number("max-stack-size-bytes"), Some(65536));
+    }
+
+    #[test]
+    fn js_config_includes_config_version_for_default_plugin() -> Result<()> {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+        let group = JsConfig::from_group_values(&plugin, vec![])?;
+        assert_eq!(group.get_number("config-version"), Some(CONFIG_VERSION));
+        Ok(())
+    }
+
+    #[test]
+    fn js_config_parses_values_by_property_type() -> Result<()> {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+        let option = |name: &str, value: &str| {
+            JsGroupValue::Option(JsGroupOption {
+                name: name.to_string(),
+                value: JsOptionValue::String(value.to_string()),
+            })
+        };
+
+        let config = JsConfig::from_group_values(
+            &plugin,
+            vec![
+                option("text-encoding", "n"),
+                option("wait-timeout-ms", "250"),
+            ],
+        )?;
+        assert_eq!(config.get("text-encoding"), Some(false));
+        assert_eq!(config.get_number("wait-timeout-ms"), Some(250));
+
+        let err =
+            JsConfig::from_group_values(&plugin, vec![option("text-encoding", "yes")]).unwrap_err();
+        assert_eq!(
+            "Invalid value `yes` for text-encoding, expected y or n",
+            err.to_string()
+        );
+
+        let err = JsConfig::from_group_values(&plugin, vec![option("wait-timeout-ms", "soon")])
+            .unwrap_err();
+        assert_eq!(
+            "Invalid value `soon` for wait-timeout-ms, expected a number",
+            err.to_string()
+        );
+
+        let err = JsConfig::from_group_values(
+            &plugin,
+            vec![JsGroupValue::Option(JsGroupOption {
+                name: "wait-timeout-ms".to_string(),
+                value: JsOptionValue::Boolean(true),
+            })],
+        )
+        .unwrap_err();
+        assert_eq!("wait-timeout-ms requires a number", err.to_string());
+        Ok(())
+    }
+
+    #[test]
+    fn numeric_property_is_recognized_from_schema() -> Result<()> {
+        let schema: ConfigSchema = serde_json::from_str(
+            r#"{"supportedProperties":[
+                {"name":"max-widgets","doc":" Max widgets.\n","type":"number","default":3},
+                {"name":"widgets","doc":" Widgets.\n","type":"boolean"}
+            ]}"#,
+        )?;
+        let [max_widgets, widgets] = &schema.supported_properties[..] else {
+            panic!("Expected two properties");
+        };
+
+        assert!(matches!(
+            max_widgets.parse_value(JsOptionValue::String("5".to_string()))?,
+            JsOptionValue::Number(5)
+        ));
+        assert!(max_widgets
+            .parse_value(JsOptionValue::String("y".to_string()))
+            .is_err());
+        assert_eq!("=<number>", max_widgets.value_help());
+        assert_eq!(" Max widgets.\n Default: 3.\n", max_widgets.doc_help());
+
+        assert!(matches!(
+            widgets.parse_value(JsOptionValue::String("y".to_string()))?,
+            JsOptionValue::Boolean(true)
+        ));
+        assert_eq!("[=y|n]", widgets.value_help());
+        assert_eq!(" Widgets.\n", widgets.doc_help());
+        Ok(())
+    }
+
+    #[test]
+    fn numeric_values_out_of_range_are_rejected() -> Result<()> {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+        let option = |name: &str, value: &str| {
+            JsGroupValue::Option(JsGroupOption {
+                name: name.to_string(),
+                value: JsOptionValue::String(value.to_string()),
+            })
+        };
+
+        let err = JsConfig::from_group_values(
+            &plugin,
+            vec![
+                option("event-loop", "y"),
+                option("wait-for-completion", "y"),
+                option("wait-timeout-ms", "0"),
+            ],
+        )
+        .unwrap_err();
+        assert_eq!(
+            "Invalid value 0 for wait-timeout-ms, expected a number of at least 1",
+            err.to_string()
+        );
+
+        let schema: ConfigSchema = serde_json::from_str(
+            r#"{"supportedProperties":[
+                {"name":"widgets","doc":"","type":"number","min":2,"max":8},
+                {"name":"max-widgets","doc":"","type":"number","max":8}
+            ]}"#,
+        )?;
+        let [widgets, max_widgets] = &schema.supported_properties[..] else {
+            panic!("Expected two properties");
+        };
+        for value in ["2", "8"] {
+            assert!(widgets
+                .parse_value(JsOptionValue::String(value.to_string()))
+                .is_ok());
+        }
+        let err = widgets
+            .parse_value(JsOptionValue::String("9".to_string()))
+            .unwrap_err();
+        assert_eq!(
+            "Invalid value 9 for widgets, expected a number from 2 to 8",
+            err.to_string()
+        );
+        let err = max_widgets
+            .parse_value(JsOptionValue::Number(9))
+            .unwrap_err();
+        assert_eq!(
+            "Invalid value 9 for max-widgets, expected a number of at most 8",
+            err.to_string()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn default_plugin_schema_declares_types_and_defaults() -> Result<()> {
+        let plugin = CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default);
+        let schema = ConfigSchema::from_cli_plugin(&plugin)?.unwrap();
+        let property = |name: &str| {
+            schema
+                .supported_properties
+                .iter()
+                .find(|property| property.name == name)
+                .unwrap()
+        };
+
+        assert!(matches!(
+            property("text-encoding").ty,
+            JsConfigPropertyType::Bool
+        ));
+        assert_eq!(
+            Some(&serde_json::Value::Bool(true)),
+            property("text-encoding").default.as_ref()
+        );
+        assert!(matches!(
+            property("wait-timeout-ms").ty,
+            JsConfigPropertyType::Number
+        ));
+        assert_eq!("=<milliseconds>", property("wait-timeout-ms").value_help());
+        assert_eq!(
+            Some(&serde_json::Value::from(262144)),
+            property("max-stack-size-bytes").default.as_ref()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn module_input_from_str() -> Result<()> {
+        assert_eq!(
+            ModuleInput {
+                name: "lib/util.js".to_string(),
+                path: PathBuf::from("src/util.js"),
+            },
+            "lib/util.js=src/util.js".parse()?
+        );
+
+        for value in ["util.js", "=util.js", "util.js="] {
+            let error = value.parse::<ModuleInput>().unwrap_err();
+            assert_eq!(
+                format!("Invalid module `{value}`, expected NAME=PATH"),
+                error.to_string()
+            );
+        }
+        Ok(())
+    }
 }
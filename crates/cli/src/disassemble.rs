@@ -0,0 +1,639 @@
+//! A disassembler for the QuickJS bytecode evaluated by the modules Javy
+//! generates.
+//!
+//! The bytecode is a module serialized by `JS_WriteObject`, which is read the
+//! way `JS_ReadObject` reads it, without loading it in a runtime.
+
+mod atoms;
+mod opcodes;
+
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+use atoms::ATOMS;
+use opcodes::{Format, Opcode, OPCODES};
+
+/// The version of the bytecode emitted by QuickJS built with
+/// `CONFIG_BIGNUM`.
+const BC_VERSION: u8 = 0x43;
+
+/// The value of the first atom which isn't predefined by QuickJS.
+const FIRST_ATOM: u32 = ATOMS.len() as u32 + 1;
+
+/// The bit tagging atoms which are integers.
+const ATOM_TAG_INT: u32 = 1 << 31;
+
+// The tags of serialized values, `BC_TAG_*` in QuickJS.
+const TAG_NULL: u8 = 1;
+const TAG_UNDEFINED: u8 = 2;
+const TAG_BOOL_FALSE: u8 = 3;
+const TAG_BOOL_TRUE: u8 = 4;
+const TAG_INT32: u8 = 5;
+const TAG_FLOAT64: u8 = 6;
+const TAG_STRING: u8 = 7;
+const TAG_OBJECT: u8 = 8;
+const TAG_ARRAY: u8 = 9;
+const TAG_BIG_INT: u8 = 10;
+const TAG_TEMPLATE_OBJECT: u8 = 11;
+const TAG_FUNCTION_BYTECODE: u8 = 12;
+const TAG_MODULE: u8 = 13;
+const TAG_BIG_FLOAT: u8 = 20;
+const TAG_BIG_DECIMAL: u8 = 21;
+
+/// Disassembles QuickJS bytecode into a listing of the instructions of each
+/// function it defines.
+pub(crate) fn disassemble(bytecode: &[u8]) -> Result<String> {
+    let mut reader = Reader {
+        bytes: bytecode,
+        pos: 0,
+        atoms: vec![],
+    };
+    if reader.u8()? != BC_VERSION {
+        bail!("Unsupported QuickJS bytecode version");
+    }
+    for _ in 0..reader.leb128()? {
+        let atom = reader.string()?;
+        reader.atoms.push(atom);
+    }
+    let value = reader.value()?;
+    if reader.pos != bytecode.len() {
+        bail!("Unexpected data after QuickJS bytecode");
+    }
+
+    let mut listing = String::new();
+    match &value {
+        Value::Module(module) => module.write(&mut listing)?,
+        Value::Function(function) => function.write(&mut listing)?,
+        _ => bail!("QuickJS bytecode doesn't define a module or a function"),
+    }
+    Ok(listing)
+}
+
+/// Finds the QuickJS bytecode evaluated by a module generated by Javy, which
+/// is the first passive data segment holding bytecode.
+pub(crate) fn find_bytecode(wasm: &[u8]) -> Result<Vec<u8>> {
+    let module = walrus::Module::from_buffer(wasm)?;
+    let bytecode = module
+        .data
+        .iter()
+        .filter(|data| matches!(data.kind, walrus::DataKind::Passive))
+        .find(|data| disassemble(&data.value).is_ok())
+        .map(|data| data.value.clone());
+    bytecode.ok_or_else(|| anyhow!("Module doesn't contain QuickJS bytecode"))
+}
+
+/// A value serialized in bytecode.
+enum Value {
+    Null,
+    Undefined,
+    Bool(bool),
+    Int(i32),
+    Float(f64),
+    String(String),
+    /// A `BigInt`, `BigFloat` or `BigDecimal`, whose digits aren't decoded.
+    BigNum(&'static str),
+    Object(Vec<(String, Value)>),
+    Array(Vec<Value>),
+    Template(Vec<Value>),
+    Function(Function),
+    Module(Module),
+}
+
+impl Value {
+    /// A short description of the value, for the operands referencing it.
+    fn describe(&self) -> String {
+        let join = |values: &[Value]| {
+            values
+                .iter()
+                .map(Value::describe)
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        match self {
+            Value::Null => "null".to_string(),
+            Value::Undefined => "undefined".to_string(),
+            Value::Bool(value) => value.to_string(),
+            Value::Int(value) => value.to_string(),
+            Value::Float(value) => value.to_string(),
+            Value::String(value) => format!("{value:?}"),
+            Value::BigNum(kind) => format!("<{kind}>"),
+            Value::Object(properties) => {
+                let properties = properties
+                    .iter()
+                    .map(|(name, value)| format!("{}: {}", quote_atom(name), value.describe()))
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", properties.join(", "))
+            }
+            Value::Array(values) => format!("[{}]", join(values)),
+            Value::Template(values) => format!("template [{}]", join(values)),
+            Value::Function(function) => format!("<function {}>", function.name),
+            Value::Module(module) => format!("<module {}>", module.name),
+        }
+    }
+}
+
+struct Module {
+    name: String,
+    requests: Vec<String>,
+    imports: Vec<String>,
+    exports: Vec<String>,
+    function: Box<Value>,
+}
+
+struct Function {
+    name: String,
+    kind: u8,
+    mode: u8,
+    args: Vec<String>,
+    vars: Vec<String>,
+    closure_vars: Vec<String>,
+    stack_size: u32,
+    /// The file, line and column the function is defined at, if the
+    /// bytecode has debug information.
+    location: Option<(String, u32, u32)>,
+    /// The instructions of the function, as their offset and disassembly.
+    instructions: Vec<(usize, String)>,
+    constants: Vec<Value>,
+}
+
+/// Reads bytecode serialized by `JS_WriteObject` with little endian numbers.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// The atoms of the bytecode which aren't predefined.
+    atoms: Vec<String>,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        let bytes = self
+            .pos
+            .checked_add(len)
+            .and_then(|end| self.bytes.get(self.pos..end))
+            .ok_or_else(|| anyhow!("Unexpected end of QuickJS bytecode"))?;
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes(2)?.try_into()?))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into()?))
+    }
+
+    fn leb128(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.u8()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("Invalid LEB128 number in QuickJS bytecode")
+    }
+
+    fn sleb128(&mut self) -> Result<i32> {
+        let value = self.leb128()?;
+        Ok((value >> 1) as i32 ^ -((value & 1) as i32))
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let len = self.leb128()?;
+        let (len, wide) = ((len >> 1) as usize, len & 1 == 1);
+        if wide {
+            let units = self
+                .bytes(len * 2)?
+                .chunks(2)
+                .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+                .collect::<Vec<_>>();
+            Ok(String::from_utf16_lossy(&units))
+        } else {
+            // Narrow strings are Latin-1.
+            Ok(self.bytes(len)?.iter().map(|&c| char::from(c)).collect())
+        }
+    }
+
+    fn atom(&mut self) -> Result<String> {
+        let value = self.leb128()?;
+        if value & 1 == 1 {
+            Ok((value >> 1).to_string())
+        } else {
+            self.atom_name(value >> 1)
+        }
+    }
+
+    /// The name of an atom, as indexed by instructions.
+    fn atom_name(&self, index: u32) -> Result<String> {
+        if index & ATOM_TAG_INT != 0 {
+            return Ok((index & !ATOM_TAG_INT).to_string());
+        }
+        let name = match index {
+            0 => Some("<null>"),
+            index if index < FIRST_ATOM => ATOMS.get(index as usize - 1).copied(),
+            index => self
+                .atoms
+                .get((index - FIRST_ATOM) as usize)
+                .map(String::as_str),
+        };
+        name.map(str::to_string)
+            .ok_or_else(|| anyhow!("Invalid atom {index} in QuickJS bytecode"))
+    }
+
+    fn value(&mut self) -> Result<Value> {
+        Ok(match self.u8()? {
+            TAG_NULL => Value::Null,
+            TAG_UNDEFINED => Value::Undefined,
+            TAG_BOOL_FALSE => Value::Bool(false),
+            TAG_BOOL_TRUE => Value::Bool(true),
+            TAG_INT32 => Value::Int(self.sleb128()?),
+            TAG_FLOAT64 => Value::Float(f64::from_bits(self.u64()?)),
+            TAG_STRING => Value::String(self.string()?),
+            TAG_OBJECT => {
+                let mut properties = vec![];
+                for _ in 0..self.leb128()? {
+                    properties.push((self.atom()?, self.value()?));
+                }
+                Value::Object(properties)
+            }
+            TAG_ARRAY => Value::Array(self.values()?),
+            TAG_TEMPLATE_OBJECT => {
+                let strings = self.values()?;
+                // The raw strings.
+                self.value()?;
+                Value::Template(strings)
+            }
+            tag @ (TAG_BIG_INT | TAG_BIG_FLOAT | TAG_BIG_DECIMAL) => self.big_num(tag)?,
+            TAG_FUNCTION_BYTECODE => Value::Function(self.function()?),
+            TAG_MODULE => Value::Module(self.module()?),
+            tag => bail!("Unsupported value tag {tag} in QuickJS bytecode"),
+        })
+    }
+
+    fn values(&mut self) -> Result<Vec<Value>> {
+        (0..self.leb128()?).map(|_| self.value()).collect()
+    }
+
+    fn big_num(&mut self, tag: u8) -> Result<Value> {
+        // Zero, infinities and NaN, encoded as the exponents 0 to 2, have no
+        // mantissa.
+        if !(0..=2).contains(&(self.sleb128()? >> 1)) {
+            let len = self.leb128()? as usize;
+            // Decimal digits are packed in nibbles.
+            self.bytes(if tag == TAG_BIG_DECIMAL {
+                len.div_ceil(2)
+            } else {
+                len
+            })?;
+        }
+        Ok(Value::BigNum(match tag {
+            TAG_BIG_INT => "bigint",
+            TAG_BIG_FLOAT => "bigfloat",
+            _ => "bigdecimal",
+        }))
+    }
+
+    fn function(&mut self) -> Result<Function> {
+        let flags = self.u16()?;
+        let kind = ((flags >> 4) & 0b11) as u8;
+        let has_debug = flags & (1 << 10) != 0;
+        let mode = self.u8()?;
+        let name = self.atom()?;
+        let arg_count = self.leb128()? as usize;
+        // The var count, defined arg count and stack size.
+        self.leb128()?;
+        self.leb128()?;
+        let stack_size = self.leb128()?;
+        let closure_var_count = self.leb128()?;
+        let constant_count = self.leb128()?;
+        let code_len = self.leb128()?;
+        let local_count = self.leb128()?;
+
+        let mut args = vec![];
+        for _ in 0..local_count {
+            args.push(self.atom()?);
+            // The scope level, the next scope and the flags.
+            self.leb128()?;
+            self.leb128()?;
+            self.u8()?;
+        }
+        let vars = args.split_off(arg_count.min(args.len()));
+        let mut closure_vars = vec![];
+        for _ in 0..closure_var_count {
+            closure_vars.push(self.atom()?);
+            // The index of the variable and the flags.
+            self.leb128()?;
+            self.u8()?;
+        }
+        let code = self.bytes(code_len as usize)?;
+        let location = if has_debug {
+            let file = self.atom()?;
+            let line = self.leb128()?;
+            // The tables mapping instructions to lines and columns.
+            let len = self.leb128()?;
+            self.bytes(len as usize)?;
+            let column = self.leb128()?;
+            let len = self.leb128()?;
+            self.bytes(len as usize)?;
+            // Columns are counted from 1 in stack traces.
+            Some((file, line, column + 1))
+        } else {
+            None
+        };
+        let mut constants = vec![];
+        for _ in 0..constant_count {
+            constants.push(self.value()?);
+        }
+
+        let mut function = Function {
+            name,
+            kind,
+            mode,
+            args,
+            vars,
+            closure_vars,
+            stack_size,
+            location,
+            instructions: vec![],
+            constants,
+        };
+        let mut pos = 0;
+        while pos < code.len() {
+            let opcode = OPCODES
+                .get(code[pos] as usize)
+                .ok_or_else(|| anyhow!("Invalid opcode {:#04x} in QuickJS bytecode", code[pos]))?;
+            let operands = code.get(pos + 1..pos + opcode.size).ok_or_else(|| {
+                anyhow!("Truncated {} instruction in QuickJS bytecode", opcode.name)
+            })?;
+            let instruction = self.instruction(&function, pos, opcode, operands)?;
+            function.instructions.push((pos, instruction));
+            pos += opcode.size;
+        }
+        Ok(function)
+    }
+
+    /// Disassembles an instruction at offset `pos` of the code of
+    /// `function`.
+    fn instruction(
+        &self,
+        function: &Function,
+        pos: usize,
+        opcode: &Opcode,
+        operands: &[u8],
+    ) -> Result<String> {
+        let u16_at = |at: usize| u16::from_le_bytes([operands[at], operands[at + 1]]);
+        let u32_at = |at: usize| u32::from_le_bytes(operands[at..at + 4].try_into().unwrap());
+        // Jumps are relative to the offset of their operand.
+        let target = |at: usize, relative: i64| pos as i64 + 1 + at as i64 + relative;
+        let var = |names: &[String], index: usize| {
+            format!("{index}: {}", names.get(index).map_or("?", String::as_str))
+        };
+        let atom = || self.atom_name(u32_at(0)).map(|atom| quote_atom(&atom));
+        let constant = |index: usize| {
+            let value = function
+                .constants
+                .get(index)
+                .map_or("?".to_string(), Value::describe);
+            format!("{index}: {value}")
+        };
+
+        let operands = match opcode.format {
+            Format::None => String::new(),
+            Format::NoneInt(n) => n.to_string(),
+            Format::Npopx(n) => n.to_string(),
+            Format::NoneLoc(index) => var(&function.vars, index.into()),
+            Format::NoneArg(index) => var(&function.args, index.into()),
+            Format::NoneVarRef(index) => var(&function.closure_vars, index.into()),
+            Format::U8 => operands[0].to_string(),
+            Format::I8 => (operands[0] as i8).to_string(),
+            Format::Loc8 => var(&function.vars, operands[0].into()),
+            Format::Const8 => constant(operands[0].into()),
+            Format::Label8 => target(0, (operands[0] as i8).into()).to_string(),
+            Format::U16 | Format::Npop => u16_at(0).to_string(),
+            Format::I16 => (u16_at(0) as i16).to_string(),
+            Format::Label16 => target(0, (u16_at(0) as i16).into()).to_string(),
+            Format::NpopU16 => format!("{},{}", u16_at(0), u16_at(2)),
+            Format::Loc => var(&function.vars, u16_at(0).into()),
+            Format::Arg => var(&function.args, u16_at(0).into()),
+            Format::VarRef => var(&function.closure_vars, u16_at(0).into()),
+            Format::I32 => (u32_at(0) as i32).to_string(),
+            Format::Const => constant(u32_at(0) as usize),
+            Format::Label => target(0, (u32_at(0) as i32).into()).to_string(),
+            Format::Atom => atom()?,
+            Format::AtomU8 => format!("{},{}", atom()?, operands[4]),
+            Format::AtomU16 => format!("{},{}", atom()?, u16_at(4)),
+            Format::AtomLabelU8 => format!(
+                "{},{},{}",
+                atom()?,
+                target(4, (u32_at(4) as i32).into()),
+                operands[8]
+            ),
+        };
+        Ok(if operands.is_empty() {
+            opcode.name.to_string()
+        } else {
+            format!("{} {operands}", opcode.name)
+        })
+    }
+
+    fn module(&mut self) -> Result<Module> {
+        let name = self.atom()?;
+        let mut requests = vec![];
+        for _ in 0..self.leb128()? {
+            requests.push(self.atom()?);
+        }
+        let request = |requests: &[String], index: u32| {
+            requests
+                .get(index as usize)
+                .map_or("?".to_string(), |name| format!("{name:?}"))
+        };
+        let mut exports = vec![];
+        for _ in 0..self.leb128()? {
+            // Local exports reference a variable, the others a requested
+            // module and the name they're imported with.
+            if self.u8()? == 0 {
+                self.leb128()?;
+                exports.push(self.atom()?);
+            } else {
+                let module = self.leb128()?;
+                let local = self.atom()?;
+                let name = self.atom()?;
+                exports.push(format!(
+                    "{name} ({local} from {})",
+                    request(&requests, module)
+                ));
+            }
+        }
+        for _ in 0..self.leb128()? {
+            let module = self.leb128()?;
+            exports.push(format!("* from {}", request(&requests, module)));
+        }
+        let mut imports = vec![];
+        for _ in 0..self.leb128()? {
+            self.leb128()?;
+            let name = self.atom()?;
+            let module = self.leb128()?;
+            imports.push(format!("{name} from {}", request(&requests, module)));
+        }
+        // Whether the module uses top level await.
+        self.u8()?;
+        let function = Box::new(self.value()?);
+        Ok(Module {
+            name,
+            requests,
+            imports,
+            exports,
+            function,
+        })
+    }
+}
+
+/// Quotes atoms which aren't identifiers.
+fn quote_atom(atom: &str) -> String {
+    let mut chars = atom.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_alphabetic() || matches!(c, '_' | '$' | '#' | '<'))
+        && chars.all(|c| c.is_alphanumeric() || matches!(c, '_' | '$' | '>'));
+    if is_identifier {
+        atom.to_string()
+    } else {
+        format!("{atom:?}")
+    }
+}
+
+impl Module {
+    fn write(&self, out: &mut String) -> Result<()> {
+        writeln!(out, "module {}", self.name)?;
+        let requests = self
+            .requests
+            .iter()
+            .map(|name| format!("{name:?}"))
+            .collect::<Vec<_>>();
+        for (label, names) in [
+            ("requests", &requests),
+            ("imports", &self.imports),
+            ("exports", &self.exports),
+        ] {
+            if !names.is_empty() {
+                writeln!(out, "  {label}: {}", names.join(", "))?;
+            }
+        }
+        match &*self.function {
+            Value::Function(function) => {
+                writeln!(out)?;
+                function.write(out)
+            }
+            _ => bail!("Module {} doesn't have a function", self.name),
+        }
+    }
+}
+
+impl Function {
+    fn write(&self, out: &mut String) -> Result<()> {
+        let kind = match self.kind {
+            1 => "function*",
+            2 => "async function",
+            3 => "async function*",
+            _ => "function",
+        };
+        write!(out, "{kind} {}", self.name)?;
+        if let Some((file, line, column)) = &self.location {
+            write!(out, " ({file}:{line}:{column})")?;
+        }
+        writeln!(out)?;
+
+        // The `JS_MODE_*` flags.
+        let modes = [(1, "strict"), (4, "math")]
+            .into_iter()
+            .filter(|(flag, _)| self.mode & flag != 0)
+            .map(|(_, mode)| mode.to_string())
+            .collect::<Vec<_>>();
+        for (label, names) in [
+            ("mode", &modes),
+            ("args", &self.args),
+            ("vars", &self.vars),
+            ("closure vars", &self.closure_vars),
+        ] {
+            if !names.is_empty() {
+                writeln!(out, "  {label}: {}", names.join(", "))?;
+            }
+        }
+        writeln!(out, "  stack size: {}", self.stack_size)?;
+        for (offset, instruction) in &self.instructions {
+            writeln!(out, "  {offset:5}  {instruction}")?;
+        }
+
+        for constant in &self.constants {
+            if let Value::Function(function) = constant {
+                writeln!(out)?;
+                function.write(out)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{disassemble, find_bytecode};
+    use anyhow::Result;
+    use walrus::{DataKind, Module, ModuleConfig};
+
+    /// `export function add(a, b) { return a + b; }` compiled to bytecode as
+    /// `function.mjs`.
+    const ADD: &[u8] = &[
+        0x43, 0x03, 0x18, 0x66, 0x75, 0x6e, 0x63, 0x74, 0x69, 0x6f, 0x6e, 0x2e, 0x6d, 0x6a, 0x73,
+        0x02, 0x61, 0x02, 0x62, 0x0d, 0xc8, 0x03, 0x00, 0x01, 0x00, 0x00, 0xd4, 0x01, 0x00, 0x00,
+        0x00, 0x0c, 0x20, 0x06, 0x01, 0xa4, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x01, 0x09, 0x00,
+        0xd4, 0x01, 0x00, 0x01, 0x08, 0xec, 0x05, 0xc2, 0x00, 0xe3, 0x29, 0x06, 0x2e, 0xc8, 0x03,
+        0x01, 0x02, 0x01, 0x26, 0x00, 0x00, 0x0c, 0x43, 0x06, 0x01, 0xd4, 0x01, 0x02, 0x00, 0x02,
+        0x02, 0x00, 0x00, 0x04, 0x02, 0xca, 0x03, 0x00, 0x01, 0x00, 0xcc, 0x03, 0x00, 0x01, 0x00,
+        0xd3, 0xd4, 0x9e, 0x28, 0xc8, 0x03, 0x01, 0x00, 0x07, 0x09, 0x00, 0x00, 0x2a, 0x00, 0x00,
+        0x0e, 0x00, 0x01, 0x08,
+    ];
+
+    #[test]
+    fn test_disassemble() -> Result<()> {
+        let listing = disassemble(ADD)?;
+        assert!(listing.starts_with("module function.mjs\n  exports: add\n"));
+        assert!(listing.contains("  fclosure8 0: <function add>\n"));
+        assert!(listing.contains(
+            "function add (function.mjs:1:8)
+  mode: strict
+  args: a, b
+  stack size: 2
+      0  get_arg0 0: a
+      1  get_arg1 1: b
+      2  add
+      3  return
+"
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn test_disassemble_rejects_invalid_bytecode() {
+        assert!(disassemble(b"console.log(42);").is_err());
+        assert!(disassemble(&ADD[..ADD.len() - 1]).is_err());
+        assert!(disassemble(&[ADD, &[0]].concat()).is_err());
+    }
+
+    #[test]
+    fn test_find_bytecode() -> Result<()> {
+        let mut module = Module::with_config(ModuleConfig::new());
+        module.data.add(DataKind::Passive, b"add".to_vec());
+        module.data.add(DataKind::Passive, ADD.to_vec());
+        assert_eq!(ADD, find_bytecode(&module.emit_wasm())?);
+
+        let mut module = Module::with_config(ModuleConfig::new());
+        assert!(find_bytecode(&module.emit_wasm()).is_err());
+        Ok(())
+    }
+}
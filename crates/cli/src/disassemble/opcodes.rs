@@ -0,0 +1,305 @@
+//! The opcodes of the QuickJS bytecode, in the order of `quickjs-opcode.h`
+//! from `rquickjs-sys` 0.6.1 built with `CONFIG_BIGNUM` and `SHORT_OPCODES`,
+//! which gives them their values. The temporary opcodes only used while
+//! compiling are left out.
+
+use Format::*;
+
+/// How the operands of an opcode are encoded. The formats only used by the
+/// temporary opcodes are left out.
+#[derive(Clone, Copy)]
+pub(super) enum Format {
+    None,
+    /// An integer implied by the opcode.
+    NoneInt(i32),
+    /// A local variable implied by the opcode.
+    NoneLoc(u16),
+    /// An argument implied by the opcode.
+    NoneArg(u16),
+    /// A closure variable implied by the opcode.
+    NoneVarRef(u16),
+    U8,
+    I8,
+    Loc8,
+    Const8,
+    Label8,
+    U16,
+    I16,
+    Label16,
+    Npop,
+    /// An argument count implied by the opcode.
+    Npopx(u16),
+    NpopU16,
+    Loc,
+    Arg,
+    VarRef,
+    I32,
+    Const,
+    Label,
+    Atom,
+    AtomU8,
+    AtomU16,
+    AtomLabelU8,
+}
+
+pub(super) struct Opcode {
+    pub(super) name: &'static str,
+    /// The size of the instruction, including its operands.
+    pub(super) size: usize,
+    pub(super) format: Format,
+}
+
+const fn op(name: &'static str, size: usize, format: Format) -> Opcode {
+    Opcode { name, size, format }
+}
+
+pub(super) const OPCODES: [Opcode; 248] = [
+    op("invalid", 1, None),
+    op("push_i32", 5, I32),
+    op("push_const", 5, Const),
+    op("fclosure", 5, Const),
+    op("push_atom_value", 5, Atom),
+    op("private_symbol", 5, Atom),
+    op("undefined", 1, None),
+    op("null", 1, None),
+    op("push_this", 1, None),
+    op("push_false", 1, None),
+    op("push_true", 1, None),
+    op("object", 1, None),
+    op("special_object", 2, U8),
+    op("rest", 3, U16),
+    op("drop", 1, None),
+    op("nip", 1, None),
+    op("nip1", 1, None),
+    op("dup", 1, None),
+    op("dup1", 1, None),
+    op("dup2", 1, None),
+    op("dup3", 1, None),
+    op("insert2", 1, None),
+    op("insert3", 1, None),
+    op("insert4", 1, None),
+    op("perm3", 1, None),
+    op("perm4", 1, None),
+    op("perm5", 1, None),
+    op("swap", 1, None),
+    op("swap2", 1, None),
+    op("rot3l", 1, None),
+    op("rot3r", 1, None),
+    op("rot4l", 1, None),
+    op("rot5l", 1, None),
+    op("call_constructor", 3, Npop),
+    op("call", 3, Npop),
+    op("tail_call", 3, Npop),
+    op("call_method", 3, Npop),
+    op("tail_call_method", 3, Npop),
+    op("array_from", 3, Npop),
+    op("apply", 3, U16),
+    op("return", 1, None),
+    op("return_undef", 1, None),
+    op("check_ctor_return", 1, None),
+    op("check_ctor", 1, None),
+    op("check_brand", 1, None),
+    op("add_brand", 1, None),
+    op("return_async", 1, None),
+    op("throw", 1, None),
+    op("throw_error", 6, AtomU8),
+    op("eval", 5, NpopU16),
+    op("apply_eval", 3, U16),
+    op("regexp", 1, None),
+    op("get_super", 1, None),
+    op("import", 1, None),
+    op("check_var", 5, Atom),
+    op("get_var_undef", 5, Atom),
+    op("get_var", 5, Atom),
+    op("put_var", 5, Atom),
+    op("put_var_init", 5, Atom),
+    op("put_var_strict", 5, Atom),
+    op("get_ref_value", 1, None),
+    op("put_ref_value", 1, None),
+    op("define_var", 6, AtomU8),
+    op("check_define_var", 6, AtomU8),
+    op("define_func", 6, AtomU8),
+    op("get_field", 5, Atom),
+    op("get_field2", 5, Atom),
+    op("put_field", 5, Atom),
+    op("get_private_field", 1, None),
+    op("put_private_field", 1, None),
+    op("define_private_field", 1, None),
+    op("get_array_el", 1, None),
+    op("get_array_el2", 1, None),
+    op("put_array_el", 1, None),
+    op("get_super_value", 1, None),
+    op("put_super_value", 1, None),
+    op("define_field", 5, Atom),
+    op("set_name", 5, Atom),
+    op("set_name_computed", 1, None),
+    op("set_proto", 1, None),
+    op("set_home_object", 1, None),
+    op("define_array_el", 1, None),
+    op("append", 1, None),
+    op("copy_data_properties", 2, U8),
+    op("define_method", 6, AtomU8),
+    op("define_method_computed", 2, U8),
+    op("define_class", 6, AtomU8),
+    op("define_class_computed", 6, AtomU8),
+    op("get_loc", 3, Loc),
+    op("put_loc", 3, Loc),
+    op("set_loc", 3, Loc),
+    op("get_arg", 3, Arg),
+    op("put_arg", 3, Arg),
+    op("set_arg", 3, Arg),
+    op("get_var_ref", 3, VarRef),
+    op("put_var_ref", 3, VarRef),
+    op("set_var_ref", 3, VarRef),
+    op("set_loc_uninitialized", 3, Loc),
+    op("get_loc_check", 3, Loc),
+    op("put_loc_check", 3, Loc),
+    op("put_loc_check_init", 3, Loc),
+    op("get_loc_checkthis", 3, Loc),
+    op("get_var_ref_check", 3, VarRef),
+    op("put_var_ref_check", 3, VarRef),
+    op("put_var_ref_check_init", 3, VarRef),
+    op("close_loc", 3, Loc),
+    op("if_false", 5, Label),
+    op("if_true", 5, Label),
+    op("goto", 5, Label),
+    op("catch", 5, Label),
+    op("gosub", 5, Label),
+    op("ret", 1, None),
+    op("nip_catch", 1, None),
+    op("to_object", 1, None),
+    op("to_propkey", 1, None),
+    op("to_propkey2", 1, None),
+    op("with_get_var", 10, AtomLabelU8),
+    op("with_put_var", 10, AtomLabelU8),
+    op("with_delete_var", 10, AtomLabelU8),
+    op("with_make_ref", 10, AtomLabelU8),
+    op("with_get_ref", 10, AtomLabelU8),
+    op("with_get_ref_undef", 10, AtomLabelU8),
+    op("make_loc_ref", 7, AtomU16),
+    op("make_arg_ref", 7, AtomU16),
+    op("make_var_ref_ref", 7, AtomU16),
+    op("make_var_ref", 5, Atom),
+    op("for_in_start", 1, None),
+    op("for_of_start", 1, None),
+    op("for_await_of_start", 1, None),
+    op("for_in_next", 1, None),
+    op("for_of_next", 2, U8),
+    op("iterator_check_object", 1, None),
+    op("iterator_get_value_done", 1, None),
+    op("iterator_close", 1, None),
+    op("iterator_next", 1, None),
+    op("iterator_call", 2, U8),
+    op("initial_yield", 1, None),
+    op("yield", 1, None),
+    op("yield_star", 1, None),
+    op("async_yield_star", 1, None),
+    op("await", 1, None),
+    op("neg", 1, None),
+    op("plus", 1, None),
+    op("dec", 1, None),
+    op("inc", 1, None),
+    op("post_dec", 1, None),
+    op("post_inc", 1, None),
+    op("dec_loc", 2, Loc8),
+    op("inc_loc", 2, Loc8),
+    op("add_loc", 2, Loc8),
+    op("not", 1, None),
+    op("lnot", 1, None),
+    op("typeof", 1, None),
+    op("delete", 1, None),
+    op("delete_var", 5, Atom),
+    op("mul", 1, None),
+    op("div", 1, None),
+    op("mod", 1, None),
+    op("add", 1, None),
+    op("sub", 1, None),
+    op("pow", 1, None),
+    op("shl", 1, None),
+    op("sar", 1, None),
+    op("shr", 1, None),
+    op("lt", 1, None),
+    op("lte", 1, None),
+    op("gt", 1, None),
+    op("gte", 1, None),
+    op("instanceof", 1, None),
+    op("in", 1, None),
+    op("eq", 1, None),
+    op("neq", 1, None),
+    op("strict_eq", 1, None),
+    op("strict_neq", 1, None),
+    op("and", 1, None),
+    op("xor", 1, None),
+    op("or", 1, None),
+    op("is_undefined_or_null", 1, None),
+    op("private_in", 1, None),
+    op("mul_pow10", 1, None),
+    op("math_mod", 1, None),
+    op("nop", 1, None),
+    op("push_minus1", 1, NoneInt(-1)),
+    op("push_0", 1, NoneInt(0)),
+    op("push_1", 1, NoneInt(1)),
+    op("push_2", 1, NoneInt(2)),
+    op("push_3", 1, NoneInt(3)),
+    op("push_4", 1, NoneInt(4)),
+    op("push_5", 1, NoneInt(5)),
+    op("push_6", 1, NoneInt(6)),
+    op("push_7", 1, NoneInt(7)),
+    op("push_i8", 2, I8),
+    op("push_i16", 3, I16),
+    op("push_const8", 2, Const8),
+    op("fclosure8", 2, Const8),
+    op("push_empty_string", 1, None),
+    op("get_loc8", 2, Loc8),
+    op("put_loc8", 2, Loc8),
+    op("set_loc8", 2, Loc8),
+    op("get_loc0", 1, NoneLoc(0)),
+    op("get_loc1", 1, NoneLoc(1)),
+    op("get_loc2", 1, NoneLoc(2)),
+    op("get_loc3", 1, NoneLoc(3)),
+    op("put_loc0", 1, NoneLoc(0)),
+    op("put_loc1", 1, NoneLoc(1)),
+    op("put_loc2", 1, NoneLoc(2)),
+    op("put_loc3", 1, NoneLoc(3)),
+    op("set_loc0", 1, NoneLoc(0)),
+    op("set_loc1", 1, NoneLoc(1)),
+    op("set_loc2", 1, NoneLoc(2)),
+    op("set_loc3", 1, NoneLoc(3)),
+    op("get_arg0", 1, NoneArg(0)),
+    op("get_arg1", 1, NoneArg(1)),
+    op("get_arg2", 1, NoneArg(2)),
+    op("get_arg3", 1, NoneArg(3)),
+    op("put_arg0", 1, NoneArg(0)),
+    op("put_arg1", 1, NoneArg(1)),
+    op("put_arg2", 1, NoneArg(2)),
+    op("put_arg3", 1, NoneArg(3)),
+    op("set_arg0", 1, NoneArg(0)),
+    op("set_arg1", 1, NoneArg(1)),
+    op("set_arg2", 1, NoneArg(2)),
+    op("set_arg3", 1, NoneArg(3)),
+    op("get_var_ref0", 1, NoneVarRef(0)),
+    op("get_var_ref1", 1, NoneVarRef(1)),
+    op("get_var_ref2", 1, NoneVarRef(2)),
+    op("get_var_ref3", 1, NoneVarRef(3)),
+    op("put_var_ref0", 1, NoneVarRef(0)),
+    op("put_var_ref1", 1, NoneVarRef(1)),
+    op("put_var_ref2", 1, NoneVarRef(2)),
+    op("put_var_ref3", 1, NoneVarRef(3)),
+    op("set_var_ref0", 1, NoneVarRef(0)),
+    op("set_var_ref1", 1, NoneVarRef(1)),
+    op("set_var_ref2", 1, NoneVarRef(2)),
+    op("set_var_ref3", 1, NoneVarRef(3)),
+    op("get_length", 1, None),
+    op("if_false8", 2, Label8),
+    op("if_true8", 2, Label8),
+    op("goto8", 2, Label8),
+    op("goto16", 3, Label16),
+    op("call0", 1, Npopx(0)),
+    op("call1", 1, Npopx(1)),
+    op("call2", 1, Npopx(2)),
+    op("call3", 1, Npopx(3)),
+    op("is_undefined", 1, None),
+    op("is_null", 1, None),
+    op("typeof_is_undefined", 1, None),
+    op("typeof_is_function", 1, None),
+];
@@ -1,63 +1,159 @@
-use anyhow::Result;
-use serde::Deserialize;
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, str};
 use wasmtime::{AsContextMut, Engine, Linker};
 use wasmtime_wasi::{pipe::MemoryOutputPipe, WasiCtxBuilder};
 
 use crate::{CliPlugin, PluginKind, commands::JsOptionValue};
 
-#[derive(Debug, Deserialize)]
+/// The version of the runtime config passed to the default plugin. The plugin
+/// rejects configs with a newer version than the one it was built with.
+pub(crate) const CONFIG_VERSION: u64 = 1;
+
+/// The name of the property holding the version of the runtime config.
+pub(crate) const CONFIG_VERSION_PROPERTY: &str = "config-version";
+
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct ConfigSchema {
     pub(crate) supported_properties: Vec<JsConfigProperty>,
 }
 
 impl ConfigSchema {
+    /// Retrieves the config schema of a plugin by calling its `config_schema`
+    /// export. User plugins without the export have no schema.
     pub(crate) fn from_cli_plugin(cli_plugin: &CliPlugin) -> Result<Option<ConfigSchema>> {
-        match cli_plugin.kind {
-            PluginKind::User => Ok(None),
-            PluginKind::Default => {
-                let engine = Engine::default();
-                let module = wasmtime::Module::new(&engine, cli_plugin.as_plugin().as_bytes())?;
-                let mut linker = Linker::new(&engine);
-                wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
-                let stdout = MemoryOutputPipe::new(usize::MAX);
-                let wasi = WasiCtxBuilder::new()
-                    .inherit_stderr()
-                    .stdout(stdout.clone())
-                    .build_p1();
-                let mut store = wasmtime::Store::new(&engine, wasi);
-                let instance = linker.instantiate(store.as_context_mut(), &module)?;
-                instance
-                    .get_typed_func::<(), ()>(store.as_context_mut(), "config_schema")?
-                    .call(store.as_context_mut(), ())?;
-                drop(store);
-                let config_json = stdout.try_into_inner().unwrap().to_vec();
-                let config_schema = serde_json::from_slice::<ConfigSchema>(&config_json)?;
-                let mut configs = Vec::with_capacity(config_schema.supported_properties.len());
-                for config in config_schema.supported_properties {
-                    configs.push(JsConfigProperty {
-                        name: config.name,
-                        doc: config.doc,
-                    });
-                }
-
-                Ok(Some(Self {
-                    supported_properties: configs,
-                }))
-            }
+        let engine = Engine::default();
+        let module = wasmtime::Module::new(&engine, cli_plugin.as_plugin().as_bytes())?;
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+        if let PluginKind::User = cli_plugin.kind {
+            // User plugins may import functions from their host, which the
+            // schema shouldn't depend on.
+            linker.define_unknown_imports_as_traps(&module)?;
         }
+        let stdout = MemoryOutputPipe::new(usize::MAX);
+        let wasi = WasiCtxBuilder::new()
+            .inherit_stderr()
+            .stdout(stdout.clone())
+            .build_p1();
+        let mut store = wasmtime::Store::new(&engine, wasi);
+        let instance = linker.instantiate(store.as_context_mut(), &module)?;
+        let config_schema =
+            match instance.get_typed_func::<(), ()>(store.as_context_mut(), "config_schema") {
+                Ok(config_schema) => config_schema,
+                Err(_) if matches!(cli_plugin.kind, PluginKind::User) => return Ok(None),
+                Err(e) => return Err(e),
+            };
+        config_schema.call(store.as_context_mut(), ())?;
+        drop(store);
+        let config_json = stdout.try_into_inner().unwrap().to_vec();
+        let config_schema = serde_json::from_slice::<ConfigSchema>(&config_json)
+            .context("Plugin returned an invalid config schema")?;
+        Ok(Some(config_schema))
     }
 }
 
 /// A property that is in the config schema returned by the plugin.
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub(crate) struct JsConfigProperty {
     /// The name of the property (e.g., `simd-json-builtins`).
     pub(crate) name: String,
     /// The documentation to display for the property.
     pub(crate) doc: String,
+    /// The type of the property's value.
+    #[serde(default, rename = "type")]
+    pub(crate) ty: JsConfigPropertyType,
+    /// The value used when the property isn't set, if the plugin declares
+    /// it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) default: Option<serde_json::Value>,
+    /// The smallest value of a numeric property, if bounded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) min: Option<u64>,
+    /// The largest value of a numeric property, if bounded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub(crate) max: Option<u64>,
+}
+
+impl JsConfigProperty {
+    /// The placeholder for the value of the property in the help text.
+    pub(crate) fn value_help(&self) -> &'static str {
+        match self.ty {
+            JsConfigPropertyType::Bool => "[=y|n]",
+            JsConfigPropertyType::Number if self.name.ends_with("-ms") => "=<milliseconds>",
+            JsConfigPropertyType::Number if self.name.ends_with("-bytes") => "=<bytes>",
+            JsConfigPropertyType::Number => "=<number>",
+            JsConfigPropertyType::String => "=<string>",
+        }
+    }
+
+    /// The documentation of the property followed by its default value, if
+    /// any.
+    pub(crate) fn doc_help(&self) -> String {
+        match &self.default {
+            Some(serde_json::Value::Bool(true)) => format!("{} Default: y.\n", self.doc),
+            Some(serde_json::Value::Bool(false)) => format!("{} Default: n.\n", self.doc),
+            Some(serde_json::Value::String(s)) => format!("{} Default: {s}.\n", self.doc),
+            Some(default) => format!("{} Default: {default}.\n", self.doc),
+            None => self.doc.clone(),
+        }
+    }
+
+    /// Converts a value given on the command line to the type of the
+    /// property, checking numbers are within its bounds.
+    pub(crate) fn parse_value(&self, value: JsOptionValue) -> Result<JsOptionValue> {
+        let value = self.convert_value(value)?;
+        if let JsOptionValue::Number(n) = value {
+            let name = &self.name;
+            match (self.min, self.max) {
+                (Some(min), Some(max)) if n < min || n > max => {
+                    bail!("Invalid value {n} for {name}, expected a number from {min} to {max}")
+                }
+                (Some(min), _) if n < min => {
+                    bail!("Invalid value {n} for {name}, expected a number of at least {min}")
+                }
+                (_, Some(max)) if n > max => {
+                    bail!("Invalid value {n} for {name}, expected a number of at most {max}")
+                }
+                _ => {}
+            }
+        }
+        Ok(value)
+    }
+
+    fn convert_value(&self, value: JsOptionValue) -> Result<JsOptionValue> {
+        let name = &self.name;
+        match (self.ty, value) {
+            (JsConfigPropertyType::Bool, value @ JsOptionValue::Boolean(_))
+            | (JsConfigPropertyType::Number, value @ JsOptionValue::Number(_))
+            | (JsConfigPropertyType::String, value @ JsOptionValue::String(_)) => Ok(value),
+            (JsConfigPropertyType::Bool, JsOptionValue::String(s)) => match s.as_str() {
+                "y" => Ok(JsOptionValue::Boolean(true)),
+                "n" => Ok(JsOptionValue::Boolean(false)),
+                _ => bail!("Invalid value `{s}` for {name}, expected y or n"),
+            },
+            (JsConfigPropertyType::Number, JsOptionValue::String(s)) => s
+                .parse()
+                .map(JsOptionValue::Number)
+                .map_err(|_| anyhow!("Invalid value `{s}` for {name}, expected a number")),
+            (JsConfigPropertyType::Number, _) => bail!("{name} requires a number"),
+            (JsConfigPropertyType::String, _) => bail!("{name} requires a string"),
+            (JsConfigPropertyType::Bool, _) => bail!("Invalid value for {name}, expected y or n"),
+        }
+    }
+}
+
+/// The type of the value of a config property.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum JsConfigPropertyType {
+    #[default]
+    #[serde(alias = "boolean")]
+    Bool,
+    Number,
+    String,
 }
 
 /// A collection of property names to their values.
@@ -82,6 +178,9 @@ impl JsConfig {
                 JsOptionValue::Number(n) => {
                     json_map.insert(key.clone(), serde_json::Value::Number((*n).into()));
                 }
+                JsOptionValue::String(s) => {
+                    json_map.insert(key.clone(), serde_json::Value::String(s.clone()));
+                }
             }
         }
         Ok(serde_json::to_vec(&json_map)?)
@@ -104,4 +203,13 @@ impl JsConfig {
             _ => None,
         }
     }
+
+    #[cfg(test)]
+    /// Retrieve a string value for a property name.
+    pub(crate) fn get_string(&self, name: &str) -> Option<&str> {
+        match self.0.get(name) {
+            Some(JsOptionValue::String(s)) => Some(s),
+            _ => None,
+        }
+    }
 }
@@ -1,21 +1,29 @@
 mod commands;
+mod disassemble;
 mod js_config;
 mod option;
 mod plugin;
+mod run;
+mod stats;
+mod watch;
 
-use crate::commands::{Cli, Command, EmitPluginCommandOpts, JsGroupValue};
-use anyhow::Result;
+use crate::commands::{
+    BuildCommandOpts, Cli, Command, EmitPluginCommandOpts, JsGroupValue, ModuleInput,
+};
+use anyhow::{bail, Context, Result};
 use clap::Parser;
 
 use commands::CodegenOptionGroup;
-use javy_codegen::{Generator, LinkingKind, Plugin, WitOptions, JS};
-use js_config::JsConfig;
+use javy_codegen::{Generator, LinkingKind, Plugin, Target, WitOptions, JS};
+use js_config::{ConfigSchema, JsConfig};
 use plugin::{
     CliPlugin, PluginKind, UninitializedPlugin, PLUGIN_MODULE, QUICKJS_PROVIDER_V2_MODULE,
 };
+use stats::BuildStats;
 use std::fs;
 use std::fs::File;
-use std::io::Write;
+use std::io::{IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
 
 fn main() -> Result<()> {
     let args = Cli::parse();
@@ -63,19 +71,16 @@ fn main() -> Result<()> {
                 .js_runtime_config(JsConfig::default().to_json()?);
             set_producer_version(&mut generator);
 
-            let wasm = generator.generate(&js)?;
+            let wasm = generator
+                .generate(&js)
+                .with_context(|| format!("Failed to compile {}", opts.input.display()))?;
 
             fs::write(&opts.output, wasm)?;
             Ok(())
         }
         Command::Build(opts) => {
             let codegen_opts: CodegenOptionGroup = opts.codegen.clone().try_into()?;
-
-            // Always assume the default plugin if no plugin is provided.
-            let cli_plugin = match &codegen_opts.plugin {
-                Some(path) => CliPlugin::new(Plugin::new_from_path(path)?, PluginKind::User),
-                None => CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default),
-            };
+            let cli_plugin = cli_plugin(&codegen_opts)?;
 
             // Check for help in JavaScript options before reading input file
             for js_value in &opts.js {
@@ -87,37 +92,112 @@ fn main() -> Result<()> {
                 }
             }
 
-            // Now require input file if we're not showing help
-            let input_path = opts.input.clone().ok_or_else(|| {
-                anyhow::anyhow!("The following required arguments were not provided: <INPUT>")
-            })?;
-
-            let js = JS::from_file(&input_path)?;
-            let js_opts = JsConfig::from_group_values(&cli_plugin, opts.js.clone())?;
-
-            let mut generator = Generator::new(cli_plugin.into_plugin());
+            if opts.stats && codegen_opts.target == Target::Component {
+                bail!("Build stats are only available for modules, not components");
+            }
 
-            // Always link to the default plugin if no plugin is provided.
-            if codegen_opts.plugin.is_none() {
-                generator.linking_default_plugin(true);
+            let input = BuildInput::from_opt(opts.input.as_deref())?;
+            if !opts.watch {
+                return build_to_output(opts, cli_plugin, &input, codegen_opts);
             }
 
-            // Configure the generator with the provided options.
-            generator
-                .wit_opts(codegen_opts.wit)
-                .source_compression(!codegen_opts.source_compression)
-                .js_runtime_config(js_opts.to_json()?);
-            set_producer_version(&mut generator);
+            let BuildInput::File(path) = &input else {
+                bail!("--watch requires an input file, the input can't be read from stdin");
+            };
+            if writes_to_stdout(&opts.output) {
+                bail!("--watch requires an output file, the output can't be written to stdout");
+            }
+            let mut paths = vec![path.clone()];
+            paths.extend(opts.modules.iter().map(|module| module.path.clone()));
+            paths.extend(codegen_opts.input_source_map.clone());
+            paths.extend(codegen_opts.wit.path.clone());
+            watch::watch(&paths, || {
+                let result = crate::cli_plugin(&codegen_opts).and_then(|cli_plugin| {
+                    build_to_output(opts, cli_plugin, &input, codegen_opts.clone())
+                });
+                match result {
+                    Ok(()) => eprintln!("Built {}", opts.output.display()),
+                    Err(e) => eprintln!("Build failed: {e:?}"),
+                }
+            })
+        }
+        Command::Run(opts) => {
+            let input = fs::read(&opts.input)
+                .with_context(|| format!("Failed to open input file {}", opts.input.display()))?;
 
-            if codegen_opts.dynamic {
-                generator.linking(LinkingKind::Dynamic);
+            let codegen_opts: CodegenOptionGroup = opts.codegen.clone().try_into()?;
+            let plugin = codegen_opts
+                .plugin
+                .as_ref()
+                .map(Plugin::new_from_path)
+                .transpose()?;
+            let wasm = if run::is_wasm(&input) {
+                input
             } else {
-                generator.linking(LinkingKind::Static);
+                let cli_plugin = cli_plugin(&codegen_opts)?;
+                build(
+                    cli_plugin,
+                    &BuildInput::File(opts.input.clone()),
+                    codegen_opts,
+                    opts.js.clone(),
+                    &opts.modules,
+                    false,
+                )?
             };
 
-            let wasm = generator.generate(&js)?;
+            let stdin = match &opts.stdin {
+                Some(path) => Some(
+                    fs::read(path)
+                        .with_context(|| format!("Failed to open stdin file {}", path.display()))?,
+                ),
+                None => None,
+            };
 
-            fs::write(&opts.output, wasm)?;
+            let outcome = run::run(&wasm, &opts.invoke, stdin, plugin.as_ref())?;
+            if opts.fuel {
+                eprintln!("Fuel consumed: {}", outcome.fuel_consumed);
+            }
+            let exit_code = outcome
+                .status
+                .with_context(|| format!("Failed to run {}", opts.input.display()))?;
+            if exit_code != 0 {
+                std::process::exit(exit_code);
+            }
+            Ok(())
+        }
+        Command::Disassemble(opts) => {
+            let input = fs::read(&opts.input)
+                .with_context(|| format!("Failed to open input file {}", opts.input.display()))?;
+
+            let bytecode = if run::is_wasm(&input) {
+                disassemble::find_bytecode(&input)?
+            } else {
+                let codegen_opts: CodegenOptionGroup = opts.codegen.clone().try_into()?;
+                let cli_plugin = cli_plugin(&codegen_opts)?;
+                let mut generator = Generator::new(cli_plugin.into_plugin());
+                generator
+                    .pretty_errors(codegen_opts.pretty_errors)
+                    .minify(codegen_opts.minify);
+                for module in &opts.modules {
+                    generator.module(module.name.clone(), JS::from_file(&module.path)?);
+                }
+                generator
+                    .bytecode(&JS::from_file(&opts.input)?)
+                    .with_context(|| format!("Failed to compile {}", opts.input.display()))?
+            };
+
+            print!("{}", disassemble::disassemble(&bytecode)?);
+            Ok(())
+        }
+        Command::PluginInfo(opts) => {
+            let cli_plugin = match &opts.plugin {
+                Some(path) => CliPlugin::new(Plugin::new_from_path(path)?, PluginKind::User),
+                None => CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default),
+            };
+            let Some(schema) = ConfigSchema::from_cli_plugin(&cli_plugin)? else {
+                bail!("The plugin does not export a config schema");
+            };
+            println!("{}", serde_json::to_string_pretty(&schema)?);
             Ok(())
         }
         Command::InitPlugin(opts) => {
@@ -136,6 +216,173 @@ fn main() -> Result<()> {
     }
 }
 
+/// The plugin to build with, which is the default plugin if the codegen
+/// options don't provide one.
+fn cli_plugin(codegen_opts: &CodegenOptionGroup) -> Result<CliPlugin> {
+    Ok(match &codegen_opts.plugin {
+        Some(path) => CliPlugin::new(Plugin::new_from_path(path)?, PluginKind::User),
+        None => CliPlugin::new(Plugin::new(PLUGIN_MODULE.into()), PluginKind::Default),
+    })
+}
+
+/// Where the JavaScript source of a build comes from.
+enum BuildInput {
+    /// A JavaScript input file.
+    File(PathBuf),
+    /// JavaScript source piped through stdin.
+    Stdin,
+}
+
+impl BuildInput {
+    /// The input for an optional `INPUT` argument, where `-` or omitting it
+    /// while stdin is not a terminal reads the source from stdin.
+    fn from_opt(input: Option<&Path>) -> Result<Self> {
+        match input {
+            Some(path) if path == Path::new("-") => Ok(Self::Stdin),
+            Some(path) => Ok(Self::File(path.to_path_buf())),
+            None if !std::io::stdin().is_terminal() => Ok(Self::Stdin),
+            None => {
+                bail!("The following required arguments were not provided: <INPUT>")
+            }
+        }
+    }
+
+    fn read(&self) -> Result<JS> {
+        match self {
+            Self::File(path) => JS::from_file(path),
+            Self::Stdin => {
+                let mut contents = String::new();
+                std::io::stdin()
+                    .read_to_string(&mut contents)
+                    .context("Failed to read JavaScript source from stdin")?;
+                Ok(JS::from_string(contents))
+            }
+        }
+    }
+
+    /// The name of the input in messages and source maps.
+    fn name(&self) -> String {
+        match self {
+            Self::File(path) => path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into_owned(),
+            Self::Stdin => "<stdin>".to_string(),
+        }
+    }
+}
+
+impl std::fmt::Display for BuildInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::File(path) => write!(f, "{}", path.display()),
+            Self::Stdin => f.write_str("<stdin>"),
+        }
+    }
+}
+
+/// Generates the WebAssembly module for the input of the build command and
+/// writes it to its output.
+fn build_to_output(
+    opts: &BuildCommandOpts,
+    cli_plugin: CliPlugin,
+    input: &BuildInput,
+    codegen_opts: CodegenOptionGroup,
+) -> Result<()> {
+    let wasm = build(
+        cli_plugin,
+        input,
+        codegen_opts,
+        opts.js.clone(),
+        &opts.modules,
+        opts.deterministic,
+    )?;
+    if opts.stats {
+        eprint!("{}", BuildStats::measure(&wasm)?);
+    }
+    if writes_to_stdout(&opts.output) {
+        let mut stdout = std::io::stdout().lock();
+        stdout.write_all(&wasm)?;
+        stdout.flush()?;
+    } else {
+        fs::write(&opts.output, wasm)?;
+    }
+    Ok(())
+}
+
+/// Whether the output path is `-`, which writes the output to stdout.
+fn writes_to_stdout(output: &Path) -> bool {
+    output == Path::new("-")
+}
+
+/// Generates the WebAssembly module for a JavaScript input.
+fn build(
+    cli_plugin: CliPlugin,
+    input: &BuildInput,
+    codegen_opts: CodegenOptionGroup,
+    js_values: Vec<JsGroupValue>,
+    modules: &[ModuleInput],
+    deterministic: bool,
+) -> Result<Vec<u8>> {
+    let js = input.read()?;
+    let js_opts = JsConfig::from_group_values(&cli_plugin, js_values)?;
+
+    let mut generator = Generator::new(cli_plugin.into_plugin());
+
+    // Always link to the default plugin if no plugin is provided.
+    if codegen_opts.plugin.is_none() {
+        generator.linking_default_plugin(true);
+    }
+
+    // Configure the generator with the provided options.
+    generator
+        .wit_opts(codegen_opts.wit)
+        .source_compression(!codegen_opts.source_compression)
+        .pretty_errors(codegen_opts.pretty_errors)
+        .minify(codegen_opts.minify)
+        .deterministic(deterministic)
+        .target(codegen_opts.target)
+        .js_runtime_config(js_opts.to_json()?);
+    set_producer_version(&mut generator);
+
+    if codegen_opts.dynamic {
+        generator.linking(LinkingKind::Dynamic);
+    } else {
+        generator.linking(LinkingKind::Static);
+    };
+
+    // Name the module after the input file, unless it's built with a user
+    // plugin which might not support naming it.
+    let module_name = match (&codegen_opts.module_name, input) {
+        (Some(name), _) => Some(name.clone()),
+        (None, BuildInput::File(_)) if codegen_opts.plugin.is_none() => Some(input.name()),
+        _ => None,
+    };
+    if let Some(name) = module_name {
+        generator.module_name(name);
+    }
+
+    if let Some(path) = &codegen_opts.input_source_map {
+        let source_map = fs::read(path)
+            .with_context(|| format!("Failed to open source map {}", path.display()))?;
+        generator.input_source_map(source_map);
+    }
+
+    for module in modules {
+        generator.module(module.name.clone(), JS::from_file(&module.path)?);
+    }
+
+    let wasm = generator
+        .generate(&js)
+        .with_context(|| format!("Failed to compile {input}"))?;
+
+    if let Some(path) = &codegen_opts.source_map {
+        fs::write(path, generator.source_map(&js, &input.name())?)?;
+    }
+    Ok(wasm)
+}
+
 fn emit_plugin(opts: &EmitPluginCommandOpts) -> Result<()> {
     let mut file: Box<dyn Write> = match opts.out.as_ref() {
         Some(path) => Box::new(File::create(path)?),
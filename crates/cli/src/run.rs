@@ -0,0 +1,115 @@
+use anyhow::Result;
+use javy_codegen::{LinkedPlugin, Plugin};
+use std::collections::BTreeSet;
+use wasmtime::{Config, Engine, Linker, Module, Store};
+use wasmtime_wasi::{pipe::MemoryInputPipe, preview1, I32Exit, WasiCtxBuilder};
+
+use crate::plugin::{PLUGIN_MODULE, QUICKJS_PROVIDER_V2_MODULE};
+
+/// The import namespace of dynamically linked modules generated for the v2
+/// plugin.
+const QUICKJS_PROVIDER_V2_NAMESPACE: &str = "javy_quickjs_provider_v2";
+
+/// The outcome of running a module.
+pub(crate) struct Outcome {
+    /// The fuel consumed while running the module, including the
+    /// instantiation of the plugin it links, if any.
+    pub(crate) fuel_consumed: u64,
+    /// The exit code of the module, or the trap it stopped with.
+    pub(crate) status: Result<i32>,
+}
+
+/// Whether `bytes` are a WebAssembly module rather than JavaScript source
+/// code.
+pub(crate) fn is_wasm(bytes: &[u8]) -> bool {
+    bytes.starts_with(b"\0asm")
+}
+
+/// Runs the function exported as `func` by a WebAssembly module generated by
+/// Javy, with the stdout and stderr of this process, and with `stdin` or the
+/// stdin of this process as its stdin.
+///
+/// Dynamically linked modules are linked to `plugin`, or to the default
+/// plugin if there's none, or to the v2 plugin, depending on the namespace
+/// they import. Modules recording that they were generated with another
+/// plugin are rejected before instantiation. The host functions the plugin
+/// imports trap when called.
+pub(crate) fn run(
+    wasm: &[u8],
+    func: &str,
+    stdin: Option<Vec<u8>>,
+    plugin: Option<&Plugin>,
+) -> Result<Outcome> {
+    let mut config = Config::new();
+    config.consume_fuel(true);
+    let engine = Engine::new(&config)?;
+    let mut linker = Linker::new(&engine);
+    preview1::add_to_linker_sync(&mut linker, |ctx| ctx)?;
+
+    let mut wasi = WasiCtxBuilder::new();
+    wasi.inherit_stdout().inherit_stderr();
+    match stdin {
+        Some(bytes) => wasi.stdin(MemoryInputPipe::new(bytes)),
+        None => wasi.inherit_stdin(),
+    };
+    let mut store = Store::new(&engine, wasi.build_p1());
+    store.set_fuel(u64::MAX)?;
+
+    let module = Module::new(&engine, wasm)?;
+    let default_plugin = Plugin::new(PLUGIN_MODULE.into());
+    let plugin = plugin.unwrap_or(&default_plugin);
+    if let Some(linked_plugin) = LinkedPlugin::from_module(wasm)? {
+        if linked_plugin.namespace != QUICKJS_PROVIDER_V2_NAMESPACE {
+            linked_plugin.ensure_provided_by(plugin)?;
+        }
+    }
+    let namespaces = module
+        .imports()
+        .map(|import| import.module())
+        .filter(|namespace| *namespace != "wasi_snapshot_preview1")
+        .collect::<BTreeSet<_>>();
+    for namespace in namespaces {
+        let plugin = if namespace == QUICKJS_PROVIDER_V2_NAMESPACE {
+            QUICKJS_PROVIDER_V2_MODULE
+        } else if namespace == plugin.import_namespace()? {
+            plugin.as_bytes()
+        } else {
+            // Let instantiating the module report the missing imports.
+            continue;
+        };
+        let plugin = Module::new(&engine, plugin)?;
+        linker.define_unknown_imports_as_traps(&plugin)?;
+        let instance = linker.instantiate(&mut store, &plugin)?;
+        linker.instance(&mut store, namespace, instance)?;
+    }
+
+    let instance = linker.instantiate(&mut store, &module)?;
+    let result = instance
+        .get_typed_func::<(), ()>(&mut store, func)?
+        .call(&mut store, ());
+
+    let fuel_consumed = u64::MAX - store.get_fuel()?;
+    let status = match result {
+        Ok(()) => Ok(0),
+        Err(e) => match e.downcast_ref::<I32Exit>() {
+            Some(exit) => Ok(exit.0),
+            None => Err(e),
+        },
+    };
+    Ok(Outcome {
+        fuel_consumed,
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::is_wasm;
+
+    #[test]
+    fn test_is_wasm() {
+        assert!(is_wasm(b"\0asm\x01\0\0\0"));
+        assert!(!is_wasm(b"console.log('\\0asm');"));
+        assert!(!is_wasm(b""));
+    }
+}
@@ -0,0 +1,64 @@
+//! The size breakdown of a generated module reported by `javy build --stats`.
+
+use anyhow::Result;
+use std::fmt;
+use walrus::IdsToIndices;
+
+use crate::disassemble;
+
+/// The name of the custom section holding the JavaScript source.
+const SOURCE_SECTION: &str = "javy_source";
+
+/// The sizes of the parts of a generated module, in bytes.
+pub(crate) struct BuildStats {
+    /// The size of the whole module.
+    total: usize,
+    /// The size of the JavaScript source embedded in the module, which is
+    /// compressed when source compression is enabled.
+    source: usize,
+    /// The size of the QuickJS bytecode evaluated by the module.
+    bytecode: usize,
+}
+
+impl BuildStats {
+    /// Measures the parts of a module generated by Javy.
+    pub(crate) fn measure(wasm: &[u8]) -> Result<Self> {
+        let module = walrus::Module::from_buffer(wasm)?;
+        let indices = IdsToIndices::default();
+        let source = module
+            .customs
+            .iter()
+            .filter(|(_, section)| section.name() == SOURCE_SECTION)
+            .map(|(_, section)| section.data(&indices).len())
+            .sum();
+        let bytecode = disassemble::find_bytecode(wasm)?.len();
+        Ok(Self {
+            total: wasm.len(),
+            source,
+            bytecode,
+        })
+    }
+
+    /// The size of everything that isn't the source or the bytecode, which
+    /// is the plugin itself when statically linked and the plugin imports
+    /// and glue code when dynamically linked.
+    fn plugin(&self) -> usize {
+        self.total.saturating_sub(self.source + self.bytecode)
+    }
+}
+
+impl fmt::Display for BuildStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.total.to_string().len();
+        let rows = [
+            ("Embedded source", self.source),
+            ("Bytecode", self.bytecode),
+            ("Plugin and imports", self.plugin()),
+        ];
+        writeln!(f, "Total size:          {:>width$} bytes", self.total)?;
+        for (name, size) in rows {
+            writeln!(f, "  {:<18} {:>width$} bytes", format!("{name}:"), size)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,82 @@
+//! Rebuilding on changes to the files a build reads, for `javy build --watch`.
+
+use anyhow::{Context, Result};
+use notify_debouncer_mini::{new_debouncer, notify::RecursiveMode, DebounceEventResult};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// How long to wait for changes to settle before rebuilding, so saving
+/// several files or an editor writing a file in steps triggers one rebuild.
+const DEBOUNCE_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// What woke the watch loop up.
+enum Event {
+    /// One of the watched files changed.
+    Changed,
+    /// The process received Ctrl-C.
+    Interrupted,
+}
+
+/// Calls `rebuild` once, and again each time one of `paths` changes, until
+/// Ctrl-C is pressed.
+///
+/// The directories of the files are watched rather than the files
+/// themselves, since editors often replace a file when saving it.
+pub(crate) fn watch(paths: &[PathBuf], mut rebuild: impl FnMut()) -> Result<()> {
+    let files = paths
+        .iter()
+        .map(|path| canonical_file(path))
+        .collect::<Result<BTreeSet<_>>>()?;
+    let (tx, rx) = mpsc::channel();
+
+    let interrupt_tx = tx.clone();
+    ctrlc::set_handler(move || {
+        let _ = interrupt_tx.send(Event::Interrupted);
+    })
+    .context("Failed to set the Ctrl-C handler")?;
+
+    let watched = files.clone();
+    let mut debouncer = new_debouncer(DEBOUNCE_TIMEOUT, move |res: DebounceEventResult| {
+        let Ok(events) = res else {
+            return;
+        };
+        if events.iter().any(|event| watched.contains(&event.path)) {
+            let _ = tx.send(Event::Changed);
+        }
+    })?;
+    let dirs: BTreeSet<_> = files.iter().filter_map(|file| file.parent()).collect();
+    for dir in dirs {
+        debouncer
+            .watcher()
+            .watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+    }
+
+    rebuild();
+    eprintln!("Watching for changes, press Ctrl-C to stop");
+    while let Ok(event) = rx.recv() {
+        match event {
+            Event::Changed => rebuild(),
+            Event::Interrupted => break,
+        }
+    }
+    Ok(())
+}
+
+/// The absolute path of a file with symbolic links of its directory
+/// resolved, which is how the watcher reports the paths of events.
+fn canonical_file(path: &Path) -> Result<PathBuf> {
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{} is not a file", path.display()))?;
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let dir = dir
+        .canonicalize()
+        .with_context(|| format!("Failed to watch {}", path.display()))?;
+    Ok(dir.join(file_name))
+}
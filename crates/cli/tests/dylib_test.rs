@@ -56,6 +56,53 @@ fn test_dylib_with_exported_func() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_dylib_with_missing_exported_func() -> Result<()> {
+    let js_src = "export function foo() {}; export default () => {};";
+
+    let mut runner = Runner::with_dylib(plugin_module()?)?;
+
+    let res = runner.exec_through_dylib(js_src, UseExportedFn::Invoke(Some("fooBar")));
+
+    let e = res.err().unwrap();
+    let stderr = &e.downcast_ref::<RunnerError>().unwrap().stderr;
+    assert!(
+        stderr.contains("function 'fooBar' not found; module exports: default, foo"),
+        "{stderr}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_dylib_with_eval_src() -> Result<()> {
+    let js_src = "export function foo() { console.log('In foo'); }; console.log('Toplevel');";
+    let mut runner = Runner::with_dylib(plugin_module()?)?;
+
+    let (output, _, _) = runner.exec_through_dylib(js_src, UseExportedFn::EvalSrc(None))?;
+    assert_eq!("Toplevel\n", str::from_utf8(&output)?);
+
+    let (output, _, _) = runner.exec_through_dylib(js_src, UseExportedFn::EvalSrc(Some("foo")))?;
+    assert_eq!("Toplevel\nIn foo\n", str::from_utf8(&output)?);
+
+    Ok(())
+}
+
+#[test]
+fn test_dylib_with_eval_src_error() -> Result<()> {
+    let js_src = "function ( {";
+    let mut runner = Runner::with_dylib(plugin_module()?)?;
+
+    let res = runner.exec_through_dylib(js_src, UseExportedFn::EvalSrc(None));
+
+    let e = res.err().unwrap();
+    let e = e.downcast_ref::<RunnerError>().unwrap();
+    assert_eq!("eval_src failed", e.err.to_string());
+    assert!(e.stderr.contains("function.mjs"), "{}", e.stderr);
+
+    Ok(())
+}
+
 fn plugin_module() -> Result<Vec<u8>> {
     std::fs::read(Plugin::Default.path()).map_err(Into::into)
 }
@@ -125,3 +125,38 @@ fn test_using_plugin_with_dynamic_works(builder: &mut Builder) -> Result<()> {
 
     Ok(())
 }
+
+#[javy_cli_test(dyn = true, commands(not(Compile)))]
+fn test_dynamic_module_records_linked_plugin(builder: &mut Builder) -> Result<()> {
+    let plugin = Plugin::User;
+    let mut runner = builder
+        .plugin(Plugin::User)
+        .preload(plugin.namespace().into(), plugin.path())
+        .input("plugin.js")
+        .build()?;
+
+    assert_eq!(
+        Some(("test_plugin".to_string(), 1)),
+        runner.linked_plugin()?
+    );
+    assert!(runner.exec(vec![]).is_ok());
+
+    Ok(())
+}
+
+#[javy_cli_test(dyn = true, commands(not(Compile)))]
+fn test_using_mismatching_plugin_with_dynamic_reports_error(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .plugin(Plugin::User)
+        .preload(Plugin::User.namespace().into(), Plugin::Default.path())
+        .input("plugin.js")
+        .build()?;
+
+    let err = runner.exec(vec![]).unwrap_err();
+    assert_eq!(
+        "module was linked against plugin namespace test_plugin version 1, provided plugin exposes javy_quickjs_provider_v3 version 0",
+        err.to_string()
+    );
+
+    Ok(())
+}
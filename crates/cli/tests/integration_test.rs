@@ -1,8 +1,16 @@
-use anyhow::{bail, Result};
+use anyhow::{anyhow, bail, Result};
+use javy_codegen::COMPILE_ERROR_FLAG;
 use javy_runner::{Builder, Plugin, Runner, RunnerError};
-use std::{path::PathBuf, process::Command, str};
-use wasmtime::{AsContextMut, Engine, Linker, Module, Store};
-use wasmtime_wasi::WasiCtxBuilder;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    str,
+    time::{Duration, Instant},
+};
+use wasmtime::{AsContextMut, Engine, Instance, Linker, Memory, Module, Store, Trap};
+use wasmtime_wasi::{pipe::MemoryOutputPipe, I32Exit, WasiCtxBuilder};
 
 use javy_test_macros::javy_cli_test;
 
@@ -111,6 +119,20 @@ fn test_using_plugin_with_static_build_fails_with_runtime_config(
     Ok(())
 }
 
+#[javy_cli_test(commands(not(Compile)))]
+fn test_using_plugin_with_static_build_and_plugin_config(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .plugin(Plugin::User)
+        .input("plugin-config.js")
+        .js_option("my-opt", "hello")
+        .build()?;
+
+    let (output, _, _) = run(&mut runner, vec![]);
+    assert_eq!(b"hello\n".to_vec(), output);
+
+    Ok(())
+}
+
 #[javy_cli_test]
 fn test_readme_script(builder: &mut Builder) -> Result<()> {
     let mut runner = builder.input("readme.js").build()?;
@@ -142,6 +164,30 @@ fn test_promises_without_event_loop(builder: &mut Builder) -> Result<()> {
     Ok(())
 }
 
+#[javy_cli_test(commands(not(Compile)))]
+fn test_modules(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("modules.js")
+        .module("lib/math.js", "modules-math.js")
+        .event_loop(true)
+        .build()?;
+
+    let (output, _, _) = run(&mut runner, vec![]);
+    assert_eq!("[4,6]".as_bytes(), output);
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_minify(builder: &mut Builder) -> Result<()> {
+    let unminified = builder.clone().input("minify.js").build()?;
+    let mut runner = builder.input("minify.js").minify(true).build()?;
+
+    let (output, _, _) = run(&mut runner, vec![]);
+    assert_eq!("Hello, Ada!\nHello, Grace!", String::from_utf8(output)?);
+    assert!(runner.wasm.len() < unminified.wasm.len());
+    Ok(())
+}
+
 #[javy_cli_test(commands(not(Compile)))]
 fn test_promise_top_level_await(builder: &mut Builder) -> Result<()> {
     let mut runner = builder
@@ -182,6 +228,50 @@ fn test_exported_promises(builder: &mut Builder) -> Result<()> {
     Ok(())
 }
 
+#[javy_cli_test(commands(not(Compile)))]
+fn test_exported_async_functions_settle(builder: &mut Builder) -> Result<()> {
+    assert_exported_async_functions_settle(builder, false)
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_exported_async_functions_settle_when_waiting_for_completion(
+    builder: &mut Builder,
+) -> Result<()> {
+    assert_exported_async_functions_settle(builder, true)
+}
+
+/// Checks an exported async function awaiting a timer completes, and one
+/// rejecting after a timer fails with the reason it was rejected with.
+fn assert_exported_async_functions_settle(
+    builder: &mut Builder,
+    wait_for_completion: bool,
+) -> Result<()> {
+    let mut runner = builder
+        .input("exported-async-fns.js")
+        .wit("exported-async-fns.wit")
+        .world("exported-async-fns")
+        .timers(true)
+        .event_loop(true)
+        .wait_for_completion(wait_for_completion)
+        .build()?;
+
+    let (_, logs, _) = run_fn(&mut runner, "sleeps", vec![]);
+    assert_eq!("slept\n", logs);
+
+    let err = runner
+        .exec_func("rejects", vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+    assert!(
+        err.stderr.contains("rejected after sleeping"),
+        "{}",
+        err.stderr
+    );
+    Ok(())
+}
+
 #[javy_cli_test]
 fn test_exported_functions_without_flag(builder: &mut Builder) -> Result<()> {
     let mut runner = builder.input("exported-fn.js").build()?;
@@ -204,6 +294,48 @@ fn test_exported_function_without_semicolons(builder: &mut Builder) -> Result<()
     Ok(())
 }
 
+#[javy_cli_test]
+fn test_exported_function_with_args(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("exported-fn-with-args.js")
+        .wit("exported-fn-with-args.wit")
+        .world("exported-fn-with-args")
+        .build()?;
+    let (result, logs, _) = runner.exec_string_func("greet", "Javy")?;
+    assert_eq!("Hello, Javy!", result);
+    assert_eq!("Greeting Javy\n", String::from_utf8(logs)?);
+    Ok(())
+}
+
+#[javy_cli_test]
+fn test_exported_function_with_too_many_args(builder: &mut Builder) -> Result<()> {
+    let err = builder
+        .input("exported-fn-with-too-many-args.js")
+        .wit("exported-fn-with-args.wit")
+        .world("exported-fn-with-args")
+        .build()
+        .err()
+        .unwrap();
+    let err = err.to_string();
+
+    assert!(
+        err.contains("JS function greet has 2 parameters but the WIT function greet has 1"),
+        "{err}"
+    );
+    Ok(())
+}
+
+#[javy_cli_test]
+fn test_compile_error(builder: &mut Builder) -> Result<()> {
+    let err = builder.input("syntax-error.js").build().err().unwrap();
+    let err = err.to_string();
+
+    assert!(err.contains("Failed to compile"), "{err}");
+    assert!(err.contains("syntax-error.js"), "{err}");
+    assert!(err.contains("function name expected (2:9)"), "{err}");
+    Ok(())
+}
+
 #[javy_cli_test]
 fn test_producers_section_present(builder: &mut Builder) -> Result<()> {
     let runner = builder.input("readme.js").build()?;
@@ -211,18 +343,731 @@ fn test_producers_section_present(builder: &mut Builder) -> Result<()> {
     runner.assert_producers()
 }
 
-#[javy_cli_test]
+#[javy_cli_test(commands(not(Compile)))]
+fn test_deterministic_builds(builder: &mut Builder) -> Result<()> {
+    let hash = |wasm: &[u8]| {
+        let mut hasher = DefaultHasher::new();
+        wasm.hash(&mut hasher);
+        hasher.finish()
+    };
+
+    builder.input("readme.js").deterministic(true);
+    let first = builder.clone().build()?;
+    let second = builder.build()?;
+
+    assert_eq!(hash(&first.wasm), hash(&second.wasm));
+    first.assert_producers()
+}
+
+#[javy_cli_test(commands(not(Compile)))]
 fn test_error_handling(builder: &mut Builder) -> Result<()> {
     let mut runner = builder.input("error.js").build()?;
     let result = runner.exec(vec![]);
     let err = result.err().unwrap().downcast::<RunnerError>().unwrap();
 
+    let expected_log_output =
+        "Error:2:9 error\n    at error (error.js:2:9)\n    at <anonymous> (error.js:5:1)\n\n";
+
+    assert_eq!(expected_log_output, err.stderr);
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Build)))]
+fn test_error_handling_with_compile_command(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("error.js").build()?;
+    let result = runner.exec(vec![]);
+    let err = result.err().unwrap().downcast::<RunnerError>().unwrap();
+
+    // The deprecated `compile` command doesn't name the module after the
+    // input.
     let expected_log_output = "Error:2:9 error\n    at error (function.mjs:2:9)\n    at <anonymous> (function.mjs:5:1)\n\n";
 
     assert_eq!(expected_log_output, err.stderr);
     Ok(())
 }
 
+#[javy_cli_test(commands(not(Compile)))]
+fn test_error_handling_without_abort(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("error.js").abort_on_error(false).build()?;
+    // The error is reported on stderr, but the module returns normally
+    // instead of trapping.
+    let (_, logs, _) = runner.exec(vec![])?;
+
+    let expected_log_output =
+        "Error:2:9 error\n    at error (error.js:2:9)\n    at <anonymous> (error.js:5:1)\n\n";
+
+    assert_eq!(expected_log_output, String::from_utf8(logs)?);
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_errors_trap_by_default(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("error.js").build()?;
+    let err = runner
+        .exec(vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+
+    assert_eq!(
+        Some(&Trap::UnreachableCodeReached),
+        err.err.downcast_ref::<Trap>()
+    );
+    assert!(err.err.downcast_ref::<I32Exit>().is_none());
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_errors_exit_with_error_exit_code(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("error.js")
+        .js_option("error-exit-code", "3")
+        .build()?;
+    let err = runner
+        .exec(vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+
+    assert_eq!(3, err.err.downcast_ref::<I32Exit>().unwrap().0);
+    assert!(err.err.downcast_ref::<Trap>().is_none());
+    assert!(
+        err.stderr.starts_with("Error:2:9 error\n"),
+        "{}",
+        err.stderr
+    );
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_pending_jobs_exit_with_error_exit_code(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("promise.js")
+        .js_option("error-exit-code", "3")
+        .build()?;
+    let err = runner
+        .exec(vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+
+    assert_eq!(3, err.err.downcast_ref::<I32Exit>().unwrap().0);
+    assert!(err.stderr.contains("Pending jobs in the event queue."));
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_structured_errors(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("error.js")
+        .js_option("structured-errors", "y")
+        .build()?;
+    let err = runner
+        .exec(vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+
+    assert_eq!(1, err.stderr.lines().count(), "{}", err.stderr);
+    let error: serde_json::Value = serde_json::from_str(&err.stderr)?;
+    assert_eq!("js-error", error["type"]);
+    assert_eq!("error", error["message"]);
+    assert!(
+        error["stack"]
+            .as_str()
+            .unwrap()
+            .contains("at error (error.js:2:9)"),
+        "{error}"
+    );
+    assert!(error["exitCode"].is_null());
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_structured_errors_for_pending_jobs(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("promise.js")
+        .js_option("structured-errors", "y")
+        .build()?;
+    let err = runner
+        .exec(vec![])
+        .err()
+        .unwrap()
+        .downcast::<RunnerError>()
+        .unwrap();
+
+    let error: serde_json::Value = serde_json::from_str(&err.stderr)?;
+    assert_eq!("pending-jobs", error["type"]);
+    assert!(
+        error["message"]
+            .as_str()
+            .unwrap()
+            .starts_with("Pending jobs in the event queue."),
+        "{error}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_source_map() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("error.js");
+    let source_map = dir.path().join("index.wasm.map");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(dir.path().join("index.wasm"))
+        .arg("-C")
+        .arg(format!("source-map={}", source_map.display()))
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+
+    let source_map: serde_json::Value = serde_json::from_slice(&std::fs::read(source_map)?)?;
+    assert_eq!(3, source_map["version"]);
+    assert_eq!("error.js", source_map["file"]);
+    assert_eq!(serde_json::json!(["error.js"]), source_map["sources"]);
+    assert!(!source_map["mappings"].as_str().unwrap().is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_build_stats() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+    let wasm = dir.path().join("readme.wasm");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(&wasm)
+        .arg("--stats")
+        .output()?;
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(output.status.success(), "{stderr}");
+
+    let size = std::fs::metadata(&wasm)?.len();
+    let total = stderr
+        .lines()
+        .find(|line| line.starts_with("Total size:"))
+        .expect("stats should include a total size line");
+    assert!(total.ends_with(&format!(" {size} bytes")), "{total}");
+    assert!(stderr.contains("Bytecode:"));
+    assert!(stderr.contains("Embedded source:"));
+    assert!(stderr.contains("Plugin and imports:"));
+    Ok(())
+}
+
+#[test]
+fn test_build_watch_rebuilds_on_change() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("input.js");
+    let wasm = dir.path().join("output.wasm");
+    std::fs::write(&input, "console.log(1);")?;
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(&wasm)
+        .arg("--watch")
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // Waits for the output to hold a build different from `previous`.
+    let wait_for_build = |previous: Option<Vec<u8>>| -> Option<Vec<u8>> {
+        let deadline = Instant::now() + Duration::from_secs(60);
+        while Instant::now() < deadline {
+            if let Ok(output) = std::fs::read(&wasm) {
+                if Some(&output) != previous.as_ref() && wasmparser::validate(&output).is_ok() {
+                    return Some(output);
+                }
+            }
+            std::thread::sleep(Duration::from_millis(100));
+        }
+        None
+    };
+
+    let result = (|| -> Result<()> {
+        let first = wait_for_build(None).expect("the input should be built");
+        std::fs::write(&input, "console.log(2);")?;
+        let second = wait_for_build(Some(first));
+        assert!(
+            second.is_some(),
+            "the input should be rebuilt after a change"
+        );
+        Ok(())
+    })();
+    child.kill()?;
+    child.wait()?;
+    result
+}
+
+#[test]
+fn test_build_from_stdin() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+    let wasm = dir.path().join("readme.wasm");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg("-")
+        .arg("-o")
+        .arg(&wasm)
+        .stdin(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&std::fs::read(&input)?)?;
+    let output = child.wait_with_output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+
+    let stdin = dir.path().join("input.json");
+    std::fs::write(&stdin, r#"{ "n": 2, "bar": "baz" }"#)?;
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&wasm)
+        .arg("--stdin")
+        .arg(&stdin)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(r#"{"foo":3,"newBar":"baz!"}"#.as_bytes(), output.stdout);
+    Ok(())
+}
+
+#[test]
+fn test_build_from_stdin_to_stdout() -> Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+
+    let mut child = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg("-")
+        .arg("-o")
+        .arg("-")
+        .arg("--stats")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(&std::fs::read(&input)?)?;
+    let output = child.wait_with_output()?;
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(output.status.success(), "{stderr}");
+    // The stats are written to stderr, leaving only the module on stdout.
+    assert!(!stderr.is_empty());
+    assert!(output.stdout.starts_with(b"\0asm"));
+
+    let mut runner = Runner::with_dylib(output.stdout)?;
+    let (output, _, _) = run(&mut runner, r#"{ "n": 2, "bar": "baz" }"#.into());
+    assert_eq!(r#"{"foo":3,"newBar":"baz!"}"#.as_bytes(), output);
+    Ok(())
+}
+
+#[test]
+fn test_build_watch_requires_an_output_file() -> Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg("-")
+        .arg("--watch")
+        .output()?;
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(
+        stderr.contains("--watch requires an output file"),
+        "{stderr}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_component_target() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let scripts = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts");
+    let wasm = dir.path().join("exported-fn.wasm");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(scripts.join("exported-fn.js"))
+        .arg("-o")
+        .arg(&wasm)
+        .arg("-C")
+        .arg("target=component")
+        .arg("-C")
+        .arg(format!("wit={}", scripts.join("exported-fn.wit").display()))
+        .arg("-C")
+        .arg("wit-world=exported-fn")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+
+    let wasm = std::fs::read(&wasm)?;
+    assert!(wasmparser::Parser::is_component(&wasm));
+    wasmparser::Validator::new_with_features(wasmparser::WasmFeatures::all())
+        .validate_all(&wasm)?;
+
+    // The component exports the functions of the world, and the `run`
+    // function of `wasi:cli` calling `_start`.
+    let mut exports = vec![];
+    for payload in wasmparser::Parser::new(0).parse_all(&wasm) {
+        if let wasmparser::Payload::ComponentExportSection(section) = payload? {
+            for export in section {
+                exports.push(export?.name.0.to_string());
+            }
+        }
+    }
+    for name in ["foo", "bar", "foo-bar"] {
+        assert!(exports.iter().any(|e| e == name), "{exports:?}");
+    }
+    assert!(
+        exports.iter().any(|e| e.starts_with("wasi:cli/run@")),
+        "{exports:?}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_plugin_info_command() -> Result<()> {
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("plugin-info")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    let properties = schema["supportedProperties"].as_array().unwrap();
+    let text_encoding = properties
+        .iter()
+        .find(|property| property["name"] == "text-encoding")
+        .unwrap();
+    assert_eq!("bool", text_encoding["type"]);
+    assert!(text_encoding["doc"]
+        .as_str()
+        .unwrap()
+        .contains("`TextEncoder` and `TextDecoder`"));
+    let wait_timeout_ms = properties
+        .iter()
+        .find(|property| property["name"] == "wait-timeout-ms")
+        .unwrap();
+    assert_eq!("number", wait_timeout_ms["type"]);
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("plugin-info")
+        .arg(Plugin::User.path())
+        .output()?;
+    assert!(output.status.success());
+    let schema: serde_json::Value = serde_json::from_slice(&output.stdout)?;
+    assert_eq!(
+        serde_json::json!([{
+            "name": "my-opt",
+            "doc": "An option passed through to the plugin.",
+            "type": "string",
+        }]),
+        schema["supportedProperties"]
+    );
+    Ok(())
+}
+
+#[test]
+fn test_run_command() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+    let stdin = dir.path().join("input.json");
+    std::fs::write(&stdin, r#"{ "n": 2, "bar": "baz" }"#)?;
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&input)
+        .arg("--stdin")
+        .arg(&stdin)
+        .arg("--fuel")
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+    assert_eq!(r#"{"foo":3,"newBar":"baz!"}"#.as_bytes(), output.stdout);
+    assert!(str::from_utf8(&output.stderr)?.starts_with("Fuel consumed: "));
+
+    // Running the built module gives the same output.
+    let wasm = dir.path().join("readme.wasm");
+    let status = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(&wasm)
+        .status()?;
+    assert!(status.success());
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&wasm)
+        .arg("--stdin")
+        .arg(&stdin)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(r#"{"foo":3,"newBar":"baz!"}"#.as_bytes(), output.stdout);
+    // The fuel consumed is only printed with `--fuel`.
+    assert!(output.stderr.is_empty());
+    Ok(())
+}
+
+#[test]
+fn test_run_command_matches_runner() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("readme.js");
+    let stdin = dir.path().join("input.json");
+    std::fs::write(&stdin, r#"{ "n": 2, "bar": "baz" }"#)?;
+    let wasm = dir.path().join("readme.wasm");
+    let status = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(&wasm)
+        .status()?;
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&wasm)
+        .arg("--stdin")
+        .arg(&stdin)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+
+    // The same module run by the test runner writes the same output.
+    let mut runner = Runner::with_dylib(std::fs::read(&wasm)?)?;
+    let (runner_stdout, runner_stderr, _) = runner.exec(std::fs::read(&stdin)?)?;
+    assert_eq!(runner_stdout, output.stdout);
+    assert_eq!(runner_stderr, output.stderr);
+    Ok(())
+}
+
+#[test]
+fn test_run_command_reports_errors() -> Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("error.js");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&input)
+        .arg("--fuel")
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(stderr.contains("at error (error.js:2:9)"), "{stderr}");
+    assert!(stderr.contains("Fuel consumed: "), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_run_command_with_dynamically_linked_plugin() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("plugin.js");
+    let plugin = format!("plugin={}", Plugin::User.path().display());
+    let wasm = dir.path().join("plugin.wasm");
+    let status = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-C")
+        .arg("dynamic")
+        .arg("-C")
+        .arg(&plugin)
+        .arg("-o")
+        .arg(&wasm)
+        .status()?;
+    assert!(status.success());
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&wasm)
+        .arg("-C")
+        .arg(&plugin)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+
+    // The module can't run with the default plugin.
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&wasm)
+        .output()?;
+    assert!(!output.status.success());
+    Ok(())
+}
+
+#[test]
+fn test_module_name() -> Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("error.js");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&input)
+        .arg("-C")
+        .arg("module-name=handler.js")
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(stderr.contains("at error (handler.js:2:9)"), "{stderr}");
+    assert!(!stderr.contains("error.js"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_source_map_remaps_stack_traces() -> Result<()> {
+    let sample_scripts = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(sample_scripts.join("source-map.js"))
+        .arg("-C")
+        .arg(format!(
+            "input-source-map={}",
+            sample_scripts.join("source-map.js.map").display()
+        ))
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(stderr.contains("at fail (src/index.ts:10:5)"), "{stderr}");
+    assert!(
+        stderr.contains("at <anonymous> (src/index.ts:13:1)"),
+        "{stderr}"
+    );
+    assert!(!stderr.contains("function.mjs"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_inline_source_map_remaps_stack_traces() -> Result<()> {
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("source-map-inline.js");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("run")
+        .arg(&input)
+        .output()?;
+    assert!(!output.status.success());
+    let stderr = str::from_utf8(&output.stderr)?;
+    assert!(stderr.contains("at fail (src/index.ts:10:5)"), "{stderr}");
+    Ok(())
+}
+
+#[test]
+fn test_disassemble_command() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let input = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("sample-scripts")
+        .join("error.js");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("disassemble")
+        .arg(&input)
+        .output()?;
+    assert!(
+        output.status.success(),
+        "{}",
+        str::from_utf8(&output.stderr)?
+    );
+    let listing = str::from_utf8(&output.stdout)?;
+    assert!(listing.starts_with("module function.mjs\n"), "{listing}");
+    assert!(
+        listing.contains("function error (function.mjs:1:1)"),
+        "{listing}"
+    );
+    assert!(listing.contains("get_var Error"), "{listing}");
+    assert!(listing.contains("throw"), "{listing}");
+
+    // The bytecode of a built module is the same.
+    let wasm = dir.path().join("error.wasm");
+    let status = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("build")
+        .arg(&input)
+        .arg("-o")
+        .arg(&wasm)
+        .status()?;
+    assert!(status.success());
+    let output = Command::new(env!("CARGO_BIN_EXE_javy"))
+        .arg("disassemble")
+        .arg(&wasm)
+        .output()?;
+    assert!(output.status.success());
+    assert_eq!(listing.as_bytes(), output.stdout);
+    Ok(())
+}
+
 #[javy_cli_test]
 fn test_same_module_outputs_different_random_result(builder: &mut Builder) -> Result<()> {
     let mut runner = builder.input("random.js").build()?;
@@ -235,6 +1080,18 @@ fn test_same_module_outputs_different_random_result(builder: &mut Builder) -> Re
     Ok(())
 }
 
+#[javy_cli_test(commands(not(Compile)))]
+fn test_random_seed_outputs_same_random_result(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("random.js")
+        .js_option("random-seed", "42")
+        .build()?;
+    let (output, _, _) = runner.exec(vec![]).unwrap();
+    let (output2, _, _) = runner.exec(vec![]).unwrap();
+    assert_eq!(output, output2);
+    Ok(())
+}
+
 #[javy_cli_test]
 fn test_exported_default_arrow_fn(builder: &mut Builder) -> Result<()> {
     let mut runner = builder
@@ -575,20 +1432,331 @@ fn test_blob_functionality(builder: &mut Builder) -> Result<()> {
     Ok(())
 }
 
+#[javy_cli_test]
+fn test_blob_write(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("blob-write.js").build()?;
+
+    let expected: Vec<u8> = (0..4096)
+        .flat_map(|_| (0..1024).map(|i| (i % 251) as u8))
+        .collect();
+
+    let (blob_output, _, blob_fuel) = run(&mut runner, b"b".to_vec());
+    assert_eq!(expected, blob_output);
+
+    let (file_output, _, file_fuel) = run(&mut runner, b"f".to_vec());
+    assert_eq!(expected, file_output);
+
+    let (array_output, _, array_fuel) = run(&mut runner, b"u".to_vec());
+    assert_eq!(expected, array_output);
+
+    // Writing a Blob or a File directly avoids copying its bytes into an
+    // ArrayBuffer.
+    assert!(blob_fuel < array_fuel, "{blob_fuel} >= {array_fuel}");
+    assert!(file_fuel < array_fuel, "{file_fuel} >= {array_fuel}");
+    Ok(())
+}
+
 #[test]
 fn test_init_plugin() -> Result<()> {
     // This test works by trying to call the `compile_src` function on the
-    // default plugin. The unwizened version should fail because the
-    // underlying Javy runtime has not been initialized yet. Using `init-plugin` on
-    // the unwizened plugin should initialize the runtime so calling
-    // `compile-src` on this module should succeed.
+    // default plugin. The unwizened version should report an error because
+    // the underlying Javy runtime has not been initialized yet. Using
+    // `init-plugin` on the unwizened plugin should initialize the runtime so
+    // calling `compile-src` on this module should succeed.
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+    let wasi = WasiCtxBuilder::new().build_p1();
+    let mut store = Store::new(&engine, wasi);
+
+    let uninitialized_plugin = uninitialized_plugin_path();
+
+    // Check that plugin is in fact uninitialized at this point.
+    let module = Module::from_file(&engine, &uninitialized_plugin)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+    // This should fail because the runtime is uninitialized.
+    let error = compile_src(store.as_context_mut(), &instance, b"")
+        .unwrap_err()
+        .to_string();
+    assert!(
+        error.contains("The Javy runtime has not been initialized"),
+        "{error}"
+    );
+
+    // Initialize the plugin.
+    let initialized_plugin = init_plugin(&uninitialized_plugin)?;
+
+    // Check the plugin is initialized and runs.
+    let module = Module::new(&engine, &initialized_plugin)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+    // This should succeed because the runtime is initialized.
+    compile_src(store.as_context_mut(), &instance, b"")?;
+    Ok(())
+}
+
+#[test]
+fn test_invoke_with_result() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+    let wasi = WasiCtxBuilder::new().build_p1();
+    let mut store = Store::new(&engine, wasi);
+
+    let plugin = init_plugin(&uninitialized_plugin_path())?;
+    let module = Module::new(&engine, &plugin)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+
+    let js_src = br#"export function foo() { return { bar: [1, "two", true], baz: null }; }"#;
+    let (bytecode_ptr, bytecode_len) = compile_src(&mut store, &instance, js_src)?;
+
+    let fn_name = b"foo";
+    let fn_name_ptr = write_bytes(&mut store, &instance, fn_name)?;
+    let fn_name_len = fn_name.len() as u32;
+    let invoke_with_result = instance.get_typed_func::<(u32, u32, u32, u32), u32>(
+        store.as_context_mut(),
+        "invoke_with_result",
+    )?;
+    let ret_ptr = invoke_with_result.call(
+        store.as_context_mut(),
+        (bytecode_ptr, bytecode_len, fn_name_ptr, fn_name_len),
+    )?;
+    let (result_ptr, result_len) = read_ret_area(&mut store, &instance, ret_ptr)?;
+
+    let mut result = vec![0; result_len as usize];
+    memory(&mut store, &instance)?.read(&store, result_ptr as usize, &mut result)?;
+    let result: serde_json::Value = serde_json::from_slice(&result)?;
+    assert_eq!(
+        serde_json::json!({ "bar": [1, "two", true], "baz": null }),
+        result
+    );
+
+    instance
+        .get_typed_func::<(u32, u32), ()>(store.as_context_mut(), "free_result")?
+        .call(store.as_context_mut(), (result_ptr, result_len))?;
+    Ok(())
+}
+
+#[test]
+fn test_runtime_stats() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+    let wasi = WasiCtxBuilder::new().build_p1();
+    let mut store = Store::new(&engine, wasi);
+
+    let plugin = init_plugin(&uninitialized_plugin_path())?;
+    let module = Module::new(&engine, &plugin)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+
+    let ret_ptr = instance
+        .get_typed_func::<(), u32>(store.as_context_mut(), "runtime_stats")?
+        .call(store.as_context_mut(), ())?;
+    let (stats_ptr, stats_len) = read_ret_area(&mut store, &instance, ret_ptr)?;
+
+    let mut stats = vec![0; stats_len as usize];
+    memory(&mut store, &instance)?.read(&store, stats_ptr as usize, &mut stats)?;
+    let stats: serde_json::Value = serde_json::from_slice(&stats)?;
+    assert!(stats["memoryUsedSize"].as_u64().unwrap() > 0);
+    assert!(stats["objectCount"].as_u64().unwrap() > 0);
+    assert_eq!(0, stats["blobStorageBytes"]);
+    assert_eq!(0, stats["pendingTimers"]);
+    assert_eq!(false, stats["hasPendingJobs"]);
+
+    instance
+        .get_typed_func::<(u32, u32), ()>(store.as_context_mut(), "free_result")?
+        .call(store.as_context_mut(), (stats_ptr, stats_len))?;
+    Ok(())
+}
+
+#[test]
+fn test_finalize_runtime() -> Result<()> {
+    let engine = Engine::default();
+    let mut linker = Linker::new(&engine);
+    wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+    let stdout = MemoryOutputPipe::new(usize::MAX);
+    let stderr = MemoryOutputPipe::new(usize::MAX);
+    let wasi = WasiCtxBuilder::new()
+        .stdout(stdout.clone())
+        .stderr(stderr.clone())
+        .build_p1();
+    let mut store = Store::new(&engine, wasi);
+
+    let plugin = init_plugin(&uninitialized_plugin_path())?;
+    let module = Module::new(&engine, &plugin)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+
+    let js_src = br#"Javy.onShutdown(() => console.log("shutting down"));"#;
+    let (bytecode_ptr, bytecode_len) = compile_src(&mut store, &instance, js_src)?;
+    let invoke =
+        instance.get_typed_func::<(u32, u32, u32, u32), ()>(store.as_context_mut(), "invoke")?;
+    invoke.call(&mut store, (bytecode_ptr, bytecode_len, 0, 0))?;
+    assert!(stdout.contents().is_empty());
+
+    instance
+        .get_typed_func::<(), ()>(store.as_context_mut(), "finalize_runtime")?
+        .call(&mut store, ())?;
+    assert_eq!(b"shutting down\n", &stdout.contents()[..]);
+
+    // Invoking after finalizing traps with the reason written to stderr.
+    assert!(invoke
+        .call(&mut store, (bytecode_ptr, bytecode_len, 0, 0))
+        .is_err());
+    assert_eq!(b"shutting down\n", &stdout.contents()[..]);
+    let message = String::from_utf8(stderr.contents().to_vec())?;
+    assert!(
+        message.contains("The Javy runtime has been finalized"),
+        "{message}"
+    );
+    Ok(())
+}
+
+#[test]
+fn test_compile_error_flag_matches_codegen() {
+    // The runner decodes `compile_src` results on its own, so its flag has
+    // to stay in sync with the one the code generator uses.
+    assert_eq!(COMPILE_ERROR_FLAG, javy_runner::COMPILE_ERROR_FLAG);
+}
+
+#[test]
+fn test_free_bytecode() -> Result<()> {
     let engine = Engine::default();
     let mut linker = Linker::new(&engine);
     wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
     let wasi = WasiCtxBuilder::new().build_p1();
     let mut store = Store::new(&engine, wasi);
 
-    let uninitialized_plugin = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+    // The test plugin exports the number of outstanding bytecode buffers.
+    let module = Module::from_file(&engine, Plugin::User.path())?;
+    linker.define_unknown_imports_as_traps(&module)?;
+    let instance = linker.instantiate(store.as_context_mut(), &module)?;
+    let outstanding_bytecode_buffers = instance
+        .get_typed_func::<(), u32>(store.as_context_mut(), "outstanding_bytecode_buffers")?;
+    let free_bytecode =
+        instance.get_typed_func::<(u32, u32), ()>(store.as_context_mut(), "free_bytecode")?;
+
+    for i in 0..5 {
+        let js_src = format!("export function foo() {{ return {i}; }}");
+        let (bytecode_ptr, bytecode_len) = compile_src(&mut store, &instance, js_src.as_bytes())?;
+        assert_eq!(1, outstanding_bytecode_buffers.call(&mut store, ())?);
+
+        free_bytecode.call(&mut store, (bytecode_ptr, bytecode_len))?;
+        assert_eq!(0, outstanding_bytecode_buffers.call(&mut store, ())?);
+
+        // Releasing the buffer again is ignored.
+        free_bytecode.call(&mut store, (bytecode_ptr, bytecode_len))?;
+        assert_eq!(0, outstanding_bytecode_buffers.call(&mut store, ())?);
+    }
+
+    // Compiling without releasing the buffers releases the previous one.
+    for i in 0..100 {
+        let js_src = format!("export function foo() {{ return {i}; }}");
+        let (bytecode_ptr, bytecode_len) = compile_src(&mut store, &instance, js_src.as_bytes())?;
+        assert_eq!(1, outstanding_bytecode_buffers.call(&mut store, ())?);
+        let mut bytecode = vec![0; bytecode_len as usize];
+        memory(&mut store, &instance)?.read(&store, bytecode_ptr as usize, &mut bytecode)?;
+        assert!(!bytecode.is_empty());
+    }
+    Ok(())
+}
+
+#[test]
+fn test_plugin_failures_are_reported_on_stderr() -> Result<()> {
+    let engine = Engine::default();
+    let module = Module::from_file(&engine, Plugin::User.path())?;
+    let instantiate = || -> Result<_> {
+        let mut linker = Linker::new(&engine);
+        wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
+        linker.define_unknown_imports_as_traps(&module)?;
+        let stderr = MemoryOutputPipe::new(usize::MAX);
+        let wasi = WasiCtxBuilder::new().stderr(stderr.clone()).build_p1();
+        let mut store = Store::new(&engine, wasi);
+        let instance = linker.instantiate(store.as_context_mut(), &module)?;
+        Ok((store, instance, stderr))
+    };
+
+    // Invalid bytecode traps with the error written to stderr.
+    let (mut store, instance, stderr) = instantiate()?;
+    let bytecode = [0xff; 16];
+    let bytecode_ptr = write_bytes(&mut store, &instance, &bytecode)?;
+    let invoke =
+        instance.get_typed_func::<(u32, u32, u32, u32), ()>(store.as_context_mut(), "invoke")?;
+    assert!(invoke
+        .call(&mut store, (bytecode_ptr, bytecode.len() as u32, 0, 0))
+        .is_err());
+    drop(store);
+    let message = String::from_utf8(stderr.contents().to_vec())?;
+    assert!(!message.trim().is_empty());
+
+    // Panics trap with the message and location written to stderr.
+    let (mut store, instance, stderr) = instantiate()?;
+    let panic = instance.get_typed_func::<(), ()>(store.as_context_mut(), "panic")?;
+    assert!(panic.call(&mut store, ()).is_err());
+    drop(store);
+    let message = String::from_utf8(stderr.contents().to_vec())?;
+    assert!(message.starts_with("Javy plugin panicked at "), "{message}");
+    assert!(message.contains("src/lib.rs:"), "{message}");
+    assert!(
+        message.contains("Deliberate panic for testing"),
+        "{message}"
+    );
+    Ok(())
+}
+
+fn memory(mut store: impl AsContextMut, instance: &Instance) -> Result<Memory> {
+    instance
+        .get_memory(store.as_context_mut(), "memory")
+        .ok_or_else(|| anyhow!("Missing memory export"))
+}
+
+/// Copies the bytes into a buffer allocated in the instance's memory.
+fn write_bytes(mut store: impl AsContextMut, instance: &Instance, bytes: &[u8]) -> Result<u32> {
+    let realloc = instance.get_typed_func::<(u32, u32, u32, u32), u32>(
+        store.as_context_mut(),
+        "canonical_abi_realloc",
+    )?;
+    let ptr = realloc.call(store.as_context_mut(), (0, 0, 1, bytes.len() as u32))?;
+    memory(&mut store, instance)?.write(store.as_context_mut(), ptr as usize, bytes)?;
+    Ok(ptr)
+}
+
+/// Reads the pointer and the length held by the buffer returned by a plugin
+/// export.
+fn read_ret_area(
+    mut store: impl AsContextMut,
+    instance: &Instance,
+    ret_ptr: u32,
+) -> Result<(u32, u32)> {
+    let mut ret_area = [0; 8];
+    memory(&mut store, instance)?.read(store.as_context_mut(), ret_ptr as usize, &mut ret_area)?;
+    let (ptr, len) = ret_area.split_at(4);
+    Ok((
+        u32::from_le_bytes(ptr.try_into()?),
+        u32::from_le_bytes(len.try_into()?),
+    ))
+}
+
+/// Compiles the JS source with the plugin, returning the pointer to and the
+/// length of the bytecode.
+fn compile_src(
+    mut store: impl AsContextMut,
+    instance: &Instance,
+    js_src: &[u8],
+) -> Result<(u32, u32)> {
+    let js_src_ptr = write_bytes(&mut store, instance, js_src)?;
+    let ret_ptr = instance
+        .get_typed_func::<(u32, u32), u32>(store.as_context_mut(), "compile_src")?
+        .call(store.as_context_mut(), (js_src_ptr, js_src.len() as u32))?;
+    let (ptr, len) = read_ret_area(&mut store, instance, ret_ptr)?;
+    if len & COMPILE_ERROR_FLAG != 0 {
+        let mut message = vec![0; (len & !COMPILE_ERROR_FLAG) as usize];
+        memory(&mut store, instance)?.read(store.as_context_mut(), ptr as usize, &mut message)?;
+        bail!("Compiling failed: {}", String::from_utf8(message)?);
+    }
+    Ok((ptr, len))
+}
+
+fn uninitialized_plugin_path() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
         .join("..")
         .join("..")
         .join(
@@ -596,18 +1764,10 @@ fn test_init_plugin() -> Result<()> {
                 .join("wasm32-wasip1")
                 .join("release")
                 .join("plugin.wasm"),
-        );
-
-    // Check that plugin is in fact uninitialized at this point.
-    let module = Module::from_file(&engine, &uninitialized_plugin)?;
-    let instance = linker.instantiate(store.as_context_mut(), &module)?;
-    let result = instance
-        .get_typed_func::<(i32, i32), i32>(store.as_context_mut(), "compile_src")?
-        .call(store.as_context_mut(), (0, 0));
-    // This should fail because the runtime is uninitialized.
-    assert!(result.is_err());
+        )
+}
 
-    // Initialize the plugin.
+fn init_plugin(uninitialized_plugin: &Path) -> Result<Vec<u8>> {
     let output = Command::new(env!("CARGO_BIN_EXE_javy"))
         .arg("init-plugin")
         .arg(uninitialized_plugin.to_str().unwrap())
@@ -618,16 +1778,7 @@ fn test_init_plugin() -> Result<()> {
             str::from_utf8(&output.stderr)?,
         );
     }
-    let initialized_plugin = output.stdout;
-
-    // Check the plugin is initialized and runs.
-    let module = Module::new(&engine, &initialized_plugin)?;
-    let instance = linker.instantiate(store.as_context_mut(), &module)?;
-    // This should succeed because the runtime is initialized.
-    instance
-        .get_typed_func::<(i32, i32), i32>(store.as_context_mut(), "compile_src")?
-        .call(store.as_context_mut(), (0, 0))?;
-    Ok(())
+    Ok(output.stdout)
 }
 
 fn run_with_u8s(r: &mut Runner, stdin: u8) -> (u8, String, u64) {
@@ -804,3 +1955,129 @@ fn test_wait_for_completion_without_event_loop_fails(builder: &mut Builder) -> R
     
     Ok(())
 }
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_interrupt_after_stops_infinite_loop(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("infinite-loop.js")
+        .interrupt_after(100)
+        .build()?;
+
+    let result = runner.exec(vec![]);
+    let err = result.err().unwrap().downcast::<RunnerError>().unwrap();
+    assert!(err.stderr.contains("interrupted"));
+
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_memory_limit_bytes(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("out-of-memory.js")
+        .memory_limit_bytes(10_000_000)
+        .build()?;
+
+    let result = runner.exec(vec![]);
+    let err = result.err().unwrap().downcast::<RunnerError>().unwrap();
+    assert!(err.stderr.contains("out of memory"));
+
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_console_table(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("console-table.js")
+        .redirect_stdout_to_stderr(false)
+        .build()?;
+
+    let (output, _logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert!(output_str.contains("│ (index) │ name  │ age │ role  │"));
+    assert!(output_str.contains("│ 0       │ alice │ 30  │       │"));
+    assert!(output_str.contains("│ 1       │ bob   │     │ admin │"));
+    assert!(output_str.contains("│ (index) │ Values │"));
+    assert!(output_str.contains("│ 1       │ second │"));
+
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_console_table_redirect_mode(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("console-table.js")
+        .redirect_stdout_to_stderr(true)
+        .build()?;
+
+    let (output, logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert!(!output_str.contains("(index)"));
+    assert!(logs.contains("│ (index) │ name  │ age │ role  │"));
+
+    Ok(())
+}
+
+#[javy_cli_test]
+fn test_console_group(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("console-group.js").build()?;
+
+    let (output, _logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert_eq!(
+        "before\nlevel 1\n  inside level 1\n  level 2\n    inside level 2\n  back in level 1\nafter\n",
+        output_str
+    );
+
+    Ok(())
+}
+
+#[javy_cli_test]
+fn test_console_count(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder.input("console-count.js").build()?;
+
+    let (output, _logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert_eq!("x: 1\nx: 2\nx: 3\nx: 1\ndefault: 1\n", output_str);
+
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_file_reader(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("file-reader.js")
+        .event_loop(true)
+        .build()?;
+
+    let (output, _logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert!(output_str.contains("readAsText async: PASS"));
+    assert!(output_str.contains("readAsText: PASS"));
+    assert!(output_str.contains("readAsArrayBuffer: PASS"));
+    assert!(output_str.contains("readAsDataURL: PASS"));
+    assert!(!output_str.contains("FAIL"));
+    assert_eq!(8, output_str.matches("PASS").count());
+
+    Ok(())
+}
+
+#[javy_cli_test(commands(not(Compile)))]
+fn test_report_error(builder: &mut Builder) -> Result<()> {
+    let mut runner = builder
+        .input("report-error.js")
+        .report_error(true)
+        .build()?;
+
+    let (output, logs, _fuel_consumed) = run(&mut runner, vec![]);
+    let output_str = String::from_utf8(output)?;
+
+    assert!(logs.contains("Uncaught Error: something went wrong"));
+    assert_eq!("still running\n", output_str);
+
+    Ok(())
+}
@@ -0,0 +1,23 @@
+// Writes a 4 MiB payload to stdout. The first byte of stdin selects how it's
+// written: "b" as a Blob, "f" as a File and "u" through an intermediate
+// Uint8Array.
+const mode = new Uint8Array(1);
+Javy.IO.readSync(0, mode);
+
+const chunk = new Uint8Array(1024);
+for (let i = 0; i < chunk.length; i++) {
+    chunk[i] = i % 251;
+}
+const parts = new Array(4096).fill(chunk.buffer);
+
+switch (String.fromCharCode(mode[0])) {
+    case "b":
+        Javy.IO.writeSync(1, new Blob(parts));
+        break;
+    case "f":
+        Javy.IO.writeSync(1, new File(parts, "output.bin"));
+        break;
+    case "u":
+        Javy.IO.writeSync(1, new Uint8Array(new Blob(parts).arrayBuffer()));
+        break;
+}
@@ -0,0 +1,6 @@
+console.count("x");
+console.count("x");
+console.count("x");
+console.countReset("x");
+console.count("x");
+console.count();
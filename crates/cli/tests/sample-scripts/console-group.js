@@ -0,0 +1,9 @@
+console.log("before");
+console.group("level 1");
+console.log("inside level 1");
+console.group("level 2");
+console.log("inside level 2");
+console.groupEnd();
+console.log("back in level 1");
+console.groupEnd();
+console.log("after");
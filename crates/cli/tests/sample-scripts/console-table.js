@@ -0,0 +1,5 @@
+console.table([
+  { name: "alice", age: 30 },
+  { name: "bob", role: "admin" },
+]);
+console.table(["first", "second"]);
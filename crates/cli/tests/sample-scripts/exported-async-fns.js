@@ -0,0 +1,11 @@
+const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+
+export async function sleeps() {
+    await sleep(10);
+    console.error("slept");
+}
+
+export async function rejects() {
+    await sleep(10);
+    throw new Error("rejected after sleeping");
+}
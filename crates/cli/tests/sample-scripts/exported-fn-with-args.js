@@ -0,0 +1,4 @@
+export function greet(name) {
+    console.error(`Greeting ${name}`);
+    return `Hello, ${name}!`;
+}
@@ -0,0 +1,3 @@
+export function greet(name, greeting) {
+    return `${greeting}, ${name}!`;
+}
@@ -0,0 +1,19 @@
+// FileReader API functionality test
+const blob = new Blob(['Hello, World!'], { type: 'text/plain' });
+
+function read(method, source, check) {
+    const reader = new FileReader();
+    let sync = true;
+    reader.onload = () => {
+        console.log(`${method} async:`, sync ? "FAIL" : "PASS");
+        console.log(`${method}:`, check(reader.result) ? "PASS" : `FAIL (got ${reader.result})`);
+    };
+    reader.onerror = () => console.log(`${method}: FAIL (error ${reader.error})`);
+    reader[method](source);
+    sync = false;
+}
+
+read("readAsText", blob, (result) => result === 'Hello, World!');
+read("readAsArrayBuffer", blob, (result) => result instanceof ArrayBuffer && result.byteLength === 13);
+read("readAsDataURL", blob, (result) => result === 'data:text/plain;base64,SGVsbG8sIFdvcmxkIQ==');
+read("readAsText", blob.slice(7, 12), (result) => result === 'World');
@@ -0,0 +1,18 @@
+/*
+ * Formats a greeting for each of the given names.
+ *
+ * Minifying this script removes this comment and the indentation below, which
+ * must not change what it writes.
+ */
+function greet(names) {
+  // Names are separated by commas and optional whitespace.
+  const separator = / *, */;
+  return names
+    .split(separator)
+    .map((name) => `Hello, ${name}!`)
+    .join("\n");
+}
+
+// The result ends up on stdout.
+const greetings = greet("Ada ,  Grace")
+Javy.IO.writeSync(1, new TextEncoder().encode(greetings));
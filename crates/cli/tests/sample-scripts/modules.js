@@ -0,0 +1,7 @@
+import { double } from "./lib/math.js";
+
+const output = [double(2)];
+import("./lib/math.js").then(({ double }) => {
+  output.push(double(3));
+  Javy.IO.writeSync(1, new TextEncoder().encode(JSON.stringify(output)));
+});
@@ -0,0 +1 @@
+new Array(1e9).fill('x');
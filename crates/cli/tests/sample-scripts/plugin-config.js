@@ -0,0 +1 @@
+console.log(pluginConfig["my-opt"]);
@@ -0,0 +1,2 @@
+reportError(new Error("something went wrong"));
+console.log("still running");
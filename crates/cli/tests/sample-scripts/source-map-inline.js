@@ -0,0 +1,6 @@
+function fail() {
+  throw new Error("boom");
+}
+
+fail();
+//# sourceMappingURL=data:application/json;base64,eyJ2ZXJzaW9uIjozLCJmaWxlIjoic291cmNlLW1hcC5qcyIsInNvdXJjZXMiOlsic3JjL2luZGV4LnRzIl0sIm5hbWVzIjpbXSwibWFwcGluZ3MiOiJBQUFBO1FBU0k7OztBQUdKIn0=
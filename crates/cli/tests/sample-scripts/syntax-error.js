@@ -0,0 +1,2 @@
+// The syntax error is on the second line.
+function ( {
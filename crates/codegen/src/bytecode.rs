@@ -1,23 +1,173 @@
-use anyhow::{anyhow, Result};
+use std::io::Write;
+
+use anyhow::{anyhow, Context, Result};
 use wasmtime::{AsContextMut, Engine, Instance, Linker, Memory, Module, Store};
-use wasmtime_wasi::{preview1::WasiP1Ctx, WasiCtxBuilder};
+use wasmtime_wasi::{pipe::MemoryOutputPipe, preview1::WasiP1Ctx, WasiCtxBuilder};
+
+/// The maximum number of bytes of the plugin's stderr captured while
+/// compiling.
+const STDERR_CAPACITY: usize = 1024 * 1024;
+
+/// The bit set in the length returned by `compile_src` when the buffer holds
+/// an error message rather than bytecode.
+pub const COMPILE_ERROR_FLAG: u32 = 1 << 31;
+
+/// Compiles source code to the bytecode of a module named `name`, or
+/// `function.mjs` if there's no name.
+pub(crate) fn compile_source(
+    plugin_bytes: &[u8],
+    name: Option<&str>,
+    js_source_code: &[u8],
+    pretty_errors: bool,
+) -> Result<Vec<u8>> {
+    compile(plugin_bytes, js_source_code, name, None, pretty_errors)
+}
+
+/// Compiles the source code of a module, resolving the modules it imports
+/// from a module table of source code. The module is the one the generated
+/// module evaluates if it has no name.
+pub(crate) fn compile_module_source(
+    plugin_bytes: &[u8],
+    name: Option<&str>,
+    js_source_code: &[u8],
+    module_sources: &[u8],
+    pretty_errors: bool,
+) -> Result<Vec<u8>> {
+    compile(
+        plugin_bytes,
+        js_source_code,
+        name,
+        Some(module_sources),
+        pretty_errors,
+    )
+}
+
+/// Encodes a module table: a sequence of entries, each made of the u32
+/// length of the name of a module, the name, the u32 length of the code of
+/// the module and the code, with the lengths in little endian.
+pub(crate) fn encode_module_table<'a>(
+    modules: impl IntoIterator<Item = (&'a str, &'a [u8])>,
+) -> Result<Vec<u8>> {
+    let mut table = vec![];
+    for (name, code) in modules {
+        for field in [name.as_bytes(), code] {
+            let len: u32 = field.len().try_into()?;
+            table.extend(len.to_le_bytes());
+            table.extend(field);
+        }
+    }
+    Ok(table)
+}
 
-pub(crate) fn compile_source(plugin_bytes: &[u8], js_source_code: &[u8]) -> Result<Vec<u8>> {
-    let (mut store, instance, memory) = create_wasm_env(plugin_bytes)?;
+fn compile(
+    plugin_bytes: &[u8],
+    js_source_code: &[u8],
+    name: Option<&str>,
+    module_sources: Option<&[u8]>,
+    pretty_errors: bool,
+) -> Result<Vec<u8>> {
+    let stderr = MemoryOutputPipe::new(STDERR_CAPACITY);
+    let (mut store, instance, memory) = create_wasm_env(plugin_bytes, stderr.clone())?;
     let (js_src_ptr, js_src_len) =
         copy_source_code_into_instance(js_source_code, store.as_context_mut(), &instance, &memory)?;
-    let ret_ptr = call_compile(js_src_ptr, js_src_len, store.as_context_mut(), &instance)?;
-    let bytecode = copy_bytecode_from_instance(ret_ptr, store.as_context_mut(), &memory)?;
+    let (name_ptr, name_len) = match name {
+        Some(name) => copy_source_code_into_instance(
+            name.as_bytes(),
+            store.as_context_mut(),
+            &instance,
+            &memory,
+        )?,
+        // A null name stands for the module the generated module evaluates,
+        // named `function.mjs`.
+        None => (0, 0),
+    };
+    let compiled = match module_sources {
+        None if name.is_none() => {
+            call_compile(js_src_ptr, js_src_len, store.as_context_mut(), &instance)
+        }
+        None => call_compile_named(
+            (js_src_ptr, js_src_len),
+            (name_ptr, name_len),
+            store.as_context_mut(),
+            &instance,
+        ),
+        Some(module_sources) => {
+            let (modules_ptr, modules_len) = copy_source_code_into_instance(
+                module_sources,
+                store.as_context_mut(),
+                &instance,
+                &memory,
+            )?;
+            call_compile_with_modules(
+                (js_src_ptr, js_src_len),
+                (name_ptr, name_len),
+                (modules_ptr, modules_len),
+                store.as_context_mut(),
+                &instance,
+            )
+        }
+    };
+    let ret_ptr = match compiled {
+        Ok(ret_ptr) => ret_ptr,
+        // Plugins built with older versions of the plugin API trap after
+        // writing the error to stderr.
+        Err(e) => {
+            let output = String::from_utf8_lossy(&stderr.contents()).into_owned();
+            if pretty_errors {
+                if let Some(error) = CompileError::parse(&output) {
+                    return Err(anyhow!(
+                        "{e}: {}",
+                        error.render(&String::from_utf8_lossy(js_source_code))
+                    ));
+                }
+            }
+            std::io::stderr().write_all(output.as_bytes())?;
+            return Err(e);
+        }
+    };
+    let (bytecode_ptr, bytecode_len) = read_ret_area(ret_ptr, store.as_context_mut(), &memory)?;
+    if bytecode_len & COMPILE_ERROR_FLAG != 0 {
+        let message = copy_bytecode_from_instance(
+            bytecode_ptr,
+            bytecode_len & !COMPILE_ERROR_FLAG,
+            store.as_context_mut(),
+            &memory,
+        )?;
+        let message = String::from_utf8_lossy(&message);
+        return Err(compile_error(&message, js_source_code, pretty_errors));
+    }
+    let bytecode =
+        copy_bytecode_from_instance(bytecode_ptr, bytecode_len, store.as_context_mut(), &memory)?;
+    free_bytecode(
+        bytecode_ptr,
+        bytecode_len,
+        store.as_context_mut(),
+        &instance,
+    )?;
     Ok(bytecode)
 }
 
-fn create_wasm_env(plugin_bytes: &[u8]) -> Result<(Store<WasiP1Ctx>, Instance, Memory)> {
+/// The error for the compilation error message reported by a plugin,
+/// rendered with the source code when pretty errors are enabled.
+fn compile_error(message: &str, js_source_code: &[u8], pretty_errors: bool) -> anyhow::Error {
+    let message = match CompileError::parse(message) {
+        Some(error) if pretty_errors => error.render(&String::from_utf8_lossy(js_source_code)),
+        Some(error) => format!("{} ({}:{})", error.message, error.line, error.column),
+        None => message.trim().to_string(),
+    };
+    anyhow!("JS compilation failed: {message}")
+}
+
+fn create_wasm_env(
+    plugin_bytes: &[u8],
+    stderr: MemoryOutputPipe,
+) -> Result<(Store<WasiP1Ctx>, Instance, Memory)> {
     let engine = Engine::default();
     let module = Module::new(&engine, plugin_bytes)?;
     let mut linker = Linker::new(&engine);
     wasmtime_wasi::preview1::add_to_linker_sync(&mut linker, |s| s)?;
     linker.define_unknown_imports_as_traps(&module)?;
-    let wasi = WasiCtxBuilder::new().inherit_stderr().build_p1();
+    let wasi = WasiCtxBuilder::new().stderr(stderr).build_p1();
     let mut store = Store::new(&engine, wasi);
     let instance = linker.instantiate(store.as_context_mut(), &module)?;
     let memory = instance
@@ -70,19 +220,187 @@ fn call_compile(
     Ok(ret_ptr)
 }
 
-fn copy_bytecode_from_instance(
+fn call_compile_named(
+    (js_src_ptr, js_src_len): (u32, u32),
+    (name_ptr, name_len): (u32, u32),
+    mut store: impl AsContextMut,
+    instance: &Instance,
+) -> Result<u32> {
+    let compile_fn = instance
+        .get_typed_func::<(u32, u32, u32, u32), u32>(store.as_context_mut(), "compile_src_named")
+        .context("The plugin does not support naming the module")?;
+    let ret_ptr = compile_fn
+        .call(
+            store.as_context_mut(),
+            (js_src_ptr, js_src_len, name_ptr, name_len),
+        )
+        .map_err(|_| anyhow!("JS compilation failed"))?;
+    Ok(ret_ptr)
+}
+
+fn call_compile_with_modules(
+    (js_src_ptr, js_src_len): (u32, u32),
+    (name_ptr, name_len): (u32, u32),
+    (modules_ptr, modules_len): (u32, u32),
+    mut store: impl AsContextMut,
+    instance: &Instance,
+) -> Result<u32> {
+    let compile_fn = instance
+        .get_typed_func::<(u32, u32, u32, u32, u32, u32), u32>(
+            store.as_context_mut(),
+            "compile_src_with_modules",
+        )
+        .context("The plugin does not support importing modules")?;
+    let ret_ptr = compile_fn
+        .call(
+            store.as_context_mut(),
+            (
+                js_src_ptr,
+                js_src_len,
+                name_ptr,
+                name_len,
+                modules_ptr,
+                modules_len,
+            ),
+        )
+        .map_err(|_| anyhow!("JS compilation failed"))?;
+    Ok(ret_ptr)
+}
+
+/// Reads the pointer to and the length of the bytecode from the buffer
+/// returned by `compile_src`.
+fn read_ret_area(
     ret_ptr: u32,
     mut store: impl AsContextMut,
     memory: &Memory,
-) -> Result<Vec<u8>> {
+) -> Result<(u32, u32)> {
     let mut ret_buffer = [0; 8];
     memory.read(store.as_context_mut(), ret_ptr.try_into()?, &mut ret_buffer)?;
 
     let bytecode_ptr = u32::from_le_bytes(ret_buffer[0..4].try_into()?);
     let bytecode_len = u32::from_le_bytes(ret_buffer[4..8].try_into()?);
+    Ok((bytecode_ptr, bytecode_len))
+}
 
+fn copy_bytecode_from_instance(
+    bytecode_ptr: u32,
+    bytecode_len: u32,
+    store: impl AsContextMut,
+    memory: &Memory,
+) -> Result<Vec<u8>> {
     let mut bytecode = vec![0; bytecode_len.try_into()?];
     memory.read(store.as_context(), bytecode_ptr.try_into()?, &mut bytecode)?;
 
     Ok(bytecode)
 }
+
+/// Releases the bytecode buffer in the instance's memory once it's been
+/// copied out.
+fn free_bytecode(
+    bytecode_ptr: u32,
+    bytecode_len: u32,
+    mut store: impl AsContextMut,
+    instance: &Instance,
+) -> Result<()> {
+    // Older plugins don't export `free_bytecode` and leak the buffer instead.
+    let Ok(free_bytecode_fn) =
+        instance.get_typed_func::<(u32, u32), ()>(store.as_context_mut(), "free_bytecode")
+    else {
+        return Ok(());
+    };
+    free_bytecode_fn.call(store.as_context_mut(), (bytecode_ptr, bytecode_len))?;
+    Ok(())
+}
+
+/// A JS compilation error reported by the plugin.
+#[derive(Debug, PartialEq)]
+struct CompileError {
+    message: String,
+    /// 1-based line of the error.
+    line: usize,
+    /// 0-based column of the error.
+    column: usize,
+}
+
+impl CompileError {
+    /// Extracts the first error reported as `Error:[file]:line:column message`
+    /// from the plugin's output.
+    fn parse(output: &str) -> Option<Self> {
+        output.lines().find_map(|line| {
+            let (_, rest) = line.split_once("Error:[")?;
+            let (_, rest) = rest.split_once("]:")?;
+            let (position, message) = rest.split_once(' ')?;
+            let (line, column) = position.split_once(':')?;
+            Some(Self {
+                message: message.to_string(),
+                line: line.parse().ok()?,
+                column: column.parse().ok()?,
+            })
+        })
+    }
+
+    /// Renders the error followed by the offending source line and a caret
+    /// under the error column.
+    fn render(&self, source: &str) -> String {
+        let position = format!("{}:{}", self.line, self.column);
+        let Some(source_line) = source.lines().nth(self.line.saturating_sub(1)) else {
+            return format!("{} ({position})", self.message);
+        };
+        let gutter = " ".repeat(self.line.to_string().len());
+        // Keep tabs so the caret lines up with the source line.
+        let padding: String = source_line
+            .chars()
+            .take(self.column)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        format!(
+            "{}\n{gutter}--> {position}\n{} | {source_line}\n{gutter} | {padding}^",
+            self.message, self.line
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{encode_module_table, CompileError};
+
+    const OUTPUT: &str = "thread '<unnamed>' panicked at crates/plugin-api/src/lib.rs:129:10:
+called `Result::unwrap()` on an `Err` value: Error:[function.mjs]:2:8 unexpected token in expression: ';'
+    at function.mjs:2:8
+";
+
+    #[test]
+    fn parse_compile_error() {
+        assert_eq!(
+            CompileError::parse(OUTPUT),
+            Some(CompileError {
+                message: "unexpected token in expression: ';'".to_string(),
+                line: 2,
+                column: 8,
+            })
+        );
+        assert_eq!(CompileError::parse("some other panic"), None);
+    }
+
+    #[test]
+    fn render_compile_error() {
+        let error = CompileError::parse(OUTPUT).unwrap();
+        assert_eq!(
+            error.render("let a = 1;\nlet b = ;\n"),
+            "unexpected token in expression: ';'\n --> 2:8\n2 | let b = ;\n  |         ^"
+        );
+        assert_eq!(
+            error.render("let a = 1;"),
+            "unexpected token in expression: ';' (2:8)"
+        );
+    }
+
+    #[test]
+    fn encode_module_table_entries() {
+        assert_eq!(
+            encode_module_table([("a.js", &b"xy"[..]), ("b", b"")]).unwrap(),
+            b"\x04\0\0\0a.js\x02\0\0\0xy\x01\0\0\0b\0\0\0\0"
+        );
+        assert!(encode_module_table([]).unwrap().is_empty());
+    }
+}
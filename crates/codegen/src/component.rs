@@ -0,0 +1,66 @@
+use anyhow::{Context, Result};
+use walrus::Module;
+use wasi_preview1_component_adapter_provider::WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER;
+use wit_component::{ComponentEncoder, StringEncoding};
+use wit_parser::Resolve;
+
+use crate::{exports::Exports, wit::WitOptions};
+
+/// The world of components generated without WIT options, which only export
+/// the `wasi:cli/run` interface provided by the adapter.
+const DEFAULT_WORLD: &str = r#"
+package javy:component;
+
+world javy {}
+"#;
+
+/// The core exports a component can be encoded from, besides the exported
+/// WIT functions.
+const COMPONENT_CORE_EXPORTS: [&str; 3] = ["_start", "memory", "cabi_realloc"];
+
+/// Removes the exports of the plugin that aren't part of the world of the
+/// component, which the component encoder would otherwise reject.
+pub(crate) fn retain_world_exports(module: &mut Module, function_exports: &Exports) {
+    let removed: Vec<_> = module
+        .exports
+        .iter()
+        .filter(|export| {
+            !COMPONENT_CORE_EXPORTS.contains(&export.name.as_str())
+                && !function_exports.iter().any(|f| f.wit == export.name)
+        })
+        .map(|export| export.id())
+        .collect();
+    for id in removed {
+        module.exports.delete(id);
+    }
+}
+
+/// Encodes a statically linked module as a component targeting the WIT world
+/// of the options, or an empty world if there is none.
+///
+/// The `wasi_snapshot_preview1` imports of the module are adapted to WASI 0.2,
+/// and `_start` is exported as the `run` function of `wasi:cli/run`.
+pub(crate) fn encode(wasm: &[u8], wit_opts: &WitOptions) -> Result<Vec<u8>> {
+    let mut resolve = Resolve::default();
+    let world_id = if wit_opts.defined() {
+        resolve.push_path(wit_opts.unwrap_path())?;
+        let (_, package_id) = resolve.package_names.first().unwrap();
+        resolve.select_world(&[*package_id], Some(wit_opts.unwrap_world()))?
+    } else {
+        let package_ids = resolve.push_str("javy.wit", DEFAULT_WORLD)?;
+        resolve.select_world(&package_ids, Some("javy"))?
+    };
+
+    let mut wasm = wasm.to_vec();
+    wit_component::embed_component_metadata(&mut wasm, &resolve, world_id, StringEncoding::UTF8)?;
+
+    ComponentEncoder::default()
+        .validate(true)
+        .module(&wasm)?
+        .adapter(
+            "wasi_snapshot_preview1",
+            WASI_SNAPSHOT_PREVIEW1_COMMAND_ADAPTER,
+        )?
+        .encode()
+        .context("Failed to encode the module as a component")
+}
@@ -11,27 +11,33 @@ pub(crate) type Exports = Vec<Export>;
 pub(crate) struct Export {
     pub wit: String,
     pub js: String,
+    /// The parameters and result of the WIT function.
+    pub signature: wit::Signature,
 }
 
 pub(crate) fn process_exports(js: &JS, wit: &Path, wit_world: &str) -> Result<Vec<Export>> {
     let js_exports = js.exports()?;
     parse_wit_exports(wit, wit_world)?
         .into_iter()
-        .map(|wit_export| {
+        .map(|(wit_export, signature)| {
             let export = wit_export.from_case(Case::Kebab).to_case(Case::Camel);
-            if !js_exports.contains(&export) {
-                Err(anyhow!("JS module does not export {export}"))
-            } else {
-                Ok(Export {
+            match js_exports.iter().find(|(name, _)| *name == export) {
+                None => Err(anyhow!("JS module does not export {export}")),
+                Some((_, params)) if *params > signature.params.len() => Err(anyhow!(
+                    "JS function {export} has {params} parameters but the WIT function {wit_export} has {}",
+                    signature.params.len()
+                )),
+                Some(_) => Ok(Export {
                     wit: wit_export,
                     js: export,
-                })
+                    signature,
+                }),
             }
         })
         .collect::<Result<Vec<Export>>>()
 }
 
-fn parse_wit_exports(wit: &Path, wit_world: &str) -> Result<Vec<String>> {
+fn parse_wit_exports(wit: &Path, wit_world: &str) -> Result<Vec<(String, wit::Signature)>> {
     // Configure wit-parser to not require semicolons but only if the relevant
     // environment variable is not already set.
     const SEMICOLONS_OPTIONAL_ENV_VAR: &str = "WIT_REQUIRE_SEMICOLONS";
@@ -13,14 +13,18 @@ use std::{
 
 use anyhow::{anyhow, bail, Context, Result};
 use brotli::enc::{self, BrotliEncoderParams};
+use sourcemap::SourceMapBuilder;
 use swc_core::{
-    common::{FileName, SourceMap},
+    common::{BytePos, FileName, SourceMap},
     ecma::{
         ast::{
-            Decl, EsVersion, ExportDecl, ExportSpecifier, Module, ModuleDecl, ModuleExportName,
-            ModuleItem, Stmt,
+            Decl, DefaultDecl, EsVersion, ExportDecl, ExportDefaultDecl, ExportSpecifier, Expr,
+            Module, ModuleDecl, ModuleExportName, ModuleItem, Pat, Stmt,
+        },
+        parser::{
+            self, lexer::Lexer, token::TokenAndSpan, Capturing, EsSyntax, Parser, StringInput,
+            Syntax,
         },
-        parser::{self, EsSyntax, Syntax},
     },
 };
 
@@ -54,9 +58,39 @@ impl JS {
         self.source_code.as_bytes()
     }
 
-    /// Compiles a JavaScript source to bytecode using a QuickJS plugin.
-    pub(crate) fn compile(&self, plugin: &Plugin) -> Result<Vec<u8>> {
-        plugin.compile_source(self.source_code.as_bytes())
+    /// Compiles a JavaScript source to bytecode using a QuickJS plugin, for a
+    /// module with name or `function.mjs` if there's no name.
+    ///
+    /// If `pretty_errors` is set, compilation errors include the offending
+    /// source line.
+    pub(crate) fn compile(
+        &self,
+        plugin: &Plugin,
+        name: Option<&str>,
+        pretty_errors: bool,
+    ) -> Result<Vec<u8>> {
+        plugin.compile_source(name, self.source_code.as_bytes(), pretty_errors)
+    }
+
+    /// Compiles the JavaScript source of a module to bytecode using a QuickJS
+    /// plugin, resolving the modules it imports from a module table of
+    /// source code.
+    ///
+    /// The module is the one the generated module evaluates, named
+    /// `function.mjs`, if it has no name.
+    pub(crate) fn compile_module(
+        &self,
+        plugin: &Plugin,
+        name: Option<&str>,
+        module_sources: &[u8],
+        pretty_errors: bool,
+    ) -> Result<Vec<u8>> {
+        plugin.compile_module_source(
+            name,
+            self.source_code.as_bytes(),
+            module_sources,
+            pretty_errors,
+        )
     }
 
     /// Get Brotli compressed JS source code as bytes.
@@ -73,8 +107,104 @@ impl JS {
         Ok(compressed_source_code)
     }
 
-    /// Get the exports from a JS instance.
-    pub(crate) fn exports(&self) -> Result<Vec<String>> {
+    /// Get a minified copy of the JS source code, without comments and
+    /// without the whitespace that isn't needed to separate tokens.
+    ///
+    /// Line breaks between tokens are kept since they can terminate
+    /// statements.
+    pub(crate) fn minify(&self) -> Result<JS> {
+        let (minified, _) = self.minified()?;
+        Ok(Self::from_string(minified))
+    }
+
+    /// Get a source map from the code embedded in generated modules, named
+    /// `file`, to the JS source code, named `source`.
+    ///
+    /// If `minify` is set, the embedded code is the minified copy of the JS
+    /// source code. Each token of the embedded code is mapped to the line and
+    /// column it starts at in the JS source code.
+    pub(crate) fn source_map(
+        &self,
+        file: &str,
+        source: &str,
+        minify: bool,
+    ) -> Result<sourcemap::SourceMap> {
+        let (code, offsets) = if minify {
+            self.minified()?
+        } else {
+            let offsets = self.tokens()?.into_iter().map(|token| {
+                let offset = token.span.lo.0 as usize - 1;
+                (offset, offset)
+            });
+            (self.source_code.to_string(), offsets.collect())
+        };
+
+        let mut builder = SourceMapBuilder::new(Some(file));
+        let source_id = builder.add_source(source);
+        builder.set_source_contents(source_id, Some(&self.source_code));
+
+        let mut generated = LineColumns::new(&code);
+        let mut original = LineColumns::new(&self.source_code);
+        for (generated_offset, original_offset) in offsets {
+            let (dst_line, dst_col) = generated.advance_to(generated_offset);
+            let (src_line, src_col) = original.advance_to(original_offset);
+            builder.add_raw(
+                dst_line,
+                dst_col,
+                src_line,
+                src_col,
+                Some(source_id),
+                None,
+                false,
+            );
+        }
+        Ok(builder.into_sourcemap())
+    }
+
+    /// Get a minified copy of the JS source code along with the offsets of
+    /// each of its tokens in the minified copy and in the JS source code.
+    fn minified(&self) -> Result<(String, Vec<(usize, usize)>)> {
+        let source = self.source_code.as_str();
+        let mut minified = String::with_capacity(source.len());
+        let mut offsets = vec![];
+        for token in self.tokens()? {
+            let text = &source[token.span.lo.0 as usize - 1..token.span.hi.0 as usize - 1];
+            if let (Some(prev), Some(next)) = (minified.chars().next_back(), text.chars().next()) {
+                if token.had_line_break {
+                    minified.push('\n');
+                } else if needs_space(prev, next) {
+                    minified.push(' ');
+                }
+            }
+            offsets.push((minified.len(), token.span.lo.0 as usize - 1));
+            minified.push_str(text);
+        }
+        Ok((minified, offsets))
+    }
+
+    /// Get the tokens of the JS source code, with spans starting at 1.
+    fn tokens(&self) -> Result<Vec<TokenAndSpan>> {
+        let source = self.source_code.as_str();
+        // Positions start at 1 since 0 is reserved for dummy spans.
+        let lexer = Lexer::new(
+            Syntax::Es(EsSyntax::default()),
+            EsVersion::Es2020,
+            StringInput::new(source, BytePos(1), BytePos(source.len() as u32 + 1)),
+            None,
+        );
+        // The tokens are captured while parsing since the lexer relies on the
+        // parser to tell regular expressions apart from divisions.
+        let mut tokens = Capturing::new(lexer);
+        Parser::new_from(tokens.clone())
+            .parse_module()
+            .map_err(|e| anyhow!(e.into_kind().msg()))
+            .with_context(|| "Invalid JavaScript")?;
+        Ok(Capturing::take(&mut tokens))
+    }
+
+    /// Get the exported functions from a JS instance, with the number of
+    /// parameters they declare.
+    pub(crate) fn exports(&self) -> Result<Vec<(String, usize)>> {
         let module = self.parse_module()?;
 
         // function foo() ...
@@ -89,13 +219,11 @@ impl JS {
                     decl: Decl::Fn(f),
                     ..
                 })) => {
-                    if !f.function.params.is_empty() {
-                        bail!("Exported functions with parameters are not supported");
-                    }
                     if f.function.is_generator {
                         bail!("Exported generators are not supported");
                     }
-                    exported_functions.push(f.ident.sym);
+                    let params = param_count(f.function.params.iter().map(|p| &p.pat));
+                    exported_functions.push((f.ident.sym, params));
                 }
                 ModuleItem::ModuleDecl(ModuleDecl::ExportNamed(e)) => {
                     for specifier in e.specifiers {
@@ -112,16 +240,25 @@ impl JS {
                         }
                     }
                 }
-                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(e)) if e.decl.is_fn_expr() => {
-                    exported_functions.push("default".into())
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultDecl(ExportDefaultDecl {
+                    decl: DefaultDecl::Fn(f),
+                    ..
+                })) => {
+                    let params = param_count(f.function.params.iter().map(|p| &p.pat));
+                    exported_functions.push(("default".into(), params))
                 }
-                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(e)) if e.expr.is_arrow() => {
-                    exported_functions.push("default".into())
+                ModuleItem::ModuleDecl(ModuleDecl::ExportDefaultExpr(e)) => {
+                    if let Expr::Arrow(arrow) = &*e.expr {
+                        exported_functions.push(("default".into(), param_count(&arrow.params)))
+                    }
                 }
                 ModuleItem::Stmt(Stmt::Decl(Decl::Fn(f))) => {
                     functions.insert(
                         f.ident.sym,
-                        (f.function.params.is_empty(), f.function.is_generator),
+                        (
+                            param_count(f.function.params.iter().map(|p| &p.pat)),
+                            f.function.is_generator,
+                        ),
                     );
                 }
                 _ => continue,
@@ -131,15 +268,11 @@ impl JS {
         let mut named_exported_functions = named_exports
             .into_iter()
             .filter_map(|(orig, exported)| {
-                if let Some((no_params, is_generator)) = functions.get(&orig) {
-                    if !no_params {
-                        Some(Err(anyhow!(
-                            "Exported functions with parameters are not supported"
-                        )))
-                    } else if *is_generator {
+                if let Some((params, is_generator)) = functions.get(&orig) {
+                    if *is_generator {
                         Some(Err(anyhow!("Exported generators are not supported")))
                     } else {
-                        Some(Ok(exported.unwrap_or(orig)))
+                        Some(Ok((exported.unwrap_or(orig), *params)))
                     }
                 } else {
                     None
@@ -149,7 +282,7 @@ impl JS {
         exported_functions.append(&mut named_exported_functions);
         Ok(exported_functions
             .into_iter()
-            .map(|f| f.to_string())
+            .map(|(f, params)| (f.to_string(), params))
             .collect())
     }
 
@@ -169,6 +302,59 @@ impl JS {
     }
 }
 
+/// Whether a space is needed between two adjacent tokens, ending and starting
+/// with the given characters, for them to be read back as the same tokens.
+fn needs_space(prev: char, next: char) -> bool {
+    let is_word =
+        |c: char| c.is_alphanumeric() || c == '_' || c == '$' || c == '\\' || !c.is_ascii();
+    (is_word(prev) && is_word(next))
+        // `1 .toString()`
+        || (prev.is_ascii_digit() && next == '.')
+        // `a + +b`, `a / /re/` and `/re/ * b` don't become `++` or a comment.
+        || matches!((prev, next), ('+', '+') | ('-', '-') | ('/', '/') | ('/', '*'))
+}
+
+/// Converts increasing byte offsets in a text to zero-based lines and
+/// columns, counted in UTF-16 code units as in source maps.
+struct LineColumns<'a> {
+    text: &'a str,
+    offset: usize,
+    line: u32,
+    column: u32,
+}
+
+impl<'a> LineColumns<'a> {
+    fn new(text: &'a str) -> Self {
+        Self {
+            text,
+            offset: 0,
+            line: 0,
+            column: 0,
+        }
+    }
+
+    /// Get the line and column of `offset`, which can't be lower than the
+    /// offset of the previous call.
+    fn advance_to(&mut self, offset: usize) -> (u32, u32) {
+        for c in self.text[self.offset..offset].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.column = 0;
+            } else {
+                self.column += c.len_utf16() as u32;
+            }
+        }
+        self.offset = offset;
+        (self.line, self.column)
+    }
+}
+
+/// The number of parameters declared by a function, not counting a rest
+/// parameter.
+fn param_count<'a>(params: impl IntoIterator<Item = &'a Pat>) -> usize {
+    params.into_iter().filter(|p| !p.is_rest()).count()
+}
+
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
@@ -198,11 +384,8 @@ mod tests {
 
     #[test]
     fn parse_func_export_with_parameter() -> Result<()> {
-        let res = parse("export function foo(bar) {}");
-        assert_eq!(
-            "Exported functions with parameters are not supported",
-            res.err().unwrap().to_string()
-        );
+        let exports = parse_with_params("export function foo(bar, ...baz) {}")?;
+        assert_eq!(vec![("foo".to_string(), 1)], exports);
         Ok(())
     }
 
@@ -247,11 +430,8 @@ mod tests {
 
     #[test]
     fn parse_named_func_export_with_arg() -> Result<()> {
-        let res = parse("function foo(bar) {}; export { foo };");
-        assert_eq!(
-            "Exported functions with parameters are not supported",
-            res.err().unwrap().to_string()
-        );
+        let exports = parse_with_params("function foo(bar, baz) {}; export { foo as qux };")?;
+        assert_eq!(vec![("qux".to_string(), 2)], exports);
         Ok(())
     }
 
@@ -318,7 +498,108 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn parse_default_exports_with_params() -> Result<()> {
+        let exports = parse_with_params("export default function(a, b) {}")?;
+        assert_eq!(vec![("default".to_string(), 2)], exports);
+        let exports = parse_with_params("export default (a) => a")?;
+        assert_eq!(vec![("default".to_string(), 1)], exports);
+        Ok(())
+    }
+
+    #[test]
+    fn minify_removes_comments_and_whitespace() -> Result<()> {
+        let minified = minify(
+            r#"
+            // Adds numbers.
+            export function add(a, b) {
+                /* The sum. */
+                return a + b;
+            }
+            "#,
+        )?;
+        assert_eq!("export function add(a,b){\nreturn a+b;\n}", minified);
+        Ok(())
+    }
+
+    #[test]
+    fn minify_keeps_tokens_apart() -> Result<()> {
+        assert_eq!("let a=1 .toString()", minify("let a = 1 .toString()")?);
+        assert_eq!("a=b+ +c- -d", minify("a = b + +c - -d")?);
+        assert_eq!("a=b/ /c/g", minify("a = b / /c/g")?);
+        assert_eq!("a=/b/ *2", minify("a = /b/ * 2")?);
+        assert_eq!("a=/ b /g in c", minify("a = / b /g in c")?);
+        assert_eq!("a=`x ${b}y`", minify("a = `x ${ b }y`")?);
+        Ok(())
+    }
+
+    #[test]
+    fn source_map_maps_tokens_to_their_source() -> Result<()> {
+        let js = JS::from_string("function f() {\n  throw new Error('\u{e9}');\n}\nf();\n".into());
+        let source_map = js.source_map("function.mjs", "input.js", false)?;
+        assert_eq!(Some("function.mjs"), source_map.get_file());
+        assert_eq!(Some("input.js"), source_map.get_source(0));
+        assert_eq!(
+            Some(js.source_code.as_str()),
+            source_map.get_source_contents(0)
+        );
+
+        let token = source_map.lookup_token(1, 8).unwrap();
+        assert_eq!((1, 8), (token.get_src_line(), token.get_src_col()));
+        assert_eq!(Some("input.js"), token.get_source());
+        Ok(())
+    }
+
+    #[test]
+    fn source_map_maps_minified_tokens_to_their_source() -> Result<()> {
+        let js =
+            JS::from_string("// f\nfunction f() {\n  /* \u{e9} */ throw new Error();\n}\n".into());
+        assert_eq!(
+            "function f(){\nthrow new Error();\n}",
+            js.minify()?.source_code.as_str()
+        );
+        let source_map = js.source_map("function.mjs", "input.js", true)?;
+
+        // `new` follows `throw` on the second line of the minified copy.
+        let token = source_map.lookup_token(1, 6).unwrap();
+        assert_eq!((1, 6), (token.get_dst_line(), token.get_dst_col()));
+        assert_eq!((2, 16), (token.get_src_line(), token.get_src_col()));
+
+        // `{` follows `)` in the minified copy.
+        let token = source_map.lookup_token(0, 12).unwrap();
+        assert_eq!((1, 13), (token.get_src_line(), token.get_src_col()));
+        Ok(())
+    }
+
+    #[test]
+    fn minify_keeps_line_breaks() -> Result<()> {
+        assert_eq!("let a=b\n(c)", minify("let a = b\n\n  (c)")?);
+        assert_eq!(
+            "function f(){return\na}",
+            minify("function f() { return /*\n*/ a }")?
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn minify_invalid_js() {
+        let res = minify("fun foo() {}");
+        assert_eq!("Invalid JavaScript", res.err().unwrap().to_string());
+    }
+
+    fn minify(js: &str) -> Result<String> {
+        let minified = JS::from_string(js.to_string()).minify()?;
+        Ok(String::from_utf8(minified.as_bytes().to_vec())?)
+    }
+
     fn parse(js: &str) -> Result<Vec<String>> {
+        Ok(parse_with_params(js)?
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect())
+    }
+
+    fn parse_with_params(js: &str) -> Result<Vec<(String, usize)>> {
         JS::from_string(js.to_string()).exports()
     }
 }
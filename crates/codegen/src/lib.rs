@@ -61,6 +61,8 @@
 //! ## Core concepts
 //! * [`Generator`] - The main entry point for generating Wasm modules.
 //! * [`Plugin`] - An initialized Javy plugin.
+//! * [`LinkedPlugin`] - The plugin a dynamically linked module was generated
+//!   with.
 //! * [`JS`] - JavaScript source code.
 //!
 //! ## Features
@@ -70,32 +72,63 @@
 //!   unstable API's exposed by this future may break in the future without
 //!   notice.
 
-use std::{fs, rc::Rc, sync::OnceLock};
+use std::{fs, rc::Rc, sync::OnceLock, time::Duration};
 
 pub(crate) mod bytecode;
+pub(crate) mod component;
 pub(crate) mod exports;
 pub(crate) mod transform;
 
 pub(crate) mod js;
 pub(crate) mod plugin;
+pub(crate) mod source_map;
 pub(crate) mod wit;
 
+pub use crate::bytecode::COMPILE_ERROR_FLAG;
 pub use crate::js::JS;
-pub use crate::plugin::Plugin;
+pub use crate::plugin::{LinkedPlugin, Plugin};
 pub use crate::wit::WitOptions;
 
 use transform::SourceCodeSection;
 use walrus::{
-    DataId, DataKind, ExportItem, FunctionBuilder, FunctionId, LocalId, MemoryId, Module, ValType,
+    ir::{LoadKind, MemArg, StoreKind, UnaryOp},
+    DataId, DataKind, ExportItem, FunctionBuilder, FunctionId, InstrSeqBuilder, LocalId, MemoryId,
+    Module, RawCustomSection, ValType,
 };
 use wasm_opt::{OptimizationOptions, ShrinkLevel};
-use wasmtime_wasi::{pipe::MemoryInputPipe, WasiCtxBuilder};
+use wasmtime_wasi::{
+    pipe::MemoryInputPipe, Deterministic, HostMonotonicClock, HostWallClock, WasiCtxBuilder,
+};
 use wizer::{Linker, Wizer};
 
-use anyhow::Result;
+use anyhow::{bail, Context, Result};
 
 static STDIN_PIPE: OnceLock<MemoryInputPipe> = OnceLock::new();
 
+/// A clock stopped at the Unix epoch, seen by the plugin while it's
+/// pre-initialized for deterministic builds.
+struct EpochClock;
+
+impl HostWallClock for EpochClock {
+    fn resolution(&self) -> Duration {
+        Duration::from_nanos(1)
+    }
+
+    fn now(&self) -> Duration {
+        Duration::ZERO
+    }
+}
+
+impl HostMonotonicClock for EpochClock {
+    fn resolution(&self) -> u64 {
+        1
+    }
+
+    fn now(&self) -> u64 {
+        0
+    }
+}
+
 /// The kind of linking to use.
 #[derive(Clone, Default)]
 pub enum LinkingKind {
@@ -106,12 +139,27 @@ pub enum LinkingKind {
     Dynamic,
 }
 
+/// The kind of WebAssembly binary to generate.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum Target {
+    #[default]
+    /// A core module.
+    Module,
+    /// A component wrapping the core module, targeting the WIT world of the
+    /// [`WitOptions`] if there is one, with its WASI imports adapted to WASI
+    /// 0.2. Requires static linking.
+    Component,
+}
+
 /// Identifiers used by the generated module.
 // This is an internal detail of this module.
 pub(crate) struct Identifiers {
     canonical_abi_realloc: FunctionId,
     eval_bytecode: Option<FunctionId>,
     invoke: FunctionId,
+    invoke_with_modules: Option<FunctionId>,
+    invoke_with_args: Option<FunctionId>,
+    set_source_map: Option<FunctionId>,
     memory: MemoryId,
 }
 
@@ -120,23 +168,38 @@ impl Identifiers {
         canonical_abi_realloc: FunctionId,
         eval_bytecode: Option<FunctionId>,
         invoke: FunctionId,
+        invoke_with_modules: Option<FunctionId>,
+        invoke_with_args: Option<FunctionId>,
+        set_source_map: Option<FunctionId>,
         memory: MemoryId,
     ) -> Self {
         Self {
             canonical_abi_realloc,
             eval_bytecode,
             invoke,
+            invoke_with_modules,
+            invoke_with_args,
+            set_source_map,
             memory,
         }
     }
 }
 
+/// The name of the module evaluating the JS source code, unless it's named
+/// with [`Generator::module_name`].
+const DEFAULT_MODULE_NAME: &str = "function.mjs";
+
 /// Helper struct to keep track of bytecode metadata.
 // This is an internal detail of this module.
 pub(crate) struct BytecodeMetadata {
     ptr: LocalId,
     len: i32,
     data_section: DataId,
+    /// The module table of the bytecode of the modules that can be
+    /// imported, if any.
+    modules: Option<Box<BytecodeMetadata>>,
+    /// The compressed source map of the JS source code, if any.
+    source_map: Option<Box<BytecodeMetadata>>,
 }
 
 impl BytecodeMetadata {
@@ -145,6 +208,8 @@ impl BytecodeMetadata {
             ptr,
             len,
             data_section,
+            modules: None,
+            source_map: None,
         }
     }
 }
@@ -156,12 +221,30 @@ pub struct Generator {
     pub(crate) plugin: plugin::Plugin,
     /// What kind of linking to use when generating a module.
     pub(crate) linking: LinkingKind,
+    /// Whether to generate a core module or a component.
+    pub(crate) target: Target,
     /// Whether to embed the compressed JS source in the generated module.
     pub(crate) source_compression: bool,
+    /// Whether compilation errors include the offending source line.
+    pub(crate) pretty_errors: bool,
+    /// Whether to minify the JS source code before compiling it.
+    pub(crate) minify: bool,
+    /// Whether the plugin sees fixed clocks and randomness while it's
+    /// pre-initialized.
+    pub(crate) deterministic: bool,
+    /// The name of the module evaluating the JS source code, if it isn't
+    /// `function.mjs`.
+    pub(crate) module_name: Option<String>,
+    /// The source map of the JS source code, as JSON.
+    pub(crate) input_source_map: Option<Vec<u8>>,
+    /// The compressed source map embedded in the module being generated.
+    embedded_source_map: Option<Vec<u8>>,
     /// WIT options for code generation.
     pub(crate) wit_opts: wit::WitOptions,
     /// JavaScript function exports.
     pub(crate) function_exports: exports::Exports,
+    /// Additional JavaScript modules the JS source code can import, by name.
+    pub(crate) modules: Vec<(String, js::JS)>,
     /// The kind of plugin a generator will link.
     plugin_kind: plugin::PluginKind,
     /// An optional JS runtime config provided as JSON bytes.
@@ -185,18 +268,85 @@ impl Generator {
         self
     }
 
+    /// Set the kind of binary to generate (default: [`Target::Module`])
+    pub fn target(&mut self, target: Target) -> &mut Self {
+        self.target = target;
+        self
+    }
+
     /// Set if JS source compression is enabled (default: false).
     pub fn source_compression(&mut self, source_compression: bool) -> &mut Self {
         self.source_compression = source_compression;
         self
     }
 
+    /// Set if compilation errors include the offending source line and a
+    /// caret under the error column (default: false).
+    pub fn pretty_errors(&mut self, pretty_errors: bool) -> &mut Self {
+        self.pretty_errors = pretty_errors;
+        self
+    }
+
+    /// Set if the JS source code, and the modules it can import, are minified
+    /// before being compiled and embedded in the generated module
+    /// (default: false).
+    pub fn minify(&mut self, minify: bool) -> &mut Self {
+        self.minify = minify;
+        self
+    }
+
+    /// Set if the plugin sees clocks stopped at the Unix epoch and a fixed
+    /// sequence of random bytes while it's pre-initialized, so generating a
+    /// module from the same inputs produces identical bytes (default: false).
+    ///
+    /// Only affects statically linked modules, dynamically linked modules
+    /// don't embed a pre-initialized plugin.
+    pub fn deterministic(&mut self, deterministic: bool) -> &mut Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// Set the name of the module evaluating the JS source code, which names
+    /// it in stack traces and errors (default: `function.mjs`).
+    ///
+    /// Requires a plugin exporting `compile_src_named`, or
+    /// `compile_src_with_modules` when the JS source code imports modules.
+    pub fn module_name(&mut self, name: impl Into<String>) -> &mut Self {
+        self.module_name = Some(name.into());
+        self
+    }
+
+    /// Set the source map, as JSON, from the JS source code to the sources
+    /// it was generated from, like a bundle to its TypeScript sources
+    /// (default: none).
+    ///
+    /// The map is embedded compressed in the generated module, and the
+    /// plugin remaps the `function.mjs` locations of the errors it reports
+    /// with it. Without it, a source map inlined in the JS source code as a
+    /// `//# sourceMappingURL=data:` URL is used if any.
+    pub fn input_source_map(&mut self, source_map: Vec<u8>) -> &mut Self {
+        self.input_source_map = Some(source_map);
+        self
+    }
+
     /// Set the wit options. (default: Empty [`WitOptions`])
     pub fn wit_opts(&mut self, wit_opts: wit::WitOptions) -> &mut Self {
         self.wit_opts = wit_opts;
         self
     }
 
+    /// Add a JS module the JS source code can import, statically or with
+    /// `import()`, with the given name (default: none).
+    ///
+    /// Import specifiers starting with `./` or `../` are resolved against the
+    /// name of the importing module, where the JS source code is named
+    /// after [`Generator::module_name`]. Requires a plugin exporting `compile_src_with_modules`
+    /// and `invoke_with_modules`.
+    pub fn module(&mut self, name: impl Into<String>, js: js::JS) -> &mut Self {
+        self.modules.push((name.into(), js));
+        self
+    }
+
     #[cfg(feature = "plugin_internal")]
     /// Set true if linking with a default plugin module.
     pub fn linking_default_plugin(&mut self, value: bool) -> &mut Self {
@@ -245,6 +395,7 @@ impl Generator {
                 STDIN_PIPE
                     .set(MemoryInputPipe::new(self.js_runtime_config.clone()))
                     .unwrap();
+                let deterministic = self.deterministic;
                 let wasm = Wizer::new()
                     .init_func("initialize_runtime")
                     .make_linker(Some(Rc::new(move |engine| {
@@ -254,13 +405,23 @@ impl Generator {
                                 // The underlying buffer backing the pipe is an Arc
                                 // so the cloning should be fast.
                                 let config = STDIN_PIPE.get().unwrap().clone();
-                                cx.wasi_ctx = Some(
-                                    WasiCtxBuilder::new()
-                                        .stdin(config)
-                                        .inherit_stdout()
-                                        .inherit_stderr()
-                                        .build_p1(),
-                                );
+                                let mut builder = WasiCtxBuilder::new();
+                                // Anything the plugin writes to stdout goes to
+                                // stderr, so it can't corrupt a module the
+                                // caller streams to stdout.
+                                builder
+                                    .stdin(config)
+                                    .stdout(wasmtime_wasi::stderr())
+                                    .inherit_stderr();
+                                if deterministic {
+                                    builder
+                                        .wall_clock(EpochClock)
+                                        .monotonic_clock(EpochClock)
+                                        .secure_random(Deterministic::new(vec![0]))
+                                        .insecure_random(Deterministic::new(vec![0]))
+                                        .insecure_random_seed(0);
+                                }
+                                cx.wasi_ctx = Some(builder.build_p1());
                             }
                             cx.wasi_ctx.as_mut().unwrap()
                         })?;
@@ -282,6 +443,33 @@ impl Generator {
                 let canonical_abi_realloc_fn = module.exports.get_func("canonical_abi_realloc")?;
                 let eval_bytecode = module.exports.get_func("eval_bytecode").ok();
                 let invoke = module.exports.get_func("invoke")?;
+                let invoke_with_modules = if self.modules.is_empty() {
+                    None
+                } else {
+                    Some(
+                        module
+                            .exports
+                            .get_func("invoke_with_modules")
+                            .context("The plugin does not support importing modules")?,
+                    )
+                };
+                let invoke_with_args = if self.has_exports_with_args() {
+                    Some(module.exports.get_func("invoke_with_args").context(
+                        "The plugin does not support exported functions with parameters or results",
+                    )?)
+                } else {
+                    None
+                };
+                let set_source_map = if self.embedded_source_map.is_some() {
+                    Some(
+                        module
+                            .exports
+                            .get_func("set_source_map")
+                            .context("The plugin does not support source maps")?,
+                    )
+                } else {
+                    None
+                };
                 let ExportItem::Memory(memory) = module
                     .exports
                     .iter()
@@ -295,6 +483,9 @@ impl Generator {
                     canonical_abi_realloc_fn,
                     eval_bytecode,
                     invoke,
+                    invoke_with_modules,
+                    invoke_with_args,
+                    set_source_map,
                     memory,
                 ))
             }
@@ -305,6 +496,17 @@ impl Generator {
                 // to determine the import_namespace.
                 let import_namespace = self.plugin_kind.import_namespace(&self.plugin)?;
 
+                // Record the plugin the module links against so running it
+                // with an incompatible plugin can be diagnosed.
+                let linked_plugin = LinkedPlugin {
+                    namespace: import_namespace.clone(),
+                    abi_version: self.plugin.abi_version()?,
+                };
+                module.customs.add(RawCustomSection {
+                    name: plugin::LINKED_PLUGIN_SECTION.to_string(),
+                    data: linked_plugin.encode(),
+                });
+
                 let canonical_abi_realloc_type = module.types.add(
                     &[ValType::I32, ValType::I32, ValType::I32, ValType::I32],
                     &[ValType::I32],
@@ -340,6 +542,49 @@ impl Generator {
                 let (invoke_fn_id, _) =
                     module.add_import_func(&import_namespace, "invoke", invoke_type);
 
+                let invoke_with_modules_fn_id = if self.modules.is_empty() {
+                    None
+                } else {
+                    let invoke_with_modules_type = module.types.add(&[ValType::I32; 6], &[]);
+                    let (invoke_with_modules_fn_id, _) = module.add_import_func(
+                        &import_namespace,
+                        "invoke_with_modules",
+                        invoke_with_modules_type,
+                    );
+                    Some(invoke_with_modules_fn_id)
+                };
+
+                let invoke_with_args_fn_id = if self.has_exports_with_args() {
+                    if matches!(self.plugin_kind, plugin::PluginKind::V2) {
+                        bail!("Exported functions with parameters or results are not supported by the v2 plugin");
+                    }
+                    let invoke_with_args_type =
+                        module.types.add(&[ValType::I32; 8], &[ValType::I32]);
+                    let (invoke_with_args_fn_id, _) = module.add_import_func(
+                        &import_namespace,
+                        "invoke_with_args",
+                        invoke_with_args_type,
+                    );
+                    Some(invoke_with_args_fn_id)
+                } else {
+                    None
+                };
+
+                let set_source_map_fn_id = if self.embedded_source_map.is_some() {
+                    if matches!(self.plugin_kind, plugin::PluginKind::V2) {
+                        bail!("Source maps are not supported by the v2 plugin");
+                    }
+                    let set_source_map_type = module.types.add(&[ValType::I32; 2], &[]);
+                    let (set_source_map_fn_id, _) = module.add_import_func(
+                        &import_namespace,
+                        "set_source_map",
+                        set_source_map_type,
+                    );
+                    Some(set_source_map_fn_id)
+                } else {
+                    None
+                };
+
                 let (memory_id, _) = module.add_import_memory(
                     &import_namespace,
                     "memory",
@@ -354,12 +599,75 @@ impl Generator {
                     canonical_abi_realloc_fn_id,
                     eval_bytecode_fn_id,
                     invoke_fn_id,
+                    invoke_with_modules_fn_id,
+                    invoke_with_args_fn_id,
+                    set_source_map_fn_id,
                     memory_id,
                 ))
             }
         }
     }
 
+    /// Compiles the JS source code, and the modules it can import into a
+    /// module table of bytecode if there are any.
+    fn compile(&self, js: &js::JS) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+        if self.modules.is_empty() {
+            let bytecode = js.compile(
+                &self.plugin,
+                self.module_name.as_deref(),
+                self.pretty_errors,
+            )?;
+            return Ok((bytecode, None));
+        }
+
+        let minified;
+        let modules = if self.minify {
+            minified = self
+                .modules
+                .iter()
+                .map(|(name, js)| {
+                    let js = js
+                        .minify()
+                        .with_context(|| format!("Failed to minify module {name}"))?;
+                    Ok((name.clone(), js))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            &minified
+        } else {
+            &self.modules
+        };
+
+        let module_sources = bytecode::encode_module_table(
+            modules
+                .iter()
+                .map(|(name, js)| (name.as_str(), js.as_bytes())),
+        )?;
+        let bytecode = js.compile_module(
+            &self.plugin,
+            self.module_name.as_deref(),
+            &module_sources,
+            self.pretty_errors,
+        )?;
+        let mut compiled = vec![];
+        for (name, js) in modules {
+            let bytecode = js
+                .compile_module(
+                    &self.plugin,
+                    Some(name),
+                    &module_sources,
+                    self.pretty_errors,
+                )
+                .with_context(|| format!("Failed to compile module {name}"))?;
+            compiled.push((name.as_str(), bytecode));
+        }
+        let modules = bytecode::encode_module_table(
+            compiled
+                .iter()
+                .map(|(name, bytecode)| (*name, bytecode.as_slice())),
+        )?;
+        Ok((bytecode, Some(modules)))
+    }
+
     /// Generate the main function.
     fn generate_main(
         &self,
@@ -367,13 +675,41 @@ impl Generator {
         js: &js::JS,
         imports: &Identifiers,
     ) -> Result<BytecodeMetadata> {
-        let bytecode = js.compile(&self.plugin)?;
+        let (bytecode, modules) = self.compile(js)?;
         let bytecode_len: i32 = bytecode.len().try_into()?;
         let bytecode_data = module.data.add(DataKind::Passive, bytecode);
+        let modules = match modules {
+            Some(modules) => {
+                let modules_len: i32 = modules.len().try_into()?;
+                let modules_data = module.data.add(DataKind::Passive, modules);
+                let modules_ptr_local = module.locals.add(ValType::I32);
+                Some(BytecodeMetadata::new(
+                    modules_ptr_local,
+                    modules_len,
+                    modules_data,
+                ))
+            }
+            None => None,
+        };
+        let source_map = match &self.embedded_source_map {
+            Some(source_map) => {
+                let source_map_len: i32 = source_map.len().try_into()?;
+                let source_map_data = module.data.add(DataKind::Passive, source_map.clone());
+                Some(BytecodeMetadata::new(
+                    module.locals.add(ValType::I32),
+                    source_map_len,
+                    source_map_data,
+                ))
+            }
+            None => None,
+        };
 
         let mut main = FunctionBuilder::new(&mut module.types, &[], &[]);
         let bytecode_ptr_local = module.locals.add(ValType::I32);
         let mut instructions = main.func_body();
+        if let Some(source_map) = &source_map {
+            Self::set_source_map(&mut instructions, imports, source_map);
+        }
         instructions
             // Allocate memory in plugin instance for bytecode array.
             .i32_const(0) // orig ptr
@@ -387,8 +723,31 @@ impl Generator {
             .i32_const(bytecode_len) // size to copy from data segment
             // top-2: dest addr, top-1: offset into source, top-0: size of memory region in bytes.
             .memory_init(imports.memory, bytecode_data);
+        if let Some(modules) = &modules {
+            // Copy the module table into allocated memory the same way.
+            instructions
+                .i32_const(0) // orig ptr
+                .i32_const(0) // orig size
+                .i32_const(1) // alignment
+                .i32_const(modules.len) // new size
+                .call(imports.canonical_abi_realloc)
+                .local_tee(modules.ptr)
+                .i32_const(0) // offset into data segment for mem.init
+                .i32_const(modules.len) // size to copy from data segment
+                .memory_init(imports.memory, modules.data_section);
+        }
         // Evaluate top level scope.
-        if let Some(eval_bytecode) = imports.eval_bytecode {
+        if let (Some(modules), Some(invoke_with_modules)) = (&modules, imports.invoke_with_modules)
+        {
+            instructions
+                .local_get(bytecode_ptr_local) // ptr to bytecode
+                .i32_const(bytecode_len)
+                .i32_const(0) // set function name ptr to null
+                .i32_const(0) // set function name len to 0
+                .local_get(modules.ptr) // ptr to module table
+                .i32_const(modules.len)
+                .call(invoke_with_modules);
+        } else if let Some(eval_bytecode) = imports.eval_bytecode {
             instructions
                 .local_get(bytecode_ptr_local) // ptr to bytecode
                 .i32_const(bytecode_len)
@@ -412,11 +771,36 @@ impl Generator {
         let main = main.finish(vec![], &mut module.funcs);
 
         module.exports.add("_start", main);
-        Ok(BytecodeMetadata::new(
-            bytecode_ptr_local,
-            bytecode_len,
-            bytecode_data,
-        ))
+        let mut bc_metadata =
+            BytecodeMetadata::new(bytecode_ptr_local, bytecode_len, bytecode_data);
+        bc_metadata.modules = modules.map(Box::new);
+        bc_metadata.source_map = source_map.map(Box::new);
+        Ok(bc_metadata)
+    }
+
+    /// Emits a call passing the embedded source map to the plugin, before
+    /// the bytecode is evaluated.
+    fn set_source_map(
+        instructions: &mut InstrSeqBuilder,
+        identifiers: &Identifiers,
+        source_map: &BytecodeMetadata,
+    ) {
+        let set_source_map = identifiers
+            .set_source_map
+            .expect("set_source_map should be resolved when embedding a source map");
+        instructions
+            .i32_const(0) // orig ptr
+            .i32_const(0) // orig len
+            .i32_const(1) // alignment
+            .i32_const(source_map.len) // new size
+            .call(identifiers.canonical_abi_realloc)
+            .local_tee(source_map.ptr)
+            .i32_const(0) // offset into data segment
+            .i32_const(source_map.len) // size to copy
+            .memory_init(identifiers.memory, source_map.data_section)
+            .local_get(source_map.ptr)
+            .i32_const(source_map.len)
+            .call(set_source_map);
     }
 
     /// Generate function exports.
@@ -428,15 +812,25 @@ impl Generator {
     ) -> Result<()> {
         if !self.function_exports.is_empty() {
             let fn_name_ptr_local = module.locals.add(ValType::I32);
+            let signature_ptr_local = module.locals.add(ValType::I32);
+            let args_ptr_local = module.locals.add(ValType::I32);
             for export in &self.function_exports {
                 // For each JS function export, add an export that copies the name of the function into memory and invokes it.
                 let js_export_bytes = export.js.as_bytes();
                 let js_export_len: i32 = js_export_bytes.len().try_into().unwrap();
                 let fn_name_data = module.data.add(DataKind::Passive, js_export_bytes.to_vec());
 
-                let mut export_fn = FunctionBuilder::new(&mut module.types, &[], &[]);
-                export_fn
-                    .func_body()
+                let params = export.signature.flat_params();
+                let results = export.signature.flat_results();
+                let arg_locals: Vec<LocalId> =
+                    params.iter().map(|ty| module.locals.add(*ty)).collect();
+
+                let mut export_fn = FunctionBuilder::new(&mut module.types, &params, &results);
+                let mut instructions = export_fn.func_body();
+                if let Some(source_map) = &bc_metadata.source_map {
+                    Self::set_source_map(&mut instructions, identifiers, source_map);
+                }
+                instructions
                     // Copy bytecode.
                     .i32_const(0) // orig ptr
                     .i32_const(0) // orig len
@@ -458,20 +852,159 @@ impl Generator {
                     .i32_const(0) // offset into data segment
                     .i32_const(js_export_len) // size to copy
                     .memory_init(identifiers.memory, fn_name_data) // copy fn name into allocated memory
-                    .data_drop(fn_name_data)
-                    // Call invoke.
-                    .local_get(bc_metadata.ptr)
-                    .i32_const(bc_metadata.len)
-                    .local_get(fn_name_ptr_local)
-                    .i32_const(js_export_len)
-                    .call(identifiers.invoke);
-                let export_fn = export_fn.finish(vec![], &mut module.funcs);
+                    .data_drop(fn_name_data);
+                match (
+                    &bc_metadata.modules,
+                    identifiers.invoke_with_modules,
+                    identifiers.invoke_with_args,
+                ) {
+                    (_, _, Some(invoke_with_args)) if !export.signature.is_empty() => {
+                        let signature = export.signature.to_string().into_bytes();
+                        let signature_len: i32 = signature.len().try_into()?;
+                        let signature_data = module.data.add(DataKind::Passive, signature);
+                        let args_len: i32 = (arg_locals.len() * 8).try_into()?;
+                        instructions
+                            // Copy signature.
+                            .i32_const(0) // orig ptr
+                            .i32_const(0) // orig len
+                            .i32_const(1) // alignment
+                            .i32_const(signature_len) // new size
+                            .call(identifiers.canonical_abi_realloc)
+                            .local_tee(signature_ptr_local)
+                            .i32_const(0) // offset into data segment
+                            .i32_const(signature_len) // size to copy
+                            .memory_init(identifiers.memory, signature_data) // copy signature into allocated memory
+                            .data_drop(signature_data);
+                        if !arg_locals.is_empty() {
+                            // Spill each argument into an 8 byte slot.
+                            instructions
+                                .i32_const(0) // orig ptr
+                                .i32_const(0) // orig len
+                                .i32_const(8) // alignment
+                                .i32_const(args_len) // new size
+                                .call(identifiers.canonical_abi_realloc)
+                                .local_set(args_ptr_local);
+                            for (i, (local, ty)) in arg_locals.iter().zip(&params).enumerate() {
+                                instructions.local_get(args_ptr_local).local_get(*local);
+                                match ty {
+                                    ValType::I32 => {
+                                        instructions.unop(UnaryOp::I64ExtendUI32);
+                                    }
+                                    ValType::F32 => {
+                                        instructions
+                                            .unop(UnaryOp::I32ReinterpretF32)
+                                            .unop(UnaryOp::I64ExtendUI32);
+                                    }
+                                    ValType::F64 => {
+                                        instructions.unop(UnaryOp::I64ReinterpretF64);
+                                    }
+                                    _ => {}
+                                }
+                                instructions.store(
+                                    identifiers.memory,
+                                    StoreKind::I64 { atomic: false },
+                                    MemArg {
+                                        align: 8,
+                                        offset: (i * 8).try_into()?,
+                                    },
+                                );
+                            }
+                        } else {
+                            instructions.i32_const(0).local_set(args_ptr_local);
+                        }
+                        instructions
+                            // Call invoke_with_args.
+                            .local_get(bc_metadata.ptr)
+                            .i32_const(bc_metadata.len)
+                            .local_get(fn_name_ptr_local)
+                            .i32_const(js_export_len)
+                            .local_get(signature_ptr_local)
+                            .i32_const(signature_len)
+                            .local_get(args_ptr_local)
+                            .i32_const(args_len)
+                            .call(invoke_with_args);
+                        match results[..] {
+                            [] => {
+                                instructions.drop();
+                            }
+                            // The pointer to the result stored in memory.
+                            [_] if export.signature.result_in_memory() => {}
+                            // Load the result from its 8 byte slot.
+                            [ty] => {
+                                let kind = match ty {
+                                    ValType::I64 => LoadKind::I64 { atomic: false },
+                                    ValType::F32 => LoadKind::F32,
+                                    ValType::F64 => LoadKind::F64,
+                                    _ => LoadKind::I32 { atomic: false },
+                                };
+                                instructions.load(
+                                    identifiers.memory,
+                                    kind,
+                                    MemArg {
+                                        align: 8,
+                                        offset: 0,
+                                    },
+                                );
+                            }
+                            _ => unreachable!("results are flattened to at most one value"),
+                        }
+                    }
+                    (Some(modules), Some(invoke_with_modules), _) => {
+                        instructions
+                            // Copy module table.
+                            .i32_const(0) // orig ptr
+                            .i32_const(0) // orig len
+                            .i32_const(1) // alignment
+                            .i32_const(modules.len) // size to copy
+                            .call(identifiers.canonical_abi_realloc)
+                            .local_tee(modules.ptr)
+                            .i32_const(0) // offset into data segment
+                            .i32_const(modules.len) // size to copy
+                            .memory_init(identifiers.memory, modules.data_section) // copy module table into allocated memory
+                            .data_drop(modules.data_section)
+                            // Call invoke_with_modules.
+                            .local_get(bc_metadata.ptr)
+                            .i32_const(bc_metadata.len)
+                            .local_get(fn_name_ptr_local)
+                            .i32_const(js_export_len)
+                            .local_get(modules.ptr)
+                            .i32_const(modules.len)
+                            .call(invoke_with_modules);
+                    }
+                    _ => {
+                        instructions
+                            // Call invoke.
+                            .local_get(bc_metadata.ptr)
+                            .i32_const(bc_metadata.len)
+                            .local_get(fn_name_ptr_local)
+                            .i32_const(js_export_len)
+                            .call(identifiers.invoke);
+                    }
+                }
+                let export_fn = export_fn.finish(arg_locals, &mut module.funcs);
                 module.exports.add(&export.wit, export_fn);
             }
         }
+        if self.has_exports_with_args() {
+            // Callers allocate the strings they pass with `cabi_realloc` and
+            // read the results from memory, following the canonical ABI.
+            module
+                .exports
+                .add("cabi_realloc", identifiers.canonical_abi_realloc);
+            if let LinkingKind::Dynamic = self.linking {
+                module.exports.add("memory", identifiers.memory);
+            }
+        }
         Ok(())
     }
 
+    /// Whether some exported functions have parameters or a result.
+    fn has_exports_with_args(&self) -> bool {
+        self.function_exports
+            .iter()
+            .any(|export| !export.signature.is_empty())
+    }
+
     /// Clean-up the generated Wasm.
     fn postprocess(&self, module: &mut Module) -> Result<Vec<u8>> {
         match self.linking {
@@ -489,6 +1022,26 @@ impl Generator {
 
                 module.exports.remove("invoke")?;
                 module.exports.remove("compile_src")?;
+                // Older plugins don't export free_bytecode.
+                if module.exports.get_func("free_bytecode").is_ok() {
+                    module.exports.remove("free_bytecode")?;
+                }
+                // Nor the exports to import modules or the ABI version.
+                for export in [
+                    "compile_src_named",
+                    "compile_src_with_modules",
+                    "invoke_with_modules",
+                    "invoke_with_args",
+                    "plugin_abi_version",
+                    "set_source_map",
+                ] {
+                    if module.exports.get_func(export).is_ok() {
+                        module.exports.remove(export)?;
+                    }
+                }
+                if let Target::Component = self.target {
+                    component::retain_world_exports(module, &self.function_exports);
+                }
 
                 // Run wasm-opt to optimize.
                 let tempdir = tempfile::tempdir()?;
@@ -567,12 +1120,44 @@ impl Generator {
     //  )
     /// Generate a Wasm module which will run the provided JS source code.
     pub fn generate(&mut self, js: &js::JS) -> Result<Vec<u8>> {
+        if !self.modules.is_empty() && matches!(self.plugin_kind, plugin::PluginKind::V2) {
+            bail!("Importing modules is not supported by the v2 plugin");
+        }
+        if matches!(
+            (&self.target, &self.linking),
+            (Target::Component, LinkingKind::Dynamic)
+        ) {
+            bail!("Components can only be generated from statically linked modules");
+        }
+
+        let source_map = match &self.input_source_map {
+            Some(json) => Some(source_map::parse(json)?),
+            None => source_map::inline(js)?,
+        };
+        if source_map.is_some() && self.minify {
+            bail!("Source maps can't be embedded when minifying the JS source code");
+        }
+        self.embedded_source_map = source_map
+            .map(|source_map| source_map::compress(source_map, self.evaluated_module_name()))
+            .transpose()?;
+
+        let minified;
+        let js = if self.minify {
+            minified = js.minify()?;
+            &minified
+        } else {
+            js
+        };
+
         if self.wit_opts.defined() {
             self.function_exports = exports::process_exports(
                 js,
                 self.wit_opts.unwrap_path(),
                 self.wit_opts.unwrap_world(),
             )?;
+            if !self.modules.is_empty() && self.has_exports_with_args() {
+                bail!("Exported functions with parameters or results can't be used with modules");
+            }
         }
 
         let mut module = self.generate_initial_module()?;
@@ -593,6 +1178,40 @@ impl Generator {
         }
 
         let wasm = self.postprocess(&mut module)?;
-        Ok(wasm)
+        match self.target {
+            Target::Module => Ok(wasm),
+            Target::Component => component::encode(&wasm, &self.wit_opts),
+        }
+    }
+
+    /// Generate a source map, as JSON, from the JS source code embedded in
+    /// the module generated for `js` to `js` itself, named `source`.
+    ///
+    /// The embedded code is named after [`Generator::module_name`] in stack
+    /// traces and errors. It differs from `js` when [`Generator::minify`] is
+    /// set.
+    pub fn source_map(&self, js: &js::JS, source: &str) -> Result<Vec<u8>> {
+        let mut source_map = vec![];
+        js.source_map(self.evaluated_module_name(), source, self.minify)?
+            .to_writer(&mut source_map)?;
+        Ok(source_map)
+    }
+
+    /// The name of the module evaluating the JS source code.
+    fn evaluated_module_name(&self) -> &str {
+        self.module_name.as_deref().unwrap_or(DEFAULT_MODULE_NAME)
+    }
+
+    /// Compiles `js` to the QuickJS bytecode the module generated for it
+    /// evaluates.
+    pub fn bytecode(&self, js: &js::JS) -> Result<Vec<u8>> {
+        let minified;
+        let js = if self.minify {
+            minified = js.minify()?;
+            &minified
+        } else {
+            js
+        };
+        Ok(self.compile(js)?.0)
     }
 }
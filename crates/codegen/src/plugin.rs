@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 use std::{borrow::Cow, fs, path::Path, str};
 
 use super::bytecode;
@@ -19,27 +19,79 @@ impl PluginKind {
     pub(crate) fn import_namespace(self, plugin: &Plugin) -> Result<String> {
         match self {
             PluginKind::V2 => Ok("javy_quickjs_provider_v2".to_string()),
-            PluginKind::User | PluginKind::Default => {
-                // The import namespace to use for this plugin.
-                let module = walrus::Module::from_buffer(plugin.as_bytes())?;
-                let import_namespace: std::borrow::Cow<'_, [u8]> = module
-                    .customs
-                    .iter()
-                    .find_map(|(_, section)| {
-                        if section.name() == "import_namespace" {
-                            Some(section)
-                        } else {
-                            None
-                        }
-                    })
-                    .ok_or_else(|| anyhow!("Plugin is missing import_namespace custom section"))?
-                    .data(&Default::default()); // Argument is required but not actually used for anything.
-                Ok(str::from_utf8(&import_namespace)?.to_string())
-            }
+            PluginKind::User | PluginKind::Default => plugin.import_namespace(),
         }
     }
 }
 
+/// The name of the custom section recording the plugin a dynamically linked
+/// module was generated with.
+pub(crate) const LINKED_PLUGIN_SECTION: &str = "javy_linked_plugin";
+
+/// The import namespace and ABI version of the plugin a dynamically linked
+/// module was generated with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LinkedPlugin {
+    /// The import namespace of the plugin.
+    pub namespace: String,
+    /// The ABI version of the plugin.
+    pub abi_version: u32,
+}
+
+impl LinkedPlugin {
+    /// Reads the plugin a dynamically linked module was generated with.
+    ///
+    /// Returns `None` for statically linked modules and for modules generated
+    /// by versions of Javy that didn't record it.
+    pub fn from_module(wasm: &[u8]) -> Result<Option<Self>> {
+        let Some(section) = custom_section(wasm, LINKED_PLUGIN_SECTION)? else {
+            return Ok(None);
+        };
+        if section.len() < 4 {
+            bail!("Invalid {LINKED_PLUGIN_SECTION} custom section");
+        }
+        let (version, namespace) = section.split_at(4);
+        Ok(Some(LinkedPlugin {
+            namespace: str::from_utf8(namespace)?.to_string(),
+            abi_version: u32::from_le_bytes(version.try_into()?),
+        }))
+    }
+
+    /// Encodes the contents of the custom section recording this plugin.
+    pub(crate) fn encode(&self) -> Vec<u8> {
+        let mut section = self.abi_version.to_le_bytes().to_vec();
+        section.extend_from_slice(self.namespace.as_bytes());
+        section
+    }
+
+    /// Ensures `plugin` provides the import namespace and ABI version the
+    /// module was generated with.
+    pub fn ensure_provided_by(&self, plugin: &Plugin) -> Result<()> {
+        let namespace = plugin.import_namespace()?;
+        let abi_version = plugin.abi_version()?;
+        if namespace != self.namespace || abi_version != self.abi_version {
+            bail!(
+                "module was linked against plugin namespace {} version {}, provided plugin exposes {namespace} version {abi_version}",
+                self.namespace,
+                self.abi_version,
+            );
+        }
+        Ok(())
+    }
+}
+
+/// Returns the contents of the custom section named `name` of a module.
+fn custom_section(wasm: &[u8], name: &str) -> Result<Option<Vec<u8>>> {
+    let module = walrus::Module::from_buffer(wasm)?;
+    let section = module
+        .customs
+        .iter()
+        .find(|(_, section)| section.name() == name)
+        // Argument is required but not actually used for anything.
+        .map(|(_, section)| section.data(&Default::default()).into_owned());
+    Ok(section)
+}
+
 /// A Javy plugin.
 #[derive(Clone, Debug, Default)]
 pub struct Plugin {
@@ -63,8 +115,139 @@ impl Plugin {
         &self.bytes
     }
 
-    /// Generate valid QuickJS bytecode from Javascript source code.
-    pub(crate) fn compile_source(&self, js_source_code: &[u8]) -> Result<Vec<u8>> {
-        bytecode::compile_source(self.as_bytes(), js_source_code)
+    /// Returns the import namespace of the [`Plugin`].
+    pub fn import_namespace(&self) -> Result<String> {
+        let namespace = custom_section(self.as_bytes(), "import_namespace")?
+            .ok_or_else(|| anyhow!("Plugin is missing import_namespace custom section"))?;
+        Ok(String::from_utf8(namespace)?)
+    }
+
+    /// Returns the ABI version of the [`Plugin`], which is `0` for plugins
+    /// that don't declare one.
+    pub fn abi_version(&self) -> Result<u32> {
+        let Some(version) = custom_section(self.as_bytes(), "abi_version")? else {
+            return Ok(0);
+        };
+        let version = version
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow!("Invalid abi_version custom section"))?;
+        Ok(u32::from_le_bytes(version))
+    }
+
+    /// Generate valid QuickJS bytecode from Javascript source code, for a
+    /// module with name or `function.mjs` if there's no name.
+    pub(crate) fn compile_source(
+        &self,
+        name: Option<&str>,
+        js_source_code: &[u8],
+        pretty_errors: bool,
+    ) -> Result<Vec<u8>> {
+        bytecode::compile_source(self.as_bytes(), name, js_source_code, pretty_errors)
+    }
+
+    /// Generate valid QuickJS bytecode from the Javascript source code of a
+    /// module, which can import the modules of a module table of source code.
+    pub(crate) fn compile_module_source(
+        &self,
+        name: Option<&str>,
+        js_source_code: &[u8],
+        module_sources: &[u8],
+        pretty_errors: bool,
+    ) -> Result<Vec<u8>> {
+        bytecode::compile_module_source(
+            self.as_bytes(),
+            name,
+            js_source_code,
+            module_sources,
+            pretty_errors,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use walrus::{Module, ModuleConfig, RawCustomSection};
+
+    use super::{LinkedPlugin, Plugin, LINKED_PLUGIN_SECTION};
+
+    fn module_with_sections(sections: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut module = Module::with_config(ModuleConfig::default());
+        for (name, data) in sections {
+            module.customs.add(RawCustomSection {
+                name: name.to_string(),
+                data: data.to_vec(),
+            });
+        }
+        module.emit_wasm()
+    }
+
+    fn plugin(namespace: &str, abi_version: Option<u32>) -> Plugin {
+        let version = abi_version.map(u32::to_le_bytes);
+        let mut sections = vec![("import_namespace", namespace.as_bytes())];
+        if let Some(version) = &version {
+            sections.push(("abi_version", version));
+        }
+        Plugin::new(module_with_sections(&sections).into())
+    }
+
+    #[test]
+    fn test_abi_version() -> Result<()> {
+        let plugin = plugin("test_plugin", Some(3));
+        assert_eq!("test_plugin", plugin.import_namespace()?);
+        assert_eq!(3, plugin.abi_version()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_abi_version_defaults_to_zero() -> Result<()> {
+        assert_eq!(0, plugin("test_plugin", None).abi_version()?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_linked_plugin_round_trip() -> Result<()> {
+        let linked = LinkedPlugin {
+            namespace: "test_plugin".to_string(),
+            abi_version: 1,
+        };
+        let wasm = module_with_sections(&[(LINKED_PLUGIN_SECTION, &linked.encode())]);
+        assert_eq!(Some(linked), LinkedPlugin::from_module(&wasm)?);
+        assert_eq!(None, LinkedPlugin::from_module(&module_with_sections(&[]))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_linked_plugin_provided_by_matching_plugin() -> Result<()> {
+        let linked = LinkedPlugin {
+            namespace: "test_plugin".to_string(),
+            abi_version: 1,
+        };
+        linked.ensure_provided_by(&plugin("test_plugin", Some(1)))
+    }
+
+    #[test]
+    fn test_linked_plugin_not_provided_by_mismatching_plugin() {
+        let linked = LinkedPlugin {
+            namespace: "test_plugin".to_string(),
+            abi_version: 1,
+        };
+
+        let err = linked
+            .ensure_provided_by(&plugin("test_plugin", Some(2)))
+            .unwrap_err();
+        assert_eq!(
+            "module was linked against plugin namespace test_plugin version 1, provided plugin exposes test_plugin version 2",
+            err.to_string()
+        );
+
+        let err = linked
+            .ensure_provided_by(&plugin("javy_quickjs_provider_v3", None))
+            .unwrap_err();
+        assert_eq!(
+            "module was linked against plugin namespace test_plugin version 1, provided plugin exposes javy_quickjs_provider_v3 version 0",
+            err.to_string()
+        );
     }
 }
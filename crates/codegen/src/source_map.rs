@@ -0,0 +1,42 @@
+//! Source maps of the JS source code to its original sources, embedded in
+//! generated modules so the plugin can remap the locations of stack traces.
+
+use anyhow::{bail, Context, Result};
+use flate2::{write::DeflateEncoder, Compression};
+use sourcemap::{DecodedMap, SourceMap};
+
+use crate::js::JS;
+
+/// Parses a source map given as JSON.
+pub(crate) fn parse(json: &[u8]) -> Result<SourceMap> {
+    SourceMap::from_slice(json).context("Failed to parse the input source map")
+}
+
+/// The source map inlined in the JS source code as a
+/// `//# sourceMappingURL=data:` URL, if any.
+pub(crate) fn inline(js: &JS) -> Result<Option<SourceMap>> {
+    let Some(reference) = sourcemap::locate_sourcemap_reference_slice(js.as_bytes())? else {
+        return Ok(None);
+    };
+    match reference
+        .get_embedded_sourcemap()
+        .context("Failed to decode the inline source map")?
+    {
+        Some(DecodedMap::Regular(source_map)) => Ok(Some(source_map)),
+        Some(_) => bail!("Only regular inline source maps are supported"),
+        None => Ok(None),
+    }
+}
+
+/// Encodes a source map the way the plugin expects it, which is deflate
+/// compressed JSON without the contents of the sources, with the name of the
+/// module evaluating the JS source code as its file.
+pub(crate) fn compress(mut source_map: SourceMap, module_name: &str) -> Result<Vec<u8>> {
+    source_map.set_file(Some(module_name));
+    for idx in 0..source_map.get_source_count() {
+        source_map.set_source_contents(idx, None);
+    }
+    let mut encoder = DeflateEncoder::new(vec![], Compression::best());
+    source_map.to_writer(&mut encoder)?;
+    Ok(encoder.finish()?)
+}
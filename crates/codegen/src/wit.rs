@@ -1,8 +1,12 @@
-use std::path::{Path, PathBuf};
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{bail, Result};
-
-use wit_parser::{Resolve, WorldItem};
+use convert_case::{Case, Casing};
+use walrus::ValType;
+use wit_parser::{Resolve, Results, Type, TypeDefKind, WorldItem};
 
 /// Options for using WIT in the code generation process.
 #[derive(Default, Clone, Debug, PartialEq)]
@@ -46,14 +50,189 @@ impl WitOptions {
     }
 }
 
-pub(crate) fn parse_exports(wit: impl AsRef<Path>, world: &str) -> Result<Vec<String>> {
+/// The WIT types supported in the parameters and results of exported
+/// functions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum WitType {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    F32,
+    F64,
+    Char,
+    String,
+    /// A record, with the names of its fields in camel case.
+    Record(Vec<(String, WitType)>),
+}
+
+impl WitType {
+    fn from_wit(resolve: &Resolve, ty: &Type) -> Result<Self> {
+        Ok(match ty {
+            Type::Bool => WitType::Bool,
+            Type::S8 => WitType::S8,
+            Type::U8 => WitType::U8,
+            Type::S16 => WitType::S16,
+            Type::U16 => WitType::U16,
+            Type::S32 => WitType::S32,
+            Type::U32 => WitType::U32,
+            Type::S64 => WitType::S64,
+            Type::U64 => WitType::U64,
+            Type::F32 => WitType::F32,
+            Type::F64 => WitType::F64,
+            Type::Char => WitType::Char,
+            Type::String => WitType::String,
+            Type::Id(id) => match &resolve.types[*id].kind {
+                TypeDefKind::Record(record) => WitType::Record(
+                    record
+                        .fields
+                        .iter()
+                        .map(|field| {
+                            let name = field.name.from_case(Case::Kebab).to_case(Case::Camel);
+                            Ok((name, WitType::from_wit(resolve, &field.ty)?))
+                        })
+                        .collect::<Result<_>>()?,
+                ),
+                TypeDefKind::Type(ty) => WitType::from_wit(resolve, ty)?,
+                kind => bail!(
+                    "Exported functions using {} types are not supported",
+                    kind.as_str()
+                ),
+            },
+        })
+    }
+
+    /// Appends the core Wasm types the type is flattened to by the canonical
+    /// ABI.
+    fn flatten(&self, flat: &mut Vec<ValType>) {
+        match self {
+            WitType::S64 | WitType::U64 => flat.push(ValType::I64),
+            WitType::F32 => flat.push(ValType::F32),
+            WitType::F64 => flat.push(ValType::F64),
+            WitType::String => flat.extend([ValType::I32, ValType::I32]),
+            WitType::Record(fields) => fields.iter().for_each(|(_, ty)| ty.flatten(flat)),
+            _ => flat.push(ValType::I32),
+        }
+    }
+}
+
+impl fmt::Display for WitType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WitType::Bool => f.write_str("bool"),
+            WitType::S8 => f.write_str("s8"),
+            WitType::U8 => f.write_str("u8"),
+            WitType::S16 => f.write_str("s16"),
+            WitType::U16 => f.write_str("u16"),
+            WitType::S32 => f.write_str("s32"),
+            WitType::U32 => f.write_str("u32"),
+            WitType::S64 => f.write_str("s64"),
+            WitType::U64 => f.write_str("u64"),
+            WitType::F32 => f.write_str("f32"),
+            WitType::F64 => f.write_str("f64"),
+            WitType::Char => f.write_str("char"),
+            WitType::String => f.write_str("string"),
+            WitType::Record(fields) => {
+                f.write_str("{")?;
+                for (i, (name, ty)) in fields.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{name}:{ty}")?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// The parameters and result of an exported function.
+///
+/// Displayed as the signature passed to the plugin's `invoke_with_args`, such
+/// as `(string,{name:string,age:u32})->string`.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct Signature {
+    pub params: Vec<WitType>,
+    pub result: Option<WitType>,
+}
+
+impl Signature {
+    /// The maximum number of core Wasm parameters a function can be
+    /// flattened to by the canonical ABI.
+    const MAX_FLAT_PARAMS: usize = 16;
+
+    /// Whether the function has no parameters and no result.
+    pub(crate) fn is_empty(&self) -> bool {
+        self.params.is_empty() && self.result.is_none()
+    }
+
+    /// The core Wasm parameters of the function.
+    pub(crate) fn flat_params(&self) -> Vec<ValType> {
+        let mut flat = vec![];
+        self.params.iter().for_each(|ty| ty.flatten(&mut flat));
+        flat
+    }
+
+    /// Whether the result is flattened to more than one value, in which case
+    /// it's stored in memory and the function returns a pointer to it.
+    pub(crate) fn result_in_memory(&self) -> bool {
+        let mut flat = vec![];
+        if let Some(ty) = &self.result {
+            ty.flatten(&mut flat);
+        }
+        flat.len() > 1
+    }
+
+    /// The core Wasm results of the function.
+    pub(crate) fn flat_results(&self) -> Vec<ValType> {
+        let mut flat = vec![];
+        match &self.result {
+            Some(_) if self.result_in_memory() => flat.push(ValType::I32),
+            Some(ty) => ty.flatten(&mut flat),
+            None => {}
+        }
+        flat
+    }
+}
+
+impl fmt::Display for Signature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("(")?;
+        for (i, ty) in self.params.iter().enumerate() {
+            if i > 0 {
+                f.write_str(",")?;
+            }
+            write!(f, "{ty}")?;
+        }
+        f.write_str(")")?;
+        if let Some(ty) = &self.result {
+            write!(f, "->{ty}")?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn parse_exports(
+    wit: impl AsRef<Path>,
+    world: &str,
+) -> Result<Vec<(String, Signature)>> {
     let mut resolve = Resolve::default();
     resolve.push_path(wit.as_ref())?;
     let (_, package_id) = resolve.package_names.first().unwrap();
     let world_id = resolve.select_world(&[*package_id], Some(world))?;
     let world = resolve.worlds.get(world_id).unwrap();
 
-    if !world.imports.is_empty() {
+    // Types used by the exported functions are imported by the world.
+    if world
+        .imports
+        .values()
+        .any(|import| !matches!(import, WorldItem::Type(_)))
+    {
         bail!("Imports in WIT file are not supported");
     }
     let mut exported_functions = vec![];
@@ -63,16 +242,78 @@ pub(crate) fn parse_exports(wit: impl AsRef<Path>, world: &str) -> Result<Vec<St
                 bail!("Exported interfaces are not supported")
             }
             WorldItem::Function(f) => {
-                if !f.params.is_empty() {
-                    bail!("Exported functions with parameters are not supported")
-                } else if f.results.len() != 0 {
-                    bail!("Exported functions with return values are not supported")
-                } else {
-                    exported_functions.push(f.name.clone())
+                let params = f
+                    .params
+                    .iter()
+                    .map(|(_, ty)| WitType::from_wit(&resolve, ty))
+                    .collect::<Result<Vec<_>>>()?;
+                let result = match &f.results {
+                    Results::Anon(ty) => Some(WitType::from_wit(&resolve, ty)?),
+                    Results::Named(results) => match &results[..] {
+                        [] => None,
+                        [(_, ty)] => Some(WitType::from_wit(&resolve, ty)?),
+                        _ => bail!("Exported functions with several results are not supported"),
+                    },
+                };
+                let signature = Signature { params, result };
+                if signature.flat_params().len() > Signature::MAX_FLAT_PARAMS {
+                    bail!(
+                        "Exported function {} has more than {} flattened parameters",
+                        f.name,
+                        Signature::MAX_FLAT_PARAMS
+                    );
                 }
+                exported_functions.push((f.name.clone(), signature))
             }
             WorldItem::Type(_) => bail!("Exported types are not supported"),
         }
     }
     Ok(exported_functions)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Signature, WitType};
+    use walrus::ValType;
+
+    #[test]
+    fn test_signature() {
+        let signature = Signature {
+            params: vec![
+                WitType::String,
+                WitType::Record(vec![
+                    ("firstName".to_string(), WitType::String),
+                    ("age".to_string(), WitType::U64),
+                ]),
+            ],
+            result: Some(WitType::F32),
+        };
+        assert_eq!(
+            "(string,{firstName:string,age:u64})->f32",
+            signature.to_string()
+        );
+        assert_eq!(
+            vec![
+                ValType::I32,
+                ValType::I32,
+                ValType::I32,
+                ValType::I32,
+                ValType::I64
+            ],
+            signature.flat_params()
+        );
+        assert_eq!(vec![ValType::F32], signature.flat_results());
+        assert!(!signature.result_in_memory());
+
+        let signature = Signature {
+            params: vec![],
+            result: Some(WitType::String),
+        };
+        assert_eq!("()->string", signature.to_string());
+        assert_eq!(vec![ValType::I32], signature.flat_results());
+        assert!(signature.result_in_memory());
+        assert!(!signature.is_empty());
+        assert!(Signature::default().is_empty());
+        assert_eq!("()", Signature::default().to_string());
+    }
+}
@@ -31,3 +31,27 @@ fn test_empty() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn test_pretty_errors() -> Result<()> {
+    let js = JS::from_file(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("tests")
+            .join("sample-scripts")
+            .join("syntax-error.js")
+            .as_path(),
+    )?;
+
+    let plugin = Plugin::new_from_path(
+        PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("default_plugin.wasm"),
+    )?;
+
+    let mut generator = Generator::new(plugin);
+    generator.linking(LinkingKind::Static).pretty_errors(true);
+
+    let err = generator.generate(&js).err().unwrap().to_string();
+    assert!(err.contains("2 |   let b = ;"), "{err}");
+    assert!(err.contains("  |           ^"), "{err}");
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+(function () {
+  // Guards the `AbortSignal` constructor, signals are created by
+  // `AbortController` and the static `AbortSignal` methods.
+  const signalKey = Symbol("AbortSignal");
+  // Whether the timers `AbortSignal.timeout` relies on are enabled. They're
+  // registered after this file is loaded, so `setTimeout` is looked up when
+  // it's called.
+  const timersEnabled = globalThis.__javy_abort_timers;
+
+  function domError(name, message) {
+    const error = new Error(message);
+    error.name = name;
+    return error;
+  }
+
+  function createSignal() {
+    return new AbortSignal(signalKey);
+  }
+
+  // Reports an error thrown by an abort listener without interrupting the
+  // other listeners.
+  function reportListenerError(error) {
+    if (typeof globalThis.reportError === "function") {
+      globalThis.reportError(error);
+    } else {
+      console.error(error);
+    }
+  }
+
+  function signalAbort(signal, reason) {
+    if (signal._aborted) {
+      return;
+    }
+    signal._aborted = true;
+    signal._reason =
+      reason === undefined
+        ? domError("AbortError", "This operation was aborted")
+        : reason;
+
+    // An aborted signal no longer follows its sources, so it's removed from
+    // them to avoid keeping it alive for as long as they are.
+    for (const source of signal._sources) {
+      source._dependents = source._dependents.filter((d) => d !== signal);
+    }
+    signal._sources = [];
+    const dependents = signal._dependents;
+    signal._dependents = [];
+
+    const event = { type: "abort", target: signal, currentTarget: signal };
+    if (typeof signal._onabort === "function") {
+      try {
+        signal._onabort.call(signal, event);
+      } catch (error) {
+        reportListenerError(error);
+      }
+    }
+    const listeners = signal._listeners;
+    signal._listeners = [];
+    for (const { listener } of listeners) {
+      try {
+        if (typeof listener === "function") {
+          listener.call(signal, event);
+        } else {
+          listener.handleEvent(event);
+        }
+      } catch (error) {
+        reportListenerError(error);
+      }
+    }
+
+    for (const dependent of dependents) {
+      signalAbort(dependent, signal._reason);
+    }
+  }
+
+  class AbortSignal {
+    constructor(key) {
+      if (key !== signalKey) {
+        throw new TypeError("Illegal constructor");
+      }
+      Object.defineProperties(this, {
+        _aborted: { value: false, writable: true },
+        _reason: { value: undefined, writable: true },
+        _onabort: { value: null, writable: true },
+        _listeners: { value: [], writable: true },
+        // The signals created by `AbortSignal.any` from this signal, and the
+        // signals this signal was created from.
+        _dependents: { value: [], writable: true },
+        _sources: { value: [], writable: true },
+      });
+    }
+
+    get aborted() {
+      return this._aborted;
+    }
+
+    get reason() {
+      return this._reason;
+    }
+
+    get onabort() {
+      return this._onabort;
+    }
+
+    set onabort(handler) {
+      this._onabort = typeof handler === "function" ? handler : null;
+    }
+
+    throwIfAborted() {
+      if (this._aborted) {
+        throw this._reason;
+      }
+    }
+
+    addEventListener(type, listener) {
+      if (type !== "abort" || listener === null || listener === undefined) {
+        return;
+      }
+      if (this._listeners.some((l) => l.listener === listener)) {
+        return;
+      }
+      // Listeners run at most once, since a signal aborts at most once.
+      this._listeners.push({ listener });
+    }
+
+    removeEventListener(type, listener) {
+      if (type !== "abort") {
+        return;
+      }
+      this._listeners = this._listeners.filter((l) => l.listener !== listener);
+    }
+
+    static abort(reason) {
+      const signal = createSignal();
+      signalAbort(signal, reason);
+      return signal;
+    }
+
+    static timeout(milliseconds) {
+      if (!timersEnabled) {
+        throw new TypeError("AbortSignal.timeout requires timers to be enabled");
+      }
+      const signal = createSignal();
+      setTimeout(() => {
+        signalAbort(signal, domError("TimeoutError", "The operation timed out."));
+      }, milliseconds);
+      return signal;
+    }
+
+    static any(signals) {
+      if (signals === null || typeof signals !== "object" ||
+        typeof signals[Symbol.iterator] !== "function") {
+        throw new TypeError(
+          "Failed to execute 'any' on 'AbortSignal': The provided value cannot be converted to a sequence.",
+        );
+      }
+      const sources = Array.from(signals);
+      for (const source of sources) {
+        if (!(source instanceof AbortSignal)) {
+          throw new TypeError(
+            "Failed to execute 'any' on 'AbortSignal': Failed to convert value to 'AbortSignal'.",
+          );
+        }
+      }
+
+      const signal = createSignal();
+      const aborted = sources.find((source) => source.aborted);
+      if (aborted !== undefined) {
+        signalAbort(signal, aborted.reason);
+        return signal;
+      }
+      for (const source of new Set(sources)) {
+        source._dependents.push(signal);
+        signal._sources.push(source);
+      }
+      return signal;
+    }
+  }
+
+  class AbortController {
+    constructor() {
+      Object.defineProperty(this, "_signal", { value: createSignal() });
+    }
+
+    get signal() {
+      return this._signal;
+    }
+
+    abort(reason) {
+      signalAbort(this._signal, reason);
+    }
+  }
+
+  for (const [name, ctor] of [["AbortSignal", AbortSignal], ["AbortController", AbortController]]) {
+    Object.defineProperty(ctor.prototype, Symbol.toStringTag, {
+      value: name,
+      configurable: true,
+    });
+  }
+
+  globalThis.AbortSignal = AbortSignal;
+  globalThis.AbortController = AbortController;
+
+  Reflect.deleteProperty(globalThis, "__javy_abort_timers");
+})();
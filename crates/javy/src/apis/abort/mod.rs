@@ -0,0 +1,174 @@
+use crate::quickjs::{context::EvalOptions, Ctx};
+use anyhow::{Error, Result};
+
+/// Register the global `AbortController` and `AbortSignal` classes.
+///
+/// `AbortSignal.timeout` throws unless `timers` is true.
+pub(crate) fn register(this: Ctx<'_>, timers: bool) -> Result<()> {
+    this.globals().set("__javy_abort_timers", timers)?;
+
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./abort.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.abort_controller(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof AbortController + typeof AbortSignal")?;
+            assert_eq!("undefinedundefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_abort_controller() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const controller = new AbortController();
+                const { signal } = controller;
+                const events = [];
+                signal.onabort = (e) => events.push(`onabort:${e.type}`);
+                signal.addEventListener("abort", (e) => events.push(`listener:${e.target === signal}`));
+                const removed = () => events.push("removed");
+                signal.addEventListener("abort", removed);
+                signal.removeEventListener("abort", removed);
+                events.push(`aborted:${signal.aborted}`);
+                controller.abort("reason");
+                controller.abort("ignored");
+                events.push(`aborted:${signal.aborted}`, `reason:${signal.reason}`);
+                try { signal.throwIfAborted(); } catch (e) { events.push(`thrown:${e}`); }
+                events.push(Object.prototype.toString.call(signal));
+                events.join(",");
+                "#,
+            )?;
+            assert_eq!(
+                "aborted:false,onabort:abort,listener:true,aborted:true,reason:reason,thrown:reason,[object AbortSignal]",
+                result
+            );
+
+            let result: String = cx.eval(
+                r#"
+                const reason = AbortSignal.abort().reason;
+                `${reason.name}: ${reason.message}`;
+                "#,
+            )?;
+            assert_eq!("AbortError: This operation was aborted", result);
+
+            let result: String =
+                cx.eval("try { new AbortSignal(); 'no error' } catch (e) { e.name }")?;
+            assert_eq!("TypeError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_without_timers() -> Result<()> {
+        let mut config = Config::default();
+        config.abort_controller(true).timers(false);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof AbortController + typeof AbortSignal")?;
+            assert_eq!("functionfunction", result);
+
+            // Even with a `setTimeout` defined by the script
+            let result: String = cx.eval(
+                r#"
+                globalThis.setTimeout = () => {};
+                try { AbortSignal.timeout(10); "no error" } catch (e) { `${e.name}: ${e.message}` }
+                "#,
+            )?;
+            assert_eq!(
+                "TypeError: AbortSignal.timeout requires timers to be enabled",
+                result
+            );
+
+            let result: String = cx.eval("typeof __javy_abort_timers")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_timeout() -> Result<()> {
+        let mut config = Config::default();
+        config.abort_controller(true).timers(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.signal = AbortSignal.timeout(0);
+                globalThis.before = signal.aborted;
+                "#,
+            )
+        })?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("`${before},${signal.aborted},${signal.reason.name}`")?;
+            assert_eq!("false,true,TimeoutError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_any() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const first = new AbortController();
+                const second = new AbortController();
+                const combined = AbortSignal.any([first.signal, second.signal]);
+                let events = 0;
+                combined.addEventListener("abort", () => events++);
+                const before = combined.aborted;
+                second.abort("second");
+                first.abort("first");
+                `${before},${combined.aborted},${combined.reason},${events}`;
+                "#,
+            )?;
+            assert_eq!("false,true,second,1", result);
+
+            // The combined signal is released by its sources once aborted
+            let result: usize =
+                cx.eval("first.signal._dependents.length + second.signal._dependents.length")?;
+            assert_eq!(0, result);
+
+            // An already aborted input aborts the result immediately
+            let result: String = cx.eval(
+                r#"
+                const pending = new AbortController();
+                const signal = AbortSignal.any([pending.signal, AbortSignal.abort("early")]);
+                `${signal.aborted},${signal.reason},${pending.signal._dependents.length}`;
+                "#,
+            )?;
+            assert_eq!("true,early,0", result);
+
+            let result: String =
+                cx.eval("try { AbortSignal.any([{}]); 'no error' } catch (e) { e.name }")?;
+            assert_eq!("TypeError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
@@ -1,9 +1,11 @@
 use crate::{
     hold, hold_and_release,
-    quickjs::{prelude::MutFn, Ctx, Function, String as JSString, Value},
+    quickjs::{
+        prelude::MutFn, Ctx, Error as JSError, Exception, Function, String as JSString, Value,
+    },
     to_js_error, val_to_string, Args,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
 /// Register `btoa` and `atob` global functions for base64 encoding/decoding.
 pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
@@ -46,15 +48,17 @@ fn btoa<'js>(args: Args<'js>) -> Result<Value<'js>> {
     // Get the string to encode
     let input_str = val_to_string(&ctx, args[0].clone())?;
     
-    // Check for invalid characters (btoa should only work with "binary strings" - ASCII range 0-255)
-    for ch in input_str.chars() {
-        if ch as u32 > 255 {
-            return Err(anyhow!("InvalidCharacterError: The string to be encoded contains characters outside of the Latin1 range"));
-        }
-    }
-
-    // Convert string to bytes (each character becomes one byte)
-    let bytes: Vec<u8> = input_str.chars().map(|c| c as u8).collect();
+    // btoa only accepts "binary strings", where each character is a byte
+    let bytes = input_str
+        .chars()
+        .map(u8::try_from)
+        .collect::<Result<Vec<u8>, _>>()
+        .map_err(|_| {
+            invalid_character_error(
+                &ctx,
+                "The string to be encoded contains characters outside of the Latin1 range",
+            )
+        })?;
     
     // Encode to base64
     let encoded = base64_encode(&bytes);
@@ -75,12 +79,9 @@ fn atob<'js>(args: Args<'js>) -> Result<Value<'js>> {
     // Get the base64 string to decode
     let input_str = val_to_string(&ctx, args[0].clone())?;
     
-    // Remove whitespace (browsers are lenient with whitespace)
-    let cleaned = input_str.chars().filter(|c| !c.is_whitespace()).collect::<String>();
-    
-    // Decode from base64
-    let decoded_bytes = base64_decode(&cleaned)
-        .map_err(|_| anyhow!("InvalidCharacterError: The string to be decoded is not correctly encoded"))?;
+    let decoded_bytes = base64_decode(&input_str).map_err(|_| {
+        invalid_character_error(&ctx, "The string to be decoded is not correctly encoded")
+    })?;
 
     // Convert bytes back to string (each byte becomes one character)
     let result_string: String = decoded_bytes.into_iter().map(|b| b as char).collect();
@@ -90,7 +91,7 @@ fn atob<'js>(args: Args<'js>) -> Result<Value<'js>> {
 }
 
 /// Simple base64 encoder implementation
-fn base64_encode(input: &[u8]) -> String {
+pub(crate) fn base64_encode(input: &[u8]) -> String {
     const ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
     
     let mut result = String::new();
@@ -124,61 +125,69 @@ fn base64_encode(input: &[u8]) -> String {
     result
 }
 
-/// Simple base64 decoder implementation
+/// Creates an error named `InvalidCharacterError`, like the `DOMException`
+/// thrown by browsers, and throws it.
+fn invalid_character_error(cx: &Ctx<'_>, message: &str) -> anyhow::Error {
+    let error = Exception::from_message(cx.clone(), message)
+        .and_then(|exception| {
+            exception.set("name", "InvalidCharacterError")?;
+            Ok(cx.throw(exception.into_value()))
+        })
+        .unwrap_or_else(|e: JSError| e);
+    error.into()
+}
+
+/// Base64 decoder following the forgiving-base64 decode algorithm of the
+/// HTML standard.
+///
+/// ASCII whitespace is ignored anywhere in the input and padding is optional,
+/// but must be correct when present.
 fn base64_decode(input: &str) -> Result<Vec<u8>> {
     const DECODE_TABLE: [u8; 128] = [
         255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
         255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255,
         255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255, 62,  255, 255, 255, 63,
-        52,  53,  54,  55,  56,  57,  58,  59,  60,  61,  255, 255, 255, 64,  255, 255,
+        52,  53,  54,  55,  56,  57,  58,  59,  60,  61,  255, 255, 255, 255, 255, 255,
         255, 0,   1,   2,   3,   4,   5,   6,   7,   8,   9,   10,  11,  12,  13,  14,
         15,  16,  17,  18,  19,  20,  21,  22,  23,  24,  25,  255, 255, 255, 255, 255,
         255, 26,  27,  28,  29,  30,  31,  32,  33,  34,  35,  36,  37,  38,  39,  40,
         41,  42,  43,  44,  45,  46,  47,  48,  49,  50,  51,  255, 255, 255, 255, 255,
     ];
-    
-    let input_bytes = input.as_bytes();
-    let input_len = input_bytes.len();
-    
-    // Check for valid length
-    if input_len % 4 != 0 {
-        return Err(anyhow!("Invalid base64 length"));
-    }
-    
-    let mut result = Vec::new();
-    let mut i = 0;
-    
-    while i < input_len {
-        let mut bitmap = 0u32;
-        let mut padding = 0;
-        
-        for j in 0..4 {
-            let byte = input_bytes[i + j];
-            if byte == b'=' {
-                padding += 1;
-                bitmap <<= 6;
-            } else if byte > 127 {
-                return Err(anyhow!("Invalid character"));
-            } else {
-                let decoded = DECODE_TABLE[byte as usize];
-                if decoded == 255 {
-                    return Err(anyhow!("Invalid character"));
-                }
-                bitmap = (bitmap << 6) | (decoded as u32);
-            }
+
+    let mut data: Vec<u8> = input
+        .bytes()
+        .filter(|b| !matches!(b, b'\t' | b'\n' | b'\x0C' | b'\r' | b' '))
+        .collect();
+
+    // Padding can only complete the last group of four characters
+    if data.len().is_multiple_of(4) {
+        if data.ends_with(b"==") {
+            data.truncate(data.len() - 2);
+        } else if data.ends_with(b"=") {
+            data.truncate(data.len() - 1);
         }
-        
-        result.push((bitmap >> 16) as u8);
-        if padding < 2 {
-            result.push((bitmap >> 8) as u8);
+    }
+    if data.len() % 4 == 1 {
+        bail!("Invalid base64 length");
+    }
+
+    let mut result = Vec::with_capacity(data.len() / 4 * 3 + 2);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for byte in data {
+        let decoded = DECODE_TABLE.get(byte as usize).copied().unwrap_or(255);
+        if decoded == 255 {
+            bail!("Invalid character");
         }
-        if padding < 1 {
-            result.push(bitmap as u8);
+        buffer = (buffer << 6) | decoded as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
         }
-        
-        i += 4;
     }
-    
+
     Ok(result)
 }
 
@@ -286,6 +295,24 @@ mod tests {
             // Test that non-Latin1 characters throw an error
             let result = cx.eval::<Value, _>("btoa('€')"); // Euro symbol (outside Latin1)
             assert!(result.is_err());
+
+            let result: String = cx.eval(
+                r#"
+                try {
+                    btoa("caf\u00e9 \u2615");
+                } catch (e) {
+                    `${e instanceof Error} ${e.name}: ${e.message}`;
+                }
+                "#,
+            )?;
+            assert_eq!(
+                "true InvalidCharacterError: The string to be encoded contains characters outside of the Latin1 range",
+                result
+            );
+
+            // Latin1 characters are encoded as their byte value
+            let result: String = cx.eval("btoa('caf\\u00e9\\u00ff')")?;
+            assert_eq!("Y2Fm6f8=", result);
             
             Ok::<_, Error>(())
         })?;
@@ -304,8 +331,20 @@ mod tests {
             assert!(result.is_err());
             
             // Test wrong length
-            let result = cx.eval::<Value, _>("atob('abc')");
+            let result = cx.eval::<Value, _>("atob('abcde')");
             assert!(result.is_err());
+
+            // Test padding in the wrong place
+            for input in ["aGVs=bG8", "aGVsbG8==", "aA="] {
+                let result: String = cx.eval(format!(
+                    "try {{ atob('{input}'); 'no error' }} catch (e) {{ e.name }}"
+                ))?;
+                assert_eq!("InvalidCharacterError", result, "{input}");
+            }
+
+            // Test missing padding
+            let result: String = cx.eval("atob('aGU')")?;
+            assert_eq!("he", result);
             
             Ok::<_, Error>(())
         })?;
@@ -327,6 +366,14 @@ mod tests {
             let result: Value = cx.eval("atob('aGVs bG8=')")?; // "hello" with space
             let decoded = val_to_string(&cx, result)?;
             assert_eq!(decoded, "hello");
+
+            // All ASCII whitespace is ignored, including around the padding
+            let result: String = cx.eval(r#"atob(" \taG\fVs\r\nbG8 = \n")"#)?;
+            assert_eq!(result, "hello");
+
+            // Other whitespace isn't
+            let result = cx.eval::<Value, _>(r#"atob("aGVs\u00a0bG8=")"#);
+            assert!(result.is_err());
             
             Ok::<_, Error>(())
         })?;
@@ -385,7 +432,12 @@ mod tests {
         
         // Test error cases
         assert!(base64_decode("invalid!").is_err());
-        assert!(base64_decode("abc").is_err()); // Wrong length
+        assert!(base64_decode("abcde").is_err()); // Wrong length
+        assert!(base64_decode("aG=U").is_err()); // Padding in the middle
+
+        // Padding is optional
+        assert_eq!(base64_decode("aGVsbG8")?, b"hello");
+        assert_eq!(base64_decode("aA")?, b"h");
         
         Ok(())
     }
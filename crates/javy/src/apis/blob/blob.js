@@ -1,23 +1,81 @@
 (function () {
     const __javy_blob_create = globalThis.__javy_blob_create;
+    const __javy_blob_handle = globalThis.__javy_blob_handle;
+    const __javy_blob_pin = globalThis.__javy_blob_pin;
     const __javy_blob_get_property = globalThis.__javy_blob_get_property;
     const __javy_blob_array_buffer = globalThis.__javy_blob_array_buffer;
     const __javy_blob_bytes = globalThis.__javy_blob_bytes;
     const __javy_blob_text = globalThis.__javy_blob_text;
+    const __javy_blob_data_url = globalThis.__javy_blob_data_url;
     const __javy_blob_slice = globalThis.__javy_blob_slice;
+    const __javy_blob_create_object_url = globalThis.__javy_blob_create_object_url;
+    const __javy_blob_resolve_object_url = globalThis.__javy_blob_resolve_object_url;
+    const __javy_blob_revoke_object_url = globalThis.__javy_blob_revoke_object_url;
     const __javy_file_create = globalThis.__javy_file_create;
 
+    // The handles releasing the storage of blobs, when they're held in a
+    // WeakMap rather than in hidden properties of the blobs. The entry of a
+    // blob, and so its handle, is released once the blob is collected.
+    const blobHandles = globalThis.__javy_blob_weak_map_handles ? new WeakMap() : null;
+
+    // Creates an error with the name of a DOMException, like "InvalidStateError"
+    function domError(name, message) {
+        const error = new Error(message);
+        error.name = name;
+        return error;
+    }
+
+    // Stores the internal blob ID as a non-enumerable property, so that
+    // blobs have no own enumerable properties
+    function setBlobId(blob, blobId) {
+        Object.defineProperty(blob, "_blobId", {
+            value: blobId,
+            writable: true,
+            enumerable: false,
+            configurable: true,
+        });
+    }
+
+    // Stores the internal blob ID of a new storage entry, along with the
+    // handle releasing the entry once the blob is garbage collected
+    function setOwnedBlobId(blob, blobId) {
+        setBlobId(blob, blobId);
+        const handle = __javy_blob_handle(blobId);
+        if (blobHandles !== null) {
+            blobHandles.set(blob, handle);
+            return;
+        }
+        Object.defineProperty(blob, "_blobHandle", {
+            value: handle,
+            writable: true,
+            enumerable: false,
+            configurable: true,
+        });
+    }
+
+    // Creates a Blob for an existing internal blob ID, without owning it
+    function blobFromId(blobId) {
+        const blob = Object.create(Blob.prototype);
+        setBlobId(blob, blobId);
+        return blob;
+    }
+
+    // Resolves a Blob or an object URL to a Blob, or returns null
+    function resolveBlob(value) {
+        if (value instanceof Blob) {
+            return value;
+        }
+        const blobId = __javy_blob_resolve_object_url(value);
+        if (blobId === undefined) {
+            return null;
+        }
+        return blobFromId(blobId);
+    }
+
     class Blob {
         constructor(blobParts = [], options = {}) {
-            // Normalize options to ensure type is a string
-            if (options.type === undefined || options.type === null) {
-                options.type = "";
-            } else {
-                options.type = String(options.type);
-            }
-            
             // Store the internal blob ID
-            this._blobId = __javy_blob_create(blobParts, options);
+            setOwnedBlobId(this, __javy_blob_create(blobParts, options));
         }
 
         get size() {
@@ -42,9 +100,8 @@
         }
 
         slice(start, end, contentType) {
-            const newBlobId = __javy_blob_slice(this._blobId, start, end, contentType);
             const newBlob = Object.create(Blob.prototype);
-            newBlob._blobId = newBlobId;
+            setOwnedBlobId(newBlob, __javy_blob_slice(this._blobId, start, end, contentType));
             return newBlob;
         }
     }
@@ -52,46 +109,179 @@
     class File extends Blob {
         constructor(fileBits, fileName, options = {}) {
             if (arguments.length < 2) {
-                throw new TypeError("File constructor requires at least 2 arguments");
+                throw new TypeError(
+                    `Failed to construct 'File': 2 arguments required, but only ${arguments.length} present.`
+                );
             }
             
             super(); // Call parent constructor but we'll override _blobId
             
-            // Normalize options
-            if (options.type === undefined || options.type === null) {
-                options.type = "";
-            } else {
-                options.type = String(options.type);
-            }
-            
-            this._blobId = __javy_file_create(fileBits, fileName, options);
-            this._name = String(fileName);
-            this._lastModified = options.lastModified || Date.now();
-            this._webkitRelativePath = "";
+            setOwnedBlobId(this, __javy_file_create(fileBits, fileName, options));
         }
 
         get name() {
-            return this._name;
+            return __javy_blob_get_property(this._blobId, "name");
         }
 
         get lastModified() {
-            return this._lastModified;
+            return __javy_blob_get_property(this._blobId, "lastModified");
         }
 
         get webkitRelativePath() {
-            return this._webkitRelativePath;
+            return "";
         }
     }
 
+    class FileReader {
+        constructor() {
+            this._readyState = FileReader.EMPTY;
+            this._result = null;
+            this._error = null;
+            this.onloadstart = null;
+            this.onload = null;
+            this.onloadend = null;
+            this.onerror = null;
+        }
+
+        get readyState() {
+            return this._readyState;
+        }
+
+        get result() {
+            return this._result;
+        }
+
+        get error() {
+            return this._error;
+        }
+
+        readAsText(blob) {
+            this._read(blob, (b) => b.text());
+        }
+
+        readAsArrayBuffer(blob) {
+            this._read(blob, (b) => b.arrayBuffer());
+        }
+
+        readAsDataURL(blob) {
+            this._read(blob, (b) => __javy_blob_data_url(b._blobId));
+        }
+
+        _read(value, read) {
+            const blob = resolveBlob(value);
+            if (blob === null) {
+                throw new TypeError("Argument must be a Blob");
+            }
+            if (this._readyState === FileReader.LOADING) {
+                throw domError("InvalidStateError", "The object is already busy reading");
+            }
+
+            this._readyState = FileReader.LOADING;
+            this._result = null;
+            this._error = null;
+
+            // Events are always delivered asynchronously, after the read
+            // method has returned.
+            Promise.resolve().then(() => {
+                this._dispatch("loadstart");
+                try {
+                    this._result = read(blob);
+                    this._readyState = FileReader.DONE;
+                    this._dispatch("load");
+                } catch (e) {
+                    this._error = e;
+                    this._readyState = FileReader.DONE;
+                    this._dispatch("error");
+                }
+                this._dispatch("loadend");
+            });
+        }
+
+        _dispatch(type) {
+            const handler = this["on" + type];
+            if (typeof handler === "function") {
+                handler.call(this, { type, target: this });
+            }
+        }
+    }
+
+    // Like Web IDL attributes, the accessors are enumerable and configurable
+    // properties of the prototypes, while the methods stay non-enumerable.
+    function makeAccessorsEnumerable(prototype, names) {
+        for (const name of names) {
+            const descriptor = Object.getOwnPropertyDescriptor(prototype, name);
+            descriptor.enumerable = true;
+            Object.defineProperty(prototype, name, descriptor);
+        }
+    }
+
+    function defineToStringTag(constructor) {
+        Object.defineProperty(constructor.prototype, Symbol.toStringTag, {
+            value: constructor.name,
+            writable: false,
+            enumerable: false,
+            configurable: true,
+        });
+    }
+
+    makeAccessorsEnumerable(Blob.prototype, ["size", "type"]);
+    makeAccessorsEnumerable(File.prototype, ["name", "lastModified", "webkitRelativePath"]);
+    defineToStringTag(Blob);
+    defineToStringTag(File);
+    defineToStringTag(FileReader);
+
+    FileReader.EMPTY = 0;
+    FileReader.LOADING = 1;
+    FileReader.DONE = 2;
+
     globalThis.Blob = Blob;
     globalThis.File = File;
+    globalThis.FileReader = FileReader;
+
+    // There is no URL implementation, so the object URL functions get their
+    // own namespace unless one already exists
+    if (globalThis.URL === undefined) {
+        globalThis.URL = {};
+    }
+    globalThis.URL.createObjectURL = function createObjectURL(blob) {
+        if (!(blob instanceof Blob)) {
+            throw new TypeError("URL.createObjectURL: argument is not a Blob");
+        }
+        return __javy_blob_create_object_url(blob._blobId);
+    };
+    globalThis.URL.revokeObjectURL = function revokeObjectURL(url) {
+        __javy_blob_revoke_object_url(String(url));
+    };
+
+    // Lets JavaScript code hand blobs to the host by ID
+    if (typeof globalThis.Javy !== "object" || globalThis.Javy === null) {
+        globalThis.Javy = {};
+    }
+    globalThis.Javy.Blob = {
+        idOf(value) {
+            const blob = resolveBlob(value);
+            if (blob === null) {
+                throw new TypeError("Javy.Blob.idOf: argument is not a Blob or object URL");
+            }
+            // The host may read the blob after it's garbage collected
+            __javy_blob_pin(blob._blobId);
+            return blob._blobId;
+        },
+    };
 
     // Clean up helper functions
     Reflect.deleteProperty(globalThis, "__javy_blob_create");
+    Reflect.deleteProperty(globalThis, "__javy_blob_handle");
+    Reflect.deleteProperty(globalThis, "__javy_blob_pin");
     Reflect.deleteProperty(globalThis, "__javy_blob_get_property");
     Reflect.deleteProperty(globalThis, "__javy_blob_array_buffer");
     Reflect.deleteProperty(globalThis, "__javy_blob_bytes");
     Reflect.deleteProperty(globalThis, "__javy_blob_text");
+    Reflect.deleteProperty(globalThis, "__javy_blob_data_url");
     Reflect.deleteProperty(globalThis, "__javy_blob_slice");
+    Reflect.deleteProperty(globalThis, "__javy_blob_create_object_url");
+    Reflect.deleteProperty(globalThis, "__javy_blob_resolve_object_url");
+    Reflect.deleteProperty(globalThis, "__javy_blob_revoke_object_url");
     Reflect.deleteProperty(globalThis, "__javy_file_create");
+    Reflect.deleteProperty(globalThis, "__javy_blob_weak_map_handles");
 })(); 
\ No newline at end of file
@@ -1,21 +1,184 @@
 use crate::{
+    apis::{base64::base64_encode, text_encoding},
     hold, hold_and_release,
-    quickjs::{prelude::MutFn, context::EvalOptions, ArrayBuffer, Ctx, Function, String as JSString, TypedArray, Value},
+    quickjs::{
+        class::{ClassId, JsClass, Readable, Trace, Tracer},
+        function::{Constructor, This}, prelude::MutFn, context::EvalOptions, convert::Coerced, ArrayBuffer, Class, Ctx, Exception, Function, Object, String as JSString, Symbol, TypedArray, Value,
+    },
     to_js_error, val_to_string, Args,
 };
 use anyhow::{anyhow, Error, Result};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex, OnceLock};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Settings of the blob APIs of a runtime
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BlobSettings {
+    /// Whether blobs and files created without a type get a type detected
+    /// from the magic bytes of their content.
+    pub sniff_type: bool,
+    /// Whether the helpers throw for unknown blob IDs rather than handling
+    /// them as empty files.
+    pub strict: bool,
+    /// The maximum number of bytes held by the blobs and files created by
+    /// the runtime.
+    pub max_storage_bytes: usize,
+    /// Whether the handles releasing the storage of blobs are held in a
+    /// `WeakMap` keyed by the blobs rather than in hidden properties.
+    pub weak_map_handles: bool,
+}
+
+impl Default for BlobSettings {
+    fn default() -> Self {
+        Self {
+            sniff_type: false,
+            strict: true,
+            max_storage_bytes: usize::MAX,
+            weak_map_handles: false,
+        }
+    }
+}
+
+/// The number of bytes held by the blobs of a runtime, and the maximum it
+/// may hold
+#[derive(Debug)]
+struct StorageBudget {
+    used: AtomicUsize,
+    max: usize,
+}
+
+impl StorageBudget {
+    fn new(max: usize) -> Self {
+        Self {
+            used: AtomicUsize::new(0),
+            max,
+        }
+    }
+
+    /// Account for `len` new bytes, failing with a `RangeError` if they don't
+    /// fit in the budget.
+    fn reserve(&self, ctx: &Ctx<'_>, len: usize) -> Result<()> {
+        let used = self.used.load(Ordering::Relaxed);
+        if len > self.max.saturating_sub(used) {
+            return Err(Error::from(Exception::throw_range(
+                ctx,
+                &format!(
+                    "Blob storage limit exceeded: {} bytes requested, {} of {} bytes in use",
+                    len, used, self.max
+                ),
+            )));
+        }
+        self.used.fetch_add(len, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+/// Bytes owned by a blob, and shared with the blobs sliced from it
+///
+/// The bytes are released from the budget they're accounted in when the last
+/// blob referencing them is dropped.
+#[derive(Debug)]
+struct BlobBytes {
+    data: Box<[u8]>,
+    budget: Option<Arc<StorageBudget>>,
+}
+
+impl Drop for BlobBytes {
+    fn drop(&mut self) {
+        if let Some(budget) = &self.budget {
+            budget.used.fetch_sub(self.data.len(), Ordering::Relaxed);
+        }
+    }
+}
 
 /// Internal blob storage with reference counting
+///
+/// The bytes are shared between a blob and all the blobs sliced from it, a
+/// slice only records the range of the shared bytes it covers. The bytes are
+/// released once the last blob referencing them is dropped.
 #[derive(Debug, Clone)]
 struct BlobData {
-    data: Vec<u8>,
+    bytes: Arc<BlobBytes>,
+    offset: usize,
+    len: usize,
     mime_type: String,
 }
 
+impl BlobData {
+    fn new(data: Vec<u8>, mime_type: String) -> Self {
+        Self::with_budget(data, mime_type, None)
+    }
+
+    fn with_budget(data: Vec<u8>, mime_type: String, budget: Option<Arc<StorageBudget>>) -> Self {
+        let len = data.len();
+        Self {
+            bytes: Arc::new(BlobBytes {
+                data: data.into(),
+                budget,
+            }),
+            offset: 0,
+            len,
+            mime_type,
+        }
+    }
+
+    /// A blob with the given bytes, accounted in `budget`.
+    fn allocate(
+        ctx: &Ctx<'_>,
+        data: Vec<u8>,
+        mime_type: String,
+        budget: &Arc<StorageBudget>,
+    ) -> Result<Self> {
+        budget.reserve(ctx, data.len())?;
+        Ok(Self::with_budget(data, mime_type, Some(budget.clone())))
+    }
+
+    /// The bytes of this blob.
+    fn data(&self) -> &[u8] {
+        &self.bytes.data[self.offset..self.offset + self.len]
+    }
+
+    /// A blob referencing the `start..end` range of this blob's bytes.
+    fn slice(&self, start: usize, end: usize, mime_type: String) -> Self {
+        Self {
+            bytes: self.bytes.clone(),
+            offset: self.offset + start,
+            len: end - start,
+            mime_type,
+        }
+    }
+}
+
+/// File data, a blob along with its metadata
+#[derive(Debug, Clone)]
+struct FileData {
+    blob: BlobData,
+    name: String,
+    last_modified: u64,
+}
+
+/// An entry in the blob storage
+#[derive(Debug, Clone)]
+enum BlobEntry {
+    Blob(BlobData),
+    File(FileData),
+}
+
+impl BlobEntry {
+    /// The blob data of the entry, regardless of its kind
+    fn blob(&self) -> &BlobData {
+        match self {
+            BlobEntry::Blob(blob) => blob,
+            BlobEntry::File(file) => &file.blob,
+        }
+    }
+}
+
 /// Global blob storage to handle blob references
-type BlobStorage = HashMap<u32, BlobData>;
+type BlobStorage = HashMap<u32, BlobEntry>;
 static BLOB_STORAGE: OnceLock<Arc<Mutex<BlobStorage>>> = OnceLock::new();
 static NEXT_BLOB_ID: OnceLock<Arc<Mutex<u32>>> = OnceLock::new();
 
@@ -23,6 +186,77 @@ fn get_blob_storage() -> &'static Arc<Mutex<BlobStorage>> {
     BLOB_STORAGE.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
 }
 
+/// IDs of the entries whose blob objects were garbage collected, removed
+/// from the storage the next time it's locked
+///
+/// The entries aren't removed while the blob objects are finalized, since
+/// garbage collection may run while the storage is locked.
+static RELEASED_BLOB_IDS: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+
+/// IDs of the entries handed to the host with `Javy.Blob.idOf`, which are
+/// kept after their blob objects are garbage collected
+static PINNED_BLOB_IDS: OnceLock<Mutex<HashSet<u32>>> = OnceLock::new();
+
+fn get_pinned_blob_ids() -> &'static Mutex<HashSet<u32>> {
+    PINNED_BLOB_IDS.get_or_init(|| Mutex::new(HashSet::new()))
+}
+
+/// Lock the blob storage, first removing the entries released since it was
+/// last locked
+fn lock_storage() -> MutexGuard<'static, BlobStorage> {
+    let mut storage = get_blob_storage().lock().unwrap();
+    let released = std::mem::take(&mut *RELEASED_BLOB_IDS.lock().unwrap());
+    if !released.is_empty() {
+        let pinned = get_pinned_blob_ids().lock().unwrap();
+        for id in released.iter().filter(|id| !pinned.contains(id)) {
+            storage.remove(id);
+        }
+    }
+    storage
+}
+
+/// Owns the storage entry of a blob object, which holds the handle in a
+/// hidden property. The entry is released once the handle is garbage
+/// collected along with the blob object.
+struct BlobHandle {
+    id: u32,
+}
+
+impl Drop for BlobHandle {
+    fn drop(&mut self) {
+        RELEASED_BLOB_IDS.lock().unwrap().push(self.id);
+    }
+}
+
+impl<'js> Trace<'js> for BlobHandle {
+    fn trace<'a>(&self, _tracer: Tracer<'a, 'js>) {}
+}
+
+impl<'js> JsClass<'js> for BlobHandle {
+    const NAME: &'static str = "BlobHandle";
+
+    type Mutable = Readable;
+
+    fn class_id() -> &'static ClassId {
+        static ID: ClassId = ClassId::new();
+        &ID
+    }
+
+    fn prototype(_ctx: &Ctx<'js>) -> crate::quickjs::Result<Option<Object<'js>>> {
+        Ok(None)
+    }
+
+    fn constructor(_ctx: &Ctx<'js>) -> crate::quickjs::Result<Option<Constructor<'js>>> {
+        Ok(None)
+    }
+}
+
+/// A handle owning the storage entry with the given ID, to be held by the
+/// blob object the entry belongs to.
+pub(crate) fn handle(ctx: Ctx<'_>, id: u32) -> Result<Value<'_>> {
+    Ok(Class::instance(ctx, BlobHandle { id })?.into_value())
+}
+
 fn get_next_blob_id() -> u32 {
     let counter = NEXT_BLOB_ID.get_or_init(|| Arc::new(Mutex::new(1)));
     let mut id = counter.lock().unwrap();
@@ -31,16 +265,147 @@ fn get_next_blob_id() -> u32 {
     current
 }
 
+/// The prefix of the URLs created by `URL.createObjectURL`
+const OBJECT_URL_PREFIX: &str = "blob:javy-internal/";
+
+/// Object URLs mapped to the ID of the storage entry they keep alive
+type ObjectUrls = HashMap<String, u32>;
+static OBJECT_URLS: OnceLock<Arc<Mutex<ObjectUrls>>> = OnceLock::new();
+
+fn get_object_urls() -> &'static Arc<Mutex<ObjectUrls>> {
+    OBJECT_URLS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+/// Host access to the bytes of the blobs created by JavaScript code.
+///
+/// JavaScript code obtains the ID of a blob with `Javy.Blob.idOf(blob)`,
+/// which keeps the blob in storage after it's garbage collected, and
+/// communicates it to the host, for example by storing it on `globalThis` or
+/// writing it to an output stream. After the invocation returns, the host
+/// reads the blob back with [`BlobRegistry::get`]. A registry is obtained
+/// with [`blob_registry`], for example in the `modify_runtime` hook of a
+/// plugin.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BlobRegistry {
+    _private: (),
+}
+
+/// A [`BlobRegistry`] to read the blobs created by JavaScript code.
+///
+/// Blobs are stored for the whole process rather than per
+/// [`Runtime`](crate::Runtime), so the registry reads the blobs created by
+/// every runtime and [`BlobRegistry::clear`] removes all of them.
+pub fn blob_registry() -> BlobRegistry {
+    BlobRegistry { _private: () }
+}
+
+impl BlobRegistry {
+    /// The bytes and MIME type of the blob or file with the given ID, if any.
+    pub fn get(&self, id: u32) -> Option<(Vec<u8>, String)> {
+        let storage = lock_storage();
+        storage.get(&id).map(|entry| {
+            let blob_data = entry.blob();
+            (blob_data.data().to_vec(), blob_data.mime_type.clone())
+        })
+    }
+
+    /// The number of bytes held in storage by all the blobs and files,
+    /// counting the bytes shared between a blob and its slices only once.
+    pub fn stored_bytes(&self) -> usize {
+        unique_bytes(lock_storage().values().map(BlobEntry::blob))
+    }
+
+    /// Removes all the blobs and files from storage, including the ones
+    /// handed to the host with `Javy.Blob.idOf` or referenced by object URLs.
+    ///
+    /// Meant for tearing down the runtime, the blob objects still alive can't
+    /// be read anymore.
+    pub fn clear(&self) {
+        let mut storage = lock_storage();
+        storage.clear();
+        get_pinned_blob_ids().lock().unwrap().clear();
+        get_object_urls().lock().unwrap().clear();
+    }
+}
+
+/// The entry with the given ID.
+///
+/// When `strict` is true an unknown ID is an error, otherwise it's treated as
+/// an empty file so that every accessor returns its default value.
+fn get_entry(storage: &BlobStorage, id: u32, strict: bool) -> Result<Cow<'_, BlobEntry>> {
+    match storage.get(&id) {
+        Some(entry) => Ok(Cow::Borrowed(entry)),
+        None if strict => Err(anyhow!("Invalid blob ID: {}", id)),
+        None => Ok(Cow::Owned(BlobEntry::File(FileData {
+            blob: BlobData::new(Vec::new(), String::new()),
+            name: String::new(),
+            last_modified: 0,
+        }))),
+    }
+}
+
+/// Copy the blob or file with the given ID into a new storage entry,
+/// returning the ID of the copy.
+pub(crate) fn clone_entry(id: u32) -> Result<u32> {
+    copy_entry(id, true)
+}
+
+/// Like [`clone_entry`], with an unknown ID handled as described in
+/// [`get_entry`].
+fn copy_entry(id: u32, strict: bool) -> Result<u32> {
+    let mut storage = lock_storage();
+    let entry = get_entry(&storage, id, strict)?.into_owned();
+    let clone_id = get_next_blob_id();
+    storage.insert(clone_id, entry);
+    Ok(clone_id)
+}
+
+/// Call `f` with the bytes of the blob or file with the given ID, without
+/// copying them.
+pub(crate) fn with_data<R>(id: u32, f: impl FnOnce(&[u8]) -> R) -> Result<R> {
+    let storage = lock_storage();
+    let entry = storage
+        .get(&id)
+        .ok_or_else(|| anyhow!("Invalid blob ID: {}", id))?;
+    Ok(f(entry.blob().data()))
+}
+
 /// Register Blob and File helper functions and JavaScript classes
-pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+pub(crate) fn register(this: Ctx<'_>, settings: BlobSettings) -> Result<()> {
+    let BlobSettings {
+        sniff_type,
+        strict,
+        max_storage_bytes,
+        weak_map_handles,
+    } = settings;
+    let budget = Arc::new(StorageBudget::new(max_storage_bytes));
     let globals = this.globals();
 
     // Register helper functions
     globals.set(
         "__javy_blob_create",
+        Function::new(this.clone(), MutFn::new({
+            let budget = budget.clone();
+            move |cx, args| {
+                let (cx, args) = hold_and_release!(cx, args);
+                blob_create(hold!(cx.clone(), args), sniff_type, &budget).map_err(|e| to_js_error(cx, e))
+            }
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_handle",
+        Function::new(this.clone(), MutFn::new(move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_handle(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_pin",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_create(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_pin(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -48,7 +413,7 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_blob_get_property",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_get_property(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_get_property(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -56,7 +421,7 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_blob_array_buffer",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_array_buffer(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_array_buffer(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -64,7 +429,7 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_blob_bytes",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_bytes(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_bytes(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -72,7 +437,15 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_blob_text",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_text(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_text(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_data_url",
+        Function::new(this.clone(), MutFn::new(move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_data_url(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -80,7 +453,31 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_blob_slice",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            blob_slice(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            blob_slice(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_create_object_url",
+        Function::new(this.clone(), MutFn::new(move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_create_object_url(hold!(cx.clone(), args), strict).map_err(|e| to_js_error(cx, e))
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_resolve_object_url",
+        Function::new(this.clone(), MutFn::new(move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_resolve_object_url(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        })),
+    )?;
+
+    globals.set(
+        "__javy_blob_revoke_object_url",
+        Function::new(this.clone(), MutFn::new(move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_revoke_object_url(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
@@ -88,10 +485,12 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         "__javy_file_create",
         Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            file_create(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+            file_create(hold!(cx.clone(), args), sniff_type, &budget).map_err(|e| to_js_error(cx, e))
         })),
     )?;
 
+    globals.set("__javy_blob_weak_map_handles", weak_map_handles)?;
+
     // Load the JavaScript implementation
     let mut opts = EvalOptions::default();
     opts.strict = false;
@@ -101,17 +500,10 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
 }
 
 /// Create a new blob and return its ID
-fn blob_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn blob_create<'js>(args: Args<'js>, sniff_type: bool, budget: &Arc<StorageBudget>) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    // Parse blobParts (first argument, defaults to empty array)
-    let blob_parts = if args.is_empty() {
-        vec![]
-    } else {
-        parse_blob_parts(&ctx, args[0].clone())?
-    };
-
     // Parse options (second argument, defaults to empty object)
     let options = if args.len() > 1 {
         parse_blob_options(&ctx, args[1].clone())?
@@ -119,6 +511,13 @@ fn blob_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
         BlobOptions::default()
     };
 
+    // Parse blobParts (first argument, defaults to empty array)
+    let blob_parts = if args.is_empty() {
+        vec![]
+    } else {
+        parse_blob_parts(&ctx, args[0].clone(), options.endings == "native", "Blob")?
+    };
+
     // Concatenate all blob parts
     let mut data = Vec::new();
     for part in blob_parts {
@@ -127,32 +526,31 @@ fn blob_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
 
     // Create blob and store it
     let id = get_next_blob_id();
-    let blob_data = BlobData { 
-        data, 
-        mime_type: options.mime_type 
-    };
-    
-    let storage = get_blob_storage();
-    storage.lock().unwrap().insert(id, blob_data);
+    let mime_type = resolve_mime_type(options.mime_type, &data, sniff_type);
+    // Lock the storage first, so that the bytes of the blobs released so far
+    // no longer count towards the budget
+    let mut storage = lock_storage();
+    let blob_data = BlobData::allocate(&ctx, data, mime_type, budget)?;
+    storage.insert(id, BlobEntry::Blob(blob_data));
 
     Ok(Value::new_number(ctx, id as f64))
 }
 
 /// Create a new file and return its ID  
-fn file_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn file_create<'js>(args: Args<'js>, sniff_type: bool, budget: &Arc<StorageBudget>) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
     if args.len() < 2 {
-        return Err(anyhow!("File constructor requires at least 2 arguments"));
+        return Err(type_error(
+            &ctx,
+            &format!(
+                "Failed to construct 'File': 2 arguments required, but only {} present.",
+                args.len()
+            ),
+        ));
     }
 
-    // Parse fileBits (first argument)
-    let file_bits = parse_blob_parts(&ctx, args[0].clone())?;
-    
-    // Parse fileName (second argument)
-    let _file_name = val_to_string(&ctx, args[1].clone())?;
-
     // Parse options (third argument, optional)
     let options = if args.len() > 2 {
         parse_file_options(&ctx, args[2].clone())?
@@ -160,6 +558,12 @@ fn file_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
         FileOptions::default()
     };
 
+    // Parse fileBits (first argument)
+    let file_bits = parse_blob_parts(&ctx, args[0].clone(), options.endings == "native", "File")?;
+    
+    // Parse fileName (second argument)
+    let name = val_to_string(&ctx, args[1].clone())?;
+
     // Concatenate all file bits
     let mut data = Vec::new();
     for part in file_bits {
@@ -168,135 +572,135 @@ fn file_create<'js>(args: Args<'js>) -> Result<Value<'js>> {
 
     // Create file blob and store it (files are just blobs with metadata)
     let id = get_next_blob_id();
-    let blob_data = BlobData { 
-        data, 
-        mime_type: options.mime_type 
+    let mime_type = resolve_mime_type(options.mime_type, &data, sniff_type);
+    let mut storage = lock_storage();
+    let file_data = FileData {
+        blob: BlobData::allocate(&ctx, data, mime_type, budget)?,
+        name,
+        last_modified: options.last_modified.unwrap_or_else(now_millis),
     };
-    
-    let storage = get_blob_storage();
-    storage.lock().unwrap().insert(id, blob_data);
+    storage.insert(id, BlobEntry::File(file_data));
 
     Ok(Value::new_number(ctx, id as f64))
 }
 
 /// Get a property of a blob by ID
-fn blob_get_property<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn blob_get_property<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    if args.len() < 2 {
-        return Err(anyhow!("blob_get_property requires 2 arguments"));
-    }
-
-    let blob_id = args[0].as_number().ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
-    let property = val_to_string(&ctx, args[1].clone())?;
-
-    let storage = get_blob_storage();
-    let storage_guard = storage.lock().unwrap();
-    
-    if let Some(blob_data) = storage_guard.get(&blob_id) {
-        match property.as_str() {
-            "size" => Ok(Value::new_number(ctx, blob_data.data.len() as f64)),
-            "type" => {
-                let js_string = JSString::from_str(ctx.clone(), &blob_data.mime_type)?;
-                Ok(Value::from_string(js_string))
-            }
-            _ => Err(anyhow!("Unknown property: {}", property))
+    let blob_id = blob_id_arg(&ctx, &args)?;
+    let property = args
+        .get(1)
+        .ok_or_else(|| type_error(&ctx, "Blob property name is required"))?;
+    let property = val_to_string(&ctx, property.clone())?;
+
+    let storage_guard = lock_storage();
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
+    match (property.as_str(), entry.as_ref()) {
+        ("size", _) => Ok(Value::new_number(ctx, blob_data.len as f64)),
+        ("type", _) => {
+            let js_string = JSString::from_str(ctx.clone(), &blob_data.mime_type)?;
+            Ok(Value::from_string(js_string))
         }
-    } else {
-        Err(anyhow!("Invalid blob ID: {}", blob_id))
+        ("name", BlobEntry::File(file_data)) => {
+            let js_string = JSString::from_str(ctx.clone(), &file_data.name)?;
+            Ok(Value::from_string(js_string))
+        }
+        ("lastModified", BlobEntry::File(file_data)) => {
+            Ok(Value::new_number(ctx, file_data.last_modified as f64))
+        }
+        _ => Err(anyhow!("Unknown property: {}", property))
     }
 }
 
 /// Get ArrayBuffer from blob by ID
-fn blob_array_buffer<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn blob_array_buffer<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    if args.is_empty() {
-        return Err(anyhow!("blob_array_buffer requires 1 argument"));
-    }
+    let blob_id = blob_id_arg(&ctx, &args)?;
 
-    let blob_id = args[0].as_number().ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    let storage_guard = lock_storage();
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
 
-    let storage = get_blob_storage();
-    let storage_guard = storage.lock().unwrap();
-    
-    if let Some(blob_data) = storage_guard.get(&blob_id) {
-        let array_buffer = ArrayBuffer::new(ctx.clone(), blob_data.data.clone())?;
-        Ok(array_buffer.into_value())
-    } else {
-        let empty_buffer = ArrayBuffer::new(ctx.clone(), Vec::<u8>::new())?;
-        Ok(empty_buffer.into_value())
-    }
+    let array_buffer = ArrayBuffer::new(ctx.clone(), blob_data.data().to_vec())?;
+    Ok(array_buffer.into_value())
 }
 
 /// Get Uint8Array from blob by ID
-fn blob_bytes<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn blob_bytes<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    if args.is_empty() {
-        return Err(anyhow!("blob_bytes requires 1 argument"));
-    }
+    let blob_id = blob_id_arg(&ctx, &args)?;
 
-    let blob_id = args[0].as_number().ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    let storage_guard = lock_storage();
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
 
-    let storage = get_blob_storage();
-    let storage_guard = storage.lock().unwrap();
-    
-    if let Some(blob_data) = storage_guard.get(&blob_id) {
-        let typed_array: TypedArray<u8> = TypedArray::new(ctx.clone(), blob_data.data.clone())?;
-        Ok(typed_array.as_value().to_owned())
-    } else {
-        let empty_array: TypedArray<u8> = TypedArray::new(ctx.clone(), Vec::<u8>::new())?;
-        Ok(empty_array.as_value().to_owned())
-    }
+    let typed_array: TypedArray<u8> = TypedArray::new(ctx.clone(), blob_data.data().to_vec())?;
+    Ok(typed_array.as_value().to_owned())
 }
 
 /// Get text content from blob by ID
-fn blob_text<'js>(args: Args<'js>) -> Result<Value<'js>> {
+fn blob_text<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    if args.is_empty() {
-        return Err(anyhow!("blob_text requires 1 argument"));
-    }
+    let blob_id = blob_id_arg(&ctx, &args)?;
 
-    let blob_id = args[0].as_number().ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    let storage_guard = lock_storage();
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
 
-    let storage = get_blob_storage();
-    let storage_guard = storage.lock().unwrap();
-    
-    if let Some(blob_data) = storage_guard.get(&blob_id) {
-        let text = String::from_utf8_lossy(&blob_data.data);
-        let js_string = JSString::from_str(ctx.clone(), &text)?;
-        Ok(Value::from_string(js_string))
+    // Decode the same way as `new TextDecoder().decode(...)`
+    let text = text_encoding::decode_lossy(blob_data.data(), false);
+    let js_string = JSString::from_str(ctx.clone(), &text)?;
+    Ok(Value::from_string(js_string))
+}
+
+/// Get a `data:` URL with the base64 encoded content of a blob by ID
+fn blob_data_url<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let blob_id = blob_id_arg(&ctx, &args)?;
+
+    let storage_guard = lock_storage();
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
+
+    let mime_type = if blob_data.mime_type.is_empty() {
+        "application/octet-stream"
     } else {
-        let js_string = JSString::from_str(ctx.clone(), "")?;
-        Ok(Value::from_string(js_string))
-    }
+        &blob_data.mime_type
+    };
+    let url = format!("data:{};base64,{}", mime_type, base64_encode(blob_data.data()));
+    let js_string = JSString::from_str(ctx.clone(), &url)?;
+    Ok(Value::from_string(js_string))
 }
 
 /// Slice a blob and return new blob ID
-fn blob_slice<'js>(args: Args<'js>) -> Result<Value<'js>> {
+///
+/// The slice shares the bytes of the blob, so it doesn't count towards the
+/// storage budget.
+fn blob_slice<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
-    if args.is_empty() {
-        return Err(anyhow!("blob_slice requires at least 1 argument"));
-    }
-
-    let blob_id = args[0].as_number().ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    let blob_id = blob_id_arg(&ctx, &args)?;
 
     let start = if args.len() > 1 && !args[1].is_undefined() {
-        Some(args[1].as_number().unwrap_or(0.0) as i64)
+        Some(to_integer(&args[1])?)
     } else {
         None
     };
 
     let end = if args.len() > 2 && !args[2].is_undefined() {
-        Some(args[2].as_number().unwrap_or(0.0) as i64)
+        Some(to_integer(&args[2])?)
     } else {
         None
     };
@@ -307,62 +711,159 @@ fn blob_slice<'js>(args: Args<'js>) -> Result<Value<'js>> {
         None
     };
 
-    let storage = get_blob_storage();
-    let storage_guard = storage.lock().unwrap();
+    let storage_guard = lock_storage();
     
-    if let Some(blob_data) = storage_guard.get(&blob_id) {
-        let len = blob_data.data.len() as i64;
-        
-        // Calculate actual start and end positions
-        let actual_start = match start {
-            Some(s) if s < 0 => (len + s).max(0) as usize,
-            Some(s) => s.min(len) as usize,
-            None => 0,
-        };
-        
-        let actual_end = match end {
-            Some(e) if e < 0 => (len + e).max(0) as usize,
-            Some(e) => e.min(len) as usize,
-            None => len as usize,
-        };
-        
-        let actual_end = actual_end.max(actual_start);
-        
-        let sliced_data = if actual_start >= blob_data.data.len() {
-            Vec::new()
-        } else {
-            blob_data.data[actual_start..actual_end.min(blob_data.data.len())].to_vec()
-        };
-        
-        // Create new blob with sliced data
-        let new_id = get_next_blob_id();
-        let new_mime_type = content_type.unwrap_or_default();
-        let new_blob_data = BlobData { 
-            data: sliced_data, 
-            mime_type: new_mime_type 
-        };
-        
-        drop(storage_guard); // Release the lock before acquiring it again
-        let storage = get_blob_storage();
-        storage.lock().unwrap().insert(new_id, new_blob_data);
+    let entry = get_entry(&storage_guard, blob_id, strict)?;
+    let blob_data = entry.blob();
+    let len = blob_data.len as i64;
+    
+    // Calculate actual start and end positions
+    let actual_start = match start {
+        Some(s) if s < 0 => (len + s).max(0) as usize,
+        Some(s) => s.min(len) as usize,
+        None => 0,
+    };
+    
+    let actual_end = match end {
+        Some(e) if e < 0 => (len + e).max(0) as usize,
+        Some(e) => e.min(len) as usize,
+        None => len as usize,
+    };
+    
+    let actual_end = actual_end.max(actual_start);
+    
+    // Create new blob referencing the sliced range
+    let new_id = get_next_blob_id();
+    let new_mime_type = content_type.map(normalize_mime_type).unwrap_or_default();
+    let new_blob_data = blob_data.slice(actual_start, actual_end, new_mime_type);
+    
+    drop(entry);
+    drop(storage_guard); // Release the lock before acquiring it again
+    lock_storage().insert(new_id, BlobEntry::Blob(new_blob_data));
 
-        Ok(Value::new_number(ctx, new_id as f64))
-    } else {
-        // Return empty blob on error
-        let new_id = get_next_blob_id();
-        let empty_blob_data = BlobData { 
-            data: Vec::new(), 
-            mime_type: String::new() 
-        };
-        
-        drop(storage_guard);
-        let storage = get_blob_storage();
-        storage.lock().unwrap().insert(new_id, empty_blob_data);
+    Ok(Value::new_number(ctx, new_id as f64))
+}
+
+/// Convert a slice bound to an integer the way WebIDL converts a
+/// `[Clamp] long long`: the value is coerced to a number, NaN becomes 0 and the
+/// fractional part is truncated.
+fn to_integer(value: &Value<'_>) -> Result<i64> {
+    let Coerced(number) = value.get::<Coerced<f64>>()?;
+    // Casting truncates towards zero, saturates, and converts NaN to 0
+    Ok(number as i64)
+}
 
-        Ok(Value::new_number(ctx, new_id as f64))
+/// Create the handle owning the storage entry of a new blob by ID
+fn blob_handle<'js>(args: Args<'js>) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let blob_id = blob_id_arg(&ctx, &args)?;
+    handle(ctx, blob_id)
+}
+
+/// Keep the storage entry of a blob by ID after its blob object is garbage
+/// collected, since the host may read it through a [`BlobRegistry`]
+fn blob_pin<'js>(args: Args<'js>) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let blob_id = blob_id_arg(&ctx, &args)?;
+    get_pinned_blob_ids().lock().unwrap().insert(blob_id);
+    Ok(Value::new_undefined(ctx))
+}
+
+/// A `TypeError` with the given message, to be thrown in JavaScript
+fn type_error(ctx: &Ctx<'_>, message: &str) -> Error {
+    Error::from(Exception::throw_type(ctx, message))
+}
+
+/// The blob ID passed as the first argument of a helper
+fn blob_id_arg(ctx: &Ctx<'_>, args: &[Value<'_>]) -> Result<u32> {
+    match args.first().and_then(Value::as_number) {
+        Some(id) => Ok(id as u32),
+        None => Err(type_error(ctx, "Blob ID must be a number")),
     }
 }
 
+/// Create an object URL for a blob by ID
+///
+/// The URL keeps its own copy of the storage entry, sharing the blob's
+/// bytes, until it's revoked.
+fn blob_create_object_url<'js>(args: Args<'js>, strict: bool) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let blob_id = blob_id_arg(&ctx, &args)?;
+    let entry_id = copy_entry(blob_id, strict)?;
+
+    let url = format!("{OBJECT_URL_PREFIX}{}", random_uuid());
+    get_object_urls()
+        .lock()
+        .unwrap()
+        .insert(url.clone(), entry_id);
+
+    let js_string = JSString::from_str(ctx, &url)?;
+    Ok(Value::from_string(js_string))
+}
+
+/// Get the blob ID an object URL refers to, or `undefined` if the URL was
+/// revoked or never created
+fn blob_resolve_object_url<'js>(args: Args<'js>) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let url = match args.first() {
+        Some(url) if url.is_string() => val_to_string(&ctx, url.clone())?,
+        _ => return Ok(Value::new_undefined(ctx)),
+    };
+    let urls = get_object_urls().lock().unwrap();
+    Ok(match urls.get(&url) {
+        Some(id) => Value::new_number(ctx, *id as f64),
+        None => Value::new_undefined(ctx),
+    })
+}
+
+/// Revoke an object URL, releasing its storage entry
+fn blob_revoke_object_url<'js>(args: Args<'js>) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    let url = match args.first() {
+        Some(url) => val_to_string(&ctx, url.clone())?,
+        None => return Ok(Value::new_undefined(ctx)),
+    };
+    if let Some(id) = get_object_urls().lock().unwrap().remove(&url) {
+        get_pinned_blob_ids().lock().unwrap().remove(&id);
+        lock_storage().remove(&id);
+    }
+    Ok(Value::new_undefined(ctx))
+}
+
+/// A random version 4 UUID
+fn random_uuid() -> String {
+    let mut bytes = fastrand::u128(..).to_be_bytes();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+/// The current time in milliseconds since the Unix epoch.
+fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
 #[derive(Default)]
 struct BlobOptions {
     mime_type: String,
@@ -376,41 +877,109 @@ struct FileOptions {
     last_modified: Option<u64>,
 }
 
-fn parse_blob_options<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<BlobOptions> {
-    if let Some(obj) = value.as_object() {
-        let mut options = BlobOptions::default();
+/// Normalize a MIME type as described in the File API specification: any
+/// character outside the printable ASCII range (U+0020 to U+007E) results in
+/// the empty string, otherwise the type is converted to ASCII lowercase.
+fn normalize_mime_type(mime_type: String) -> String {
+    if mime_type.chars().all(|c| ('\u{20}'..='\u{7E}').contains(&c)) {
+        mime_type.to_ascii_lowercase()
+    } else {
+        String::new()
+    }
+}
 
-        if let Ok(type_val) = obj.get::<_, Value>("type") {
-            options.mime_type = val_to_string(ctx, type_val)?;
-        }
+/// Magic bytes of the content types detected when sniffing.
+const MAGIC_BYTES: &[(&[u8], &str)] = &[
+    (b"\x89PNG\r\n\x1a\n", "image/png"),
+    (b"\xFF\xD8\xFF", "image/jpeg"),
+    (b"GIF87a", "image/gif"),
+    (b"GIF89a", "image/gif"),
+    (b"%PDF-", "application/pdf"),
+    (b"PK\x03\x04", "application/zip"),
+];
+
+/// Detect the MIME type of `data` from its magic bytes.
+fn sniff_mime_type(data: &[u8]) -> Option<&'static str> {
+    if data.len() >= 12 && &data[..4] == b"RIFF" && &data[8..12] == b"WEBP" {
+        return Some("image/webp");
+    }
 
-        if let Ok(endings_val) = obj.get::<_, Value>("endings") {
-            let endings = val_to_string(ctx, endings_val)?;
-            if endings == "native" || endings == "transparent" {
-                options.endings = endings;
-            }
-        }
+    MAGIC_BYTES
+        .iter()
+        .find(|(magic, _)| data.starts_with(magic))
+        .map(|(_, mime_type)| *mime_type)
+}
 
-        Ok(options)
+/// The type of a new blob, sniffed from `data` if enabled and no type was
+/// given.
+fn resolve_mime_type(mime_type: String, data: &[u8], sniff_type: bool) -> String {
+    if sniff_type && mime_type.is_empty() {
+        sniff_mime_type(data).unwrap_or_default().to_string()
+    } else {
+        mime_type
+    }
+}
+
+/// The `type` option of a blob or file, the empty string if missing.
+fn parse_type_option<'a>(ctx: &Ctx<'a>, options: &Object<'a>) -> Result<String> {
+    let type_val: Value = options.get("type")?;
+    if type_val.is_undefined() || type_val.is_null() {
+        Ok(String::new())
+    } else {
+        Ok(normalize_mime_type(val_to_string(ctx, type_val)?))
+    }
+}
+
+/// The `endings` option of a blob or file, the empty string if missing.
+fn parse_endings_option<'a>(ctx: &Ctx<'a>, options: &Object<'a>) -> Result<String> {
+    let endings_val: Value = options.get("endings")?;
+    if endings_val.is_undefined() {
+        return Ok(String::new());
+    }
+    let endings = val_to_string(ctx, endings_val)?;
+    if endings == "native" || endings == "transparent" {
+        Ok(endings)
+    } else {
+        Err(type_error(
+            ctx,
+            &format!("The provided value '{endings}' is not a valid enum value of type EndingType."),
+        ))
+    }
+}
+
+/// Fails with a `TypeError` unless `value` is an options object, `undefined`
+/// or `null`.
+fn check_options<'a>(ctx: &Ctx<'a>, value: &Value<'a>, constructor: &str, kind: &str) -> Result<()> {
+    if value.is_object() || value.is_undefined() || value.is_null() {
+        Ok(())
+    } else {
+        Err(type_error(
+            ctx,
+            &format!("Failed to construct '{constructor}': The provided value is not of type '{kind}'."),
+        ))
+    }
+}
+
+fn parse_blob_options<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<BlobOptions> {
+    check_options(ctx, &value, "Blob", "BlobPropertyBag")?;
+    if let Some(obj) = value.as_object() {
+        Ok(BlobOptions {
+            mime_type: parse_type_option(ctx, obj)?,
+            endings: parse_endings_option(ctx, obj)?,
+        })
     } else {
         Ok(BlobOptions::default())
     }
 }
 
 fn parse_file_options<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<FileOptions> {
+    check_options(ctx, &value, "File", "FilePropertyBag")?;
     if let Some(obj) = value.as_object() {
-        let mut options = FileOptions::default();
-
-        if let Ok(type_val) = obj.get::<_, Value>("type") {
-            options.mime_type = val_to_string(ctx, type_val)?;
-        }
-
-        if let Ok(endings_val) = obj.get::<_, Value>("endings") {
-            let endings = val_to_string(ctx, endings_val)?;
-            if endings == "native" || endings == "transparent" {
-                options.endings = endings;
-            }
-        }
+        let mut options = FileOptions {
+            mime_type: parse_type_option(ctx, obj)?,
+            endings: parse_endings_option(ctx, obj)?,
+            ..Default::default()
+        };
 
         if let Ok(last_modified_val) = obj.get::<_, Value>("lastModified") {
             if let Some(num) = last_modified_val.as_number() {
@@ -424,7 +993,48 @@ fn parse_file_options<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<FileOptions
     }
 }
 
-fn parse_blob_parts<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<Vec<Vec<u8>>> {
+/// The line ending used for `endings: "native"`. Javy targets WASI, so this is
+/// always a line feed.
+const NATIVE_LINE_ENDING: &str = "\n";
+
+/// Convert line endings in a string part to the native line ending, as
+/// described in the File API specification: `\r\n` and lone `\r` and `\n`
+/// are all replaced.
+fn convert_line_endings_to_native(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\r' => {
+                chars.next_if_eq(&'\n');
+                result.push_str(NATIVE_LINE_ENDING);
+            }
+            '\n' => result.push_str(NATIVE_LINE_ENDING),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Parse blob parts into bytes. When `native_endings` is true, line endings in
+/// string parts are converted to the native line ending. Primitive values
+/// aren't sequences of parts and fail with a `TypeError` naming the
+/// `constructor`.
+fn parse_blob_parts<'a>(
+    ctx: &Ctx<'a>,
+    value: Value<'a>,
+    native_endings: bool,
+    constructor: &str,
+) -> Result<Vec<Vec<u8>>> {
+    if !value.is_object() {
+        return Err(type_error(
+            ctx,
+            &format!(
+                "Failed to construct '{constructor}': The provided value cannot be converted to a sequence."
+            ),
+        ));
+    }
+
     let mut parts = Vec::new();
 
     if value.is_array() {
@@ -432,26 +1042,69 @@ fn parse_blob_parts<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<Vec<Vec<u8>>>
             let len = array.len();
             for i in 0..len {
                 if let Ok(item) = array.get::<_, Value>(i as u32) {
-                    let part_data = convert_to_bytes(ctx, item)?;
+                    let part_data = convert_to_bytes(ctx, item, native_endings)?;
                     parts.push(part_data);
                 }
             }
         }
     } else {
-        // Single item, treat as array with one element
-        let part_data = convert_to_bytes(ctx, value)?;
-        parts.push(part_data);
+        let object = value.as_object().unwrap();
+        let iterator: Value = object.get(Symbol::iterator(ctx.clone()))?;
+        if let Some(iterator) = iterator.as_function() {
+            // Iterables, such as a `Set` or the result of a generator
+            let iterator: Object = iterator.call((This(value.clone()),))?;
+            let next: Function = iterator.get("next")?;
+            loop {
+                let result: Object = next.call((This(iterator.clone()),))?;
+                if result.get::<_, bool>("done")? {
+                    break;
+                }
+                parts.push(convert_to_bytes(ctx, result.get("value")?, native_endings)?);
+            }
+        } else if object.contains_key("length")? {
+            // Array-likes, such as `{ length: 1, 0: 'a' }`
+            let len = object.get::<_, f64>("length")? as u32;
+            for i in 0..len {
+                parts.push(convert_to_bytes(ctx, object.get(i)?, native_endings)?);
+            }
+        } else {
+            return Err(type_error(
+                ctx,
+                &format!(
+                    "Failed to construct '{constructor}': The object must have a callable @@iterator property."
+                ),
+            ));
+        }
     }
 
     Ok(parts)
 }
 
-fn convert_to_bytes<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<Vec<u8>> {
+/// The ID of a blob part that's a `Blob` or a `File`, if it is one.
+fn blob_part_id<'a>(ctx: &Ctx<'a>, obj: &Object<'a>) -> Result<Option<u32>> {
+    let blob: Value = ctx.globals().get("Blob")?;
+    if !blob.is_function() || !obj.is_instance_of(&blob) {
+        return Ok(None);
+    }
+    Ok(obj.get::<_, Option<u32>>("_blobId")?)
+}
+
+fn convert_to_bytes<'a>(ctx: &Ctx<'a>, value: Value<'a>, native_endings: bool) -> Result<Vec<u8>> {
+    let string_to_bytes = |s: String| {
+        if native_endings {
+            convert_line_endings_to_native(&s).into_bytes()
+        } else {
+            s.into_bytes()
+        }
+    };
+
     if value.is_string() {
         let s = val_to_string(ctx, value)?;
-        Ok(s.into_bytes())
+        Ok(string_to_bytes(s))
     } else if let Some(obj) = value.as_object() {
-        if let Some(array_buffer) = obj.as_array_buffer() {
+        if let Some(id) = blob_part_id(ctx, obj)? {
+            with_data(id, <[u8]>::to_vec)
+        } else if let Some(array_buffer) = obj.as_array_buffer() {
             if let Some(bytes) = array_buffer.as_bytes() {
                 Ok(bytes.to_vec())
             } else {
@@ -489,20 +1142,38 @@ fn convert_to_bytes<'a>(ctx: &Ctx<'a>, value: Value<'a>) -> Result<Vec<u8>> {
             } else {
                 // Try to convert to string as fallback
                 let s = val_to_string(ctx, value)?;
-                Ok(s.into_bytes())
+                Ok(string_to_bytes(s))
             }
         }
     } else {
         // Try to convert to string as fallback
         let s = val_to_string(ctx, value)?;
-        Ok(s.into_bytes())
+        Ok(string_to_bytes(s))
     }
 }
 
+/// The number of bytes held in storage by the blobs with the given IDs,
+/// counting bytes shared between blobs only once.
+#[cfg(test)]
+fn stored_bytes(ids: &[u32]) -> usize {
+    let storage = lock_storage();
+    unique_bytes(ids.iter().filter_map(|id| storage.get(id).map(BlobEntry::blob)))
+}
+
+/// The number of bytes held by the given blobs, counting bytes shared
+/// between blobs only once.
+fn unique_bytes<'a>(blobs: impl Iterator<Item = &'a BlobData>) -> usize {
+    let mut seen = HashSet::new();
+    blobs
+        .filter(|blob_data| seen.insert(Arc::as_ptr(&blob_data.bytes) as *const u8))
+        .map(|blob_data| blob_data.bytes.data.len())
+        .sum()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{Config, Runtime};
+    use crate::{quickjs::Module, Config, Runtime};
     use anyhow::Error;
 
     #[test]
@@ -510,7 +1181,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Check that Blob is available
             let result: Value = cx.eval("typeof Blob")?;
@@ -532,7 +1203,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test empty blob
             let result: Value = cx.eval("new Blob()")?;
@@ -556,7 +1227,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test size property
             let result: Value = cx.eval("new Blob(['hello']).size")?;
@@ -582,7 +1253,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test text method
             let result: Value = cx.eval("new Blob(['hello world']).text()")?;
@@ -604,7 +1275,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test basic slice
             let result: Value = cx.eval("new Blob(['hello world']).slice(0, 5).text()")?;
@@ -626,12 +1297,487 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_blob_slice_coerces_bounds() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let cases = [
+                ("slice('2')", "llo world"),
+                ("slice('2', '-2')", "llo wor"),
+                ("slice(1.9)", "ello world"),
+                ("slice(-1.9)", "d"),
+                ("slice(1.5, 4.9)", "ell"),
+                ("slice('abc', 2)", "he"),
+                ("slice(null, 2)", "he"),
+                ("slice({ valueOf: () => 6 })", "world"),
+                ("slice(-Infinity, Infinity)", "hello world"),
+            ];
+            for (js, expected) in cases {
+                let result: String = cx.eval(format!("new Blob(['hello world']).{js}.text()"))?;
+                assert_eq!(result, expected, "{js}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_mime_type_normalization() -> Result<()> {
+        let config = Config::default();
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            register(cx.clone(), BlobSettings::default())?;
+
+            // Test uppercase type is lowercased
+            let result: Value = cx.eval("new Blob([], { type: 'Text/HTML' }).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "text/html");
+
+            // Test type with a non-ASCII character is replaced with the empty string
+            let result: Value = cx.eval("new Blob([], { type: 'text/plain\u{e9}' }).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "");
+
+            // Test File type is normalized
+            let result: Value = cx.eval("new File([], 'test.txt', { type: 'TEXT/Plain' }).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "text/plain");
+
+            // Test slice content type is normalized
+            let result: Value = cx.eval("new Blob(['test']).slice(0, 2, 'Application/JSON').type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "application/json");
+
+            let result: Value = cx.eval("new Blob(['test']).slice(0, 2, 'text/\u{2603}').type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "");
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_slice_shares_storage() -> Result<()> {
+        let config = Config::default();
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            register(cx.clone(), BlobSettings::default())?;
+
+            let ids: Vec<u32> = cx.eval(
+                r#"
+                const blob = new Blob([new Uint8Array(1024 * 1024)]);
+                const ids = [blob._blobId];
+                for (let i = 0; i < 100; i++) {
+                    const window = blob.slice(i * 1024, (i + 1) * 1024);
+                    ids.push(window._blobId);
+                    ids.push(window.slice(1, -1)._blobId);
+                }
+                ids
+                "#,
+            )?;
+            assert_eq!(ids.len(), 201);
+            assert_eq!(stored_bytes(&ids), 1024 * 1024);
+
+            // Nested slices resolve relative to their parent slice
+            let result: Value = cx.eval("new Blob(['hello world']).slice(2).slice(1, -2).text()")?;
+            let text = val_to_string(&cx, result)?;
+            assert_eq!(text, "lo wor");
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_storage_bytes() -> Result<()> {
+        let mut config = Config::default();
+        config.max_blob_storage_bytes(1024);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const results = [];
+                const error = (f) => {
+                    try { f(); return 'no error'; } catch (e) { return `${e.name}: ${e.message}`; }
+                };
+                let first = new Blob([new Uint8Array(600)]);
+                results.push(error(() => new Blob([new Uint8Array(600)])));
+                results.push(error(() => new File([new Uint8Array(600)], 'a.bin')));
+
+                // Slices share the bytes of their blob
+                const slices = [];
+                for (let i = 0; i < 10; i++) {
+                    slices.push(first.slice(i, i + 100));
+                }
+                results.push(slices.reduce((size, slice) => size + slice.size, 0));
+
+                // The bytes are released once the blob and its slices are
+                // garbage collected
+                first = null;
+                results.push(error(() => new Blob([new Uint8Array(600)])));
+                slices.length = 0;
+                results.push(error(() => new Blob([new Uint8Array(600)])));
+                results.join("; ")
+                "#,
+            )?;
+            assert_eq!(
+                result,
+                "RangeError: Blob storage limit exceeded: 600 bytes requested, 600 of 1024 bytes in use; \
+                 RangeError: Blob storage limit exceeded: 600 bytes requested, 600 of 1024 bytes in use; \
+                 1000; \
+                 RangeError: Blob storage limit exceeded: 600 bytes requested, 600 of 1024 bytes in use; \
+                 no error"
+            );
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_garbage_collected_blobs_are_released() -> Result<()> {
+        let runtime = Runtime::default();
+        let ids: Vec<u32> = runtime.context().with(|cx| {
+            cx.eval(
+                r#"
+                (() => {
+                    const released = new Blob(['released']);
+                    const pinned = new Blob(['pinned']);
+                    return [released._blobId, Javy.Blob.idOf(pinned)];
+                })()
+                "#,
+            )
+        })?;
+        let (released, pinned) = (ids[0], ids[1]);
+        runtime.context().with(|cx| {
+            // Locking the storage removes the released entries
+            cx.eval::<(), _>("new Blob([])")?;
+            Ok::<_, Error>(())
+        })?;
+
+        let registry = blob_registry();
+        assert_eq!(None, registry.get(released));
+        assert_eq!(
+            Some((b"pinned".to_vec(), String::new())),
+            registry.get(pinned)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_weak_map_handles_are_released() -> Result<()> {
+        let mut config = Config::default();
+        config.blob_weak_map_handles(true);
+        let runtime = Runtime::new(config)?;
+        let ids: Vec<u32> = runtime.context().with(|cx| {
+            cx.eval(
+                r#"
+                (() => {
+                    const released = new Blob(['released']);
+                    const kept = new File(['kept'], 'kept.txt');
+                    globalThis.kept = kept;
+                    if ('_blobHandle' in released || '_blobHandle' in kept) {
+                        throw new Error('handles should be held in a WeakMap');
+                    }
+                    return [released._blobId, kept._blobId];
+                })()
+                "#,
+            )
+        })?;
+        let (released, kept) = (ids[0], ids[1]);
+        runtime.context().with(|cx| {
+            // Locking the storage removes the released entries
+            cx.eval::<(), _>("new Blob([])")?;
+            Ok::<_, Error>(())
+        })?;
+
+        let registry = blob_registry();
+        assert_eq!(None, registry.get(released));
+        assert_eq!(Some((b"kept".to_vec(), String::new())), registry.get(kept));
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("delete globalThis.kept; new Blob([])")?;
+            Ok::<_, Error>(())
+        })?;
+        assert_eq!(None, registry.get(kept));
+        Ok(())
+    }
+
+    #[test]
+    fn test_file_reader() -> Result<()> {
+        let config = Config::default();
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.events = [];
+                globalThis.results = {};
+                function read(method, blob, key) {
+                    const reader = new FileReader();
+                    reader.onload = (e) => {
+                        events.push(key + ":" + e.type);
+                        results[key] = reader.result;
+                    };
+                    reader.onloadend = (e) => events.push(key + ":" + e.type + ":" + reader.readyState);
+                    reader[method](blob);
+                    events.push(key + ":" + reader.readyState);
+                }
+                const blob = new Blob(['hello world'], { type: 'text/plain' });
+                read('readAsText', blob, 'text');
+                read('readAsArrayBuffer', blob, 'buffer');
+                read('readAsDataURL', blob, 'url');
+                read('readAsText', blob.slice(6), 'slice');
+                "#,
+            )?;
+
+            // No events are delivered synchronously
+            let events: Vec<String> = cx.eval("events")?;
+            assert_eq!(events, ["text:1", "buffer:1", "url:1", "slice:1"]);
+            Ok::<_, Error>(())
+        })?;
+
+        runtime.resolve_pending_jobs()?;
+
+        runtime.context().with(|cx| {
+            let events: Vec<String> = cx.eval("events.slice(4)")?;
+            assert_eq!(
+                events,
+                [
+                    "text:load", "text:loadend:2",
+                    "buffer:load", "buffer:loadend:2",
+                    "url:load", "url:loadend:2",
+                    "slice:load", "slice:loadend:2",
+                ]
+            );
+
+            let text: String = cx.eval("results.text")?;
+            assert_eq!(text, "hello world");
+
+            let length: usize = cx.eval("results.buffer instanceof ArrayBuffer ? results.buffer.byteLength : -1")?;
+            assert_eq!(length, 11);
+
+            let url: String = cx.eval("results.url")?;
+            assert_eq!(url, "data:text/plain;base64,aGVsbG8gd29ybGQ=");
+
+            let text: String = cx.eval("results.slice")?;
+            assert_eq!(text, "world");
+
+            // Reading something other than a Blob throws
+            assert!(cx.eval::<(), _>("new FileReader().readAsText('nope')").is_err());
+
+            // Reading while a read is in progress throws an InvalidStateError
+            let name: String = cx.eval(
+                "const busy = new FileReader(); busy.readAsText(new Blob(['a'])); try { busy.readAsText(new Blob(['b'])); 'no error' } catch (e) { e.name }",
+            )?;
+            assert_eq!(name, "InvalidStateError");
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_registry() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let module = Module::declare(
+                cx.clone(),
+                "function.mjs",
+                "export function run() { globalThis.outputBlobId = Javy.Blob.idOf(new Blob(['hello'], { type: 'text/plain' })); }",
+            )?;
+            let (module, _) = module.eval()?;
+            let run: Function = module.get("run")?;
+            run.call::<_, ()>(())?;
+
+            // Test non-blob values are rejected
+            let result: Value = cx.eval("try { Javy.Blob.idOf({}); 'no error' } catch (e) { e.name }")?;
+            assert_eq!(val_to_string(&cx, result)?, "TypeError");
+            Ok::<_, Error>(())
+        })?;
+
+        let id: u32 = runtime
+            .context()
+            .with(|cx| cx.globals().get("outputBlobId"))?;
+        let registry = blob_registry();
+        assert_eq!(
+            Some((b"hello".to_vec(), "text/plain".to_string())),
+            registry.get(id)
+        );
+        assert_eq!(None, registry.get(u32::MAX));
+        Ok(())
+    }
+
+    #[test]
+    fn test_object_url() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.url = URL.createObjectURL(new Blob(['hello'], { type: 'text/plain' }));
+                globalThis.urlBlobId = Javy.Blob.idOf(url);
+                globalThis.reader = new FileReader();
+                reader.readAsText(url);
+                "#,
+            )?;
+
+            let url: String = cx.eval("url")?;
+            let uuid = url.strip_prefix("blob:javy-internal/").unwrap();
+            assert_eq!(uuid.len(), 36);
+            assert_eq!(&uuid[14..15], "4");
+
+            // Each call creates a new URL
+            let distinct: bool = cx.eval("URL.createObjectURL(new Blob([])) !== URL.createObjectURL(new Blob([]))")?;
+            assert!(distinct);
+
+            let result: Value = cx.eval("try { URL.createObjectURL('nope'); 'no error' } catch (e) { e.name }")?;
+            assert_eq!(val_to_string(&cx, result)?, "TypeError");
+            Ok::<_, Error>(())
+        })?;
+
+        runtime.resolve_pending_jobs()?;
+
+        let id: u32 = runtime.context().with(|cx| {
+            let text: String = cx.eval("reader.result")?;
+            assert_eq!(text, "hello");
+            cx.globals().get("urlBlobId")
+        })?;
+        let registry = blob_registry();
+        assert_eq!(
+            Some((b"hello".to_vec(), "text/plain".to_string())),
+            registry.get(id)
+        );
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("URL.revokeObjectURL(url); URL.revokeObjectURL(url);")?;
+
+            // Revoked URLs no longer resolve
+            let result: Value = cx.eval("try { Javy.Blob.idOf(url); 'no error' } catch (e) { e.name }")?;
+            assert_eq!(val_to_string(&cx, result)?, "TypeError");
+            let result: Value = cx.eval("try { new FileReader().readAsText(url); 'no error' } catch (e) { e.name }")?;
+            assert_eq!(val_to_string(&cx, result)?, "TypeError");
+            Ok::<_, Error>(())
+        })?;
+
+        // The entry kept alive by the URL is dropped
+        assert_eq!(None, registry.get(id));
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_text_matches_text_decoder() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let cases = [
+                // UTF-8 BOM followed by "hi"
+                ("[0xEF, 0xBB, 0xBF, 0x68, 0x69]", "hi"),
+                // Invalid continuation bytes
+                ("[0x61, 0xC3, 0x28, 0x62, 0xE2, 0x82]", "a\u{FFFD}(b\u{FFFD}"),
+                // Only the leading BOM is stripped
+                ("[0xEF, 0xBB, 0xBF, 0xEF, 0xBB, 0xBF]", "\u{FEFF}"),
+            ];
+            for (bytes, expected) in cases {
+                let blob = format!("new Blob([new Uint8Array({bytes})])");
+                let text: String = cx.eval(format!("{blob}.text()"))?;
+                let decoded: String =
+                    cx.eval(format!("new TextDecoder().decode({blob}.arrayBuffer())"))?;
+                assert_eq!(expected, text, "unexpected text for {bytes}");
+                assert_eq!(decoded, text, "text differs from TextDecoder for {bytes}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_endings() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            register(cx.clone(), BlobSettings::default())?;
+
+            let cases = [
+                (r#""a\r\nb""#, "transparent", 4, "a\r\nb"),
+                (r#""a\r\nb""#, "native", 3, "a\nb"),
+                (r#""a\rb""#, "transparent", 3, "a\rb"),
+                (r#""a\rb""#, "native", 3, "a\nb"),
+                (r#""a\r\nb\rc\nd\r""#, "transparent", 9, "a\r\nb\rc\nd\r"),
+                (r#""a\r\nb\rc\nd\r""#, "native", 8, "a\nb\nc\nd\n"),
+            ];
+            for (part, endings, size, text) in cases {
+                let blob = format!("new Blob([{part}], {{ endings: '{endings}' }})");
+                let result: Value = cx.eval(format!("{blob}.size"))?;
+                assert_eq!(result.as_number().unwrap(), size as f64);
+
+                let result: Value = cx.eval(format!("{blob}.text()"))?;
+                let result_str = val_to_string(&cx, result)?;
+                assert_eq!(result_str, text);
+            }
+
+            // Test files convert line endings
+            let result: Value =
+                cx.eval("new File(['a\\r\\nb'], 'a.txt', { endings: 'native' }).size")?;
+            assert_eq!(result.as_number().unwrap(), 3.0);
+
+            // Test binary parts are left untouched
+            let result: Value = cx.eval(
+                "new Blob([new Uint8Array([0x61, 0x0D, 0x0A, 0x62])], { endings: 'native' }).size",
+            )?;
+            assert_eq!(result.as_number().unwrap(), 4.0);
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_blob_sniff_type() -> Result<()> {
+        let mut config = Config::default();
+        config.blob_sniff_type(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            // Test PNG magic bytes are detected
+            let result: Value = cx.eval(
+                "new File([new Uint8Array([0x89, 0x50, 0x4E, 0x47, 0x0D, 0x0A, 0x1A, 0x0A, 0, 0])], 'image.png').type",
+            )?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "image/png");
+
+            // Test PDF magic bytes are detected for blobs
+            let result: Value = cx.eval("new Blob(['%PDF-1.7']).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "application/pdf");
+
+            // Test an explicit type takes precedence
+            let result: Value = cx.eval("new Blob(['%PDF-1.7'], { type: 'text/plain' }).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "text/plain");
+
+            // Test unknown content falls back to the empty string
+            let result: Value = cx.eval("new Blob(['hello']).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "");
+
+            Ok::<_, Error>(())
+        })?;
+
+        // Test sniffing is disabled by default
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|cx| {
+            let result: Value = cx.eval("new Blob(['%PDF-1.7']).type")?;
+            let type_str = val_to_string(&cx, result)?;
+            assert_eq!(type_str, "");
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
     #[test]
     fn test_file_constructor() -> Result<()> {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test basic file
             let result: Value = cx.eval("new File(['content'], 'test.txt')")?;
@@ -651,7 +1797,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test name property
             let result: Value = cx.eval("new File(['content'], 'test.txt').name")?;
@@ -677,12 +1823,42 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_file_metadata_storage() -> Result<()> {
+        let config = Config::default();
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            register(cx.clone(), BlobSettings::default())?;
+
+            // Test name survives slicing, and the slice is a plain Blob
+            let result: Value = cx.eval(
+                "const f = new File(['hello world'], 'test.txt'); const s = f.slice(0, 5); [f.name, s instanceof File, typeof s.name].join(',')",
+            )?;
+            let text = val_to_string(&cx, result)?;
+            assert_eq!(text, "test.txt,false,undefined");
+
+            // Test name and lastModified can't be reassigned
+            let result: Value = cx.eval(
+                "const g = new File([], 'a.txt', { lastModified: 42 }); try { g.name = 'b.txt'; } catch (e) {} try { g.lastModified = 0; } catch (e) {} g.name + ':' + g.lastModified",
+            )?;
+            let text = val_to_string(&cx, result)?;
+            assert_eq!(text, "a.txt:42");
+
+            // Test default lastModified is a positive number from the clock
+            let result: Value = cx.eval("new File([], 'c.txt').lastModified")?;
+            assert!(result.as_number().unwrap() > 0.0);
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
     #[test]
     fn test_file_inherited_methods() -> Result<()> {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test inherited text method
             let result: Value = cx.eval("new File(['hello world'], 'test.txt').text()")?;
@@ -704,7 +1880,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test multiple string parts
             let result: Value = cx.eval("new Blob(['hello', ' ', 'world']).text()")?;
@@ -720,12 +1896,33 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_blob_parts() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const hello = new Blob(["hello"]);
+                const file = new File([hello, " ", hello.slice(1, 3)], "a.txt");
+                const blob = new Blob([file, "!"]);
+                [file.size, blob.size].join(" ")
+                "#,
+            )?;
+            assert_eq!("8 9", result);
+            let result: Value = cx.eval("blob.text()")?;
+            let text = val_to_string(&cx, result)?;
+            assert_eq!("hello el!", text);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
     #[test]
     fn test_edge_cases() -> Result<()> {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test slice beyond bounds
             let result: Value = cx.eval("new Blob(['hello']).slice(10, 20).size")?;
@@ -736,20 +1933,219 @@ mod tests {
             assert_eq!(result.as_number().unwrap() as u64, 0);
             
             // Test File constructor with missing arguments
-            let result = cx.eval::<Value, _>("new File(['content'])");
-            assert!(result.is_err());
+            let result: String = cx.eval("try { new File(['content']); 'no error' } catch (e) { `${e.name}: ${e.message}` }")?;
+            assert_eq!(
+                result,
+                "TypeError: Failed to construct 'File': 2 arguments required, but only 1 present."
+            );
             
             Ok::<_, Error>(())
         })?;
         Ok(())
     }
 
+    #[test]
+    fn test_iterable_blob_parts() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let cases = [
+                ("new Blob(new Set(['a', 'b', 'a']))", "ab"),
+                ("new Blob((function* () { yield 'a'; yield new Uint8Array([98]); })())", "ab"),
+                ("new Blob({ length: 2, 0: 'a', 1: 'b' })", "ab"),
+                ("new File(new Set(['a', 'b']), 'a.txt')", "ab"),
+            ];
+            for (js, expected) in cases {
+                let result: String = cx.eval(format!("{js}.text()"))?;
+                assert_eq!(result, expected, "{js}");
+            }
+
+            // Errors thrown while iterating propagate
+            let result: String = cx.eval(
+                r#"
+                try {
+                    new Blob((function* () { throw new Error('oops'); })());
+                    'no error'
+                } catch (e) { e.message }
+                "#,
+            )?;
+            assert_eq!(result, "oops");
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_type_errors() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.error = (f) => {
+                    try { f(); return 'no error'; } catch (e) { return `${e.name}: ${e.message}`; }
+                };
+                "#,
+            )?;
+
+            let cases = [
+                ("new Blob('hello')", "TypeError: Failed to construct 'Blob': The provided value cannot be converted to a sequence."),
+                ("new Blob(42)", "TypeError: Failed to construct 'Blob': The provided value cannot be converted to a sequence."),
+                ("new Blob(null)", "TypeError: Failed to construct 'Blob': The provided value cannot be converted to a sequence."),
+                ("new File(42, 'a.txt')", "TypeError: Failed to construct 'File': The provided value cannot be converted to a sequence."),
+                ("new Blob({})", "TypeError: Failed to construct 'Blob': The object must have a callable @@iterator property."),
+                ("new Blob([], 'text/plain')", "TypeError: Failed to construct 'Blob': The provided value is not of type 'BlobPropertyBag'."),
+                ("new File([], 'a.txt', 1)", "TypeError: Failed to construct 'File': The provided value is not of type 'FilePropertyBag'."),
+                ("new Blob([], { endings: 'unix' })", "TypeError: The provided value 'unix' is not a valid enum value of type EndingType."),
+                ("new File([])", "TypeError: Failed to construct 'File': 2 arguments required, but only 1 present."),
+            ];
+            for (js, expected) in cases {
+                let result: String = cx.eval(format!("error(() => {js})"))?;
+                assert_eq!(result, expected, "{js}");
+            }
+
+            // Options aren't modified, and a missing or null type is empty
+            let result: String = cx.eval("const options = {}; new Blob([], options); JSON.stringify(options)")?;
+            assert_eq!(result, "{}");
+            let result: String = cx.eval("new Blob([], { type: null }).type")?;
+            assert_eq!(result, "");
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_blob_id() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.error = (f) => {
+                    try { f(); return 'no error'; } catch (e) { return e.message; }
+                };
+                globalThis.blob = Object.create(Blob.prototype);
+                blob._blobId = 4294967295;
+                "#,
+            )?;
+
+            for js in ["blob.size", "blob.type", "blob.text()", "blob.arrayBuffer()", "blob.bytes()", "blob.slice(1)", "URL.createObjectURL(blob)"] {
+                let result: String = cx.eval(format!("error(() => {js})"))?;
+                assert_eq!(result, "Invalid blob ID: 4294967295", "{js}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_prototype_shape() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let checks = [
+                (
+                    "JSON.stringify(Object.getOwnPropertyDescriptor(Blob.prototype, 'size'), (k, v) => typeof v === 'function' ? typeof v : v)",
+                    r#"{"get":"function","enumerable":true,"configurable":true}"#,
+                ),
+                ("typeof Object.getOwnPropertyDescriptor(Blob.prototype, 'type').get", "function"),
+                ("typeof Object.getOwnPropertyDescriptor(File.prototype, 'name').get", "function"),
+                ("Object.getOwnPropertyDescriptor(File.prototype, 'lastModified').enumerable", "true"),
+                ("Object.getOwnPropertyDescriptor(Blob.prototype, 'text').enumerable", "false"),
+                ("Object.getOwnPropertyDescriptor(Blob.prototype, 'slice').writable", "true"),
+                ("Object.keys(new Blob(['a'])).length", "0"),
+                ("Object.keys(new File(['a'], 'a.txt')).length", "0"),
+                ("new Blob([]).constructor.name", "Blob"),
+                ("new File([], 'a.txt').constructor.name", "File"),
+                ("Object.prototype.toString.call(new Blob([]))", "[object Blob]"),
+                ("Object.prototype.toString.call(new File([], 'a.txt'))", "[object File]"),
+                ("Object.prototype.toString.call(new FileReader())", "[object FileReader]"),
+                ("Object.prototype.toString.call(new Blob(['abc']).slice(1))", "[object Blob]"),
+                ("new File([], 'a.txt') instanceof Blob", "true"),
+                ("Object.getPrototypeOf(File) === Blob", "true"),
+            ];
+            for (js, expected) in checks {
+                let result: Value = cx.eval(js)?;
+                assert_eq!(val_to_string(&cx, result)?, expected, "{js}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_subclass() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                class MyBlob extends Blob {
+                    constructor(text) {
+                        super([text], { type: "text/plain" });
+                    }
+
+                    shout() {
+                        return this.text().toUpperCase();
+                    }
+                }
+                globalThis.blob = new MyBlob("hello");
+                "#,
+            )?;
+            let checks = [
+                ("blob instanceof MyBlob", "true"),
+                ("blob instanceof Blob", "true"),
+                ("blob.constructor.name", "MyBlob"),
+                ("Object.prototype.toString.call(blob)", "[object Blob]"),
+                ("blob.size", "5"),
+                ("blob.type", "text/plain"),
+                ("blob.shout()", "HELLO"),
+                ("blob.slice(1).text()", "ello"),
+            ];
+            for (js, expected) in checks {
+                let result: Value = cx.eval(js)?;
+                assert_eq!(val_to_string(&cx, result)?, expected, "{js}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_blob_id_not_strict() -> Result<()> {
+        let mut config = Config::default();
+        config.strict_blob(false);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.results = [];
+                globalThis.blob = Object.create(File.prototype);
+                blob._blobId = 4294967295;
+                results.push(blob.size, blob.type, blob.name, blob.lastModified);
+                results.push(blob.slice(1).size);
+                results.push(URL.createObjectURL(blob).startsWith("blob:"));
+                results.push(blob.text(), blob.arrayBuffer().byteLength, blob.bytes().length);
+                const reader = new FileReader();
+                reader.onload = () => results.push(reader.result);
+                reader.readAsDataURL(blob);
+                "#,
+            )?;
+            Ok::<_, Error>(())
+        })?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("JSON.stringify(results)")?;
+            assert_eq!(
+                result,
+                r#"[0,"","",0,0,true,"",0,0,"data:application/octet-stream;base64,"]"#
+            );
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
     #[test]
     fn test_debug_blob_constructor() -> Result<()> {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Debug test: Check if Blob exists and is callable
             println!("Testing Blob existence...");
@@ -791,7 +2187,7 @@ mod tests {
         let config = Config::default();
         let runtime = Runtime::new(config)?;
         runtime.context().with(|cx| {
-            register(cx.clone())?;
+            register(cx.clone(), BlobSettings::default())?;
             
             // Test comprehensive integration without console dependencies
             let test_script = r#"
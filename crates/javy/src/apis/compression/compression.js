@@ -0,0 +1,132 @@
+(function () {
+  const __javy_compression_create = globalThis.__javy_compression_create;
+  const __javy_compression_write = globalThis.__javy_compression_write;
+  const __javy_compression_finish = globalThis.__javy_compression_finish;
+  const __javy_compression_drop = globalThis.__javy_compression_drop;
+  const TransformStream = globalThis.TransformStream;
+  const formats = ["deflate", "deflate-raw", "gzip"];
+
+  // The transform stream of each compression and decompression stream.
+  const transforms = new WeakMap();
+
+  function transformOf(object, name) {
+    if (!transforms.has(object)) {
+      throw new TypeError(`Illegal invocation: not a ${name}`);
+    }
+    return transforms.get(object);
+  }
+
+  // Creates a transform stream feeding the chunks to a codec, which is
+  // dropped once the stream is done with it.
+  function createTransform(name, format, decompress) {
+    format = String(format);
+    if (!formats.includes(format)) {
+      throw new TypeError(
+        `Failed to construct '${name}': Unsupported compression format: '${format}'`
+      );
+    }
+    const handle = __javy_compression_create(format, decompress);
+    let active = true;
+    const release = () => {
+      if (active) {
+        active = false;
+        __javy_compression_drop(handle);
+      }
+    };
+    return new TransformStream({
+      transform(chunk, controller) {
+        let buffer = chunk;
+        let byteOffset = 0;
+        let byteLength = chunk && chunk.byteLength;
+        if (ArrayBuffer.isView(chunk)) {
+          buffer = chunk.buffer;
+          byteOffset = chunk.byteOffset;
+        }
+        if (!(buffer instanceof ArrayBuffer)) {
+          release();
+          throw new TypeError(
+            "The provided value is not of type '(ArrayBuffer or ArrayBufferView)'"
+          );
+        }
+        let output;
+        try {
+          output = __javy_compression_write(handle, buffer, byteOffset, byteLength);
+        } catch (error) {
+          active = false;
+          throw new TypeError(error.message);
+        }
+        if (output.byteLength > 0) {
+          controller.enqueue(new Uint8Array(output));
+        }
+      },
+      flush(controller) {
+        active = false;
+        let output;
+        try {
+          output = __javy_compression_finish(handle);
+        } catch (error) {
+          throw new TypeError(error.message);
+        }
+        if (output.byteLength > 0) {
+          controller.enqueue(new Uint8Array(output));
+        }
+      },
+      cancel() {
+        release();
+      },
+    });
+  }
+
+  class CompressionStream {
+    constructor(format) {
+      if (arguments.length < 1) {
+        throw new TypeError(
+          "Failed to construct 'CompressionStream': 1 argument required, but only 0 present."
+        );
+      }
+      transforms.set(this, createTransform("CompressionStream", format, false));
+    }
+
+    get readable() {
+      return transformOf(this, "CompressionStream").readable;
+    }
+
+    get writable() {
+      return transformOf(this, "CompressionStream").writable;
+    }
+  }
+
+  class DecompressionStream {
+    constructor(format) {
+      if (arguments.length < 1) {
+        throw new TypeError(
+          "Failed to construct 'DecompressionStream': 1 argument required, but only 0 present."
+        );
+      }
+      transforms.set(this, createTransform("DecompressionStream", format, true));
+    }
+
+    get readable() {
+      return transformOf(this, "DecompressionStream").readable;
+    }
+
+    get writable() {
+      return transformOf(this, "DecompressionStream").writable;
+    }
+  }
+
+  for (const constructor of [CompressionStream, DecompressionStream]) {
+    Object.defineProperty(constructor.prototype, Symbol.toStringTag, {
+      value: constructor.name,
+      configurable: true,
+    });
+  }
+
+  globalThis.CompressionStream = CompressionStream;
+  globalThis.DecompressionStream = DecompressionStream;
+
+  Reflect.deleteProperty(globalThis, "__javy_compression_create");
+  Reflect.deleteProperty(globalThis, "__javy_compression_write");
+  Reflect.deleteProperty(globalThis, "__javy_compression_finish");
+  Reflect.deleteProperty(globalThis, "__javy_compression_drop");
+})();
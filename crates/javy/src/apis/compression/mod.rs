@@ -0,0 +1,513 @@
+use std::{cell::RefCell, collections::HashMap, io::Write, rc::Rc};
+
+use crate::{
+    hold, hold_and_release,
+    quickjs::{context::EvalOptions, ArrayBuffer, Ctx, Function, Value},
+    to_js_error, val_to_string, Args,
+};
+use anyhow::{anyhow, bail, Error, Result};
+use flate2::{
+    write::{DeflateEncoder, GzDecoder, GzEncoder, ZlibEncoder},
+    Compression, Decompress, FlushDecompress, Status,
+};
+
+/// How much the output buffer of a decompressor grows at a time.
+const OUTPUT_CHUNK_SIZE: usize = 32 * 1024;
+
+/// A compressor or decompressor of one of the supported formats, writing its
+/// output to a buffer drained after each chunk.
+enum Codec {
+    GzipEncoder(GzEncoder<Vec<u8>>),
+    DeflateEncoder(ZlibEncoder<Vec<u8>>),
+    DeflateRawEncoder(DeflateEncoder<Vec<u8>>),
+    GzipDecoder(GzDecoder<Vec<u8>>),
+    DeflateDecoder(Inflater),
+    DeflateRawDecoder(Inflater),
+}
+
+/// A decompressor of the `deflate` and `deflate-raw` formats which, unlike
+/// the `flate2` writers, knows whether the end of the compressed data was
+/// reached, so truncated and trailing data can be rejected.
+struct Inflater {
+    inner: Decompress,
+    ended: bool,
+}
+
+impl Inflater {
+    fn new(zlib_header: bool) -> Self {
+        Self {
+            inner: Decompress::new(zlib_header),
+            ended: false,
+        }
+    }
+
+    fn write(&mut self, mut input: &[u8]) -> Result<Vec<u8>> {
+        let mut output = vec![];
+        while !input.is_empty() {
+            if self.ended {
+                bail!("Trailing data after the end of the compressed data");
+            }
+            output.reserve(OUTPUT_CHUNK_SIZE);
+            let total_in = self.inner.total_in();
+            let total_out = self.inner.total_out();
+            let status = self
+                .inner
+                .decompress_vec(input, &mut output, FlushDecompress::None)?;
+            input = &input[(self.inner.total_in() - total_in) as usize..];
+            match status {
+                Status::StreamEnd => self.ended = true,
+                // Without progress, all the input is buffered by the
+                // decompressor until there's more.
+                Status::BufError
+                    if self.inner.total_in() == total_in && self.inner.total_out() == total_out =>
+                {
+                    break
+                }
+                _ => {}
+            }
+        }
+        Ok(output)
+    }
+
+    fn finish(self) -> Result<Vec<u8>> {
+        if !self.ended {
+            bail!("Unexpected end of the compressed data");
+        }
+        Ok(vec![])
+    }
+}
+
+impl Codec {
+    fn new(format: &str, decompress: bool) -> Result<Self> {
+        Ok(match (format, decompress) {
+            ("gzip", false) => Self::GzipEncoder(GzEncoder::new(vec![], Compression::default())),
+            ("deflate", false) => {
+                Self::DeflateEncoder(ZlibEncoder::new(vec![], Compression::default()))
+            }
+            ("deflate-raw", false) => {
+                Self::DeflateRawEncoder(DeflateEncoder::new(vec![], Compression::default()))
+            }
+            ("gzip", true) => Self::GzipDecoder(GzDecoder::new(vec![])),
+            ("deflate", true) => Self::DeflateDecoder(Inflater::new(true)),
+            ("deflate-raw", true) => Self::DeflateRawDecoder(Inflater::new(false)),
+            _ => bail!("Unsupported compression format: {format}"),
+        })
+    }
+
+    /// Process a chunk, returning the output available so far.
+    fn write(&mut self, chunk: &[u8]) -> Result<Vec<u8>> {
+        let output = match self {
+            Self::GzipEncoder(e) => {
+                e.write_all(chunk)?;
+                e.get_mut()
+            }
+            Self::DeflateEncoder(e) => {
+                e.write_all(chunk)?;
+                e.get_mut()
+            }
+            Self::DeflateRawEncoder(e) => {
+                e.write_all(chunk)?;
+                e.get_mut()
+            }
+            Self::GzipDecoder(d) => {
+                d.write_all(chunk)?;
+                d.get_mut()
+            }
+            Self::DeflateDecoder(d) | Self::DeflateRawDecoder(d) => return d.write(chunk),
+        };
+        Ok(std::mem::take(output))
+    }
+
+    /// Process the end of the input, returning the remaining output.
+    fn finish(self) -> Result<Vec<u8>> {
+        Ok(match self {
+            Self::GzipEncoder(e) => e.finish()?,
+            Self::DeflateEncoder(e) => e.finish()?,
+            Self::DeflateRawEncoder(e) => e.finish()?,
+            Self::GzipDecoder(d) => d.finish()?,
+            Self::DeflateDecoder(d) | Self::DeflateRawDecoder(d) => d.finish()?,
+        })
+    }
+}
+
+/// The codecs of the streams being compressed or decompressed, by handle.
+#[derive(Default)]
+struct Codecs {
+    next_handle: u32,
+    codecs: HashMap<u32, Codec>,
+}
+
+/// Register the global `CompressionStream` and `DecompressionStream` classes.
+///
+/// They're transform streams, so the streams APIs must be registered first.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let globals = this.globals();
+    let codecs = Rc::new(RefCell::new(Codecs::default()));
+
+    let create_codecs = codecs.clone();
+    globals.set(
+        "__javy_compression_create",
+        Function::new(this.clone(), move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            create(hold!(cx.clone(), args), &create_codecs).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    let write_codecs = codecs.clone();
+    globals.set(
+        "__javy_compression_write",
+        Function::new(this.clone(), move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            write(hold!(cx.clone(), args), &write_codecs).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    let finish_codecs = codecs.clone();
+    globals.set(
+        "__javy_compression_finish",
+        Function::new(this.clone(), move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            finish(hold!(cx.clone(), args), &finish_codecs).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    globals.set(
+        "__javy_compression_drop",
+        Function::new(this.clone(), move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            drop_codec(hold!(cx.clone(), args), &codecs).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./compression.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+fn handle_arg(args: &[Value<'_>]) -> Result<u32> {
+    Ok(args[0]
+        .as_number()
+        .ok_or_else(|| anyhow!("handle must be a number"))? as u32)
+}
+
+/// Create a codec for a format, compressing or decompressing, returning its
+/// handle.
+fn create<'js>(args: Args<'js>, codecs: &RefCell<Codecs>) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    if args.len() != 2 {
+        bail!(
+            "Wrong number of arguments. Expected 2 arguments. Got: {}",
+            args.len()
+        );
+    }
+
+    let format = val_to_string(&cx, args[0].clone())?;
+    let decompress = args[1]
+        .as_bool()
+        .ok_or_else(|| anyhow!("decompress must be a boolean"))?;
+    let codec = Codec::new(&format, decompress)?;
+
+    let mut codecs = codecs.borrow_mut();
+    let handle = codecs.next_handle;
+    codecs.next_handle = handle.wrapping_add(1);
+    codecs.codecs.insert(handle, codec);
+    Ok(Value::new_number(cx, handle as f64))
+}
+
+/// Process the bytes of an `ArrayBuffer` with the codec of a handle, returning
+/// the output as a new `ArrayBuffer`. The codec is dropped if it fails.
+fn write<'js>(args: Args<'js>, codecs: &RefCell<Codecs>) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    if args.len() != 4 {
+        bail!(
+            "Wrong number of arguments. Expected 4 arguments. Got: {}",
+            args.len()
+        );
+    }
+
+    let handle = handle_arg(&args)?;
+    let buffer = args[1]
+        .as_object()
+        .and_then(|buffer| buffer.as_array_buffer())
+        .ok_or_else(|| anyhow!("buffer must be an ArrayBuffer"))?
+        .as_bytes()
+        .ok_or_else(|| anyhow!("Couldn't retrieve &[u8] from buffer"))?;
+    let byte_offset = args[2]
+        .as_number()
+        .ok_or_else(|| anyhow!("offset must be a number"))? as usize;
+    let byte_length = args[3]
+        .as_number()
+        .ok_or_else(|| anyhow!("byte_length must be a number"))? as usize;
+    let chunk = buffer
+        .get(byte_offset..byte_offset + byte_length)
+        .ok_or_else(|| anyhow!("offset and length are out of bounds"))?;
+
+    let mut codecs = codecs.borrow_mut();
+    let codec = codecs
+        .codecs
+        .get_mut(&handle)
+        .ok_or_else(|| anyhow!("Unknown compression handle: {handle}"))?;
+    let output = match codec.write(chunk) {
+        Ok(output) => output,
+        Err(e) => {
+            codecs.codecs.remove(&handle);
+            return Err(e);
+        }
+    };
+    Ok(ArrayBuffer::new(cx, output)?.into_value())
+}
+
+/// Finish and drop the codec of a handle, returning the remaining output as a
+/// new `ArrayBuffer`.
+fn finish<'js>(args: Args<'js>, codecs: &RefCell<Codecs>) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    if args.len() != 1 {
+        bail!(
+            "Wrong number of arguments. Expected 1 argument. Got: {}",
+            args.len()
+        );
+    }
+
+    let handle = handle_arg(&args)?;
+    let codec = codecs
+        .borrow_mut()
+        .codecs
+        .remove(&handle)
+        .ok_or_else(|| anyhow!("Unknown compression handle: {handle}"))?;
+    Ok(ArrayBuffer::new(cx, codec.finish()?)?.into_value())
+}
+
+/// Drop the codec of a handle, when its stream is cancelled or aborted.
+fn drop_codec<'js>(args: Args<'js>, codecs: &RefCell<Codecs>) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    if args.len() != 1 {
+        bail!(
+            "Wrong number of arguments. Expected 1 argument. Got: {}",
+            args.len()
+        );
+    }
+
+    codecs.borrow_mut().codecs.remove(&handle_arg(&args)?);
+    Ok(Value::new_undefined(cx))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_js_error, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config
+            .text_encoding(true)
+            .streams(true)
+            .compression_streams(true);
+        Runtime::new(config)
+    }
+
+    /// Evaluates `src`, runs the pending jobs and returns `globalThis.result`.
+    fn eval_result(runtime: &Runtime, src: &str) -> Result<String> {
+        runtime
+            .context()
+            .with(|cx| cx.eval::<(), _>(src).map_err(|e| from_js_error(cx, e)))?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            cx.eval("String(globalThis.result)")
+                .map_err(|e| from_js_error(cx, e))
+        })
+    }
+
+    const HELPERS: &str = r#"
+        // Writes the chunks to a transform stream and concatenates the
+        // chunks read from it.
+        async function pipe(transform, chunks) {
+            const writer = transform.writable.getWriter();
+            const written = (async () => {
+                for (const chunk of chunks) {
+                    await writer.write(chunk);
+                }
+                await writer.close();
+            })();
+            const parts = [];
+            let length = 0;
+            for await (const part of transform.readable) {
+                parts.push(part);
+                length += part.byteLength;
+            }
+            await written;
+            const bytes = new Uint8Array(length);
+            let offset = 0;
+            for (const part of parts) {
+                bytes.set(part, offset);
+                offset += part.byteLength;
+            }
+            return bytes;
+        }
+    "#;
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof CompressionStream")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_requires_streams() {
+        let mut config = Config::default();
+        config.compression_streams(true);
+        let err = Runtime::new(config).err().unwrap();
+        assert_eq!(
+            "Streams are required for CompressionStream and DecompressionStream",
+            err.to_string()
+        );
+    }
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            &format!(
+                r#"
+                {HELPERS}
+                const text = "Hello, compression! ".repeat(100);
+                (async () => {{
+                    const results = [];
+                    for (const format of ["gzip", "deflate", "deflate-raw"]) {{
+                        const input = new TextEncoder().encode(text);
+                        const compressed = await pipe(new CompressionStream(format), [
+                            input.subarray(0, 7),
+                            input.subarray(7).buffer.slice(7),
+                        ]);
+                        const decompressed = await pipe(new DecompressionStream(format), [
+                            compressed.subarray(0, 10),
+                            compressed.subarray(10),
+                        ]);
+                        const output = new TextDecoder().decode(decompressed);
+                        results.push(
+                            `${{format}}:${{output === text}}:${{compressed.byteLength < input.byteLength}}`
+                        );
+                    }}
+                    globalThis.result = results.join(",");
+                }})();
+                "#
+            ),
+        )?;
+        assert_eq!(
+            "gzip:true:true,deflate:true:true,deflate-raw:true:true",
+            result
+        );
+
+        // The formats have their own headers
+        let result = eval_result(
+            &runtime,
+            &format!(
+                r#"
+                {HELPERS}
+                (async () => {{
+                    const gzip = await pipe(new CompressionStream("gzip"), [new Uint8Array([1])]);
+                    const deflate = await pipe(new CompressionStream("deflate"), [new Uint8Array([1])]);
+                    globalThis.result = `${{gzip[0]}},${{gzip[1]}} ${{deflate[0]}}`;
+                }})();
+                "#
+            ),
+        )?;
+        assert_eq!("31,139 120", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipe_through() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const source = () =>
+                new ReadableStream({
+                    start(controller) {
+                        controller.enqueue(new TextEncoder().encode("piped"));
+                        controller.close();
+                    },
+                });
+            (async () => {
+                const stream = source()
+                    .pipeThrough(new CompressionStream("gzip"))
+                    .pipeThrough(new DecompressionStream("gzip"));
+                let text = "";
+                for await (const chunk of stream) {
+                    text += new TextDecoder().decode(chunk);
+                }
+                globalThis.result = text;
+            })();
+            "#,
+        )?;
+        assert_eq!("piped", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            &format!(
+                r#"
+                {HELPERS}
+                const errors = [];
+                try {{ new CompressionStream("brotli"); }} catch (e) {{ errors.push(e.name); }}
+                try {{ new DecompressionStream(); }} catch (e) {{ errors.push(e.name); }}
+                (async () => {{
+                    try {{
+                        await pipe(new DecompressionStream("gzip"), [new Uint8Array([1, 2, 3, 4])]);
+                    }} catch (e) {{
+                        errors.push(e.name);
+                    }}
+                    for (const format of ["gzip", "deflate", "deflate-raw"]) {{
+                        const compressed = await pipe(new CompressionStream(format), [
+                            new TextEncoder().encode("truncated"),
+                        ]);
+                        try {{
+                            await pipe(new DecompressionStream(format), [compressed.subarray(0, 5)]);
+                            errors.push(`${{format}}:no error`);
+                        }} catch (e) {{
+                            errors.push(e.name);
+                        }}
+                    }}
+                    for (const format of ["gzip", "deflate", "deflate-raw"]) {{
+                        const compressed = await pipe(new CompressionStream(format), [
+                            new TextEncoder().encode("trailing"),
+                        ]);
+                        try {{
+                            await pipe(new DecompressionStream(format), [compressed, new Uint8Array([0])]);
+                            errors.push(`${{format}}:no error`);
+                        }} catch (e) {{
+                            errors.push(e.name);
+                        }}
+                    }}
+                    try {{
+                        await pipe(new CompressionStream("gzip"), ["not bytes"]);
+                    }} catch (e) {{
+                        errors.push(e.name);
+                    }}
+                    globalThis.result = errors.join(",");
+                }})();
+                "#
+            ),
+        )?;
+        assert_eq!(["TypeError"; 10].join(","), result);
+
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof __javy_compression_create")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,349 @@
+use crate::{
+    quickjs::{prelude::This, Ctx, Function, Object, Type, Value},
+    val_to_string,
+};
+use anyhow::{bail, Result};
+
+/// The number of array, `Map` and `Set` items shown before the remaining
+/// ones are summarized.
+const MAX_ITEMS: usize = 100;
+
+/// Options of the [`inspect`] formatter.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct InspectOptions {
+    /// The nesting depth up to which objects are shown. Deeper non-empty
+    /// objects are shown as `[Object]`, `[Array]` or their class name.
+    pub(crate) depth: usize,
+    /// The nesting depth at which inspection fails, guarding against runaway
+    /// recursion when `depth` is large.
+    pub(crate) max_depth: usize,
+}
+
+impl Default for InspectOptions {
+    fn default() -> Self {
+        Self {
+            depth: 2,
+            max_depth: 1000,
+        }
+    }
+}
+
+/// Formats `value` similarly to Node's `util.inspect`: nested objects are
+/// shown up to the configured depth and circular references as `[Circular]`.
+/// Values that can't be inspected, for example because a `Proxy` trap
+/// throws, are shown as `[Inspection error]`.
+pub(super) fn inspect<'js>(ctx: &Ctx<'js>, value: Value<'js>, options: &InspectOptions) -> String {
+    let result = Inspector::new(ctx, options).and_then(|mut inspector| inspector.inspect(value, 0));
+    result.unwrap_or_else(|_| {
+        // Clear the pending exception, if any.
+        ctx.catch();
+        "[Inspection error]".to_string()
+    })
+}
+
+struct Inspector<'a, 'js> {
+    ctx: &'a Ctx<'js>,
+    options: &'a InspectOptions,
+    /// The objects on the path from the inspected value to the current one.
+    path: Vec<Value<'js>>,
+    /// `Object.prototype.toString`, to tell built-in objects apart.
+    to_string_tag: Function<'js>,
+    /// `Object.getOwnPropertyDescriptor`, to show accessors without calling
+    /// them.
+    get_own_property_descriptor: Function<'js>,
+    /// `Array.from`, to list the items of iterable objects.
+    array_from: Function<'js>,
+}
+
+impl<'a, 'js> Inspector<'a, 'js> {
+    fn new(ctx: &'a Ctx<'js>, options: &'a InspectOptions) -> Result<Self> {
+        let object: Object = ctx.globals().get("Object")?;
+        let prototype: Object = object.get("prototype")?;
+        let array: Object = ctx.globals().get("Array")?;
+        Ok(Self {
+            ctx,
+            options,
+            path: vec![],
+            to_string_tag: prototype.get("toString")?,
+            get_own_property_descriptor: object.get("getOwnPropertyDescriptor")?,
+            array_from: array.get("from")?,
+        })
+    }
+
+    fn inspect(&mut self, value: Value<'js>, level: usize) -> Result<String> {
+        match value.type_of() {
+            Type::String => Ok(quote(&val_to_string(self.ctx, value)?)),
+            Type::Float
+                if value
+                    .as_float()
+                    .is_some_and(|n| n == 0.0 && n.is_sign_negative()) =>
+            {
+                Ok("-0".to_string())
+            }
+            Type::BigInt => Ok(format!("{}n", val_to_string(self.ctx, value)?)),
+            Type::Function | Type::Constructor => self.inspect_function(value),
+            Type::Array | Type::Object | Type::Promise | Type::Exception => {
+                let object = value.as_object().unwrap().clone();
+                if self.path.contains(&value) {
+                    return Ok("[Circular]".to_string());
+                }
+                if self.path.len() >= self.options.max_depth {
+                    bail!("Maximum inspection depth exceeded");
+                }
+
+                self.path.push(value);
+                let result = self.inspect_object(object, level);
+                self.path.pop();
+                result
+            }
+            _ => val_to_string(self.ctx, value),
+        }
+    }
+
+    fn inspect_function(&self, value: Value<'js>) -> Result<String> {
+        let function = value.as_object().unwrap();
+        let name = match function.get::<_, Value>("name")? {
+            name if name.is_string() => val_to_string(self.ctx, name)?,
+            _ => String::new(),
+        };
+        let source = val_to_string(self.ctx, value.clone())?;
+        Ok(match (source.starts_with("class"), name.is_empty()) {
+            (true, true) => "[class (anonymous)]".to_string(),
+            (true, false) => format!("[class {name}]"),
+            (false, true) => "[Function (anonymous)]".to_string(),
+            (false, false) => format!("[Function: {name}]"),
+        })
+    }
+
+    fn inspect_object(&mut self, object: Object<'js>, level: usize) -> Result<String> {
+        let tag = self.tag(&object)?;
+        match tag.as_str() {
+            "Date" => {
+                let time: f64 = self
+                    .call_method(&object, "getTime")?
+                    .as_number()
+                    .unwrap_or(f64::NAN);
+                if time.is_nan() {
+                    Ok("Invalid Date".to_string())
+                } else {
+                    val_to_string(self.ctx, self.call_method(&object, "toISOString")?)
+                }
+            }
+            "RegExp" => val_to_string(self.ctx, self.call_method(&object, "toString")?),
+            "Error" => {
+                let message = val_to_string(self.ctx, self.call_method(&object, "toString")?)?;
+                Ok(if level == 0 {
+                    message
+                } else {
+                    format!("[{message}]")
+                })
+            }
+            "ArrayBuffer" => {
+                let byte_length: Value = object.get("byteLength")?;
+                Ok(format!(
+                    "ArrayBuffer {{ byteLength: {} }}",
+                    val_to_string(self.ctx, byte_length)?
+                ))
+            }
+            "Map" | "Set" => {
+                let size: usize = object.get::<_, f64>("size")? as usize;
+                let prefix = format!("{tag}({size})");
+                if size == 0 {
+                    return Ok(format!("{prefix} {{}}"));
+                }
+                if level > self.options.depth {
+                    return Ok(format!("[{tag}]"));
+                }
+
+                let entries: Object = self.array_from.call((object,))?;
+                let mut items = vec![];
+                for entry in entries.as_array().unwrap().iter::<Value>().take(MAX_ITEMS) {
+                    let entry = entry?;
+                    items.push(if tag == "Map" {
+                        let entry = entry.as_array().unwrap();
+                        let key = self.inspect(entry.get(0)?, level + 1)?;
+                        let value = self.inspect(entry.get(1)?, level + 1)?;
+                        format!("{key} => {value}")
+                    } else {
+                        self.inspect(entry, level + 1)?
+                    });
+                }
+                push_more_items(&mut items, size);
+                Ok(format!("{prefix} {{ {} }}", items.join(", ")))
+            }
+            _ if object.is_array() => {
+                let array = object.as_array().unwrap();
+                let len = array.len();
+                if len == 0 {
+                    return Ok("[]".to_string());
+                }
+                if level > self.options.depth {
+                    return Ok("[Array]".to_string());
+                }
+                let items = self.inspect_items(&object, len, level)?;
+                Ok(format!("[ {} ]", items.join(", ")))
+            }
+            _ if tag.ends_with("Array") => {
+                // Typed arrays
+                let len = object.get::<_, f64>("length")? as usize;
+                if len == 0 {
+                    return Ok(format!("{tag}(0) []"));
+                }
+                if level > self.options.depth {
+                    return Ok(format!("[{tag}]"));
+                }
+                let items = self.inspect_items(&object, len, level)?;
+                Ok(format!("{tag}({len}) [ {} ]", items.join(", ")))
+            }
+            _ => self.inspect_plain_object(object, level),
+        }
+    }
+
+    /// Inspects the first items of an array-like object, summarizing holes
+    /// and the items past [`MAX_ITEMS`].
+    fn inspect_items(
+        &mut self,
+        object: &Object<'js>,
+        len: usize,
+        level: usize,
+    ) -> Result<Vec<String>> {
+        let mut items = vec![];
+        let mut holes = 0;
+        for i in 0..len.min(MAX_ITEMS) {
+            if !object.contains_key(i as u32)? {
+                holes += 1;
+                continue;
+            }
+            push_holes(&mut items, &mut holes);
+            items.push(self.inspect(object.get(i as u32)?, level + 1)?);
+        }
+        push_holes(&mut items, &mut holes);
+        push_more_items(&mut items, len);
+        Ok(items)
+    }
+
+    fn inspect_plain_object(&mut self, object: Object<'js>, level: usize) -> Result<String> {
+        let prefix = match object.get_prototype() {
+            None => "[Object: null prototype] ".to_string(),
+            Some(_) => match self.constructor_name(&object)? {
+                Some(name) if name != "Object" => format!("{name} "),
+                _ => String::new(),
+            },
+        };
+
+        let keys = object.keys::<String>().collect::<Result<Vec<_>, _>>()?;
+        if keys.is_empty() {
+            return Ok(format!("{prefix}{{}}"));
+        }
+        if level > self.options.depth {
+            let name = prefix.trim_end();
+            return Ok(format!(
+                "[{}]",
+                if name.is_empty() { "Object" } else { name }
+            ));
+        }
+
+        let mut properties = vec![];
+        for key in keys {
+            let descriptor: Object = self
+                .get_own_property_descriptor
+                .call((object.clone(), key.as_str()))?;
+            let getter = !descriptor.get::<_, Value>("get")?.is_undefined();
+            let setter = !descriptor.get::<_, Value>("set")?.is_undefined();
+            let value = match (getter, setter) {
+                (true, true) => "[Getter/Setter]".to_string(),
+                (true, false) => "[Getter]".to_string(),
+                (false, true) => "[Setter]".to_string(),
+                (false, false) => self.inspect(descriptor.get("value")?, level + 1)?,
+            };
+            properties.push(format!("{}: {value}", format_key(&key)));
+        }
+        Ok(format!("{prefix}{{ {} }}", properties.join(", ")))
+    }
+
+    /// The `X` in the `[object X]` tag of `object`.
+    fn tag(&self, object: &Object<'js>) -> Result<String> {
+        let tag: Value = self.to_string_tag.call((This(object.clone()),))?;
+        let tag = val_to_string(self.ctx, tag)?;
+        Ok(tag
+            .strip_prefix("[object ")
+            .and_then(|tag| tag.strip_suffix(']'))
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    fn constructor_name(&self, object: &Object<'js>) -> Result<Option<String>> {
+        let constructor: Value = object.get("constructor")?;
+        let Some(constructor) = constructor.as_function() else {
+            return Ok(None);
+        };
+        let name: Value = constructor.get("name")?;
+        Ok(match name.is_string() {
+            true => Some(val_to_string(self.ctx, name)?).filter(|name| !name.is_empty()),
+            false => None,
+        })
+    }
+
+    fn call_method(&self, object: &Object<'js>, name: &str) -> Result<Value<'js>> {
+        let method: Function = object.get(name)?;
+        Ok(method.call((This(object.clone()),))?)
+    }
+}
+
+fn push_holes(items: &mut Vec<String>, holes: &mut usize) {
+    match *holes {
+        0 => {}
+        1 => items.push("<1 empty item>".to_string()),
+        n => items.push(format!("<{n} empty items>")),
+    }
+    *holes = 0;
+}
+
+fn push_more_items(items: &mut Vec<String>, len: usize) {
+    match len.saturating_sub(MAX_ITEMS) {
+        0 => {}
+        1 => items.push("... 1 more item".to_string()),
+        n => items.push(format!("... {n} more items")),
+    }
+}
+
+/// Formats a property key, quoting it unless it's a valid identifier.
+fn format_key(key: &str) -> String {
+    let mut chars = key.chars();
+    let is_identifier = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == '$')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '$');
+    if is_identifier {
+        key.to_string()
+    } else {
+        quote(key)
+    }
+}
+
+/// Quotes a string with single quotes, or with double quotes if it contains
+/// single quotes but no double quotes.
+fn quote(s: &str) -> String {
+    let quote = if s.contains('\'') && !s.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push(quote);
+    for c in s.chars() {
+        match c {
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            '\\' => quoted.push_str("\\\\"),
+            c if c == quote => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+    }
+    quoted.push(quote);
+    quoted
+}
@@ -1,15 +1,71 @@
-use std::io::Write;
+use std::{
+    cell::{Cell, RefCell},
+    collections::{hash_map::Entry, HashMap},
+    io::Write,
+    rc::Rc,
+    time::Instant,
+};
 
 use crate::{
     hold, hold_and_release,
-    quickjs::{prelude::MutFn, Ctx, Function, Object, Value},
-    to_js_error, val_to_string, Args,
+    quickjs::{
+        prelude::{MutFn, Rest, This},
+        Ctx, Function, Object, Value,
+    },
+    to_js_error, val_to_string, Args, ConsoleLevel, ConsoleSink,
 };
 use anyhow::Result;
 
+mod inspect;
+use inspect::inspect;
+pub(crate) use inspect::InspectOptions;
+
+/// State of a registered `console` object that is scoped to a single
+/// invocation.
+#[derive(Clone, Default)]
+pub(crate) struct ConsoleState {
+    /// How logged objects are inspected. Not reset between invocations.
+    inspect: InspectOptions,
+    /// The host callback receiving the arguments of `.log`, `.warn` and
+    /// `.error` calls, if any. Not reset between invocations.
+    sink: Option<ConsoleSink>,
+    /// The current `console.group` nesting level.
+    group_depth: Rc<Cell<usize>>,
+    /// The start time of the `console.time` timers, by label.
+    timers: Rc<RefCell<HashMap<String, Instant>>>,
+    /// The `console.count` counters, by label.
+    counters: Rc<RefCell<HashMap<String, u64>>>,
+}
+
+impl ConsoleState {
+    /// Resets the state to its initial values.
+    pub(crate) fn reset(&self) {
+        self.group_depth.set(0);
+        self.timers.borrow_mut().clear();
+        self.counters.borrow_mut().clear();
+    }
+
+    fn indentation(&self) -> String {
+        "  ".repeat(self.group_depth.get())
+    }
+}
+
 /// Register a `console` object on the global object with `.log`, `.warn` and `.error`
-/// streams.
-pub(crate) fn register<T, U, V>(this: Ctx<'_>, mut log_stream: T, mut warn_stream: U, mut error_stream: V) -> Result<()>
+/// streams. `.table`, `.group`, `.groupCollapsed`, `.timeEnd`, `.timeLog` and
+/// `.count` write to the same stream as `.log`, warnings about unknown timer
+/// and counter labels are written to the same stream as `.warn`.
+///
+/// Logged objects are formatted according to `inspect`. When a `sink` is
+/// given, it receives the arguments of `.log`, `.warn` and `.error` calls
+/// instead of their streams.
+pub(crate) fn register<T, U, V>(
+    this: Ctx<'_>,
+    log_stream: T,
+    warn_stream: U,
+    mut error_stream: V,
+    inspect: InspectOptions,
+    sink: Option<ConsoleSink>,
+) -> Result<ConsoleState>
 where
     T: Write + 'static,
     U: Write + 'static,
@@ -17,55 +73,603 @@ where
 {
     let globals = this.globals();
     let console = Object::new(this.clone())?;
+    let log_stream = Rc::new(RefCell::new(log_stream));
+    let warn_stream = Rc::new(RefCell::new(warn_stream));
+    let state = ConsoleState {
+        inspect,
+        sink,
+        ..Default::default()
+    };
 
+    let stream = log_stream.clone();
+    let st = state.clone();
     console.set(
         "log",
         Function::new(
             this.clone(),
             MutFn::new(move |cx, args| {
                 let (cx, args) = hold_and_release!(cx, args);
-                log(hold!(cx.clone(), args), &mut log_stream).map_err(|e| to_js_error(cx, e))
+                log(
+                    hold!(cx.clone(), args),
+                    &mut *stream.borrow_mut(),
+                    &st,
+                    ConsoleLevel::Log,
+                )
+                .map_err(|e| to_js_error(cx, e))
+            }),
+        )?,
+    )?;
+
+    let stream = log_stream.clone();
+    let st = state.clone();
+    console.set(
+        "table",
+        Function::new(
+            this.clone(),
+            MutFn::new(move |cx, args| {
+                let (cx, args) = hold_and_release!(cx, args);
+                table(hold!(cx.clone(), args), &mut *stream.borrow_mut(), &st)
+                    .map_err(|e| to_js_error(cx, e))
+            }),
+        )?,
+    )?;
+
+    for name in ["group", "groupCollapsed"] {
+        let stream = log_stream.clone();
+        let st = state.clone();
+        console.set(
+            name,
+            Function::new(
+                this.clone(),
+                MutFn::new(move |cx, args| {
+                    let (cx, args) = hold_and_release!(cx, args);
+                    group(hold!(cx.clone(), args), &mut *stream.borrow_mut(), &st)
+                        .map_err(|e| to_js_error(cx, e))
+                }),
+            )?,
+        )?;
+    }
+
+    let st = state.clone();
+    console.set(
+        "groupEnd",
+        Function::new(this.clone(), move || {
+            st.group_depth.set(st.group_depth.get().saturating_sub(1));
+        })?,
+    )?;
+
+    let stream = warn_stream.clone();
+    let st = state.clone();
+    console.set(
+        "time",
+        Function::new(
+            this.clone(),
+            MutFn::new(move |cx, args| {
+                let (cx, args) = hold_and_release!(cx, args);
+                time(hold!(cx.clone(), args), &mut *stream.borrow_mut(), &st)
+                    .map_err(|e| to_js_error(cx, e))
             }),
         )?,
     )?;
 
+    for (name, end) in [("timeEnd", true), ("timeLog", false)] {
+        let stream = log_stream.clone();
+        let warnings = warn_stream.clone();
+        let st = state.clone();
+        console.set(
+            name,
+            Function::new(
+                this.clone(),
+                MutFn::new(move |cx, args| {
+                    let (cx, args) = hold_and_release!(cx, args);
+                    time_log(
+                        hold!(cx.clone(), args),
+                        &mut *stream.borrow_mut(),
+                        &mut *warnings.borrow_mut(),
+                        &st,
+                        end,
+                    )
+                    .map_err(|e| to_js_error(cx, e))
+                }),
+            )?,
+        )?;
+    }
+
+    let stream = log_stream.clone();
+    let st = state.clone();
+    console.set(
+        "count",
+        Function::new(
+            this.clone(),
+            MutFn::new(move |cx, args| {
+                let (cx, args) = hold_and_release!(cx, args);
+                count(hold!(cx.clone(), args), &mut *stream.borrow_mut(), &st)
+                    .map_err(|e| to_js_error(cx, e))
+            }),
+        )?,
+    )?;
+
+    let stream = warn_stream.clone();
+    let st = state.clone();
+    console.set(
+        "countReset",
+        Function::new(
+            this.clone(),
+            MutFn::new(move |cx, args| {
+                let (cx, args) = hold_and_release!(cx, args);
+                count_reset(hold!(cx.clone(), args), &mut *stream.borrow_mut(), &st)
+                    .map_err(|e| to_js_error(cx, e))
+            }),
+        )?,
+    )?;
+
+    let stream = warn_stream;
+    let st = state.clone();
     console.set(
         "warn",
         Function::new(
             this.clone(),
             MutFn::new(move |cx, args| {
                 let (cx, args) = hold_and_release!(cx, args);
-                log(hold!(cx.clone(), args), &mut warn_stream).map_err(|e| to_js_error(cx, e))
+                log(
+                    hold!(cx.clone(), args),
+                    &mut *stream.borrow_mut(),
+                    &st,
+                    ConsoleLevel::Warn,
+                )
+                .map_err(|e| to_js_error(cx, e))
             }),
         )?,
     )?;
 
+    let st = state.clone();
     console.set(
         "error",
         Function::new(
             this.clone(),
             MutFn::new(move |cx, args| {
                 let (cx, args) = hold_and_release!(cx, args);
-                log(hold!(cx.clone(), args), &mut error_stream).map_err(|e| to_js_error(cx, e))
+                log(
+                    hold!(cx.clone(), args),
+                    &mut error_stream,
+                    &st,
+                    ConsoleLevel::Error,
+                )
+                .map_err(|e| to_js_error(cx, e))
             }),
         )?,
     )?;
 
     globals.set("console", console)?;
+    Ok(state)
+}
+
+/// Writes `text` to `stream`, indenting every line by the current group depth.
+fn write_indented<T: Write>(stream: &mut T, state: &ConsoleState, text: &str) -> Result<()> {
+    let indentation = state.indentation();
+    for line in text.split('\n') {
+        writeln!(stream, "{indentation}{line}")?;
+    }
     Ok(())
 }
 
-fn log<'js, T: Write>(args: Args<'js>, stream: &mut T) -> Result<Value<'js>> {
+/// Formats console arguments. When the first argument is a string, it is
+/// interpreted as a printf-style format string consuming the following
+/// arguments. Any argument left over is formatted with [`format_arg`] and
+/// appended, separated by spaces.
+fn format_args<'js>(ctx: &Ctx<'js>, state: &ConsoleState, args: Vec<Value<'js>>) -> Result<String> {
+    let mut args = args.into_iter().peekable();
+    // Whether the next argument is separated from the message, which is the
+    // case once a format string, even an empty one, was consumed.
+    let (mut message, mut separate) = match args.peek() {
+        Some(first) if first.is_string() => {
+            let format = val_to_string(ctx, args.next().unwrap())?;
+            (format_string(ctx, state, &format, &mut args)?, true)
+        }
+        _ => (String::new(), false),
+    };
+
+    for arg in args {
+        if separate {
+            message.push(' ');
+        }
+        separate = true;
+
+        message.push_str(&format_arg(ctx, state, arg)?);
+    }
+    Ok(message)
+}
+
+/// Formats a console argument not consumed by a format specifier. Strings and
+/// functions are written as is, other values are inspected.
+fn format_arg<'js>(ctx: &Ctx<'js>, state: &ConsoleState, arg: Value<'js>) -> Result<String> {
+    if arg.is_string() || arg.is_function() {
+        val_to_string(ctx, arg)
+    } else {
+        Ok(inspect(ctx, arg, &state.inspect))
+    }
+}
+
+/// Replaces the `%s`, `%d`, `%i`, `%f`, `%o`, `%O`, `%j` and `%%` specifiers
+/// in `format` with the formatted arguments taken from `args`. Specifiers
+/// without a corresponding argument, and unknown specifiers, are kept as is.
+fn format_string<'js>(
+    ctx: &Ctx<'js>,
+    state: &ConsoleState,
+    format: &str,
+    args: &mut impl Iterator<Item = Value<'js>>,
+) -> Result<String> {
+    let mut message = String::with_capacity(format.len());
+    let mut chars = format.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            message.push(c);
+            continue;
+        }
+
+        let Some(&specifier) = chars.peek() else {
+            message.push(c);
+            break;
+        };
+        if specifier == '%' {
+            chars.next();
+            message.push('%');
+            continue;
+        }
+        if !matches!(specifier, 's' | 'd' | 'i' | 'f' | 'o' | 'O' | 'j') {
+            message.push(c);
+            continue;
+        }
+        let Some(arg) = args.next() else {
+            message.push(c);
+            continue;
+        };
+
+        chars.next();
+        let formatted = match specifier {
+            's' => val_to_string(ctx, arg)?,
+            'd' => call_global(ctx, "Number", arg)?,
+            'i' => call_global(ctx, "parseInt", arg)?,
+            'f' => call_global(ctx, "parseFloat", arg)?,
+            'j' => stringify(ctx, arg, state.inspect.max_depth)?,
+            _ => inspect(ctx, arg, &state.inspect),
+        };
+        message.push_str(&formatted);
+    }
+    Ok(message)
+}
+
+/// Calls the global function `name` with `arg`, returning the result as a
+/// string or `NaN` if the call throws.
+fn call_global<'js>(ctx: &Ctx<'js>, name: &str, arg: Value<'js>) -> Result<String> {
+    let function: Function = ctx.globals().get(name)?;
+    match function.call::<_, Value>((arg,)) {
+        Ok(value) => val_to_string(ctx, value),
+        Err(_) => {
+            // Clear the pending exception.
+            ctx.catch();
+            Ok("NaN".to_string())
+        }
+    }
+}
+
+/// Formats `arg` as JSON, or as `[Circular]` if it contains circular
+/// references.
+///
+/// Inspecting values nested more than `max_depth` levels deep, for example
+/// through getters returning new objects on every access, fails with
+/// `[Inspection error]` instead of exhausting the stack. So does any other
+/// error thrown while inspecting, such as a throwing getter.
+fn stringify<'js>(ctx: &Ctx<'js>, arg: Value<'js>, max_depth: usize) -> Result<String> {
+    // The objects on the path from the root to the value being serialized.
+    // `JSON.stringify` serializes depth-first and calls the replacer with the
+    // object holding the value, so the holder is always on the path.
+    let path: Rc<RefCell<Vec<Value<'js>>>> = Rc::default();
+    let replacer = Function::new(ctx.clone(), {
+        let path = path.clone();
+        move |cx: Ctx<'js>, holder: This<Value<'js>>, _key: Value<'js>, value: Value<'js>| {
+            let mut path = path.borrow_mut();
+            match path.iter().rposition(|ancestor| *ancestor == holder.0) {
+                Some(position) => path.truncate(position + 1),
+                None => path.push(holder.0),
+            }
+            if value.is_object() {
+                if path.len() > max_depth {
+                    return Err(to_js_error(
+                        cx,
+                        anyhow::anyhow!("Maximum inspection depth exceeded"),
+                    ));
+                }
+                path.push(value.clone());
+            }
+            Ok::<_, crate::quickjs::Error>(value)
+        }
+    })?;
+
+    let result = ctx.json_stringify_replacer(arg, replacer);
+    path.borrow_mut().clear();
+    match result {
+        Ok(Some(json)) => Ok(json.to_string()?),
+        Ok(None) => Ok("undefined".to_string()),
+        Err(_) => {
+            let is_circular = ctx
+                .catch()
+                .as_exception()
+                .and_then(|exception| exception.message())
+                .is_some_and(|message| message.contains("circular"));
+            Ok(if is_circular {
+                "[Circular]"
+            } else {
+                "[Inspection error]"
+            }
+            .to_string())
+        }
+    }
+}
+
+fn log<'js, T: Write>(
+    args: Args<'js>,
+    stream: &mut T,
+    state: &ConsoleState,
+    level: ConsoleLevel,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    if let Some(sink) = &state.sink {
+        sink(level, &ctx, &args.0)?;
+        return Ok(Value::new_undefined(ctx));
+    }
+
+    let message = format_args(&ctx, state, args.into_inner())?;
+    write_indented(stream, state, &message)?;
+
+    Ok(Value::new_undefined(ctx))
+}
+
+fn group<'js, T: Write>(
+    args: Args<'js>,
+    stream: &mut T,
+    state: &ConsoleState,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+    if !args.is_empty() {
+        let label = format_args(&ctx, state, args)?;
+        write_indented(stream, state, &label)?;
+    }
+    state.group_depth.set(state.group_depth.get() + 1);
+
+    Ok(Value::new_undefined(ctx))
+}
+
+/// The label used by the `console.time` and `console.count` functions when
+/// none is given.
+const DEFAULT_LABEL: &str = "default";
+
+fn label<'js>(ctx: &Ctx<'js>, args: &[Value<'js>]) -> Result<String> {
+    match args.first() {
+        Some(label) if !label.is_undefined() => val_to_string(ctx, label.clone()),
+        _ => Ok(DEFAULT_LABEL.to_string()),
+    }
+}
+
+fn time<'js, T: Write>(
+    args: Args<'js>,
+    warn_stream: &mut T,
+    state: &ConsoleState,
+) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
-    for (i, arg) in args.into_inner().into_iter().enumerate() {
-        if i != 0 {
-            write!(stream, " ")?;
+    let label = label(&ctx, &args)?;
+
+    match state.timers.borrow_mut().entry(label) {
+        Entry::Occupied(entry) => {
+            let warning = format!("Warning: Timer '{}' already exists", entry.key());
+            write_indented(warn_stream, state, &warning)?;
+        }
+        Entry::Vacant(entry) => {
+            entry.insert(Instant::now());
         }
+    }
+
+    Ok(Value::new_undefined(ctx))
+}
+
+/// Logs the elapsed time of a `console.time` timer, removing the timer if
+/// `end` is true.
+fn time_log<'js, T: Write, U: Write>(
+    args: Args<'js>,
+    stream: &mut T,
+    warn_stream: &mut U,
+    state: &ConsoleState,
+    end: bool,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+    let label = label(&ctx, &args)?;
+
+    let start = if end {
+        state.timers.borrow_mut().remove(&label)
+    } else {
+        state.timers.borrow().get(&label).copied()
+    };
 
-        let str = val_to_string(&ctx, arg)?;
-        write!(stream, "{str}")?;
+    match start {
+        Some(start) => {
+            let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+            let mut message = format!("{label}: {elapsed:.3}ms");
+            if !end && args.len() > 1 {
+                message.push(' ');
+                message.push_str(&format_args(&ctx, state, args[1..].to_vec())?);
+            }
+            write_indented(stream, state, &message)?;
+        }
+        None => {
+            let name = if end { "timeEnd" } else { "timeLog" };
+            let warning = format!("Warning: No such label '{label}' for console.{name}()");
+            write_indented(warn_stream, state, &warning)?;
+        }
     }
-    writeln!(stream)?;
+
+    Ok(Value::new_undefined(ctx))
+}
+
+fn count<'js, T: Write>(
+    args: Args<'js>,
+    stream: &mut T,
+    state: &ConsoleState,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let label = label(&ctx, &args)?;
+
+    let count = {
+        let mut counters = state.counters.borrow_mut();
+        let count = counters.entry(label.clone()).or_default();
+        *count += 1;
+        *count
+    };
+    write_indented(stream, state, &format!("{label}: {count}"))?;
+
+    Ok(Value::new_undefined(ctx))
+}
+
+fn count_reset<'js, T: Write>(
+    args: Args<'js>,
+    warn_stream: &mut T,
+    state: &ConsoleState,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let label = label(&ctx, &args)?;
+
+    let reset = match state.counters.borrow_mut().get_mut(&label) {
+        Some(count) => {
+            *count = 0;
+            true
+        }
+        None => false,
+    };
+    if !reset {
+        write_indented(
+            warn_stream,
+            state,
+            &format!("Warning: Count for '{label}' does not exist"),
+        )?;
+    }
+
+    Ok(Value::new_undefined(ctx))
+}
+
+/// Name of the column holding the row keys in `console.table`.
+const INDEX_COLUMN: &str = "(index)";
+/// Name of the column holding primitive row values in `console.table`.
+const VALUES_COLUMN: &str = "Values";
+
+fn table<'js, T: Write>(
+    args: Args<'js>,
+    stream: &mut T,
+    state: &ConsoleState,
+) -> Result<Value<'js>> {
+    let (ctx, args) = args.release();
+    let args = args.into_inner();
+
+    // Non-object data is logged as is.
+    let data = match args.first().and_then(|data| data.as_object()) {
+        Some(data) => data.clone(),
+        None => {
+            return log(
+                Args::hold(ctx, Rest(args)),
+                stream,
+                state,
+                ConsoleLevel::Log,
+            )
+        }
+    };
+
+    let filter = match args.get(1).and_then(|properties| properties.as_array()) {
+        Some(properties) => Some(
+            properties
+                .iter::<Value>()
+                .map(|property| val_to_string(&ctx, property?))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+
+    let mut columns: Vec<String> = vec![];
+    let mut has_values = false;
+    let mut rows = vec![];
+    for key in data.keys::<String>() {
+        let key = key?;
+        let value: Value = data.get(key.as_str())?;
+        let mut cells = HashMap::new();
+        match value.as_object() {
+            Some(row) if !value.is_function() => {
+                for column in row.keys::<String>() {
+                    let column = column?;
+                    if filter.as_ref().is_some_and(|f| !f.contains(&column)) {
+                        continue;
+                    }
+                    let cell = val_to_string(&ctx, row.get(column.as_str())?)?;
+                    if !columns.contains(&column) {
+                        columns.push(column.clone());
+                    }
+                    cells.insert(column, cell);
+                }
+            }
+            _ => {
+                has_values = true;
+                cells.insert(VALUES_COLUMN.to_string(), val_to_string(&ctx, value)?);
+            }
+        }
+        rows.push((key, cells));
+    }
+
+    if let Some(filter) = filter {
+        // Keep the order in which the properties were requested.
+        columns = filter;
+    }
+    if has_values {
+        columns.push(VALUES_COLUMN.to_string());
+    }
+
+    let mut header = vec![INDEX_COLUMN.to_string()];
+    header.extend(columns.iter().cloned());
+    let body = rows
+        .into_iter()
+        .map(|(key, mut cells)| {
+            let mut line = vec![key];
+            line.extend(columns.iter().map(|c| cells.remove(c).unwrap_or_default()));
+            line
+        })
+        .collect::<Vec<_>>();
+
+    let widths = header
+        .iter()
+        .enumerate()
+        .map(|(i, title)| {
+            body.iter()
+                .map(|line| line[i].chars().count())
+                .chain([title.chars().count()])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>();
+
+    let border = |left: &str, middle: &str, right: &str| {
+        let segments = widths.iter().map(|w| "─".repeat(w + 2)).collect::<Vec<_>>();
+        format!("{left}{}{right}", segments.join(middle))
+    };
+    let line = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, w)| format!(" {cell}{} ", " ".repeat(w - cell.chars().count())))
+            .collect::<Vec<_>>();
+        format!("│{}│", cells.join("│"))
+    };
+
+    let mut output = vec![border("┌", "┬", "┐"), line(&header), border("├", "┼", "┤")];
+    output.extend(body.iter().map(|cells| line(cells)));
+    output.push(border("└", "┴", "┘"));
+    write_indented(stream, state, &output.join("\n"))?;
 
     Ok(Value::new_undefined(ctx))
 }
@@ -73,9 +677,9 @@ fn log<'js, T: Write>(args: Args<'js>, stream: &mut T) -> Result<Value<'js>> {
 #[cfg(test)]
 mod tests {
     use crate::{
-        apis::console::register,
+        apis::console::{register, InspectOptions},
         quickjs::{Object, Value},
-        Runtime,
+        Config, ConsoleLevel, Runtime,
     };
     use anyhow::{Error, Result};
     use std::cell::RefCell;
@@ -90,9 +694,51 @@ mod tests {
             assert!(console.get::<&str, Value<'_>>("log").is_ok());
             assert!(console.get::<&str, Value<'_>>("warn").is_ok());
             assert!(console.get::<&str, Value<'_>>("error").is_ok());
+            assert!(console.get::<&str, Value<'_>>("table").is_ok());
+
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
 
+    #[test]
+    fn test_sink() -> Result<()> {
+        let calls = Rc::new(RefCell::new(vec![]));
+        let mut config = Config::default();
+        let sink_calls = calls.clone();
+        config.console_sink(move |level, cx, args| {
+            let args = args
+                .iter()
+                .map(|arg| {
+                    let json = cx.json_stringify(arg.clone())?;
+                    Ok(json
+                        .map(|json| json.to_string())
+                        .transpose()?
+                        .unwrap_or_default())
+                })
+                .collect::<Result<Vec<_>>>()?;
+            sink_calls.borrow_mut().push((level, args));
+            Ok(())
+        });
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                "console.log('x', { a: 1 }); console.warn([1, 'two']); console.error(undefined);",
+            )?;
             Ok::<_, Error>(())
         })?;
+
+        assert_eq!(
+            *calls.borrow(),
+            [
+                (
+                    ConsoleLevel::Log,
+                    vec!["\"x\"".to_string(), r#"{"a":1}"#.to_string()]
+                ),
+                (ConsoleLevel::Warn, vec![r#"[1,"two"]"#.to_string()]),
+                (ConsoleLevel::Error, vec![String::new()]),
+            ]
+        );
         Ok(())
     }
 
@@ -103,7 +749,15 @@ mod tests {
         let ctx = runtime.context();
 
         ctx.with(|this| {
-            register(this.clone(), stream.clone(), stream.clone(), stream.clone()).unwrap();
+            register(
+                this.clone(),
+                stream.clone(),
+                stream.clone(),
+                stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
             this.eval::<(), _>("console.log(\"hello world\");")?;
             assert_eq!(b"hello world\n", stream.buffer.borrow().as_slice());
             stream.clear();
@@ -130,30 +784,33 @@ mod tests {
 
             test_console_log!(
                 "console.log([1, \"two\", 3.42, null, 5])",
-                "1,two,3.42,,5\n"
+                "[ 1, 'two', 3.42, null, 5 ]\n"
             );
 
             test_console_log!(
                 "console.log(2.3, true, { foo: 'bar' }, null, undefined)",
-                "2.3 true [object Object] null undefined\n"
+                "2.3 true { foo: 'bar' } null undefined\n"
             );
 
             test_console_log!(
                 "console.log(new Date(0))",
-                "Thu Jan 01 1970 00:00:00 GMT+0000\n"
+                "1970-01-01T00:00:00.000Z\n"
             );
 
-            test_console_log!("console.log(new ArrayBuffer())", "[object ArrayBuffer]\n");
+            test_console_log!(
+                "console.log(new ArrayBuffer())",
+                "ArrayBuffer { byteLength: 0 }\n"
+            );
 
             test_console_log!("console.log(NaN)", "NaN\n");
 
-            test_console_log!("console.log(new Set())", "[object Set]\n");
+            test_console_log!("console.log(new Set())", "Set(0) {}\n");
 
-            test_console_log!("console.log(new Map())", "[object Map]\n");
+            test_console_log!("console.log(new Map())", "Map(0) {}\n");
 
             test_console_log!(
                 "function Foo(){}; console.log(new Foo())",
-                "[object Object]\n"
+                "Foo {}\n"
             );
 
             test_console_log!("console.log(Symbol())", "Symbol()\n");
@@ -177,6 +834,445 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_console_inspect() -> Result<()> {
+        let mut stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            register(this.clone(), stream.clone(), stream.clone(), stream.clone(), InspectOptions::default(), None).unwrap();
+
+            let cases = [
+                // Nested objects and arrays
+                ("{ a: 1, b: 'two', c: [1, [2, [3, [4]]]] }", "{ a: 1, b: 'two', c: [ 1, [ 2, [Array] ] ] }"),
+                ("{ a: { b: { c: { d: 1 } } } }", "{ a: { b: { c: [Object] } } }"),
+                ("{ a: { b: { c: {} } } }", "{ a: { b: { c: {} } } }"),
+                ("[{ x: 1 }, [], {}]", "[ { x: 1 }, [], {} ]"),
+                ("{ 'a-b': 1, $c: 2, \"it's\": \"it's\" }", "{ 'a-b': 1, $c: 2, \"it's\": \"it's\" }"),
+                ("[1, , , 4]", "[ 1, <2 empty items>, 4 ]"),
+                ("new Array(102).fill(0)", &format!("[ {}, ... 2 more items ]", ["0"; 100].join(", "))),
+                // Built-in objects
+                ("new Map([['a', { b: 1 }]])", "Map(1) { 'a' => { b: 1 } }"),
+                ("new Set([1, 'x'])", "Set(2) { 1, 'x' }"),
+                ("new Uint8Array([1, 2])", "Uint8Array(2) [ 1, 2 ]"),
+                ("{ d: new Date(0), r: /a+/g, e: new TypeError('bad') }", "{ d: 1970-01-01T00:00:00.000Z, r: /a+/g, e: [TypeError: bad] }"),
+                ("new Error('top')", "Error: top"),
+                ("{ f() {}, g: () => {}, C: class Foo {} }", "{ f: [Function: f], g: [Function: g], C: [class Foo] }"),
+                ("{ n: -0, b: 10n, s: Symbol('s'), u: undefined }", "{ n: -0, b: 10n, s: Symbol(s), u: undefined }"),
+                ("Object.create(null)", "[Object: null prototype] {}"),
+                ("new (class Point { constructor() { this.x = 1; } })()", "Point { x: 1 }"),
+                ("{ get a() { return 1; }, set b(v) {} }", "{ a: [Getter], b: [Setter] }"),
+            ];
+            for (value, expected) in cases {
+                stream.clear();
+                this.eval::<(), _>(format!("console.log({value});"))?;
+                assert_eq!(
+                    format!("{expected}\n"),
+                    std::str::from_utf8(stream.buffer.borrow().as_slice()).unwrap(),
+                    "unexpected output for {value}"
+                );
+            }
+
+            // Self-referential objects
+            stream.clear();
+            this.eval::<(), _>(
+                r#"
+                const o = { name: "o" };
+                o.self = o;
+                o.list = [o];
+                const shared = { s: 1 };
+                console.log(o);
+                console.log({ a: shared, b: shared });
+                "#,
+            )?;
+            assert_eq!(
+                "{ name: 'o', self: [Circular], list: [ [Circular] ] }\n{ a: { s: 1 }, b: { s: 1 } }\n",
+                std::str::from_utf8(stream.buffer.borrow().as_slice()).unwrap()
+            );
+
+            Ok::<_, Error>(())
+        })?;
+
+        // The depth is configurable
+        let runtime = Runtime::default();
+        runtime.context().with(|this| {
+            let options = InspectOptions {
+                depth: 0,
+                ..Default::default()
+            };
+            register(
+                this.clone(),
+                stream.clone(),
+                stream.clone(),
+                stream.clone(),
+                options,
+                None,
+            )
+            .unwrap();
+            stream.clear();
+            this.eval::<(), _>("console.log({ a: { b: 1 }, c: [] });")?;
+            assert_eq!(
+                b"{ a: [Object], c: [] }\n",
+                stream.buffer.borrow().as_slice()
+            );
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_table() -> Result<()> {
+        let mut log_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            register(
+                this.clone(),
+                log_stream.clone(),
+                error_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+            macro_rules! test_console_table {
+                ($js:expr, $expected:expr) => {{
+                    this.eval::<(), _>($js)?;
+                    assert_eq!(
+                        $expected,
+                        std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+                    );
+                    log_stream.clear();
+                }};
+            }
+
+            test_console_table!(
+                "console.table([{ a: 1, b: 'two' }, { a: 3, c: true }])",
+                concat!(
+                    "┌─────────┬───┬─────┬──────┐\n",
+                    "│ (index) │ a │ b   │ c    │\n",
+                    "├─────────┼───┼─────┼──────┤\n",
+                    "│ 0       │ 1 │ two │      │\n",
+                    "│ 1       │ 3 │     │ true │\n",
+                    "└─────────┴───┴─────┴──────┘\n",
+                )
+            );
+
+            test_console_table!(
+                "console.table(['apple', 'banana'])",
+                concat!(
+                    "┌─────────┬────────┐\n",
+                    "│ (index) │ Values │\n",
+                    "├─────────┼────────┤\n",
+                    "│ 0       │ apple  │\n",
+                    "│ 1       │ banana │\n",
+                    "└─────────┴────────┘\n",
+                )
+            );
+
+            test_console_table!(
+                "console.table({ x: { a: 1, b: 2 }, y: { a: 3, b: 4 } }, ['b'])",
+                concat!(
+                    "┌─────────┬───┐\n",
+                    "│ (index) │ b │\n",
+                    "├─────────┼───┤\n",
+                    "│ x       │ 2 │\n",
+                    "│ y       │ 4 │\n",
+                    "└─────────┴───┘\n",
+                )
+            );
+
+            test_console_table!("console.table('not tabular')", "not tabular\n");
+
+            assert!(error_stream.buffer.borrow().is_empty());
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_group() -> Result<()> {
+        let log_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            let state = register(
+                this.clone(),
+                log_stream.clone(),
+                error_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+            this.eval::<(), _>(
+                r#"
+                console.groupEnd();
+                console.log("top");
+                console.group("outer");
+                console.log("a\nb");
+                console.groupCollapsed("inner", 1);
+                console.error("nested");
+                console.groupEnd();
+                console.log("back");
+                console.groupEnd();
+                console.log("done");
+                console.group();
+                "#,
+            )?;
+            assert_eq!(
+                "top\nouter\n  a\n  b\n  inner 1\n  back\ndone\n",
+                std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+            );
+            assert_eq!(
+                "    nested\n",
+                std::str::from_utf8(error_stream.buffer.borrow().as_slice()).unwrap()
+            );
+
+            state.reset();
+            this.eval::<(), _>("console.log('reset');")?;
+            assert!(std::str::from_utf8(log_stream.buffer.borrow().as_slice())
+                .unwrap()
+                .ends_with("done\nreset\n"));
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_time() -> Result<()> {
+        let log_stream = SharedStream::default();
+        let warn_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            register(this.clone(), log_stream.clone(), warn_stream.clone(), error_stream.clone(), InspectOptions::default(), None).unwrap();
+            this.eval::<(), _>(
+                r#"
+                console.time("work");
+                let n = 0;
+                for (let i = 0; i < 1000; i++) { n += i; }
+                console.timeLog("work", "halfway", n);
+                console.timeEnd("work");
+                console.timeEnd("work");
+                console.timeLog("missing");
+                "#,
+            )?;
+
+            let logs = String::from_utf8(log_stream.buffer.borrow().clone())?;
+            let lines = logs.lines().collect::<Vec<_>>();
+            assert_eq!(2, lines.len());
+            for (line, suffix) in lines.iter().zip(["ms halfway 499500", "ms"]) {
+                let elapsed = line
+                    .strip_prefix("work: ")
+                    .and_then(|rest| rest.strip_suffix(suffix))
+                    .unwrap_or_else(|| panic!("unexpected output: {line}"));
+                let elapsed: f64 = elapsed.parse()?;
+                assert!((0.0..60_000.0).contains(&elapsed));
+            }
+
+            assert_eq!(
+                "Warning: No such label 'work' for console.timeEnd()\nWarning: No such label 'missing' for console.timeLog()\n",
+                std::str::from_utf8(warn_stream.buffer.borrow().as_slice()).unwrap()
+            );
+            assert!(error_stream.buffer.borrow().is_empty());
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_format_specifiers() -> Result<()> {
+        let mut log_stream = SharedStream::default();
+        let warn_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            register(
+                this.clone(),
+                log_stream.clone(),
+                warn_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+
+            let cases = [
+                (r#""%s has %d items", "cart", 3"#, "cart has 3 items"),
+                (r#""%d", 1.5"#, "1.5"),
+                (r#""%i", 1.5"#, "1"),
+                (r#""%f", "2.5px""#, "2.5"),
+                (r#""%d", "abc""#, "NaN"),
+                (r#""%j", { a: [1, "b"] }"#, r#"{"a":[1,"b"]}"#),
+                (r#""%o", { a: 1 }"#, "{ a: 1 }"),
+                (r#""%O", "str""#, "'str'"),
+                (r#""100%%""#, "100%"),
+                (r#""%s and %s", "one""#, "one and %s"),
+                (r#""%x %s", "y""#, "%x y"),
+                (r#""%s", "a", "b", 1"#, "a b 1"),
+                (r#""trailing %""#, "trailing %"),
+                (r#"1, "%s", 2"#, "1 %s 2"),
+                (r#""", "x""#, " x"),
+                (r#""", """#, " "),
+                (r#""""#, ""),
+            ];
+            for (args, expected) in cases {
+                log_stream.clear();
+                this.eval::<(), _>(format!("console.log({args});"))?;
+                assert_eq!(
+                    format!("{expected}\n"),
+                    std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap(),
+                    "unexpected output for console.log({args})"
+                );
+            }
+
+            log_stream.clear();
+            this.eval::<(), _>("const o = {}; o.o = o; console.log('%j', o);")?;
+            assert_eq!(b"[Circular]\n", log_stream.buffer.borrow().as_slice());
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_inspect_max_depth() -> Result<()> {
+        let mut log_stream = SharedStream::default();
+        let warn_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            let options = InspectOptions {
+                depth: usize::MAX,
+                max_depth: 3,
+            };
+            register(this.clone(), log_stream.clone(), warn_stream.clone(), error_stream.clone(), options, None).unwrap();
+
+            // Getters returning a new object on every access recurse forever
+            this.eval::<(), _>(
+                r#"
+                const recursive = { get self() { return { ...recursive }; } };
+                function deep() {
+                    return { name: "deep", get child() { return deep(); } };
+                }
+                console.log("%j", recursive);
+                console.log("%j", deep());
+                "#,
+            )?;
+            assert_eq!(
+                "[Inspection error]\n[Inspection error]\n",
+                std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+            );
+
+            // Values within the limit are printed
+            log_stream.clear();
+            this.eval::<(), _>(r#"console.log("%j", { a: { b: { c: 1 } }, d: [1, [2]] });"#)?;
+            assert_eq!(
+                "{\"a\":{\"b\":{\"c\":1}},\"d\":[1,[2]]}\n",
+                std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+            );
+
+            log_stream.clear();
+            this.eval::<(), _>(r#"console.log("%j", { a: { b: { c: { d: 1 } } } });"#)?;
+            assert_eq!(b"[Inspection error]\n", log_stream.buffer.borrow().as_slice());
+
+            // Throwing getters are reported the same way
+            log_stream.clear();
+            this.eval::<(), _>(r#"console.log("%j", { get x() { throw new Error("boom"); } });"#)?;
+            assert_eq!(b"[Inspection error]\n", log_stream.buffer.borrow().as_slice());
+
+            // The inspector doesn't call getters, and applies the same limit
+            log_stream.clear();
+            this.eval::<(), _>(
+                r#"
+                console.log(recursive);
+                console.log({ a: { b: { c: 1 } } });
+                console.log({ a: { b: { c: { d: 1 } } } });
+                console.log(new Proxy({}, { ownKeys() { throw new Error("boom"); } }));
+                "#,
+            )?;
+            assert_eq!(
+                "{ self: [Getter] }\n{ a: { b: { c: 1 } } }\n[Inspection error]\n[Inspection error]\n",
+                std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+            );
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_console_count() -> Result<()> {
+        let mut log_stream = SharedStream::default();
+        let warn_stream = SharedStream::default();
+        let error_stream = SharedStream::default();
+        let runtime = Runtime::default();
+        let ctx = runtime.context();
+
+        ctx.with(|this| {
+            let state = register(
+                this.clone(),
+                log_stream.clone(),
+                warn_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+            this.eval::<(), _>(
+                r#"
+                console.count("x");
+                console.count("x");
+                console.count("x");
+                console.countReset("x");
+                console.count("x");
+                console.count();
+                console.countReset("missing");
+                "#,
+            )?;
+            assert_eq!(
+                "x: 1\nx: 2\nx: 3\nx: 1\ndefault: 1\n",
+                std::str::from_utf8(log_stream.buffer.borrow().as_slice()).unwrap()
+            );
+            assert_eq!(
+                "Warning: Count for 'missing' does not exist\n",
+                std::str::from_utf8(warn_stream.buffer.borrow().as_slice()).unwrap()
+            );
+            assert!(error_stream.buffer.borrow().is_empty());
+
+            // Counters restart for each invocation
+            log_stream.clear();
+            state.reset();
+            this.eval::<(), _>("console.count('x');")?;
+            assert_eq!(b"x: 1\n", log_stream.buffer.borrow().as_slice());
+
+            Ok::<_, Error>(())
+        })?;
+
+        Ok(())
+    }
+
     #[test]
     fn test_console_streams() -> Result<()> {
         let mut log_stream = SharedStream::default();
@@ -187,7 +1283,15 @@ mod tests {
         let ctx = runtime.context();
 
         ctx.with(|this| {
-            register(this.clone(), log_stream.clone(), warn_stream.clone(), error_stream.clone()).unwrap();
+            register(
+                this.clone(),
+                log_stream.clone(),
+                warn_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
             this.eval::<(), _>("console.log(\"hello world\");")?;
             assert_eq!(b"hello world\n", log_stream.buffer.borrow().as_slice());
             assert!(warn_stream.buffer.borrow().is_empty());
@@ -225,8 +1329,16 @@ mod tests {
 
         ctx.with(|this| {
             // Normal mode: log->stdout, warn->stderr, error->stderr
-            register(this.clone(), log_stream.clone(), warn_stream.clone(), error_stream.clone()).unwrap();
-            
+            register(
+                this.clone(),
+                log_stream.clone(),
+                warn_stream.clone(),
+                error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+
             this.eval::<(), _>("console.log('normal log');")?;
             this.eval::<(), _>("console.warn('normal warn');")?;
             this.eval::<(), _>("console.error('normal error');")?;
@@ -245,8 +1357,16 @@ mod tests {
 
         ctx.with(|this| {
             // Redirected mode: all -> stderr (simulated by using same stream)
-            register(this.clone(), redirected_log_stream.clone(), redirected_warn_stream.clone(), redirected_error_stream.clone()).unwrap();
-            
+            register(
+                this.clone(),
+                redirected_log_stream.clone(),
+                redirected_warn_stream.clone(),
+                redirected_error_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+
             this.eval::<(), _>("console.log('redirected log');")?;
             this.eval::<(), _>("console.warn('redirected warn');")?;
             this.eval::<(), _>("console.error('redirected error');")?;
@@ -263,8 +1383,16 @@ mod tests {
 
         ctx.with(|this| {
             // Redirect mode: console.log, warn, error all use stderr
-            register(this.clone(), all_stderr_stream.clone(), all_stderr_stream.clone(), all_stderr_stream.clone()).unwrap();
-            
+            register(
+                this.clone(),
+                all_stderr_stream.clone(),
+                all_stderr_stream.clone(),
+                all_stderr_stream.clone(),
+                InspectOptions::default(),
+                None,
+            )
+            .unwrap();
+
             this.eval::<(), _>("console.log('redirect-log');")?;
             this.eval::<(), _>("console.warn('redirect-warn');")?;
             this.eval::<(), _>("console.error('redirect-error');")?;
@@ -0,0 +1,64 @@
+(function () {
+  const __javy_crypto_digest = globalThis.__javy_crypto_digest;
+  const algorithms = ["SHA-1", "SHA-256", "SHA-384", "SHA-512"];
+
+  function notSupportedError(message) {
+    const error = new Error(message);
+    error.name = "NotSupportedError";
+    return error;
+  }
+
+  // Normalizes an algorithm identifier, a name or an object with a `name`,
+  // to the upper case name of a supported digest algorithm.
+  function normalizeAlgorithm(algorithm) {
+    const name =
+      algorithm !== null && typeof algorithm === "object"
+        ? algorithm.name
+        : algorithm;
+    if (name === undefined) {
+      throw new TypeError("Algorithm: name: Missing or not a string");
+    }
+    const normalized = String(name).toUpperCase();
+    if (!algorithms.includes(normalized)) {
+      throw notSupportedError("Unrecognized algorithm name");
+    }
+    return normalized;
+  }
+
+  class SubtleCrypto {
+    digest(algorithm, data) {
+      // The digest is computed in a microtask, and errors reject the
+      // returned promise rather than being thrown.
+      return Promise.resolve().then(() => {
+        const name = normalizeAlgorithm(algorithm);
+        let buffer = data;
+        let byteOffset = 0;
+        let byteLength = data && data.byteLength;
+        if (ArrayBuffer.isView(data)) {
+          buffer = data.buffer;
+          byteOffset = data.byteOffset;
+        }
+        if (!(buffer instanceof ArrayBuffer)) {
+          throw new TypeError(
+            "The provided value is not of type '(ArrayBuffer or ArrayBufferView)'",
+          );
+        }
+        return __javy_crypto_digest(name, buffer, byteOffset, byteLength);
+      });
+    }
+  }
+
+  const subtle = new SubtleCrypto();
+
+  class Crypto {
+    get subtle() {
+      return subtle;
+    }
+  }
+
+  globalThis.SubtleCrypto = SubtleCrypto;
+  globalThis.Crypto = Crypto;
+  globalThis.crypto = new Crypto();
+
+  Reflect.deleteProperty(globalThis, "__javy_crypto_digest");
+})();
@@ -0,0 +1,214 @@
+use crate::{
+    hold, hold_and_release,
+    quickjs::{context::EvalOptions, ArrayBuffer, Ctx, Function, Value},
+    to_js_error, val_to_string, Args,
+};
+use anyhow::{anyhow, bail, Error, Result};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+/// Register the global `crypto` object with `crypto.subtle.digest`.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let globals = this.globals();
+    globals.set(
+        "__javy_crypto_digest",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            digest(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./crypto.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+/// Hash the given bytes of an `ArrayBuffer` with a normalized algorithm name,
+/// returning the digest as a new `ArrayBuffer`.
+fn digest(args: Args<'_>) -> Result<Value<'_>> {
+    let (cx, args) = args.release();
+    if args.len() != 4 {
+        bail!(
+            "Wrong number of arguments. Expected 4 arguments. Got: {}",
+            args.len()
+        );
+    }
+
+    let algorithm = val_to_string(&cx, args[0].clone())?;
+    let buffer = args[1]
+        .as_object()
+        .and_then(|buffer| buffer.as_array_buffer())
+        .ok_or_else(|| anyhow!("buffer must be an ArrayBuffer"))?
+        .as_bytes()
+        .ok_or_else(|| anyhow!("Couldn't retrieve &[u8] from buffer"))?;
+    let byte_offset = args[2]
+        .as_number()
+        .ok_or_else(|| anyhow!("offset must be a number"))? as usize;
+    let byte_length = args[3]
+        .as_number()
+        .ok_or_else(|| anyhow!("byte_length must be a number"))? as usize;
+    let data = buffer
+        .get(byte_offset..byte_offset + byte_length)
+        .ok_or_else(|| anyhow!("offset and length are out of bounds"))?;
+
+    let hash = match algorithm.as_str() {
+        "SHA-1" => Sha1::digest(data).to_vec(),
+        "SHA-256" => Sha256::digest(data).to_vec(),
+        "SHA-384" => Sha384::digest(data).to_vec(),
+        "SHA-512" => Sha512::digest(data).to_vec(),
+        _ => bail!("Unrecognized algorithm name: {algorithm}"),
+    };
+    Ok(ArrayBuffer::new(cx, hash)?.into_value())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        quickjs::{context::EvalOptions, Value},
+        Config, Runtime,
+    };
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.crypto(true).text_encoding(true);
+        Runtime::new(config)
+    }
+
+    /// Evaluate `script`, run the pending jobs and return the global `result`.
+    fn eval(runtime: &Runtime, script: &str) -> Result<String> {
+        runtime.context().with(|cx| {
+            let mut opts = EvalOptions::default();
+            opts.strict = false;
+            cx.eval_with_options::<(), _>(script, opts)?;
+            Ok::<_, Error>(())
+        })?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.globals().get("result")?;
+            Ok(result)
+        })
+    }
+
+    const TO_HEX: &str = r#"
+        function toHex(buffer) {
+            return Array.from(new Uint8Array(buffer))
+                .map((byte) => byte.toString(16).padStart(2, "0"))
+                .join("");
+        }
+    "#;
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof crypto")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval(
+            &runtime,
+            &format!(
+                r#"{TO_HEX}
+                const data = new TextEncoder().encode("abc");
+                result = "pending";
+                Promise.all([
+                    crypto.subtle.digest("SHA-1", data),
+                    crypto.subtle.digest("sha-256", data.buffer),
+                    crypto.subtle.digest({{ name: "SHA-384" }}, new DataView(data.buffer)),
+                    crypto.subtle.digest("SHA-512", data),
+                ]).then((digests) => {{
+                    result = digests.map(toHex).join(",");
+                }});
+                "#
+            ),
+        )?;
+        let expected = [
+            "a9993e364706816aba3e25717850c26c9cd0d89d",
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "cb00753f45a35e8bb5a03d699ac65007272c32ab0eded1631a8b605a43ff5bed\
+             8086072ba1e7cc2358baeca134c825a7",
+            "ddaf35a193617abacc417349ae20413112e6fa4e89a97ea20a9eeee64b55d39a\
+             2192992a274fc1a836ba3c23a3feebbd454d4423643ce80e2a9ac94fa54ca49f",
+        ];
+        assert_eq!(expected.join(","), result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_of_a_view() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval(
+            &runtime,
+            &format!(
+                r#"{TO_HEX}
+                const data = new TextEncoder().encode("xxabcxx").subarray(2, 5);
+                crypto.subtle.digest("SHA-256", data).then((digest) => {{
+                    result = toHex(digest);
+                }});
+                "#
+            ),
+        )?;
+        assert_eq!(
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_digest_is_asynchronous() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let value: Value = cx.eval(
+                r#"
+                let resolved = false;
+                crypto.subtle.digest("SHA-256", new Uint8Array()).then(() => {
+                    resolved = true;
+                });
+                resolved
+                "#,
+            )?;
+            assert_eq!(Some(false), value.as_bool());
+            Ok::<_, Error>(())
+        })?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            let resolved: bool = cx.eval("resolved")?;
+            assert!(resolved);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval(
+            &runtime,
+            r#"
+            result = "";
+            crypto.subtle.digest("MD5", new Uint8Array()).catch((e) => {
+                result += `${e.name}: ${e.message};`;
+            });
+            crypto.subtle.digest("SHA-256", "abc").catch((e) => {
+                result += `${e.name}: ${e.message}`;
+            });
+            "#,
+        )?;
+        assert_eq!(
+            "NotSupportedError: Unrecognized algorithm name;\
+             TypeError: The provided value is not of type '(ArrayBuffer or ArrayBufferView)'",
+            result
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,202 @@
+use std::{cell::RefCell, collections::HashSet, io::Write};
+
+use crate::{
+    hold, hold_and_release,
+    quickjs::{Ctx, Function, Value},
+    to_js_error, Args,
+};
+use anyhow::{anyhow, bail, Error, Result};
+
+/// The deprecated APIs and the warning written the first time they're used,
+/// by name.
+const DEPRECATIONS: &[(&str, &str)] = &[
+    (
+        "Event.srcElement",
+        "`Event.prototype.srcElement` is deprecated, use `Event.prototype.target` instead.",
+    ),
+    (
+        "Event.returnValue",
+        "`Event.prototype.returnValue` is deprecated, use `Event.prototype.defaultPrevented` and `Event.prototype.preventDefault` instead.",
+    ),
+];
+
+/// Register the `__javy_deprecated` helper, which the JavaScript APIs call
+/// with the name of a deprecated API when it's used. A warning is written to
+/// `stream` the first time each deprecated API is used.
+///
+/// The APIs capture the helper when they're registered, so it must be
+/// registered before them and removed with [`unregister`] afterwards.
+pub(crate) fn register<T>(this: Ctx<'_>, stream: T) -> Result<()>
+where
+    T: Write + 'static,
+{
+    let stream = RefCell::new(stream);
+    let warned = RefCell::new(HashSet::new());
+
+    this.globals().set(
+        "__javy_deprecated",
+        Function::new(this.clone(), move |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            warn_once(hold!(cx.clone(), args), &stream, &warned).map_err(|e| to_js_error(cx, e))
+        })?,
+    )?;
+
+    Ok::<_, Error>(())
+}
+
+/// Remove the `__javy_deprecated` helper once the APIs using it are
+/// registered.
+pub(crate) fn unregister(this: Ctx<'_>) -> Result<()> {
+    this.globals().remove("__javy_deprecated")?;
+    Ok(())
+}
+
+/// Write the warning of the deprecated API named by the first argument, unless
+/// it was already written.
+fn warn_once<'js, T: Write>(
+    args: Args<'js>,
+    stream: &RefCell<T>,
+    warned: &RefCell<HashSet<&'static str>>,
+) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    if args.len() != 1 {
+        bail!(
+            "Wrong number of arguments. Expected 1 argument. Got: {}",
+            args.len()
+        );
+    }
+
+    let name = args[0]
+        .as_string()
+        .ok_or_else(|| anyhow!("name must be a string"))?
+        .to_string()?;
+    let (name, message) = DEPRECATIONS
+        .iter()
+        .find(|(deprecated, _)| *deprecated == name)
+        .ok_or_else(|| anyhow!("Unknown deprecation: {name}"))?;
+
+    if warned.borrow_mut().insert(name) {
+        writeln!(stream.borrow_mut(), "Warning: {message}")?;
+    }
+
+    Ok(Value::new_undefined(cx))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{register, unregister};
+    use crate::{apis::events, Config, Runtime};
+    use anyhow::{Error, Result};
+    use std::{cell::RefCell, io, rc::Rc};
+
+    #[derive(Clone, Default)]
+    struct SharedStream(Rc<RefCell<Vec<u8>>>);
+
+    impl io::Write for SharedStream {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_warns_once() -> Result<()> {
+        let stream = SharedStream::default();
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            register(cx.clone(), stream.clone())?;
+            events::register(cx.clone())?;
+            unregister(cx.clone())?;
+
+            let result: bool = cx.eval(
+                r#"
+                const target = new EventTarget();
+                const event = new Event("test");
+                target.dispatchEvent(event);
+                target.dispatchEvent(new Event("test"));
+                event.srcElement === target &&
+                    event.srcElement === target &&
+                    new Event("test").srcElement === null
+                "#,
+            )?;
+            assert!(result);
+            assert_eq!(
+                "Warning: `Event.prototype.srcElement` is deprecated, use `Event.prototype.target` instead.\n",
+                String::from_utf8(stream.0.borrow().clone())?
+            );
+
+            cx.eval::<(), _>("new Event('test').returnValue = false")?;
+            cx.eval::<(), _>("new Event('test').returnValue")?;
+            let output = String::from_utf8(stream.0.borrow().clone())?;
+            assert_eq!(2, output.lines().count());
+            assert!(output
+                .lines()
+                .nth(1)
+                .unwrap()
+                .contains("`Event.prototype.returnValue` is deprecated"));
+
+            let result: String = cx.eval("typeof __javy_deprecated")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_deprecated_apis_work_without_warnings() -> Result<()> {
+        let mut config = Config::default();
+        config.events(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let result: bool = cx.eval(
+                r#"
+                const event = new Event("test", { cancelable: true });
+                event.returnValue = false;
+                event.defaultPrevented && !event.returnValue
+                "#,
+            )?;
+            assert!(result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_helper_is_not_global() -> Result<()> {
+        let mut config = Config::default();
+        config.events(true).deprecation_warnings(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof __javy_deprecated")?;
+            assert_eq!("undefined", result);
+            let result: bool = cx.eval("new Event('test').srcElement === null")?;
+            assert!(result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_deprecation() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            register(cx.clone(), SharedStream::default())?;
+            let result: String = cx.eval(
+                r#"
+                try {
+                    __javy_deprecated("unknown");
+                    "no error"
+                } catch (e) {
+                    e.message
+                }
+                "#,
+            )?;
+            assert_eq!("Unknown deprecation: unknown", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
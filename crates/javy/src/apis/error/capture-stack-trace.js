@@ -0,0 +1,49 @@
+(function () {
+  function frameName(frame) {
+    const trimmed = frame.trim();
+    if (!trimmed.startsWith("at ")) {
+      return undefined;
+    }
+    const rest = trimmed.slice(3);
+    const end = rest.indexOf(" ");
+    return end === -1 ? rest : rest.slice(0, end);
+  }
+
+  Object.defineProperty(Error, "captureStackTrace", {
+    value: function captureStackTrace(targetObject, constructorOpt) {
+      if (
+        targetObject === null ||
+        (typeof targetObject !== "object" && typeof targetObject !== "function")
+      ) {
+        throw new TypeError("Invalid argument");
+      }
+
+      // QuickJS only records the call frames in `stack`, the first of which
+      // is this function.
+      let frames = (new Error().stack || "")
+        .split("\n")
+        .filter((frame) => frame.length > 0)
+        .slice(1);
+
+      if (typeof constructorOpt === "function" && constructorOpt.name) {
+        const index = frames.findIndex(
+          (frame) => frameName(frame) === constructorOpt.name
+        );
+        if (index !== -1) {
+          frames = frames.slice(index + 1);
+        }
+      }
+
+      const header = Error.prototype.toString.call(targetObject);
+      Object.defineProperty(targetObject, "stack", {
+        value: [header, ...frames].join("\n"),
+        writable: true,
+        configurable: true,
+        enumerable: false,
+      });
+    },
+    writable: true,
+    configurable: true,
+    enumerable: false,
+  });
+})();
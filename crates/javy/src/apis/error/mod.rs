@@ -0,0 +1,140 @@
+use crate::quickjs::{context::EvalOptions, Ctx};
+use anyhow::{Error, Result};
+
+/// Register the `Error.captureStackTrace` function.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./capture-stack-trace.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+/// Register the global `reportError` function, which writes errors to the
+/// stream used by `console.error`.
+///
+/// Requires `console` to be registered.
+pub(crate) fn register_report_error(this: Ctx<'_>) -> Result<()> {
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./report-error.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{quickjs::Value, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.error_capture_stack_trace(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_capture_stack_trace_sets_stack() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|this| {
+            let stack: String = this.eval(
+                r#"
+                const o = {};
+                Error.captureStackTrace(o);
+                o.stack
+                "#,
+            )?;
+            assert!(!stack.is_empty());
+            assert!(stack.starts_with("Error"));
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_stack_trace_trims_constructor_frames() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|this| {
+            let stack: String = this.eval(
+                r#"
+                function MyError(message) {
+                    this.name = "MyError";
+                    this.message = message;
+                    Error.captureStackTrace(this, MyError);
+                }
+                function outer() {
+                    return new MyError("boom");
+                }
+                outer().stack
+                "#,
+            )?;
+            assert!(stack.starts_with("MyError: boom\n"));
+            assert!(!stack.contains("at MyError"));
+            assert!(stack.contains("at outer"));
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_stack_trace_non_object_throws() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|this| {
+            let result: Value = this.eval(
+                r#"
+                let threw = false;
+                try {
+                    Error.captureStackTrace(1);
+                } catch (e) {
+                    threw = e instanceof TypeError;
+                }
+                threw
+                "#,
+            )?;
+            assert_eq!(Some(true), result.as_bool());
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_error_does_not_throw() -> Result<()> {
+        let mut config = Config::default();
+        config.report_error(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|this| {
+            let result: String = this.eval(
+                r#"
+                reportError(new Error("x"));
+                reportError("not an error");
+                "continued"
+                "#,
+            )?;
+            assert_eq!("continued", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_report_error_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|this| {
+            let result: String = this.eval("typeof reportError")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_capture_stack_trace_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|this| {
+            let result: String = this.eval("typeof Error.captureStackTrace")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
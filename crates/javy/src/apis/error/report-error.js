@@ -0,0 +1,23 @@
+(function () {
+  // Capture `console.error` so that reported errors are routed to the same
+  // stream even if `console` is replaced later on.
+  const consoleError = console.error;
+
+  globalThis.reportError = function reportError(error) {
+    if (arguments.length < 1) {
+      throw new TypeError("reportError requires 1 argument");
+    }
+
+    let message;
+    if (error instanceof Error) {
+      message = `Uncaught ${error.name}: ${error.message}`;
+      const stack = typeof error.stack === "string" ? error.stack.trimEnd() : "";
+      if (stack.length > 0) {
+        message += `\n${stack}`;
+      }
+    } else {
+      message = `Uncaught ${String(error)}`;
+    }
+    consoleError(message);
+  };
+})();
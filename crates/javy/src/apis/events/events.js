@@ -0,0 +1,277 @@
+(function () {
+  const NONE = 0;
+  const AT_TARGET = 2;
+  const __javy_deprecated = globalThis.__javy_deprecated;
+
+  // The listeners of each target, by event type.
+  const listenersOf = new WeakMap();
+
+  function domError(name, message) {
+    const error = new Error(message);
+    error.name = name;
+    return error;
+  }
+
+  // Reports an error thrown by a listener without interrupting the dispatch.
+  function reportListenerError(error) {
+    if (typeof globalThis.reportError === "function") {
+      globalThis.reportError(error);
+    } else {
+      console.error(error);
+    }
+  }
+
+  // Warns about the use of a deprecated API when deprecation warnings are
+  // enabled.
+  function deprecated(name) {
+    if (__javy_deprecated !== undefined) {
+      __javy_deprecated(name);
+    }
+  }
+
+  function flattenOptions(options) {
+    if (typeof options === "boolean") {
+      return { capture: options };
+    }
+    if (options === null || typeof options !== "object") {
+      return { capture: false };
+    }
+    return {
+      capture: Boolean(options.capture),
+      once: Boolean(options.once),
+      passive: Boolean(options.passive),
+      signal: options.signal,
+    };
+  }
+
+  function targetListeners(target) {
+    if (!listenersOf.has(target)) {
+      throw new TypeError("Illegal invocation");
+    }
+    return listenersOf.get(target);
+  }
+
+  function removeListener(listeners, type, entry) {
+    entry.removed = true;
+    const entries = listeners.get(type);
+    const index = entries.indexOf(entry);
+    if (index !== -1) {
+      entries.splice(index, 1);
+    }
+  }
+
+  class Event {
+    constructor(type, init) {
+      if (arguments.length === 0) {
+        throw new TypeError(
+          "Failed to construct 'Event': 1 argument required, but only 0 present."
+        );
+      }
+      const options = init === undefined || init === null ? {} : init;
+      Object.defineProperties(this, {
+        _type: { value: String(type) },
+        _bubbles: { value: Boolean(options.bubbles) },
+        _cancelable: { value: Boolean(options.cancelable) },
+        _composed: { value: Boolean(options.composed) },
+        _target: { value: null, writable: true },
+        _currentTarget: { value: null, writable: true },
+        _eventPhase: { value: NONE, writable: true },
+        _defaultPrevented: { value: false, writable: true },
+        _inPassiveListener: { value: false, writable: true },
+        _dispatching: { value: false, writable: true },
+        _stopImmediatePropagation: { value: false, writable: true },
+        _timeStamp: { value: Date.now() },
+      });
+    }
+
+    get type() {
+      return this._type;
+    }
+
+    get target() {
+      return this._target;
+    }
+
+    get currentTarget() {
+      return this._currentTarget;
+    }
+
+    get eventPhase() {
+      return this._eventPhase;
+    }
+
+    get bubbles() {
+      return this._bubbles;
+    }
+
+    get cancelable() {
+      return this._cancelable;
+    }
+
+    get composed() {
+      return this._composed;
+    }
+
+    get defaultPrevented() {
+      return this._defaultPrevented;
+    }
+
+    get isTrusted() {
+      return false;
+    }
+
+    get timeStamp() {
+      return this._timeStamp;
+    }
+
+    get srcElement() {
+      deprecated("Event.srcElement");
+      return this._target;
+    }
+
+    get returnValue() {
+      deprecated("Event.returnValue");
+      return !this._defaultPrevented;
+    }
+
+    set returnValue(value) {
+      deprecated("Event.returnValue");
+      if (!value) {
+        this.preventDefault();
+      }
+    }
+
+    composedPath() {
+      return this._currentTarget === null ? [] : [this._currentTarget];
+    }
+
+    preventDefault() {
+      if (this._cancelable && !this._inPassiveListener) {
+        this._defaultPrevented = true;
+      }
+    }
+
+    // Events are only dispatched to their target, so there's no propagation
+    // to stop beyond the listeners of the target.
+    stopPropagation() {}
+
+    stopImmediatePropagation() {
+      this._stopImmediatePropagation = true;
+    }
+  }
+
+  for (const [name, value] of [
+    ["NONE", NONE],
+    ["CAPTURING_PHASE", 1],
+    ["AT_TARGET", AT_TARGET],
+    ["BUBBLING_PHASE", 3],
+  ]) {
+    Object.defineProperty(Event, name, { value, enumerable: true });
+    Object.defineProperty(Event.prototype, name, { value, enumerable: true });
+  }
+
+  class EventTarget {
+    constructor() {
+      listenersOf.set(this, new Map());
+    }
+
+    addEventListener(type, callback, options) {
+      const listeners = targetListeners(this);
+      const { capture, once, passive, signal } = flattenOptions(options);
+      if (callback === null || callback === undefined) {
+        return;
+      }
+      if (signal !== undefined && signal.aborted) {
+        return;
+      }
+      type = String(type);
+      if (!listeners.has(type)) {
+        listeners.set(type, []);
+      }
+      const entries = listeners.get(type);
+      if (entries.some((e) => e.callback === callback && e.capture === capture)) {
+        return;
+      }
+      const entry = { callback, capture, once, passive, removed: false };
+      entries.push(entry);
+      if (signal !== undefined) {
+        signal.addEventListener("abort", () =>
+          removeListener(listeners, type, entry)
+        );
+      }
+    }
+
+    removeEventListener(type, callback, options) {
+      const listeners = targetListeners(this);
+      const { capture } = flattenOptions(options);
+      const entry = (listeners.get(String(type)) || []).find(
+        (e) => e.callback === callback && e.capture === capture
+      );
+      if (entry !== undefined) {
+        removeListener(listeners, String(type), entry);
+      }
+    }
+
+    dispatchEvent(event) {
+      const listeners = targetListeners(this);
+      if (!(event instanceof Event)) {
+        throw new TypeError(
+          "Failed to execute 'dispatchEvent' on 'EventTarget': parameter 1 is not of type 'Event'."
+        );
+      }
+      if (event._dispatching) {
+        throw domError(
+          "InvalidStateError",
+          "The event is already being dispatched."
+        );
+      }
+
+      event._dispatching = true;
+      event._target = this;
+      event._currentTarget = this;
+      event._eventPhase = AT_TARGET;
+      // Listeners added while dispatching don't receive the event, listeners
+      // removed while dispatching don't receive it if they haven't yet.
+      const entries = (listeners.get(event.type) || []).slice();
+      for (const entry of entries) {
+        if (entry.removed) {
+          continue;
+        }
+        if (entry.once) {
+          removeListener(listeners, event.type, entry);
+        }
+        event._inPassiveListener = entry.passive;
+        try {
+          if (typeof entry.callback === "function") {
+            entry.callback.call(this, event);
+          } else if (typeof entry.callback.handleEvent === "function") {
+            entry.callback.handleEvent(event);
+          }
+        } catch (error) {
+          reportListenerError(error);
+        }
+        event._inPassiveListener = false;
+        if (event._stopImmediatePropagation) {
+          break;
+        }
+      }
+      event._dispatching = false;
+      event._currentTarget = null;
+      event._eventPhase = NONE;
+      event._stopImmediatePropagation = false;
+      return !event._defaultPrevented;
+    }
+  }
+
+  Object.defineProperty(Event.prototype, Symbol.toStringTag, {
+    value: "Event",
+    configurable: true,
+  });
+  Object.defineProperty(EventTarget.prototype, Symbol.toStringTag, {
+    value: "EventTarget",
+    configurable: true,
+  });
+
+  globalThis.Event = Event;
+  globalThis.EventTarget = EventTarget;
+})();
@@ -0,0 +1,178 @@
+use crate::quickjs::{context::EvalOptions, Ctx};
+use anyhow::{Error, Result};
+
+/// Register the global `Event` and `EventTarget` classes.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./events.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.events(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof Event + typeof EventTarget")?;
+            assert_eq!("undefinedundefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_dispatch_order() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const target = new EventTarget();
+                const calls = [];
+                const first = (e) => calls.push(`first:${e.type}:${e.target === target}`);
+                const second = () => calls.push("second");
+                const removed = () => calls.push("removed");
+                target.addEventListener("ping", first);
+                target.addEventListener("ping", removed);
+                target.addEventListener("ping", { handleEvent: () => calls.push("object") });
+                target.addEventListener("ping", second);
+                // Adding the same listener again is ignored
+                target.addEventListener("ping", first);
+                target.addEventListener("other", () => calls.push("other"));
+                target.removeEventListener("ping", removed);
+                target.dispatchEvent(new Event("ping"));
+                target.removeEventListener("ping", first);
+                target.dispatchEvent(new Event("ping"));
+                calls.join(",");
+                "#,
+            )?;
+            assert_eq!("first:ping:true,object,second,object,second", result);
+
+            // Listeners removed while dispatching aren't called
+            let result: String = cx.eval(
+                r#"
+                const other = new EventTarget();
+                const log = [];
+                const late = () => log.push("late");
+                other.addEventListener("ping", () => {
+                    log.push("early");
+                    other.removeEventListener("ping", late);
+                    other.addEventListener("ping", () => log.push("added"));
+                });
+                other.addEventListener("ping", late);
+                other.dispatchEvent(new Event("ping"));
+                log.join(",");
+                "#,
+            )?;
+            assert_eq!("early", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_once() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const target = new EventTarget();
+                let once = 0;
+                let always = 0;
+                target.addEventListener("ping", () => once++, { once: true });
+                target.addEventListener("ping", () => always++);
+                target.dispatchEvent(new Event("ping"));
+                target.dispatchEvent(new Event("ping"));
+                target.dispatchEvent(new Event("ping"));
+                `${once},${always}`;
+                "#,
+            )?;
+            assert_eq!("1,3", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_prevent_default() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const target = new EventTarget();
+                target.addEventListener("ping", (e) => e.preventDefault());
+                const results = [];
+                for (const init of [{ cancelable: true }, {}]) {
+                    const event = new Event("ping", init);
+                    const notPrevented = target.dispatchEvent(event);
+                    results.push(`${notPrevented}:${event.defaultPrevented}`);
+                }
+                const passive = new EventTarget();
+                passive.addEventListener("ping", (e) => e.preventDefault(), { passive: true });
+                results.push(passive.dispatchEvent(new Event("ping", { cancelable: true })));
+                results.join(",");
+                "#,
+            )?;
+            assert_eq!("false:true,true:false,true", result);
+
+            let result: String = cx.eval(
+                r#"
+                const event = new Event("ping");
+                [
+                    event.type,
+                    event.target,
+                    event.currentTarget,
+                    event.eventPhase,
+                    event.defaultPrevented,
+                    Object.prototype.toString.call(event),
+                    Object.prototype.toString.call(new EventTarget()),
+                ].join(",");
+                "#,
+            )?;
+            assert_eq!("ping,,,0,false,[object Event],[object EventTarget]", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_subclass() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                class Emitter extends EventTarget {
+                    emit(type) {
+                        return this.dispatchEvent(new Event(type));
+                    }
+                }
+                const emitter = new Emitter();
+                let target;
+                emitter.addEventListener("ping", function (e) {
+                    target = this === emitter && e.target === emitter;
+                });
+                emitter.emit("ping");
+                const errors = [];
+                try { new EventTarget().dispatchEvent({ type: "ping" }); } catch (e) { errors.push(e.name); }
+                try { EventTarget.prototype.addEventListener.call({}, "ping", () => {}); } catch (e) { errors.push(e.name); }
+                try { new Event(); } catch (e) { errors.push(e.name); }
+                `${target},${errors.join(",")}`;
+                "#,
+            )?;
+            assert_eq!("true,TypeError,TypeError,TypeError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
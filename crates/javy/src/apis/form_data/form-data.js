@@ -0,0 +1,169 @@
+(function () {
+  const Blob = globalThis.Blob;
+  const File = globalThis.File;
+
+  // The entries of each form data, as `[name, value]` pairs in insertion
+  // order.
+  const entriesOf = new WeakMap();
+
+  function formEntries(formData) {
+    if (!entriesOf.has(formData)) {
+      throw new TypeError("Illegal invocation");
+    }
+    return entriesOf.get(formData);
+  }
+
+  function requireArguments(method, count, actual) {
+    if (actual < count) {
+      throw new TypeError(
+        `Failed to execute '${method}' on 'FormData': ${count} arguments required, but only ${actual} present.`
+      );
+    }
+  }
+
+  // Creates an entry the way the XHR specification does: blobs become files,
+  // named `blob` unless a file name is given, and other values are converted
+  // to strings. Files keep referencing the bytes of the blob they're created
+  // from.
+  function createEntry(method, name, value, fileName, argumentCount) {
+    name = String(name);
+    if (!(value instanceof Blob)) {
+      if (argumentCount > 2) {
+        throw new TypeError(
+          `Failed to execute '${method}' on 'FormData': parameter 2 is not of type 'Blob'.`
+        );
+      }
+      return [name, String(value)];
+    }
+    if (value instanceof File && fileName === undefined) {
+      return [name, value];
+    }
+    const options = { type: value.type };
+    if (value instanceof File) {
+      options.lastModified = value.lastModified;
+    }
+    const file = new File(
+      [value],
+      fileName === undefined ? "blob" : String(fileName),
+      options
+    );
+    return [name, file];
+  }
+
+  class FormData {
+    constructor(form) {
+      if (form !== undefined) {
+        throw new TypeError(
+          "Failed to construct 'FormData': HTML forms are not supported."
+        );
+      }
+      entriesOf.set(this, []);
+    }
+
+    append(name, value, fileName) {
+      const entries = formEntries(this);
+      requireArguments("append", 2, arguments.length);
+      entries.push(
+        createEntry("append", name, value, fileName, arguments.length)
+      );
+    }
+
+    set(name, value, fileName) {
+      const entries = formEntries(this);
+      requireArguments("set", 2, arguments.length);
+      const entry = createEntry("set", name, value, fileName, arguments.length);
+      // Replaces the first entry with the name and removes the others.
+      const index = entries.findIndex(([n]) => n === entry[0]);
+      if (index === -1) {
+        entries.push(entry);
+        return;
+      }
+      entries[index] = entry;
+      for (let i = entries.length - 1; i > index; i--) {
+        if (entries[i][0] === entry[0]) {
+          entries.splice(i, 1);
+        }
+      }
+    }
+
+    get(name) {
+      const entries = formEntries(this);
+      requireArguments("get", 1, arguments.length);
+      name = String(name);
+      const entry = entries.find(([n]) => n === name);
+      return entry === undefined ? null : entry[1];
+    }
+
+    getAll(name) {
+      const entries = formEntries(this);
+      requireArguments("getAll", 1, arguments.length);
+      name = String(name);
+      return entries.filter(([n]) => n === name).map(([, value]) => value);
+    }
+
+    has(name) {
+      const entries = formEntries(this);
+      requireArguments("has", 1, arguments.length);
+      name = String(name);
+      return entries.some(([n]) => n === name);
+    }
+
+    delete(name) {
+      const entries = formEntries(this);
+      requireArguments("delete", 1, arguments.length);
+      name = String(name);
+      for (let i = entries.length - 1; i >= 0; i--) {
+        if (entries[i][0] === name) {
+          entries.splice(i, 1);
+        }
+      }
+    }
+
+    forEach(callback, thisArg) {
+      const entries = formEntries(this);
+      if (typeof callback !== "function") {
+        throw new TypeError(
+          "Failed to execute 'forEach' on 'FormData': parameter 1 is not of type 'Function'."
+        );
+      }
+      // Entries appended or deleted by the callback are taken into account,
+      // like the iterators do.
+      for (let i = 0; i < entries.length; i++) {
+        const [name, value] = entries[i];
+        callback.call(thisArg, value, name, this);
+      }
+    }
+
+    *entries() {
+      const entries = formEntries(this);
+      for (let i = 0; i < entries.length; i++) {
+        const [name, value] = entries[i];
+        yield [name, value];
+      }
+    }
+
+    *keys() {
+      for (const [name] of this.entries()) {
+        yield name;
+      }
+    }
+
+    *values() {
+      for (const [, value] of this.entries()) {
+        yield value;
+      }
+    }
+  }
+
+  Object.defineProperty(FormData.prototype, Symbol.iterator, {
+    value: FormData.prototype.entries,
+    writable: true,
+    configurable: true,
+  });
+  Object.defineProperty(FormData.prototype, Symbol.toStringTag, {
+    value: "FormData",
+    configurable: true,
+  });
+
+  globalThis.FormData = FormData;
+})();
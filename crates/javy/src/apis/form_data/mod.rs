@@ -0,0 +1,163 @@
+use crate::quickjs::{context::EvalOptions, Ctx};
+use anyhow::{Error, Result};
+
+/// Register the global `FormData` class.
+///
+/// Requires the `Blob` APIs to be registered.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./form-data.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.form_data(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof FormData")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_multiple_values() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const form = new FormData();
+                form.append("a", "1");
+                form.append("b", 2);
+                form.append("a", "3");
+                const results = [
+                    form.get("a"),
+                    form.getAll("a").join(","),
+                    form.getAll("b")[0],
+                    String(form.get("c")),
+                    form.getAll("c").length,
+                    form.has("b"),
+                ];
+                form.set("a", "4");
+                results.push(form.getAll("a").join(","));
+                form.delete("b");
+                results.push(form.has("b"));
+                results.join(" ")
+                "#,
+            )?;
+            assert_eq!("1 1,3 2 null 0 true 4 false", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_iteration() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const form = new FormData();
+                form.append("a", "1");
+                form.append("b", "2");
+                form.append("a", "3");
+                const seen = [];
+                form.forEach((value, name) => seen.push(`${name}=${value}`));
+                [
+                    [...form].map(([name, value]) => `${name}=${value}`).join("&"),
+                    [...form.keys()].join(","),
+                    [...form.values()].join(","),
+                    seen.join("&"),
+                    Object.prototype.toString.call(form),
+                ].join(" ")
+                "#,
+            )?;
+            assert_eq!(
+                "a=1&b=2&a=3 a,b,a 1,2,3 a=1&b=2&a=3 [object FormData]",
+                result
+            );
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_files() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const form = new FormData();
+                const file = new File(["hello"], "hello.txt", { type: "text/plain" });
+                form.append("file", file);
+                form.append("blob", new Blob(["abc"], { type: "text/csv" }));
+                form.append("renamed", file, "other.txt");
+                const blob = form.get("blob");
+                const renamed = form.get("renamed");
+                [
+                    form.get("file") === file,
+                    form.get("file").name,
+                    blob instanceof File,
+                    blob.name,
+                    blob.type,
+                    blob.size,
+                    renamed.name,
+                    renamed.type,
+                    renamed.lastModified === file.lastModified,
+                ].join(" ")
+                "#,
+            )?;
+            assert_eq!(
+                "true hello.txt true blob text/csv 3 other.txt text/plain true",
+                result
+            );
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: String = cx.eval(
+                r#"
+                const form = new FormData();
+                const errors = [];
+                for (const f of [
+                    () => form.append("a"),
+                    () => form.append("a", "b", "c.txt"),
+                    () => form.get(),
+                    () => FormData.prototype.get.call({}, "a"),
+                    () => new FormData({}),
+                ]) {
+                    try {
+                        f();
+                        errors.push("no error");
+                    } catch (e) {
+                        errors.push(e.name);
+                    }
+                }
+                errors.join(",")
+                "#,
+            )?;
+            assert_eq!("TypeError,TypeError,TypeError,TypeError,TypeError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
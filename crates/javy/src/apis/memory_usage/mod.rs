@@ -0,0 +1,96 @@
+use crate::{
+    blob_registry,
+    quickjs::{self, qjs, Ctx, Function, Object},
+    runtime,
+};
+use anyhow::{Error, Result};
+
+/// Register a `Javy.memoryUsage` function returning the memory used by the
+/// runtime, for scripts that want to throttle themselves.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let globals = this.globals();
+    if globals.get::<_, Object>("Javy").is_err() {
+        globals.set("Javy", Object::new(this.clone())?)?
+    }
+
+    let javy: Object = globals.get("Javy")?;
+    javy.set(
+        "memoryUsage",
+        Function::new(this.clone(), memory_usage)?.with_name("memoryUsage")?,
+    )?;
+    Ok::<_, Error>(())
+}
+
+/// The memory used by the QuickJS runtime of the context, and by the blobs
+/// which live outside of it.
+fn memory_usage(cx: Ctx<'_>) -> quickjs::Result<Object<'_>> {
+    // SAFETY: The runtime of a live context is live.
+    let usage = unsafe { runtime::compute_memory_usage(qjs::JS_GetRuntime(cx.as_raw().as_ptr())) };
+    let object = Object::new(cx.clone())?;
+    // Short names for the bytes used by objects and allocated by the
+    // runtime, which are the numbers scripts usually watch.
+    object.set("used", usage.memory_used_size as f64)?;
+    object.set("allocated", usage.malloc_size as f64)?;
+    object.set("mallocSize", usage.malloc_size as f64)?;
+    object.set("mallocCount", usage.malloc_count as f64)?;
+    object.set("memoryUsedSize", usage.memory_used_size as f64)?;
+    object.set("memoryUsedCount", usage.memory_used_count as f64)?;
+    object.set("atomCount", usage.atom_count as f64)?;
+    object.set("stringCount", usage.str_count as f64)?;
+    object.set("objectCount", usage.obj_count as f64)?;
+    object.set("arrayCount", usage.array_count as f64)?;
+    object.set("functionCount", usage.js_func_count as f64)?;
+    object.set("blobStorageBytes", blob_registry().stored_bytes() as f64)?;
+    Ok(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Runtime};
+    use anyhow::{Error, Result};
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof globalThis.Javy?.memoryUsage")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_usage() -> Result<()> {
+        let mut config = Config::default();
+        config.memory_usage(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let before: f64 = cx.eval("Javy.memoryUsage().memoryUsedSize")?;
+            let after: f64 = cx.eval(
+                "globalThis.array = new Array(100000).fill(0).map((_, i) => ({ i }));
+                Javy.memoryUsage().memoryUsedSize",
+            )?;
+            assert!(after > before + 1_000_000.0, "{before} -> {after}");
+
+            let (used, allocated): (f64, f64) =
+                cx.eval("const { used, allocated } = Javy.memoryUsage(); [used, allocated]")?;
+            assert!(used > before, "{before} -> {used}");
+            assert!(allocated >= used, "{used} used, {allocated} allocated");
+            cx.eval::<(), _>("globalThis.more = new Array(100000).fill(0).map((_, i) => ({ i }))")?;
+            let more: f64 = cx.eval("Javy.memoryUsage().used")?;
+            assert!(more > used + 1_000_000.0, "{used} -> {more}");
+
+            let objects: f64 = cx.eval("Javy.memoryUsage().objectCount")?;
+            assert!(objects > 100_000.0);
+
+            let blob_bytes: f64 = cx.eval(
+                "globalThis.blob = new Blob([new Uint8Array(1024)]);
+                Javy.memoryUsage().blobStorageBytes",
+            )?;
+            assert!(blob_bytes >= 1024.0);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
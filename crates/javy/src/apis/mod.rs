@@ -49,6 +49,12 @@
 //! Provides an implementation of `Javy.IO.readSync` and `Javy.IO.writeSync`.
 //! Disabled by default.
 //!
+//! ### `MemoryUsage`
+//!
+//! Provides `Javy.memoryUsage` returning the memory used by the QuickJS
+//! runtime and by blobs.
+//! Disabled by default.
+//!
 //! ### `Base64`
 //!
 //! Provides implementations of `btoa` and `atob` for base64 encoding and decoding.
@@ -56,20 +62,116 @@
 //!
 //! ### `Blob`
 //!
-//! Provides implementations of `Blob` and `File` constructors with their methods.
+//! Provides implementations of `Blob` and `File` constructors with their methods,
+//! `URL.createObjectURL` and `URL.revokeObjectURL`, and `Javy.Blob.idOf` to
+//! hand blobs to the host through a [`BlobRegistry`](crate::BlobRegistry).
+//! Always available.
+//!
+//! ### `Javy.onShutdown`
+//!
+//! Provides `Javy.onShutdown` registering the functions run by
+//! [`Runtime::run_shutdown_hooks`](crate::Runtime::run_shutdown_hooks) before
+//! the runtime is torn down.
 //! Always available.
 //!
+//! ### `ReadableStream`, `WritableStream` and `TransformStream`
+//!
+//! Provides the global `ReadableStream`, `WritableStream` and
+//! `TransformStream` classes with their default controllers, readers and
+//! writers, and piping. Byte streams and BYOB readers aren't supported.
+//! Disabled by default.
+//!
+//! ### `CompressionStream` and `DecompressionStream`
+//!
+//! Provides the global `CompressionStream` and `DecompressionStream` classes
+//! for the `gzip`, `deflate` and `deflate-raw` formats. Requires the streams
+//! APIs.
+//! Disabled by default.
+//!
+//! ### `FormData`
+//!
+//! Provides the global `FormData` class. `Blob` values are stored as `File`
+//! objects.
+//! Disabled by default.
+//!
+//! ### `structuredClone`
+//!
+//! Provides the global `structuredClone` function for plain objects, arrays,
+//! primitives, `Date`, `RegExp`, `Map`, `Set`, `ArrayBuffer`, typed arrays
+//! and `Blob`/`File`.
+//! Disabled by default.
+//!
+//! ### `crypto`
+//!
+//! Provides the global `crypto` object with `crypto.subtle.digest` for the
+//! SHA-1, SHA-256, SHA-384 and SHA-512 algorithms.
+//! Disabled by default.
+//!
+//! ### `navigator`
+//!
+//! Provides a minimal global `navigator` object with a configurable
+//! `userAgent` and a `hardwareConcurrency` of 1.
+//! Disabled by default.
+//!
+//! ### `Event`
+//!
+//! Provides the global `Event` and `EventTarget` classes. Events are only
+//! dispatched to their target, there's no propagation.
+//! Disabled by default.
+//!
+//! ### `AbortController`
+//!
+//! Provides the global `AbortController` and `AbortSignal` classes, with
+//! `AbortSignal.abort`, `AbortSignal.any` and, when timers are enabled,
+//! `AbortSignal.timeout`.
+//! Disabled by default.
+//!
+//! ### Deprecation warnings
+//!
+//! Writes a warning to stderr the first time each deprecated API, such as
+//! `Event.prototype.srcElement`, is used.
+//! Disabled by default.
+//!
+//! ### `RegExp` cache
+//!
+//! Reuses the regular expressions constructed from the same string pattern
+//! and flags with `new RegExp(pattern, flags)`, except for the ones with the
+//! `g` or `y` flags.
+//! Disabled by default.
+//!
+//! ### `Transcode`
+//!
+//! Provides `Javy.msgpackToJson` and `Javy.jsonToMsgpack` to convert between
+//! MessagePack and JSON encoded bytes without creating JavaScript values.
+//! Available when both the `messagepack` and `json` crate features are
+//! enabled.
+//!
 //! ###  `JSON`
 //! Provides an efficient implementation of JSON functions based on [`simd-json`](https://crates.io/crates/simd-json/0.13.10)
 //! and [`serde_json`](https://crates.io/crates/serde_json)
 //!
 //! Disabled by default.
+pub(crate) mod abort;
 pub(crate) mod base64;
 pub(crate) mod blob;
+pub(crate) mod compression;
 pub(crate) mod console;
+pub(crate) mod crypto;
+pub(crate) mod deprecation;
+pub(crate) mod error;
+pub(crate) mod events;
+pub(crate) mod form_data;
 #[cfg(feature = "json")]
 pub(crate) mod json;
+pub(crate) mod memory_usage;
+pub(crate) mod navigator;
 pub(crate) mod random;
+pub(crate) mod regexp_cache;
+pub(crate) mod shutdown;
 pub(crate) mod stream_io;
+pub(crate) mod streams;
+pub(crate) mod structured_clone;
 pub(crate) mod text_encoding;
 pub(crate) mod timers;
+#[cfg(all(feature = "messagepack", feature = "json"))]
+pub(crate) mod transcode;
@@ -0,0 +1,62 @@
+use crate::quickjs::{Ctx, Object};
+use anyhow::{Error, Result};
+
+/// Register a minimal global `navigator` object exposing `userAgent` and
+/// `hardwareConcurrency`.
+pub(crate) fn register(cx: Ctx<'_>, user_agent: &str) -> Result<()> {
+    let navigator = Object::new(cx.clone())?;
+    navigator.set("userAgent", user_agent)?;
+    // Javy runs JavaScript on a single thread.
+    navigator.set("hardwareConcurrency", 1)?;
+    cx.globals().set("navigator", navigator)?;
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Config, Runtime};
+    use anyhow::{Error, Result};
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof navigator")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_navigator() -> Result<()> {
+        let mut config = Config::default();
+        config.navigator(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let user_agent: String = cx.eval("navigator.userAgent")?;
+            assert!(user_agent.contains("Javy"));
+            assert_eq!(format!("Javy/{}", env!("CARGO_PKG_VERSION")), user_agent);
+            let concurrency: i32 = cx.eval("navigator.hardwareConcurrency")?;
+            assert_eq!(1, concurrency);
+            let result: String = cx.eval("typeof navigator.language")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_custom_user_agent() -> Result<()> {
+        let mut config = Config::default();
+        config.navigator(true).navigator_user_agent("MyApp/1.0");
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let user_agent: String = cx.eval("navigator.userAgent")?;
+            assert_eq!("MyApp/1.0", user_agent);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
@@ -1,12 +1,21 @@
+use std::cell::RefCell;
+
 use crate::quickjs::{prelude::Func, Ctx, Object};
 use anyhow::{Error, Result};
+use fastrand::Rng;
 
 /// Register a `random` object on the global object that seeds itself at first
-/// execution.
-pub(crate) fn register(cx: Ctx) -> Result<()> {
+/// execution, or that generates the sequence of `seed` if there's one.
+pub(crate) fn register(cx: Ctx, seed: Option<u64>) -> Result<()> {
     let globals = cx.globals();
     let math: Object<'_> = globals.get("Math").expect("Math global to be defined");
-    math.set("random", Func::from(fastrand::f64))?;
+    match seed {
+        Some(seed) => {
+            let rng = RefCell::new(Rng::with_seed(seed));
+            math.set("random", Func::from(move || rng.borrow_mut().f64()))?
+        }
+        None => math.set("random", Func::from(fastrand::f64))?,
+    }
 
     Ok::<_, Error>(())
 }
@@ -15,7 +24,7 @@ pub(crate) fn register(cx: Ctx) -> Result<()> {
 mod tests {
     use crate::{
         quickjs::{context::EvalOptions, Value},
-        Runtime,
+        Config, Runtime,
     };
     use anyhow::{Error, Result};
 
@@ -38,4 +47,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_random_seed() -> Result<()> {
+        let sequence = |seed| -> Result<Vec<f64>> {
+            let mut config = Config::default();
+            config.random_seed(seed);
+            let runtime = Runtime::new(config)?;
+            let sequence = runtime
+                .context()
+                .with(|cx| cx.eval("Array.from({ length: 5 }, () => Math.random())"))?;
+            Ok(sequence)
+        };
+
+        let first = sequence(Some(42))?;
+        assert!(first.iter().all(|n| (0.0..1.0).contains(n)));
+        assert_eq!(first, sequence(Some(42))?);
+        assert_ne!(first, sequence(Some(43))?);
+        assert_ne!(sequence(None)?, sequence(None)?);
+        Ok(())
+    }
 }
@@ -0,0 +1,158 @@
+use std::cell::Cell;
+
+use crate::quickjs::{context::EvalOptions, prelude::Func, Ctx};
+use anyhow::{Error, Result};
+
+thread_local! {
+    /// The number of regular expressions compiled because they weren't in the
+    /// cache.
+    static COMPILED: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Wrap the global `RegExp` constructor so that constructing a regular
+/// expression without the `g` or `y` flags from a string pattern reuses a
+/// previously compiled one with the same pattern and flags.
+///
+/// Requires the `RegExp` and `Proxy` intrinsics to be registered.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    this.globals().set(
+        "__javy_regexp_cache_miss",
+        Func::from(|| COMPILED.with(|compiled| compiled.set(compiled.get() + 1))),
+    )?;
+
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./regexp-cache.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+/// The number of regular expressions compiled on this thread because they
+/// weren't in the cache.
+#[cfg(test)]
+fn compiled() -> usize {
+    COMPILED.with(Cell::get)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::compiled;
+    use crate::{quickjs::Value, val_to_string, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.regexp_cache(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_repeated_construction() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let before = compiled();
+            let result: String = cx.eval(
+                r#"
+                const matches = [];
+                for (let i = 0; i < 100; i++) {
+                    const re = new RegExp("(\\d+)-(\\w+)", "i");
+                    matches.push(re.exec(`${i}-item${i}`)[2]);
+                }
+                matches.slice(0, 3).concat(matches.slice(-1)).join(",")
+                "#,
+            )?;
+            assert_eq!("item0,item1,item2,item99", result);
+            assert_eq!(1, compiled() - before);
+
+            // Calling `RegExp` without `new` shares the cache
+            let result: bool = cx.eval(r#"RegExp("(\\d+)-(\\w+)", "i").test("1-a")"#)?;
+            assert!(result);
+            assert_eq!(1, compiled() - before);
+
+            // Other flags are a different entry
+            let result: bool = cx.eval(r#"new RegExp("(\\d+)-(\\w+)").test("1-a")"#)?;
+            assert!(result);
+            assert_eq!(2, compiled() - before);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_stateful_regexps_are_not_cached() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let before = compiled();
+            let result: String = cx.eval(
+                r#"
+                const results = [];
+                for (let i = 0; i < 3; i++) {
+                    const global = new RegExp("a", "g");
+                    const sticky = new RegExp("b", "y");
+                    results.push(global.exec("aa").index, global.lastIndex);
+                    results.push(sticky.test("bb"), sticky.lastIndex);
+                }
+                results.join(",")
+                "#,
+            )?;
+            assert_eq!("0,1,true,1,0,1,true,1,0,1,true,1", result);
+            assert_eq!(0, compiled() - before);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_regexp_semantics() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let checks = [
+                ("new RegExp('a') instanceof RegExp", "true"),
+                ("/a/.constructor === RegExp", "true"),
+                ("RegExp.prototype.constructor === RegExp", "true"),
+                ("RegExp.name", "RegExp"),
+                ("new RegExp('a', 'i').flags", "i"),
+                ("String(new RegExp('a/b'))", "/a\\/b/"),
+                ("'a-b-c'.split(new RegExp('-')).join()", "a,b,c"),
+                (
+                    "[...'a1b2'.matchAll(new RegExp('\\\\d', 'g'))].join()",
+                    "1,2",
+                ),
+                ("const re = /x/; RegExp(re) === re", "true"),
+                ("new RegExp(/x/g, 'i').flags", "i"),
+                (
+                    "class MyRegExp extends RegExp {}; new MyRegExp('a') instanceof MyRegExp",
+                    "true",
+                ),
+                (
+                    "try { new RegExp('('); 'no error' } catch (e) { e.name }",
+                    "SyntaxError",
+                ),
+            ];
+            for (js, expected) in checks {
+                let result: Value = cx.eval(js)?;
+                assert_eq!(val_to_string(&cx, result)?, expected, "{js}");
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: bool = cx.eval("new RegExp('a') === new RegExp('a')")?;
+            assert!(!result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_requires_proxy() {
+        let mut config = Config::default();
+        config.regexp_cache(true).proxy(false);
+        assert!(Runtime::new(config).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+(function () {
+  const __javy_regexp_cache_miss = globalThis.__javy_regexp_cache_miss;
+  const NativeRegExp = RegExp;
+  // The oldest entry is evicted once the cache is full
+  const MAX_ENTRIES = 256;
+  const cache = new Map();
+
+  // Returns the cached regular expression for a string pattern and flags, or
+  // undefined if the arguments can't be cached. Regular expressions with the
+  // `g` or `y` flags read and update `lastIndex` when matching, so they're
+  // never shared.
+  function cached(pattern, flags) {
+    if (typeof pattern !== "string") {
+      return undefined;
+    }
+    if (flags === undefined) {
+      flags = "";
+    } else if (typeof flags !== "string") {
+      return undefined;
+    }
+    if (flags.includes("g") || flags.includes("y")) {
+      return undefined;
+    }
+
+    const key = `${flags}/${pattern}`;
+    let regexp = cache.get(key);
+    if (regexp === undefined) {
+      // Invalid patterns throw here and are never cached
+      regexp = new NativeRegExp(pattern, flags);
+      __javy_regexp_cache_miss();
+      if (cache.size >= MAX_ENTRIES) {
+        cache.delete(cache.keys().next().value);
+      }
+      cache.set(key, regexp);
+    }
+    regexp.lastIndex = 0;
+    return regexp;
+  }
+
+  // The IsRegExp abstract operation
+  function isRegExp(value) {
+    if (value === null || (typeof value !== "object" && typeof value !== "function")) {
+      return false;
+    }
+    const matcher = value[Symbol.match];
+    if (matcher !== undefined) {
+      return Boolean(matcher);
+    }
+    return value instanceof NativeRegExp;
+  }
+
+  const CachingRegExp = new Proxy(NativeRegExp, {
+    construct(target, args, newTarget) {
+      // Subclasses always get a new instance of their own class
+      if (newTarget === CachingRegExp) {
+        const regexp = cached(args[0], args[1]);
+        if (regexp !== undefined) {
+          return regexp;
+        }
+        return Reflect.construct(target, args);
+      }
+      return Reflect.construct(target, args, newTarget);
+    },
+    apply(target, thisArg, args) {
+      // `RegExp(regexp)` returns its argument, the native check compares the
+      // argument's constructor with the native constructor instead of this
+      // proxy
+      const [pattern, flags] = args;
+      if (flags === undefined && isRegExp(pattern) && pattern.constructor === CachingRegExp) {
+        return pattern;
+      }
+      const regexp = cached(pattern, flags);
+      if (regexp !== undefined) {
+        return regexp;
+      }
+      return Reflect.apply(target, thisArg, args);
+    },
+  });
+
+  Object.defineProperty(NativeRegExp.prototype, "constructor", {
+    value: CachingRegExp,
+    writable: true,
+    enumerable: false,
+    configurable: true,
+  });
+  globalThis.RegExp = CachingRegExp;
+
+  Reflect.deleteProperty(globalThis, "__javy_regexp_cache_miss");
+})();
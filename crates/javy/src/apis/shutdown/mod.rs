@@ -0,0 +1,112 @@
+use crate::{
+    from_js_error,
+    quickjs::{self, object::Property, Array, Ctx, Exception, Function, Object, Value},
+};
+use anyhow::{Error, Result};
+
+/// The hidden global holding the hooks registered with `Javy.onShutdown`.
+const HOOKS: &str = "__javy_shutdown_hooks";
+
+/// Register a `Javy.onShutdown` function registering the hooks run, in
+/// registration order, by [`run_hooks`] before the runtime is torn down.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let globals = this.globals();
+    if globals.get::<_, Object>("Javy").is_err() {
+        globals.set("Javy", Object::new(this.clone())?)?
+    }
+    globals.prop(HOOKS, Property::from(Array::new(this.clone())?))?;
+
+    let javy: Object = globals.get("Javy")?;
+    javy.set(
+        "onShutdown",
+        Function::new(this.clone(), on_shutdown)?.with_name("onShutdown")?,
+    )?;
+    Ok::<_, Error>(())
+}
+
+/// Add the hook to the hooks run on shutdown.
+fn on_shutdown<'js>(cx: Ctx<'js>, hook: Value<'js>) -> quickjs::Result<()> {
+    if !hook.is_function() {
+        return Err(Exception::throw_type(
+            &cx,
+            "The shutdown hook must be a function",
+        ));
+    }
+    let hooks: Array = cx.globals().get(HOOKS)?;
+    hooks.set(hooks.len(), hook)
+}
+
+/// Run the hooks registered with `Javy.onShutdown` in registration order,
+/// removing them so they run only once. Stops at the first hook throwing.
+pub(crate) fn run_hooks(cx: Ctx<'_>) -> Result<()> {
+    let Ok(hooks) = cx.globals().get::<_, Array>(HOOKS) else {
+        return Ok(());
+    };
+    let registered = hooks
+        .iter::<Function>()
+        .collect::<quickjs::Result<Vec<_>>>()
+        .map_err(|e| from_js_error(cx.clone(), e))?;
+    hooks
+        .as_object()
+        .set("length", 0)
+        .map_err(|e| from_js_error(cx.clone(), e))?;
+    for hook in registered {
+        hook.call::<_, ()>(())
+            .map_err(|e| from_js_error(cx.clone(), e))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{quickjs::Value, Runtime};
+    use anyhow::{Error, Result};
+
+    #[test]
+    fn test_hooks_run_once_in_registration_order() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<Value, _>(
+                "globalThis.calls = [];
+                Javy.onShutdown(() => calls.push('first'));
+                Javy.onShutdown(() => calls.push('second'));",
+            )?;
+            Ok::<_, Error>(())
+        })?;
+
+        runtime.run_shutdown_hooks()?;
+        runtime.run_shutdown_hooks()?;
+
+        runtime.context().with(|cx| {
+            let calls: String = cx.eval("calls.join()")?;
+            assert_eq!("first,second", calls);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_hooks_must_be_functions() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let message: String =
+                cx.eval("try { Javy.onShutdown(42); '' } catch (e) { `${e.name}: ${e.message}` }")?;
+            assert_eq!("TypeError: The shutdown hook must be a function", message);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_throwing_hooks_return_errors() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            cx.eval::<Value, _>("Javy.onShutdown(() => { throw new Error('flush failed') })")?;
+            Ok::<_, Error>(())
+        })?;
+
+        let err = runtime.run_shutdown_hooks().unwrap_err();
+        assert!(err.to_string().contains("flush failed"), "{err}");
+        Ok(())
+    }
+}
@@ -1,6 +1,8 @@
 (function () {
   const __javy_io_readSync = globalThis.__javy_io_readSync;
   const __javy_io_writeSync = globalThis.__javy_io_writeSync;
+  const __javy_io_writeBlobSync = globalThis.__javy_io_writeBlobSync;
+  const Blob = globalThis.Blob;
   globalThis.Javy.IO = {
     readSync(fd, data) {
       if (!(data instanceof Uint8Array)) {
@@ -14,8 +16,13 @@
       );
     },
     writeSync(fd, data) {
+      // Blobs and files are written from the blob storage, without copying
+      // their bytes into an ArrayBuffer first.
+      if (Blob !== undefined && data instanceof Blob) {
+        return __javy_io_writeBlobSync(fd, data._blobId);
+      }
       if (!(data instanceof Uint8Array)) {
-        throw TypeError("Data needs to be an Uint8Array");
+        throw TypeError("Data needs to be an Uint8Array or a Blob");
       }
       return __javy_io_writeSync(
         fd,
@@ -28,4 +35,5 @@
 
   Reflect.deleteProperty(globalThis, "__javy_io_readSync");
   Reflect.deleteProperty(globalThis, "__javy_io_writeSync");
+  Reflect.deleteProperty(globalThis, "__javy_io_writeBlobSync");
 })();
@@ -2,6 +2,7 @@ use anyhow::{anyhow, bail, Error, Result};
 use std::io::{Read, Stdin, Write};
 
 use crate::{
+    apis::blob,
     hold, hold_and_release,
     quickjs::{qjs::JS_GetArrayBuffer, Ctx, Function, Object, Value},
     to_js_error, Args,
@@ -23,6 +24,14 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
         }),
     )?;
 
+    globals.set(
+        "__javy_io_writeBlobSync",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            write_blob(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
     globals.set(
         "__javy_io_readSync",
         Function::new(this.clone(), |cx, args| {
@@ -61,24 +70,38 @@ fn extract_args<'a, 'js: 'a>(
     Ok((fd, data, offset, length))
 }
 
-fn write(args: Args<'_>) -> Result<Value<'_>> {
-    enum Fd {
-        Stdout,
-        Stderr,
+/// A file descriptor that can be written to.
+enum Fd {
+    Stdout,
+    Stderr,
+}
+
+impl Fd {
+    fn from_value(fd: &Value<'_>) -> Result<Self> {
+        match fd
+            .as_int()
+            .ok_or_else(|| anyhow!("File descriptor must be a number"))?
+        {
+            1 => Ok(Fd::Stdout),
+            2 => Ok(Fd::Stderr),
+            x => anyhow::bail!(
+                "Unsupported file descriptor: {x}. Only stdout(1) and stderr(2) are supported"
+            ),
+        }
     }
 
+    fn writer(&self) -> Box<dyn Write> {
+        match self {
+            Fd::Stdout => Box::new(std::io::stdout()),
+            Fd::Stderr => Box::new(std::io::stderr()),
+        }
+    }
+}
+
+fn write(args: Args<'_>) -> Result<Value<'_>> {
     let (cx, args) = args.release();
     let (fd, data, offset, length) = extract_args(&args, "Javy.IO.writeSync")?;
-    let fd = match fd
-        .as_int()
-        .ok_or_else(|| anyhow!("File descriptor must be a number"))?
-    {
-        1 => Fd::Stdout,
-        2 => Fd::Stderr,
-        x => anyhow::bail!(
-            "Unsupported file descriptor: {x}. Only stdout(1) and stderr(2) are supported"
-        ),
-    };
+    let fd = Fd::from_value(fd)?;
     let data = data
         .as_object()
         .ok_or_else(|| anyhow!("Data must be an Object"))?
@@ -94,20 +117,34 @@ fn write(args: Args<'_>) -> Result<Value<'_>> {
         .as_number()
         .ok_or_else(|| anyhow!("offset must be a number"))? as usize;
     let data = &data[offset..(offset + length)];
-    let n = match fd {
-        Fd::Stdout => {
-            let mut fd = std::io::stdout();
-            let n = fd.write(data)?;
-            fd.flush()?;
-            n
-        }
-        Fd::Stderr => {
-            let mut fd = std::io::stderr();
-            let n = fd.write(data)?;
-            fd.flush()?;
-            n
-        }
+    let mut writer = fd.writer();
+    let n = writer.write(data)?;
+    writer.flush()?;
+
+    Ok(Value::new_number(cx, n as f64))
+}
+
+/// Writes all the bytes of a blob or file, given by ID, directly from the
+/// blob storage.
+fn write_blob(args: Args<'_>) -> Result<Value<'_>> {
+    let (cx, args) = args.release();
+    let [fd, blob_id, ..] = &args[..] else {
+        bail!(
+            "Javy.IO.writeSync expects 2 parameters: the file descriptor and the Blob ID. Got: {} parameters.",
+            args.len()
+        );
     };
+    let fd = Fd::from_value(fd)?;
+    let blob_id = blob_id
+        .as_number()
+        .ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+
+    let n = blob::with_data(blob_id, |data| {
+        let mut writer = fd.writer();
+        writer.write_all(data)?;
+        writer.flush()?;
+        Ok::<_, Error>(data.len())
+    })??;
 
     Ok(Value::new_number(cx, n as f64))
 }
@@ -0,0 +1,384 @@
+use crate::quickjs::{context::EvalOptions, Ctx};
+use anyhow::{Error, Result};
+
+/// Register the global `ReadableStream`, `WritableStream` and
+/// `TransformStream` classes, along with their default controllers, readers
+/// and writers.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    // The writable streams add piping to the readable streams, and the
+    // transform streams are built on both.
+    for source in [
+        include_str!("./readable-stream.js"),
+        include_str!("./writable-stream.js"),
+        include_str!("./transform-stream.js"),
+    ] {
+        let mut opts = EvalOptions::default();
+        opts.strict = false;
+        this.eval_with_options::<(), _>(source, opts)?;
+    }
+
+    Ok::<_, Error>(())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{from_js_error, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.streams(true);
+        Runtime::new(config)
+    }
+
+    /// Evaluates `src`, runs the pending jobs and returns `globalThis.result`.
+    fn eval_result(runtime: &Runtime, src: &str) -> Result<String> {
+        runtime
+            .context()
+            .with(|cx| cx.eval::<(), _>(src).map_err(|e| from_js_error(cx, e)))?;
+        runtime.resolve_pending_jobs()?;
+        runtime.context().with(|cx| {
+            cx.eval("String(globalThis.result)")
+                .map_err(|e| from_js_error(cx, e))
+        })
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof ReadableStream")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_all_chunks() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const stream = new ReadableStream({
+                start(controller) {
+                    controller.enqueue("a");
+                    controller.enqueue("b");
+                    controller.close();
+                },
+            });
+            const reader = stream.getReader();
+            (async () => {
+                const chunks = [];
+                while (true) {
+                    const { value, done } = await reader.read();
+                    if (done) break;
+                    chunks.push(value);
+                }
+                await reader.closed;
+                globalThis.result = `${chunks.join(",")} ${stream.locked}`;
+            })();
+            "#,
+        )?;
+        assert_eq!("a,b true", result);
+
+        // Chunks are pulled as they're read
+        let result = eval_result(
+            &runtime,
+            r#"
+            let pulls = 0;
+            const pulled = new ReadableStream(
+                {
+                    pull(controller) {
+                        pulls++;
+                        if (pulls > 3) {
+                            controller.close();
+                        } else {
+                            controller.enqueue(pulls);
+                        }
+                    },
+                },
+                { highWaterMark: 0 },
+            );
+            (async () => {
+                const chunks = [];
+                for await (const chunk of pulled) {
+                    chunks.push(chunk);
+                }
+                globalThis.result = `${chunks.join(",")} ${pulls} ${pulled.locked}`;
+            })();
+            "#,
+        )?;
+        assert_eq!("1,2,3 4 false", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cancel() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const events = [];
+            const stream = new ReadableStream({
+                start(controller) {
+                    controller.enqueue(1);
+                    controller.enqueue(2);
+                },
+                cancel(reason) {
+                    events.push(`cancel:${reason}`);
+                },
+            });
+            const reader = stream.getReader();
+            (async () => {
+                events.push(`read:${(await reader.read()).value}`);
+                events.push(`cancelled:${await reader.cancel("stop")}`);
+                const { value, done } = await reader.read();
+                events.push(`read:${value}:${done}`);
+                globalThis.result = events.join(",");
+            })();
+            "#,
+        )?;
+        assert_eq!(
+            "read:1,cancel:stop,cancelled:undefined,read:undefined:true",
+            result
+        );
+
+        // Breaking out of an iteration cancels the stream
+        let result = eval_result(
+            &runtime,
+            r#"
+            let cancelled = false;
+            let next = 0;
+            const infinite = new ReadableStream({
+                pull(controller) {
+                    controller.enqueue(next++);
+                },
+                cancel() {
+                    cancelled = true;
+                },
+            });
+            (async () => {
+                for await (const chunk of infinite) {
+                    if (chunk === 2) break;
+                }
+                globalThis.result = `${cancelled} ${infinite.locked}`;
+            })();
+            "#,
+        )?;
+        assert_eq!("true false", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const errors = [];
+            let controller;
+            const stream = new ReadableStream({
+                start(c) {
+                    controller = c;
+                },
+            });
+            const reader = stream.getReader();
+            try { stream.getReader(); } catch (e) { errors.push(e.name); }
+            (async () => {
+                try { await stream.cancel(); } catch (e) { errors.push(e.name); }
+                const pending = reader.read();
+                controller.error(new Error("boom"));
+                try { await pending; } catch (e) { errors.push(e.message); }
+                try { await reader.closed; } catch (e) { errors.push(e.message); }
+                try { controller.enqueue(1); } catch (e) { errors.push(e.name); }
+                errors.push(String(controller.desiredSize));
+
+                const closed = new ReadableStream({ start: (c) => c.close() });
+                try { new ReadableStream({ start(c) { c.close(); c.close(); } }); } catch (e) {
+                    errors.push(e.name);
+                }
+                errors.push(String((await closed.getReader().read()).done));
+                globalThis.result = errors.join(",");
+            })();
+            "#,
+        )?;
+        assert_eq!(
+            "TypeError,TypeError,boom,boom,TypeError,null,TypeError,true",
+            result
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_writable_stream() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const events = [];
+            const stream = new WritableStream(
+                {
+                    write(chunk) {
+                        events.push(`write:${chunk}`);
+                        return Promise.resolve();
+                    },
+                    close() {
+                        events.push("close");
+                    },
+                },
+                { highWaterMark: 2 },
+            );
+            const writer = stream.getWriter();
+            events.push(`size:${writer.desiredSize}`);
+            writer.write("a");
+            writer.write("b");
+            events.push(`size:${writer.desiredSize}`);
+            (async () => {
+                await writer.close();
+                await writer.closed;
+                events.push(`locked:${stream.locked}`);
+                try { await writer.write("c"); } catch (e) { events.push(e.name); }
+                globalThis.result = events.join(",");
+            })();
+            "#,
+        )?;
+        assert_eq!(
+            "size:2,size:0,write:a,write:b,close,locked:true,TypeError",
+            result
+        );
+
+        // Errors of the sink reject the pending writes
+        let result = eval_result(
+            &runtime,
+            r#"
+            const failing = new WritableStream({
+                write() {
+                    throw new Error("boom");
+                },
+            });
+            const failingWriter = failing.getWriter();
+            (async () => {
+                const errors = [];
+                const first = failingWriter.write(1);
+                const second = failingWriter.write(2);
+                try { await first; } catch (e) { errors.push(e.message); }
+                try { await second; } catch (e) { errors.push(e.message); }
+                try { await failingWriter.closed; } catch (e) { errors.push(e.message); }
+                globalThis.result = errors.join(",");
+            })();
+            "#,
+        )?;
+        assert_eq!("boom,boom,boom", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_transform_stream() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const upperCase = new TransformStream({
+                transform(chunk, controller) {
+                    controller.enqueue(chunk.toUpperCase());
+                },
+                flush(controller) {
+                    controller.enqueue("!");
+                },
+            });
+            const source = new ReadableStream({
+                start(controller) {
+                    controller.enqueue("a");
+                    controller.enqueue("b");
+                    controller.close();
+                },
+            });
+            (async () => {
+                const chunks = [];
+                for await (const chunk of source.pipeThrough(upperCase)) {
+                    chunks.push(chunk);
+                }
+                globalThis.result = `${chunks.join(",")} ${source.locked}`;
+            })();
+            "#,
+        )?;
+        assert_eq!("A,B,! false", result);
+
+        // Chunks are passed through without a `transform` method, and written
+        // chunks wait for the readable side to be read
+        let result = eval_result(
+            &runtime,
+            r#"
+            const identity = new TransformStream();
+            const writer = identity.writable.getWriter();
+            const reader = identity.readable.getReader();
+            (async () => {
+                const events = [];
+                const written = writer.write(1).then(() => events.push("written"));
+                events.push(`read:${(await reader.read()).value}`);
+                await written;
+                writer.close();
+                events.push(`done:${(await reader.read()).done}`);
+                globalThis.result = events.join(",");
+            })();
+            "#,
+        )?;
+        assert_eq!("read:1,written,done:true", result);
+        Ok(())
+    }
+
+    #[test]
+    fn test_pipe_to() -> Result<()> {
+        let runtime = runtime()?;
+        let result = eval_result(
+            &runtime,
+            r#"
+            const written = [];
+            let closed = false;
+            const destination = new WritableStream({
+                write(chunk) {
+                    written.push(chunk);
+                },
+                close() {
+                    closed = true;
+                },
+            });
+            const source = new ReadableStream({
+                start(controller) {
+                    controller.enqueue(1);
+                    controller.enqueue(2);
+                    controller.close();
+                },
+            });
+            (async () => {
+                await source.pipeTo(destination);
+                globalThis.result = `${written.join(",")} ${closed} ${destination.locked}`;
+            })();
+            "#,
+        )?;
+        assert_eq!("1,2 true false", result);
+
+        // Errors of the source abort the destination
+        let result = eval_result(
+            &runtime,
+            r#"
+            let aborted;
+            const failing = new ReadableStream({
+                pull() {
+                    throw new Error("boom");
+                },
+            });
+            (async () => {
+                try {
+                    await failing.pipeTo(new WritableStream({ abort(reason) { aborted = reason; } }));
+                } catch (e) {
+                    globalThis.result = `${e.message} ${aborted.message}`;
+                }
+            })();
+            "#,
+        )?;
+        assert_eq!("boom boom", result);
+        Ok(())
+    }
+}
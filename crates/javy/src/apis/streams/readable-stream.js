@@ -0,0 +1,434 @@
+(function () {
+  // Guards the constructors of the controller and the reader, which are
+  // created by the streams.
+  const constructorKey = Symbol("ReadableStream");
+
+  // The internal state of each stream, controller and reader.
+  const streams = new WeakMap();
+  const controllers = new WeakMap();
+  const readers = new WeakMap();
+
+  function internal(map, object, name) {
+    if (!map.has(object)) {
+      throw new TypeError(`Illegal invocation: not a ${name}`);
+    }
+    return map.get(object);
+  }
+
+  function deferred() {
+    let resolve;
+    let reject;
+    const promise = new Promise((res, rej) => {
+      resolve = res;
+      reject = rej;
+    });
+    return { promise, resolve, reject };
+  }
+
+  // Rejects a promise nobody might be waiting on, without reporting it as
+  // unhandled.
+  function rejectHandled(pending, reason) {
+    pending.promise.catch(() => {});
+    pending.reject(reason);
+  }
+
+  // Calls a method of the underlying source, turning its result or the error
+  // it throws into a promise.
+  function promiseCall(source, name, args) {
+    try {
+      const method = source[name];
+      if (method === undefined) {
+        return Promise.resolve(undefined);
+      }
+      return Promise.resolve(method.apply(source, args));
+    } catch (error) {
+      return Promise.reject(error);
+    }
+  }
+
+  function sizeAlgorithm(strategy) {
+    const size = strategy.size;
+    if (size === undefined) {
+      return () => 1;
+    }
+    if (typeof size !== "function") {
+      throw new TypeError("The size of the queuing strategy must be a function.");
+    }
+    return (chunk) => size.call(undefined, chunk);
+  }
+
+  function highWaterMarkOf(strategy) {
+    const highWaterMark =
+      strategy.highWaterMark === undefined ? 1 : Number(strategy.highWaterMark);
+    if (Number.isNaN(highWaterMark) || highWaterMark < 0) {
+      throw new RangeError("The high water mark must be a non-negative number.");
+    }
+    return highWaterMark;
+  }
+
+  function desiredSize(stream) {
+    if (stream.state === "errored") {
+      return null;
+    }
+    if (stream.state === "closed") {
+      return 0;
+    }
+    return stream.highWaterMark - stream.queueTotalSize;
+  }
+
+  function shouldCallPull(stream) {
+    if (stream.state !== "readable" || stream.closeRequested || !stream.started) {
+      return false;
+    }
+    if (stream.reader !== null && stream.reader.readRequests.length > 0) {
+      return true;
+    }
+    return desiredSize(stream) > 0;
+  }
+
+  function callPullIfNeeded(stream) {
+    if (!shouldCallPull(stream)) {
+      return;
+    }
+    if (stream.pulling) {
+      stream.pullAgain = true;
+      return;
+    }
+    stream.pulling = true;
+    promiseCall(stream.source, "pull", [stream.controller]).then(
+      () => {
+        stream.pulling = false;
+        if (stream.pullAgain) {
+          stream.pullAgain = false;
+          callPullIfNeeded(stream);
+        }
+      },
+      (error) => errorStream(stream, error)
+    );
+  }
+
+  function resetQueue(stream) {
+    stream.queue = [];
+    stream.queueTotalSize = 0;
+  }
+
+  function closeStream(stream) {
+    stream.state = "closed";
+    const reader = stream.reader;
+    if (reader === null) {
+      return;
+    }
+    for (const request of reader.readRequests.splice(0)) {
+      request.resolve({ value: undefined, done: true });
+    }
+    reader.closed.resolve(undefined);
+  }
+
+  function errorStream(stream, error) {
+    if (stream.state !== "readable") {
+      return;
+    }
+    resetQueue(stream);
+    stream.state = "errored";
+    stream.storedError = error;
+    const reader = stream.reader;
+    if (reader === null) {
+      return;
+    }
+    for (const request of reader.readRequests.splice(0)) {
+      request.reject(error);
+    }
+    rejectHandled(reader.closed, error);
+  }
+
+  function cancelStream(stream, reason) {
+    stream.disturbed = true;
+    if (stream.state === "closed") {
+      return Promise.resolve(undefined);
+    }
+    if (stream.state === "errored") {
+      return Promise.reject(stream.storedError);
+    }
+    closeStream(stream);
+    resetQueue(stream);
+    return promiseCall(stream.source, "cancel", [reason]).then(() => undefined);
+  }
+
+  class ReadableStreamDefaultController {
+    constructor(key) {
+      if (key !== constructorKey) {
+        throw new TypeError("Illegal constructor");
+      }
+    }
+
+    get desiredSize() {
+      return desiredSize(internal(controllers, this, "ReadableStreamDefaultController"));
+    }
+
+    enqueue(chunk) {
+      const stream = internal(controllers, this, "ReadableStreamDefaultController");
+      if (stream.closeRequested || stream.state !== "readable") {
+        throw new TypeError("Cannot enqueue a chunk into a closed stream.");
+      }
+
+      const reader = stream.reader;
+      if (reader !== null && reader.readRequests.length > 0) {
+        reader.readRequests.shift().resolve({ value: chunk, done: false });
+      } else {
+        let size;
+        try {
+          size = stream.size(chunk);
+          if (typeof size !== "number" || Number.isNaN(size) || size < 0 || size === Infinity) {
+            throw new RangeError("The size of a chunk must be a non-negative, finite number.");
+          }
+        } catch (error) {
+          errorStream(stream, error);
+          throw error;
+        }
+        stream.queue.push({ chunk, size });
+        stream.queueTotalSize += size;
+      }
+      callPullIfNeeded(stream);
+    }
+
+    close() {
+      const stream = internal(controllers, this, "ReadableStreamDefaultController");
+      if (stream.closeRequested || stream.state !== "readable") {
+        throw new TypeError("Cannot close a stream that is already closed.");
+      }
+      stream.closeRequested = true;
+      if (stream.queue.length === 0) {
+        closeStream(stream);
+      }
+    }
+
+    error(error) {
+      errorStream(internal(controllers, this, "ReadableStreamDefaultController"), error);
+    }
+  }
+
+  class ReadableStreamDefaultReader {
+    constructor(stream) {
+      const state = internal(streams, stream, "ReadableStream");
+      if (state.reader !== null) {
+        throw new TypeError("The stream is already locked to a reader.");
+      }
+      const reader = { stream: state, readRequests: [], closed: deferred() };
+      if (state.state === "closed") {
+        reader.closed.resolve(undefined);
+      } else if (state.state === "errored") {
+        rejectHandled(reader.closed, state.storedError);
+      }
+      state.reader = reader;
+      readers.set(this, reader);
+    }
+
+    get closed() {
+      return internal(readers, this, "ReadableStreamDefaultReader").closed.promise;
+    }
+
+    read() {
+      let reader;
+      try {
+        reader = internal(readers, this, "ReadableStreamDefaultReader");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      const stream = reader.stream;
+      if (stream === null) {
+        return Promise.reject(new TypeError("The reader has been released."));
+      }
+
+      stream.disturbed = true;
+      if (stream.state === "closed") {
+        return Promise.resolve({ value: undefined, done: true });
+      }
+      if (stream.state === "errored") {
+        return Promise.reject(stream.storedError);
+      }
+      if (stream.queue.length > 0) {
+        const { chunk, size } = stream.queue.shift();
+        stream.queueTotalSize = Math.max(0, stream.queueTotalSize - size);
+        if (stream.closeRequested && stream.queue.length === 0) {
+          closeStream(stream);
+        } else {
+          callPullIfNeeded(stream);
+        }
+        return Promise.resolve({ value: chunk, done: false });
+      }
+
+      const request = deferred();
+      reader.readRequests.push(request);
+      callPullIfNeeded(stream);
+      return request.promise;
+    }
+
+    cancel(reason) {
+      let reader;
+      try {
+        reader = internal(readers, this, "ReadableStreamDefaultReader");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (reader.stream === null) {
+        return Promise.reject(new TypeError("The reader has been released."));
+      }
+      return cancelStream(reader.stream, reason);
+    }
+
+    releaseLock() {
+      const reader = internal(readers, this, "ReadableStreamDefaultReader");
+      const stream = reader.stream;
+      if (stream === null) {
+        return;
+      }
+      const error = new TypeError("The reader has been released.");
+      for (const request of reader.readRequests.splice(0)) {
+        request.reject(error);
+      }
+      if (stream.state === "readable") {
+        rejectHandled(reader.closed, error);
+      } else {
+        reader.closed = deferred();
+        rejectHandled(reader.closed, error);
+      }
+      stream.reader = null;
+      reader.stream = null;
+    }
+  }
+
+  class ReadableStream {
+    constructor(underlyingSource = {}, strategy = {}) {
+      if (underlyingSource === null || typeof underlyingSource !== "object") {
+        throw new TypeError("The underlying source must be an object.");
+      }
+      if (underlyingSource.type !== undefined) {
+        throw new RangeError(`The stream type ${underlyingSource.type} is not supported.`);
+      }
+      const strategyObject = strategy === null || strategy === undefined ? {} : strategy;
+      const controller = new ReadableStreamDefaultController(constructorKey);
+      const stream = {
+        state: "readable",
+        reader: null,
+        disturbed: false,
+        storedError: undefined,
+        source: underlyingSource,
+        controller,
+        queue: [],
+        queueTotalSize: 0,
+        size: sizeAlgorithm(strategyObject),
+        highWaterMark: highWaterMarkOf(strategyObject),
+        started: false,
+        closeRequested: false,
+        pulling: false,
+        pullAgain: false,
+      };
+      streams.set(this, stream);
+      controllers.set(controller, stream);
+
+      let start;
+      try {
+        start = underlyingSource.start === undefined
+          ? undefined
+          : underlyingSource.start.call(underlyingSource, controller);
+      } catch (error) {
+        errorStream(stream, error);
+        throw error;
+      }
+      Promise.resolve(start).then(
+        () => {
+          stream.started = true;
+          callPullIfNeeded(stream);
+        },
+        (error) => errorStream(stream, error)
+      );
+    }
+
+    get locked() {
+      return internal(streams, this, "ReadableStream").reader !== null;
+    }
+
+    cancel(reason) {
+      let stream;
+      try {
+        stream = internal(streams, this, "ReadableStream");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (stream.reader !== null) {
+        return Promise.reject(new TypeError("Cannot cancel a locked stream."));
+      }
+      return cancelStream(stream, reason);
+    }
+
+    getReader(options) {
+      internal(streams, this, "ReadableStream");
+      if (options !== undefined && options !== null && options.mode !== undefined) {
+        throw new RangeError(`The reader mode ${options.mode} is not supported.`);
+      }
+      return new ReadableStreamDefaultReader(this);
+    }
+
+    // Reads the chunks of the stream, cancelling it if the iteration stops
+    // early unless `preventCancel` is set.
+    values(options) {
+      const preventCancel = Boolean(options && options.preventCancel);
+      const reader = this.getReader();
+      let finished = false;
+      const finish = () => {
+        finished = true;
+        reader.releaseLock();
+      };
+      return {
+        next() {
+          if (finished) {
+            return Promise.resolve({ value: undefined, done: true });
+          }
+          return reader.read().then(
+            (result) => {
+              if (result.done) {
+                finish();
+              }
+              return result;
+            },
+            (error) => {
+              finish();
+              throw error;
+            }
+          );
+        },
+        return(value) {
+          if (finished) {
+            return Promise.resolve({ value, done: true });
+          }
+          const cancelled = preventCancel ? Promise.resolve() : reader.cancel(value);
+          finish();
+          return cancelled.then(() => ({ value, done: true }));
+        },
+        [Symbol.asyncIterator]() {
+          return this;
+        },
+      };
+    }
+  }
+
+  Object.defineProperty(ReadableStream.prototype, Symbol.asyncIterator, {
+    value: ReadableStream.prototype.values,
+    writable: true,
+    configurable: true,
+  });
+  for (const constructor of [
+    ReadableStream,
+    ReadableStreamDefaultController,
+    ReadableStreamDefaultReader,
+  ]) {
+    Object.defineProperty(constructor.prototype, Symbol.toStringTag, {
+      value: constructor.name,
+      configurable: true,
+    });
+  }
+
+  globalThis.ReadableStream = ReadableStream;
+  globalThis.ReadableStreamDefaultController = ReadableStreamDefaultController;
+  globalThis.ReadableStreamDefaultReader = ReadableStreamDefaultReader;
+})();
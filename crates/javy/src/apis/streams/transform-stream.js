@@ -0,0 +1,254 @@
+(function () {
+  const ReadableStream = globalThis.ReadableStream;
+  const WritableStream = globalThis.WritableStream;
+
+  // Guards the constructor of the controller, which is created by the
+  // streams.
+  const constructorKey = Symbol("TransformStream");
+
+  // The internal state of each stream and controller.
+  const streams = new WeakMap();
+  const controllers = new WeakMap();
+
+  function internal(map, object, name) {
+    if (!map.has(object)) {
+      throw new TypeError(`Illegal invocation: not a ${name}`);
+    }
+    return map.get(object);
+  }
+
+  function deferred() {
+    let resolve;
+    let reject;
+    const promise = new Promise((res, rej) => {
+      resolve = res;
+      reject = rej;
+    });
+    return { promise, resolve, reject };
+  }
+
+  // Calls a method of the transformer, turning its result or the error it
+  // throws into a promise.
+  function promiseCall(transformer, name, args) {
+    try {
+      const method = transformer[name];
+      if (method === undefined) {
+        return Promise.resolve(undefined);
+      }
+      return Promise.resolve(method.apply(transformer, args));
+    } catch (error) {
+      return Promise.reject(error);
+    }
+  }
+
+  // Writes to the writable side wait while the readable side has as many
+  // chunks queued as it wants, until they're read.
+  function setBackpressure(stream, backpressure) {
+    if (stream.backpressure === backpressure) {
+      return;
+    }
+    if (stream.backpressureChange !== null) {
+      stream.backpressureChange.resolve(undefined);
+    }
+    stream.backpressure = backpressure;
+    stream.backpressureChange = deferred();
+  }
+
+  // Errors both sides of the stream.
+  function errorStream(stream, error) {
+    if (stream.readableController !== null) {
+      stream.readableController.error(error);
+    }
+    errorWritable(stream, error);
+  }
+
+  function errorWritable(stream, error) {
+    if (!stream.errored) {
+      stream.errored = true;
+      stream.storedError = error;
+    }
+    if (stream.writableController !== null) {
+      stream.writableController.error(error);
+    }
+    if (stream.backpressure) {
+      setBackpressure(stream, false);
+    }
+  }
+
+  // Transformers without a `transform` method pass the chunks through.
+  function transformChunk(stream, chunk) {
+    const transformed =
+      stream.transformer.transform === undefined
+        ? promiseCall(stream.controller, "enqueue", [chunk])
+        : promiseCall(stream.transformer, "transform", [chunk, stream.controller]);
+    return transformed.catch((error) => {
+      errorStream(stream, error);
+      throw error;
+    });
+  }
+
+  class TransformStreamDefaultController {
+    constructor(key) {
+      if (key !== constructorKey) {
+        throw new TypeError("Illegal constructor");
+      }
+    }
+
+    get desiredSize() {
+      const stream = internal(controllers, this, "TransformStreamDefaultController");
+      return stream.readableController.desiredSize;
+    }
+
+    enqueue(chunk) {
+      const stream = internal(controllers, this, "TransformStreamDefaultController");
+      try {
+        stream.readableController.enqueue(chunk);
+      } catch (error) {
+        errorWritable(stream, error);
+        throw error;
+      }
+      if (stream.readableController.desiredSize <= 0) {
+        setBackpressure(stream, true);
+      }
+    }
+
+    error(error) {
+      errorStream(internal(controllers, this, "TransformStreamDefaultController"), error);
+    }
+
+    terminate() {
+      const stream = internal(controllers, this, "TransformStreamDefaultController");
+      try {
+        stream.readableController.close();
+      } catch (_) {
+        // The readable side is already closed.
+      }
+      errorWritable(stream, new TypeError("The transform stream has been terminated."));
+    }
+  }
+
+  class TransformStream {
+    constructor(transformer = {}, writableStrategy = {}, readableStrategy = {}) {
+      if (transformer === null || typeof transformer !== "object") {
+        throw new TypeError("The transformer must be an object.");
+      }
+      if (transformer.readableType !== undefined) {
+        throw new RangeError("The readable type of the transformer is not supported.");
+      }
+      if (transformer.writableType !== undefined) {
+        throw new RangeError("The writable type of the transformer is not supported.");
+      }
+      const controller = new TransformStreamDefaultController(constructorKey);
+      const stream = {
+        transformer,
+        controller,
+        readableController: null,
+        writableController: null,
+        readable: null,
+        writable: null,
+        backpressure: undefined,
+        backpressureChange: null,
+        errored: false,
+        storedError: undefined,
+      };
+      const started = deferred();
+
+      const readableStrategyObject =
+        readableStrategy === null || readableStrategy === undefined ? {} : readableStrategy;
+      stream.writable = new WritableStream(
+        {
+          start(writableController) {
+            stream.writableController = writableController;
+            return started.promise;
+          },
+          write(chunk) {
+            if (stream.backpressure) {
+              return stream.backpressureChange.promise.then(() => {
+                if (stream.errored) {
+                  throw stream.storedError;
+                }
+                return transformChunk(stream, chunk);
+              });
+            }
+            return transformChunk(stream, chunk);
+          },
+          close() {
+            return promiseCall(transformer, "flush", [controller]).then(
+              () => {
+                try {
+                  stream.readableController.close();
+                } catch (_) {
+                  // The readable side was errored or closed by the transformer.
+                }
+              },
+              (error) => {
+                errorStream(stream, error);
+                throw error;
+              }
+            );
+          },
+          abort(reason) {
+            stream.readableController.error(reason);
+            return promiseCall(transformer, "cancel", [reason]);
+          },
+        },
+        writableStrategy
+      );
+      stream.readable = new ReadableStream(
+        {
+          start(readableController) {
+            stream.readableController = readableController;
+            return started.promise;
+          },
+          pull() {
+            setBackpressure(stream, false);
+            return stream.backpressureChange.promise;
+          },
+          cancel(reason) {
+            errorWritable(stream, reason);
+            return promiseCall(transformer, "cancel", [reason]);
+          },
+        },
+        {
+          highWaterMark:
+            readableStrategyObject.highWaterMark === undefined
+              ? 0
+              : readableStrategyObject.highWaterMark,
+          size: readableStrategyObject.size,
+        }
+      );
+      setBackpressure(stream, true);
+      streams.set(this, stream);
+      controllers.set(controller, stream);
+
+      let start;
+      try {
+        start = transformer.start === undefined
+          ? undefined
+          : transformer.start.call(transformer, controller);
+      } catch (error) {
+        started.reject(error);
+        throw error;
+      }
+      started.resolve(start);
+    }
+
+    get readable() {
+      return internal(streams, this, "TransformStream").readable;
+    }
+
+    get writable() {
+      return internal(streams, this, "TransformStream").writable;
+    }
+  }
+
+  for (const constructor of [TransformStream, TransformStreamDefaultController]) {
+    Object.defineProperty(constructor.prototype, Symbol.toStringTag, {
+      value: constructor.name,
+      configurable: true,
+    });
+  }
+
+  globalThis.TransformStream = TransformStream;
+  globalThis.TransformStreamDefaultController = TransformStreamDefaultController;
+})();
@@ -0,0 +1,573 @@
+(function () {
+  // Guards the constructors of the controller and the writer, which are
+  // created by the streams.
+  const constructorKey = Symbol("WritableStream");
+
+  // The internal state of each stream, controller and writer.
+  const streams = new WeakMap();
+  const controllers = new WeakMap();
+  const writers = new WeakMap();
+
+  function internal(map, object, name) {
+    if (!map.has(object)) {
+      throw new TypeError(`Illegal invocation: not a ${name}`);
+    }
+    return map.get(object);
+  }
+
+  function deferred() {
+    let resolve;
+    let reject;
+    const promise = new Promise((res, rej) => {
+      resolve = res;
+      reject = rej;
+    });
+    return { promise, resolve, reject };
+  }
+
+  // Rejects a promise nobody might be waiting on, without reporting it as
+  // unhandled.
+  function rejectHandled(pending, reason) {
+    pending.promise.catch(() => {});
+    pending.reject(reason);
+  }
+
+  // Calls a method of the underlying sink, turning its result or the error it
+  // throws into a promise.
+  function promiseCall(sink, name, args) {
+    try {
+      const method = sink[name];
+      if (method === undefined) {
+        return Promise.resolve(undefined);
+      }
+      return Promise.resolve(method.apply(sink, args));
+    } catch (error) {
+      return Promise.reject(error);
+    }
+  }
+
+  function sizeAlgorithm(strategy) {
+    const size = strategy.size;
+    if (size === undefined) {
+      return () => 1;
+    }
+    if (typeof size !== "function") {
+      throw new TypeError("The size of the queuing strategy must be a function.");
+    }
+    return (chunk) => size.call(undefined, chunk);
+  }
+
+  function highWaterMarkOf(strategy) {
+    const highWaterMark =
+      strategy.highWaterMark === undefined ? 1 : Number(strategy.highWaterMark);
+    if (Number.isNaN(highWaterMark) || highWaterMark < 0) {
+      throw new RangeError("The high water mark must be a non-negative number.");
+    }
+    return highWaterMark;
+  }
+
+  function desiredSize(stream) {
+    if (stream.state === "errored") {
+      return null;
+    }
+    if (stream.state === "closed") {
+      return 0;
+    }
+    return stream.highWaterMark - stream.queueTotalSize;
+  }
+
+  function closeQueuedOrInFlight(stream) {
+    return stream.closeRequest !== null;
+  }
+
+  // Swaps the `ready` promise of the writer for a pending one while the queue
+  // is full, and resolves it once there's room again.
+  function updateBackpressure(stream) {
+    const writer = stream.writer;
+    if (writer === null || stream.state !== "writable" || closeQueuedOrInFlight(stream)) {
+      return;
+    }
+    const backpressure = desiredSize(stream) <= 0;
+    if (backpressure === stream.backpressure) {
+      return;
+    }
+    stream.backpressure = backpressure;
+    if (backpressure) {
+      writer.ready = deferred();
+    } else {
+      writer.ready.resolve(undefined);
+    }
+  }
+
+  function errorStream(stream, error) {
+    if (stream.state !== "writable") {
+      return;
+    }
+    stream.state = "errored";
+    stream.storedError = error;
+    // The chunk being written, if any, is settled once the sink is done with
+    // it.
+    for (const { pending } of stream.queue.splice(stream.inFlight ? 1 : 0)) {
+      if (pending !== null) {
+        rejectHandled(pending, error);
+      }
+    }
+    stream.queueTotalSize = 0;
+    if (stream.closeRequest !== null && !stream.inFlight) {
+      rejectHandled(stream.closeRequest, error);
+    }
+    const writer = stream.writer;
+    if (writer !== null) {
+      rejectHandled(writer.closed, error);
+      if (stream.backpressure) {
+        rejectHandled(writer.ready, error);
+      } else {
+        writer.ready = deferred();
+        rejectHandled(writer.ready, error);
+      }
+    }
+  }
+
+  function finishClose(stream) {
+    stream.state = "closed";
+    stream.closeRequest.resolve(undefined);
+    if (stream.writer !== null) {
+      stream.writer.closed.resolve(undefined);
+    }
+  }
+
+  // Hands the chunk at the head of the queue, or the close request once all
+  // the chunks are written, to the underlying sink, one at a time.
+  function advanceQueue(stream) {
+    if (!stream.started || stream.inFlight || stream.state !== "writable") {
+      return;
+    }
+    if (stream.queue.length === 0) {
+      return;
+    }
+    const { chunk, size, pending } = stream.queue[0];
+    stream.inFlight = true;
+
+    if (pending === null) {
+      stream.queue.shift();
+      promiseCall(stream.sink, "close", []).then(
+        () => {
+          stream.inFlight = false;
+          if (stream.state === "writable") {
+            finishClose(stream);
+          } else {
+            rejectHandled(stream.closeRequest, stream.storedError);
+          }
+        },
+        (error) => {
+          stream.inFlight = false;
+          errorStream(stream, error);
+          rejectHandled(stream.closeRequest, error);
+        }
+      );
+      return;
+    }
+
+    promiseCall(stream.sink, "write", [chunk, stream.controller]).then(
+      () => {
+        stream.inFlight = false;
+        if (stream.state !== "writable") {
+          pending.resolve(undefined);
+          return;
+        }
+        stream.queue.shift();
+        stream.queueTotalSize = Math.max(0, stream.queueTotalSize - size);
+        pending.resolve(undefined);
+        updateBackpressure(stream);
+        advanceQueue(stream);
+      },
+      (error) => {
+        stream.inFlight = false;
+        if (stream.queue[0] !== undefined && stream.queue[0].pending === pending) {
+          stream.queue.shift();
+        }
+        pending.reject(error);
+        errorStream(stream, error);
+      }
+    );
+  }
+
+  function writeChunk(stream, chunk) {
+    if (stream.state === "errored") {
+      return Promise.reject(stream.storedError);
+    }
+    if (closeQueuedOrInFlight(stream) || stream.state === "closed") {
+      return Promise.reject(new TypeError("Cannot write to a closed stream."));
+    }
+    let size;
+    try {
+      size = stream.size(chunk);
+      if (typeof size !== "number" || Number.isNaN(size) || size < 0 || size === Infinity) {
+        throw new RangeError("The size of a chunk must be a non-negative, finite number.");
+      }
+    } catch (error) {
+      errorStream(stream, error);
+      return Promise.reject(error);
+    }
+    const pending = deferred();
+    stream.queue.push({ chunk, size, pending });
+    stream.queueTotalSize += size;
+    updateBackpressure(stream);
+    advanceQueue(stream);
+    return pending.promise;
+  }
+
+  function closeStream(stream) {
+    if (stream.state === "errored") {
+      return Promise.reject(stream.storedError);
+    }
+    if (closeQueuedOrInFlight(stream) || stream.state === "closed") {
+      return Promise.reject(new TypeError("The stream is already closing or closed."));
+    }
+    stream.closeRequest = deferred();
+    if (stream.writer !== null && stream.backpressure) {
+      stream.writer.ready.resolve(undefined);
+    }
+    stream.queue.push({ chunk: undefined, size: 0, pending: null });
+    advanceQueue(stream);
+    return stream.closeRequest.promise;
+  }
+
+  function abortStream(stream, reason) {
+    if (stream.state === "closed" || stream.state === "errored") {
+      return Promise.resolve(undefined);
+    }
+    errorStream(stream, reason);
+    return promiseCall(stream.sink, "abort", [reason]).then(() => undefined);
+  }
+
+  class WritableStreamDefaultController {
+    constructor(key) {
+      if (key !== constructorKey) {
+        throw new TypeError("Illegal constructor");
+      }
+    }
+
+    error(error) {
+      errorStream(internal(controllers, this, "WritableStreamDefaultController"), error);
+    }
+  }
+
+  class WritableStreamDefaultWriter {
+    constructor(stream) {
+      const state = internal(streams, stream, "WritableStream");
+      if (state.writer !== null) {
+        throw new TypeError("The stream is already locked to a writer.");
+      }
+      const writer = { stream: state, closed: deferred(), ready: deferred() };
+      if (state.state === "closed") {
+        writer.closed.resolve(undefined);
+        writer.ready.resolve(undefined);
+      } else if (state.state === "errored") {
+        rejectHandled(writer.closed, state.storedError);
+        rejectHandled(writer.ready, state.storedError);
+      } else if (!state.backpressure || closeQueuedOrInFlight(state)) {
+        writer.ready.resolve(undefined);
+      }
+      state.writer = writer;
+      writers.set(this, writer);
+    }
+
+    get closed() {
+      return internal(writers, this, "WritableStreamDefaultWriter").closed.promise;
+    }
+
+    get ready() {
+      return internal(writers, this, "WritableStreamDefaultWriter").ready.promise;
+    }
+
+    get desiredSize() {
+      const writer = internal(writers, this, "WritableStreamDefaultWriter");
+      if (writer.stream === null) {
+        throw new TypeError("The writer has been released.");
+      }
+      return desiredSize(writer.stream);
+    }
+
+    write(chunk) {
+      let writer;
+      try {
+        writer = internal(writers, this, "WritableStreamDefaultWriter");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (writer.stream === null) {
+        return Promise.reject(new TypeError("The writer has been released."));
+      }
+      return writeChunk(writer.stream, chunk);
+    }
+
+    close() {
+      let writer;
+      try {
+        writer = internal(writers, this, "WritableStreamDefaultWriter");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (writer.stream === null) {
+        return Promise.reject(new TypeError("The writer has been released."));
+      }
+      return closeStream(writer.stream);
+    }
+
+    abort(reason) {
+      let writer;
+      try {
+        writer = internal(writers, this, "WritableStreamDefaultWriter");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (writer.stream === null) {
+        return Promise.reject(new TypeError("The writer has been released."));
+      }
+      return abortStream(writer.stream, reason);
+    }
+
+    releaseLock() {
+      const writer = internal(writers, this, "WritableStreamDefaultWriter");
+      const stream = writer.stream;
+      if (stream === null) {
+        return;
+      }
+      const error = new TypeError("The writer has been released.");
+      writer.closed = deferred();
+      rejectHandled(writer.closed, error);
+      writer.ready = deferred();
+      rejectHandled(writer.ready, error);
+      stream.writer = null;
+      writer.stream = null;
+    }
+  }
+
+  class WritableStream {
+    constructor(underlyingSink = {}, strategy = {}) {
+      if (underlyingSink === null || typeof underlyingSink !== "object") {
+        throw new TypeError("The underlying sink must be an object.");
+      }
+      if (underlyingSink.type !== undefined) {
+        throw new RangeError(`The stream type ${underlyingSink.type} is not supported.`);
+      }
+      const strategyObject = strategy === null || strategy === undefined ? {} : strategy;
+      const controller = new WritableStreamDefaultController(constructorKey);
+      const stream = {
+        state: "writable",
+        writer: null,
+        storedError: undefined,
+        sink: underlyingSink,
+        controller,
+        // The chunks waiting to be written, followed by a `pending` of `null`
+        // once the stream is asked to close.
+        queue: [],
+        queueTotalSize: 0,
+        size: sizeAlgorithm(strategyObject),
+        highWaterMark: highWaterMarkOf(strategyObject),
+        started: false,
+        inFlight: false,
+        closeRequest: null,
+        backpressure: false,
+      };
+      stream.backpressure = desiredSize(stream) <= 0;
+      streams.set(this, stream);
+      controllers.set(controller, stream);
+
+      let start;
+      try {
+        start = underlyingSink.start === undefined
+          ? undefined
+          : underlyingSink.start.call(underlyingSink, controller);
+      } catch (error) {
+        errorStream(stream, error);
+        throw error;
+      }
+      Promise.resolve(start).then(
+        () => {
+          stream.started = true;
+          advanceQueue(stream);
+        },
+        (error) => errorStream(stream, error)
+      );
+    }
+
+    get locked() {
+      return internal(streams, this, "WritableStream").writer !== null;
+    }
+
+    abort(reason) {
+      let stream;
+      try {
+        stream = internal(streams, this, "WritableStream");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (stream.writer !== null) {
+        return Promise.reject(new TypeError("Cannot abort a locked stream."));
+      }
+      return abortStream(stream, reason);
+    }
+
+    close() {
+      let stream;
+      try {
+        stream = internal(streams, this, "WritableStream");
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      if (stream.writer !== null) {
+        return Promise.reject(new TypeError("Cannot close a locked stream."));
+      }
+      return closeStream(stream);
+    }
+
+    getWriter() {
+      internal(streams, this, "WritableStream");
+      return new WritableStreamDefaultWriter(this);
+    }
+  }
+
+  // Pipes the chunks of a readable stream to a writable stream, one at a time,
+  // waiting for each chunk to be written before reading the next one.
+  function pipeTo(source, destination, options) {
+    const preventClose = Boolean(options && options.preventClose);
+    const preventAbort = Boolean(options && options.preventAbort);
+    const preventCancel = Boolean(options && options.preventCancel);
+    const signal = options && options.signal;
+    const reader = source.getReader();
+    const writer = destination.getWriter();
+
+    return new Promise((resolve, reject) => {
+      let done = false;
+      const finish = (action, error, isError) => {
+        if (done) {
+          return;
+        }
+        done = true;
+        const release = () => {
+          reader.releaseLock();
+          writer.releaseLock();
+          if (isError) {
+            reject(error);
+          } else {
+            resolve(undefined);
+          }
+        };
+        action().then(release, (actionError) => {
+          error = actionError;
+          isError = true;
+          release();
+        });
+      };
+      const none = () => Promise.resolve();
+
+      if (signal !== undefined) {
+        const onAbort = () => {
+          const reason = signal.reason;
+          finish(
+            () =>
+              Promise.all([
+                preventAbort ? undefined : writer.abort(reason),
+                preventCancel ? undefined : reader.cancel(reason),
+              ]),
+            reason,
+            true
+          );
+        };
+        if (signal.aborted) {
+          onAbort();
+          return;
+        }
+        signal.addEventListener("abort", onAbort, { once: true });
+      }
+
+      const step = () => {
+        if (done) {
+          return;
+        }
+        writer.ready
+          .then(() => reader.read(), (error) => {
+            finish(() => (preventCancel ? none() : reader.cancel(error)), error, true);
+            return null;
+          })
+          .then(
+            (result) => {
+              if (result === null || done) {
+                return;
+              }
+              if (result.done) {
+                finish(() => (preventClose ? none() : writer.close()), undefined, false);
+                return;
+              }
+              writer.write(result.value).then(step, (error) => {
+                finish(() => (preventCancel ? none() : reader.cancel(error)), error, true);
+              });
+            },
+            (error) => {
+              finish(() => (preventAbort ? none() : writer.abort(error)), error, true);
+            }
+          );
+      };
+      step();
+    });
+  }
+
+  const ReadableStream = globalThis.ReadableStream;
+
+  Object.defineProperty(ReadableStream.prototype, "pipeTo", {
+    value: function (destination, options) {
+      try {
+        if (!(destination instanceof WritableStream)) {
+          throw new TypeError("The destination must be a WritableStream.");
+        }
+        if (this.locked) {
+          throw new TypeError("Cannot pipe a locked stream.");
+        }
+        if (destination.locked) {
+          throw new TypeError("Cannot pipe to a locked stream.");
+        }
+      } catch (error) {
+        return Promise.reject(error);
+      }
+      return pipeTo(this, destination, options);
+    },
+    writable: true,
+    configurable: true,
+  });
+
+  Object.defineProperty(ReadableStream.prototype, "pipeThrough", {
+    value: function (transform, options) {
+      const { writable, readable } = transform;
+      if (!(writable instanceof WritableStream) || !(readable instanceof ReadableStream)) {
+        throw new TypeError("The transform must have a writable and a readable stream.");
+      }
+      if (this.locked) {
+        throw new TypeError("Cannot pipe a locked stream.");
+      }
+      if (writable.locked) {
+        throw new TypeError("Cannot pipe to a locked stream.");
+      }
+      pipeTo(this, writable, options).catch(() => {});
+      return readable;
+    },
+    writable: true,
+    configurable: true,
+  });
+
+  for (const constructor of [
+    WritableStream,
+    WritableStreamDefaultController,
+    WritableStreamDefaultWriter,
+  ]) {
+    Object.defineProperty(constructor.prototype, Symbol.toStringTag, {
+      value: constructor.name,
+      configurable: true,
+    });
+  }
+
+  globalThis.WritableStream = WritableStream;
+  globalThis.WritableStreamDefaultController = WritableStreamDefaultController;
+  globalThis.WritableStreamDefaultWriter = WritableStreamDefaultWriter;
+})();
@@ -0,0 +1,237 @@
+use crate::{
+    apis::blob,
+    hold, hold_and_release,
+    quickjs::{context::EvalOptions, Ctx, Function, Value},
+    to_js_error, Args,
+};
+use anyhow::{anyhow, Error, Result};
+
+/// Register the global `structuredClone` function.
+///
+/// Requires the blob APIs to be registered. `weak_map_handles` must match the
+/// setting of the blob APIs.
+pub(crate) fn register(this: Ctx<'_>, weak_map_handles: bool) -> Result<()> {
+    let globals = this.globals();
+    globals.set(
+        "__javy_blob_clone",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            clone_blob(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+    globals.set(
+        "__javy_blob_handle",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            blob_handle(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    globals.set("__javy_blob_weak_map_handles", weak_map_handles)?;
+
+    let mut opts = EvalOptions::default();
+    opts.strict = false;
+    this.eval_with_options::<(), _>(include_str!("./structured-clone.js"), opts)?;
+
+    Ok::<_, Error>(())
+}
+
+/// Copy the blob with the given ID into a new storage entry, returning the ID
+/// of the copy.
+fn clone_blob(args: Args<'_>) -> Result<Value<'_>> {
+    let (cx, args) = args.release();
+    let id = args
+        .first()
+        .and_then(|id| id.as_number())
+        .ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    let clone_id = blob::clone_entry(id)?;
+    Ok(Value::new_number(cx, clone_id as f64))
+}
+
+/// Create the handle owning the storage entry of a cloned blob.
+fn blob_handle(args: Args<'_>) -> Result<Value<'_>> {
+    let (cx, args) = args.release();
+    let id = args
+        .first()
+        .and_then(|id| id.as_number())
+        .ok_or_else(|| anyhow!("Blob ID must be a number"))? as u32;
+    blob::handle(cx, id)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{apis::blob, quickjs::Value, val_to_string, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    fn runtime() -> Result<Runtime> {
+        let mut config = Config::default();
+        config.structured_clone(true);
+        Runtime::new(config)
+    }
+
+    #[test]
+    fn test_disabled_by_default() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("typeof structuredClone")?;
+            assert_eq!("undefined", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_values() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: Value = cx.eval(
+                r#"
+                const bytes = new Uint8Array([1, 2, 3, 4]);
+                const original = {
+                    s: "str", n: 1.5, b: true, z: null, u: undefined,
+                    list: [1, "two", [3]],
+                    date: new Date(1000),
+                    re: /ab+c/gi,
+                    set: new Set([1, 2]),
+                    bytes,
+                    view: new Uint8Array(bytes.buffer, 1, 2),
+                };
+                const copy = structuredClone(original);
+                bytes[1] = 42;
+                [
+                    copy !== original,
+                    copy.s === "str" && copy.n === 1.5 && copy.b === true && copy.z === null,
+                    "u" in copy && copy.u === undefined,
+                    JSON.stringify(copy.list) === '[1,"two",[3]]' && copy.list !== original.list,
+                    copy.date instanceof Date && copy.date.getTime() === 1000,
+                    copy.re instanceof RegExp && copy.re.source === "ab+c" && copy.re.flags === "gi",
+                    copy.set instanceof Set && copy.set.has(1) && copy.set.has(2),
+                    copy.bytes instanceof Uint8Array && copy.bytes[1] === 2,
+                    copy.view.buffer === copy.bytes.buffer && copy.view[0] === 2,
+                ].every(Boolean)
+                "#,
+            )?;
+            assert_eq!(Some(true), result.as_bool());
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_cyclic_graph() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            let result: Value = cx.eval(
+                r#"
+                const a = { name: "a" };
+                const b = { name: "b", a };
+                a.b = b;
+                a.self = a;
+                a.list = [a, b];
+                const copy = structuredClone(a);
+                [
+                    copy !== a,
+                    copy.self === copy,
+                    copy.b.a === copy,
+                    copy.list[0] === copy && copy.list[1] === copy.b,
+                    copy.b !== b,
+                ].every(Boolean)
+                "#,
+            )?;
+            assert_eq!(Some(true), result.as_bool());
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_clone_map_with_blob() -> Result<()> {
+        let runtime = runtime()?;
+        let (original_id, copy_id) = runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                r#"
+                globalThis.original = new Map([
+                    ["blob", new Blob(["hello"], { type: "text/plain" })],
+                    ["file", new File(["world"], "a.txt", { lastModified: 7 })],
+                ]);
+                globalThis.copy = structuredClone(original);
+                "#,
+            )?;
+
+            let result: Value = cx.eval(
+                r#"
+                const blob = copy.get("blob");
+                const file = copy.get("file");
+                [
+                    copy instanceof Map && copy !== original,
+                    blob instanceof Blob && blob !== original.get("blob"),
+                    blob.type === "text/plain" && blob.size === 5 && blob.text() === "hello",
+                    file instanceof File && file.name === "a.txt" && file.lastModified === 7,
+                    file.text() === "world",
+                ].every(Boolean)
+                "#,
+            )?;
+            assert_eq!(Some(true), result.as_bool());
+
+            let original_id: u32 = cx.eval("original.get('blob')._blobId")?;
+            let copy_id: u32 = cx.eval("copy.get('blob')._blobId")?;
+            Ok::<_, Error>((original_id, copy_id))
+        })?;
+
+        // The clone has its own storage entry
+        assert_ne!(original_id, copy_id);
+        let registry = blob::blob_registry();
+        assert_eq!(registry.get(original_id), registry.get(copy_id));
+        assert!(blob::clone_entry(u32::MAX).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_cloned_blob_weak_map_handle() -> Result<()> {
+        let mut config = Config::default();
+        config.structured_clone(true).blob_weak_map_handles(true);
+        let runtime = Runtime::new(config)?;
+        let (original_id, copy_id) = runtime.context().with(|cx| {
+            let ids: Vec<u32> = cx.eval(
+                r#"
+                globalThis.original = new Blob(["hello"]);
+                (() => {
+                    const copy = structuredClone(original);
+                    if ("_blobHandle" in copy) {
+                        throw new Error("handle should be held in a WeakMap");
+                    }
+                    return [original._blobId, copy._blobId];
+                })()
+                "#,
+            )?;
+            // Locking the storage removes the released entries
+            cx.eval::<(), _>("new Blob([])")?;
+            Ok::<_, Error>((ids[0], ids[1]))
+        })?;
+
+        // The collected copy is released, the original is kept
+        let registry = blob::blob_registry();
+        assert_eq!(None, registry.get(copy_id));
+        assert!(registry.get(original_id).is_some());
+        Ok(())
+    }
+
+    #[test]
+    fn test_unsupported_values_throw() -> Result<()> {
+        let runtime = runtime()?;
+        runtime.context().with(|cx| {
+            for value in ["() => {}", "Symbol('s')", "{ f() {} }", "new WeakMap()", "[Promise.resolve()]"] {
+                let result: Value = cx.eval(format!(
+                    "try {{ structuredClone({value}); 'no error' }} catch (e) {{ `${{e.name}}: ${{e.message}}` }}"
+                ))?;
+                let message = val_to_string(&cx, result)?;
+                assert!(
+                    message.starts_with("TypeError: DataCloneError:"),
+                    "unexpected result for {value}: {message}"
+                );
+            }
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
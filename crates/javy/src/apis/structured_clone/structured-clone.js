@@ -0,0 +1,140 @@
+(function () {
+  const __javy_blob_clone = globalThis.__javy_blob_clone;
+  const __javy_blob_handle = globalThis.__javy_blob_handle;
+  const TypedArray = Object.getPrototypeOf(Uint8Array);
+  // The handles releasing the storage of cloned blobs, when they're held in
+  // a WeakMap rather than in hidden properties of the blobs.
+  const blobHandles = globalThis.__javy_blob_weak_map_handles
+    ? new WeakMap()
+    : null;
+
+  function dataCloneError(description) {
+    return new TypeError(`DataCloneError: ${description} could not be cloned`);
+  }
+
+  function structuredClone(value) {
+    // Maps already cloned objects to their clone, preserving shared and
+    // circular references.
+    const memory = new Map();
+
+    function clone(value) {
+      const type = typeof value;
+      if (type === "function") {
+        throw dataCloneError(`function ${value.name || "anonymous"}`);
+      }
+      if (type === "symbol") {
+        throw dataCloneError(String(value));
+      }
+      if (value === null || type !== "object") {
+        return value;
+      }
+      if (memory.has(value)) {
+        return memory.get(value);
+      }
+
+      if (value instanceof Blob) {
+        const copy = Object.create(Object.getPrototypeOf(value));
+        const blobId = __javy_blob_clone(value._blobId);
+        Object.defineProperty(copy, "_blobId", {
+          value: blobId,
+          writable: true,
+          enumerable: false,
+          configurable: true,
+        });
+        // Releases the copy's storage entry once it's garbage collected
+        const handle = __javy_blob_handle(blobId);
+        if (blobHandles !== null) {
+          blobHandles.set(copy, handle);
+        } else {
+          Object.defineProperty(copy, "_blobHandle", {
+            value: handle,
+            writable: true,
+            enumerable: false,
+            configurable: true,
+          });
+        }
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof ArrayBuffer) {
+        const copy = value.slice(0);
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof TypedArray) {
+        const buffer = clone(value.buffer);
+        const copy = new value.constructor(buffer, value.byteOffset, value.length);
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof DataView) {
+        const buffer = clone(value.buffer);
+        const copy = new DataView(buffer, value.byteOffset, value.byteLength);
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof Date) {
+        const copy = new Date(value.getTime());
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof RegExp) {
+        const copy = new RegExp(value.source, value.flags);
+        memory.set(value, copy);
+        return copy;
+      }
+
+      if (value instanceof Map) {
+        const copy = new Map();
+        memory.set(value, copy);
+        for (const [key, entry] of value) {
+          copy.set(clone(key), clone(entry));
+        }
+        return copy;
+      }
+
+      if (value instanceof Set) {
+        const copy = new Set();
+        memory.set(value, copy);
+        for (const entry of value) {
+          copy.add(clone(entry));
+        }
+        return copy;
+      }
+
+      let copy;
+      if (Array.isArray(value)) {
+        copy = new Array(value.length);
+      } else if (Object.prototype.toString.call(value) === "[object Object]") {
+        copy = {};
+      } else {
+        throw dataCloneError(Object.prototype.toString.call(value));
+      }
+      memory.set(value, copy);
+      for (const key of Object.keys(value)) {
+        copy[key] = clone(value[key]);
+      }
+      return copy;
+    }
+
+    try {
+      return clone(value);
+    } finally {
+      // `clone` refers to itself, so the closure, and the memory with it, is
+      // only freed by the cycle collector. Clearing the memory lets the
+      // values it holds be released as soon as they're unreachable.
+      memory.clear();
+    }
+  }
+
+  globalThis.structuredClone = structuredClone;
+
+  Reflect.deleteProperty(globalThis, "__javy_blob_clone");
+  Reflect.deleteProperty(globalThis, "__javy_blob_handle");
+  Reflect.deleteProperty(globalThis, "__javy_blob_weak_map_handles");
+})();
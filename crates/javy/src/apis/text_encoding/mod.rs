@@ -1,9 +1,10 @@
-use std::str;
+use std::{borrow::Cow, str};
 
 use crate::{
     hold, hold_and_release,
     quickjs::{
-        context::EvalOptions, Ctx, Exception, Function, String as JSString, TypedArray, Value,
+        context::EvalOptions, qjs::JS_GetArrayBuffer, Ctx, Exception, Function, Object,
+        String as JSString, TypedArray, Value,
     },
     to_js_error, to_string_lossy, Args,
 };
@@ -13,7 +14,7 @@ use anyhow::{anyhow, bail, Error, Result};
 pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
     let globals = this.globals();
     globals.set(
-        "__javy_decodeUtf8BufferToString",
+        "__javy_decodeBufferToString",
         Function::new(this.clone(), |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
             decode(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
@@ -26,6 +27,13 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
             encode(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
         }),
     )?;
+    globals.set(
+        "__javy_encodeStringIntoUtf8Buffer",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            encode_into(hold!(cx.clone(), args)).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
     let mut opts = EvalOptions::default();
     opts.strict = false;
     this.eval_with_options::<(), _>(include_str!("./text-encoding.js"), opts)?;
@@ -33,12 +41,29 @@ pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
     Ok::<_, Error>(())
 }
 
-/// Decode a UTF-8 byte buffer as a JavaScript String.
+/// Strip the UTF-8 byte order mark from the start of `bytes`, if any.
+fn strip_bom(bytes: &[u8]) -> &[u8] {
+    match bytes {
+        // [0xEF, 0xBB, 0xBF] is the UTF-8 BOM which we want to strip
+        [0xEF, 0xBB, 0xBF, rest @ ..] => rest,
+        _ => bytes,
+    }
+}
+
+/// Decode UTF-8 bytes the way a non-fatal `TextDecoder` does: invalid
+/// sequences are replaced with U+FFFD and, unless `ignore_bom` is true, a
+/// leading byte order mark is stripped.
+pub(crate) fn decode_lossy(bytes: &[u8], ignore_bom: bool) -> Cow<'_, str> {
+    let bytes = if ignore_bom { bytes } else { strip_bom(bytes) };
+    String::from_utf8_lossy(bytes)
+}
+
+/// Decode a byte buffer in the given encoding as a JavaScript String.
 fn decode(args: Args<'_>) -> Result<Value<'_>> {
     let (cx, args) = args.release();
-    if args.len() != 5 {
+    if args.len() != 6 {
         bail!(
-            "Wrong number of arguments. Expected 5 arguments. Got: {}",
+            "Wrong number of arguments. Expected 6 arguments. Got: {}",
             args.len()
         );
     }
@@ -57,37 +82,100 @@ fn decode(args: Args<'_>) -> Result<Value<'_>> {
     let byte_length = args[2]
         .as_number()
         .ok_or_else(|| anyhow!("byte_length must be a number"))? as usize;
-    let fatal = args[3]
+    let encoding = args[3]
+        .as_string()
+        .ok_or_else(|| anyhow!("encoding must be a string"))?
+        .to_string()?;
+    let fatal = args[4]
         .as_bool()
         .ok_or_else(|| anyhow!("fatal must be a boolean"))?;
-    let ignore_bom = args[4]
+    let ignore_bom = args[5]
         .as_bool()
         .ok_or_else(|| anyhow!("ignore_bom must be a boolean"))?;
 
-    let mut view = buffer
+    let view = buffer
         .get(byte_offset..(byte_offset + byte_length))
         .ok_or_else(|| anyhow!("Provided offset and length is not valid for provided buffer"))?;
 
-    if !ignore_bom {
-        view = match view {
-            // [0xEF, 0xBB, 0xBF] is the UTF-8 BOM which we want to strip
-            [0xEF, 0xBB, 0xBF, rest @ ..] => rest,
-            _ => view,
-        };
-    }
+    let invalid =
+        || Exception::throw_type(&cx, &format!("The encoded data was not valid {encoding}"));
+    let js_string = match encoding.as_str() {
+        "utf-8" if fatal => {
+            let view = if ignore_bom { view } else { strip_bom(view) };
+            JSString::from_str(cx.clone(), str::from_utf8(view).map_err(|_| invalid())?)
+        }
+        "utf-8" => JSString::from_str(cx.clone(), &decode_lossy(view, ignore_bom)),
+        "utf-16le" | "utf-16be" => {
+            let big_endian = encoding == "utf-16be";
+            let decoded = decode_utf16(view, big_endian, ignore_bom, fatal).ok_or_else(invalid)?;
+            JSString::from_str(cx.clone(), &decoded)
+        }
+        "windows-1252" => JSString::from_str(cx.clone(), &decode_windows_1252(view)),
+        _ => bail!("Unsupported encoding: {encoding}"),
+    };
 
-    let js_string = if fatal {
-        JSString::from_str(
-            cx.clone(),
-            str::from_utf8(view)
-                .map_err(|_| Exception::throw_type(&cx, "The encoded data was not valid utf-8"))?,
-        )
+    Ok(Value::from_string(js_string?))
+}
+
+/// Decode UTF-16 bytes, stripping a leading byte order mark unless
+/// `ignore_bom` is true. Unpaired surrogates and a trailing odd byte are
+/// replaced with U+FFFD, or make decoding fail when `fatal` is true.
+fn decode_utf16(bytes: &[u8], big_endian: bool, ignore_bom: bool, fatal: bool) -> Option<String> {
+    let bom: &[u8] = if big_endian {
+        &[0xFE, 0xFF]
     } else {
-        let str = String::from_utf8_lossy(view);
-        JSString::from_str(cx, &str)
+        &[0xFF, 0xFE]
+    };
+    let bytes = match bytes.strip_prefix(bom) {
+        Some(rest) if !ignore_bom => rest,
+        _ => bytes,
     };
 
-    Ok(Value::from_string(js_string?))
+    let (units, remainder) = bytes.as_chunks::<2>();
+    let odd = !remainder.is_empty();
+    let units = units.iter().map(|&unit| {
+        if big_endian {
+            u16::from_be_bytes(unit)
+        } else {
+            u16::from_le_bytes(unit)
+        }
+    });
+
+    let mut decoded = String::with_capacity(bytes.len() / 2);
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => decoded.push(c),
+            Err(_) if fatal => return None,
+            Err(_) => decoded.push(char::REPLACEMENT_CHARACTER),
+        }
+    }
+    if odd {
+        if fatal {
+            return None;
+        }
+        decoded.push(char::REPLACEMENT_CHARACTER);
+    }
+    Some(decoded)
+}
+
+/// The characters of the bytes 0x80 to 0x9F in windows-1252. The other bytes
+/// decode to the code point with the same value.
+const WINDOWS_1252_HIGH: [char; 32] = [
+    '\u{20AC}', '\u{0081}', '\u{201A}', '\u{0192}', '\u{201E}', '\u{2026}', '\u{2020}', '\u{2021}',
+    '\u{02C6}', '\u{2030}', '\u{0160}', '\u{2039}', '\u{0152}', '\u{008D}', '\u{017D}', '\u{008F}',
+    '\u{0090}', '\u{2018}', '\u{2019}', '\u{201C}', '\u{201D}', '\u{2022}', '\u{2013}', '\u{2014}',
+    '\u{02DC}', '\u{2122}', '\u{0161}', '\u{203A}', '\u{0153}', '\u{009D}', '\u{017E}', '\u{0178}',
+];
+
+/// Decode windows-1252 bytes, which can't be invalid.
+fn decode_windows_1252(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| match byte {
+            0x80..=0x9F => WINDOWS_1252_HIGH[(byte - 0x80) as usize],
+            _ => char::from(byte),
+        })
+        .collect()
 }
 
 /// Encode a JavaScript String into a JavaScript UInt8Array.
@@ -111,6 +199,61 @@ fn encode(args: Args<'_>) -> Result<Value<'_>> {
         .to_owned())
 }
 
+/// Encode a JavaScript String into a slice of an ArrayBuffer, returning an
+/// object with the number of UTF-16 code units read and bytes written.
+fn encode_into(args: Args<'_>) -> Result<Value<'_>> {
+    let (cx, args) = args.release();
+    if args.len() != 4 {
+        bail!("Wrong number of arguments. Expected 4. Got {}", args.len());
+    }
+
+    let js_string = args[0]
+        .as_string()
+        .ok_or_else(|| anyhow!("Argument must be a String"))?;
+    let byte_offset = args[2]
+        .as_number()
+        .ok_or_else(|| anyhow!("offset must be a number"))? as usize;
+    let byte_length = args[3]
+        .as_number()
+        .ok_or_else(|| anyhow!("byte_length must be a number"))? as usize;
+
+    // Safety
+    // The ArrayBuffer is only borrowed for the duration of this call, during
+    // which no JavaScript code runs that could detach or resize it.
+    let buffer = unsafe {
+        let mut len = 0;
+        let ptr = JS_GetArrayBuffer(cx.as_raw().as_ptr(), &mut len, args[1].as_raw());
+        if ptr.is_null() {
+            bail!("buffer must be an ArrayBuffer");
+        }
+        std::slice::from_raw_parts_mut(ptr, len as _)
+    };
+    let destination = buffer
+        .get_mut(byte_offset..(byte_offset + byte_length))
+        .ok_or_else(|| anyhow!("Provided offset and length is not valid for provided buffer"))?;
+
+    let source = js_string
+        .to_string()
+        .unwrap_or_else(|error| to_string_lossy(&cx, js_string, error));
+    let mut read = 0;
+    let mut written = 0;
+    for c in source.chars() {
+        let len = c.len_utf8();
+        // Only whole code points are written.
+        if written + len > destination.len() {
+            break;
+        }
+        c.encode_utf8(&mut destination[written..]);
+        written += len;
+        read += c.len_utf16();
+    }
+
+    let result = Object::new(cx)?;
+    result.set("read", read)?;
+    result.set("written", written)?;
+    Ok(result.into_value())
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{quickjs::Value, Config, Runtime};
@@ -138,4 +281,166 @@ mod tests {
         })?;
         Ok(())
     }
+
+    #[test]
+    fn test_decode_stream() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: String = this.eval(
+                r#"
+                let decoder = new TextDecoder('utf-8');
+                let bytes = new TextEncoder().encode('a😀b');
+                [
+                    decoder.decode(bytes.subarray(0, 3), { stream: true }),
+                    decoder.decode(bytes.subarray(3), { stream: true }),
+                    decoder.decode(),
+                ].join('|');
+            "#,
+            )?;
+            assert_eq!("a|😀b|", result);
+
+            let result: String = this.eval(
+                r#"
+                decoder.decode(new Uint8Array([0xF0, 0x9F]), { stream: true }) + '|' +
+                    decoder.decode();
+            "#,
+            )?;
+            assert_eq!("|\u{FFFD}", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_stream_bom() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: String = this.eval(
+                r#"
+                let decoder = new TextDecoder();
+                let bom = [0xEF, 0xBB, 0xBF];
+                [
+                    decoder.decode(new Uint8Array([...bom, 0x61]), { stream: true }),
+                    decoder.decode(new Uint8Array([...bom, 0x62])),
+                    decoder.decode(new Uint8Array([...bom, 0x63])),
+                ].join('|');
+            "#,
+            )?;
+            assert_eq!("a|\u{FEFF}b|c", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_encode_into() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            this.eval::<(), _>(
+                r#"
+                function encodeInto(source, size) {
+                    let buffer = new Uint8Array(size + 2).fill(0xAA);
+                    let { read, written } = new TextEncoder().encodeInto(source, buffer.subarray(1, size + 1));
+                    return [read, written, ...buffer].join(',');
+                }
+            "#,
+            )?;
+
+            // Exact fit.
+            let result: String = this.eval("encodeInto('aé', 3)")?;
+            assert_eq!("2,3,170,97,195,169,170", result);
+
+            // A multi-byte sequence that doesn't fit isn't split.
+            let result: String = this.eval("encodeInto('aé', 2)")?;
+            assert_eq!("1,1,170,97,170,170", result);
+
+            // Surrogate pairs are read as a whole or not at all.
+            let result: String = this.eval("encodeInto('a😀', 4)")?;
+            assert_eq!("1,1,170,97,170,170,170,170", result);
+            let result: String = this.eval("encodeInto('a😀', 5)")?;
+            assert_eq!("3,5,170,97,240,159,152,128,170", result);
+
+            // Lone surrogates are encoded as replacement characters.
+            let result: String = this.eval("encodeInto('\\uD800', 3)")?;
+            assert_eq!("1,3,170,239,191,189,170", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_utf16_and_windows_1252() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let cases = [
+                // Labels resolve to the encoding names
+                ("new TextDecoder('UCS-2').encoding", "utf-16le"),
+                ("new TextDecoder(' utf-16be ').encoding", "utf-16be"),
+                ("new TextDecoder('iso-8859-1').encoding", "windows-1252"),
+                ("new TextDecoder('latin1').encoding", "windows-1252"),
+                // The byte order mark is stripped
+                (
+                    "new TextDecoder('utf-16le').decode(new Uint8Array([0xFF, 0xFE, 0x68, 0, 0x3D, 0xD8, 0, 0xDE]))",
+                    "h😀",
+                ),
+                (
+                    "new TextDecoder('utf-16le', { ignoreBOM: true }).decode(new Uint8Array([0xFF, 0xFE, 0x68, 0]))",
+                    "\u{FEFF}h",
+                ),
+                (
+                    "new TextDecoder('utf-16be').decode(new Uint8Array([0xFE, 0xFF, 0, 0x68, 0xD8, 0x3D]))",
+                    "h\u{FFFD}",
+                ),
+                // The same bytes are valid windows-1252 but not UTF-8
+                ("new TextDecoder('latin1').decode(new Uint8Array([0x63, 0x61, 0x66, 0xE9, 0x80]))", "café€"),
+                ("new TextDecoder().decode(new Uint8Array([0x63, 0x61, 0x66, 0xE9, 0x80]))", "caf\u{FFFD}"),
+            ];
+            for (js, expected) in cases {
+                let result: String = this.eval(js)?;
+                assert_eq!(expected, result, "{js}");
+            }
+
+            let result: String = this.eval(
+                r#"
+                try {
+                    new TextDecoder('utf-16le', { fatal: true }).decode(new Uint8Array([0x68]));
+                    'no error'
+                } catch (e) { `${e.name}: ${e.message}` }
+            "#,
+            )?;
+            assert_eq!("TypeError: The encoded data was not valid utf-16le", result);
+
+            // A surrogate pair split across streamed chunks
+            let result: String = this.eval(
+                r#"
+                let decoder = new TextDecoder('utf-16le');
+                [
+                    decoder.decode(new Uint8Array([0x68, 0, 0x3D]), { stream: true }),
+                    decoder.decode(new Uint8Array([0xD8, 0]), { stream: true }),
+                    decoder.decode(new Uint8Array([0xDE])),
+                ].join('|');
+            "#,
+            )?;
+            assert_eq!("h||😀", result);
+
+            let result: String = this.eval(
+                "try { new TextDecoder('gbk'); 'no error' } catch (e) { e.name }",
+            )?;
+            assert_eq!("RangeError", result);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
 }
@@ -1,43 +1,141 @@
 (function () {
-    const __javy_decodeUtf8BufferToString = globalThis.__javy_decodeUtf8BufferToString;
+    const __javy_decodeBufferToString = globalThis.__javy_decodeBufferToString;
     const __javy_encodeStringToUtf8Buffer = globalThis.__javy_encodeStringToUtf8Buffer;
+    const __javy_encodeStringIntoUtf8Buffer = globalThis.__javy_encodeStringIntoUtf8Buffer;
+
+    // Labels of the supported encodings, keyed by encoding name.
+    const encodingLabels = {
+        "utf-8": ["unicode-1-1-utf-8", "unicode11utf8", "unicode20utf8", "utf-8", "utf8", "x-unicode20utf8"],
+        "utf-16le": ["csunicode", "iso-10646-ucs-2", "ucs-2", "unicode", "unicodefeff", "utf-16", "utf-16le"],
+        "utf-16be": ["unicodefffe", "utf-16be"],
+        "windows-1252": [
+            "ansi_x3.4-1968", "ascii", "cp1252", "cp819", "csisolatin1", "ibm819", "iso-8859-1", "iso-ir-100",
+            "iso8859-1", "iso88591", "iso_8859-1", "iso_8859-1:1987", "l1", "latin1", "us-ascii", "windows-1252",
+            "x-cp1252",
+        ],
+    };
+
+    function encodingForLabel(label) {
+        label = String(label).replace(/^[\t\n\f\r ]+|[\t\n\f\r ]+$/g, "").toLowerCase();
+        for (const [encoding, labels] of Object.entries(encodingLabels)) {
+            if (labels.includes(label)) {
+                return encoding;
+            }
+        }
+        // Not spec-compliant behaviour for labels of unsupported encodings
+        throw new RangeError(`The encoding label provided ('${label}') is invalid or not supported.`);
+    }
 
     class TextDecoder {
         constructor(label = "utf-8", options = {}) {
-            label = label.trim().toLowerCase();
-            const acceptedLabels = ["utf-8", "utf8", "unicode-1-1-utf-8", "unicode11utf8", "unicode20utf8", "x-unicode20utf8"];
-            if (!acceptedLabels.includes(label)) {
-                // Not spec-compliant behaviour
-                throw new RangeError("The encoding label provided must be utf-8");
-            }
+            const encoding = encodingForLabel(label);
             Object.defineProperties(this, {
-                encoding: { value: "utf-8", enumerable: true, writable: false },
+                encoding: { value: encoding, enumerable: true, writable: false },
                 fatal: { value: !!options.fatal, enumerable: true, writable: false },
                 ignoreBOM: { value: !!options.ignoreBOM, enumerable: true, writable: false },
+                // Bytes of an incomplete sequence at the end of the last
+                // streamed chunk.
+                _pending: { value: null, enumerable: false, writable: true },
+                // Whether the start of the current stream has been decoded.
+                _started: { value: false, enumerable: false, writable: true },
             })
         }
 
         decode(input, options = {}) {
+            let bytes;
             if (input === undefined) {
-                return "";
+                bytes = new Uint8Array(0);
+            } else if (ArrayBuffer.isView(input)) {
+                bytes = new Uint8Array(input.buffer, input.byteOffset, input.byteLength);
+            } else if (input instanceof ArrayBuffer) {
+                bytes = new Uint8Array(input);
+            } else {
+                throw new TypeError("The provided value is not of type '(ArrayBuffer or ArrayBufferView)'");
             }
 
-            if (options.stream) {
-                throw new Error("Streaming decode is not supported");
+            if (this._pending !== null) {
+                const joined = new Uint8Array(this._pending.length + bytes.length);
+                joined.set(this._pending);
+                joined.set(bytes, this._pending.length);
+                bytes = joined;
+                this._pending = null;
             }
 
-            // backing buffer would not have byteOffset and may have different byteLength
-            let byteOffset = input.byteOffset || 0;
-            let byteLength = input.byteLength;
-            if (ArrayBuffer.isView(input)) {
-                input = input.buffer;
+            const stream = !!options.stream;
+            if (stream) {
+                const incomplete = incompleteSequenceLength(this.encoding, bytes);
+                if (incomplete > 0) {
+                    this._pending = bytes.slice(bytes.length - incomplete);
+                    bytes = bytes.subarray(0, bytes.length - incomplete);
+                }
             }
 
-            if (!(input instanceof ArrayBuffer)) {
-                throw new TypeError("The provided value is not of type '(ArrayBuffer or ArrayBufferView)'");
+            // Only a byte order mark at the start of the stream is stripped.
+            const ignoreBOM = this.ignoreBOM || this._started;
+            this._started = stream && (this._started || bytes.length > 0);
+            return __javy_decodeBufferToString(bytes.buffer, bytes.byteOffset, bytes.byteLength, this.encoding, this.fatal, ignoreBOM);
+        }
+    }
+
+    // Returns the number of bytes at the end of `bytes` that start a sequence
+    // of the encoding without completing it.
+    function incompleteSequenceLength(encoding, bytes) {
+        switch (encoding) {
+            case "utf-8": return incompleteUtf8SequenceLength(bytes);
+            case "utf-16le": return incompleteUtf16SequenceLength(bytes, false);
+            case "utf-16be": return incompleteUtf16SequenceLength(bytes, true);
+            default: return 0;
+        }
+    }
+
+    // A trailing odd byte or a trailing high surrogate.
+    function incompleteUtf16SequenceLength(bytes, bigEndian) {
+        const odd = bytes.length % 2;
+        const end = bytes.length - odd;
+        if (end >= 2) {
+            const high = bigEndian ? bytes[end - 2] : bytes[end - 1];
+            if (high >= 0xD8 && high <= 0xDB) {
+                return odd + 2;
+            }
+        }
+        return odd;
+    }
+
+    function incompleteUtf8SequenceLength(bytes) {
+        for (let i = 1; i <= Math.min(3, bytes.length); i++) {
+            const byte = bytes[bytes.length - i];
+            if ((byte & 0xC0) === 0x80) {
+                // Continuation byte, keep looking for the leading byte.
+                continue;
             }
+            let length = 1;
+            if (byte >= 0xC2 && byte <= 0xDF) {
+                length = 2;
+            } else if (byte >= 0xE0 && byte <= 0xEF) {
+                length = 3;
+            } else if (byte >= 0xF0 && byte <= 0xF4) {
+                length = 4;
+            }
+            if (length <= i) {
+                return 0;
+            }
+            // A second byte outside of the range allowed after this leading
+            // byte makes the sequence invalid rather than incomplete.
+            if (i > 1 && !isValidSecondByte(byte, bytes[bytes.length - i + 1])) {
+                return 0;
+            }
+            return i;
+        }
+        return 0;
+    }
 
-            return __javy_decodeUtf8BufferToString(input, byteOffset, byteLength, this.fatal, this.ignoreBOM);
+    function isValidSecondByte(lead, byte) {
+        switch (lead) {
+            case 0xE0: return byte >= 0xA0;
+            case 0xED: return byte <= 0x9F;
+            case 0xF0: return byte >= 0x90;
+            case 0xF4: return byte <= 0x8F;
+            default: return true;
         }
     }
 
@@ -54,13 +152,18 @@
         }
 
         encodeInto(source, destination) {
-            throw new Error("encodeInto is not supported");
+            if (!(destination instanceof Uint8Array)) {
+                throw new TypeError("The provided value is not of type 'Uint8Array'");
+            }
+            source = String(source);
+            return __javy_encodeStringIntoUtf8Buffer(source, destination.buffer, destination.byteOffset, destination.byteLength);
         }
     }
 
     globalThis.TextDecoder = TextDecoder;
     globalThis.TextEncoder = TextEncoder;
 
-    Reflect.deleteProperty(globalThis, "__javy_decodeUtf8BufferToString");
+    Reflect.deleteProperty(globalThis, "__javy_decodeBufferToString");
     Reflect.deleteProperty(globalThis, "__javy_encodeStringToUtf8Buffer");
+    Reflect.deleteProperty(globalThis, "__javy_encodeStringIntoUtf8Buffer");
 })();
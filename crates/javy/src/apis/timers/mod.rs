@@ -5,19 +5,25 @@ use queue::{TimerCallback, TimerQueue};
 
 use crate::{
     hold, hold_and_release,
-    quickjs::{prelude::MutFn, Ctx, Function, Value},
-    to_js_error, val_to_string, Args,
+    quickjs::{prelude::MutFn, Array, Ctx, Function, Value},
+    to_js_error, val_to_string, Args, TimerBatch,
 };
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
 
 pub struct TimersRuntime {
     queue: Arc<Mutex<TimerQueue>>,
+    /// Maximum number of extra arguments forwarded to timer callbacks.
+    max_args: usize,
+    /// How many expired timers are fired per call to `process_timers`.
+    batch: TimerBatch,
 }
 
 impl TimersRuntime {
-    pub fn new() -> Self {
+    pub fn new(max_args: usize, batch: TimerBatch) -> Self {
         Self {
             queue: Arc::new(Mutex::new(TimerQueue::new())),
+            max_args,
+            batch,
         }
     }
 
@@ -26,9 +32,10 @@ impl TimersRuntime {
         let globals = this.globals();
 
         let queue = self.queue.clone();
+        let max_args = self.max_args;
         globals.set("setTimeout", Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            set_timeout(&queue, hold!(cx.clone(), args))
+            set_timeout(&queue, max_args, hold!(cx.clone(), args))
                 .map_err(|e| to_js_error(cx, e))
         }))?)?;
 
@@ -40,9 +47,10 @@ impl TimersRuntime {
         }))?)?;
 
         let queue = self.queue.clone();
+        let max_args = self.max_args;
         globals.set("setInterval", Function::new(this.clone(), MutFn::new(move |cx, args| {
             let (cx, args) = hold_and_release!(cx, args);
-            set_interval(&queue, hold!(cx.clone(), args))
+            set_interval(&queue, max_args, hold!(cx.clone(), args))
                 .map_err(|e| to_js_error(cx, e))
         }))?)?;
 
@@ -59,7 +67,10 @@ impl TimersRuntime {
     /// Process expired timers - should be called by the event loop
     pub fn process_timers(&self, ctx: Ctx<'_>) -> Result<()> {
         let mut queue = self.queue.lock().unwrap();
-        let expired_timers = queue.get_expired_timers();
+        let expired_timers = match self.batch {
+            TimerBatch::All => queue.get_expired_timers(),
+            TimerBatch::One => queue.pop_expired_timer().into_iter().collect(),
+        };
 
         // Reschedule intervals before releasing the lock
         for timer in &expired_timers {
@@ -79,13 +90,16 @@ impl TimersRuntime {
                     }
                 },
                 TimerCallback::Function => {
-                    let code = format!("globalThis.__timer_callback_{}()", timer.id);
+                    let code = format!(
+                        "globalThis.__timer_callback_{id}(...(globalThis.__timer_args_{id} || []))",
+                        id = timer.id
+                    );
                     if let Err(e) = ctx.eval::<(), _>(code.as_str()) {
                         eprintln!("Timer callback error: {}", e);
                     }
                     // remove the callback from the global object, unless it's an interval
                     if timer.interval_ms.is_none() {
-                        ctx.globals().remove(format!("__timer_callback_{}", timer.id))?;
+                        remove_callback(&ctx, timer.id)?;
                     }
                 },
             };
@@ -99,15 +113,64 @@ impl TimersRuntime {
         let queue = self.queue.lock().unwrap();
         queue.has_pending_timers()
     }
+
+    /// The number of pending timers, including intervals
+    pub fn pending_timers(&self) -> usize {
+        self.queue.lock().unwrap().len()
+    }
+
+    /// Cancel all pending timers, including intervals
+    pub fn clear_timers(&self, ctx: Ctx<'_>) -> Result<()> {
+        let timers = self.queue.lock().unwrap().clear();
+        for timer in timers {
+            if let TimerCallback::Function = timer.callback {
+                remove_callback(&ctx, timer.id)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Persist a function callback, and any extra arguments to forward to it, on
+/// the global object.
+fn store_callback<'js>(ctx: &Ctx<'js>, timer_id: u32, args: &[Value<'js>]) -> Result<()> {
+    ctx.globals().set(format!("__timer_callback_{}", timer_id), args[0].clone())?;
+
+    if args.len() > 2 {
+        let extra_args = Array::new(ctx.clone())?;
+        for (i, arg) in args[2..].iter().enumerate() {
+            extra_args.set(i, arg.clone())?;
+        }
+        ctx.globals().set(format!("__timer_args_{}", timer_id), extra_args)?;
+    }
+
+    Ok(())
+}
+
+/// Remove a function callback and its extra arguments from the global object.
+fn remove_callback(ctx: &Ctx<'_>, timer_id: u32) -> Result<()> {
+    ctx.globals().remove(format!("__timer_callback_{}", timer_id))?;
+    ctx.globals().remove(format!("__timer_args_{}", timer_id))?;
+    Ok(())
+}
+
+/// Ensure the number of extra arguments to forward doesn't exceed `max_args`.
+fn check_extra_args(name: &str, args: &[Value<'_>], max_args: usize) -> Result<()> {
+    let extra_args = args.len().saturating_sub(2);
+    if extra_args > max_args {
+        bail!("{name} accepts at most {max_args} extra arguments, got {extra_args}");
+    }
+    Ok(())
 }
 
-fn set_timeout<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Result<Value<'js>> {
+fn set_timeout<'js>(queue: &Arc<Mutex<TimerQueue>>, max_args: usize, args: Args<'js>) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
     if args.is_empty() {
         return Err(anyhow!("setTimeout requires at least 1 argument"));
     }
+    check_extra_args("setTimeout", &args, max_args)?;
 
     let callback_str = val_to_string(&ctx, args[0].clone())?;
     let callback = if args[0].is_function() {
@@ -129,7 +192,7 @@ fn set_timeout<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Result<V
     drop(queue);
 
     if args[0].is_function() {
-        ctx.globals().set(format!("__timer_callback_{}", timer_id), args[0].clone())?;
+        store_callback(&ctx, timer_id, &args)?;
     }
 
     Ok(Value::new_int(ctx, timer_id as i32))
@@ -150,19 +213,20 @@ fn clear_timeout<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Result
     drop(queue);
 
     if removed {
-        ctx.globals().remove(format!("__timer_callback_{}", timer_id))?;
+        remove_callback(&ctx, timer_id)?;
     }
 
     Ok(Value::new_undefined(ctx))
 }
 
-fn set_interval<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Result<Value<'js>> {
+fn set_interval<'js>(queue: &Arc<Mutex<TimerQueue>>, max_args: usize, args: Args<'js>) -> Result<Value<'js>> {
     let (ctx, args) = args.release();
     let args = args.into_inner();
 
     if args.is_empty() {
         return Err(anyhow!("setInterval requires at least 1 argument"));
     }
+    check_extra_args("setInterval", &args, max_args)?;
 
     let callback_str = val_to_string(&ctx, args[0].clone())?;
     let callback = if args[0].is_function() {
@@ -184,7 +248,7 @@ fn set_interval<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Result<
     drop(queue);
 
     if args[0].is_function() {
-        ctx.globals().set(format!("__timer_callback_{}", timer_id), args[0].clone())?;
+        store_callback(&ctx, timer_id, &args)?;
     }
 
     Ok(Value::new_int(ctx, timer_id as i32))
@@ -205,7 +269,7 @@ fn clear_interval<'js>(queue: &Arc<Mutex<TimerQueue>>, args: Args<'js>) -> Resul
     drop(queue);
 
     if removed {
-        ctx.globals().remove(format!("__timer_callback_{}", timer_id))?;
+        remove_callback(&ctx, timer_id)?;
     }
 
     Ok(Value::new_undefined(ctx))
@@ -740,4 +804,136 @@ mod tests {
         })?;
         Ok(())
     }
+
+    #[test]
+    fn test_function_callback_extra_arguments() -> Result<()> {
+        let mut config = Config::default();
+        config.timers(true).timer_max_args(2);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("
+                globalThis.result = '';
+                setTimeout((a, b) => { globalThis.result = a + b; }, 0, 'forwarded', '!');
+            ")?;
+            Ok::<_, Error>(())
+        })?;
+
+        runtime.resolve_pending_jobs()?;
+
+        runtime.context().with(|cx| {
+            assert_eq!("forwarded!", cx.eval::<String, _>("globalThis.result")?);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_extra_arguments_beyond_cap_throw() -> Result<()> {
+        let mut config = Config::default();
+        config.timers(true).timer_max_args(2);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|cx| {
+            let result: String = cx.eval("
+                let message = '';
+                try {
+                    setTimeout(() => {}, 0, 1, 2, 3);
+                } catch (e) {
+                    message = e.message;
+                }
+                message
+            ")?;
+            assert_eq!("setTimeout accepts at most 2 extra arguments, got 3", result);
+
+            let result: String = cx.eval("
+                let intervalMessage = '';
+                try {
+                    setInterval(() => {}, 0, ...new Array(10));
+                } catch (e) {
+                    intervalMessage = e.message;
+                }
+                intervalMessage
+            ")?;
+            assert_eq!("setInterval accepts at most 2 extra arguments, got 10", result);
+            Ok::<_, Error>(())
+        })?;
+
+        assert!(!runtime.has_pending_timers());
+        Ok(())
+    }
+
+    fn timer_batch_order(batch: TimerBatch) -> Result<String> {
+        let mut config = Config::default();
+        config.timers(true).timer_batch(batch);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("
+                globalThis.order = [];
+                setTimeout(() => {
+                    order.push('t1');
+                    Promise.resolve().then(() => order.push('m1'));
+                }, 0);
+                setTimeout(() => {
+                    order.push('t2');
+                    Promise.resolve().then(() => order.push('m2'));
+                }, 1);
+            ")?;
+            Ok::<_, Error>(())
+        })?;
+
+        // Wait for both timers to expire
+        std::thread::sleep(std::time::Duration::from_millis(5));
+        while runtime.has_pending_jobs() {
+            runtime.resolve_pending_jobs()?;
+        }
+
+        runtime.context().with(|cx| {
+            let order: String = cx.eval("order.join(',')")?;
+            Ok(order)
+        })
+    }
+
+    #[test]
+    fn test_timer_batch_all() -> Result<()> {
+        // All expired timers fire before any microtask runs
+        assert_eq!("t1,t2,m1,m2", timer_batch_order(TimerBatch::All)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_timer_batch_one() -> Result<()> {
+        // Microtasks scheduled by a timer run before the next timer fires
+        assert_eq!("t1,m1,t2,m2", timer_batch_order(TimerBatch::One)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_clear_timers() -> Result<()> {
+        let mut config = Config::default();
+        config.timers(true);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("
+                setTimeout(() => {}, 1000, 'arg');
+                setInterval(() => {}, 1);
+                setTimeout('1 + 1', 1000);
+            ")?;
+            Ok::<_, Error>(())
+        })?;
+        assert!(runtime.has_pending_timers());
+
+        runtime.clear_timers()?;
+        assert!(!runtime.has_pending_timers());
+
+        // The stored callbacks and arguments are released
+        runtime.context().with(|cx| {
+            let leftovers: usize = cx.eval("Object.keys(globalThis).filter((key) => key.startsWith('__timer_')).length")?;
+            assert_eq!(0, leftovers);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
 }
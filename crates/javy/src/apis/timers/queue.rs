@@ -100,10 +100,29 @@ impl TimerQueue {
         expired
     }
 
+    pub fn pop_expired_timer(&mut self) -> Option<Timer> {
+        let now = Self::now();
+        if self.timers.peek()?.fire_time <= now {
+            self.timers.pop()
+        } else {
+            None
+        }
+    }
+
     pub fn has_pending_timers(&self) -> bool {
         !self.timers.is_empty()
     }
 
+    /// The number of pending timers, including intervals.
+    pub fn len(&self) -> usize {
+        self.timers.len()
+    }
+
+    /// Removes all the timers, returning them.
+    pub fn clear(&mut self) -> Vec<Timer> {
+        self.timers.drain().collect()
+    }
+
     fn now() -> u64 {
         SystemTime::now()
             .duration_since(UNIX_EPOCH)
@@ -134,11 +153,13 @@ mod tests {
         assert_eq!(id3, 3);
 
         assert!(queue.has_pending_timers());
+        assert_eq!(3, queue.len());
 
         // Remove a timer
         assert!(queue.remove_timer(id2));
         assert!(!queue.remove_timer(999)); // Non-existent timer
 
         assert!(queue.has_pending_timers());
+        assert_eq!(2, queue.len());
     }
 }
@@ -0,0 +1,118 @@
+use anyhow::{anyhow, bail, Error, Result};
+
+use crate::{
+    hold, hold_and_release, messagepack,
+    quickjs::{Ctx, Function, Object, TypedArray, Value},
+    to_js_error, Args,
+};
+
+/// Register `Javy.msgpackToJson` and `Javy.jsonToMsgpack` functions on the
+/// global object.
+pub(crate) fn register(this: Ctx<'_>) -> Result<()> {
+    let globals = this.globals();
+    if globals.get::<_, Object>("Javy").is_err() {
+        globals.set("Javy", Object::new(this.clone())?)?
+    }
+
+    globals.set(
+        "__javy_msgpack_to_json",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            transcode(hold!(cx.clone(), args), messagepack::to_json).map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    globals.set(
+        "__javy_json_to_msgpack",
+        Function::new(this.clone(), |cx, args| {
+            let (cx, args) = hold_and_release!(cx, args);
+            transcode(hold!(cx.clone(), args), messagepack::from_json)
+                .map_err(|e| to_js_error(cx, e))
+        }),
+    )?;
+
+    this.eval::<(), _>(include_str!("transcode.js"))?;
+    Ok::<_, Error>(())
+}
+
+/// Transcode the bytes in the given buffer range with `f`, returning the
+/// result as a `Uint8Array`.
+fn transcode<'js>(args: Args<'js>, f: fn(&[u8]) -> Result<Vec<u8>>) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    let [buffer, offset, length, ..] = &args[..] else {
+        bail!(
+            "Expected 3 parameters: the ArrayBuffer, the byteOffset and the byteLength. Got: {} parameters.",
+            args.len()
+        );
+    };
+
+    let buffer = buffer
+        .as_object()
+        .ok_or_else(|| anyhow!("buffer must be an object"))?
+        .as_array_buffer()
+        .ok_or_else(|| anyhow!("buffer must be an ArrayBuffer"))?
+        .as_bytes()
+        .ok_or_else(|| anyhow!("Couldn't retrive &[u8] from buffer"))?;
+    let offset = offset
+        .as_number()
+        .ok_or_else(|| anyhow!("offset must be a number"))? as usize;
+    let length = length
+        .as_number()
+        .ok_or_else(|| anyhow!("length must be a number"))? as usize;
+    let bytes = buffer
+        .get(offset..offset + length)
+        .ok_or_else(|| anyhow!("Provided offset and length is not valid for provided buffer"))?;
+
+    let output = f(bytes)?;
+    Ok(TypedArray::new(cx, output)?.as_value().to_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{quickjs::Value, val_to_string, Config, Runtime};
+    use anyhow::{Error, Result};
+
+    #[test]
+    fn test_round_trip() -> Result<()> {
+        let mut config = Config::default();
+        config.text_encoding(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|cx| {
+            let result: Value = cx.eval(
+                r#"
+                const json = '{"name":"javy","tags":["wasm","js"],"nested":{"n":42,"f":1.5,"b":true,"z":null}}';
+                const encoded = new TextEncoder().encode(json);
+                const msgpack = Javy.jsonToMsgpack(encoded);
+                const decoded = new TextDecoder().decode(Javy.msgpackToJson(msgpack));
+                JSON.stringify(JSON.parse(decoded)) === JSON.stringify(JSON.parse(json))
+                "#,
+            )?;
+            assert_eq!(Some(true), result.as_bool());
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_input_throws() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|cx| {
+            let result: Value = cx.eval(
+                "try { Javy.jsonToMsgpack(new Uint8Array([0x7b])); 'no error' } catch (e) { 'error' }",
+            )?;
+            assert_eq!("error", val_to_string(&cx, result)?);
+
+            let result: Value = cx.eval(
+                "try { Javy.msgpackToJson(new Uint8Array([0xc1])); 'no error' } catch (e) { 'error' }",
+            )?;
+            assert_eq!("error", val_to_string(&cx, result)?);
+
+            let result: Value = cx.eval(
+                "try { Javy.msgpackToJson('not bytes'); 'no error' } catch (e) { e.name }",
+            )?;
+            assert_eq!("TypeError", val_to_string(&cx, result)?);
+            Ok::<_, Error>(())
+        })?;
+        Ok(())
+    }
+}
@@ -0,0 +1,27 @@
+(function () {
+  const __javy_msgpack_to_json = globalThis.__javy_msgpack_to_json;
+  const __javy_json_to_msgpack = globalThis.__javy_json_to_msgpack;
+
+  function toUint8Array(bytes, name) {
+    if (bytes instanceof ArrayBuffer) {
+      return new Uint8Array(bytes);
+    }
+    if (bytes instanceof Uint8Array) {
+      return bytes;
+    }
+    throw TypeError(`${name} expects an Uint8Array or an ArrayBuffer`);
+  }
+
+  globalThis.Javy.msgpackToJson = function (bytes) {
+    const data = toUint8Array(bytes, "Javy.msgpackToJson");
+    return __javy_msgpack_to_json(data.buffer, data.byteOffset, data.byteLength);
+  };
+
+  globalThis.Javy.jsonToMsgpack = function (bytes) {
+    const data = toUint8Array(bytes, "Javy.jsonToMsgpack");
+    return __javy_json_to_msgpack(data.buffer, data.byteOffset, data.byteLength);
+  };
+
+  Reflect.deleteProperty(globalThis, "__javy_msgpack_to_json");
+  Reflect.deleteProperty(globalThis, "__javy_json_to_msgpack");
+})();
@@ -1,8 +1,72 @@
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    rc::Rc,
+    time::Duration,
+};
+
+use crate::quickjs::{Ctx, Value};
 use anyhow::{bail, Result};
 use bitflags::bitflags;
 
+/// The `console` function a [`ConsoleSink`] is called from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConsoleLevel {
+    /// `console.log`
+    Log,
+    /// `console.warn`
+    Warn,
+    /// `console.error`
+    Error,
+}
+
+/// A host callback receiving the raw arguments of `console.log`,
+/// `console.warn` and `console.error` calls.
+pub type ConsoleSink = Rc<dyn for<'js> Fn(ConsoleLevel, &Ctx<'js>, &[Value<'js>]) -> Result<()>>;
+
+/// The code of a module served by a [`ModuleLoader`].
+#[derive(Debug, Clone)]
+pub enum ModuleSource {
+    /// JavaScript source code, compiled when the module is imported.
+    Code(String),
+    /// Bytecode compiled by
+    /// [`Runtime::compile_to_bytecode`](crate::Runtime::compile_to_bytecode)
+    /// for a module of the same name.
+    Bytecode(Vec<u8>),
+}
+
+/// A host callback returning the code of the module with the given resolved
+/// name, or `None` if there's no such module.
+pub type ModuleLoader = Rc<dyn Fn(&str) -> Option<ModuleSource>>;
+
+/// How many expired timers the event loop fires per iteration.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TimerBatch {
+    /// Fire all expired timers, then run the pending microtasks.
+    #[default]
+    All,
+    /// Fire a single expired timer, then run the pending microtasks, giving
+    /// microtasks scheduled by a timer a chance to run before the next timer
+    /// fires. This matches the HTML event loop processing model.
+    One,
+}
+
+/// How the sources compiled by
+/// [`Runtime::compile_to_bytecode`](crate::Runtime::compile_to_bytecode) are
+/// parsed.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceType {
+    /// An ES module, where a top-level `return` is a syntax error.
+    #[default]
+    Module,
+    /// A script wrapped in a function like a CommonJS module, with `module`
+    /// and `exports` in scope, so that a top-level `return` ends it. Scripts
+    /// can't use static `import` or `export` declarations.
+    Script,
+}
+
 bitflags! {
     /// Flags to represent available JavaScript features.
+    #[derive(Clone, Copy)]
     pub(crate) struct JSIntrinsics: u32  {
         const DATE = 1;
         const EVAL = 1 << 1;
@@ -20,6 +84,16 @@ bitflags! {
         const BIGNUM_EXTENSION = 1 << 13;
         const TEXT_ENCODING = 1 << 14;
         const TIMERS = 1 << 15;
+        const ERROR_CAPTURE_STACK_TRACE = 1 << 16;
+        const REPORT_ERROR = 1 << 17;
+        const STRUCTURED_CLONE = 1 << 18;
+        const CRYPTO = 1 << 19;
+        const NAVIGATOR = 1 << 20;
+        const ABORT_CONTROLLER = 1 << 21;
+        const EVENTS = 1 << 22;
+        const FORM_DATA = 1 << 23;
+        const STREAMS = 1 << 24;
+        const COMPRESSION_STREAMS = 1 << 25;
     }
 }
 
@@ -36,8 +110,10 @@ bitflags! {
     /// users to extend the runtime with non-standard functionality directly
     /// from the CLI, at this point many, if not most, of these APIs will be
     /// moved out.
+    #[derive(Clone, Copy)]
     pub(crate) struct JavyIntrinsics: u32 {
         const STREAM_IO = 1;
+        const MEMORY_USAGE = 1 << 1;
     }
 }
 
@@ -45,6 +121,7 @@ bitflags! {
 ///
 /// These are the global configuration options to create a [`Runtime`](crate::Runtime),
 /// and customize its behavior.
+#[derive(Clone)]
 pub struct Config {
     /// JavaScript features.
     pub(crate) intrinsics: JSIntrinsics,
@@ -67,6 +144,61 @@ pub struct Config {
     /// The limit on the max size of stack the runtime will use. Default is
     /// 256 * 1024.
     pub(crate) max_stack_size: usize,
+    /// The number of times the QuickJS interrupt handler may be invoked
+    /// before execution is interrupted. Default is `None`, meaning
+    /// execution is never interrupted.
+    pub(crate) interrupt_after: Option<u64>,
+    /// The maximum duration of a run, after which execution is interrupted.
+    /// Default is `None`, meaning runs aren't bounded in time.
+    pub(crate) total_deadline: Option<Duration>,
+    /// How many invocations of the QuickJS interrupt handler happen between
+    /// checks of the deadline and samples of the memory usage. Default is 1.
+    pub(crate) interrupt_check_interval: u32,
+    /// Whether the runtime tracks the peak of the memory it uses.
+    pub(crate) track_peak_memory: bool,
+    /// The maximum number of extra arguments that `setTimeout` and
+    /// `setInterval` forward to their callbacks. Default is 32.
+    pub(crate) timer_max_args: usize,
+    /// How many expired timers are fired per event loop iteration. Default
+    /// is [`TimerBatch::All`].
+    pub(crate) timer_batch: TimerBatch,
+    /// The nesting depth up to which `console` functions show objects.
+    /// Default is 2.
+    pub(crate) console_depth: usize,
+    /// The maximum nesting depth of values inspected by `console` functions.
+    /// Default is 1000.
+    pub(crate) console_inspect_max_depth: usize,
+    /// Whether to detect the type of blobs and files created without one
+    /// from the magic bytes of their content.
+    pub(crate) blob_sniff_type: bool,
+    /// Whether the blob helpers throw for unknown blob IDs rather than
+    /// handling them as empty files.
+    pub(crate) strict_blob: bool,
+    /// The maximum number of bytes held by the blobs and files created by
+    /// JavaScript code. Default is unlimited.
+    pub(crate) max_blob_storage_bytes: usize,
+    /// Whether the handles releasing the storage of blobs are held in a
+    /// `WeakMap` keyed by the blobs rather than in hidden properties.
+    pub(crate) blob_weak_map_handles: bool,
+    /// Whether regular expressions constructed from the same string pattern
+    /// and flags are reused.
+    pub(crate) regexp_cache: bool,
+    /// The callback receiving the arguments of `console` calls instead of
+    /// the output streams, if any.
+    pub(crate) console_sink: Option<ConsoleSink>,
+    /// The callback serving the modules imported by JavaScript code, if any.
+    pub(crate) module_loader: Option<ModuleLoader>,
+    /// The `navigator.userAgent` string. Default is `Javy/<version>`.
+    pub(crate) navigator_user_agent: String,
+    /// Whether a warning is written to stderr the first time each deprecated
+    /// API is used.
+    pub(crate) deprecation_warnings: bool,
+    /// Whether each invocation runs in a fresh context.
+    pub(crate) isolate_invocations: bool,
+    /// The seed of the generator backing `Math.random`, if it's seeded.
+    pub(crate) random_seed: Option<u64>,
+    /// How compiled sources are parsed. Default is [`SourceType::Module`].
+    pub(crate) source_type: SourceType,
 }
 
 impl Default for Config {
@@ -74,6 +206,16 @@ impl Default for Config {
     fn default() -> Self {
         let mut intrinsics = JSIntrinsics::all();
         intrinsics.set(JSIntrinsics::TEXT_ENCODING, false);
+        intrinsics.set(JSIntrinsics::ERROR_CAPTURE_STACK_TRACE, false);
+        intrinsics.set(JSIntrinsics::REPORT_ERROR, false);
+        intrinsics.set(JSIntrinsics::STRUCTURED_CLONE, false);
+        intrinsics.set(JSIntrinsics::CRYPTO, false);
+        intrinsics.set(JSIntrinsics::NAVIGATOR, false);
+        intrinsics.set(JSIntrinsics::ABORT_CONTROLLER, false);
+        intrinsics.set(JSIntrinsics::EVENTS, false);
+        intrinsics.set(JSIntrinsics::FORM_DATA, false);
+        intrinsics.set(JSIntrinsics::STREAMS, false);
+        intrinsics.set(JSIntrinsics::COMPRESSION_STREAMS, false);
         Self {
             intrinsics,
             javy_intrinsics: JavyIntrinsics::empty(),
@@ -82,6 +224,26 @@ impl Default for Config {
             gc_threshold: usize::MAX,
             memory_limit: usize::MAX,
             max_stack_size: 256 * 1024, // from rquickjs
+            interrupt_after: None,
+            total_deadline: None,
+            interrupt_check_interval: 1,
+            track_peak_memory: false,
+            timer_max_args: 32,
+            timer_batch: TimerBatch::All,
+            console_depth: 2,
+            console_inspect_max_depth: 1000,
+            blob_sniff_type: false,
+            strict_blob: true,
+            max_blob_storage_bytes: usize::MAX,
+            blob_weak_map_handles: false,
+            regexp_cache: false,
+            console_sink: None,
+            module_loader: None,
+            navigator_user_agent: format!("Javy/{}", env!("CARGO_PKG_VERSION")),
+            deprecation_warnings: false,
+            isolate_invocations: false,
+            random_seed: None,
+            source_type: SourceType::Module,
         }
     }
 }
@@ -181,6 +343,238 @@ impl Config {
         self
     }
 
+    /// The maximum number of extra arguments that `setTimeout` and
+    /// `setInterval` forward to their callbacks. Calls with more arguments
+    /// throw. Default is 32.
+    pub fn timer_max_args(&mut self, max_args: usize) -> &mut Self {
+        self.timer_max_args = max_args;
+        self
+    }
+
+    /// How many expired timers are fired each time pending jobs are
+    /// resolved. Default is [`TimerBatch::All`].
+    pub fn timer_batch(&mut self, batch: TimerBatch) -> &mut Self {
+        self.timer_batch = batch;
+        self
+    }
+
+    /// Whether blobs and files created without a type get a type detected
+    /// from the magic bytes of their content, e.g. `image/png` for PNG images.
+    /// Disabled by default.
+    pub fn blob_sniff_type(&mut self, enable: bool) -> &mut Self {
+        self.blob_sniff_type = enable;
+        self
+    }
+
+    /// Whether reading a blob or file with an unknown ID, for example one
+    /// forged with `Object.create(Blob.prototype)`, throws an `Invalid blob
+    /// ID` error. Otherwise the blob is read as an empty file, `size` is 0,
+    /// `text()` resolves to `""` and so on.
+    /// Enabled by default.
+    pub fn strict_blob(&mut self, enable: bool) -> &mut Self {
+        self.strict_blob = enable;
+        self
+    }
+
+    /// The maximum number of bytes held by the blobs and files created by
+    /// JavaScript code. The bytes of blobs live outside of the QuickJS heap,
+    /// so they don't count towards [`Config::memory_limit`].
+    ///
+    /// Creating a `Blob` or `File` that would exceed the limit throws a
+    /// `RangeError`. Slices share the bytes of the blob they're sliced from
+    /// and don't count towards the limit. The bytes are released once all the
+    /// blobs referencing them are garbage collected.
+    /// Default is unlimited.
+    pub fn max_blob_storage_bytes(&mut self, max_bytes: usize) -> &mut Self {
+        self.max_blob_storage_bytes = max_bytes;
+        self
+    }
+
+    /// Whether the handles releasing the storage of blobs and files once they
+    /// are garbage collected are held in a private `WeakMap` keyed by the
+    /// blobs, rather than in a hidden `_blobHandle` property of the blobs.
+    ///
+    /// The storage is released in both cases once a blob is garbage
+    /// collected, but a `WeakMap` keeps the handles out of reach of
+    /// JavaScript code, which can't copy or delete them.
+    /// Disabled by default.
+    pub fn blob_weak_map_handles(&mut self, enable: bool) -> &mut Self {
+        self.blob_weak_map_handles = enable;
+        self
+    }
+
+    /// Whether constructing a `RegExp` from a string pattern, with
+    /// `new RegExp(pattern, flags)` or `RegExp(pattern, flags)`, reuses the
+    /// regular expression previously constructed from the same pattern and
+    /// flags instead of compiling it again.
+    ///
+    /// Regular expressions with the `g` or `y` flags depend on `lastIndex`
+    /// and are never reused. The `lastIndex` of a reused regular expression is
+    /// reset, but other properties set on it are shared. Requires the
+    /// `RegExp` and `Proxy` intrinsics.
+    /// Disabled by default.
+    pub fn regexp_cache(&mut self, enable: bool) -> &mut Self {
+        self.regexp_cache = enable;
+        self
+    }
+
+    /// Configures whether a V8 compatible `Error.captureStackTrace` will be
+    /// available.
+    /// Disabled by default.
+    pub fn error_capture_stack_trace(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics
+            .set(JSIntrinsics::ERROR_CAPTURE_STACK_TRACE, enable);
+        self
+    }
+
+    /// The nesting depth up to which `console` functions show the properties
+    /// of logged objects, similarly to the `depth` option of Node's
+    /// `util.inspect`. Deeper objects are shown as `[Object]` or `[Array]`.
+    /// Default is 2.
+    pub fn console_depth(&mut self, depth: usize) -> &mut Self {
+        self.console_depth = depth;
+        self
+    }
+
+    /// A callback receiving the level and the raw arguments of
+    /// `console.log`, `console.warn` and `console.error` calls, for example
+    /// to serialize logged objects as JSON in a structured logging pipeline.
+    /// When set, these calls are no longer formatted and written to the
+    /// output streams. Default is `None`.
+    pub fn console_sink(
+        &mut self,
+        sink: impl for<'js> Fn(ConsoleLevel, &Ctx<'js>, &[Value<'js>]) -> Result<()> + 'static,
+    ) -> &mut Self {
+        self.console_sink = Some(Rc::new(sink));
+        self
+    }
+
+    /// A callback serving the code of the modules imported by JavaScript
+    /// code, statically or with `import()`, by their resolved name.
+    ///
+    /// Specifiers starting with `./` or `../` are resolved against the name
+    /// of the importing module, other specifiers are used as is. Importing a
+    /// module the callback doesn't know about fails with an error naming the
+    /// specifier. Static imports are resolved when a module is compiled and
+    /// when its bytecode is loaded with [`load_module`](crate::load_module).
+    /// Default is `None`, meaning modules can't be imported.
+    pub fn module_loader(
+        &mut self,
+        loader: impl Fn(&str) -> Option<ModuleSource> + 'static,
+    ) -> &mut Self {
+        self.module_loader = Some(Rc::new(loader));
+        self
+    }
+
+    /// The maximum nesting depth of values inspected by `console` functions,
+    /// including the `%j` format specifier. Inspecting deeper values, for
+    /// example through getters returning a new object on every access, prints
+    /// `[Inspection error]` instead. This is a guard against runaway
+    /// recursion rather than a display setting like
+    /// [`Config::console_depth`]. Default is 1000.
+    pub fn console_inspect_max_depth(&mut self, max_depth: usize) -> &mut Self {
+        self.console_inspect_max_depth = max_depth;
+        self
+    }
+
+    /// Configures whether the global `reportError` function will be
+    /// available. Reported errors are written to the same stream as
+    /// `console.error`.
+    /// Disabled by default.
+    pub fn report_error(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::REPORT_ERROR, enable);
+        self
+    }
+
+    /// Configures whether the global `structuredClone` function will be
+    /// available.
+    /// Disabled by default.
+    pub fn structured_clone(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::STRUCTURED_CLONE, enable);
+        self
+    }
+
+    /// Configures whether the global `crypto` object, providing
+    /// `crypto.subtle.digest`, will be available.
+    /// Disabled by default.
+    pub fn crypto(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::CRYPTO, enable);
+        self
+    }
+
+    /// Configures whether a minimal global `navigator` object, with
+    /// `userAgent` and `hardwareConcurrency`, will be available.
+    /// Disabled by default.
+    pub fn navigator(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::NAVIGATOR, enable);
+        self
+    }
+
+    /// The `navigator.userAgent` string reported when the `navigator` object
+    /// is available. Default is `Javy/<version>`.
+    pub fn navigator_user_agent(&mut self, user_agent: impl Into<String>) -> &mut Self {
+        self.navigator_user_agent = user_agent.into();
+        self
+    }
+
+    /// Configures whether the global `AbortController` and `AbortSignal`
+    /// classes, including `AbortSignal.any`, will be available, independently
+    /// of [`Config::timers`]. `AbortSignal.timeout` throws a `TypeError` unless
+    /// timers are enabled as well.
+    /// Disabled by default.
+    pub fn abort_controller(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::ABORT_CONTROLLER, enable);
+        self
+    }
+
+    /// Configures whether the global `Event` and `EventTarget` classes will be
+    /// available.
+    /// Disabled by default.
+    pub fn events(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::EVENTS, enable);
+        self
+    }
+
+    /// Configures whether the global `FormData` class will be available.
+    /// `Blob` values are stored as `File` objects referencing the bytes of
+    /// the blob.
+    /// Disabled by default.
+    pub fn form_data(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::FORM_DATA, enable);
+        self
+    }
+
+    /// Configures whether the global `ReadableStream`, `WritableStream` and
+    /// `TransformStream` classes, with their default controllers, readers and
+    /// writers, will be available. Reads and writes are resolved through the
+    /// pending jobs of the runtime.
+    /// Disabled by default.
+    pub fn streams(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics.set(JSIntrinsics::STREAMS, enable);
+        self
+    }
+
+    /// Configures whether the global `CompressionStream` and
+    /// `DecompressionStream` classes, supporting the `gzip`, `deflate` and
+    /// `deflate-raw` formats, will be available. This setting requires
+    /// [`Config::streams`] to be enabled as well.
+    /// Disabled by default.
+    pub fn compression_streams(&mut self, enable: bool) -> &mut Self {
+        self.intrinsics
+            .set(JSIntrinsics::COMPRESSION_STREAMS, enable);
+        self
+    }
+
+    /// Whether a warning is written to stderr the first time a deprecated API,
+    /// such as `Event.prototype.srcElement`, is used. Each warning is written
+    /// once per [`Runtime`](crate::Runtime), however many times the API is
+    /// used.
+    /// Disabled by default.
+    pub fn deprecation_warnings(&mut self, enable: bool) -> &mut Self {
+        self.deprecation_warnings = enable;
+        self
+    }
+
     /// Whether the `Javy.IO` intrinsic will be available.
     /// Disabled by default.
     pub fn javy_stream_io(&mut self, enable: bool) -> &mut Self {
@@ -188,6 +582,15 @@ impl Config {
         self
     }
 
+    /// Whether the `Javy.memoryUsage` function, returning the memory used by
+    /// the runtime and by blobs, will be available.
+    /// Disabled by default.
+    pub fn memory_usage(&mut self, enable: bool) -> &mut Self {
+        self.javy_intrinsics
+            .set(JavyIntrinsics::MEMORY_USAGE, enable);
+        self
+    }
+
     /// Enables whether the output of console.log will be redirected to
     /// `stderr`.
     pub fn redirect_stdout_to_stderr(&mut self, enable: bool) -> &mut Self {
@@ -216,6 +619,9 @@ impl Config {
 
     /// The limit on the max amount of memory the runtime will use. Default is
     /// unlimited.
+    ///
+    /// Allocations past the limit fail with an `out of memory` internal
+    /// error rather than growing the memory of the instance.
     pub fn memory_limit(&mut self, bytes: usize) -> &mut Self {
         self.memory_limit = bytes;
         self
@@ -223,16 +629,154 @@ impl Config {
 
     /// The limit on the max size of stack the runtime will use. Default is
     /// 256 * 1024.
+    ///
+    /// Exceeding it, for example with unbounded recursion, fails with a
+    /// `stack overflow` internal error.
     pub fn max_stack_size(&mut self, bytes: usize) -> &mut Self {
         self.max_stack_size = bytes;
         self
     }
 
+    /// Interrupts execution after the QuickJS interrupt handler has been
+    /// invoked `count` times. QuickJS invokes the handler periodically while
+    /// executing bytecode, so this bounds the amount of work a script can
+    /// perform independently of any metering done by the host.
+    ///
+    /// The interval between invocations is fixed by QuickJS at 10000 polls
+    /// of its interrupt counter, which happen on backward jumps and function
+    /// calls. Execution therefore stops within roughly `count * 10000` loop
+    /// iterations or calls. How often the handler checks the deadline set
+    /// with [`Config::total_deadline`] is configured with
+    /// [`Config::interrupt_check_interval`].
+    ///
+    /// The budget applies to a whole run, measured from the creation of the
+    /// runtime or the last call to
+    /// [`Runtime::reset_invocation_state`](crate::Runtime::reset_invocation_state),
+    /// and [`Runtime::budget_exceeded`](crate::Runtime::budget_exceeded)
+    /// tells an interruption caused by it apart from other errors.
+    /// Disabled by default.
+    pub fn interrupt_after(&mut self, count: u64) -> &mut Self {
+        self.interrupt_after = Some(count);
+        self
+    }
+
+    /// Bounds the duration of a whole run, measured from the creation of the
+    /// runtime or the last call to
+    /// [`Runtime::reset_invocation_state`](crate::Runtime::reset_invocation_state).
+    ///
+    /// Synchronous code is interrupted by the QuickJS interrupt handler once
+    /// the deadline has passed, and event loops driving the runtime are
+    /// expected to stop waiting for pending jobs when
+    /// [`Runtime::deadline_exceeded`](crate::Runtime::deadline_exceeded)
+    /// returns `true`. Unlike [`Config::interrupt_after`], this bounds both
+    /// the evaluation of a module and the asynchronous work it schedules.
+    /// Disabled by default.
+    pub fn total_deadline(&mut self, deadline: Option<Duration>) -> &mut Self {
+        self.total_deadline = deadline;
+        self
+    }
+
+    /// Only checks the deadline set with [`Config::total_deadline`], and
+    /// samples the memory usage tracked with [`Config::track_peak_memory`],
+    /// once every `interval` invocations of the QuickJS interrupt handler.
+    ///
+    /// QuickJS invokes the handler every 10000 polls of its interrupt
+    /// counter, so a deadline that has passed interrupts execution within
+    /// roughly `interval * 10000` loop iterations or calls. Larger intervals
+    /// read the clock and walk the heap less often, at the cost of
+    /// interrupting later. Checking on every invocation is already cheap
+    /// compared to the work between them.
+    /// Default is 1.
+    pub fn interrupt_check_interval(&mut self, interval: u32) -> &mut Self {
+        self.interrupt_check_interval = interval;
+        self
+    }
+
+    /// Whether the runtime tracks the peak of the memory it uses, reported by
+    /// [`Runtime::peak_memory`](crate::Runtime::peak_memory).
+    ///
+    /// The memory usage is sampled each time QuickJS invokes its interrupt
+    /// handler while executing bytecode, after resolving the pending jobs and
+    /// when the peak is read. Sampling walks the heap of the runtime, which
+    /// slows down execution.
+    /// Disabled by default.
+    pub fn track_peak_memory(&mut self, enable: bool) -> &mut Self {
+        self.track_peak_memory = enable;
+        self
+    }
+
+    /// Whether each invocation started with
+    /// [`Runtime::begin_invocation`](crate::Runtime::begin_invocation) runs
+    /// in a fresh context, with the APIs enabled by this configuration
+    /// registered again, so the globals set, the timers scheduled and the
+    /// blobs created by an invocation aren't visible to the next one.
+    ///
+    /// The contexts share the runtime, so its atoms, shapes and memory limits
+    /// are kept across invocations, but modules are evaluated again in each
+    /// context. Creating a context and registering the APIs makes each
+    /// invocation slower, and globals added to the context of the runtime
+    /// after it's created aren't added to the fresh contexts.
+    /// Disabled by default.
+    pub fn isolate_invocations(&mut self, enable: bool) -> &mut Self {
+        self.isolate_invocations = enable;
+        self
+    }
+
+    /// Seeds the generator backing `Math.random`, so the same seed produces
+    /// the same sequence of numbers, for example for tests comparing the
+    /// output of scripts to golden outputs. The sequence starts again from
+    /// the seed in each fresh context of [`Config::isolate_invocations`].
+    /// The numbers aren't cryptographically secure either way.
+    /// Unseeded by default.
+    pub fn random_seed(&mut self, seed: Option<u64>) -> &mut Self {
+        self.random_seed = seed;
+        self
+    }
+
+    /// How the sources compiled by
+    /// [`Runtime::compile_to_bytecode`](crate::Runtime::compile_to_bytecode)
+    /// are parsed. Sources of modules served by [`Config::module_loader`] are
+    /// always parsed as modules. Default is [`SourceType::Module`].
+    pub fn source_type(&mut self, source_type: SourceType) -> &mut Self {
+        self.source_type = source_type;
+        self
+    }
+
+    /// A hash of the options that can change the bytecode compiled by a
+    /// runtime with this config, to key caches of compiled bytecode by.
+    pub fn bytecode_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        env!("CARGO_PKG_VERSION").hash(&mut hasher);
+        self.intrinsics.bits().hash(&mut hasher);
+        self.javy_intrinsics.bits().hash(&mut hasher);
+        self.simd_json_builtins.hash(&mut hasher);
+        self.source_type.hash(&mut hasher);
+        hasher.finish()
+    }
+
     pub(crate) fn validate(self) -> Result<Self> {
         if self.simd_json_builtins && !self.intrinsics.contains(JSIntrinsics::JSON) {
             bail!("JSON Intrinsic is required to override JSON.parse and JSON.stringify");
         }
 
+        if self.regexp_cache
+            && !self.intrinsics.contains(
+                JSIntrinsics::REGEXP | JSIntrinsics::REGEXP_COMPILER | JSIntrinsics::PROXY,
+            )
+        {
+            bail!("RegExp, RegExp compiler and Proxy intrinsics are required to cache regular expressions");
+        }
+
+        if self.intrinsics.contains(JSIntrinsics::COMPRESSION_STREAMS)
+            && !self.intrinsics.contains(JSIntrinsics::STREAMS)
+        {
+            bail!("Streams are required for CompressionStream and DecompressionStream");
+        }
+
+        if self.interrupt_check_interval == 0 {
+            bail!("The interrupt check interval must be at least 1");
+        }
+
         Ok(self)
     }
 }
@@ -40,15 +40,19 @@
 //! * `messagepack` - functions for converting between [`quickjs::JSValueRef`]
 //!   and MessagePack byte slices
 
+pub use apis::blob::{blob_registry, BlobRegistry};
 pub use config::*;
+pub use loader::load_module;
 pub use rquickjs as quickjs;
 pub use runtime::Runtime;
 use std::str;
 
 pub mod alloc;
 mod config;
+mod loader;
 mod runtime;
 mod serde;
+pub mod source_map;
 
 use anyhow::{anyhow, Error, Result};
 use rquickjs::{
@@ -100,13 +104,49 @@ macro_rules! hold {
     };
 }
 
+/// A JavaScript exception converted to an [`anyhow::Error`] by
+/// [`from_js_error`], keeping its message and stack trace apart.
+#[derive(Debug)]
+pub struct JsException {
+    description: String,
+    message: String,
+    stack: Option<String>,
+}
+
+impl JsException {
+    /// The message of the exception.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// The stack trace of the exception, if it has one.
+    pub fn stack(&self) -> Option<&str> {
+        self.stack.as_deref()
+    }
+}
+
+impl std::fmt::Display for JsException {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.description)
+    }
+}
+
+impl std::error::Error for JsException {}
+
 /// Handles a JavaScript error or exception and converts to [anyhow::Error].
+///
+/// Exceptions are converted to a [`JsException`], which can be retrieved from
+/// the error with [`anyhow::Error::downcast_ref`].
 pub fn from_js_error(ctx: Ctx<'_>, e: JSError) -> Error {
     if e.is_exception() {
         let val = ctx.catch();
 
         if let Some(exception) = val.clone().into_exception() {
-            anyhow!("{exception}")
+            Error::new(JsException {
+                description: exception.to_string(),
+                message: exception.message().unwrap_or_default(),
+                stack: exception.stack().filter(|stack| !stack.is_empty()),
+            })
         } else {
             anyhow!(val_to_string(&ctx, val).unwrap_or_else(|_| "Internal error".to_string()))
         }
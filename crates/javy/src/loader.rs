@@ -0,0 +1,170 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+    ptr,
+    rc::Rc,
+};
+
+use rquickjs::{
+    loader::{Loader, Resolver},
+    module::Declared,
+    qjs, Ctx, Error, Module, Result, Runtime as QRuntime,
+};
+
+use crate::config::{ModuleLoader, ModuleSource};
+
+/// The modules served by a [`ModuleLoader`].
+struct Modules {
+    loader: ModuleLoader,
+    /// The code of the modules that were resolved but not loaded yet.
+    fetched: HashMap<String, ModuleSource>,
+    /// The names of the modules that were loaded, which QuickJS reuses.
+    loaded: HashSet<String>,
+}
+
+/// Resolves the specifiers of imported modules, making sure the loader
+/// knows about them.
+struct SourceResolver(Rc<RefCell<Modules>>);
+
+/// Loads the modules fetched by the [`SourceResolver`].
+struct SourceLoader(Rc<RefCell<Modules>>);
+
+/// Serves the modules imported by JavaScript code in the runtime from the
+/// given loader.
+pub(crate) fn set_loader(rt: &QRuntime, loader: ModuleLoader) {
+    let modules = Rc::new(RefCell::new(Modules {
+        loader,
+        fetched: HashMap::new(),
+        loaded: HashSet::new(),
+    }));
+    rt.set_loader(SourceResolver(modules.clone()), SourceLoader(modules));
+}
+
+impl Resolver for SourceResolver {
+    fn resolve<'js>(&mut self, _ctx: &Ctx<'js>, base: &str, name: &str) -> Result<String> {
+        let resolved = resolve_specifier(base, name);
+        let mut modules = self.0.borrow_mut();
+        if modules.loaded.contains(&resolved) || modules.fetched.contains_key(&resolved) {
+            return Ok(resolved);
+        }
+        match (modules.loader)(&resolved) {
+            Some(source) => {
+                modules.fetched.insert(resolved.clone(), source);
+                Ok(resolved)
+            }
+            None => Err(Error::new_resolving_message(base, name, "no such module")),
+        }
+    }
+}
+
+impl Loader for SourceLoader {
+    fn load<'js>(&mut self, ctx: &Ctx<'js>, name: &str) -> Result<Module<'js, Declared>> {
+        // The borrow is released before declaring the module, which resolves
+        // the modules it imports.
+        let source = self.0.borrow_mut().fetched.remove(name);
+        let module = match source {
+            Some(ModuleSource::Code(code)) => Module::declare(ctx.clone(), name, code)?,
+            Some(ModuleSource::Bytecode(bytecode)) => {
+                // SAFETY: The contract of `ModuleLoader` is to return bytecode
+                // compiled by `Runtime::compile_to_bytecode`.
+                let module = unsafe { Module::load(ctx.clone(), &bytecode)? };
+                let compiled: String = module.name()?;
+                if compiled != name {
+                    return Err(Error::new_loading_message(
+                        name,
+                        format!("the bytecode is of module '{compiled}'"),
+                    ));
+                }
+                module
+            }
+            None => return Err(Error::new_loading_message(name, "no such module")),
+        };
+        self.0.borrow_mut().loaded.insert(name.to_string());
+        Ok(module)
+    }
+}
+
+/// Resolves specifiers starting with `./` or `../` against the name of the
+/// importing module. Other specifiers are used as is.
+fn resolve_specifier(base: &str, name: &str) -> String {
+    if !name.starts_with("./") && !name.starts_with("../") {
+        return name.to_string();
+    }
+    let mut segments: Vec<&str> = base.split('/').collect();
+    // The file name of the importing module.
+    segments.pop();
+    for segment in name.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+thread_local! {
+    /// The module read by [`load_module`], handed over to
+    /// [`Module::from_load_fn`].
+    static READ_MODULE: Cell<*mut qjs::JSModuleDef> = const { Cell::new(ptr::null_mut()) };
+}
+
+/// Loads a module from bytecode compiled by
+/// [`Runtime::compile_to_bytecode`](crate::Runtime::compile_to_bytecode),
+/// resolving the modules it imports with the
+/// [`ModuleLoader`](crate::ModuleLoader) of the runtime.
+///
+/// Unlike [`Module::load`], the loaded module can be evaluated even if it
+/// imports other modules.
+///
+/// # Safety
+/// `bytecode` must be valid QuickJS module bytecode.
+pub unsafe fn load_module<'js>(ctx: Ctx<'js>, bytecode: &[u8]) -> Result<Module<'js, Declared>> {
+    let raw = ctx.as_raw().as_ptr();
+    let value = qjs::JS_ReadObject(
+        raw,
+        bytecode.as_ptr(),
+        bytecode.len() as _,
+        (qjs::JS_READ_OBJ_BYTECODE | qjs::JS_READ_OBJ_ROM_DATA) as i32,
+    );
+    if qjs::JS_IsException(value) {
+        return Err(Error::Exception);
+    }
+    if qjs::JS_VALUE_GET_TAG(value) != qjs::JS_TAG_MODULE {
+        qjs::JS_FreeValue(raw, value);
+        return Err(Error::new_loading_message("<bytecode>", "not a module"));
+    }
+    if qjs::JS_ResolveModule(raw, value) < 0 {
+        return Err(Error::Exception);
+    }
+
+    unsafe extern "C" fn read_module(
+        _ctx: *mut qjs::JSContext,
+        _name: *const qjs::c_char,
+    ) -> *mut qjs::JSModuleDef {
+        READ_MODULE.with(|module| module.replace(ptr::null_mut()))
+    }
+
+    READ_MODULE.with(|module| module.set(qjs::JS_VALUE_GET_PTR(value).cast()));
+    Module::from_load_fn(ctx, "", read_module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve_specifier;
+
+    #[test]
+    fn test_resolve_specifier() {
+        assert_eq!("util.js", resolve_specifier("function.mjs", "./util.js"));
+        assert_eq!(
+            "lib/util.js",
+            resolve_specifier("function.mjs", "./lib/util.js")
+        );
+        assert_eq!("lib/b.js", resolve_specifier("lib/a.js", "./b.js"));
+        assert_eq!("b.js", resolve_specifier("lib/a.js", "../b.js"));
+        assert_eq!("b.js", resolve_specifier("a.js", "../b.js"));
+        assert_eq!("lib/b.js", resolve_specifier("lib/a.js", "lib/b.js"));
+    }
+}
@@ -4,22 +4,127 @@ use anyhow::Result;
 
 /// Transcodes a byte slice containing a MessagePack encoded payload into a [`JSValueRef`].
 ///
+/// MessagePack binary values are decoded as `Uint8Array` objects.
+///
 /// Arguments:
 /// * `context` - A reference to the [`JSContextRef`] that will contain the
 ///   returned [`JSValueRef`].
 /// * `bytes` - A byte slice containing a MessagePack encoded payload.
 pub fn transcode_input<'js>(context: Ctx<'js>, bytes: &[u8]) -> Result<Value<'js>> {
+    transcode_input_with_blobs(context, bytes, false)
+}
+
+/// Transcodes a byte slice containing a MessagePack encoded payload into a
+/// [`JSValueRef`], decoding MessagePack binary values as `Blob` objects when
+/// `bytes_as_blobs` is set and as `Uint8Array` objects otherwise.
+///
+/// Decoding binary values as `Blob` objects requires the `Blob` API to be
+/// enabled.
+pub fn transcode_input_with_blobs<'js>(
+    context: Ctx<'js>,
+    bytes: &[u8],
+    bytes_as_blobs: bool,
+) -> Result<Value<'js>> {
     let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes);
     let mut serializer = Serializer::from_context(context.clone())?;
+    serializer.bytes_as_blobs = bytes_as_blobs;
     serde_transcode::transcode(&mut deserializer, &mut serializer)?;
     Ok(serializer.value)
 }
 
+/// Transcodes a byte slice containing a MessagePack encoded payload into a
+/// JSON encoded byte vector, without materializing JavaScript values.
+#[cfg(feature = "json")]
+pub fn to_json(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes);
+    let mut serializer = serde_json::Serializer::new(&mut output);
+    serde_transcode::transcode(&mut deserializer, &mut serializer)?;
+    Ok(output)
+}
+
+/// Transcodes a byte slice containing a JSON encoded payload into a
+/// MessagePack encoded byte vector, without materializing JavaScript values.
+#[cfg(feature = "json")]
+pub fn from_json(bytes: &[u8]) -> Result<Vec<u8>> {
+    let mut output = Vec::new();
+    let mut deserializer = serde_json::Deserializer::from_slice(bytes);
+    let mut serializer = rmp_serde::Serializer::new(&mut output);
+    serde_transcode::transcode(&mut deserializer, &mut serializer)?;
+    deserializer.end()?;
+    Ok(output)
+}
+
 /// Transcodes a [`JSValueRef`] into a MessagePack encoded byte vector.
+///
+/// `Blob` and `File` objects are encoded as MessagePack binary values holding
+/// their contents. Their type and name are not preserved.
 pub fn transcode_output(val: Value<'_>) -> Result<Vec<u8>> {
     let mut output = Vec::new();
-    let mut deserializer = Deserializer::from(val);
+    let mut deserializer = Deserializer::from(val).blobs_as_bytes(true);
     let mut serializer = rmp_serde::Serializer::new(&mut output);
     serde_transcode::transcode(&mut deserializer, &mut serializer)?;
     Ok(output)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{val_to_string, Config, Runtime};
+    use anyhow::Error;
+
+    #[test]
+    fn test_file_round_trip() -> Result<()> {
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|cx| {
+            let value: Value = cx.eval(
+                r#"({
+                    name: "payload",
+                    file: new File([new Uint8Array([0, 1, 254, 255])], "data.bin", {
+                        type: "application/octet-stream",
+                    }),
+                })"#,
+            )?;
+            let bytes = transcode_output(value)?;
+            // The file is encoded as a `bin 8` value.
+            let bin = [0xc4, 4, 0, 1, 254, 255];
+            assert!(bytes.windows(bin.len()).any(|w| w == bin));
+
+            let value = transcode_input_with_blobs(cx.clone(), &bytes, true)?;
+            cx.globals().set("decoded", value)?;
+            let result: Value = cx.eval(
+                r#"decoded.name + ":" + (decoded.file instanceof Blob) + ":" +
+                    Array.from(decoded.file.bytes()).join(",")"#,
+            )?;
+            assert_eq!("payload:true:0,1,254,255", val_to_string(&cx, result)?);
+            Ok::<_, Error>(())
+        })
+    }
+
+    #[test]
+    fn test_binary_decodes_to_uint8array() -> Result<()> {
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|cx| {
+            let value: Value = cx.eval("new Blob([new Uint8Array([1, 2, 3])])")?;
+            let bytes = transcode_output(value)?;
+
+            let value = transcode_input(cx.clone(), &bytes)?;
+            cx.globals().set("decoded", value)?;
+            let result: Value = cx
+                .eval(r#"(decoded instanceof Uint8Array) + ":" + Array.from(decoded).join(",")"#)?;
+            assert_eq!("true:1,2,3", val_to_string(&cx, result)?);
+            Ok::<_, Error>(())
+        })
+    }
+
+    #[test]
+    fn test_json_stringify_blob() -> Result<()> {
+        let runtime = Runtime::new(Config::default())?;
+        runtime.context().with(|cx| {
+            let result: Value =
+                cx.eval(r#"JSON.stringify({ file: new File(["abc"], "a.txt") })"#)?;
+            assert_eq!(r#"{"file":{}}"#, val_to_string(&cx, result)?);
+            Ok::<_, Error>(())
+        })
+    }
+}
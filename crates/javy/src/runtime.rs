@@ -2,20 +2,35 @@
 use super::from_js_error;
 #[cfg(feature = "json")]
 use crate::apis::json;
+#[cfg(all(feature = "messagepack", feature = "json"))]
+use crate::apis::transcode;
 use crate::{
-    apis::{base64, blob, console, random, stream_io, text_encoding, timers::TimersRuntime},
-    config::{JSIntrinsics, JavyIntrinsics},
-    Config,
+    apis::{
+        abort, base64,
+        blob::{self, BlobSettings},
+        compression,
+        console::{self, ConsoleState, InspectOptions},
+        crypto, deprecation, error, events, form_data, memory_usage, navigator, random,
+        regexp_cache, shutdown, stream_io, streams, structured_clone, text_encoding,
+        timers::TimersRuntime,
+    },
+    config::{JSIntrinsics, JavyIntrinsics, SourceType},
+    loader, Config,
 };
 
 use anyhow::{bail, Result};
 use rquickjs::{
     context::{intrinsic, Intrinsic},
+    qjs,
+    runtime::MemoryUsage,
     Context, Module, Runtime as QRuntime,
 };
 use std::{
+    cell::Cell,
     io::{stderr, stdout},
-    mem::ManuallyDrop,
+    mem::{self, ManuallyDrop, MaybeUninit},
+    rc::Rc,
+    time::{Duration, Instant},
 };
 
 /// A JavaScript Runtime.
@@ -41,6 +56,66 @@ pub struct Runtime {
     inner: ManuallyDrop<QRuntime>,
     /// Timers runtime state, if enabled.
     timers: Option<TimersRuntime>,
+    /// State of the `console` object scoped to a single invocation.
+    console: ConsoleState,
+    /// The peak of the memory used by the runtime, if tracked.
+    peak_memory: Option<Rc<Cell<usize>>>,
+    /// The deadline of the current run, if bounded.
+    deadline: Option<Deadline>,
+    /// The execution budget of the current run, if bounded.
+    budget: Option<Budget>,
+    /// The configuration the context of each invocation is created from, if
+    /// invocations are isolated.
+    isolation: Option<Config>,
+    /// How compiled sources are parsed.
+    source_type: SourceType,
+}
+
+/// The execution budget of a run bounded by [`Config::interrupt_after`].
+struct Budget {
+    limit: u64,
+    /// The interrupt handler invocations of the run, shared with the
+    /// interrupt handler.
+    used: Rc<Cell<u64>>,
+}
+
+impl Budget {
+    fn new(limit: u64) -> Self {
+        Self {
+            limit,
+            used: Rc::default(),
+        }
+    }
+
+    fn exceeded(&self) -> bool {
+        self.used.get() > self.limit
+    }
+}
+
+/// The deadline of a run bounded by [`Config::total_deadline`].
+struct Deadline {
+    duration: Duration,
+    /// When the run must end, shared with the interrupt handler. `None` if
+    /// it's too far in the future to be represented.
+    at: Rc<Cell<Option<Instant>>>,
+}
+
+impl Deadline {
+    fn new(duration: Duration) -> Self {
+        Self {
+            duration,
+            at: Rc::new(Cell::new(Instant::now().checked_add(duration))),
+        }
+    }
+
+    fn restart(&self) {
+        self.at.set(Instant::now().checked_add(self.duration));
+    }
+}
+
+/// Whether the deadline at `at` has passed.
+fn passed(at: &Cell<Option<Instant>>) -> bool {
+    at.get().is_some_and(|at| Instant::now() >= at)
 }
 
 impl Runtime {
@@ -48,19 +123,44 @@ impl Runtime {
     pub fn new(config: Config) -> Result<Self> {
         let rt = ManuallyDrop::new(QRuntime::new()?);
         let timers = if config.intrinsics.contains(JSIntrinsics::TIMERS) {
-            Some(TimersRuntime::new())
+            Some(TimersRuntime::new(
+                config.timer_max_args,
+                config.timer_batch,
+            ))
         } else {
             None
         };
 
-        let context = Self::build_from_config(&rt, config, &timers)?;
-        Ok(Self { inner: rt, context, timers })
+        let peak_memory = config.track_peak_memory.then(Rc::default);
+        let deadline = config.total_deadline.map(Deadline::new);
+        let budget = config.interrupt_after.map(Budget::new);
+        let isolation = config.isolate_invocations.then(|| config.clone());
+        let source_type = config.source_type;
+
+        let (context, console) =
+            Self::build_from_config(&rt, config, &timers, &peak_memory, &deadline, &budget)?;
+        Ok(Self {
+            inner: rt,
+            context,
+            timers,
+            console,
+            peak_memory,
+            deadline,
+            budget,
+            isolation,
+            source_type,
+        })
     }
 
-    fn build_from_config(rt: &QRuntime, cfg: Config, timers: &Option<TimersRuntime>) -> Result<ManuallyDrop<Context>> {
+    fn build_from_config(
+        rt: &QRuntime,
+        cfg: Config,
+        timers: &Option<TimersRuntime>,
+        peak_memory: &Option<Rc<Cell<usize>>>,
+        deadline: &Option<Deadline>,
+        budget: &Option<Budget>,
+    ) -> Result<(ManuallyDrop<Context>, ConsoleState)> {
         let cfg = cfg.validate()?;
-        let intrinsics = &cfg.intrinsics;
-        let javy_intrinsics = &cfg.javy_intrinsics;
 
         rt.set_gc_threshold(cfg.gc_threshold);
         rt.set_memory_limit(cfg.memory_limit);
@@ -70,6 +170,57 @@ impl Runtime {
         // the same intrinsic twice.
         let context = Context::custom::<()>(rt)?;
 
+        if let Some(loader) = &cfg.module_loader {
+            loader::set_loader(rt, loader.clone());
+        }
+
+        if budget.is_some() || peak_memory.is_some() || deadline.is_some() {
+            let budget = budget
+                .as_ref()
+                .map(|budget| (budget.limit, budget.used.clone()));
+            let peak_memory = peak_memory.clone();
+            let deadline = deadline.as_ref().map(|deadline| deadline.at.clone());
+            let check_interval = cfg.interrupt_check_interval;
+            // The invocations since the deadline was last checked.
+            let unchecked = Cell::new(0u32);
+            // The handler can't lock the runtime, which is already locked
+            // while executing bytecode, so it samples the memory usage of the
+            // raw runtime.
+            let raw = context.with(|ctx| unsafe { qjs::JS_GetRuntime(ctx.as_raw().as_ptr()) });
+            rt.set_interrupt_handler(Some(Box::new(move || {
+                unchecked.set(unchecked.get() + 1);
+                if unchecked.get() >= check_interval {
+                    unchecked.set(0);
+                    if let Some(peak) = &peak_memory {
+                        peak.set(peak.get().max(unsafe { memory_used(raw) }));
+                    }
+                    if deadline.as_deref().is_some_and(passed) {
+                        return true;
+                    }
+                }
+                match &budget {
+                    Some((limit, used)) => {
+                        used.set(used.get().saturating_add(1));
+                        used.get() > *limit
+                    }
+                    None => false,
+                }
+            })));
+        }
+
+        let console = Self::register_apis(&context, &cfg, timers);
+        Ok((ManuallyDrop::new(context), console))
+    }
+
+    /// Registers the intrinsics and APIs enabled by `cfg` in `context`.
+    fn register_apis(
+        context: &Context,
+        cfg: &Config,
+        timers: &Option<TimersRuntime>,
+    ) -> ConsoleState {
+        let intrinsics = &cfg.intrinsics;
+        let javy_intrinsics = &cfg.javy_intrinsics;
+
         // We use `Context::with` to ensure that there's a proper lock on the
         // context, making it totally safe to add the intrinsics below.
         context.with(|ctx| {
@@ -79,7 +230,8 @@ impl Runtime {
             // NB: Users of Javy as a crate are welcome to switch this config,
             // however note that the usage of a custom `Random` implementation
             // should not affect the output of `Math.random()`.
-            random::register(ctx.clone()).expect("registering `random` APIs to succeed");
+            random::register(ctx.clone(), cfg.random_seed)
+                .expect("registering `random` APIs to succeed");
 
             if intrinsics.contains(JSIntrinsics::DATE) {
                 unsafe { intrinsic::Date::add_intrinsic(ctx.as_raw()) }
@@ -143,20 +295,109 @@ impl Runtime {
                     .expect("registering TextEncoding APIs to succeed");
             }
 
+            if intrinsics.contains(JSIntrinsics::ERROR_CAPTURE_STACK_TRACE) {
+                error::register(ctx.clone())
+                    .expect("registering Error.captureStackTrace to succeed");
+            }
+
+            // Registered before the APIs having deprecated parts, which
+            // capture the helper emitting the warnings
+            if cfg.deprecation_warnings {
+                deprecation::register(ctx.clone(), stderr())
+                    .expect("registering deprecation warnings to succeed");
+            }
+
+            // Registered before the other APIs so they can build on it
+            if intrinsics.contains(JSIntrinsics::EVENTS) {
+                events::register(ctx.clone()).expect("registering Event APIs to succeed");
+            }
+
             // Base64 APIs are always available
-            base64::register(ctx.clone())
-                .expect("registering base64 APIs to succeed");
+            base64::register(ctx.clone()).expect("registering base64 APIs to succeed");
 
             // Blob APIs are always available
-            blob::register(ctx.clone())
-                .expect("registering blob APIs to succeed");
+            let blob_settings = BlobSettings {
+                sniff_type: cfg.blob_sniff_type,
+                strict: cfg.strict_blob,
+                max_storage_bytes: cfg.max_blob_storage_bytes,
+                weak_map_handles: cfg.blob_weak_map_handles,
+            };
+            blob::register(ctx.clone(), blob_settings).expect("registering blob APIs to succeed");
+
+            // Shutdown hooks are always available
+            shutdown::register(ctx.clone()).expect("registering onShutdown to succeed");
+
+            if intrinsics.contains(JSIntrinsics::STREAMS) {
+                streams::register(ctx.clone()).expect("registering streams APIs to succeed");
+            }
+
+            if intrinsics.contains(JSIntrinsics::COMPRESSION_STREAMS) {
+                compression::register(ctx.clone())
+                    .expect("registering CompressionStream to succeed");
+            }
+
+            if intrinsics.contains(JSIntrinsics::FORM_DATA) {
+                form_data::register(ctx.clone()).expect("registering FormData to succeed");
+            }
+
+            if intrinsics.contains(JSIntrinsics::STRUCTURED_CLONE) {
+                structured_clone::register(ctx.clone(), cfg.blob_weak_map_handles)
+                    .expect("registering structuredClone to succeed");
+            }
+
+            if intrinsics.contains(JSIntrinsics::CRYPTO) {
+                crypto::register(ctx.clone()).expect("registering crypto APIs to succeed");
+            }
 
-            if cfg.redirect_stdout_to_stderr {
-                console::register(ctx.clone(), stderr(), stderr(), stderr())
-                    .expect("registering console to succeed");
+            if intrinsics.contains(JSIntrinsics::NAVIGATOR) {
+                navigator::register(ctx.clone(), &cfg.navigator_user_agent)
+                    .expect("registering navigator to succeed");
+            }
+
+            if intrinsics.contains(JSIntrinsics::ABORT_CONTROLLER) {
+                abort::register(ctx.clone(), intrinsics.contains(JSIntrinsics::TIMERS))
+                    .expect("registering AbortController to succeed");
+            }
+
+            if cfg.regexp_cache {
+                regexp_cache::register(ctx.clone())
+                    .expect("registering the RegExp cache to succeed");
+            }
+
+            if cfg.deprecation_warnings {
+                deprecation::unregister(ctx.clone())
+                    .expect("unregistering deprecation warnings to succeed");
+            }
+
+            let inspect = InspectOptions {
+                depth: cfg.console_depth,
+                max_depth: cfg.console_inspect_max_depth,
+            };
+            let console = if cfg.redirect_stdout_to_stderr {
+                console::register(
+                    ctx.clone(),
+                    stderr(),
+                    stderr(),
+                    stderr(),
+                    inspect,
+                    cfg.console_sink.clone(),
+                )
+                .expect("registering console to succeed")
             } else {
-                console::register(ctx.clone(), stdout(), stderr(), stderr())
-                    .expect("registering console to succeed");
+                console::register(
+                    ctx.clone(),
+                    stdout(),
+                    stderr(),
+                    stderr(),
+                    inspect,
+                    cfg.console_sink.clone(),
+                )
+                .expect("registering console to succeed")
+            };
+
+            if intrinsics.contains(JSIntrinsics::REPORT_ERROR) {
+                error::register_report_error(ctx.clone())
+                    .expect("registering reportError to succeed");
             }
 
             if javy_intrinsics.contains(JavyIntrinsics::STREAM_IO) {
@@ -164,13 +405,22 @@ impl Runtime {
                     .expect("registering StreamIO functions to succeed");
             }
 
+            if javy_intrinsics.contains(JavyIntrinsics::MEMORY_USAGE) {
+                memory_usage::register(ctx.clone())
+                    .expect("registering memoryUsage function to succeed");
+            }
+
+            #[cfg(all(feature = "messagepack", feature = "json"))]
+            transcode::register(ctx.clone()).expect("registering transcode functions to succeed");
+
             if let Some(timers) = timers {
-                timers.register_globals(ctx.clone())
+                timers
+                    .register_globals(ctx.clone())
                     .expect("registering timer APIs to succeed");
             }
-        });
 
-        Ok(ManuallyDrop::new(context))
+            console
+        })
     }
 
     /// A reference to the inner [Context].
@@ -178,6 +428,84 @@ impl Runtime {
         &self.context
     }
 
+    /// Resets the state that is scoped to a single invocation, like the
+    /// `console.group` nesting level. The peak memory, if tracked, is reset to
+    /// the memory currently used, and the deadline and the execution budget
+    /// of the run, if bounded, are restarted.
+    pub fn reset_invocation_state(&self) {
+        self.console.reset();
+        if let Some(peak) = &self.peak_memory {
+            peak.set(self.memory_used());
+        }
+        if let Some(deadline) = &self.deadline {
+            deadline.restart();
+        }
+        if let Some(budget) = &self.budget {
+            budget.used.set(0);
+        }
+    }
+
+    /// Prepares the runtime for an invocation, resetting the state scoped to
+    /// a single invocation like [`Runtime::reset_invocation_state`].
+    ///
+    /// If [`Config::isolate_invocations`] is enabled, the pending timers are
+    /// cancelled and the context is replaced with a fresh one, in which the
+    /// configured APIs are registered again.
+    pub fn begin_invocation(&mut self) -> Result<()> {
+        if let Some(cfg) = &self.isolation {
+            self.clear_timers()?;
+            let context = Context::custom::<()>(&self.inner)?;
+            let console = Self::register_apis(&context, cfg, &self.timers);
+            let previous = mem::replace(&mut self.context, ManuallyDrop::new(context));
+            // Unlike the context of the runtime, the contexts of the previous
+            // invocations are dropped, along with the objects only they use.
+            drop(ManuallyDrop::into_inner(previous));
+            self.inner.run_gc();
+            self.console = console;
+        }
+        self.reset_invocation_state();
+        Ok(())
+    }
+
+    /// Whether the execution budget of the run set with
+    /// [`Config::interrupt_after`] is exhausted.
+    pub fn budget_exceeded(&self) -> bool {
+        self.budget.as_ref().is_some_and(Budget::exceeded)
+    }
+
+    /// Whether the deadline of the run set with [`Config::total_deadline`]
+    /// has passed.
+    pub fn deadline_exceeded(&self) -> bool {
+        self.deadline
+            .as_ref()
+            .is_some_and(|deadline| passed(&deadline.at))
+    }
+
+    /// The peak of the memory used by the runtime, in bytes, since it was
+    /// created or its invocation state was last reset, if tracking it is
+    /// enabled with [`Config::track_peak_memory`].
+    pub fn peak_memory(&self) -> Option<usize> {
+        self.sample_peak_memory();
+        self.peak_memory.as_ref().map(|peak| peak.get())
+    }
+
+    fn sample_peak_memory(&self) {
+        if let Some(peak) = &self.peak_memory {
+            peak.set(peak.get().max(self.memory_used()));
+        }
+    }
+
+    fn memory_used(&self) -> usize {
+        self.memory_usage().memory_used_size as usize
+    }
+
+    /// The memory used by the QuickJS runtime, as computed by
+    /// `JS_ComputeMemoryUsage`. Blobs live outside of it, the bytes they hold
+    /// are reported by [`BlobRegistry::stored_bytes`](crate::BlobRegistry::stored_bytes).
+    pub fn memory_usage(&self) -> MemoryUsage {
+        self.inner.memory_usage()
+    }
+
     /// Resolves all the pending jobs in the queue.
     pub fn resolve_pending_jobs(&self) -> Result<()> {
         // Process timers if enabled
@@ -201,6 +529,7 @@ impl Runtime {
                 }
             }
         }
+        self.sample_peak_memory();
 
         Ok(())
     }
@@ -211,6 +540,13 @@ impl Runtime {
         has_js_jobs || self.has_pending_timers()
     }
 
+    /// The number of pending timers, including intervals.
+    pub fn pending_timers(&self) -> usize {
+        self.timers
+            .as_ref()
+            .map_or(0, |timers| timers.pending_timers())
+    }
+
     pub fn has_pending_timers(&self) -> bool {
         if let Some(timers) = &self.timers {
             timers.has_pending_timers()
@@ -219,14 +555,68 @@ impl Runtime {
         }
     }
 
+    /// Runs the functions registered with `Javy.onShutdown`, in registration
+    /// order, so scripts can flush their state before the runtime is torn
+    /// down. Each function runs at most once, the jobs they schedule are left
+    /// pending.
+    pub fn run_shutdown_hooks(&self) -> Result<()> {
+        self.context.with(shutdown::run_hooks)
+    }
+
+    /// Cancels all the pending timers, including intervals.
+    pub fn clear_timers(&self) -> Result<()> {
+        match &self.timers {
+            Some(timers) => self.context.with(|ctx| timers.clear_timers(ctx)),
+            None => Ok(()),
+        }
+    }
+
     /// Compiles the given module to bytecode.
+    ///
+    /// Sources are parsed according to [`Config::source_type`], scripts are
+    /// compiled to the bytecode of a module evaluating the wrapped script.
     pub fn compile_to_bytecode(&self, name: &str, contents: &str) -> Result<Vec<u8>> {
+        let contents = match self.source_type {
+            SourceType::Module => contents.to_string(),
+            SourceType::Script => wrap_script(contents),
+        };
         self.context()
             .with(|this| Module::declare(this.clone(), name, contents)?.write_le())
             .map_err(|e| self.context().with(|cx| from_js_error(cx.clone(), e)))
     }
 }
 
+/// Wraps a script in a function called with `module` and `exports`, like
+/// CommonJS modules are, so that a top-level `return` ends the script.
+///
+/// The wrapper starts on the first line of the script to keep the line
+/// numbers of stack traces and errors, and ends on a line of its own in case
+/// the script ends with a comment.
+fn wrap_script(contents: &str) -> String {
+    format!(
+        "const module = {{ exports: {{}} }}; (function (exports, module) {{{contents}\n}}).call(module.exports, module.exports, module);"
+    )
+}
+
+/// The memory used by a runtime, in bytes.
+///
+/// # Safety
+/// `rt` must point to a live runtime.
+unsafe fn memory_used(rt: *mut qjs::JSRuntime) -> usize {
+    compute_memory_usage(rt).memory_used_size as usize
+}
+
+/// The memory usage of a runtime, which can be computed while the runtime is
+/// locked, unlike [`Runtime::memory_usage`].
+///
+/// # Safety
+/// `rt` must point to a live runtime.
+pub(crate) unsafe fn compute_memory_usage(rt: *mut qjs::JSRuntime) -> MemoryUsage {
+    let mut usage = MaybeUninit::uninit();
+    qjs::JS_ComputeMemoryUsage(rt, usage.as_mut_ptr());
+    usage.assume_init()
+}
+
 impl Default for Runtime {
     /// Returns a [`Runtime`] with a default configuration.
     ///
@@ -236,3 +626,402 @@ impl Default for Runtime {
         Self::new(Config::default()).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Module, Runtime};
+    use crate::{load_module, quickjs::Value, Config, ModuleSource, SourceType};
+    use anyhow::Result;
+    use std::time::Duration;
+
+    #[test]
+    fn test_interrupt_after_stops_infinite_loop() -> Result<()> {
+        let mut config = Config::default();
+        config.interrupt_after(10);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("while (true) {}");
+            let err = result.expect_err("infinite loop should be interrupted");
+            assert!(matches!(err, rquickjs::Error::Exception));
+            let exception = this.catch();
+            let message = exception
+                .as_exception()
+                .and_then(|e| e.message())
+                .unwrap_or_default();
+            assert_eq!("interrupted", message);
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupt_after_allows_bounded_work() -> Result<()> {
+        let mut config = Config::default();
+        config.interrupt_after(1_000);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: i32 =
+                this.eval("let n = 0; for (let i = 0; i < 100; i++) { n += i; } n")?;
+            assert_eq!(4950, result);
+            Ok::<_, anyhow::Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupt_after_is_reset_for_each_run() -> Result<()> {
+        let mut config = Config::default();
+        config.interrupt_after(10);
+        let runtime = Runtime::new(config)?;
+        assert!(!runtime.budget_exceeded());
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("while (true) {}");
+            assert!(matches!(result, Err(rquickjs::Error::Exception)));
+        });
+        assert!(runtime.budget_exceeded());
+
+        // Resetting the invocation state starts a new run with a full budget.
+        runtime.reset_invocation_state();
+        assert!(!runtime.budget_exceeded());
+        runtime.context().with(|this| {
+            let result: i32 = this
+                .eval("function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); } fib(20)")?;
+            assert_eq!(6765, result);
+            Ok::<_, anyhow::Error>(())
+        })?;
+        assert!(!runtime.budget_exceeded());
+
+        Ok(())
+    }
+
+    fn global_set_by_previous_invocation(isolate: bool) -> Result<bool> {
+        let mut config = Config::default();
+        config.isolate_invocations(isolate);
+        let mut runtime = Runtime::new(config)?;
+
+        runtime.begin_invocation()?;
+        runtime
+            .context()
+            .with(|this| this.eval::<(), _>("globalThis.x = 1"))?;
+
+        runtime.begin_invocation()?;
+        let visible = runtime
+            .context()
+            .with(|this| this.eval::<bool, _>("globalThis.x === 1"))?;
+        Ok(visible)
+    }
+
+    #[test]
+    fn test_globals_are_shared_between_invocations() -> Result<()> {
+        assert!(global_set_by_previous_invocation(false)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_isolate_invocations_hides_globals() -> Result<()> {
+        assert!(!global_set_by_previous_invocation(true)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_isolate_invocations_registers_apis_again() -> Result<()> {
+        let mut config = Config::default();
+        config.isolate_invocations(true).timers(true);
+        let mut runtime = Runtime::new(config)?;
+
+        runtime.begin_invocation()?;
+        runtime
+            .context()
+            .with(|this| this.eval::<(), _>("setTimeout(() => {}, 60000)"))?;
+        assert_eq!(1, runtime.pending_timers());
+
+        runtime.begin_invocation()?;
+        assert_eq!(0, runtime.pending_timers());
+        let encoded = runtime.context().with(|this| {
+            this.eval::<String, _>(
+                "typeof setTimeout + ' ' + btoa('javy') + ' ' + typeof console.log",
+            )
+        })?;
+        assert_eq!("function amF2eQ== function", encoded);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_total_deadline_stops_infinite_loop() -> Result<()> {
+        let mut config = Config::default();
+        config.total_deadline(Some(Duration::from_millis(10)));
+        let runtime = Runtime::new(config)?;
+        assert!(!runtime.deadline_exceeded());
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("while (true) {}");
+            assert!(matches!(result, Err(rquickjs::Error::Exception)));
+        });
+        assert!(runtime.deadline_exceeded());
+
+        // Resetting the invocation state starts a new run.
+        runtime.reset_invocation_state();
+        assert!(!runtime.deadline_exceeded());
+        Ok(())
+    }
+
+    /// The number of iterations of a tight loop evaluated before a deadline
+    /// that has already passed interrupts it.
+    fn iterations_before_deadline(check_interval: u32) -> Result<u32> {
+        let mut config = Config::default();
+        config
+            .total_deadline(Some(Duration::ZERO))
+            .interrupt_check_interval(check_interval);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("var i = 0; while (true) { i++; }");
+            assert!(matches!(result, Err(rquickjs::Error::Exception)));
+            Ok(this.globals().get("i")?)
+        })
+    }
+
+    #[test]
+    fn test_interrupt_check_interval() -> Result<()> {
+        // QuickJS invokes the interrupt handler every 10000 polls, and the
+        // loop polls once per iteration.
+        let every_invocation = iterations_before_deadline(1)?;
+        assert!(every_invocation <= 2 * 10_000, "{every_invocation}");
+
+        let every_tenth_invocation = iterations_before_deadline(10)?;
+        assert!(
+            every_tenth_invocation <= 11 * 10_000,
+            "{every_tenth_invocation}"
+        );
+        assert!(
+            every_tenth_invocation > every_invocation,
+            "{every_tenth_invocation} <= {every_invocation}"
+        );
+
+        let mut config = Config::default();
+        config.interrupt_check_interval(0);
+        assert!(Runtime::new(config).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_limit() -> Result<()> {
+        let mut config = Config::default();
+        config.memory_limit(10_000_000);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("new Array(1e9).fill('x')");
+            assert!(matches!(result, Err(rquickjs::Error::Exception)));
+            let exception = this.catch();
+            let message = exception
+                .as_exception()
+                .and_then(|e| e.message())
+                .unwrap_or_default();
+            assert_eq!("out of memory", message);
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_max_stack_size() -> Result<()> {
+        let mut config = Config::default();
+        config.max_stack_size(64 * 1024);
+        let runtime = Runtime::new(config)?;
+
+        runtime.context().with(|this| {
+            let result: rquickjs::Result<Value<'_>> = this.eval("function f() { f(); } f()");
+            assert!(matches!(result, Err(rquickjs::Error::Exception)));
+            let exception = this.catch();
+            let message = exception
+                .as_exception()
+                .and_then(|e| e.message())
+                .unwrap_or_default();
+            assert_eq!("stack overflow", message);
+
+            // The runtime is still usable after unwinding.
+            let result: i32 = this.eval("1 + 1")?;
+            assert_eq!(2, result);
+            Ok::<_, anyhow::Error>(())
+        })?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_memory_usage() -> Result<()> {
+        let mut config = Config::default();
+        config.timers(true);
+        let runtime = Runtime::new(config)?;
+        let before = runtime.memory_usage();
+
+        runtime.context().with(|this| {
+            this.eval::<(), _>(
+                "globalThis.array = new Array(100000).fill(0).map((_, i) => ({ i }));
+                setTimeout(() => {}, 1000);
+                setInterval(() => {}, 1000);",
+            )
+        })?;
+        let after = runtime.memory_usage();
+        assert!(after.memory_used_size > before.memory_used_size + 1_000_000);
+        assert!(after.obj_count > before.obj_count + 100_000);
+        assert_eq!(2, runtime.pending_timers());
+
+        runtime.clear_timers()?;
+        assert_eq!(0, runtime.pending_timers());
+        assert_eq!(0, Runtime::default().pending_timers());
+        Ok(())
+    }
+
+    fn peak_memory_of(script: &str) -> Result<usize> {
+        let mut config = Config::default();
+        config.track_peak_memory(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|this| {
+            this.eval::<(), _>(script)?;
+            Ok::<_, anyhow::Error>(())
+        })?;
+        Ok(runtime.peak_memory().expect("peak memory to be tracked"))
+    }
+
+    #[test]
+    fn test_peak_memory() -> Result<()> {
+        let empty = peak_memory_of("")?;
+        // The objects are released before the script ends, so only the
+        // samples taken while it runs see them.
+        let allocating = peak_memory_of(
+            r#"
+            let objects = [];
+            for (let i = 0; i < 100000; i++) {
+                objects.push({ i, s: "x".repeat(10) + i });
+            }
+            objects = null;
+            "#,
+        )?;
+        assert!(
+            allocating > empty + 1024 * 1024,
+            "peak of {allocating} bytes isn't above {empty} bytes"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_peak_memory_is_reset() -> Result<()> {
+        let mut config = Config::default();
+        config.track_peak_memory(true);
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|this| {
+            this.eval::<(), _>("let big = new Array(100000).fill(1); big = null;")?;
+            Ok::<_, anyhow::Error>(())
+        })?;
+        let peak = runtime.peak_memory().unwrap();
+        runtime.reset_invocation_state();
+        assert!(runtime.peak_memory().unwrap() < peak);
+
+        assert_eq!(None, Runtime::default().peak_memory());
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_loader() -> Result<()> {
+        let util = Runtime::default()
+            .compile_to_bytecode("lib/util.js", "export function double(x) { return x * 2; }")?;
+        let mut config = Config::default();
+        config.module_loader(move |name| {
+            (name == "lib/util.js").then(|| ModuleSource::Bytecode(util.clone()))
+        });
+        let runtime = Runtime::new(config)?;
+        let entry = runtime.compile_to_bytecode(
+            "function.mjs",
+            r#"
+            import { double } from "./lib/util.js";
+            globalThis.staticResult = double(2);
+            import("./lib/util.js").then(({ double }) => {
+              globalThis.dynamicResult = double(3);
+            });
+            "#,
+        )?;
+        runtime.context().with(|this| {
+            unsafe { load_module(this.clone(), &entry)? }.eval()?;
+            Ok::<_, anyhow::Error>(())
+        })?;
+        runtime.resolve_pending_jobs()?;
+
+        runtime.context().with(|this| {
+            assert_eq!(4, this.globals().get::<_, i32>("staticResult")?);
+            assert_eq!(6, this.globals().get::<_, i32>("dynamicResult")?);
+            Ok::<_, anyhow::Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_loader_source() -> Result<()> {
+        let mut config = Config::default();
+        config.module_loader(|name| match name {
+            "a.js" => Some(ModuleSource::Code(
+                r#"export { double } from "./lib/b.js";"#.into(),
+            )),
+            "lib/b.js" => Some(ModuleSource::Code(
+                "export function double(x) { return x * 2; }".into(),
+            )),
+            _ => None,
+        });
+        let runtime = Runtime::new(config)?;
+        runtime.context().with(|this| {
+            Module::evaluate(
+                this.clone(),
+                "function.mjs",
+                r#"import { double } from "./a.js"; globalThis.result = double(4);"#,
+            )?;
+            assert_eq!(8, this.globals().get::<_, i32>("result")?);
+            Ok::<_, anyhow::Error>(())
+        })?;
+        Ok(())
+    }
+
+    #[test]
+    fn test_module_loader_unknown_module() -> Result<()> {
+        let mut config = Config::default();
+        config.module_loader(|_| None);
+        let runtime = Runtime::new(config)?;
+        let err = runtime
+            .compile_to_bytecode("function.mjs", r#"import { x } from "./missing.js";"#)
+            .unwrap_err();
+        assert_eq!(
+            "Error: Error resolving module './missing.js' from 'function.mjs': no such module",
+            err.to_string().trim_end()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_script_source_type() -> Result<()> {
+        let source = r#"
+            globalThis.exported = exports === module.exports;
+            return;
+            globalThis.unreachable = true;
+            // A trailing comment"#;
+        assert!(Runtime::default()
+            .compile_to_bytecode("function.mjs", source)
+            .is_err());
+
+        let mut config = Config::default();
+        config.source_type(SourceType::Script);
+        let runtime = Runtime::new(config)?;
+        let bytecode = runtime.compile_to_bytecode("function.mjs", source)?;
+        runtime.context().with(|this| {
+            unsafe { load_module(this.clone(), &bytecode)? }.eval()?;
+            assert!(this.globals().get::<_, bool>("exported")?);
+            assert_eq!(None, this.globals().get::<_, Option<bool>>("unreachable")?);
+            Ok::<_, anyhow::Error>(())
+        })?;
+        Ok(())
+    }
+}
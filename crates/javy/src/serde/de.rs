@@ -1,3 +1,4 @@
+use crate::apis::blob;
 use crate::quickjs::{
     function::This,
     object::ObjectIter,
@@ -47,6 +48,8 @@ pub struct Deserializer<'js> {
     current_kv: Option<(Value<'js>, Value<'js>)>,
     /// Stack to track circular dependencies.
     stack: Vec<Value<'js>>,
+    /// Whether `Blob` and `File` objects are deserialized as their bytes.
+    blobs_as_bytes: bool,
 }
 
 impl<'de> From<Value<'de>> for Deserializer<'de> {
@@ -58,11 +61,20 @@ impl<'de> From<Value<'de>> for Deserializer<'de> {
             // We are probaby over allocating here. But it's probably fine to
             // over allocate to avoid paying the cost of subsequent allocations.
             stack: Vec::with_capacity(100),
+            blobs_as_bytes: false,
         }
     }
 }
 
 impl<'js> Deserializer<'js> {
+    /// Deserialize `Blob` and `File` objects as their contents, instead of
+    /// as plain objects.
+    #[cfg(feature = "messagepack")]
+    pub(crate) fn blobs_as_bytes(mut self, enable: bool) -> Self {
+        self.blobs_as_bytes = enable;
+        self
+    }
+
     fn deserialize_number<'de, V>(&mut self, visitor: V) -> Result<V::Value>
     where
         V: de::Visitor<'de>,
@@ -193,6 +205,12 @@ impl<'de> de::Deserializer<'de> for &mut Deserializer<'de> {
             return result;
         }
 
+        if self.blobs_as_bytes {
+            if let Some(id) = get_blob_id(&self.value)? {
+                return blob::with_data(id, |data| visitor.visit_bytes(data))?;
+            }
+        }
+
         if self.value.is_object() {
             ensure_supported(&self.value)?;
 
@@ -493,6 +511,18 @@ fn get_to_string<'a>(value: &Value<'a>) -> Option<Function<'a>> {
 }
 
 /// Gets the underlying class id of the value.
+/// Returns the storage ID of the value, if it's a `Blob` or a `File`.
+fn get_blob_id(value: &Value<'_>) -> Result<Option<u32>> {
+    let Some(o) = value.as_object() else {
+        return Ok(None);
+    };
+    let class: Value = value.ctx().globals().get("Blob")?;
+    if !class.is_constructor() || !o.is_instance_of(&class) {
+        return Ok(None);
+    }
+    Ok(o.get("_blobId")?)
+}
+
 fn get_class_id(v: &Value) -> u32 {
     unsafe { JS_GetClassID(v.as_raw()) }
 }
@@ -1,4 +1,4 @@
-use crate::quickjs::{object::Property, Array, Ctx, Object, String as JSString, Value};
+use crate::quickjs::{object::Property, Array, Ctx, Object, String as JSString, TypedArray, Value};
 use crate::serde::err::{Error, Result};
 use anyhow::anyhow;
 
@@ -18,6 +18,9 @@ pub struct Serializer<'js> {
     pub context: Ctx<'js>,
     pub value: Value<'js>,
     pub key: Value<'js>,
+    /// Whether byte buffers are serialized as `Blob` objects rather than as
+    /// `Uint8Array` objects.
+    pub bytes_as_blobs: bool,
 }
 
 impl SerError for Error {
@@ -32,8 +35,17 @@ impl<'js> Serializer<'js> {
             context: context.clone(),
             value: Value::new_undefined(context.clone()),
             key: Value::new_undefined(context),
+            bytes_as_blobs: false,
         })
     }
+
+    /// Creates a serializer for a nested value, inheriting this serializer's
+    /// settings.
+    fn nested(&self) -> Result<Self> {
+        let mut serializer = Self::from_context(self.context.clone())?;
+        serializer.bytes_as_blobs = self.bytes_as_blobs;
+        Ok(serializer)
+    }
 }
 
 impl ser::Serializer for &mut Serializer<'_> {
@@ -214,8 +226,20 @@ impl ser::Serializer for &mut Serializer<'_> {
         Ok(())
     }
 
-    fn serialize_bytes(self, _: &[u8]) -> Result<()> {
-        Err(Error::custom("Cannot serialize bytes"))
+    fn serialize_bytes(self, v: &[u8]) -> Result<()> {
+        let bytes = TypedArray::<u8>::new_copy(self.context.clone(), v)?;
+        if self.bytes_as_blobs {
+            let class: Value = self.context.globals().get("Blob")?;
+            let Some(class) = class.as_constructor() else {
+                return Err(Error::custom("Blob is not enabled"));
+            };
+            let parts = Array::new(self.context.clone())?;
+            parts.set(0, bytes)?;
+            self.value = class.construct((parts,))?;
+        } else {
+            self.value = bytes.into_value();
+        }
+        Ok(())
     }
 }
 
@@ -227,7 +251,7 @@ impl ser::SerializeSeq for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut element_serializer = Serializer::from_context(self.context.clone())?;
+        let mut element_serializer = self.nested()?;
         value.serialize(&mut element_serializer)?;
 
         if let Some(v) = self.value.as_array() {
@@ -251,7 +275,7 @@ impl ser::SerializeTuple for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut element_serializer = Serializer::from_context(self.context.clone())?;
+        let mut element_serializer = self.nested()?;
         value.serialize(&mut element_serializer)?;
 
         if let Some(v) = self.value.as_array() {
@@ -276,7 +300,7 @@ impl ser::SerializeTupleStruct for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut field_serializer = Serializer::from_context(self.context.clone())?;
+        let mut field_serializer = self.nested()?;
         value.serialize(&mut field_serializer)?;
         if let Some(v) = self.value.as_array() {
             return v
@@ -300,7 +324,7 @@ impl ser::SerializeTupleVariant for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut field_serializer = Serializer::from_context(self.context.clone())?;
+        let mut field_serializer = self.nested()?;
         value.serialize(&mut field_serializer)?;
 
         if let Some(v) = self.value.as_array() {
@@ -325,7 +349,7 @@ impl ser::SerializeMap for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut key_serializer = Serializer::from_context(self.context.clone())?;
+        let mut key_serializer = self.nested()?;
         key.serialize(&mut key_serializer)?;
         self.key = key_serializer.value;
         Ok(())
@@ -335,7 +359,7 @@ impl ser::SerializeMap for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut map_serializer = Serializer::from_context(self.context.clone())?;
+        let mut map_serializer = self.nested()?;
         value.serialize(&mut map_serializer)?;
         if let Some(o) = self.value.as_object() {
             let prop = Property::from(map_serializer.value.clone())
@@ -362,7 +386,7 @@ impl ser::SerializeStruct for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut field_serializer = Serializer::from_context(self.context.clone())?;
+        let mut field_serializer = self.nested()?;
         value.serialize(&mut field_serializer)?;
 
         if let Some(o) = self.value.as_object() {
@@ -387,7 +411,7 @@ impl ser::SerializeStructVariant for &mut Serializer<'_> {
     where
         T: ?Sized + Serialize,
     {
-        let mut field_serializer = Serializer::from_context(self.context.clone())?;
+        let mut field_serializer = self.nested()?;
         value.serialize(&mut field_serializer)?;
 
         if let Some(o) = self.value.as_object() {
@@ -0,0 +1,235 @@
+//! Remapping of locations in generated JavaScript to its original sources
+//! with a [source map](https://tc39.es/source-map/).
+//!
+//! Only the `sources` and `mappings` of a source map are used, which lets
+//! stack traces of a bundle name the original files, lines and columns.
+
+use anyhow::{anyhow, bail, Result};
+use std::fmt::Write;
+
+/// A decoded source map.
+#[derive(Debug, Default, Clone)]
+pub struct SourceMap {
+    sources: Vec<String>,
+    /// The segments of each generated line, sorted by generated column.
+    lines: Vec<Vec<Segment>>,
+}
+
+/// A mapping from a generated column to an original location.
+#[derive(Debug, Clone, Copy)]
+struct Segment {
+    column: u32,
+    source: u32,
+    original_line: u32,
+    original_column: u32,
+}
+
+/// A location in an original source, with zero-based line and column.
+#[derive(Debug, PartialEq, Eq)]
+pub struct OriginalLocation<'a> {
+    pub source: &'a str,
+    pub line: u32,
+    pub column: u32,
+}
+
+impl SourceMap {
+    /// Decodes the Base64 VLQ `mappings` of a source map referencing
+    /// `sources`.
+    pub fn new(sources: Vec<String>, mappings: &str) -> Result<Self> {
+        let mut lines = vec![];
+        let mut source = 0i64;
+        let mut original_line = 0i64;
+        let mut original_column = 0i64;
+        for line in mappings.split(';') {
+            let mut segments = vec![];
+            let mut column = 0i64;
+            for segment in line.split(',').filter(|s| !s.is_empty()) {
+                let fields = decode_vlq(segment)?;
+                column += fields[0];
+                match fields.len() {
+                    // Segments without a source don't map anything.
+                    1 => continue,
+                    4 | 5 => {}
+                    len => bail!("Invalid source map segment with {len} fields: {segment}"),
+                }
+                source += fields[1];
+                original_line += fields[2];
+                original_column += fields[3];
+                if source as usize >= sources.len() {
+                    bail!("Source map segment references missing source {source}");
+                }
+                segments.push(Segment {
+                    column: u32::try_from(column)?,
+                    source: u32::try_from(source)?,
+                    original_line: u32::try_from(original_line)?,
+                    original_column: u32::try_from(original_column)?,
+                });
+            }
+            segments.sort_by_key(|segment| segment.column);
+            lines.push(segments);
+        }
+        Ok(Self { sources, lines })
+    }
+
+    /// The original location of a zero-based generated line and column,
+    /// which is the one of the closest segment starting at or before the
+    /// column.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<OriginalLocation<'_>> {
+        let segments = self.lines.get(line as usize)?;
+        let index = segments.partition_point(|segment| segment.column <= column);
+        let segment = segments.get(index.checked_sub(1)?)?;
+        Some(OriginalLocation {
+            source: &self.sources[segment.source as usize],
+            line: segment.original_line,
+            column: segment.original_column,
+        })
+    }
+
+    /// Replaces the `file:LINE:COLUMN` locations in a stack trace or an error
+    /// message, with one-based lines and columns like QuickJS reports them,
+    /// by their original locations. Locations without a mapping are left
+    /// as is.
+    pub fn remap_stack(&self, text: &str, file: &str) -> String {
+        let prefix = format!("{file}:");
+        let mut remapped = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(start) = rest.find(&prefix) {
+            remapped.push_str(&rest[..start]);
+            let position = &rest[start + prefix.len()..];
+            let original = parse_position(position).and_then(|(line, column, len)| {
+                let location = self.lookup(line.checked_sub(1)?, column.checked_sub(1)?)?;
+                Some((location, len))
+            });
+            match original {
+                Some((location, len)) => {
+                    let _ = write!(
+                        remapped,
+                        "{}:{}:{}",
+                        location.source,
+                        location.line + 1,
+                        location.column + 1
+                    );
+                    rest = &position[len..];
+                }
+                None => {
+                    remapped.push_str(&prefix);
+                    rest = position;
+                }
+            }
+        }
+        remapped.push_str(rest);
+        remapped
+    }
+}
+
+/// Parses the `LINE:COLUMN` at the start of `text`, returning the line, the
+/// column and the length of the position.
+fn parse_position(text: &str) -> Option<(u32, u32, usize)> {
+    let digits = |s: &str| s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let line_len = digits(text);
+    let line = text[..line_len].parse().ok()?;
+    let rest = text[line_len..].strip_prefix(':')?;
+    let column_len = digits(rest);
+    let column = rest[..column_len].parse().ok()?;
+    Some((line, column, line_len + 1 + column_len))
+}
+
+/// Decodes the Base64 VLQ values of a segment.
+fn decode_vlq(segment: &str) -> Result<Vec<i64>> {
+    let mut values = vec![];
+    let mut value = 0i64;
+    let mut shift = 0;
+    for c in segment.bytes() {
+        let digit = base64_value(c)
+            .ok_or_else(|| anyhow!("Invalid Base64 VLQ character {:?}", c as char))?;
+        if shift > 60 {
+            bail!("Base64 VLQ value too large in segment {segment}");
+        }
+        value |= i64::from(digit & 0b11111) << shift;
+        if digit & 0b100000 != 0 {
+            shift += 5;
+            continue;
+        }
+        // The least significant bit is the sign.
+        let magnitude = value >> 1;
+        values.push(if value & 1 == 1 {
+            -magnitude
+        } else {
+            magnitude
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 {
+        bail!("Truncated Base64 VLQ value in segment {segment}");
+    }
+    Ok(values)
+}
+
+fn base64_value(c: u8) -> Option<u8> {
+    match c {
+        b'A'..=b'Z' => Some(c - b'A'),
+        b'a'..=b'z' => Some(c - b'a' + 26),
+        b'0'..=b'9' => Some(c - b'0' + 52),
+        b'+' => Some(62),
+        b'/' => Some(63),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_vlq() -> Result<()> {
+        assert_eq!(vec![0, 0, 0, 0], decode_vlq("AAAA")?);
+        assert_eq!(vec![1, -1, 15, 16], decode_vlq("CDegB")?);
+        assert_eq!(vec![-1024, 123456], decode_vlq("hgCgkxH")?);
+        assert!(decode_vlq("g").is_err());
+        assert!(decode_vlq("A!").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lookup() -> Result<()> {
+        // Line 1: column 0 maps to a.ts 0:0, column 4 to a.ts 1:2.
+        // Line 2: column 2 maps to b.ts 9:4.
+        let map = SourceMap::new(vec!["a.ts".into(), "b.ts".into()], "AAAA,IACE;ECQE")?;
+
+        let location = |source, line, column| OriginalLocation {
+            source,
+            line,
+            column,
+        };
+        assert_eq!(Some(location("a.ts", 0, 0)), map.lookup(0, 0));
+        assert_eq!(Some(location("a.ts", 0, 0)), map.lookup(0, 3));
+        assert_eq!(Some(location("a.ts", 1, 2)), map.lookup(0, 4));
+        assert_eq!(Some(location("a.ts", 1, 2)), map.lookup(0, 100));
+        assert_eq!(None, map.lookup(1, 1));
+        assert_eq!(Some(location("b.ts", 9, 4)), map.lookup(1, 2));
+        assert_eq!(None, map.lookup(2, 0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_mappings() {
+        assert!(SourceMap::new(vec!["a.ts".into()], "ACAA").is_err());
+        assert!(SourceMap::new(vec!["a.ts".into()], "AA").is_err());
+    }
+
+    #[test]
+    fn test_remap_stack() -> Result<()> {
+        let map = SourceMap::new(vec!["src/index.ts".into()], "AAAA;IASI")?;
+        let stack = "Error: oops\n    at foo (function.mjs:2:5)\n    at <anonymous> (function.mjs:7:1)\n    at bar (other.js:2:5)";
+        assert_eq!(
+            "Error: oops\n    at foo (src/index.ts:10:5)\n    at <anonymous> (function.mjs:7:1)\n    at bar (other.js:2:5)",
+            map.remap_stack(stack, "function.mjs")
+        );
+        assert_eq!(
+            "function.mjs:x",
+            map.remap_stack("function.mjs:x", "function.mjs")
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,296 @@
+//! Checks of the framing of QuickJS bytecode before it's loaded, so corrupt
+//! or truncated bytecode is reported instead of being read by QuickJS.
+
+use anyhow::{anyhow, bail, Error, Result};
+use std::fmt::Write;
+
+/// The flag QuickJS sets in the version of the bytecode it writes when it's
+/// built with `CONFIG_BIGNUM`.
+const BC_VERSION_BIGNUM_FLAG: u8 = 0x40;
+
+/// The latest version of the bytecode written by QuickJS, without the bignum
+/// flag.
+const MAX_BC_VERSION: u8 = 3;
+
+/// The tag of a serialized module, `BC_TAG_MODULE` in QuickJS.
+const TAG_MODULE: u8 = 13;
+
+/// The type of the export entries of the variables of the module itself,
+/// `JS_EXPORT_TYPE_LOCAL` in QuickJS. The other entries re-export a binding
+/// of an imported module.
+const EXPORT_TYPE_LOCAL: u8 = 0;
+
+/// The number of leading bytes shown in the errors about invalid bytecode.
+const SHOWN_BYTES: usize = 16;
+
+/// Checks the bytecode starts with a QuickJS bytecode version, followed by
+/// an atom table fitting in the bytecode and a module.
+///
+/// With an expected version, the bytecode must have been written by a
+/// QuickJS with that version rather than any known one.
+pub(crate) fn validate(bytecode: &[u8], expected_version: Option<u8>) -> Result<()> {
+    let mut reader = Reader { bytecode, pos: 0 };
+    reader.module(expected_version, |_, _| {})
+}
+
+/// An atom referenced by bytecode.
+#[derive(Debug, PartialEq)]
+pub(crate) enum Atom {
+    /// An atom predefined by QuickJS, with its index.
+    Predefined(u32),
+    /// An integer.
+    Int(u32),
+    /// A string of the atom table of the bytecode.
+    String(String),
+}
+
+/// The index the first atom of the atom table of the bytecode is referenced
+/// with, which is the number of atoms predefined by QuickJS.
+///
+/// The module must have a name that isn't a predefined atom, which is the
+/// first atom QuickJS adds to the atom table when writing bytecode.
+pub(crate) fn first_atom(bytecode: &[u8]) -> Result<u32> {
+    let mut reader = Reader { bytecode, pos: 0 };
+    reader.module(None, |_, _| {})?;
+    match reader.leb128()? {
+        atom if atom & 1 == 0 => Ok(atom >> 1),
+        _ => bail!("bytecode doesn't define a named module"),
+    }
+}
+
+/// The names exported by the module in the bytecode, in the order they are
+/// exported, read without loading the module. The names re-exported with
+/// `export * from` aren't included since they're only known once the
+/// imported modules are resolved.
+///
+/// Atoms referenced with an index below `first_atom` are predefined.
+pub(crate) fn export_names(bytecode: &[u8], first_atom: u32) -> Result<Vec<Atom>> {
+    let mut reader = Reader { bytecode, pos: 0 };
+    let mut atoms = vec![];
+    reader.module(None, |bytes, wide| atoms.push(decode_atom(bytes, wide)))?;
+    let atom = |reader: &mut Reader| -> Result<Atom> {
+        let atom = reader.leb128()?;
+        let index = atom >> 1;
+        if atom & 1 == 1 {
+            Ok(Atom::Int(index))
+        } else if index < first_atom {
+            Ok(Atom::Predefined(index))
+        } else {
+            atoms
+                .get((index - first_atom) as usize)
+                .map(|atom| Atom::String(atom.clone()))
+                .ok_or_else(|| anyhow!("atom {index} isn't in the atom table"))
+        }
+    };
+
+    // The name of the module and of the modules it imports.
+    atom(&mut reader)?;
+    for _ in 0..reader.leb128()? {
+        atom(&mut reader)?;
+    }
+
+    let mut names = vec![];
+    for _ in 0..reader.leb128()? {
+        // The index of the variable, or the index of the imported module and
+        // the name of the binding it exports.
+        if reader.u8()? == EXPORT_TYPE_LOCAL {
+            reader.leb128()?;
+        } else {
+            reader.leb128()?;
+            atom(&mut reader)?;
+        }
+        names.push(atom(&mut reader)?);
+    }
+    Ok(names)
+}
+
+/// Decodes a string of the atom table, made of Latin-1 characters or, if
+/// it's wide, of UTF-16 code units.
+fn decode_atom(bytes: &[u8], wide: bool) -> String {
+    if wide {
+        let units: Vec<_> = bytes
+            .chunks_exact(2)
+            .map(|unit| u16::from_le_bytes([unit[0], unit[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        bytes.iter().copied().map(char::from).collect()
+    }
+}
+
+/// Adds the length and the first bytes of the bytecode to an error about
+/// loading it, to diagnose corrupted bytecode.
+pub(crate) fn invalid_bytecode_error(bytecode: &[u8], e: Error) -> Error {
+    let mut hex = String::new();
+    for byte in bytecode.iter().take(SHOWN_BYTES) {
+        let _ = write!(hex, "{byte:02x}");
+    }
+    let ellipsis = if bytecode.len() > SHOWN_BYTES {
+        "..."
+    } else {
+        ""
+    };
+    anyhow!(
+        "Invalid QuickJS bytecode of {} bytes starting with {hex}{ellipsis}: {e}",
+        bytecode.len()
+    )
+}
+
+struct Reader<'a> {
+    bytecode: &'a [u8],
+    pos: usize,
+}
+
+impl Reader<'_> {
+    /// Reads the version, passes the strings of the atom table to `atom`
+    /// with whether they're wide, and reads the tag of the module.
+    fn module(
+        &mut self,
+        expected_version: Option<u8>,
+        mut atom: impl FnMut(&[u8], bool),
+    ) -> Result<()> {
+        let version = self.u8()?;
+        match expected_version {
+            Some(expected) if version != expected => bail!(
+                "bytecode version {version:#04x} doesn't match the version {expected:#04x} of the plugin"
+            ),
+            None if !(1..=MAX_BC_VERSION).contains(&(version & !BC_VERSION_BIGNUM_FLAG)) => {
+                bail!("unknown bytecode version {version:#04x}")
+            }
+            _ => {}
+        }
+        for _ in 0..self.leb128()? {
+            let len = self.leb128()?;
+            let (len, wide) = ((len >> 1) as usize, len & 1 == 1);
+            let start = self.pos;
+            self.skip(if wide { len * 2 } else { len })?;
+            atom(&self.bytecode[start..self.pos], wide);
+        }
+        if self.u8()? != TAG_MODULE {
+            bail!("bytecode doesn't define a module");
+        }
+        Ok(())
+    }
+
+    fn skip(&mut self, len: usize) -> Result<()> {
+        match self.pos.checked_add(len) {
+            Some(end) if end <= self.bytecode.len() => {
+                self.pos = end;
+                Ok(())
+            }
+            _ => bail!("bytecode is truncated"),
+        }
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        let byte = *self
+            .bytecode
+            .get(self.pos)
+            .ok_or_else(|| anyhow!("bytecode is truncated"))?;
+        self.pos += 1;
+        Ok(byte)
+    }
+
+    fn leb128(&mut self) -> Result<u32> {
+        let mut value = 0u32;
+        for shift in (0..32).step_by(7) {
+            let byte = self.u8()?;
+            value |= u32::from(byte & 0x7f) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        bail!("invalid LEB128 number in bytecode")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The version, an atom table with the narrow string "a" and the wide
+    // string "b", and a module tag.
+    const BYTECODE: &[u8] = &[0x43, 2, 2, b'a', 3, b'b', 0, TAG_MODULE, 0];
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(BYTECODE, None).is_ok());
+        assert!(validate(BYTECODE, Some(0x43)).is_ok());
+
+        let error = |bytecode: &[u8], expected_version| {
+            validate(bytecode, expected_version)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!("bytecode is truncated", error(&[], None));
+        assert_eq!("bytecode is truncated", error(&BYTECODE[..5], None));
+        assert_eq!(
+            "unknown bytecode version 0x47",
+            error(&[0x47, 0, TAG_MODULE], None)
+        );
+        assert_eq!(
+            "bytecode version 0x43 doesn't match the version 0x03 of the plugin",
+            error(BYTECODE, Some(0x03))
+        );
+        assert_eq!(
+            "bytecode doesn't define a module",
+            error(&[0x43, 0, 12], None)
+        );
+    }
+
+    #[test]
+    fn test_export_names() {
+        // The atoms `function.mjs`, `foo` and `./dep.mjs` following 200
+        // predefined atoms, and a module named `function.mjs` importing
+        // `./dep.mjs` and exporting its variables as `foo` and as a
+        // predefined atom, and re-exporting `foo` of `./dep.mjs` as `7`.
+        let mut bytecode = vec![0x43, 3, 24];
+        bytecode.extend(b"function.mjs");
+        bytecode.extend([6]);
+        bytecode.extend(b"foo");
+        bytecode.extend([18]);
+        bytecode.extend(b"./dep.mjs");
+        bytecode.extend([TAG_MODULE, 0x90, 0x03, 1, 0x94, 0x03, 3]);
+        bytecode.extend([EXPORT_TYPE_LOCAL, 0, 0x92, 0x03]);
+        bytecode.extend([EXPORT_TYPE_LOCAL, 1, 44]);
+        bytecode.extend([1, 0, 0x92, 0x03, 15]);
+
+        assert_eq!(200, first_atom(&bytecode).unwrap());
+        assert_eq!(
+            vec![
+                Atom::String("foo".into()),
+                Atom::Predefined(22),
+                Atom::Int(7)
+            ],
+            export_names(&bytecode, 200).unwrap()
+        );
+        assert_eq!(
+            "atom 202 isn't in the atom table",
+            export_names(&bytecode, 199).unwrap_err().to_string()
+        );
+        assert_eq!(
+            "bytecode is truncated",
+            export_names(&bytecode[..bytecode.len() - 1], 200)
+                .unwrap_err()
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn test_decode_atom() {
+        assert_eq!("caf\u{e9}", decode_atom(&[b'c', b'a', b'f', 0xe9], false));
+        assert_eq!("\u{3c0}", decode_atom(&[0xc0, 0x03], true));
+    }
+
+    #[test]
+    fn test_invalid_bytecode_error() {
+        assert_eq!(
+            "Invalid QuickJS bytecode of 3 bytes starting with 43000d: oops",
+            invalid_bytecode_error(&[0x43, 0, 13], anyhow!("oops")).to_string()
+        );
+        assert_eq!(
+            "Invalid QuickJS bytecode of 20 bytes starting with 00000000000000000000000000000000...: oops",
+            invalid_bytecode_error(&[0; 20], anyhow!("oops")).to_string()
+        );
+    }
+}
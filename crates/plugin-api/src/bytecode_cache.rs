@@ -0,0 +1,145 @@
+//! A least recently used cache of the bytecode compiled by `compile_src`.
+
+use anyhow::Result;
+use std::collections::VecDeque;
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+/// Bytecode compiled from JS source code, keyed by a hash of the source, the
+/// name of the module and the fingerprint of the runtime config, since the
+/// config can change the emitted bytecode.
+///
+/// Caches are expected to be small, so entries are looked up linearly.
+pub(crate) struct BytecodeCache {
+    /// The maximum number of entries. The cache is disabled when it's 0.
+    capacity: usize,
+    /// The fingerprint of the config of the runtime compiling the bytecode.
+    config_fingerprint: u64,
+    /// The cached bytecode by key, from the least to the most recently used.
+    entries: VecDeque<(u64, Vec<u8>)>,
+    /// The number of compilations served from the cache.
+    hits: usize,
+}
+
+impl BytecodeCache {
+    /// Creates a disabled cache.
+    pub(crate) const fn new() -> Self {
+        Self {
+            capacity: 0,
+            config_fingerprint: 0,
+            entries: VecDeque::new(),
+            hits: 0,
+        }
+    }
+
+    /// Sets the maximum number of entries, evicting the least recently used
+    /// ones above it, and the fingerprint of the config of the runtime
+    /// compiling the bytecode.
+    pub(crate) fn configure(&mut self, capacity: usize, config_fingerprint: u64) {
+        self.capacity = capacity;
+        self.config_fingerprint = config_fingerprint;
+        while self.entries.len() > capacity {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Returns the cached bytecode of the module with name and source, or
+    /// caches the bytecode returned by `compile`.
+    pub(crate) fn get_or_compile(
+        &mut self,
+        name: &str,
+        js_src: &str,
+        compile: impl FnOnce() -> Result<Vec<u8>>,
+    ) -> Result<Vec<u8>> {
+        if self.capacity == 0 {
+            return compile();
+        }
+
+        let key = self.key(name, js_src);
+        if let Some(index) = self.entries.iter().position(|(k, _)| *k == key) {
+            let entry = self.entries.remove(index).unwrap();
+            let bytecode = entry.1.clone();
+            self.entries.push_back(entry);
+            self.hits += 1;
+            return Ok(bytecode);
+        }
+
+        let bytecode = compile()?;
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((key, bytecode.clone()));
+        Ok(bytecode)
+    }
+
+    /// The number of compilations served from the cache.
+    pub(crate) fn hits(&self) -> usize {
+        self.hits
+    }
+
+    fn key(&self, name: &str, js_src: &str) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.config_fingerprint.hash(&mut hasher);
+        name.hash(&mut hasher);
+        js_src.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(cache: &mut BytecodeCache, js_src: &str) -> Vec<u8> {
+        cache
+            .get_or_compile("function.mjs", js_src, || Ok(js_src.as_bytes().to_vec()))
+            .unwrap()
+    }
+
+    #[test]
+    fn test_disabled_cache() {
+        let mut cache = BytecodeCache::new();
+        compile(&mut cache, "1");
+        compile(&mut cache, "1");
+        assert_eq!(0, cache.hits());
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = BytecodeCache::new();
+        cache.configure(2, 0);
+        compile(&mut cache, "1");
+        compile(&mut cache, "2");
+        // Makes `2` the least recently used.
+        compile(&mut cache, "1");
+        assert_eq!(1, cache.hits());
+
+        compile(&mut cache, "3");
+        compile(&mut cache, "1");
+        assert_eq!(2, cache.hits());
+        compile(&mut cache, "2");
+        assert_eq!(2, cache.hits());
+    }
+
+    #[test]
+    fn test_keyed_by_config_fingerprint() {
+        let mut cache = BytecodeCache::new();
+        cache.configure(2, 1);
+        compile(&mut cache, "1");
+        cache.configure(2, 2);
+        compile(&mut cache, "1");
+        assert_eq!(0, cache.hits());
+        compile(&mut cache, "1");
+        assert_eq!(1, cache.hits());
+    }
+
+    #[test]
+    fn test_compile_errors_are_not_cached() {
+        let mut cache = BytecodeCache::new();
+        cache.configure(2, 0);
+        assert!(cache
+            .get_or_compile("function.mjs", "(", || anyhow::bail!("oops"))
+            .is_err());
+        compile(&mut cache, "(");
+        assert_eq!(0, cache.hits());
+    }
+}
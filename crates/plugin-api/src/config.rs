@@ -1,6 +1,17 @@
+use anyhow::{bail, Result};
 use std::ops::{Deref, DerefMut};
 
-#[derive(Default)]
+/// How the instance ends after an error when aborting on errors is enabled
+/// with [`Config::abort_on_error`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExitStrategy {
+    /// Aborts, which the host sees as an `unreachable` trap.
+    #[default]
+    Abort,
+    /// Exits with the code, which the host sees as a WASI exit status.
+    ExitCode(u8),
+}
+
 /// A configuration for the Javy plugin API.
 pub struct Config {
     /// The runtime config.
@@ -11,6 +22,38 @@ pub struct Config {
     pub(crate) wait_for_completion: bool,
     /// Maximum time to wait for async operations in milliseconds. None means infinite wait.
     pub(crate) wait_timeout_ms: Option<u64>,
+    /// Whether to cancel pending timers when the wait timeout is reached.
+    pub(crate) cancel_timers_on_timeout: bool,
+    /// Whether the `list_exports` export can list the exported names.
+    pub(crate) list_exports: bool,
+    /// Whether errors abort the instance.
+    pub(crate) abort_on_error: bool,
+    /// How errors end the instance when they abort it.
+    pub(crate) error_exit_strategy: ExitStrategy,
+    /// The number of compiled bytecode buffers cached by `compile_src`.
+    pub(crate) bytecode_cache_entries: usize,
+    /// Whether bytecode written by a different QuickJS version is rejected.
+    pub(crate) strict_bytecode: bool,
+    /// Whether errors are written to stderr as a line of JSON.
+    pub(crate) structured_errors: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            runtime_config: javy::Config::default(),
+            event_loop: false,
+            wait_for_completion: false,
+            wait_timeout_ms: None,
+            cancel_timers_on_timeout: false,
+            list_exports: false,
+            abort_on_error: true,
+            error_exit_strategy: ExitStrategy::Abort,
+            bytecode_cache_entries: 0,
+            strict_bytecode: false,
+            structured_errors: false,
+        }
+    }
 }
 
 impl Config {
@@ -40,6 +83,81 @@ impl Config {
         self.wait_timeout_ms = timeout_ms;
         self
     }
+
+    /// Whether to cancel the pending timers, including intervals, when the
+    /// wait timeout is reached. Otherwise they're left queued (default).
+    pub fn cancel_timers_on_timeout(&mut self, enabled: bool) -> &mut Self {
+        self.cancel_timers_on_timeout = enabled;
+        self
+    }
+
+    /// Whether the `list_exports` export can list the names exported by a
+    /// module, without evaluating it.
+    pub fn list_exports(&mut self, enabled: bool) -> &mut Self {
+        self.list_exports = enabled;
+        self
+    }
+
+    /// Whether errors reported by the exports invoking JS functions abort the
+    /// instance after being written to stderr (default). Otherwise the
+    /// exports return normally, with a null pointer for the exports returning
+    /// one, so embedders can recover.
+    pub fn abort_on_error(&mut self, enabled: bool) -> &mut Self {
+        self.abort_on_error = enabled;
+        self
+    }
+
+    /// How the instance ends after an error is written to stderr, when errors
+    /// abort it. [`ExitStrategy::Abort`] (default) traps, while
+    /// [`ExitStrategy::ExitCode`] exits with the code so the host can tell a
+    /// failed script from a crashed instance.
+    pub fn error_exit_strategy(&mut self, strategy: ExitStrategy) -> &mut Self {
+        self.error_exit_strategy = strategy;
+        self
+    }
+
+    /// The number of bytecode buffers compiled by `compile_src` and
+    /// `compile_src_named` to cache, so compiling the same source again in an
+    /// instance skips parsing it. The least recently used buffers are evicted
+    /// first. Default is 0, disabling the cache.
+    pub fn bytecode_cache_entries(&mut self, entries: usize) -> &mut Self {
+        self.bytecode_cache_entries = entries;
+        self
+    }
+
+    /// Whether the exports evaluating bytecode reject bytecode whose version
+    /// differs from the version of the bytecode compiled by the plugin, rather
+    /// than only bytecode without a known QuickJS version. Disabled by
+    /// default.
+    pub fn strict_bytecode(&mut self, enabled: bool) -> &mut Self {
+        self.strict_bytecode = enabled;
+        self
+    }
+
+    /// Whether errors are written to stderr as a single line of JSON with the
+    /// `type` of the error (`js-error`, `timeout` or `pending-jobs`), its
+    /// `message`, its JS `stack` and the `exitCode` of the instance, instead
+    /// of free-form text. Disabled by default.
+    pub fn structured_errors(&mut self, enabled: bool) -> &mut Self {
+        self.structured_errors = enabled;
+        self
+    }
+
+    pub(crate) fn validate(self) -> Result<Self> {
+        if self.wait_for_completion && !self.event_loop {
+            bail!("wait_for_completion requires event_loop to be enabled");
+        }
+
+        if self.wait_timeout_ms.is_some() && !self.wait_for_completion {
+            bail!("wait_timeout_ms requires wait_for_completion to be enabled");
+        }
+
+        if self.cancel_timers_on_timeout && self.wait_timeout_ms.is_none() {
+            bail!("cancel_timers_on_timeout requires wait_timeout_ms to be set");
+        }
+
+        Ok(self)
+    }
 }
 
 impl Deref for Config {
@@ -0,0 +1,200 @@
+use std::{collections::BTreeMap, rc::Rc};
+
+use anyhow::{anyhow, bail, Result};
+use javy::{
+    hold, hold_and_release,
+    quickjs::{Array, Ctx, Function, Object, String as JSString, Value},
+    to_js_error, val_to_string, Args, Runtime,
+};
+use serde::{Deserialize, Serialize};
+
+/// The maximum nesting depth of the values passed to and returned by host
+/// functions.
+const MAX_DEPTH: usize = 1000;
+
+/// A JavaScript value passed to or returned by a host function.
+///
+/// It mirrors the JSON data model, with the addition of `undefined`, so it can
+/// be converted to and from other types with `serde`. Functions, symbols and
+/// other values that don't fit in this model can't be passed to host
+/// functions.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum JsValue {
+    /// `null`.
+    Null,
+    /// `undefined`, serialized as `null`.
+    Undefined,
+    /// A boolean.
+    Bool(bool),
+    /// A number.
+    Number(f64),
+    /// A string.
+    String(String),
+    /// An array.
+    Array(Vec<JsValue>),
+    /// An object with its own enumerable string-keyed properties.
+    Object(BTreeMap<String, JsValue>),
+}
+
+type Callback = dyn Fn(&[JsValue]) -> Result<JsValue>;
+
+/// A host function to register with [`RuntimeExt::register_namespace`].
+pub struct HostFunction {
+    name: String,
+    callback: Rc<Callback>,
+}
+
+impl HostFunction {
+    /// A host function named `name` calling `callback` with the arguments it's
+    /// called with from JavaScript.
+    pub fn new(
+        name: impl Into<String>,
+        callback: impl Fn(&[JsValue]) -> Result<JsValue> + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            callback: Rc::new(callback),
+        }
+    }
+}
+
+/// Registers Rust functions callable from JavaScript on a [`Runtime`], without
+/// having to deal with QuickJS values and their lifetimes.
+///
+/// Errors returned by the functions are thrown as JavaScript errors with the
+/// same message.
+pub trait RuntimeExt {
+    /// Makes `callback` available as the global function `name`.
+    fn register_function(
+        &self,
+        name: &str,
+        callback: impl Fn(&[JsValue]) -> Result<JsValue> + 'static,
+    ) -> Result<()>;
+
+    /// Makes the `functions` available as methods of the global object
+    /// `namespace`, which is created if it doesn't exist yet.
+    fn register_namespace(
+        &self,
+        namespace: &str,
+        functions: impl IntoIterator<Item = HostFunction>,
+    ) -> Result<()>;
+}
+
+impl RuntimeExt for Runtime {
+    fn register_function(
+        &self,
+        name: &str,
+        callback: impl Fn(&[JsValue]) -> Result<JsValue> + 'static,
+    ) -> Result<()> {
+        self.context().with(|cx| {
+            let function = host_function(cx.clone(), Rc::new(callback))?;
+            cx.globals().set(name, function)?;
+            Ok(())
+        })
+    }
+
+    fn register_namespace(
+        &self,
+        namespace: &str,
+        functions: impl IntoIterator<Item = HostFunction>,
+    ) -> Result<()> {
+        self.context().with(|cx| {
+            let globals = cx.globals();
+            let object = match globals.get::<_, Value>(namespace)? {
+                value if value.is_undefined() => {
+                    let object = Object::new(cx.clone())?;
+                    globals.set(namespace, object.clone())?;
+                    object
+                }
+                value => value
+                    .into_object()
+                    .ok_or_else(|| anyhow!("{namespace} is not an object"))?,
+            };
+            for HostFunction { name, callback } in functions {
+                object.set(name, host_function(cx.clone(), callback)?)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+fn host_function(cx: Ctx<'_>, callback: Rc<Callback>) -> Result<Function<'_>> {
+    Ok(Function::new(cx, move |cx, args| {
+        let (cx, args) = hold_and_release!(cx, args);
+        call(hold!(cx.clone(), args), &*callback).map_err(|e| to_js_error(cx, e))
+    })?)
+}
+
+fn call<'js>(args: Args<'js>, callback: &Callback) -> Result<Value<'js>> {
+    let (cx, args) = args.release();
+    let args = args
+        .iter()
+        .map(|arg| from_js(&cx, arg.clone(), 0))
+        .collect::<Result<Vec<_>>>()?;
+    to_js(&cx, &callback(&args)?, 0)
+}
+
+fn from_js<'js>(cx: &Ctx<'js>, value: Value<'js>, depth: usize) -> Result<JsValue> {
+    if depth > MAX_DEPTH {
+        bail!("Value is nested too deeply");
+    }
+
+    if value.is_undefined() {
+        Ok(JsValue::Undefined)
+    } else if value.is_null() {
+        Ok(JsValue::Null)
+    } else if let Some(b) = value.as_bool() {
+        Ok(JsValue::Bool(b))
+    } else if let Some(n) = value.as_number() {
+        Ok(JsValue::Number(n))
+    } else if value.is_string() {
+        Ok(JsValue::String(val_to_string(cx, value)?))
+    } else if let Some(array) = value.as_array() {
+        array
+            .iter()
+            .map(|element| from_js(cx, element?, depth + 1))
+            .collect::<Result<_>>()
+            .map(JsValue::Array)
+    } else if value.is_function() || !value.is_object() {
+        bail!("Unsupported value type: {}", value.type_name())
+    } else {
+        let object = value.as_object().unwrap();
+        object
+            .props::<JSString, Value>()
+            .map(|prop| {
+                let (key, value) = prop?;
+                Ok((key.to_string()?, from_js(cx, value, depth + 1)?))
+            })
+            .collect::<Result<_>>()
+            .map(JsValue::Object)
+    }
+}
+
+fn to_js<'js>(cx: &Ctx<'js>, value: &JsValue, depth: usize) -> Result<Value<'js>> {
+    if depth > MAX_DEPTH {
+        bail!("Value is nested too deeply");
+    }
+
+    Ok(match value {
+        JsValue::Null => Value::new_null(cx.clone()),
+        JsValue::Undefined => Value::new_undefined(cx.clone()),
+        JsValue::Bool(b) => Value::new_bool(cx.clone(), *b),
+        JsValue::Number(n) => Value::new_number(cx.clone(), *n),
+        JsValue::String(s) => JSString::from_str(cx.clone(), s)?.into_value(),
+        JsValue::Array(elements) => {
+            let array = Array::new(cx.clone())?;
+            for (i, element) in elements.iter().enumerate() {
+                array.set(i, to_js(cx, element, depth + 1)?)?;
+            }
+            array.into_value()
+        }
+        JsValue::Object(properties) => {
+            let object = Object::new(cx.clone())?;
+            for (key, value) in properties {
+                object.set(key, to_js(cx, value, depth + 1)?)?;
+            }
+            object.into_value()
+        }
+    })
+}
@@ -6,8 +6,8 @@
 //! use javy_plugin_api::Config;
 //!
 //! // Dynamically linked modules will use `my_javy_plugin_v1` as the import
-//! // namespace.
-//! import_namespace!("my_javy_plugin_v1");
+//! // namespace, and record they were linked against ABI version 1 of it.
+//! import_namespace!("my_javy_plugin_v1", 1);
 //!
 //! #[export_name = "initialize_runtime"]
 //! pub extern "C" fn initialize_runtime() {
@@ -25,10 +25,16 @@
 //!
 //! # Core concepts
 //! * [`javy`] - a re-export of the [`javy`] crate.
-//! * [`import_namespace`] - required to provide an import namespace when the
-//!   plugin is used to generate dynamically linked modules.
+//! * [`import_namespace`] - required to provide an import namespace, and
+//!   optionally an ABI version, when the plugin is used to generate
+//!   dynamically linked modules.
 //! * [`initialize_runtime`] - used to configure the QuickJS runtime with a
 //!   [`Config`] to add behavior to the created [`javy::Runtime`].
+//! * [`initialize_runtime_with`] - like [`initialize_runtime`], additionally
+//!   setting up the context, for example injecting globals with
+//!   [`set_global_json`].
+//! * [`RuntimeExt`] - used in [`initialize_runtime`] to expose host functions
+//!   to JavaScript, taking and returning [`JsValue`]s.
 //!
 //! # Features
 //! * `json` - enables the `json` feature in the `javy` crate.
@@ -38,26 +44,95 @@
 // and we can safely reason about the accesses to the Javy Runtime. We also
 // don't want to introduce overhead from taking unnecessary mutex locks.
 #![allow(static_mut_refs)]
-use anyhow::{anyhow, bail, Error, Result};
-pub use config::Config;
-use javy::quickjs::{self, Ctx, Error as JSError, Function, Module, Value};
-use javy::{from_js_error, Runtime};
+use anyhow::{anyhow, bail, Context, Error, Result};
+use bytecode_cache::BytecodeCache;
+pub use config::{Config, ExitStrategy};
+use flate2::read::DeflateDecoder;
+pub use host_functions::{HostFunction, JsValue, RuntimeExt};
+use javy::quickjs::{
+    self, function::Rest, module::Declared, qjs, Ctx, Error as JSError, Function, Module, Object,
+    Persistent, PromiseState, Value,
+};
+use javy::source_map::SourceMap;
+use javy::{blob_registry, from_js_error, load_module, JsException, ModuleSource, Runtime};
+use serde::{Deserialize, Serialize};
 use std::cell::OnceCell;
-use std::{process, slice, str};
+use std::collections::BTreeMap;
+use std::ffi::CStr;
+use std::io::{self, Read, Write};
+use std::{panic, process, ptr, slice, str};
 
 pub use javy;
 
+mod bytecode;
+mod bytecode_cache;
 mod config;
+mod host_functions;
 mod namespace;
+mod wit;
 
+/// The name of the module evaluating the JS source code, unless it's
+/// compiled with [`compile_src_named`] or named with
+/// [`compile_src_with_modules`].
 const FUNCTION_MODULE_NAME: &str = "function.mjs";
 
 static mut COMPILE_SRC_RET_AREA: [u32; 2] = [0; 2];
+/// The message of the last error returned by `compile_src`, which stays valid
+/// until the next compilation fails.
+static mut COMPILE_SRC_ERROR: String = String::new();
+static mut LIST_EXPORTS_RET_AREA: [u32; 2] = [0; 2];
+static mut INVOKE_WITH_RESULT_RET_AREA: [u32; 2] = [0; 2];
+static mut RUNTIME_STATS_RET_AREA: [u32; 2] = [0; 2];
+static mut INVOKE_WITH_ARGS_RET_AREA: u64 = 0;
+
+/// The memory holding the result of the last `invoke_with_args` call.
+static mut INVOKE_WITH_ARGS_ALLOCATIONS: wit::Allocations = wit::Allocations::new();
+
+/// The bytecode buffer returned by the last `compile_src` call, released by
+/// the next call unless `free_bytecode` released it first.
+static mut BYTECODE_BUFFER: Option<Box<[u8]>> = None;
+
+/// The bytecode compiled by `compile_src`, if enabled with
+/// [`Config::bytecode_cache_entries`].
+static mut BYTECODE_CACHE: BytecodeCache = BytecodeCache::new();
+
+/// The modules the evaluated modules can import, by name.
+static mut MODULES: BTreeMap<String, ModuleSource> = BTreeMap::new();
 
 static mut RUNTIME: OnceCell<Runtime> = OnceCell::new();
 static mut EVENT_LOOP_ENABLED: bool = false;
 static mut WAIT_FOR_COMPLETION: bool = false;
 static mut WAIT_TIMEOUT_MS: Option<u64> = None;
+static mut CANCEL_TIMERS_ON_TIMEOUT: bool = false;
+/// The index the first atom of the atom table of bytecode is referenced with,
+/// if [`Config::list_exports`] is enabled.
+static mut LIST_EXPORTS_FIRST_ATOM: Option<u32> = None;
+static mut ABORT_ON_ERROR: bool = true;
+static mut ERROR_EXIT_STRATEGY: ExitStrategy = ExitStrategy::Abort;
+static mut STRUCTURED_ERRORS: bool = false;
+/// The version of the bytecode the evaluated bytecode must have, if
+/// [`Config::strict_bytecode`] is enabled.
+static mut STRICT_BYTECODE_VERSION: Option<u8> = None;
+/// The source map remapping the locations of errors written to stderr, with
+/// the name of the module it maps.
+static mut SOURCE_MAP: Option<(String, SourceMap)> = None;
+static mut TIMED_OUT: bool = false;
+/// Whether the runtime was torn down with `finalize_runtime`.
+static mut FINALIZED: bool = false;
+
+static UNINITIALIZED_ERR: &str =
+    "The Javy runtime has not been initialized, `initialize_runtime` must be called first";
+
+static FINALIZED_ERR: &str =
+    "The Javy runtime has been finalized with `finalize_runtime` and can't be used anymore";
+
+/// The bit set in the length returned by `compile_src` when the buffer holds
+/// an error message rather than bytecode.
+pub const COMPILE_ERROR_FLAG: u32 = 1 << 31;
+
+static DEADLINE_ERR: &str = "Timeout: the run exceeded its total deadline";
+
+static BUDGET_ERR: &str = "interrupted: execution budget exceeded";
 
 static EVENT_LOOP_ERR: &str = r#"
                 Pending jobs in the event queue.
@@ -66,37 +141,135 @@ static EVENT_LOOP_ERR: &str = r#"
             "#;
 
 /// Initializes the Javy runtime.
+///
+/// The modules imported by JavaScript code are served from the module table
+/// passed to [`invoke_with_modules`] and [`compile_src_with_modules`],
+/// replacing the module loader of the [`Config`] if any.
+///
+/// Returns an error if the configuration is invalid or the runtime can't be
+/// constructed.
 pub fn initialize_runtime<F>(config: Config, modify_runtime: F) -> Result<()>
 where
     F: FnOnce(Runtime) -> Runtime,
 {
-    // Validate configuration dependencies
-    if config.wait_for_completion && !config.event_loop {
-        bail!("wait_for_completion requires event_loop to be enabled");
-    }
-    
-    let runtime = Runtime::new(config.runtime_config).unwrap();
+    initialize_runtime_with(config, modify_runtime, |_| Ok(()))
+}
+
+/// Initializes the Javy runtime like [`initialize_runtime`], then calls
+/// `setup_context` with the context of the runtime, for example to set
+/// globals with [`set_global_json`].
+///
+/// Since the runtime is initialized when the plugin is pre-initialized, the
+/// globals are part of the snapshot and visible to all the invocations of the
+/// instance, unless invocations are isolated with
+/// [`javy::Config::isolate_invocations`].
+///
+/// Returns an error if the configuration is invalid, the runtime can't be
+/// constructed or `setup_context` fails.
+pub fn initialize_runtime_with<F, S>(
+    config: Config,
+    modify_runtime: F,
+    setup_context: S,
+) -> Result<()>
+where
+    F: FnOnce(Runtime) -> Runtime,
+    S: for<'js> FnOnce(&Ctx<'js>) -> Result<()>,
+{
+    set_panic_hook();
+    let mut config = config.validate()?;
+    config
+        .runtime_config
+        .module_loader(|name| unsafe { MODULES.get(name) }.cloned());
+
+    let bytecode_fingerprint = config.runtime_config.bytecode_fingerprint();
+    let runtime =
+        Runtime::new(config.runtime_config).context("failed to construct QuickJS runtime")?;
+    let strict_bytecode_version = if config.strict_bytecode {
+        // The version is the first byte of any bytecode the runtime compiles.
+        let bytecode = runtime.compile_to_bytecode(FUNCTION_MODULE_NAME, "")?;
+        Some(bytecode[0])
+    } else {
+        None
+    };
+    let list_exports_first_atom = if config.list_exports {
+        // Atoms are referenced by the same index in any bytecode the runtime
+        // compiles.
+        let bytecode = runtime.compile_to_bytecode(FUNCTION_MODULE_NAME, "")?;
+        Some(bytecode::first_atom(&bytecode)?)
+    } else {
+        None
+    };
     let runtime = modify_runtime(runtime);
+    runtime
+        .context()
+        .with(|cx| setup_context(&cx))
+        .context("failed to set up the QuickJS context")?;
     unsafe {
         RUNTIME.take(); // Allow re-initializing.
         RUNTIME
             .set(runtime)
-            // `unwrap` requires error `T` to implement `Debug` but `set`
-            // returns the `javy::Runtime` on error and `javy::Runtime` does not
-            // implement `Debug`.
-            .map_err(|_| anyhow!("Could not pre-initialize javy::Runtime"))
-            .unwrap();
+            // `set` returns the `javy::Runtime` on error and `javy::Runtime`
+            // does not implement `Debug`.
+            .map_err(|_| anyhow!("Could not pre-initialize javy::Runtime"))?;
         EVENT_LOOP_ENABLED = config.event_loop;
         WAIT_FOR_COMPLETION = config.wait_for_completion;
         WAIT_TIMEOUT_MS = config.wait_timeout_ms;
+        CANCEL_TIMERS_ON_TIMEOUT = config.cancel_timers_on_timeout;
+        LIST_EXPORTS_FIRST_ATOM = list_exports_first_atom;
+        ABORT_ON_ERROR = config.abort_on_error;
+        ERROR_EXIT_STRATEGY = config.error_exit_strategy;
+        STRUCTURED_ERRORS = config.structured_errors;
+        BYTECODE_CACHE.configure(config.bytecode_cache_entries, bytecode_fingerprint);
+        STRICT_BYTECODE_VERSION = strict_bytecode_version;
+        FINALIZED = false;
     };
     Ok(())
 }
 
+/// The error explaining why the runtime isn't available.
+fn missing_runtime_err() -> Error {
+    if unsafe { FINALIZED } {
+        anyhow!(FINALIZED_ERR)
+    } else {
+        anyhow!(UNINITIALIZED_ERR)
+    }
+}
+
+/// Replaces the default panic hook with one writing the message and location
+/// of panics to stderr and flushing it, so hosts see why the instance trapped
+/// rather than a bare `unreachable` trap.
+fn set_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let mut stderr = io::stderr();
+        let _ = writeln!(stderr, "Javy plugin {info}");
+        let _ = stderr.flush();
+    }));
+}
+
+/// Sets the global `name` to the JavaScript value of the JSON `value`.
+///
+/// Meant to be called from the `setup_context` callback of
+/// [`initialize_runtime_with`].
+pub fn set_global_json(cx: &Ctx<'_>, name: &str, value: &serde_json::Value) -> Result<()> {
+    let value = cx
+        .json_parse(value.to_string())
+        .map_err(|e| from_js_error(cx.clone(), e))?;
+    cx.globals()
+        .set(name, value)
+        .map_err(|e| from_js_error(cx.clone(), e))
+}
+
 /// Compiles JS source code to QuickJS bytecode.
 ///
 /// Returns a pointer to a buffer containing a 32-bit pointer to the bytecode byte array and the
-/// u32 length of the bytecode byte array.
+/// u32 length of the bytecode byte array. The bytecode byte array stays valid until the next
+/// compilation, which releases it, so it must be copied out before compiling again. It can be
+/// released earlier with [`free_bytecode`].
+///
+/// If the source code can't be compiled, the error is written to stderr and the buffer contains
+/// a 32-bit pointer to the UTF-8 error message and its u32 length with [`COMPILE_ERROR_FLAG`]
+/// set instead. The error message stays valid until the next compilation fails and must not be
+/// released.
 ///
 /// # Arguments
 ///
@@ -108,6 +281,187 @@ where
 /// * `js_src_ptr` must reference a valid array of unsigned bytes of `js_src_len` length
 #[export_name = "compile_src"]
 pub unsafe extern "C" fn compile_src(js_src_ptr: *const u8, js_src_len: usize) -> *const u32 {
+    return_bytecode(compile(slice::from_raw_parts(js_src_ptr, js_src_len)))
+}
+
+/// Compiles JS source code to the QuickJS bytecode of a module with name,
+/// which names it in stack traces and errors instead of `function.mjs`.
+///
+/// Returns a pointer to a buffer containing a 32-bit pointer to the bytecode
+/// byte array and the u32 length of the bytecode byte array, like
+/// [`compile_src`].
+///
+/// If the name isn't UTF-8 or the source code can't be compiled, the buffer
+/// contains the error message like [`compile_src`].
+///
+/// # Arguments
+///
+/// * `js_src_ptr` - A pointer to the start of a byte array containing UTF-8 JS source code
+/// * `js_src_len` - The length of the byte array containing JS source code
+/// * `name_ptr` - A pointer to the UTF-8 name of the module
+/// * `name_len` - The length of the name of the module
+///
+/// # Safety
+///
+/// * `js_src_ptr` must reference a valid array of unsigned bytes of `js_src_len` length
+/// * `name_ptr` must reference a valid array of unsigned bytes of `name_len`
+///   length
+#[export_name = "compile_src_named"]
+pub unsafe extern "C" fn compile_src_named(
+    js_src_ptr: *const u8,
+    js_src_len: usize,
+    name_ptr: *const u8,
+    name_len: usize,
+) -> *const u32 {
+    let name = str::from_utf8(slice::from_raw_parts(name_ptr, name_len))
+        .context("The module name must be UTF-8");
+    let js_src = slice::from_raw_parts(js_src_ptr, js_src_len);
+    return_bytecode(name.and_then(|name| compile_named(name, js_src)))
+}
+
+/// Compiles the JS source code of a module to QuickJS bytecode, resolving the
+/// modules it imports from a module table of JS source code.
+///
+/// A module table is a sequence of entries, each made of the u32 length of
+/// the name of a module, the UTF-8 name, the u32 length of the code of the
+/// module and the code, with the lengths in little endian.
+///
+/// Returns a pointer to a buffer containing a 32-bit pointer to the bytecode
+/// byte array and the u32 length of the bytecode byte array, like
+/// [`compile_src`].
+///
+/// If the source code can't be compiled, the buffer contains the error
+/// message like [`compile_src`].
+///
+/// # Arguments
+///
+/// * `js_src_ptr` - A pointer to the start of a byte array containing UTF-8 JS source code
+/// * `js_src_len` - The length of the byte array containing JS source code
+/// * `name_ptr` - A pointer to the UTF-8 name of the module, or a null
+///   pointer for the module passed to [`invoke`]
+/// * `name_len` - The length of the name of the module
+/// * `modules_ptr` - A pointer to the start of the module table
+/// * `modules_len` - The length of the module table
+///
+/// # Safety
+///
+/// * `js_src_ptr` must reference a valid array of unsigned bytes of `js_src_len` length
+/// * If `name_ptr` is not 0, it must reference a UTF-8 string with `name_len`
+///   byte length
+/// * `modules_ptr` must reference a valid array of unsigned bytes of
+///   `modules_len` length
+#[export_name = "compile_src_with_modules"]
+pub unsafe extern "C" fn compile_src_with_modules(
+    js_src_ptr: *const u8,
+    js_src_len: usize,
+    name_ptr: *const u8,
+    name_len: usize,
+    modules_ptr: *const u8,
+    modules_len: usize,
+) -> *const u32 {
+    let name = if !name_ptr.is_null() {
+        str::from_utf8(slice::from_raw_parts(name_ptr, name_len))
+            .context("The module name must be UTF-8")
+    } else {
+        Ok(FUNCTION_MODULE_NAME)
+    };
+    let modules = slice::from_raw_parts(modules_ptr, modules_len);
+    return_bytecode(name.and_then(|name| {
+        let modules = parse_source_table(modules)?;
+        compile_with_modules(name, slice::from_raw_parts(js_src_ptr, js_src_len), modules)
+    }))
+}
+
+/// Leaks the compiled bytecode and returns the buffer pointing to it, or
+/// writes the compilation error to stderr and returns the buffer pointing to
+/// its message.
+unsafe fn return_bytecode(bytecode: Result<Vec<u8>>) -> *const u32 {
+    // The host copied the previous bytecode out before compiling again.
+    BYTECODE_BUFFER = None;
+    let bytecode = bytecode.and_then(|bytecode| {
+        if bytecode.len() >= COMPILE_ERROR_FLAG as usize {
+            bail!("The bytecode of {} bytes is too large", bytecode.len());
+        }
+        Ok(bytecode)
+    });
+    match bytecode {
+        Ok(bytecode) => {
+            let (bytecode_ptr, len) = store_bytecode(bytecode);
+            COMPILE_SRC_RET_AREA[0] = bytecode_ptr as u32;
+            COMPILE_SRC_RET_AREA[1] = len as u32;
+        }
+        Err(e) => {
+            eprintln!("{e}");
+            COMPILE_SRC_ERROR = e.to_string();
+            // Error messages are far shorter than 2 GiB.
+            COMPILE_SRC_RET_AREA[0] = COMPILE_SRC_ERROR.as_ptr() as u32;
+            COMPILE_SRC_RET_AREA[1] = COMPILE_SRC_ERROR.len() as u32 | COMPILE_ERROR_FLAG;
+        }
+    }
+    COMPILE_SRC_RET_AREA.as_ptr()
+}
+
+/// Releases the bytecode byte array returned by the last [`compile_src`]
+/// call, before the next call releases it.
+///
+/// Pointers that don't reference the outstanding bytecode byte array, for
+/// example because it was already released, are reported on stderr and
+/// ignored.
+///
+/// # Arguments
+///
+/// * `bytecode_ptr` - The pointer to the bytecode byte array
+/// * `bytecode_len` - The length of the bytecode byte array
+///
+/// # Safety
+///
+/// * The memory of the bytecode byte array must not be read after it's
+///   released
+#[export_name = "free_bytecode"]
+pub unsafe extern "C" fn free_bytecode(bytecode_ptr: *mut u8, bytecode_len: usize) {
+    if let Err(e) = release_bytecode(bytecode_ptr, bytecode_len) {
+        eprintln!("{e}");
+    }
+}
+
+/// The number of bytecode byte arrays returned by [`compile_src`] that
+/// haven't been released yet, at most one since each compilation releases
+/// the previous one.
+///
+/// Meant for tests checking that compiling doesn't leak bytecode.
+#[doc(hidden)]
+pub fn outstanding_bytecode_buffers() -> usize {
+    unsafe { BYTECODE_BUFFER.is_some() }.into()
+}
+
+/// Keeps the bytecode buffer so it outlives `compile_src` and can be read
+/// from memory, until the next compilation or `free_bytecode` releases it.
+fn store_bytecode(bytecode: Vec<u8>) -> (*const u8, usize) {
+    let buffer = unsafe { BYTECODE_BUFFER.insert(bytecode.into_boxed_slice()) };
+    (buffer.as_ptr(), buffer.len())
+}
+
+/// Drops the bytecode buffer kept by `store_bytecode`, unless it isn't the
+/// one referenced.
+fn release_bytecode(bytecode_ptr: *mut u8, bytecode_len: usize) -> Result<()> {
+    let outstanding = unsafe { BYTECODE_BUFFER.as_deref() }.is_some_and(|bytecode| {
+        bytecode.as_ptr() == bytecode_ptr.cast_const() && bytecode.len() == bytecode_len
+    });
+    if !outstanding {
+        bail!("Not an outstanding bytecode buffer: {bytecode_ptr:?} with length {bytecode_len}");
+    }
+    unsafe { BYTECODE_BUFFER = None };
+    Ok(())
+}
+
+/// Compiles UTF-8 JS source code to QuickJS bytecode.
+fn compile(js_src: &[u8]) -> Result<Vec<u8>> {
+    compile_named(FUNCTION_MODULE_NAME, js_src)
+}
+
+/// Compiles UTF-8 JS source code to the QuickJS bytecode of a module with
+/// name.
+fn compile_named(name: &str, js_src: &[u8]) -> Result<Vec<u8>> {
     // Use initialized runtime when compiling because certain runtime
     // configurations can cause different bytecode to be emitted.
     //
@@ -121,24 +475,138 @@ pub unsafe extern "C" fn compile_src(js_src_ptr: *const u8, js_src_len: usize) -
     //
     // Setting `config.bignum_extension` to `true` will produce different
     // bytecode than if it were set to `false`.
-    let runtime = unsafe { RUNTIME.get().unwrap() };
-    let js_src = str::from_utf8(slice::from_raw_parts(js_src_ptr, js_src_len)).unwrap();
+    let runtime = unsafe { RUNTIME.get() }.ok_or_else(missing_runtime_err)?;
+    let js_src = str::from_utf8(js_src)?;
+    unsafe {
+        BYTECODE_CACHE.get_or_compile(name, js_src, || runtime.compile_to_bytecode(name, js_src))
+    }
+}
 
-    let bytecode = runtime
-        .compile_to_bytecode(FUNCTION_MODULE_NAME, js_src)
-        .unwrap();
+/// The number of compilations by [`compile_src`] and [`compile_src_named`]
+/// served from the cache enabled with [`Config::bytecode_cache_entries`].
+pub fn bytecode_cache_hits() -> usize {
+    unsafe { BYTECODE_CACHE.hits() }
+}
 
-    // We need the bytecode buffer to live longer than this function so it can be read from memory
-    let len = bytecode.len();
-    let bytecode_ptr = Box::leak(bytecode.into_boxed_slice()).as_ptr();
-    COMPILE_SRC_RET_AREA[0] = bytecode_ptr as u32;
-    COMPILE_SRC_RET_AREA[1] = len.try_into().unwrap();
-    COMPILE_SRC_RET_AREA.as_ptr()
+/// Compiles the UTF-8 JS source code of the module with name to QuickJS
+/// bytecode, with the given modules available to import.
+///
+/// The imported modules are compiled and kept by the runtime, so this is
+/// meant to be called once per instance of the plugin.
+fn compile_with_modules(
+    name: &str,
+    js_src: &[u8],
+    modules: BTreeMap<String, ModuleSource>,
+) -> Result<Vec<u8>> {
+    let runtime = unsafe { RUNTIME.get() }.ok_or_else(missing_runtime_err)?;
+    let js_src = str::from_utf8(js_src)?;
+    unsafe { MODULES = modules };
+    runtime.compile_to_bytecode(name, js_src)
+}
+
+/// Parses a module table, as described in [`compile_src_with_modules`].
+fn parse_module_table(mut table: &[u8]) -> Result<BTreeMap<String, Vec<u8>>> {
+    fn take_field<'a>(table: &mut &'a [u8]) -> Result<&'a [u8]> {
+        let Some((len, rest)) = table.split_first_chunk::<4>() else {
+            bail!("Truncated module table");
+        };
+        let len = u32::from_le_bytes(*len) as usize;
+        if rest.len() < len {
+            bail!("Truncated module table");
+        }
+        let (field, rest) = rest.split_at(len);
+        *table = rest;
+        Ok(field)
+    }
+
+    let mut modules = BTreeMap::new();
+    while !table.is_empty() {
+        let name = str::from_utf8(take_field(&mut table)?)
+            .context("Module names in the module table must be UTF-8")?;
+        let code = take_field(&mut table)?;
+        modules.insert(name.to_string(), code.to_vec());
+    }
+    Ok(modules)
+}
+
+/// Parses a module table of UTF-8 JS source code.
+fn parse_source_table(table: &[u8]) -> Result<BTreeMap<String, ModuleSource>> {
+    parse_module_table(table)?
+        .into_iter()
+        .map(|(name, code)| {
+            let code = String::from_utf8(code)
+                .with_context(|| format!("The source code of module {name} must be UTF-8"))?;
+            Ok((name, ModuleSource::Code(code)))
+        })
+        .collect()
+}
+
+/// Sets the source map of the JS source code, which remaps the locations of
+/// the errors written to stderr to the original sources.
+///
+/// The locations remapped are the ones in the `file` of the source map, or
+/// in `function.mjs` if it has none.
+///
+/// # Safety
+///
+/// * `source_map_ptr` must reference a valid array of bytes of
+///   `source_map_len` length, holding a deflate compressed source map.
+#[export_name = "set_source_map"]
+pub unsafe extern "C" fn set_source_map(source_map_ptr: *const u8, source_map_len: usize) {
+    match parse_source_map(slice::from_raw_parts(source_map_ptr, source_map_len)) {
+        Ok(source_map) => SOURCE_MAP = Some(source_map),
+        Err(e) => handle_error(e),
+    }
+}
+
+/// Decompresses and decodes a source map, along with the name of the module
+/// it maps.
+fn parse_source_map(compressed: &[u8]) -> Result<(String, SourceMap)> {
+    #[derive(Deserialize)]
+    #[serde(rename_all = "camelCase")]
+    struct RawSourceMap {
+        #[serde(default)]
+        file: Option<String>,
+        #[serde(default)]
+        source_root: Option<String>,
+        sources: Vec<Option<String>>,
+        mappings: String,
+    }
+
+    let mut json = vec![];
+    DeflateDecoder::new(compressed)
+        .read_to_end(&mut json)
+        .context("Failed to decompress the source map")?;
+    let raw: RawSourceMap =
+        serde_json::from_slice(&json).context("Failed to parse the source map")?;
+    let root = raw.source_root.unwrap_or_default();
+    let sources = raw
+        .sources
+        .into_iter()
+        .map(|source| {
+            let source = source.unwrap_or_default();
+            if root.is_empty() {
+                source
+            } else {
+                format!("{}/{source}", root.trim_end_matches('/'))
+            }
+        })
+        .collect();
+    let file = raw.file.unwrap_or_else(|| FUNCTION_MODULE_NAME.to_string());
+    Ok((file, SourceMap::new(sources, &raw.mappings)?))
 }
 
 /// Evaluates QuickJS bytecode and optionally invokes exported JS function with
 /// name.
 ///
+/// When the function returns a promise and the event loop is enabled, the
+/// event loop runs until the promise settles, and a rejection is reported as
+/// an error.
+///
+/// Errors, including a function name that isn't UTF-8 or isn't exported by
+/// the module, are written to stderr and abort, unless aborting is disabled
+/// with [`Config::abort_on_error`].
+///
 /// # Safety
 ///
 /// * `bytecode_ptr` must reference a valid array of bytes of `bytecode_len`
@@ -153,45 +621,686 @@ pub unsafe extern "C" fn invoke(
     fn_name_len: usize,
 ) {
     let bytecode = slice::from_raw_parts(bytecode_ptr, bytecode_len);
-    let fn_name = if !fn_name_ptr.is_null() && fn_name_len != 0 {
-        Some(str::from_utf8_unchecked(slice::from_raw_parts(
-            fn_name_ptr,
-            fn_name_len,
-        )))
+    match optional_fn_name(fn_name_ptr, fn_name_len) {
+        Ok(fn_name) => run_bytecode(bytecode, fn_name),
+        Err(e) => handle_error(e),
+    }
+}
+
+/// Compiles JS source code and evaluates it, optionally invoking the exported
+/// JS function with name, like [`compile_src`] followed by [`invoke`] but
+/// without copying the bytecode back and forth.
+///
+/// Returns 0 on success. If the source code can't be compiled or evaluated,
+/// or the function throws, the error is written to stderr and 1 is returned.
+///
+/// # Safety
+///
+/// * `js_src_ptr` must reference a valid array of unsigned bytes of
+///   `js_src_len` length.
+/// * If `fn_name_ptr` is not 0, it must reference a UTF-8 string with
+///   `fn_name_len` byte length.
+#[export_name = "eval_src"]
+pub unsafe extern "C" fn eval_src(
+    js_src_ptr: *const u8,
+    js_src_len: usize,
+    fn_name_ptr: *const u8,
+    fn_name_len: usize,
+) -> u32 {
+    let js_src = slice::from_raw_parts(js_src_ptr, js_src_len);
+    let result = optional_fn_name(fn_name_ptr, fn_name_len).and_then(|fn_name| {
+        let bytecode = compile(js_src)?;
+        eval_and_call(&bytecode, fn_name, vec![])
+    });
+    match result {
+        Ok(_) => 0,
+        Err(e) => {
+            eprintln!("{e}");
+            1
+        }
+    }
+}
+
+/// The function name passed to [`invoke`] and [`eval_src`], if any.
+///
+/// Returns an error if the name isn't UTF-8.
+///
+/// # Safety
+///
+/// * If `fn_name_ptr` is not 0, it must reference an array of bytes of
+///   `fn_name_len` length.
+unsafe fn optional_fn_name<'a>(
+    fn_name_ptr: *const u8,
+    fn_name_len: usize,
+) -> Result<Option<&'a str>> {
+    if !fn_name_ptr.is_null() && fn_name_len != 0 {
+        fn_name(fn_name_ptr, fn_name_len).map(Some)
     } else {
-        None
+        Ok(None)
+    }
+}
+
+/// The function name passed to the exports invoking a function.
+///
+/// Returns an error if the name isn't UTF-8.
+///
+/// # Safety
+///
+/// * `fn_name_ptr` must reference an array of bytes of `fn_name_len` length.
+unsafe fn fn_name<'a>(fn_name_ptr: *const u8, fn_name_len: usize) -> Result<&'a str> {
+    let fn_name = slice::from_raw_parts(fn_name_ptr, fn_name_len);
+    str::from_utf8(fn_name).map_err(|e| {
+        anyhow!(
+            "The function name must be UTF-8, got {:?}: {e}",
+            String::from_utf8_lossy(fn_name)
+        )
+    })
+}
+
+/// Evaluates QuickJS bytecode and optionally invokes exported JS function with
+/// name, like [`invoke`], with the modules of a module table of QuickJS
+/// bytecode available to import.
+///
+/// The module table, described in [`compile_src_with_modules`], replaces the
+/// modules passed to previous invocations. The bytecode of each module must
+/// have been compiled with the name of the module. Modules that were already
+/// imported are reused by QuickJS, unless invocations are isolated with
+/// [`javy::Config::isolate_invocations`].
+///
+/// # Safety
+///
+/// * `bytecode_ptr` must reference a valid array of bytes of `bytecode_len`
+///   length.
+/// * If `fn_name_ptr` is not 0, it must reference a UTF-8 string with
+///   `fn_name_len` byte length.
+/// * `modules_ptr` must reference a valid array of bytes of `modules_len`
+///   length.
+#[export_name = "invoke_with_modules"]
+pub unsafe extern "C" fn invoke_with_modules(
+    bytecode_ptr: *const u8,
+    bytecode_len: usize,
+    fn_name_ptr: *const u8,
+    fn_name_len: usize,
+    modules_ptr: *const u8,
+    modules_len: usize,
+) {
+    match parse_module_table(slice::from_raw_parts(modules_ptr, modules_len)) {
+        Ok(modules) => {
+            set_bytecode_modules(modules);
+            invoke(bytecode_ptr, bytecode_len, fn_name_ptr, fn_name_len);
+        }
+        Err(e) => handle_error(e),
+    }
+}
+
+/// Replaces the modules available to import with the given QuickJS bytecode,
+/// by name.
+fn set_bytecode_modules(modules: BTreeMap<String, Vec<u8>>) {
+    unsafe {
+        MODULES = modules
+            .into_iter()
+            .map(|(name, bytecode)| (name, ModuleSource::Bytecode(bytecode)))
+            .collect();
+    }
+}
+
+/// Evaluates QuickJS bytecode, invokes the exported JS function with name and
+/// serializes the value it returns.
+///
+/// A returned promise is resolved following the same event loop rules as
+/// [`invoke`]. The resolved value is serialized as JSON, or as MessagePack
+/// when the `messagepack` feature is enabled.
+///
+/// Returns a pointer to a buffer containing a 32-bit pointer to the serialized
+/// byte array and the u32 length of the byte array. The byte array must be
+/// released with [`free_result`]. If the invocation fails and aborting is
+/// disabled with [`Config::abort_on_error`], the error is written to stderr
+/// and a null pointer is returned.
+///
+/// # Safety
+///
+/// * `bytecode_ptr` must reference a valid array of bytes of `bytecode_len`
+///   length.
+/// * `fn_name_ptr` must reference an array of bytes of `fn_name_len` length.
+#[export_name = "invoke_with_result"]
+pub unsafe extern "C" fn invoke_with_result(
+    bytecode_ptr: *const u8,
+    bytecode_len: usize,
+    fn_name_ptr: *const u8,
+    fn_name_len: usize,
+) -> *const u32 {
+    let bytecode = slice::from_raw_parts(bytecode_ptr, bytecode_len);
+    let result = match fn_name(fn_name_ptr, fn_name_len)
+        .and_then(|fn_name| call_with_result(bytecode, fn_name))
+    {
+        Ok(result) => result,
+        Err(e) => {
+            handle_error(e);
+            return ptr::null();
+        }
+    };
+
+    // The result buffer lives until the host releases it with `free_result`
+    let len = result.len();
+    let result_ptr = Box::leak(result.into_boxed_slice()).as_ptr();
+    INVOKE_WITH_RESULT_RET_AREA[0] = result_ptr as u32;
+    INVOKE_WITH_RESULT_RET_AREA[1] = len.try_into().expect("result length to fit in 32 bits");
+    INVOKE_WITH_RESULT_RET_AREA.as_ptr()
+}
+
+/// Evaluates QuickJS bytecode and invokes the exported JS function with name
+/// with arguments, for the exports of WIT functions with parameters or a
+/// result.
+///
+/// The WIT types of the parameters and result are described by a signature
+/// such as `(string,{name:string,age:u32})->string`, where a record is written
+/// as its fields between braces. Each argument is flattened to core Wasm
+/// values following the canonical ABI, each held by the low bytes of an 8 byte
+/// little-endian slot. The strings they point to must have been allocated with
+/// `canonical_abi_realloc` and are released.
+///
+/// Returns a null pointer if the signature has no result. Otherwise returns a
+/// pointer to an 8 byte slot holding the result if it's flattened to a single
+/// core Wasm value, or to the result stored in memory following the canonical
+/// ABI. The result stays valid until the next call. If the invocation fails
+/// and aborting is disabled with [`Config::abort_on_error`], the error is
+/// written to stderr and a null pointer is returned.
+///
+/// # Safety
+///
+/// * `bytecode_ptr` must reference a valid array of bytes of `bytecode_len`
+///   length.
+/// * `fn_name_ptr` must reference an array of bytes of `fn_name_len` length.
+/// * `signature_ptr` must reference an array of bytes of `signature_len`
+///   length.
+/// * If `args_len` is not 0, `args_ptr` must reference the `args_len` bytes of
+///   the slots of the arguments.
+#[export_name = "invoke_with_args"]
+#[allow(clippy::too_many_arguments)]
+pub unsafe extern "C" fn invoke_with_args(
+    bytecode_ptr: *const u8,
+    bytecode_len: usize,
+    fn_name_ptr: *const u8,
+    fn_name_len: usize,
+    signature_ptr: *const u8,
+    signature_len: usize,
+    args_ptr: *const u8,
+    args_len: usize,
+) -> *const u8 {
+    let bytecode = slice::from_raw_parts(bytecode_ptr, bytecode_len);
+    let signature = slice::from_raw_parts(signature_ptr, signature_len);
+    let args = if args_len == 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(args_ptr, args_len)
     };
-    run_bytecode(bytecode, fn_name);
+    fn_name(fn_name_ptr, fn_name_len)
+        .and_then(|fn_name| {
+            let signature = str::from_utf8(signature).context("The signature must be UTF-8")?;
+            call_with_args(bytecode, fn_name, signature, args)
+        })
+        .unwrap_or_else(|e| {
+            handle_error(e);
+            ptr::null()
+        })
+}
+
+/// Releases a result returned by [`invoke_with_result`], [`runtime_stats`]
+/// or [`list_exports`].
+///
+/// # Safety
+///
+/// * `ptr` and `len` must be the pointer and length of a result returned by
+///   [`invoke_with_result`], [`runtime_stats`] or [`list_exports`] that
+///   hasn't been released yet.
+#[export_name = "free_result"]
+pub unsafe extern "C" fn free_result(ptr: *mut u8, len: usize) {
+    drop(Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len)));
+}
+
+/// Reports the memory used by the runtime and the work it has pending, as a
+/// JSON object.
+///
+/// The object has the `mallocSize`, `mallocCount`, `memoryUsedSize`,
+/// `memoryUsedCount`, `atomCount`, `stringCount`, `objectCount`,
+/// `arrayCount` and `functionCount` computed by QuickJS, the
+/// `blobStorageBytes` held by blobs outside of the QuickJS heap, the number
+/// of `pendingTimers` and whether `hasPendingJobs`.
+///
+/// Returns a pointer to a buffer containing a 32-bit pointer to the UTF-8 JSON
+/// byte array and the u32 length of the JSON byte array. The byte array must
+/// be released with [`free_result`]. If the runtime isn't initialized, the
+/// error is handled like errors of invocations and a null pointer is
+/// returned when aborting is disabled with [`Config::abort_on_error`].
+#[export_name = "runtime_stats"]
+pub unsafe extern "C" fn runtime_stats() -> *const u32 {
+    let json = match RUNTIME.get().ok_or_else(missing_runtime_err) {
+        Ok(runtime) => runtime_stats_json(runtime),
+        Err(e) => {
+            handle_error(e);
+            return ptr::null();
+        }
+    };
+
+    // The JSON buffer lives until the host releases it with `free_result`
+    let len = json.len();
+    let json_ptr = Box::leak(json.into_bytes().into_boxed_slice()).as_ptr();
+    RUNTIME_STATS_RET_AREA[0] = json_ptr as u32;
+    RUNTIME_STATS_RET_AREA[1] = len.try_into().expect("JSON length to fit in 32 bits");
+    RUNTIME_STATS_RET_AREA.as_ptr()
+}
+
+/// Tears down the runtime so the host can drop the instance without losing
+/// state.
+///
+/// Runs the functions the scripts registered with `Javy.onShutdown`, in
+/// registration order, and drives the event loop like after an invocation,
+/// following [`Config::event_loop`], [`Config::wait_for_completion`] and
+/// [`Config::wait_timeout_ms`]. Then cancels the pending timers, removes the
+/// blobs from storage and drops the runtime. Invoking a function afterwards
+/// fails until the runtime is initialized again.
+///
+/// Errors are written to stderr and handled like errors of invocations,
+/// after the runtime is torn down.
+#[export_name = "finalize_runtime"]
+pub extern "C" fn finalize_runtime() {
+    if let Err(e) = finalize() {
+        handle_error(e);
+    }
+}
+
+/// Runs the shutdown hooks and tears down the runtime, even if a hook fails.
+fn finalize() -> Result<()> {
+    let runtime = unsafe { RUNTIME.get() }.ok_or_else(missing_runtime_err)?;
+    let result = runtime
+        .run_shutdown_hooks()
+        .and_then(|()| ensure_pending_jobs(runtime));
+    runtime.clear_timers()?;
+    unsafe {
+        RUNTIME.take();
+        FINALIZED = true;
+    }
+    blob_registry().clear();
+    result
+}
+
+/// Serializes the statistics reported by [`runtime_stats`] as JSON.
+fn runtime_stats_json(runtime: &Runtime) -> String {
+    let usage = runtime.memory_usage();
+    let stats = [
+        ("mallocSize", usage.malloc_size.to_string()),
+        ("mallocCount", usage.malloc_count.to_string()),
+        ("memoryUsedSize", usage.memory_used_size.to_string()),
+        ("memoryUsedCount", usage.memory_used_count.to_string()),
+        ("atomCount", usage.atom_count.to_string()),
+        ("stringCount", usage.str_count.to_string()),
+        ("objectCount", usage.obj_count.to_string()),
+        ("arrayCount", usage.array_count.to_string()),
+        ("functionCount", usage.js_func_count.to_string()),
+        (
+            "blobStorageBytes",
+            blob_registry().stored_bytes().to_string(),
+        ),
+        ("pendingTimers", runtime.pending_timers().to_string()),
+        ("hasPendingJobs", runtime.has_pending_jobs().to_string()),
+    ];
+    let fields: Vec<_> = stats
+        .iter()
+        .map(|(name, value)| format!("\"{name}\":{value}"))
+        .collect();
+    format!("{{{}}}", fields.join(","))
+}
+
+/// Lists the names exported by the module in the QuickJS bytecode, as a JSON
+/// array, without evaluating the module.
+///
+/// The names are read from the export entries of the module, so the names of
+/// exported values that aren't functions are listed as well, since what they
+/// hold is only known once the module is evaluated. Names re-exported with
+/// `export * from` aren't listed. Requires `list_exports` to be enabled in the
+/// [`Config`].
+///
+/// Returns a pointer to a buffer containing a 32-bit pointer to the UTF-8 JSON
+/// byte array and the u32 length of the JSON byte array. The byte array must
+/// be released with [`free_result`]. If the bytecode can't be read, the error
+/// is handled like errors of invocations and a null pointer is returned when
+/// aborting is disabled with [`Config::abort_on_error`].
+///
+/// # Safety
+///
+/// * `bytecode_ptr` must reference a valid array of bytes of `bytecode_len`
+///   length.
+#[export_name = "list_exports"]
+pub unsafe extern "C" fn list_exports(bytecode_ptr: *const u8, bytecode_len: usize) -> *const u32 {
+    let bytecode = slice::from_raw_parts(bytecode_ptr, bytecode_len);
+    let json = match export_names_json(bytecode) {
+        Ok(json) => json,
+        Err(e) => {
+            handle_error(e);
+            return ptr::null();
+        }
+    };
+
+    // The JSON buffer lives until the host releases it with `free_result`
+    let len = json.len();
+    let json_ptr = Box::leak(json.into_bytes().into_boxed_slice()).as_ptr();
+    LIST_EXPORTS_RET_AREA[0] = json_ptr as u32;
+    LIST_EXPORTS_RET_AREA[1] = len.try_into().expect("JSON length to fit in 32 bits");
+    LIST_EXPORTS_RET_AREA.as_ptr()
+}
+
+/// Serializes the names exported by the module in the given bytecode as a
+/// sorted JSON array.
+fn export_names_json(bytecode: &[u8]) -> Result<String> {
+    let Some(first_atom) = (unsafe { LIST_EXPORTS_FIRST_ATOM }) else {
+        bail!("Listing exports requires list_exports to be enabled");
+    };
+    let runtime = unsafe { RUNTIME.get() }.ok_or_else(missing_runtime_err)?;
+    let names = bytecode::validate(bytecode, unsafe { STRICT_BYTECODE_VERSION })
+        .and_then(|()| bytecode::export_names(bytecode, first_atom))
+        .map_err(|e| bytecode::invalid_bytecode_error(bytecode, e))?;
+    let mut names = runtime.context().with(|cx| {
+        names
+            .into_iter()
+            .map(|name| match name {
+                bytecode::Atom::String(name) => Ok(name),
+                bytecode::Atom::Int(name) => Ok(name.to_string()),
+                bytecode::Atom::Predefined(atom) => predefined_atom(&cx, atom),
+            })
+            .collect::<Result<Vec<_>>>()
+    })?;
+    names.sort();
+    Ok(serde_json::to_string(&names)?)
+}
+
+/// The string of an atom predefined by QuickJS.
+fn predefined_atom(cx: &Ctx<'_>, atom: u32) -> Result<String> {
+    let cx = cx.as_raw().as_ptr();
+    let ptr = unsafe { qjs::JS_AtomToCString(cx, atom) };
+    if ptr.is_null() {
+        bail!("atom {atom} isn't a predefined atom");
+    }
+    let name = unsafe { CStr::from_ptr(ptr) }
+        .to_string_lossy()
+        .into_owned();
+    unsafe { qjs::JS_FreeCString(cx, ptr) };
+    Ok(name)
+}
+
+/// Evaluate the given bytecode.
+///
+/// Deprecated for use outside of this crate.
+///
+/// Evaluating also prepares (or "instantiates") the state of the JavaScript
+/// engine given all the information encoded in the bytecode.
+pub fn run_bytecode(bytecode: &[u8], fn_name: Option<&str>) {
+    eval_and_call(bytecode, fn_name, vec![])
+        .map(|_| ())
+        .unwrap_or_else(handle_error)
+}
+
+/// Evaluates the given bytecode, invokes the exported function with name if
+/// there is one, and handles the pending jobs.
+///
+/// Returns the value returned by the function. A promise it returns is run
+/// until it settles when the event loop is enabled, and fails the call if
+/// it's rejected.
+fn eval_and_call(
+    bytecode: &[u8],
+    fn_name: Option<&str>,
+    args: Vec<wit::WitValue>,
+) -> Result<Option<Persistent<Value<'static>>>> {
+    let runtime = unsafe { RUNTIME.get_mut() }.ok_or_else(missing_runtime_err)?;
+    runtime.begin_invocation()?;
+    let runtime = &*runtime;
+    unsafe { TIMED_OUT = false };
+    let value = runtime
+        .context()
+        .with(|this| {
+            let module = unsafe { load_bytecode(&this, bytecode)? };
+            let (module, promise) = module.eval()?;
+
+            handle_maybe_promise(this.clone(), promise.into())?;
+
+            match fn_name {
+                Some(fn_name) => {
+                    let fun = exported_function(&this, &module.namespace()?, fn_name)?;
+                    let args = args
+                        .into_iter()
+                        .map(|arg| arg.into_js(&this))
+                        .collect::<quickjs::Result<Vec<_>>>()?;
+                    let value: Value = fun.call((Rest(args),))?;
+                    handle_maybe_promise(this.clone(), value.clone())?;
+                    Ok(Some(Persistent::save(&this, value)))
+                }
+                None => Ok(None),
+            }
+        })
+        .map_err(|e| runtime.context().with(|cx| from_js_error(cx.clone(), e)))
+        .map_err(|e| interrupt_error(runtime, e))?;
+    if let Some(value) = &value {
+        wait_for_promise(runtime, value).map_err(|e| interrupt_error(runtime, e))?;
+    }
+    ensure_pending_jobs(runtime).map_err(|e| interrupt_error(runtime, e))?;
+    if let Some(value) = &value {
+        ensure_not_rejected(runtime, value)?;
+    }
+    Ok(value)
+}
+
+/// Runs the event loop until the promise returned by an exported function
+/// settles, unless waiting for all the async operations to complete already
+/// does.
+fn wait_for_promise(rt: &Runtime, value: &Persistent<Value<'static>>) -> Result<()> {
+    if unsafe { !EVENT_LOOP_ENABLED || WAIT_FOR_COMPLETION } {
+        return Ok(());
+    }
+    wait_until(rt, || {
+        rt.context().with(|this| {
+            let value = value
+                .clone()
+                .restore(&this)
+                .map_err(|e| from_js_error(this.clone(), e))?;
+            Ok(value
+                .as_promise()
+                .is_none_or(|promise| promise.state() != PromiseState::Pending))
+        })
+    })?;
+    Ok(())
+}
+
+/// Fails with the reason the promise returned by an exported function was
+/// rejected with, if it was rejected.
+fn ensure_not_rejected(rt: &Runtime, value: &Persistent<Value<'static>>) -> Result<()> {
+    rt.context().with(|this| {
+        let value = value
+            .clone()
+            .restore(&this)
+            .map_err(|e| from_js_error(this.clone(), e))?;
+        match value.as_promise() {
+            Some(promise) if promise.state() == PromiseState::Rejected => {
+                match promise.result::<Value>() {
+                    Some(Err(e)) => Err(from_js_error(this.clone(), e)),
+                    _ => Ok(()),
+                }
+            }
+            _ => Ok(()),
+        }
+    })
+}
+
+/// Loads the module from bytecode once its framing is checked, with the
+/// length and the first bytes of invalid bytecode in the error.
+///
+/// # Safety
+///
+/// The bytecode must not be corrupted past its framing, which QuickJS can't
+/// detect.
+unsafe fn load_bytecode<'js>(
+    this: &Ctx<'js>,
+    bytecode: &[u8],
+) -> quickjs::Result<Module<'js, Declared>> {
+    bytecode::validate(bytecode, STRICT_BYTECODE_VERSION).map_err(|e| {
+        javy::to_js_error(this.clone(), bytecode::invalid_bytecode_error(bytecode, e))
+    })?;
+    load_module(this.clone(), bytecode).map_err(|e| {
+        let e = from_js_error(this.clone(), e);
+        javy::to_js_error(this.clone(), bytecode::invalid_bytecode_error(bytecode, e))
+    })
+}
+
+/// The function exported with name by the module with the namespace.
+///
+/// Throws an error listing the names the module exports if it doesn't export
+/// the name.
+fn exported_function<'js>(
+    this: &Ctx<'js>,
+    namespace: &Object<'js>,
+    fn_name: &str,
+) -> quickjs::Result<Function<'js>> {
+    if !namespace.contains_key(fn_name)? {
+        let names = namespace
+            .keys::<String>()
+            .collect::<quickjs::Result<Vec<_>>>()?;
+        return Err(javy::to_js_error(
+            this.clone(),
+            anyhow!(missing_export_message(fn_name, &names)),
+        ));
+    }
+    let value: Value = namespace.get(fn_name)?;
+    value.into_function().ok_or_else(|| {
+        javy::to_js_error(
+            this.clone(),
+            anyhow!("'{fn_name}' exported by the module is not a function"),
+        )
+    })
+}
+
+/// The error message for a function name that isn't exported by a module
+/// exporting the names.
+fn missing_export_message(fn_name: &str, names: &[String]) -> String {
+    if names.is_empty() {
+        format!("function '{fn_name}' not found; module has no exports")
+    } else {
+        format!(
+            "function '{fn_name}' not found; module exports: {}",
+            names.join(", ")
+        )
+    }
+}
+
+/// Replaces the error of a run interrupted at its total deadline or once its
+/// execution budget is exhausted, which QuickJS reports as a bare
+/// `interrupted` error, with an error telling which limit was reached.
+fn interrupt_error(rt: &Runtime, e: Error) -> Error {
+    if rt.deadline_exceeded() {
+        anyhow!(DEADLINE_ERR)
+    } else if rt.budget_exceeded() {
+        anyhow!(BUDGET_ERR)
+    } else {
+        e
+    }
+}
+
+/// Invokes the exported function with name and serializes the value it
+/// returns, or the value its promise resolved to.
+fn call_with_result(bytecode: &[u8], fn_name: &str) -> Result<Vec<u8>> {
+    let value =
+        eval_and_call(bytecode, Some(fn_name), vec![])?.expect("function to have been invoked");
+    let runtime = unsafe { RUNTIME.get() }.ok_or_else(missing_runtime_err)?;
+    runtime.context().with(|this| {
+        let value = settled_value(this.clone(), value, fn_name)?;
+        serialize_result(this.clone(), value)
+    })
+}
+
+/// Invokes the exported function with name with the arguments lifted from
+/// their flattened representation and lowers the value it returns, or the
+/// value its promise resolved to, following the signature.
+///
+/// Returns a pointer to the lowered result, or a null pointer if the
+/// signature has no result.
+///
+/// # Safety
+///
+/// * The arguments must be valid for the signature, see
+///   [`wit::Signature::lift_args`].
+unsafe fn call_with_args(
+    bytecode: &[u8],
+    fn_name: &str,
+    signature: &str,
+    args: &[u8],
+) -> Result<*const u8> {
+    let signature = wit::Signature::parse(signature)?;
+    let args = signature.lift_args(args)?;
+    INVOKE_WITH_ARGS_ALLOCATIONS.release();
+    let value =
+        eval_and_call(bytecode, Some(fn_name), args)?.expect("function to have been invoked");
+    let Some(ty) = &signature.result else {
+        return Ok(ptr::null());
+    };
+    let runtime = RUNTIME.get().ok_or_else(missing_runtime_err)?;
+    runtime.context().with(|this| {
+        let value = settled_value(this.clone(), value, fn_name)?;
+        wit::lower_result(
+            ty,
+            &value,
+            &mut INVOKE_WITH_ARGS_RET_AREA,
+            &mut INVOKE_WITH_ARGS_ALLOCATIONS,
+        )
+        .with_context(|| format!("Invalid value returned by {fn_name}"))
+    })
+}
+
+/// Restores the value returned by the function with name, replacing a
+/// promise with the value it resolved to.
+fn settled_value<'js>(
+    this: Ctx<'js>,
+    value: Persistent<Value<'static>>,
+    fn_name: &str,
+) -> Result<Value<'js>> {
+    let value = value
+        .restore(&this)
+        .map_err(|e| from_js_error(this.clone(), e))?;
+    match value.as_promise() {
+        Some(promise) => match promise.result::<Value>() {
+            Some(result) => result.map_err(|e| from_js_error(this.clone(), e)),
+            None => bail!("The promise returned by {fn_name} did not settle"),
+        },
+        None => Ok(value),
+    }
 }
 
-/// Evaluate the given bytecode.
-///
-/// Deprecated for use outside of this crate.
-///
-/// Evaluating also prepares (or "instantiates") the state of the JavaScript
-/// engine given all the information encoded in the bytecode.
-pub fn run_bytecode(bytecode: &[u8], fn_name: Option<&str>) {
-    let runtime = unsafe { RUNTIME.get() }.unwrap();
-    runtime
-        .context()
-        .with(|this| {
-            let module = unsafe { Module::load(this.clone(), bytecode)? };
-            let (module, promise) = module.eval()?;
+/// Serializes the result of an invocation as MessagePack.
+#[cfg(feature = "messagepack")]
+fn serialize_result<'js>(_this: Ctx<'js>, value: Value<'js>) -> Result<Vec<u8>> {
+    javy::messagepack::transcode_output(value)
+}
 
-            handle_maybe_promise(this.clone(), promise.into())?;
+/// Serializes the result of an invocation as JSON, with `undefined` serialized
+/// as `null`.
+#[cfg(not(feature = "messagepack"))]
+fn serialize_result<'js>(this: Ctx<'js>, value: Value<'js>) -> Result<Vec<u8>> {
+    let json = this
+        .json_stringify(value)
+        .and_then(|json| json.map(|json| json.to_string()).transpose())
+        .map_err(|e| from_js_error(this.clone(), e))?;
+    Ok(json.unwrap_or_else(|| "null".to_string()).into_bytes())
+}
 
-            if let Some(fn_name) = fn_name {
-                let fun: Function = module.get(fn_name)?;
-                // Exported functions are guaranteed not to have arguments so
-                // we can safely pass an empty tuple for arguments.
-                let value = fun.call(())?;
-                handle_maybe_promise(this.clone(), value)?
-            }
-            Ok(())
-        })
-        .map_err(|e| runtime.context().with(|cx| from_js_error(cx.clone(), e)))
-        .and_then(|_: ()| ensure_pending_jobs(runtime))
-        .unwrap_or_else(handle_error)
+/// Whether waiting for the async operations of the last invocation to
+/// complete reached the wait timeout.
+///
+/// Unless timers are cancelled on timeout, the timers left pending are still
+/// reported by [`Runtime::has_pending_jobs`].
+pub fn timed_out() -> bool {
+    unsafe { TIMED_OUT }
+}
+
+/// The peak of the memory used by the last invocation, in bytes, if tracking
+/// it is enabled with [`javy::Config::track_peak_memory`].
+pub fn peak_memory() -> Option<usize> {
+    unsafe { RUNTIME.get() }.and_then(|runtime| runtime.peak_memory())
 }
 
 /// Handles the promise returned by evaluating the JS bytecode.
@@ -223,8 +1332,16 @@ fn handle_maybe_promise(this: Ctx, value: Value) -> quickjs::Result<()> {
 fn ensure_pending_jobs(rt: &Runtime) -> Result<()> {
     if unsafe { EVENT_LOOP_ENABLED } {
         if unsafe { WAIT_FOR_COMPLETION } {
-            // Wait for all async operations to complete
-            wait_for_completion(rt)
+            // Wait for all async operations to complete. Reaching the timeout
+            // isn't an error, the jobs left pending are either cancelled or
+            // left queued.
+            if let Completion::TimedOut = wait_for_completion(rt)? {
+                unsafe { TIMED_OUT = true };
+                if unsafe { CANCEL_TIMERS_ON_TIMEOUT } {
+                    rt.clear_timers()?;
+                }
+            }
+            Ok(())
         } else {
             // Original behavior: resolve once
             rt.resolve_pending_jobs()
@@ -236,7 +1353,22 @@ fn ensure_pending_jobs(rt: &Runtime) -> Result<()> {
     }
 }
 
-fn wait_for_completion(rt: &Runtime) -> Result<()> {
+/// How waiting for async operations to complete ended.
+#[derive(Debug, PartialEq)]
+enum Completion {
+    /// No jobs are pending.
+    Completed,
+    /// The wait timeout was reached with jobs still pending.
+    TimedOut,
+}
+
+fn wait_for_completion(rt: &Runtime) -> Result<Completion> {
+    wait_until(rt, || Ok(false))
+}
+
+/// Runs the event loop until `done` returns true or no jobs are pending,
+/// within the wait timeout if there is one.
+fn wait_until(rt: &Runtime, mut done: impl FnMut() -> Result<bool>) -> Result<Completion> {
     use std::{thread, time::{Duration, Instant}};
     
     const SLEEP_MS: u64 = 1; // 1ms sleep between iterations
@@ -249,16 +1381,27 @@ fn wait_for_completion(rt: &Runtime) -> Result<()> {
         rt.resolve_pending_jobs()?;
         
         // Check if there are still pending jobs
-        if !rt.has_pending_jobs() {
+        if done()? || !rt.has_pending_jobs() {
             break;
         }
+
+        if rt.deadline_exceeded() {
+            bail!(DEADLINE_ERR);
+        }
         
         // Check timeout if configured
         if let Some(timeout) = timeout_ms {
             let elapsed = start_time.elapsed().as_millis() as u64;
             if elapsed >= timeout {
-                eprintln!("Warning: Timeout reached ({} ms) while waiting for async operations to complete", timeout);
-                break;
+                let message = format!(
+                    "Timeout reached ({timeout} ms) while waiting for async operations to complete"
+                );
+                if unsafe { STRUCTURED_ERRORS } {
+                    write_structured_error("timeout", &message, None, None);
+                } else {
+                    eprintln!("Warning: {message}");
+                }
+                return Ok(Completion::TimedOut);
             }
         }
         
@@ -266,12 +1409,104 @@ fn wait_for_completion(rt: &Runtime) -> Result<()> {
         thread::sleep(Duration::from_millis(SLEEP_MS));
     }
     
-    Ok(())
+    Ok(Completion::Completed)
 }
 
+/// Writes the error to stderr and ends the instance following the
+/// [`Config::error_exit_strategy`], unless aborting is disabled with
+/// [`Config::abort_on_error`].
 fn handle_error(e: Error) {
-    eprintln!("{e}");
-    process::abort();
+    if unsafe { STRUCTURED_ERRORS } {
+        let exception = e
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<JsException>());
+        let message = exception.map_or_else(|| e.to_string(), |e| e.message().to_string());
+        let stack = exception.and_then(JsException::stack).map(remap_stack);
+        write_structured_error(
+            structured_error_type(&e),
+            &message,
+            stack.as_deref(),
+            error_exit_code(),
+        );
+    } else {
+        eprintln!("{}", remap_stack(&e.to_string()));
+    }
+    if unsafe { ABORT_ON_ERROR } {
+        let _ = io::stderr().flush();
+        match unsafe { ERROR_EXIT_STRATEGY } {
+            ExitStrategy::Abort => process::abort(),
+            ExitStrategy::ExitCode(code) => process::exit(code.into()),
+        }
+    }
+}
+
+/// The code the instance exits with after an error, if it exits rather than
+/// aborting or returning to the host.
+fn error_exit_code() -> Option<u8> {
+    match unsafe { (ABORT_ON_ERROR, ERROR_EXIT_STRATEGY) } {
+        (true, ExitStrategy::ExitCode(code)) => Some(code),
+        _ => None,
+    }
+}
+
+/// Remaps the locations in a stack trace with the source map, if one is set.
+fn remap_stack(stack: &str) -> String {
+    match unsafe { SOURCE_MAP.as_ref() } {
+        Some((file, source_map)) => source_map.remap_stack(stack, file),
+        None => stack.to_string(),
+    }
+}
+
+/// An error written to stderr as a line of JSON with
+/// [`Config::structured_errors`].
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StructuredError<'a> {
+    #[serde(rename = "type")]
+    ty: &'static str,
+    message: &'a str,
+    stack: Option<&'a str>,
+    /// The code the instance exits with, which is null when the error aborts
+    /// the instance, returns to the host or is only a warning.
+    exit_code: Option<u8>,
+}
+
+fn write_structured_error(
+    ty: &'static str,
+    message: &str,
+    stack: Option<&str>,
+    exit_code: Option<u8>,
+) {
+    let error = StructuredError {
+        ty,
+        message: message.trim(),
+        stack,
+        exit_code,
+    };
+    eprintln!(
+        "{}",
+        serde_json::to_string(&error).expect("structured error to serialize")
+    );
+}
+
+/// The type of the error in its structured representation: `pending-jobs`
+/// when jobs were scheduled without the event loop, `timeout` when the run
+/// was interrupted at a limit, or `js-error` otherwise.
+fn structured_error_type(e: &Error) -> &'static str {
+    let is = |expected: &str| {
+        e.chain()
+            .any(|cause| match cause.downcast_ref::<JsException>() {
+                Some(exception) => exception.message() == expected,
+                None => cause.to_string() == expected,
+            })
+    };
+    if is(EVENT_LOOP_ERR) {
+        "pending-jobs"
+    } else if is(DEADLINE_ERR) || is(BUDGET_ERR) {
+        "timeout"
+    } else {
+        "js-error"
+    }
 }
 
 #[cfg(test)]
@@ -285,7 +1520,7 @@ mod tests {
         let runtime = Runtime::new(javy_config).unwrap();
         
         let result = wait_for_completion(&runtime);
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Completion::Completed);
     }
 
     #[test]
@@ -300,7 +1535,7 @@ mod tests {
         });
         
         let result = wait_for_completion(&runtime);
-        assert!(result.is_ok());
+        assert_eq!(result.unwrap(), Completion::Completed);
         
         // Verify the timer executed
         runtime.context().with(|cx| {
@@ -328,4 +1563,932 @@ mod tests {
         let result = ensure_pending_jobs(&runtime);
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_timeout_reports_timed_out() {
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .timers(true)
+            .wait_for_completion(true)
+            .wait_timeout_ms(Some(10));
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+        unsafe { TIMED_OUT = false };
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("setInterval(() => {}, 1)").unwrap();
+        });
+
+        // The timeout isn't an error, and the interval is left queued
+        assert!(ensure_pending_jobs(runtime).is_ok());
+        assert!(timed_out());
+        assert!(runtime.has_pending_timers());
+
+        // Cancelling timers on timeout leaves nothing pending
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .timers(true)
+            .wait_for_completion(true)
+            .wait_timeout_ms(Some(10))
+            .cancel_timers_on_timeout(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+        unsafe { TIMED_OUT = false };
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>("setInterval(() => {}, 1)").unwrap();
+        });
+
+        assert!(ensure_pending_jobs(runtime).is_ok());
+        assert!(timed_out());
+        assert!(!runtime.has_pending_jobs());
+    }
+
+    #[test]
+    fn test_total_deadline() {
+        use std::time::{Duration, Instant};
+
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .timers(true)
+            .wait_for_completion(true)
+            .total_deadline(Some(Duration::from_millis(50)));
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        // The deadline is reached while the event loop runs the timers
+        // scheduled after a long synchronous loop.
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"
+                let n = 0;
+                for (let i = 0; i < 100000; i++) { n += i; }
+                setInterval(() => { for (let i = 0; i < 10000; i++) { n += i; } }, 1);
+                "#,
+            )
+            .unwrap();
+        let start = Instant::now();
+        let error = eval_and_call(&bytecode, None, vec![]).unwrap_err();
+        assert_eq!(DEADLINE_ERR, error.to_string());
+        assert!(start.elapsed() < Duration::from_secs(5));
+        runtime.clear_timers().unwrap();
+
+        // The deadline is restarted for each run, and interrupts synchronous
+        // code.
+        let bytecode = runtime
+            .compile_to_bytecode(FUNCTION_MODULE_NAME, "while (true) {}")
+            .unwrap();
+        let error = eval_and_call(&bytecode, None, vec![]).unwrap_err();
+        assert_eq!(DEADLINE_ERR, error.to_string());
+    }
+
+    #[test]
+    fn test_execution_budget() {
+        let mut config = Config::default();
+        config.interrupt_after(10);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let infinite = runtime
+            .compile_to_bytecode(FUNCTION_MODULE_NAME, "while (true) {}")
+            .unwrap();
+        let error = eval_and_call(&infinite, None, vec![]).unwrap_err();
+        assert_eq!(BUDGET_ERR, error.to_string());
+
+        // The budget is reset for each run, so the exhausted budget of the
+        // previous run doesn't interrupt this one.
+        let fib = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                "function fib(n) { return n < 2 ? n : fib(n - 1) + fib(n - 2); } fib(20);",
+            )
+            .unwrap();
+        eval_and_call(&fib, None, vec![]).unwrap();
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    /// Whether a global set by an invocation is visible to the next one.
+    fn global_visible_to_next_invocation(isolate: bool) -> bool {
+        let mut config = Config::default();
+        config.isolate_invocations(isolate);
+        initialize_runtime(config, |rt| rt).unwrap();
+
+        let set = compile(b"globalThis.x = 1;").unwrap();
+        eval_and_call(&set, None, vec![]).unwrap();
+        let get = compile(b"if (globalThis.x !== 1) throw new Error('x is not set');").unwrap();
+        let visible = eval_and_call(&get, None, vec![]).is_ok();
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        visible
+    }
+
+    #[test]
+    fn test_globals_are_visible_to_next_invocation() {
+        assert!(global_visible_to_next_invocation(false));
+    }
+
+    #[test]
+    fn test_isolate_invocations() {
+        assert!(!global_visible_to_next_invocation(true));
+    }
+
+    #[test]
+    fn test_runtime_stats() {
+        let mut config = Config::default();
+        config.event_loop(true).timers(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let stats =
+            || -> serde_json::Value { serde_json::from_str(&runtime_stats_json(runtime)).unwrap() };
+
+        let before = stats();
+        assert_eq!(0, before["pendingTimers"]);
+        assert_eq!(false, before["hasPendingJobs"]);
+
+        runtime.context().with(|cx| {
+            cx.eval::<(), _>(
+                "globalThis.array = new Array(100000).fill(0).map((_, i) => ({ i }));
+                setTimeout(() => {}, 1000);",
+            )
+            .unwrap();
+        });
+        let after = stats();
+        let memory_used = |stats: &serde_json::Value| stats["memoryUsedSize"].as_i64().unwrap();
+        assert!(memory_used(&after) > memory_used(&before) + 1_000_000);
+        assert!(after["objectCount"].as_i64() > before["objectCount"].as_i64());
+        assert_eq!(1, after["pendingTimers"]);
+        assert_eq!(true, after["hasPendingJobs"]);
+        runtime.clear_timers().unwrap();
+    }
+
+    #[test]
+    fn test_peak_memory() {
+        let mut config = Config::default();
+        config.track_peak_memory(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                "let a = new Array(100000).fill({}).map((_, i) => ({ i })); a = null;",
+            )
+            .unwrap();
+        eval_and_call(&bytecode, None, vec![]).unwrap();
+        let allocating = peak_memory().unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(FUNCTION_MODULE_NAME, "")
+            .unwrap();
+        eval_and_call(&bytecode, None, vec![]).unwrap();
+        assert!(peak_memory().unwrap() < allocating);
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        assert_eq!(None, peak_memory());
+    }
+
+    #[test]
+    fn test_list_exports() {
+        let mut config = Config::default();
+        config.list_exports(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"
+                globalThis.evaluated = true;
+                export function foo() {}
+                export const bar = () => {};
+                export const baz = 42;
+                export { foo as length };
+                export default function () { throw new Error("called"); }
+                throw new Error("evaluated");
+                "#,
+            )
+            .unwrap();
+        assert_eq!(
+            r#"["bar","baz","default","foo","length"]"#,
+            export_names_json(&bytecode).unwrap()
+        );
+        // Listing the exports doesn't evaluate the module.
+        let evaluated = runtime
+            .context()
+            .with(|cx| cx.globals().contains_key("evaluated"))
+            .unwrap();
+        assert!(!evaluated);
+
+        let bytecode = runtime
+            .compile_to_bytecode(FUNCTION_MODULE_NAME, "export default () => {};")
+            .unwrap();
+        assert_eq!(r#"["default"]"#, export_names_json(&bytecode).unwrap());
+
+        let error = export_names_json(&[0x43, 0]).unwrap_err().to_string();
+        assert!(error.starts_with("Invalid QuickJS bytecode"), "{error}");
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        assert_eq!(
+            "Listing exports requires list_exports to be enabled",
+            export_names_json(&bytecode).unwrap_err().to_string()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "messagepack"))]
+    fn test_call_with_result() {
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .timers(true)
+            .wait_for_completion(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"
+                export function object() { return { a: 1, b: [true, "two"] }; }
+                export async function promise() { return "resolved"; }
+                export function timer() {
+                    return new Promise((resolve) => setTimeout(() => resolve(42), 1));
+                }
+                export function nothing() {}
+                "#,
+            )
+            .unwrap();
+        let result = |fn_name| String::from_utf8(call_with_result(&bytecode, fn_name).unwrap());
+        assert_eq!(r#"{"a":1,"b":[true,"two"]}"#, result("object").unwrap());
+        assert_eq!(r#""resolved""#, result("promise").unwrap());
+        assert_eq!("42", result("timer").unwrap());
+        assert_eq!("null", result("nothing").unwrap());
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"export async function rejected() { throw new Error("rejected"); }"#,
+            )
+            .unwrap();
+        let error = call_with_result(&bytecode, "rejected").unwrap_err();
+        assert!(error.to_string().contains("rejected"), "{error}");
+    }
+
+    #[test]
+    #[cfg(not(feature = "messagepack"))]
+    fn test_exported_promises_settle() {
+        for wait_for_completion in [false, true] {
+            let mut config = Config::default();
+            config
+                .event_loop(true)
+                .timers(true)
+                .wait_for_completion(wait_for_completion);
+            initialize_runtime(config, |rt| rt).unwrap();
+            let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+            let bytecode = runtime
+                .compile_to_bytecode(
+                    FUNCTION_MODULE_NAME,
+                    r#"
+                    const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+                    export async function resolves() { await sleep(10); return "later"; }
+                    export async function rejects() { await sleep(10); throw new Error("rejected after sleeping"); }
+                    "#,
+                )
+                .unwrap();
+            let result = call_with_result(&bytecode, "resolves").unwrap();
+            assert_eq!(r#""later""#, String::from_utf8(result).unwrap());
+
+            let error = eval_and_call(&bytecode, Some("rejects"), vec![]).unwrap_err();
+            assert!(
+                error.to_string().contains("rejected after sleeping"),
+                "{error}"
+            );
+        }
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    #[test]
+    fn test_structured_error_type() {
+        assert_eq!(
+            "pending-jobs",
+            structured_error_type(&anyhow!(EVENT_LOOP_ERR))
+        );
+        assert_eq!("timeout", structured_error_type(&anyhow!(DEADLINE_ERR)));
+        assert_eq!(
+            "timeout",
+            structured_error_type(&anyhow!(BUDGET_ERR).context("Invalid value"))
+        );
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        let error = |src| {
+            let bytecode = compile(src).unwrap();
+            eval_and_call(&bytecode, None, vec![]).unwrap_err()
+        };
+        let e = error(b"Promise.resolve().then(() => {});");
+        assert_eq!("pending-jobs", structured_error_type(&e));
+        let e = error(b"function fail() { throw new Error('failed'); }\nfail();");
+        assert_eq!("js-error", structured_error_type(&e));
+        let exception = e.downcast_ref::<JsException>().unwrap();
+        assert_eq!("failed", exception.message());
+        assert!(exception.stack().unwrap().contains("at fail"));
+    }
+
+    #[test]
+    fn test_call_with_args() -> Result<()> {
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime.compile_to_bytecode(
+            FUNCTION_MODULE_NAME,
+            r#"
+            export function greet(name, times) {
+                return { greeting: `Hello, ${name}!`.length * times, big: 2n ** 40n };
+            }
+            export function count() { return 3; }
+            export function nothing() { return 3; }
+            "#,
+        )?;
+        let name = "Javy";
+        let name_ptr =
+            unsafe { javy::alloc::canonical_abi_realloc(ptr::null_mut(), 0, 1, name.len()) };
+        unsafe { ptr::copy_nonoverlapping(name.as_ptr(), name_ptr.cast(), name.len()) };
+        let args: Vec<u8> = [name_ptr as u64, name.len() as u64, 2]
+            .iter()
+            .flat_map(|slot| slot.to_le_bytes())
+            .collect();
+        let result = unsafe {
+            call_with_args(
+                &bytecode,
+                "greet",
+                "(string,u32)->{greeting:u16,big:u64}",
+                &args,
+            )?
+        };
+        let result = unsafe { slice::from_raw_parts(result, 16) };
+        assert_eq!(24u16.to_le_bytes(), result[..2]);
+        assert_eq!((1u64 << 40).to_le_bytes(), result[8..]);
+
+        let result = unsafe { call_with_args(&bytecode, "count", "()->f64", &[])? };
+        assert_eq!(3.0f64.to_bits(), unsafe { result.cast::<u64>().read() });
+        assert!(unsafe { call_with_args(&bytecode, "nothing", "()", &[])? }.is_null());
+
+        let error = unsafe { call_with_args(&bytecode, "count", "()->string", &[]) }.unwrap_err();
+        assert_eq!(
+            "Invalid value returned by count: Expected a string, got int",
+            format!("{error:#}")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_invoke_with_args_rejects_invalid_signature() {
+        let mut config = Config::default();
+        config.abort_on_error(false);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                "export function count() { return 3; }",
+            )
+            .unwrap();
+        let fn_name = "count";
+        let signature = [b'(', b')', 0xff];
+        let result = unsafe {
+            invoke_with_args(
+                bytecode.as_ptr(),
+                bytecode.len(),
+                fn_name.as_ptr(),
+                fn_name.len(),
+                signature.as_ptr(),
+                signature.len(),
+                ptr::null(),
+                0,
+            )
+        };
+        assert!(result.is_null());
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    #[test]
+    fn test_eval_src() {
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+
+        let eval = |src: &str, fn_name: Option<&str>| {
+            let (fn_name_ptr, fn_name_len) =
+                fn_name.map_or((ptr::null(), 0), |f| (f.as_ptr(), f.len()));
+            unsafe { eval_src(src.as_ptr(), src.len(), fn_name_ptr, fn_name_len) }
+        };
+
+        let src = "globalThis.evaluated = 1; export function foo() { globalThis.called = 2; }";
+        assert_eq!(0, eval(src, None));
+        assert_eq!(0, eval(src, Some("foo")));
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+        runtime.context().with(|cx| {
+            let globals: Vec<i32> = cx
+                .eval("[globalThis.evaluated, globalThis.called]")
+                .unwrap();
+            assert_eq!(vec![1, 2], globals);
+        });
+
+        // Compilation and runtime errors are reported without trapping
+        assert_eq!(1, eval("function ( {", None));
+        assert_eq!(1, eval("throw new Error('thrown');", None));
+        assert_eq!(1, eval("export const foo = 1;", Some("foo")));
+    }
+
+    /// Reads the bytecode or the error message from the buffer returned by
+    /// `compile_src`.
+    fn compile_src_result(ret_area: *const u32) -> Result<Vec<u8>, String> {
+        let (ptr, len) = unsafe { (*ret_area, *ret_area.add(1)) };
+        let bytes = |len| unsafe { slice::from_raw_parts(ptr as *const u8, len as usize) };
+        if len & COMPILE_ERROR_FLAG == 0 {
+            Ok(bytes(len).to_vec())
+        } else {
+            Err(String::from_utf8(bytes(len & !COMPILE_ERROR_FLAG).to_vec()).unwrap())
+        }
+    }
+
+    #[test]
+    fn test_compile_src() {
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+
+        let src = "let a = 1;\nfunction ( {";
+        let error = compile(src.as_bytes()).unwrap_err().to_string();
+        assert!(error.contains("function.mjs]:2:"), "{error}");
+        assert_eq!(
+            Err(error),
+            compile_src_result(unsafe { compile_src(src.as_ptr(), src.len()) })
+        );
+        let src = [0xff];
+        let error = compile_src_result(unsafe { compile_src(src.as_ptr(), src.len()) });
+        assert!(error.is_err());
+
+        let src = "export function foo() {}";
+        let ret_area = unsafe { compile_src(src.as_ptr(), src.len()) };
+        let bytecode = compile_src_result(ret_area).unwrap();
+        assert_eq!(compile(src.as_bytes()).unwrap(), bytecode);
+        unsafe { free_bytecode(*ret_area as *mut u8, bytecode.len()) };
+
+        let error = compile_named("handler.js", b"let a = 1;\nfunction ( {")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("handler.js]:2:"), "{error}");
+        let name = "handler.js";
+        let ret_area =
+            unsafe { compile_src_named(src.as_ptr(), src.len(), name.as_ptr(), name.len()) };
+        assert!(compile_src_result(ret_area).is_ok());
+        let name = [0xff];
+        let ret_area =
+            unsafe { compile_src_named(src.as_ptr(), src.len(), name.as_ptr(), name.len()) };
+        assert_eq!(
+            Err("The module name must be UTF-8".to_string()),
+            compile_src_result(ret_area)
+        );
+
+        // Checked here rather than in a separate test because the buffer
+        // is kept globally.
+        let (first_ptr, first_len) = store_bytecode(vec![1, 2, 3]);
+        assert_eq!(1, outstanding_bytecode_buffers());
+
+        // Storing the next buffer releases the previous one.
+        let (second_ptr, second_len) = store_bytecode(vec![4, 5]);
+        assert_eq!(1, outstanding_bytecode_buffers());
+        assert!(release_bytecode(first_ptr.cast_mut(), first_len).is_err());
+
+        // Releasing with the wrong length, or twice, is rejected
+        assert!(release_bytecode(second_ptr.cast_mut(), first_len).is_err());
+        assert!(release_bytecode(second_ptr.cast_mut(), second_len).is_ok());
+        assert_eq!(0, outstanding_bytecode_buffers());
+        assert!(release_bytecode(second_ptr.cast_mut(), second_len).is_err());
+
+        // Compiling many snippets without releasing them keeps one buffer.
+        for i in 0..100 {
+            let src = format!("export function foo() {{ return {i}; }}");
+            unsafe { compile_src(src.as_ptr(), src.len()) };
+            assert_eq!(1, outstanding_bytecode_buffers());
+        }
+        // Failing to compile releases it too.
+        let src = "export function";
+        unsafe { compile_src(src.as_ptr(), src.len()) };
+        assert_eq!(0, outstanding_bytecode_buffers());
+    }
+
+    #[test]
+    fn test_invalid_bytecode() {
+        let mut config = Config::default();
+        config.abort_on_error(false);
+        initialize_runtime(config, |rt| rt).unwrap();
+        let bytecode = compile(b"export function foo() { return 1; }").unwrap();
+        assert!(eval_and_call(&bytecode, Some("foo"), vec![]).is_ok());
+
+        let truncated = &bytecode[..bytecode.len() / 2];
+        let mut flipped = bytecode.clone();
+        flipped[0] ^= 0x01;
+        for invalid in [truncated, &flipped] {
+            let error = eval_and_call(invalid, Some("foo"), vec![])
+                .unwrap_err()
+                .to_string();
+            let prefix = format!(
+                "Invalid QuickJS bytecode of {} bytes starting with {:02x}",
+                invalid.len(),
+                invalid[0]
+            );
+            assert!(error.starts_with(&prefix), "{error}");
+
+            // Returns rather than aborting.
+            unsafe { invoke(invalid.as_ptr(), invalid.len(), ptr::null(), 0) };
+        }
+
+        // Bytecode with the version of a QuickJS built without bignums
+        // loads unless the bytecode is strict.
+        let mut other_version = bytecode.clone();
+        other_version[0] ^= 0x40;
+        let error = eval_and_call(&other_version, None, vec![])
+            .unwrap_err()
+            .to_string();
+        assert!(!error.contains("doesn't match"), "{error}");
+
+        let mut config = Config::default();
+        config.abort_on_error(false).strict_bytecode(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        assert!(eval_and_call(&bytecode, Some("foo"), vec![]).is_ok());
+        let error = eval_and_call(&other_version, None, vec![])
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains(&format!(
+                "bytecode version {:#04x} doesn't match the version {:#04x} of the plugin",
+                other_version[0], bytecode[0]
+            )),
+            "{error}"
+        );
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    #[test]
+    fn test_bytecode_cache() {
+        let mut config = Config::default();
+        config.bytecode_cache_entries(4);
+        initialize_runtime(config, |rt| rt).unwrap();
+
+        let hits = bytecode_cache_hits();
+        let src = "export function foo() { return 1; }";
+        let bytecode = compile(src.as_bytes()).unwrap();
+        assert_eq!(hits, bytecode_cache_hits());
+        assert_eq!(bytecode, compile(src.as_bytes()).unwrap());
+        assert_eq!(hits + 1, bytecode_cache_hits());
+
+        // Changing the source or the name of the module misses the cache.
+        let changed = compile(b"export function foo() { return 2; }").unwrap();
+        assert_ne!(bytecode, changed);
+        compile_named("handler.js", src.as_bytes()).unwrap();
+        assert_eq!(hits + 1, bytecode_cache_hits());
+
+        // The returned buffer holds a copy of the cached bytecode, so it can
+        // be released.
+        for _ in 0..2 {
+            let ret_area = unsafe { compile_src(src.as_ptr(), src.len()) };
+            assert_eq!(1, outstanding_bytecode_buffers());
+            let (ptr, len) = unsafe { (*ret_area, *ret_area.add(1)) };
+            unsafe { free_bytecode(ptr as *mut u8, len as usize) };
+            assert_eq!(0, outstanding_bytecode_buffers());
+        }
+        assert_eq!(hits + 3, bytecode_cache_hits());
+
+        // Re-initializing with a different config misses the cache.
+        let mut config = Config::default();
+        config.bytecode_cache_entries(4).text_encoding(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        compile(src.as_bytes()).unwrap();
+        assert_eq!(hits + 3, bytecode_cache_hits());
+    }
+
+    #[test]
+    #[cfg(not(feature = "messagepack"))]
+    fn test_initialize_runtime_with_globals() {
+        initialize_runtime_with(
+            Config::default(),
+            |rt| rt,
+            |cx| {
+                set_global_json(
+                    cx,
+                    "ENV",
+                    &serde_json::json!({ "apiKey": "secret", "limits": [1, 2.5], "debug": null }),
+                )
+            },
+        )
+        .unwrap();
+
+        let bytecode = compile(b"export function env() { return globalThis.ENV; }").unwrap();
+        assert_eq!(
+            r#"{"apiKey":"secret","debug":null,"limits":[1,2.5]}"#,
+            String::from_utf8(call_with_result(&bytecode, "env").unwrap()).unwrap()
+        );
+
+        let error =
+            initialize_runtime_with(Config::default(), |rt| rt, |_| bail!("no ENV")).unwrap_err();
+        assert_eq!(
+            "failed to set up the QuickJS context: no ENV",
+            format!("{error:#}")
+        );
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    #[test]
+    fn test_missing_export() {
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+
+        let bytecode =
+            compile(b"export function foo() {} export default () => {}; export const bar = 1;")
+                .unwrap();
+        let error = eval_and_call(&bytecode, Some("fooBar"), vec![]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("function 'fooBar' not found; module exports: bar, default, foo"),
+            "{error}"
+        );
+        let error = eval_and_call(&bytecode, Some("bar"), vec![]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("'bar' exported by the module is not a function"),
+            "{error}"
+        );
+
+        let bytecode = compile(b"globalThis.x = 1;").unwrap();
+        let error = eval_and_call(&bytecode, Some("foo"), vec![]).unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .contains("function 'foo' not found; module has no exports"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_invalid_fn_name() {
+        let name = [b'f', 0xff, b'o'];
+        let error = unsafe { fn_name(name.as_ptr(), name.len()) }.unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("The function name must be UTF-8, got \"f\u{fffd}o\""),
+            "{error}"
+        );
+        assert_eq!(None, unsafe { optional_fn_name(ptr::null(), 0) }.unwrap());
+    }
+
+    #[test]
+    fn test_parse_source_map() -> Result<()> {
+        use flate2::{write::DeflateEncoder, Compression};
+        use std::io::Write;
+
+        let json = r#"{"version":3,"sourceRoot":"src/","sources":["index.ts"],"names":[],"mappings":"AAAA;QASI"}"#;
+        let mut encoder = DeflateEncoder::new(vec![], Compression::default());
+        encoder.write_all(json.as_bytes())?;
+        let (file, source_map) = parse_source_map(&encoder.finish()?)?;
+        assert_eq!(FUNCTION_MODULE_NAME, file);
+        assert_eq!(
+            "    at fail (src/index.ts:10:5)",
+            source_map.remap_stack("    at fail (function.mjs:2:9)", &file)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_initialize_runtime_errors() {
+        let error =
+            |config: Config| format!("{:#}", initialize_runtime(config, |rt| rt).unwrap_err());
+
+        let mut config = Config::default();
+        config.wait_for_completion(true);
+        assert_eq!(
+            "wait_for_completion requires event_loop to be enabled",
+            error(config)
+        );
+
+        let mut config = Config::default();
+        config.event_loop(true).wait_timeout_ms(Some(10));
+        assert_eq!(
+            "wait_timeout_ms requires wait_for_completion to be enabled",
+            error(config)
+        );
+
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .wait_for_completion(true)
+            .cancel_timers_on_timeout(true);
+        assert_eq!(
+            "cancel_timers_on_timeout requires wait_timeout_ms to be set",
+            error(config)
+        );
+
+        let mut config = Config::default();
+        config.regexp_cache(true).proxy(false);
+        assert_eq!(
+            "failed to construct QuickJS runtime: RegExp, RegExp compiler and Proxy intrinsics are required to cache regular expressions",
+            error(config)
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "messagepack"))]
+    fn test_host_functions() {
+        initialize_runtime(Config::default(), |runtime| {
+            runtime
+                .register_function("hostAdd", |args| match args {
+                    [JsValue::Number(a), JsValue::Number(b)] => Ok(JsValue::Number(a + b)),
+                    _ => bail!("hostAdd expects two numbers"),
+                })
+                .unwrap();
+            runtime
+                .register_namespace(
+                    "myHost",
+                    [
+                        HostFunction::new("echo", |args| Ok(JsValue::Array(args.to_vec()))),
+                        HostFunction::new("fail", |_| bail!("host failure")),
+                    ],
+                )
+                .unwrap();
+            runtime
+        })
+        .unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"
+                export function add() { return hostAdd(1, 2.5); }
+                export function echo() {
+                    return myHost.echo("a", [true, null], { b: { c: 1 } }, undefined);
+                }
+                export function caught() {
+                    try { myHost.fail(); } catch (e) { return `${e.name}: ${e.message}`; }
+                }
+                export function uncaught() { return hostAdd("1", 2); }
+                export function unsupported() { return myHost.echo(() => {}); }
+                "#,
+            )
+            .unwrap();
+        let result = |fn_name| String::from_utf8(call_with_result(&bytecode, fn_name).unwrap());
+        assert_eq!("3.5", result("add").unwrap());
+        assert_eq!(
+            r#"["a",[true,null],{"b":{"c":1}},null]"#,
+            result("echo").unwrap()
+        );
+        assert_eq!(r#""Error: host failure""#, result("caught").unwrap());
+
+        let error = call_with_result(&bytecode, "uncaught").unwrap_err();
+        assert!(
+            error.to_string().contains("hostAdd expects two numbers"),
+            "{error}"
+        );
+        let error = call_with_result(&bytecode, "unsupported").unwrap_err();
+        assert!(error.to_string().contains("Unsupported"), "{error}");
+    }
+
+    #[test]
+    fn test_finalize_runtime() {
+        static mut FLUSHED: Vec<String> = Vec::new();
+
+        let mut config = Config::default();
+        config
+            .event_loop(true)
+            .timers(true)
+            .wait_for_completion(true)
+            .abort_on_error(false);
+        initialize_runtime(config, |runtime| {
+            runtime
+                .register_function("flush", |args| match args {
+                    [JsValue::String(s)] => {
+                        unsafe { FLUSHED.push(s.clone()) };
+                        Ok(JsValue::Undefined)
+                    }
+                    _ => bail!("flush expects a string"),
+                })
+                .unwrap();
+            runtime
+        })
+        .unwrap();
+        let runtime = unsafe { RUNTIME.get() }.unwrap();
+
+        let bytecode = runtime
+            .compile_to_bytecode(
+                FUNCTION_MODULE_NAME,
+                r#"
+                const sleep = (ms) => new Promise((resolve) => setTimeout(resolve, ms));
+                Javy.onShutdown(async () => { await sleep(10); flush("later"); });
+                Javy.onShutdown(() => flush("now"));
+                "#,
+            )
+            .unwrap();
+        eval_and_call(&bytecode, None, vec![]).unwrap();
+        assert!(unsafe { FLUSHED.is_empty() });
+
+        finalize().unwrap();
+        assert_eq!(vec!["now", "later"], unsafe { FLUSHED.clone() });
+        assert!(unsafe { RUNTIME.get() }.is_none());
+
+        let error = eval_and_call(&bytecode, None, vec![]).unwrap_err();
+        assert_eq!(FINALIZED_ERR, error.to_string());
+        let error = finalize().unwrap_err();
+        assert_eq!(FINALIZED_ERR, error.to_string());
+        assert!(unsafe { runtime_stats() }.is_null());
+        assert_eq!(2, unsafe { FLUSHED.len() });
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+    }
+
+    #[test]
+    #[cfg(not(feature = "messagepack"))]
+    fn test_modules() {
+        let sources = || {
+            BTreeMap::from([(
+                "lib/math.js".to_string(),
+                ModuleSource::Code("export function double(x) { return x * 2; }".into()),
+            )])
+        };
+        let entry = r#"
+            import { double } from "./lib/math.js";
+            export function staticImport() { return double(2); }
+            export async function dynamicImport() {
+                const { double } = await import("./lib/math.js");
+                return double(3);
+            }
+            "#;
+
+        // Each module is compiled by a separate instance of the plugin.
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        let bytecode =
+            compile_with_modules(FUNCTION_MODULE_NAME, entry.as_bytes(), sources()).unwrap();
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        let math = compile_with_modules(
+            "lib/math.js",
+            b"export function double(x) { return x * 2; }",
+            sources(),
+        )
+        .unwrap();
+
+        let mut config = Config::default();
+        config.event_loop(true);
+        initialize_runtime(config, |rt| rt).unwrap();
+        set_bytecode_modules(BTreeMap::from([("lib/math.js".to_string(), math)]));
+        let result = |fn_name| String::from_utf8(call_with_result(&bytecode, fn_name).unwrap());
+        assert_eq!("4", result("staticImport").unwrap());
+        assert_eq!("6", result("dynamicImport").unwrap());
+
+        initialize_runtime(Config::default(), |rt| rt).unwrap();
+        let error = compile_with_modules(
+            FUNCTION_MODULE_NAME,
+            br#"import { x } from "./missing.js";"#,
+            sources(),
+        )
+        .unwrap_err();
+        assert!(
+            error.to_string().contains(
+                "Error resolving module './missing.js' from 'function.mjs': no such module"
+            ),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_parse_module_table() {
+        let mut table = vec![];
+        for (name, code) in [("a.js", &b"a"[..]), ("lib/b.js", b"bb")] {
+            table.extend((name.len() as u32).to_le_bytes());
+            table.extend(name.as_bytes());
+            table.extend((code.len() as u32).to_le_bytes());
+            table.extend(code);
+        }
+        let modules = parse_module_table(&table).unwrap();
+        assert_eq!(
+            vec![("a.js", &b"a"[..]), ("lib/b.js", b"bb")],
+            modules
+                .iter()
+                .map(|(name, code)| (name.as_str(), code.as_slice()))
+                .collect::<Vec<_>>()
+        );
+        assert!(parse_module_table(&[]).unwrap().is_empty());
+
+        let error = parse_module_table(&table[..table.len() - 1]).unwrap_err();
+        assert_eq!("Truncated module table", error.to_string());
+        let error = parse_module_table(&[1, 0]).unwrap_err();
+        assert_eq!("Truncated module table", error.to_string());
+    }
 }
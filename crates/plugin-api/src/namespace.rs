@@ -1,8 +1,18 @@
 /// Create a custom section named `import_namespace` with the contents of the
 /// string argument.
+///
+/// An optional second argument declares the ABI version of the plugin, which
+/// defaults to `0`. It's stored as a little-endian `u32` in a custom section
+/// named `abi_version` and returned by the `plugin_abi_version` export.
+/// Dynamically linked modules record the namespace and ABI version of the
+/// plugin they're generated with, so they can be checked against the plugin
+/// they're run with.
 #[macro_export]
 macro_rules! import_namespace {
     ($str:literal) => {
+        $crate::import_namespace!($str, 0);
+    };
+    ($str:literal, $version:literal) => {
         const IMPORT_NAMESPACE_BYTES: &[u8] = $str.as_bytes();
 
         #[link_section = "import_namespace"]
@@ -15,5 +25,16 @@ macro_rules! import_namespace {
             }
             arr
         };
+
+        const PLUGIN_ABI_VERSION: u32 = $version;
+
+        #[link_section = "abi_version"]
+        pub static PLUGIN_ABI_VERSION_BYTES: [u8; 4] = PLUGIN_ABI_VERSION.to_le_bytes();
+
+        /// The ABI version of the plugin.
+        #[export_name = "plugin_abi_version"]
+        pub extern "C" fn plugin_abi_version() -> u32 {
+            PLUGIN_ABI_VERSION
+        }
     };
 }
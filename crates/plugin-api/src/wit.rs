@@ -0,0 +1,575 @@
+//! Lifting the arguments and lowering the results of exported functions with
+//! WIT parameters and results, following the canonical ABI.
+//!
+//! The WIT types of a function are described by a signature such as
+//! `(string,{name:string,age:u32})->string`, where a record is written as its
+//! fields between braces, with the names of the fields as seen by JavaScript.
+
+use anyhow::{anyhow, bail, Result};
+use javy::alloc::{canonical_abi_free, canonical_abi_realloc};
+use javy::quickjs::{self, BigInt, Ctx, Object, String as JSString, Value};
+use std::{ptr, slice, str};
+
+/// The WIT types supported in the signatures of exported functions.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum WitType {
+    Bool,
+    S8,
+    U8,
+    S16,
+    U16,
+    S32,
+    U32,
+    S64,
+    U64,
+    F32,
+    F64,
+    Char,
+    String,
+    /// A record, with the names of its fields as seen by JavaScript.
+    Record(Vec<(String, WitType)>),
+}
+
+/// The core Wasm types values are flattened to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FlatType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+/// The parameters and result of an exported function.
+#[derive(Debug, PartialEq)]
+pub(crate) struct Signature {
+    pub(crate) params: Vec<WitType>,
+    pub(crate) result: Option<WitType>,
+}
+
+/// A value lifted from its flattened representation, before it's converted
+/// to a JavaScript value.
+#[derive(Debug, PartialEq)]
+pub(crate) enum WitValue {
+    Bool(bool),
+    Number(f64),
+    BigInt(i64),
+    BigUint(u64),
+    String(String),
+    Record(Vec<(String, WitValue)>),
+}
+
+impl Signature {
+    /// Parses a signature, as described in the module documentation.
+    pub(crate) fn parse(signature: &str) -> Result<Self> {
+        let mut parser = Parser { rest: signature };
+        parser.expect("(")?;
+        let mut params = vec![];
+        if !parser.eat(")") {
+            loop {
+                params.push(parser.ty()?);
+                if parser.eat(")") {
+                    break;
+                }
+                parser.expect(",")?;
+            }
+        }
+        let result = if parser.eat("->") {
+            Some(parser.ty()?)
+        } else {
+            None
+        };
+        if !parser.rest.is_empty() {
+            bail!("Unexpected `{}` in signature {signature}", parser.rest);
+        }
+        Ok(Signature { params, result })
+    }
+
+    /// Lifts the arguments from their flattened core Wasm values, each held
+    /// by the low bytes of an 8 byte little-endian slot.
+    ///
+    /// # Safety
+    ///
+    /// * The strings the arguments point to must have been allocated with
+    ///   `canonical_abi_realloc` and are released.
+    pub(crate) unsafe fn lift_args(&self, slots: &[u8]) -> Result<Vec<WitValue>> {
+        let mut slots = slots
+            .as_chunks::<8>()
+            .0
+            .iter()
+            .map(|slot| u64::from_le_bytes(*slot));
+        self.params.iter().map(|ty| ty.lift(&mut slots)).collect()
+    }
+}
+
+/// A cursor over a signature.
+struct Parser<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    fn eat(&mut self, token: &str) -> bool {
+        match self.rest.strip_prefix(token) {
+            Some(rest) => {
+                self.rest = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> Result<()> {
+        if !self.eat(token) {
+            bail!("Expected `{token}` in signature at `{}`", self.rest);
+        }
+        Ok(())
+    }
+
+    fn ident(&mut self) -> Result<&'a str> {
+        let len = self
+            .rest
+            .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '$'))
+            .unwrap_or(self.rest.len());
+        if len == 0 {
+            bail!("Expected a name in signature at `{}`", self.rest);
+        }
+        let (ident, rest) = self.rest.split_at(len);
+        self.rest = rest;
+        Ok(ident)
+    }
+
+    fn ty(&mut self) -> Result<WitType> {
+        if self.eat("{") {
+            let mut fields = vec![];
+            loop {
+                let name = self.ident()?.to_string();
+                self.expect(":")?;
+                fields.push((name, self.ty()?));
+                if self.eat("}") {
+                    return Ok(WitType::Record(fields));
+                }
+                self.expect(",")?;
+            }
+        }
+        Ok(match self.ident()? {
+            "bool" => WitType::Bool,
+            "s8" => WitType::S8,
+            "u8" => WitType::U8,
+            "s16" => WitType::S16,
+            "u16" => WitType::U16,
+            "s32" => WitType::S32,
+            "u32" => WitType::U32,
+            "s64" => WitType::S64,
+            "u64" => WitType::U64,
+            "f32" => WitType::F32,
+            "f64" => WitType::F64,
+            "char" => WitType::Char,
+            "string" => WitType::String,
+            name => bail!("Unsupported type `{name}` in signature"),
+        })
+    }
+}
+
+impl WitType {
+    /// Appends the core Wasm types the type is flattened to.
+    fn flatten(&self, flat: &mut Vec<FlatType>) {
+        match self {
+            WitType::S64 | WitType::U64 => flat.push(FlatType::I64),
+            WitType::F32 => flat.push(FlatType::F32),
+            WitType::F64 => flat.push(FlatType::F64),
+            WitType::String => flat.extend([FlatType::I32, FlatType::I32]),
+            WitType::Record(fields) => fields.iter().for_each(|(_, ty)| ty.flatten(flat)),
+            _ => flat.push(FlatType::I32),
+        }
+    }
+
+    /// The core Wasm type of the type if it's flattened to a single value.
+    fn flat_type(&self) -> Option<FlatType> {
+        let mut flat = vec![];
+        self.flatten(&mut flat);
+        match flat[..] {
+            [flat] => Some(flat),
+            _ => None,
+        }
+    }
+
+    /// The size of the type in memory.
+    fn size(&self) -> usize {
+        match self {
+            WitType::Bool | WitType::S8 | WitType::U8 => 1,
+            WitType::S16 | WitType::U16 => 2,
+            WitType::S32 | WitType::U32 | WitType::F32 | WitType::Char => 4,
+            WitType::S64 | WitType::U64 | WitType::F64 | WitType::String => 8,
+            WitType::Record(fields) => {
+                let size = fields.iter().fold(0usize, |offset, (_, ty)| {
+                    offset.next_multiple_of(ty.align()) + ty.size()
+                });
+                size.next_multiple_of(self.align())
+            }
+        }
+    }
+
+    /// The alignment of the type in memory.
+    fn align(&self) -> usize {
+        match self {
+            WitType::String => 4,
+            WitType::Record(fields) => fields.iter().map(|(_, ty)| ty.align()).max().unwrap_or(1),
+            _ => self.size(),
+        }
+    }
+
+    /// Lifts a value from the slots of its flattened representation.
+    unsafe fn lift(&self, slots: &mut impl Iterator<Item = u64>) -> Result<WitValue> {
+        let mut next = || slots.next().ok_or_else(|| anyhow!("Missing arguments"));
+        Ok(match self {
+            WitType::Bool => WitValue::Bool(next()? as u32 != 0),
+            WitType::S8 => WitValue::Number((next()? as i8).into()),
+            WitType::U8 => WitValue::Number((next()? as u8).into()),
+            WitType::S16 => WitValue::Number((next()? as i16).into()),
+            WitType::U16 => WitValue::Number((next()? as u16).into()),
+            WitType::S32 => WitValue::Number((next()? as i32).into()),
+            WitType::U32 => WitValue::Number((next()? as u32).into()),
+            WitType::S64 => WitValue::BigInt(next()? as i64),
+            WitType::U64 => WitValue::BigUint(next()?),
+            WitType::F32 => WitValue::Number(f32::from_bits(next()? as u32).into()),
+            WitType::F64 => WitValue::Number(f64::from_bits(next()?)),
+            WitType::Char => {
+                let code = next()? as u32;
+                let c = char::from_u32(code)
+                    .ok_or_else(|| anyhow!("Invalid char argument {code:#x}"))?;
+                WitValue::String(c.to_string())
+            }
+            WitType::String => {
+                let ptr = next()? as usize as *mut u8;
+                let len = next()? as usize;
+                let string = str::from_utf8(slice::from_raw_parts(ptr, len))
+                    .map(str::to_string)
+                    .map_err(|_| anyhow!("String arguments must be UTF-8"));
+                canonical_abi_free(ptr, len, 1);
+                WitValue::String(string?)
+            }
+            WitType::Record(fields) => WitValue::Record(
+                fields
+                    .iter()
+                    .map(|(name, ty)| Ok((name.clone(), ty.lift(slots)?)))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+
+    /// Lowers a value of a type flattened to a single value to the bits of
+    /// its slot.
+    fn lower_flat(&self, value: &Value) -> Result<u64> {
+        Ok(match (self, self.flat_type()) {
+            (WitType::Record(fields), _) => {
+                let [(name, ty)] = &fields[..] else {
+                    bail!("Record with several fields lowered as a single value");
+                };
+                ty.lower_flat(&field(value, name)?)?
+            }
+            (WitType::Bool, _) => expect_bool(value)? as u64,
+            (WitType::S64, _) => expect_i64(value)? as u64,
+            (WitType::U64, _) => expect_i64(value)? as u64,
+            (_, Some(FlatType::F32)) => (expect_number(value)? as f32).to_bits().into(),
+            (_, Some(FlatType::F64)) => expect_number(value)?.to_bits(),
+            (WitType::Char, _) => expect_char(value)?.into(),
+            (_, Some(FlatType::I32)) => expect_number(value)? as i64 as u32 as u64,
+            _ => bail!("Value lowered as a single value"),
+        })
+    }
+
+    /// Stores a value at `ptr`, allocating the strings it holds.
+    unsafe fn store(
+        &self,
+        value: &Value,
+        ptr: *mut u8,
+        allocations: &mut Allocations,
+    ) -> Result<()> {
+        match self {
+            WitType::Bool | WitType::S8 | WitType::U8 => {
+                ptr.write(self.lower_flat(value)? as u8);
+            }
+            WitType::S16 | WitType::U16 => {
+                ptr.cast::<u16>()
+                    .write_unaligned((self.lower_flat(value)? as u16).to_le());
+            }
+            WitType::S32 | WitType::U32 | WitType::F32 | WitType::Char => {
+                ptr.cast::<u32>()
+                    .write_unaligned((self.lower_flat(value)? as u32).to_le());
+            }
+            WitType::S64 | WitType::U64 | WitType::F64 => {
+                ptr.cast::<u64>()
+                    .write_unaligned(self.lower_flat(value)?.to_le());
+            }
+            WitType::String => {
+                let string = expect_string(value)?;
+                let bytes = allocations.alloc(string.len(), 1);
+                ptr::copy_nonoverlapping(string.as_ptr(), bytes, string.len());
+                ptr.cast::<u32>().write_unaligned((bytes as u32).to_le());
+                ptr.add(4)
+                    .cast::<u32>()
+                    .write_unaligned((string.len() as u32).to_le());
+            }
+            WitType::Record(fields) => {
+                let mut offset = 0usize;
+                for (name, ty) in fields {
+                    offset = offset.next_multiple_of(ty.align());
+                    ty.store(&field(value, name)?, ptr.add(offset), allocations)?;
+                    offset += ty.size();
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The name of the type in error messages.
+    fn name(&self) -> &'static str {
+        match self {
+            WitType::Record(_) => "record",
+            WitType::String | WitType::Char => "string",
+            WitType::Bool => "boolean",
+            _ => "number",
+        }
+    }
+}
+
+/// The memory allocated for the results of exported functions, which stays
+/// valid until the next function is invoked.
+pub(crate) struct Allocations(Vec<(*mut u8, usize, usize)>);
+
+impl Allocations {
+    pub(crate) const fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    unsafe fn alloc(&mut self, size: usize, align: usize) -> *mut u8 {
+        let ptr = canonical_abi_realloc(ptr::null_mut(), 0, align, size).cast();
+        self.0.push((ptr, size, align));
+        ptr
+    }
+
+    /// Releases the memory allocated for the previous results.
+    pub(crate) unsafe fn release(&mut self) {
+        for (ptr, size, align) in self.0.drain(..) {
+            canonical_abi_free(ptr, size, align);
+        }
+    }
+}
+
+/// Lowers the result of an exported function.
+///
+/// Returns a pointer to the 8 byte slot holding the result if it's flattened
+/// to a single core Wasm value, or to the result stored in memory otherwise.
+///
+/// # Safety
+///
+/// * The returned memory must not be read after `allocations` are released.
+pub(crate) unsafe fn lower_result(
+    ty: &WitType,
+    value: &Value,
+    slot: &mut u64,
+    allocations: &mut Allocations,
+) -> Result<*const u8> {
+    if ty.flat_type().is_some() {
+        *slot = ty.lower_flat(value)?.to_le();
+        return Ok((slot as *mut u64).cast());
+    }
+    let ptr = allocations.alloc(ty.size(), ty.align());
+    ty.store(value, ptr, allocations)?;
+    Ok(ptr)
+}
+
+impl WitValue {
+    /// Converts the value to a JavaScript value.
+    pub(crate) fn into_js<'js>(self, this: &Ctx<'js>) -> quickjs::Result<Value<'js>> {
+        Ok(match self {
+            WitValue::Bool(b) => Value::new_bool(this.clone(), b),
+            WitValue::Number(n) => Value::new_number(this.clone(), n),
+            WitValue::BigInt(n) => BigInt::from_i64(this.clone(), n)?.into_value(),
+            WitValue::BigUint(n) => BigInt::from_u64(this.clone(), n)?.into_value(),
+            WitValue::String(s) => JSString::from_str(this.clone(), &s)?.into_value(),
+            WitValue::Record(fields) => {
+                let object = Object::new(this.clone())?;
+                for (name, value) in fields {
+                    object.set(name, value.into_js(this)?)?;
+                }
+                object.into_value()
+            }
+        })
+    }
+}
+
+fn field<'js>(value: &Value<'js>, name: &str) -> Result<Value<'js>> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| anyhow!("Expected a record, got {}", value.type_name()))?;
+    Ok(object.get(name)?)
+}
+
+fn expect_bool(value: &Value) -> Result<bool> {
+    value
+        .as_bool()
+        .ok_or_else(|| mismatch(&WitType::Bool, value))
+}
+
+fn expect_number(value: &Value) -> Result<f64> {
+    value
+        .as_number()
+        .ok_or_else(|| mismatch(&WitType::F64, value))
+}
+
+fn expect_i64(value: &Value) -> Result<i64> {
+    match value.as_big_int() {
+        Some(n) => Ok(n.clone().to_i64()?),
+        None => Ok(expect_number(value)? as i64),
+    }
+}
+
+fn expect_string(value: &Value) -> Result<String> {
+    match value.as_string() {
+        Some(s) => Ok(s.to_string()?),
+        None => Err(mismatch(&WitType::String, value)),
+    }
+}
+
+fn expect_char(value: &Value) -> Result<u32> {
+    let string = expect_string(value)?;
+    let mut chars = string.chars();
+    match (chars.next(), chars.next()) {
+        (Some(c), None) => Ok(c.into()),
+        _ => bail!("Expected a single character, got {string:?}"),
+    }
+}
+
+fn mismatch(ty: &WitType, value: &Value) -> anyhow::Error {
+    anyhow!("Expected a {}, got {}", ty.name(), value.type_name())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lower_result, Allocations, Signature, WitType, WitValue};
+    use anyhow::Result;
+    use javy::{quickjs::Value, Runtime};
+
+    #[test]
+    fn test_parse_signature() -> Result<()> {
+        assert_eq!(
+            Signature {
+                params: vec![],
+                result: None
+            },
+            Signature::parse("()")?
+        );
+        assert_eq!(
+            Signature {
+                params: vec![
+                    WitType::String,
+                    WitType::Record(vec![
+                        ("name".to_string(), WitType::String),
+                        ("age".to_string(), WitType::U32),
+                    ]),
+                ],
+                result: Some(WitType::S64),
+            },
+            Signature::parse("(string,{name:string,age:u32})->s64")?
+        );
+        assert_eq!(
+            "Unsupported type `list` in signature",
+            Signature::parse("(list)").unwrap_err().to_string()
+        );
+        assert!(Signature::parse("(string").is_err());
+        assert!(Signature::parse("()->").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_layout() -> Result<()> {
+        let record = Signature::parse("()->{a:u8,b:string,c:u16,d:f64}")?
+            .result
+            .unwrap();
+        assert_eq!(8, record.align());
+        assert_eq!(24, record.size());
+        assert_eq!(2, WitType::Record(vec![("a".into(), WitType::U16)]).size());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lift_args() -> Result<()> {
+        let signature = Signature::parse("(bool,s8,u32,s64,f32,char,{x:f64,y:u16})")?;
+        let slots: Vec<u8> = [
+            1u64,
+            0xff,
+            u32::MAX.into(),
+            -2i64 as u64,
+            1.5f32.to_bits().into(),
+            'é'.into(),
+            2.25f64.to_bits(),
+            7,
+        ]
+        .iter()
+        .flat_map(|slot| slot.to_le_bytes())
+        .collect();
+        assert_eq!(
+            vec![
+                WitValue::Bool(true),
+                WitValue::Number(-1.0),
+                WitValue::Number(u32::MAX.into()),
+                WitValue::BigInt(-2),
+                WitValue::Number(1.5),
+                WitValue::String("é".to_string()),
+                WitValue::Record(vec![
+                    ("x".to_string(), WitValue::Number(2.25)),
+                    ("y".to_string(), WitValue::Number(7.0)),
+                ]),
+            ],
+            unsafe { signature.lift_args(&slots)? }
+        );
+        assert!(unsafe { signature.lift_args(&slots[..8]) }.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lower_flat_results() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|this| {
+            let lower = |ty: &str, js: &str| -> Result<u64> {
+                let ty = Signature::parse(&format!("()->{ty}"))?.result.unwrap();
+                let value: Value = this.eval(js)?;
+                let mut slot = 0;
+                let mut allocations = Allocations::new();
+                let ptr = unsafe { lower_result(&ty, &value, &mut slot, &mut allocations)? };
+                assert_eq!(ptr, (&slot as *const u64).cast());
+                Ok(slot)
+            };
+            assert_eq!(1, lower("bool", "true")?);
+            assert_eq!(u32::MAX as u64, lower("s32", "-1")?);
+            assert_eq!(-3i64 as u64, lower("s64", "-3n")?);
+            assert_eq!(42, lower("u64", "42")?);
+            assert_eq!(0.5f32.to_bits() as u64, lower("f32", "0.5")?);
+            assert_eq!('x' as u64, lower("char", "'x'")?);
+            assert_eq!(9, lower("{count:u32}", "({ count: 9 })")?);
+            assert_eq!(
+                "Expected a number, got string",
+                lower("u32", "'9'").unwrap_err().to_string()
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_store_record() -> Result<()> {
+        let runtime = Runtime::default();
+        runtime.context().with(|this| {
+            let ty = Signature::parse("()->{a:u8,c:u16,d:f64}")?.result.unwrap();
+            let value: Value = this.eval("({ a: 1, c: 515, d: 0.5 })")?;
+            let mut slot = 0;
+            let mut allocations = Allocations::new();
+            let ptr = unsafe { lower_result(&ty, &value, &mut slot, &mut allocations)? };
+            let stored = unsafe { std::slice::from_raw_parts(ptr, ty.size()) };
+            assert_eq!(1, stored[0]);
+            assert_eq!(515u16.to_le_bytes(), stored[2..4]);
+            assert_eq!(0.5f64.to_le_bytes(), stored[8..]);
+            unsafe { allocations.release() };
+            Ok(())
+        })
+    }
+}
@@ -2,6 +2,7 @@ use javy_plugin_api::{import_namespace, Config};
 use shared_config::{SharedConfig, SharedConfigExtended};
 use std::io;
 use std::io::Read;
+use std::process;
 use std::slice;
 
 mod shared_config;
@@ -25,14 +26,26 @@ pub extern "C" fn initialize_runtime() {
     let mut config_bytes = vec![];
     let shared_config = match io::stdin().read_to_end(&mut config_bytes) {
         Ok(0) => None,
-        Ok(_) => Some(SharedConfigExtended::parse_extended_from_json(&config_bytes).unwrap()),
+        Ok(_) => match SharedConfigExtended::parse_extended_from_json(&config_bytes) {
+            Ok(shared_config) => Some(shared_config),
+            Err(e) => exit_with_error(e.context("failed to parse runtime config")),
+        },
         Err(e) => panic!("Error reading from stdin: {e}"),
     };
     if let Some(shared_config) = shared_config {
         shared_config.apply_to_config(&mut config);
     }
 
-    javy_plugin_api::initialize_runtime(config, |runtime| runtime).unwrap();
+    if let Err(e) = javy_plugin_api::initialize_runtime(config, |runtime| runtime) {
+        exit_with_error(e);
+    }
+}
+
+/// Writes the error to stderr and exits, so that a configuration problem is
+/// reported without trapping.
+fn exit_with_error(error: anyhow::Error) -> ! {
+    eprintln!("Error initializing runtime: {error:#}");
+    process::exit(1);
 }
 
 /// Evaluates QuickJS bytecode
@@ -1,26 +1,34 @@
 //! APIs and data structures for receiving runtime configuration from the Javy CLI.
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use javy_plugin_api::Config;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::io::{stdout, Write};
 
 mod runtime_config;
 
+/// The newest version of the runtime config the plugin understands. The CLI
+/// passes the version of the config it generates as `config-version`.
+const CONFIG_VERSION: u64 = 1;
+
 use crate::runtime_config;
 
 runtime_config! {
     #[derive(Debug, Default, Deserialize)]
-    #[serde(deny_unknown_fields, rename_all = "kebab-case")]
+    #[serde(rename_all = "kebab-case")]
     pub struct SharedConfig {
         /// Whether to enable the `Javy.readSync` and `Javy.writeSync` builtins.
+        #[default = true]
         javy_stream_io: Option<bool>,
         /// Whether to override the `JSON.parse` and `JSON.stringify`
         /// implementations with an alternative, more performant, SIMD based
         /// implemetation.
+        #[default = true]
         simd_json_builtins: Option<bool>,
         /// Whether to enable support for the `TextEncoder` and `TextDecoder`
         /// APIs.
+        #[default = true]
         text_encoding: Option<bool>,
         /// Whether to enable the event loop.
         event_loop: Option<bool>,
@@ -30,86 +38,218 @@ runtime_config! {
         redirect_stdout_to_stderr: Option<bool>,
         /// Whether to wait for async operations (timers, promises) to complete before exiting.
         wait_for_completion: Option<bool>,
+        /// Whether to cancel pending timers, including intervals, when `wait-timeout-ms` is reached.
+        cancel_timers_on_timeout: Option<bool>,
+        /// Whether the `list_exports` export can list the names exported by a module.
+        list_exports: Option<bool>,
+        /// Whether to enable a V8 compatible `Error.captureStackTrace`.
+        error_capture_stack_trace: Option<bool>,
+        /// Whether to enable the global `reportError` function.
+        report_error: Option<bool>,
+        /// Whether to enable the global `structuredClone` function.
+        structured_clone: Option<bool>,
+        /// Whether to enable the global `crypto` object with `crypto.subtle.digest`.
+        crypto: Option<bool>,
+        /// Whether to enable a minimal global `navigator` object.
+        navigator: Option<bool>,
+        /// Whether to enable the global `AbortController` and `AbortSignal` classes.
+        abort_controller: Option<bool>,
+        /// Whether to enable the global `Event` and `EventTarget` classes.
+        events: Option<bool>,
+        /// Whether to enable the global `ReadableStream`, `WritableStream` and
+        /// `TransformStream` classes.
+        streams: Option<bool>,
+        /// Whether to enable the global `CompressionStream` and
+        /// `DecompressionStream` classes. Requires `streams`.
+        compression_streams: Option<bool>,
+        /// Whether to enable the global `FormData` class.
+        form_data: Option<bool>,
+        /// Whether to detect the type of blobs created without one from their content.
+        blob_sniff_type: Option<bool>,
+        /// Whether reading a blob with an unknown ID throws rather than reading an empty file.
+        #[default = true]
+        strict_blob: Option<bool>,
+        /// Whether the handles releasing the storage of blobs are held in a `WeakMap` keyed by the blobs.
+        blob_weak_map_handles: Option<bool>,
+        /// Whether to reuse regular expressions constructed from the same string pattern and flags.
+        regexp_cache: Option<bool>,
+        /// Whether to write a warning to stderr the first time each deprecated API is used.
+        deprecation_warnings: Option<bool>,
+        /// Whether to enable the `Javy.memoryUsage` function.
+        memory_usage: Option<bool>,
+        /// Whether each invocation runs in a fresh context, not seeing the globals and timers of the previous ones.
+        isolate_invocations: Option<bool>,
+        /// Whether errors abort the instance rather than being written to stderr before returning normally.
+        #[default = true]
+        abort_on_error: Option<bool>,
+        /// Whether errors are written to stderr as a single line of JSON with their type, message and stack.
+        structured_errors: Option<bool>,
+        /// Exit code the instance exits with after an error, instead of trapping, when errors abort it.
+        #[min = 1]
+        #[max = 255]
+        #[setter = error_exit_strategy]
+        error_exit_code: Option<u64>,
+        /// Maximum time to wait for async operations in milliseconds.
+        #[min = 1]
+        wait_timeout_ms: Option<u64>,
+        /// Number of QuickJS interrupt handler invocations in an invocation after which execution is interrupted.
+        #[min = 1]
+        interrupt_after: Option<u64>,
+        /// Maximum number of bytes held by the blobs created by JavaScript code.
+        max_blob_storage_bytes: Option<u64>,
+        /// Maximum number of bytes the QuickJS runtime can allocate.
+        #[setter = memory_limit]
+        memory_limit_bytes: Option<u64>,
+        /// Maximum size in bytes of the stack used by the QuickJS runtime.
+        #[default = 256 * 1024]
+        #[setter = max_stack_size]
+        max_stack_size_bytes: Option<u64>,
+        /// Seed of the generator backing `Math.random`, making the numbers it returns the same in each run.
+        random_seed: Option<u64>,
     }
 }
 
-// Additional fields that can't be handled by the runtime_config macro
+// Fields of the runtime config passed by the CLI that aren't runtime config
+// properties
 #[derive(Debug, Default, Deserialize)]
-#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+#[serde(rename_all = "kebab-case")]
 pub struct SharedConfigExtended {
     #[serde(flatten)]
     pub base: SharedConfig,
-    /// Maximum time to wait for async operations in milliseconds.
-    pub wait_timeout_ms: Option<u64>,
+    /// The version of the runtime config, which can't be newer than the one
+    /// the plugin understands.
+    pub config_version: Option<u64>,
+    /// Fields the plugin doesn't know about, like the ones passed by a newer
+    /// CLI, which are ignored with a warning rather than rejected.
+    #[serde(flatten)]
+    unknown_fields: BTreeMap<String, serde_json::Value>,
 }
 
 impl SharedConfig {
     pub fn parse_from_json(config: &[u8]) -> Result<Self> {
-        // First try to parse as extended config to get timeout parameter
-        let extended: SharedConfigExtended = serde_json::from_slice(config)?;
+        let extended = SharedConfigExtended::parse_extended_from_json(config)?;
         Ok(extended.base)
     }
+}
 
-    pub fn apply_to_config(&self, config: &mut Config) {
-        if let Some(enable) = self.javy_stream_io {
-            config.javy_stream_io(enable);
-        }
-        if let Some(enable) = self.simd_json_builtins {
-            config.simd_json_builtins(enable);
-        }
-        if let Some(enable) = self.text_encoding {
-            config.text_encoding(enable);
-        }
-        if let Some(enable) = self.event_loop {
-            config.event_loop(enable);
-        }
-        if let Some(enable) = self.timers {
-            config.timers(enable);
-        }
-        if let Some(enable) = self.redirect_stdout_to_stderr {
-            config.redirect_stdout_to_stderr(enable);
+impl SharedConfigExtended {
+    pub fn parse_extended_from_json(config: &[u8]) -> Result<Self> {
+        let config = serde_json::from_slice::<Self>(config)?;
+        if let Some(version) = config.config_version {
+            if version > CONFIG_VERSION {
+                bail!(
+                    "runtime config version {version} is newer than version {CONFIG_VERSION} \
+                    supported by the plugin, use the plugin of the same Javy release as the CLI"
+                );
+            }
         }
-        if let Some(enable) = self.wait_for_completion {
-            config.wait_for_completion(enable);
+        if let Some(warning) = config.unknown_fields_warning() {
+            eprintln!("{warning}");
         }
+        Ok(config)
     }
-}
 
-impl SharedConfigExtended {
-    pub fn parse_extended_from_json(config: &[u8]) -> Result<Self> {
-        Ok(serde_json::from_slice::<Self>(config)?)
+    /// The warning listing the fields that were ignored because the plugin
+    /// doesn't know about them, if any.
+    fn unknown_fields_warning(&self) -> Option<String> {
+        if self.unknown_fields.is_empty() {
+            return None;
+        }
+        let names = self
+            .unknown_fields
+            .keys()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ");
+        Some(format!(
+            "Warning: ignoring unknown runtime config options: {names}"
+        ))
     }
-    
+
     pub fn apply_to_config(&self, config: &mut Config) {
-        // Apply base config
         self.base.apply_to_config(config);
-        
-        // Apply timeout parameter
-        if let Some(timeout_ms) = self.wait_timeout_ms {
-            config.wait_timeout_ms(Some(timeout_ms));
-        }
     }
 }
 
 #[export_name = "config_schema"]
 pub fn config_schema() {
-    // Get the base schema from the macro
-    let mut base_schema = SharedConfig::config_schema();
-    
-    // Add the wait-timeout-ms parameter
-    base_schema.supported_properties.push(
-        crate::shared_config::runtime_config::ConfigProperty {
-            name: "wait-timeout-ms".to_string(),
-            doc: "Maximum time to wait for async operations in milliseconds.\n".to_string(),
-        }
-    );
-    
+    let schema = SharedConfig::config_schema();
     stdout()
-        .write_all(
-            serde_json::to_string(&base_schema)
-                .unwrap()
-                .as_bytes(),
-        )
+        .write_all(serde_json::to_string(&schema).unwrap().as_bytes())
         .unwrap();
     stdout().flush().unwrap();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_fields_are_ignored_with_a_warning() -> Result<()> {
+        let config = br#"{"timers": true, "wait-timeout-ms": 10, "future-option": 1}"#;
+        let extended = SharedConfigExtended::parse_extended_from_json(config)?;
+        assert_eq!(Some(true), extended.base.timers);
+        assert_eq!(Some(10), extended.base.wait_timeout_ms);
+        assert_eq!(
+            Some("Warning: ignoring unknown runtime config options: future-option".to_string()),
+            extended.unknown_fields_warning()
+        );
+
+        let config = SharedConfig::parse_from_json(br#"{"future-option": true}"#)?;
+        assert_eq!(None, config.timers);
+        Ok(())
+    }
+
+    #[test]
+    fn newer_config_versions_are_rejected() -> Result<()> {
+        let config = format!(r#"{{"config-version": {CONFIG_VERSION}, "timers": true}}"#);
+        let extended = SharedConfigExtended::parse_extended_from_json(config.as_bytes())?;
+        assert_eq!(Some(true), extended.base.timers);
+
+        let config = format!(r#"{{"config-version": {}}}"#, CONFIG_VERSION + 1);
+        let error = SharedConfigExtended::parse_extended_from_json(config.as_bytes())
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            format!(
+                "runtime config version {} is newer than version {CONFIG_VERSION} supported by \
+                the plugin, use the plugin of the same Javy release as the CLI",
+                CONFIG_VERSION + 1
+            ),
+            error
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn schema_declares_the_type_of_properties() {
+        let schema = serde_json::to_value(SharedConfig::config_schema()).unwrap();
+        let property = |name: &str| {
+            schema["supportedProperties"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .find(|property| property["name"] == name)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(
+            serde_json::json!({
+                "name": "wait-timeout-ms",
+                "doc": "Maximum time to wait for async operations in milliseconds.\n",
+                "type": "number",
+                "min": 1,
+            }),
+            property("wait-timeout-ms")
+        );
+        assert_eq!("bool", property("timers")["type"]);
+        assert_eq!(256 * 1024, property("max-stack-size-bytes")["default"]);
+    }
+
+    #[test]
+    fn known_fields_do_not_warn() -> Result<()> {
+        let extended = SharedConfigExtended::parse_extended_from_json(br#"{"timers": false}"#)?;
+        assert_eq!(None, extended.unknown_fields_warning());
+        Ok(())
+    }
+}
@@ -1,3 +1,4 @@
+use javy_plugin_api::ExitStrategy;
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -11,10 +12,87 @@ pub(super) struct ConfigSchema {
 pub(super) struct ConfigProperty {
     pub(super) name: String,
     pub(super) doc: String,
+    /// The type of the property's value, one of `bool`, `number` or `string`.
+    #[serde(rename = "type")]
+    pub(super) ty: &'static str,
+    /// The value used when the property isn't set, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) default: Option<serde_json::Value>,
+    /// The smallest value of a numeric property, if bounded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) min: Option<u64>,
+    /// The largest value of a numeric property, if bounded.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub(super) max: Option<u64>,
 }
 
+/// Converts the value of a runtime config property to the argument of the
+/// [`javy_plugin_api::Config`] method applying it.
+pub(super) trait ConfigArg<T> {
+    fn into_arg(self) -> T;
+}
+
+impl ConfigArg<bool> for bool {
+    fn into_arg(self) -> bool {
+        self
+    }
+}
+
+impl ConfigArg<u64> for u64 {
+    fn into_arg(self) -> u64 {
+        self
+    }
+}
+
+impl ConfigArg<Option<u64>> for u64 {
+    fn into_arg(self) -> Option<u64> {
+        Some(self)
+    }
+}
+
+impl ConfigArg<usize> for u64 {
+    /// Saturates values that don't fit in a `usize`.
+    fn into_arg(self) -> usize {
+        usize::try_from(self).unwrap_or(usize::MAX)
+    }
+}
+
+impl ConfigArg<ExitStrategy> for u64 {
+    /// Saturates codes above 255.
+    fn into_arg(self) -> ExitStrategy {
+        ExitStrategy::ExitCode(u8::try_from(self).unwrap_or(u8::MAX))
+    }
+}
+
+impl ConfigArg<i64> for i64 {
+    fn into_arg(self) -> i64 {
+        self
+    }
+}
+
+/// Declares a struct of runtime config properties, along with its
+/// `config_schema` and an `apply_to_config` method calling the
+/// [`javy_plugin_api::Config`] method with the name of each property that is
+/// set, or the one given with `#[setter = name]`.
+///
+/// Properties are `Option<bool>`, `Option<u64>` or `Option<i64>`, and can
+/// declare a `#[default = value]` and, for numbers, `#[min = value]` and
+/// `#[max = value]` bounds.
 #[macro_export]
 macro_rules! runtime_config {
+    (@default) => { None };
+    (@default $default:expr) => { Some(serde_json::Value::from($default)) };
+    (@bound) => { None };
+    (@bound $bound:literal) => { Some($bound) };
+    (@type bool) => { "bool" };
+    (@type u64) => { "number" };
+    (@type i64) => { "number" };
+    (@apply $config:ident, $value:ident, $opt:ident) => {
+        $config.$opt($crate::shared_config::runtime_config::ConfigArg::into_arg($value))
+    };
+    (@apply $config:ident, $value:ident, $opt:ident, $setter:ident) => {
+        $config.$setter($crate::shared_config::runtime_config::ConfigArg::into_arg($value))
+    };
     (
         $(#[$attr:meta])*
         pub struct $opts:ident {
@@ -22,7 +100,19 @@ macro_rules! runtime_config {
                 $(
                     #[doc = $doc:tt]
                 )*
-                $opt:ident: Option<bool>,
+                $(
+                    #[default = $default:expr]
+                )?
+                $(
+                    #[min = $min:literal]
+                )?
+                $(
+                    #[max = $max:literal]
+                )?
+                $(
+                    #[setter = $setter:ident]
+                )?
+                $opt:ident: Option<$ty:ident>,
             )+
         }
     ) => {
@@ -32,7 +122,7 @@ macro_rules! runtime_config {
                 $(
                     #[doc = $doc]
                 )*
-                $opt: Option<bool>,
+                $opt: Option<$ty>,
             )+
         }
 
@@ -45,12 +135,24 @@ macro_rules! runtime_config {
                                 $crate::shared_config::runtime_config::ConfigProperty {
                                     name: stringify!($opt).replace('_', "-").to_string(),
                                     doc: concat!($($doc, "\n",)*).into(),
+                                    ty: $crate::runtime_config!(@type $ty),
+                                    default: $crate::runtime_config!(@default $($default)?),
+                                    min: $crate::runtime_config!(@bound $($min)?),
+                                    max: $crate::runtime_config!(@bound $($max)?),
                                 }
                             },
                         )+
                     ]
                 }
             }
+
+            pub fn apply_to_config(&self, config: &mut javy_plugin_api::Config) {
+                $(
+                    if let Some(value) = self.$opt {
+                        $crate::runtime_config!(@apply config, value, $opt $(, $setter)?);
+                    }
+                )+
+            }
         }
     }
 }
@@ -11,6 +11,10 @@ use wasmtime::{AsContextMut, Config, Engine, Instance, Linker, Module, OptLevel,
 use wasmtime_wasi::pipe::{MemoryInputPipe, MemoryOutputPipe};
 use wasmtime_wasi::{preview1::WasiP1Ctx, WasiCtxBuilder};
 
+/// The bit set in the length returned by `compile_src` when the buffer holds
+/// an error message rather than bytecode.
+pub const COMPILE_ERROR_FLAG: u32 = 1 << 31;
+
 #[derive(Clone)]
 pub enum JavyCommand {
     Build,
@@ -86,6 +90,24 @@ pub struct Builder {
     redirect_stdout_to_stderr: Option<bool>,
     /// Whether to wait for async operations to complete before exiting.
     wait_for_completion: Option<bool>,
+    /// Number of interrupt handler invocations before execution is
+    /// interrupted.
+    interrupt_after: Option<u64>,
+    /// Maximum number of bytes the QuickJS runtime can allocate.
+    memory_limit_bytes: Option<u64>,
+    /// Whether to enable the global `reportError` function.
+    report_error: Option<bool>,
+    /// Whether errors abort the instance.
+    abort_on_error: Option<bool>,
+    /// Runtime config properties without a dedicated option, like the ones
+    /// supported by a user plugin.
+    js_options: Vec<(String, String)>,
+    /// Additional modules the JS source can import, by name.
+    modules: Vec<(String, PathBuf)>,
+    /// Whether to minify the JS source before compiling it.
+    minify: Option<bool>,
+    /// Whether to build with `--deterministic`.
+    deterministic: bool,
     built: bool,
     /// Preload the module at path, using the given instance name.
     preload: Option<(String, PathBuf)>,
@@ -114,6 +136,14 @@ impl Default for Builder {
             timers: None,
             redirect_stdout_to_stderr: None,
             wait_for_completion: None,
+            interrupt_after: None,
+            memory_limit_bytes: None,
+            report_error: None,
+            abort_on_error: None,
+            js_options: vec![],
+            modules: vec![],
+            minify: None,
+            deterministic: false,
             plugin: Plugin::Default,
         }
     }
@@ -185,6 +215,46 @@ impl Builder {
         self
     }
 
+    pub fn interrupt_after(&mut self, count: u64) -> &mut Self {
+        self.interrupt_after = Some(count);
+        self
+    }
+
+    pub fn memory_limit_bytes(&mut self, bytes: u64) -> &mut Self {
+        self.memory_limit_bytes = Some(bytes);
+        self
+    }
+
+    pub fn report_error(&mut self, enabled: bool) -> &mut Self {
+        self.report_error = Some(enabled);
+        self
+    }
+
+    pub fn abort_on_error(&mut self, enabled: bool) -> &mut Self {
+        self.abort_on_error = Some(enabled);
+        self
+    }
+
+    pub fn js_option(&mut self, name: impl Into<String>, value: impl Into<String>) -> &mut Self {
+        self.js_options.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn module(&mut self, name: impl Into<String>, path: impl Into<PathBuf>) -> &mut Self {
+        self.modules.push((name.into(), path.into()));
+        self
+    }
+
+    pub fn minify(&mut self, enabled: bool) -> &mut Self {
+        self.minify = Some(enabled);
+        self
+    }
+
+    pub fn deterministic(&mut self, enabled: bool) -> &mut Self {
+        self.deterministic = enabled;
+        self
+    }
+
     pub fn command(&mut self, command: JavyCommand) -> &mut Self {
         self.command = command;
         self
@@ -219,6 +289,14 @@ impl Builder {
             timers,
             redirect_stdout_to_stderr,
             wait_for_completion,
+            interrupt_after,
+            memory_limit_bytes,
+            report_error,
+            abort_on_error,
+            js_options,
+            modules,
+            minify,
+            deterministic,
             built: _,
             preload,
             command,
@@ -248,6 +326,14 @@ impl Builder {
                 timers,
                 redirect_stdout_to_stderr,
                 wait_for_completion,
+                interrupt_after,
+                memory_limit_bytes,
+                report_error,
+                abort_on_error,
+                js_options,
+                modules,
+                minify,
+                deterministic,
                 plugin,
             ),
         }
@@ -308,6 +394,8 @@ impl StoreContext {
 pub enum UseExportedFn {
     EvalBytecode,
     Invoke(Option<&'static str>),
+    /// Compiles and evaluates the source in one call with `eval_src`.
+    EvalSrc(Option<&'static str>),
 }
 
 impl Runner {
@@ -325,6 +413,14 @@ impl Runner {
         timers: Option<bool>,
         redirect_stdout_to_stderr: Option<bool>,
         wait_for_completion: Option<bool>,
+        interrupt_after: Option<u64>,
+        memory_limit_bytes: Option<u64>,
+        report_error: Option<bool>,
+        abort_on_error: Option<bool>,
+        js_options: Vec<(String, String)>,
+        modules: Vec<(String, PathBuf)>,
+        minify: Option<bool>,
+        deterministic: bool,
         plugin: Plugin,
     ) -> Result<Self> {
         // This directory is unique and will automatically get deleted
@@ -333,6 +429,10 @@ impl Runner {
         let wasm_file = Self::out_wasm(&tempdir);
         let js_file = root.join(source);
         let wit_file = wit.map(|p| root.join(p));
+        let modules: Vec<_> = modules
+            .into_iter()
+            .map(|(name, path)| (name, root.join(path)))
+            .collect();
 
         let args = Self::build_args(
             &js_file,
@@ -347,6 +447,14 @@ impl Runner {
             &timers,
             &redirect_stdout_to_stderr,
             &wait_for_completion,
+            &interrupt_after,
+            &memory_limit_bytes,
+            &report_error,
+            &abort_on_error,
+            &js_options,
+            &modules,
+            &minify,
+            deterministic,
             &plugin,
         );
 
@@ -544,6 +652,59 @@ impl Runner {
         Ok(())
     }
 
+    /// The import namespace and ABI version of the plugin the module was
+    /// dynamically linked against, if it records them.
+    pub fn linked_plugin(&self) -> Result<Option<(String, u32)>> {
+        Self::read_linked_plugin(&self.wasm)
+    }
+
+    fn read_linked_plugin(wasm: &[u8]) -> Result<Option<(String, u32)>> {
+        let Some(section) = Self::custom_section(wasm, "javy_linked_plugin")? else {
+            return Ok(None);
+        };
+        if section.len() < 4 {
+            bail!("Invalid javy_linked_plugin custom section");
+        }
+        let (version, namespace) = section.split_at(4);
+        Ok(Some((
+            str::from_utf8(namespace)?.to_string(),
+            u32::from_le_bytes(version.try_into()?),
+        )))
+    }
+
+    /// Ensures the plugin preloaded for a dynamically linked module provides
+    /// the import namespace and ABI version the module was linked against.
+    fn ensure_linked_plugin_provided(wasm: &[u8], plugin: &[u8]) -> Result<()> {
+        let (Some((namespace, version)), Some(plugin_namespace)) = (
+            Self::read_linked_plugin(wasm)?,
+            Self::custom_section(plugin, "import_namespace")?,
+        ) else {
+            return Ok(());
+        };
+        let plugin_namespace = str::from_utf8(plugin_namespace)?;
+        let plugin_version = match Self::custom_section(plugin, "abi_version")? {
+            Some(version) => u32::from_le_bytes(version.try_into()?),
+            None => 0,
+        };
+        if namespace != plugin_namespace || version != plugin_version {
+            bail!(
+                "module was linked against plugin namespace {namespace} version {version}, provided plugin exposes {plugin_namespace} version {plugin_version}"
+            );
+        }
+        Ok(())
+    }
+
+    fn custom_section<'a>(wasm: &'a [u8], name: &str) -> Result<Option<&'a [u8]>> {
+        for payload in wasmparser::Parser::new(0).parse_all(wasm) {
+            if let wasmparser::Payload::CustomSection(c) = payload? {
+                if c.name() == name {
+                    return Ok(Some(c.data()));
+                }
+            }
+        }
+        Ok(None)
+    }
+
     fn out_wasm(dir: &TempDir) -> PathBuf {
         let name = format!("{}.wasm", uuid::Uuid::new_v4());
         let file = dir.path().join(name);
@@ -570,6 +731,14 @@ impl Runner {
         timers: &Option<bool>,
         redirect_stdout_to_stderr: &Option<bool>,
         wait_for_completion: &Option<bool>,
+        interrupt_after: &Option<u64>,
+        memory_limit_bytes: &Option<u64>,
+        report_error: &Option<bool>,
+        abort_on_error: &Option<bool>,
+        js_options: &[(String, String)],
+        modules: &[(String, PathBuf)],
+        minify: &Option<bool>,
+        deterministic: bool,
         plugin: &Plugin,
     ) -> Vec<String> {
         let mut args = vec![
@@ -632,6 +801,48 @@ impl Runner {
             args.push(format!("wait-for-completion={}", if enabled { "y" } else { "n" }));
         }
 
+        if let Some(count) = *interrupt_after {
+            args.push("-J".to_string());
+            args.push(format!("interrupt-after={count}"));
+        }
+
+        if let Some(bytes) = *memory_limit_bytes {
+            args.push("-J".to_string());
+            args.push(format!("memory-limit-bytes={bytes}"));
+        }
+
+        if let Some(enabled) = *report_error {
+            args.push("-J".to_string());
+            args.push(format!("report-error={}", if enabled { "y" } else { "n" }));
+        }
+
+        if let Some(enabled) = *abort_on_error {
+            args.push("-J".to_string());
+            args.push(format!(
+                "abort-on-error={}",
+                if enabled { "y" } else { "n" }
+            ));
+        }
+
+        for (name, value) in js_options {
+            args.push("-J".to_string());
+            args.push(format!("{name}={value}"));
+        }
+
+        for (name, path) in modules {
+            args.push("--module".to_string());
+            args.push(format!("{name}={}", path.to_str().unwrap()));
+        }
+
+        if let Some(enabled) = *minify {
+            args.push("-C".to_string());
+            args.push(format!("minify={}", if enabled { "y" } else { "n" }));
+        }
+
+        if deterministic {
+            args.push("--deterministic".to_string());
+        }
+
         if matches!(plugin, Plugin::User | Plugin::DefaultAsUser) {
             args.push("-C".to_string());
             args.push(format!("plugin={}", plugin.path().to_str().unwrap()));
@@ -709,9 +920,54 @@ impl Runner {
 
     pub fn exec_func(&mut self, func: &str, input: Vec<u8>) -> Result<(Vec<u8>, Vec<u8>, u64)> {
         let mut store = Self::setup_store(self.linker.engine(), input)?;
+        let instance = self.instantiate(&mut store)?;
+        let run = instance.get_typed_func::<(), ()>(store.as_context_mut(), func)?;
+
+        let res = run.call(store.as_context_mut(), ());
+
+        self.extract_store_data(res, store)
+    }
+
+    /// Invokes an exported function taking and returning a string, passing
+    /// them as the canonical ABI does, and returns the string it returned.
+    pub fn exec_string_func(&mut self, func: &str, arg: &str) -> Result<(String, Vec<u8>, u64)> {
+        let mut store = Self::setup_store(self.linker.engine(), vec![])?;
+        let instance = self.instantiate(&mut store)?;
+        let memory = instance
+            .get_memory(store.as_context_mut(), "memory")
+            .ok_or_else(|| anyhow!("Missing memory export"))?;
+        let realloc = instance
+            .get_typed_func::<(u32, u32, u32, u32), u32>(store.as_context_mut(), "cabi_realloc")?;
+        let arg_len: u32 = arg.len().try_into()?;
+        let arg_ptr = realloc.call(store.as_context_mut(), (0, 0, 1, arg_len))?;
+        memory.write(store.as_context_mut(), arg_ptr.try_into()?, arg.as_bytes())?;
+        let run = instance.get_typed_func::<(u32, u32), u32>(store.as_context_mut(), func)?;
+
+        let mut result = String::new();
+        let res = run
+            .call(store.as_context_mut(), (arg_ptr, arg_len))
+            .and_then(|ret_ptr| {
+                let mut ret_buffer = [0; 8];
+                memory.read(&store, ret_ptr.try_into()?, &mut ret_buffer)?;
+                let ptr = u32::from_le_bytes(ret_buffer[0..4].try_into()?);
+                let len = u32::from_le_bytes(ret_buffer[4..8].try_into()?);
+                let mut bytes = vec![0; len.try_into()?];
+                memory.read(&store, ptr.try_into()?, &mut bytes)?;
+                result = String::from_utf8(bytes)?;
+                Ok(())
+            });
+
+        let (_, logs, fuel_consumed) = self.extract_store_data(res, store)?;
+        Ok((result, logs, fuel_consumed))
+    }
+
+    /// Instantiates the module, after the plugin it's dynamically linked
+    /// against if one is preloaded.
+    fn instantiate(&mut self, store: &mut Store<StoreContext>) -> Result<Instance> {
         let module = Module::from_binary(self.linker.engine(), &self.wasm)?;
 
         if let Some((name, bytes)) = &self.preload {
+            Self::ensure_linked_plugin_provided(&self.wasm, bytes)?;
             let module = Module::from_binary(self.linker.engine(), bytes)?;
             // Allow unknown imports for dynamically linked `test-plugin`.
             self.linker.define_unknown_imports_as_traps(&module)?;
@@ -723,12 +979,7 @@ impl Runner {
 
         // Allow unknown imports for statically linked `test-plugin`.
         self.linker.define_unknown_imports_as_traps(&module)?;
-        let instance = self.linker.instantiate(store.as_context_mut(), &module)?;
-        let run = instance.get_typed_func::<(), ()>(store.as_context_mut(), func)?;
-
-        let res = run.call(store.as_context_mut(), ());
-
-        self.extract_store_data(res, store)
+        self.linker.instantiate(store.as_context_mut(), &module)
     }
 
     pub fn exec_through_dylib(
@@ -741,6 +992,22 @@ impl Runner {
 
         let instance = self.linker.instantiate(store.as_context_mut(), &module)?;
 
+        if let UseExportedFn::EvalSrc(func) = use_exported_fn {
+            let (src_ptr, src_len) = Self::copy_func_name(src, &instance, store.as_context_mut())?;
+            let (fn_ptr, fn_len) = match func {
+                Some(func) => Self::copy_func_name(func, &instance, store.as_context_mut())?,
+                None => (0, 0),
+            };
+            let res = instance
+                .get_typed_func::<(u32, u32, u32, u32), u32>(store.as_context_mut(), "eval_src")?
+                .call(store.as_context_mut(), (src_ptr, src_len, fn_ptr, fn_len))
+                .and_then(|status| match status {
+                    0 => Ok(()),
+                    _ => bail!("eval_src failed"),
+                });
+            return self.extract_store_data(res, store);
+        }
+
         let (bc_ptr, bc_len) = Self::compile(src.as_bytes(), store.as_context_mut(), &instance)?;
         let res = match use_exported_fn {
             UseExportedFn::EvalBytecode => instance
@@ -755,6 +1022,7 @@ impl Runner {
                     .get_typed_func::<(u32, u32, u32, u32), ()>(store.as_context_mut(), "invoke")?
                     .call(store.as_context_mut(), (bc_ptr, bc_len, fn_ptr, fn_len))
             }
+            UseExportedFn::EvalSrc(_) => unreachable!("evaluated without compiling"),
         };
 
         self.extract_store_data(res, store)
@@ -808,9 +1076,17 @@ impl Runner {
             (js_src_ptr, source.len().try_into()?),
         )?;
         let mut ret_buffer = [0; 8];
-        memory.read(store.as_context(), ret_ptr.try_into()?, &mut ret_buffer)?;
+        memory.read(&store, ret_ptr.try_into()?, &mut ret_buffer)?;
         let bytecode_ptr = u32::from_le_bytes(ret_buffer[0..4].try_into()?);
         let bytecode_len = u32::from_le_bytes(ret_buffer[4..8].try_into()?);
+        if bytecode_len & COMPILE_ERROR_FLAG != 0 {
+            let mut message = vec![0; (bytecode_len & !COMPILE_ERROR_FLAG).try_into()?];
+            memory.read(&store, bytecode_ptr.try_into()?, &mut message)?;
+            bail!(
+                "JS compilation failed: {}",
+                String::from_utf8_lossy(&message)
+            );
+        }
 
         Ok((bytecode_ptr, bytecode_len))
     }
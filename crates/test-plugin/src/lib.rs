@@ -1,9 +1,10 @@
 //! Plugin used for testing. We need a plugin with slightly different behavior
 //! to validate a plugin is actually used when it should be.
 
-use javy_plugin_api::{import_namespace, javy::quickjs::prelude::Func, Config};
+use javy_plugin_api::{import_namespace, javy::quickjs::prelude::Func, set_global_json, Config};
+use std::io::{self, Read, Write};
 
-import_namespace!("test_plugin");
+import_namespace!("test_plugin", 1);
 
 #[link(wasm_import_module = "some_host")]
 extern "C" {
@@ -12,15 +13,58 @@ extern "C" {
 
 #[export_name = "initialize_runtime"]
 pub extern "C" fn initialize_runtime() {
+    // The runtime config given with `-J` options, exposed to scripts as
+    // `pluginConfig`.
+    let mut config_bytes = vec![];
+    io::stdin().read_to_end(&mut config_bytes).unwrap();
+    let plugin_config = if config_bytes.is_empty() {
+        serde_json::json!({})
+    } else {
+        serde_json::from_slice(&config_bytes).unwrap()
+    };
+
     let config = Config::default();
-    javy_plugin_api::initialize_runtime(config, |runtime| {
-        runtime.context().with(|ctx| {
-            ctx.globals().set("plugin", true).unwrap();
-            ctx.globals()
-                .set("func", Func::from(|| unsafe { imported_function() }))
-                .unwrap();
-        });
-        runtime
-    })
+    javy_plugin_api::initialize_runtime_with(
+        config,
+        |runtime| {
+            runtime.context().with(|ctx| {
+                ctx.globals().set("plugin", true).unwrap();
+                ctx.globals()
+                    .set("func", Func::from(|| unsafe { imported_function() }))
+                    .unwrap();
+            });
+            runtime
+        },
+        |cx| set_global_json(cx, "pluginConfig", &plugin_config),
+    )
     .unwrap();
 }
+
+#[export_name = "config_schema"]
+pub extern "C" fn config_schema() {
+    let schema = serde_json::json!({
+        "supportedProperties": [{
+            "name": "my-opt",
+            "doc": "An option passed through to the plugin.",
+            "type": "string",
+        }],
+    });
+    io::stdout()
+        .write_all(schema.to_string().as_bytes())
+        .unwrap();
+    io::stdout().flush().unwrap();
+}
+
+/// Used by tests to check compiling doesn't leak bytecode buffers.
+#[export_name = "outstanding_bytecode_buffers"]
+pub extern "C" fn outstanding_bytecode_buffers() -> u32 {
+    javy_plugin_api::outstanding_bytecode_buffers()
+        .try_into()
+        .unwrap()
+}
+
+/// Used by tests to check panics are reported on stderr.
+#[export_name = "panic"]
+pub extern "C" fn panic() {
+    panic!("Deliberate panic for testing");
+}